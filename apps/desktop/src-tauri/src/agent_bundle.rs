@@ -0,0 +1,216 @@
+//! Shareable agent bundles.
+//!
+//! A bundle packs a `UserAgentConfig` together with its template and the
+//! skill definitions it selects (see `agenthub_runtime::bundle`), so a working
+//! agent can be sent to another user as one file. Bundles are signed with a
+//! per-install Ed25519 key; import verifies the signature (rejecting files
+//! modified after export) and re-checks compatibility with this runtime.
+//!
+//! Agents available for export live in the local agent library
+//! (`agents.json`), populated by `agent_save` and `agent_import`. Bundle
+//! files are read and written through the
+//! [file sandbox](crate::file_sandbox); exports outside its workspace need
+//! the user's approval.
+
+use agenthub_runtime::agent_compiler::UserAgentConfig;
+use agenthub_runtime::agent_template::AgentTemplate;
+use agenthub_runtime::bundle::AgentBundle;
+use agenthub_runtime::skill::SkillDefinition;
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{file_sandbox, resources};
+
+const LIBRARY_STORE: &str = "agents.json";
+const KEY_STORE: &str = "credentials.json";
+const SIGNING_KEY: &str = "bundle_signing_key";
+
+/// Bundle files larger than this (5 MB) are rejected on import.
+const MAX_BUNDLE_BYTES: u64 = 5 * 1024 * 1024;
+
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+// ── File format ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize)]
+struct BundleSignature {
+    algorithm: String,
+    /// Base64 Ed25519 public key of the exporter.
+    public_key: String,
+    /// Base64 signature over `AgentBundle::canonical_bytes`.
+    value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedBundle {
+    bundle: AgentBundle,
+    signature: BundleSignature,
+}
+
+/// Result of a successful import.
+#[derive(Serialize)]
+pub struct ImportedAgent {
+    /// Library ID assigned to the imported agent.
+    pub id: String,
+    pub name: String,
+    /// Short fingerprint of the exporter's signing key.
+    pub signer: String,
+    /// `true` when the bundle was signed by this install.
+    pub self_signed: bool,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+/// Loads this install's signing key, generating and persisting one on first use.
+fn signing_key(app: &AppHandle) -> Result<Ed25519KeyPair, String> {
+    let store = app
+        .store(KEY_STORE)
+        .map_err(|e| format!("credential store unavailable: {e}"))?;
+
+    if let Some(pkcs8) = store
+        .get(SIGNING_KEY)
+        .and_then(|v| v.as_str().and_then(|s| B64.decode(s).ok()))
+    {
+        return Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|e| format!("stored signing key is invalid: {e}"));
+    }
+
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map_err(|_| "failed to generate signing key".to_string())?;
+    store.set(SIGNING_KEY, serde_json::Value::String(B64.encode(pkcs8.as_ref())));
+    store.save().map_err(|e| format!("failed to save signing key: {e}"))?;
+    Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).map_err(|e| format!("signing key rejected: {e}"))
+}
+
+fn fingerprint(public_key: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, public_key)
+        .as_ref()
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn load_library_bundle(app: &AppHandle, id: &str) -> Result<AgentBundle, String> {
+    let store = app
+        .store(LIBRARY_STORE)
+        .map_err(|e| format!("agent library unavailable: {e}"))?;
+    let value = store.get(id).ok_or_else(|| format!("unknown agent '{id}'"))?;
+    serde_json::from_value(value).map_err(|e| format!("agent '{id}' is corrupted: {e}"))
+}
+
+fn save_library_bundle(app: &AppHandle, id: &str, bundle: &AgentBundle) -> Result<(), String> {
+    let store = app
+        .store(LIBRARY_STORE)
+        .map_err(|e| format!("agent library unavailable: {e}"))?;
+    let value = serde_json::to_value(bundle).map_err(|e| e.to_string())?;
+    store.set(id, value);
+    store.save().map_err(|e| format!("failed to save agent library: {e}"))
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Saves an agent with its template and skills into the local library so it
+/// can be exported. Returns the library ID (`id`, or a new one when omitted).
+#[tauri::command]
+pub async fn agent_save(
+    app: AppHandle,
+    id: Option<String>,
+    config: UserAgentConfig,
+    template: AgentTemplate,
+    system_instruction: String,
+    skills: Vec<SkillDefinition>,
+) -> Result<String, String> {
+    let template = AgentTemplate {
+        system_instruction: system_instruction.into(),
+        ..template
+    };
+    let bundle = AgentBundle::new(config, &template, &skills).map_err(|e| e.to_string())?;
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    save_library_bundle(&app, &id, &bundle)?;
+    Ok(id)
+}
+
+/// Writes library agent `id` to `path` as a signed bundle file. `path` must
+/// be writable under the file sandbox.
+#[tauri::command]
+pub async fn agent_export(app: AppHandle, id: String, path: String) -> Result<(), String> {
+    let bundle = load_library_bundle(&app, &id)?;
+    bundle.validate().map_err(|e| format!("agent '{id}' cannot be exported: {e}"))?;
+
+    let key = signing_key(&app)?;
+    let signature = BundleSignature {
+        algorithm: SIGNATURE_ALGORITHM.into(),
+        public_key: B64.encode(key.public_key().as_ref()),
+        value: B64.encode(key.sign(&bundle.canonical_bytes()).as_ref()),
+    };
+    let file = serde_json::to_vec_pretty(&SignedBundle { bundle, signature })
+        .map_err(|e| format!("bundle serialization failed: {e}"))?;
+    let path = file_sandbox::writable_path(&app, &path).await?;
+
+    tokio::task::spawn_blocking(move || {
+        resources::ensure_disk_space(&app, &path, file.len() as u64)?;
+        std::fs::write(&path, file).map_err(|e| format!("write failed: {e}"))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Imports a bundle file into the local library after verifying its
+/// signature and compatibility with this runtime. `path` must be readable
+/// under the file sandbox.
+#[tauri::command]
+pub async fn agent_import(app: AppHandle, path: String) -> Result<ImportedAgent, String> {
+    let sandbox = app.clone();
+    let bytes = tokio::task::spawn_blocking(move || {
+        let path = file_sandbox::resolve(&sandbox, &path, file_sandbox::Access::Read)?;
+        let meta = std::fs::metadata(&path).map_err(|e| format!("stat failed: {e}"))?;
+        if meta.len() > MAX_BUNDLE_BYTES {
+            return Err(format!(
+                "bundle too large: {} bytes (max {MAX_BUNDLE_BYTES})",
+                meta.len()
+            ));
+        }
+        std::fs::read(&path).map_err(|e| format!("read failed: {e}"))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)?;
+
+    let signed: SignedBundle =
+        serde_json::from_slice(&bytes).map_err(|e| format!("not an agent bundle: {e}"))?;
+    let sig = &signed.signature;
+    if sig.algorithm != SIGNATURE_ALGORITHM {
+        return Err(format!("unsupported signature algorithm '{}'", sig.algorithm));
+    }
+    let public_key = B64
+        .decode(&sig.public_key)
+        .map_err(|_| "bundle public key is not valid base64")?;
+    let signature = B64
+        .decode(&sig.value)
+        .map_err(|_| "bundle signature is not valid base64")?;
+    UnparsedPublicKey::new(&ED25519, &public_key)
+        .verify(&signed.bundle.canonical_bytes(), &signature)
+        .map_err(|_| "bundle signature is invalid — the file was modified after export")?;
+
+    signed
+        .bundle
+        .validate()
+        .map_err(|e| format!("bundle is not compatible: {e}"))?;
+
+    let own_key = signing_key(&app)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    save_library_bundle(&app, &id, &signed.bundle)?;
+
+    Ok(ImportedAgent {
+        id,
+        name: signed.bundle.agent.name.clone(),
+        signer: fingerprint(&public_key),
+        self_signed: own_key.public_key().as_ref() == public_key.as_slice(),
+    })
+}
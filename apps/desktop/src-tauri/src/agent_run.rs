@@ -0,0 +1,83 @@
+//! Running library agents.
+//!
+//! `agent_run` compiles an agent from the local library (see
+//! [`agent_bundle`](crate::agent_bundle)) against its bundled template and
+//! skills, and runs it through the runtime's `ExecutionEngine`, calling the
+//! provider directly. A run launched from a chat session is attributed to
+//! it: each call is recorded in the usage ledger under the session's ID, and
+//! the run's spend comes back with its outputs.
+
+use std::collections::HashMap;
+
+use agenthub_runtime::agent_compiler::AgentCompiler;
+use agenthub_runtime::agent_template::TemplateRegistry;
+use agenthub_runtime::calculator;
+use agenthub_runtime::execution_engine::ExecutionEngine;
+use agenthub_runtime::memory::{MemoryEntry, MemoryManager};
+use agenthub_runtime::skill_executor::SkillExecutor;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::skills::{DirectProvider, SessionSkillUsage};
+use crate::{agent_bundle, budget, redact, shutdown, AppState};
+
+/// Outcome of [`agent_run`].
+#[derive(Serialize)]
+pub struct AgentRunResult {
+    /// Validated output of each skill, by skill ID.
+    pub outputs: HashMap<String, serde_json::Value>,
+    pub total_tokens: u32,
+    pub total_cost: f64,
+    /// The run's spend, when launched from a chat session.
+    pub session: Option<SessionSkillUsage>,
+}
+
+/// Runs library agent `id` once with `memories` as its memory.
+///
+/// Skills call `provider` directly with `api_key` (or the stored key) and
+/// `model`. Without a model, OpenAI runs let the engine pick between its
+/// models by remaining budget; other providers use their default. The run's
+/// usage is attributed to chat session `session_id`, when given.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn agent_run(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    provider: String,
+    model: Option<String>,
+    api_key: Option<String>,
+    memories: Option<Vec<MemoryEntry>>,
+    session_id: Option<String>,
+) -> Result<AgentRunResult, String> {
+    shutdown::ensure_running()?;
+    let bundle = agent_bundle::load_library_bundle(&app, &id)?;
+    let mut templates = TemplateRegistry::new();
+    templates.register(bundle.template());
+    let agent = AgentCompiler::compile(&bundle.agent, &templates, &bundle.skills)
+        .map_err(|e| format!("agent '{id}' can't be compiled: {e}"))?;
+
+    budget::ensure_within(&app)?;
+    let routed = model.is_none() && provider == "openai";
+    let direct = DirectProvider::resolve(&app, &state, provider, model, api_key, session_id.clone()).await?;
+    let direct = if routed { direct.routed() } else { direct };
+
+    let mut memory = MemoryManager::new();
+    for entry in memories.unwrap_or_default() {
+        memory.add(entry);
+    }
+
+    tokio::task::block_in_place(|| {
+        let mut executor = SkillExecutor::new();
+        calculator::register(&mut executor);
+        let mut engine = ExecutionEngine::new(executor);
+        engine.attribute_to_conversation(session_id);
+        let result = engine.execute(&agent, &memory, &direct).map_err(redact::error)?;
+        Ok(AgentRunResult {
+            outputs: result.outputs.into_iter().collect(),
+            total_tokens: result.total_tokens,
+            total_cost: result.total_cost,
+            session: result.conversation.map(Into::into),
+        })
+    })
+}
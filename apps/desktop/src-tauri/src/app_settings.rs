@@ -0,0 +1,225 @@
+//! Typed view of the general settings.
+//!
+//! [`Settings`] gathers the preferences that used to be read and written one
+//! store key at a time. `settings_get` returns them all, with defaults for
+//! the unset ones; `settings_set` takes a patch of just the fields to change,
+//! validates the result as a whole and writes it in a single save, so a bad
+//! value leaves every setting as it was. Each successful change is announced
+//! on `settings:changed`.
+//!
+//! Field names are the store keys, so the per-setting commands (e.g.
+//! `settings_set_stream_idle_timeout`) and this module see the same values.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::{bedrock, language, redact, settings, updater};
+
+/// Providers that can be chosen as the default.
+const KNOWN_PROVIDERS: &[&str] = &[
+    "openai", "anthropic", "google", "gemini", "groq", "mistral", "deepseek", "xai",
+    "perplexity", "cohere", "bedrock", "custom", "local-openai",
+];
+const MAX_MODEL_LEN: usize = 200;
+
+/// The general settings, stored in `settings.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Provider the UI preselects for new chats.
+    pub default_provider: Option<String>,
+    /// Model the UI preselects; `null` for the provider's default.
+    pub default_model: Option<String>,
+    /// Use keys stored with `provider_keys_set` for calls without an
+    /// `api_key`; when off those calls go to the gateway.
+    pub use_stored_provider_keys: bool,
+    /// Base URL of the `custom` provider (see `settings_set_custom_provider`).
+    pub custom_base_url: Option<String>,
+    pub custom_model: Option<String>,
+    pub bedrock_region: Option<String>,
+    /// Stream stall timeout, 5–120 seconds.
+    pub stream_idle_timeout_secs: u64,
+    /// Language code; `null` lets the model decide.
+    pub response_language: Option<String>,
+    pub redaction: redact::RedactionConfig,
+    pub update_channel: updater::UpdateChannel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_provider: None,
+            default_model: None,
+            use_stored_provider_keys: true,
+            custom_base_url: None,
+            custom_model: None,
+            bedrock_region: None,
+            stream_idle_timeout_secs: settings::DEFAULT_STREAM_IDLE_TIMEOUT_SECS,
+            response_language: None,
+            redaction: redact::RedactionConfig::default(),
+            update_channel: updater::UpdateChannel::default(),
+        }
+    }
+}
+
+/// Trims `value`, treating an empty one as unset.
+fn trimmed(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_owned()).filter(|v| !v.is_empty())
+}
+
+impl Settings {
+    /// Normalizes the values and rejects invalid ones.
+    fn validate(mut self) -> Result<Self, String> {
+        self.default_provider = trimmed(self.default_provider).map(|p| p.to_ascii_lowercase());
+        if let Some(p) = &self.default_provider {
+            if !KNOWN_PROVIDERS.contains(&p.as_str()) {
+                return Err(format!("unknown provider '{p}'"));
+            }
+        }
+        self.default_model = trimmed(self.default_model);
+        self.custom_model = trimmed(self.custom_model);
+        for model in [&self.default_model, &self.custom_model].into_iter().flatten() {
+            if model.len() > MAX_MODEL_LEN {
+                return Err(format!("model name is too long (max {MAX_MODEL_LEN} characters)"));
+            }
+        }
+        self.custom_base_url = trimmed(self.custom_base_url)
+            .map(|u| settings::normalize_base_url(&u))
+            .transpose()?;
+        if self.default_provider.as_deref() == Some("custom") && self.custom_base_url.is_none() {
+            return Err("the custom provider needs a base URL".into());
+        }
+        self.bedrock_region = trimmed(self.bedrock_region);
+        if let Some(r) = &self.bedrock_region {
+            bedrock::validate_region(r)?;
+        }
+        if !(settings::MIN_STREAM_IDLE_TIMEOUT_SECS..=settings::MAX_STREAM_IDLE_TIMEOUT_SECS)
+            .contains(&self.stream_idle_timeout_secs)
+        {
+            return Err(format!(
+                "stream idle timeout must be between {} and {} seconds (got {})",
+                settings::MIN_STREAM_IDLE_TIMEOUT_SECS,
+                settings::MAX_STREAM_IDLE_TIMEOUT_SECS,
+                self.stream_idle_timeout_secs
+            ));
+        }
+        self.response_language = trimmed(self.response_language)
+            .map(|l| language::normalize(&l).map(str::to_owned))
+            .transpose()?;
+        self.redaction.extra = self
+            .redaction
+            .extra
+            .into_iter()
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+            .collect();
+        Ok(self)
+    }
+
+    fn to_map(&self) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+        match serde_json::to_value(self).map_err(|e| e.to_string())? {
+            serde_json::Value::Object(map) => Ok(map),
+            _ => Err("settings must serialize to an object".into()),
+        }
+    }
+}
+
+/// Payload of `settings:changed`.
+#[derive(Serialize, Clone)]
+struct SettingsChanged {
+    settings: Settings,
+    /// Names of the fields that changed.
+    changed: Vec<String>,
+}
+
+fn get<T: DeserializeOwned>(store: &tauri_plugin_store::Store<tauri::Wry>, key: &str) -> Option<T> {
+    store.get(key).and_then(|v| serde_json::from_value(v).ok())
+}
+
+/// Reads the settings; unset or unreadable values get their defaults.
+pub fn load(app: &AppHandle) -> Settings {
+    let Ok(store) = app.store(settings::SETTINGS_STORE) else {
+        return Settings::default();
+    };
+    let d = Settings::default();
+    Settings {
+        default_provider: get(&store, "default_provider"),
+        default_model: get(&store, "default_model"),
+        use_stored_provider_keys: get(&store, "use_stored_provider_keys").unwrap_or(d.use_stored_provider_keys),
+        custom_base_url: get(&store, "custom_base_url"),
+        custom_model: get(&store, "custom_model"),
+        bedrock_region: get(&store, "bedrock_region"),
+        stream_idle_timeout_secs: get(&store, "stream_idle_timeout_secs").unwrap_or(d.stream_idle_timeout_secs),
+        response_language: get(&store, "response_language"),
+        redaction: get(&store, "redaction").unwrap_or(d.redaction),
+        update_channel: get(&store, "update_channel").unwrap_or(d.update_channel),
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns all general settings.
+#[tauri::command]
+pub async fn settings_get(app: AppHandle) -> Settings {
+    load(&app)
+}
+
+/// Applies `patch` — an object with the fields to change — and returns the
+/// updated settings. Unknown fields and invalid values fail the whole patch.
+#[tauri::command]
+pub async fn settings_set(
+    app: AppHandle,
+    patch: serde_json::Map<String, serde_json::Value>,
+) -> Result<Settings, String> {
+    let old = load(&app).to_map()?;
+    let mut merged = old.clone();
+    for (key, value) in patch {
+        if !merged.contains_key(&key) {
+            return Err(format!("unknown setting '{key}'"));
+        }
+        merged.insert(key, value);
+    }
+    let next: Settings = serde_json::from_value(serde_json::Value::Object(merged))
+        .map_err(|e| format!("invalid settings: {e}"))?;
+    let next = next.validate()?;
+    let new = next.to_map()?;
+    let changed: Vec<String> = new
+        .iter()
+        .filter(|(k, v)| old.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .collect();
+    if changed.is_empty() {
+        return Ok(next);
+    }
+
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let write = |values: &serde_json::Map<String, serde_json::Value>| {
+        for key in &changed {
+            match values.get(key) {
+                Some(serde_json::Value::Null) | None => {
+                    store.delete(key);
+                }
+                Some(v) => store.set(key.clone(), v.clone()),
+            }
+        }
+    };
+    write(&new);
+    if let Err(e) = store.save() {
+        // Put the old values back so memory matches the file.
+        write(&old);
+        return Err(format!("failed to save settings: {e}"));
+    }
+
+    if changed.iter().any(|k| k == "redaction") {
+        redact::configure(next.redaction.clone());
+    }
+    if changed.iter().any(|k| k == "update_channel") {
+        app.state::<updater::Updates>().clear();
+    }
+    let _ = app.emit("settings:changed", SettingsChanged { settings: next.clone(), changed });
+    Ok(next)
+}
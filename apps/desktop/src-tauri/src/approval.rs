@@ -0,0 +1,200 @@
+//! User approval for dangerous computer-use actions.
+//!
+//! Shell commands, file writes outside the sandbox workspace, hotkeys that
+//! quit or close things (Cmd+Q, Alt+F4, …) and killing processes the app
+//! didn't start wait for the user before they run. [`require`] emits
+//! `approval:request` and blocks the command until the UI answers with
+//! `approval_respond`, for at most five minutes. Answering with `always`
+//! remembers the exact action (the same command line, file, chord or
+//! process name) so it runs without asking from then on; the list is
+//! stored under `approval_always_allow`.
+//!
+//! # Events
+//! - `approval:request` — [`ApprovalRequest`]; answer with `approval_respond`.
+//! - `approval:resolved` — `{ id, approved }` once answered or timed out,
+//!   so every window can close its prompt.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::oneshot;
+
+use crate::settings;
+
+const ALWAYS_ALLOW_KEY: &str = "approval_always_allow";
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_ALWAYS_ALLOW: usize = 500;
+
+/// An action that needs the user's approval.
+pub struct Action {
+    /// `shell`, `file_write`, `hotkey` or `process_kill`.
+    pub kind: &'static str,
+    /// What will happen, for the prompt.
+    pub summary: String,
+    /// Identifies the action for "always allow".
+    pub key: String,
+}
+
+impl Action {
+    pub fn shell(command: &str) -> Self {
+        Self { kind: "shell", summary: format!("Run: {command}"), key: format!("shell:{command}") }
+    }
+
+    pub fn file_write(path: &std::path::Path) -> Self {
+        let path = path.to_string_lossy();
+        Self {
+            kind: "file_write",
+            summary: format!("Write outside the workspace: {path}"),
+            key: format!("file_write:{path}"),
+        }
+    }
+
+    pub fn hotkey(chord: &str) -> Self {
+        Self { kind: "hotkey", summary: format!("Press {chord}"), key: format!("hotkey:{chord}") }
+    }
+
+    /// "Always allow" covers every process with the same name.
+    pub fn process_kill(name: &str, pid: u32) -> Self {
+        Self {
+            kind: "process_kill",
+            summary: format!("Stop {name} (PID {pid})"),
+            key: format!("process_kill:{name}"),
+        }
+    }
+}
+
+/// Payload of `approval:request`.
+#[derive(Serialize, Clone)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub kind: &'static str,
+    pub summary: String,
+    pub key: String,
+}
+
+#[derive(Serialize, Clone)]
+struct ApprovalResolved {
+    id: String,
+    approved: bool,
+}
+
+struct Answer {
+    approved: bool,
+    always: bool,
+}
+
+/// Approvals waiting for an answer, managed via `tauri::Builder::manage`.
+#[derive(Default)]
+pub struct Approvals {
+    pending: Mutex<HashMap<String, oneshot::Sender<Answer>>>,
+}
+
+impl Approvals {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, oneshot::Sender<Answer>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cancels every pending approval, failing the commands waiting on
+    /// them; called at shutdown.
+    pub fn cancel_all(&self) {
+        self.lock().clear();
+    }
+}
+
+fn always_allowed(app: &AppHandle) -> Vec<String> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(ALWAYS_ALLOW_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save_always_allowed(app: &AppHandle, keys: &[String]) -> Result<(), String> {
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    store.set(ALWAYS_ALLOW_KEY, serde_json::json!(keys));
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+/// Waits for the user to approve `action`; fails when they deny it, don't
+/// answer in time, or the app shuts down meanwhile. Returns at once for
+/// actions marked "always allow".
+pub async fn require(app: &AppHandle, action: Action) -> Result<(), String> {
+    if always_allowed(app).contains(&action.key) {
+        return Ok(());
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    let approvals = app.state::<Approvals>();
+    approvals.lock().insert(id.clone(), tx);
+    let _ = app.emit("approval:request", ApprovalRequest {
+        id: id.clone(),
+        kind: action.kind,
+        summary: action.summary,
+        key: action.key.clone(),
+    });
+
+    let answer = tokio::time::timeout(APPROVAL_TIMEOUT, rx).await;
+    approvals.lock().remove(&id);
+    let answer = match answer {
+        Ok(Ok(answer)) => answer,
+        Ok(Err(_)) => return Err("approval was cancelled".into()),
+        Err(_) => {
+            let _ = app.emit("approval:resolved", ApprovalResolved { id, approved: false });
+            return Err("approval timed out".into());
+        }
+    };
+    if !answer.approved {
+        return Err("denied by the user".into());
+    }
+    if answer.always {
+        let mut keys = always_allowed(app);
+        if !keys.contains(&action.key) && keys.len() < MAX_ALWAYS_ALLOW {
+            keys.push(action.key);
+            save_always_allowed(app, &keys)?;
+        }
+    }
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Answers the `approval:request` with `id`. With `always`, an approved
+/// action won't be asked about again.
+#[tauri::command]
+pub fn approval_respond(
+    app: AppHandle,
+    approvals: tauri::State<'_, Approvals>,
+    id: String,
+    approved: bool,
+    always: Option<bool>,
+) -> Result<(), String> {
+    let tx = approvals.lock().remove(&id).ok_or("unknown or expired approval")?;
+    let _ = tx.send(Answer { approved, always: always.unwrap_or(false) });
+    let _ = app.emit("approval:resolved", ApprovalResolved { id, approved });
+    Ok(())
+}
+
+/// Lists the actions marked "always allow".
+#[tauri::command]
+pub async fn approval_list_always(app: AppHandle) -> Vec<String> {
+    always_allowed(&app)
+}
+
+/// Makes `key` ask for approval again; returns whether it was allowed.
+#[tauri::command]
+pub async fn approval_revoke_always(app: AppHandle, key: String) -> Result<bool, String> {
+    let mut keys = always_allowed(&app);
+    let before = keys.len();
+    keys.retain(|k| *k != key);
+    if keys.len() == before {
+        return Ok(false);
+    }
+    save_always_allowed(&app, &keys)?;
+    Ok(true)
+}
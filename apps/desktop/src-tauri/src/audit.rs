@@ -0,0 +1,204 @@
+//! Audit log of computer-use actions.
+//!
+//! Every mouse, keyboard, shell and file command records what it was asked
+//! to do, by which module (its optional `module` argument), and how it
+//! ended, in the `computer_audit` table of the app's `memory.db`; terminal
+//! sessions record their opening and each line submitted to them.
+//! Arguments are summarized — text to type and file contents by length
+//! only — and passed through [`redact`](crate::redact) before they are
+//! stored. The log keeps the last 90 days and at most
+//! 50 000 entries; `computer_audit_list` pages through it and
+//! `computer_audit_export` dumps it as JSON Lines or CSV.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{redact, usage_ledger};
+
+const MAX_ENTRIES: i64 = 50_000;
+const RETENTION_DAYS: i64 = 90;
+/// Retention is enforced every this many records.
+const PRUNE_EVERY: u32 = 100;
+/// Stored argument summaries and errors are cut to this many characters.
+const MAX_TEXT_CHARS: usize = 1000;
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1000;
+
+static AUDIT: OnceLock<Mutex<Connection>> = OnceLock::new();
+static RECORDED: AtomicU32 = AtomicU32::new(0);
+
+/// Opens (creating if needed) the audit table in `dir`. Until it is open,
+/// actions aren't recorded.
+pub fn open(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    let conn = Connection::open(dir.join(usage_ledger::DB_FILE)).map_err(|e| format!("can't open audit log: {e}"))?;
+    // The usage ledger writes to the same file.
+    conn.busy_timeout(std::time::Duration::from_secs(2))
+        .map_err(|e| format!("can't open audit log: {e}"))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS computer_audit (
+             id      INTEGER PRIMARY KEY,
+             ts      INTEGER NOT NULL,
+             command TEXT    NOT NULL,
+             module  TEXT,
+             args    TEXT    NOT NULL,
+             ok      INTEGER NOT NULL,
+             error   TEXT
+         );
+         CREATE INDEX IF NOT EXISTS computer_audit_ts ON computer_audit (ts);",
+    )
+    .map_err(|e| format!("can't create audit log: {e}"))?;
+    let _ = AUDIT.set(Mutex::new(conn));
+    Ok(())
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let conn = AUDIT.get().ok_or("audit log is not open")?;
+    f(&conn.lock().unwrap_or_else(|e| e.into_inner())).map_err(|e| format!("audit log error: {e}"))
+}
+
+fn clip(text: &str) -> String {
+    let text = redact::redact(text);
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((i, _)) => format!("{}…", &text[..i]),
+        None => text,
+    }
+}
+
+/// Records a finished computer-use command. `args` is a summary of its
+/// arguments; `module` the module that called it, when known. A failed
+/// write never fails the command it describes.
+pub fn record<T>(command: &str, args: serde_json::Value, module: Option<&str>, result: &Result<T, String>) {
+    let _ = with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO computer_audit (ts, command, module, args, ok, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                chrono::Utc::now().timestamp_millis(),
+                command,
+                module,
+                clip(&args.to_string()),
+                result.is_ok(),
+                result.as_ref().err().map(|e| clip(e)),
+            ],
+        )?;
+        if RECORDED.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == 0 {
+            let cutoff = (chrono::Utc::now() - chrono::Duration::days(RETENTION_DAYS)).timestamp_millis();
+            conn.execute(
+                "DELETE FROM computer_audit WHERE ts < ?1
+                 OR id <= (SELECT id FROM computer_audit ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+                params![cutoff, MAX_ENTRIES],
+            )?;
+        }
+        Ok(())
+    });
+}
+
+/// One recorded action.
+#[derive(Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// RFC 3339 timestamp.
+    pub timestamp: String,
+    pub command: String,
+    pub module: Option<String>,
+    /// JSON summary of the arguments.
+    pub args: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+fn entry(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let ts: i64 = row.get(1)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: chrono::DateTime::from_timestamp_millis(ts).unwrap_or_default().to_rfc3339(),
+        command: row.get(2)?,
+        module: row.get(3)?,
+        args: row.get(4)?,
+        ok: row.get(5)?,
+        error: row.get(6)?,
+    })
+}
+
+const COLUMNS: &str = "id, ts, command, module, args, ok, error";
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Lists recorded actions, newest first. `before_id` pages backwards from
+/// an entry; `command` and `module` filter exactly. `limit` defaults to 100
+/// (at most 1000).
+#[tauri::command]
+pub fn computer_audit_list(
+    limit: Option<u32>,
+    before_id: Option<i64>,
+    command: Option<String>,
+    module: Option<String>,
+) -> Result<Vec<AuditEntry>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {MAX_LIST_LIMIT}"));
+    }
+    with_conn(|conn| {
+        conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM computer_audit
+             WHERE (?1 IS NULL OR id < ?1) AND (?2 IS NULL OR command = ?2) AND (?3 IS NULL OR module = ?3)
+             ORDER BY id DESC LIMIT ?4"
+        ))?
+        .query_map(params![before_id, command, module, limit], entry)?
+        .collect()
+    })
+}
+
+/// Exports the whole log, oldest first, as `jsonl` (the default) or `csv`.
+#[tauri::command]
+pub async fn computer_audit_export(format: Option<String>) -> Result<String, String> {
+    let csv = match format.as_deref().unwrap_or("jsonl") {
+        "jsonl" => false,
+        "csv" => true,
+        other => return Err(format!("unknown export format '{other}' (use jsonl or csv)")),
+    };
+    tokio::task::spawn_blocking(move || {
+        let entries = with_conn(|conn| {
+            conn.prepare(&format!("SELECT {COLUMNS} FROM computer_audit ORDER BY id"))?
+                .query_map([], entry)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+        let mut out = String::new();
+        if csv {
+            out.push_str("id,timestamp,command,module,args,ok,error\n");
+        }
+        for e in &entries {
+            if csv {
+                let fields = [
+                    e.id.to_string(),
+                    e.timestamp.clone(),
+                    csv_field(&e.command),
+                    csv_field(e.module.as_deref().unwrap_or("")),
+                    csv_field(&e.args),
+                    e.ok.to_string(),
+                    csv_field(e.error.as_deref().unwrap_or("")),
+                ];
+                out.push_str(&fields.join(","));
+            } else {
+                out.push_str(&serde_json::to_string(e).map_err(|e| e.to_string())?);
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
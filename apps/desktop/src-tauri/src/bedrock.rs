@@ -0,0 +1,339 @@
+//! AWS Bedrock transport for the direct provider path.
+//!
+//! Requests go to Bedrock's `InvokeModel` endpoints and are signed with AWS
+//! Signature Version 4 using the user's own IAM credentials. Streaming
+//! responses arrive in the binary `application/vnd.amazon.eventstream`
+//! framing; [`EventStreamDecoder`] splits them into messages whose `chunk`
+//! payloads carry ordinary Anthropic streaming events.
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chrono::{DateTime, Utc};
+use ring::{digest, hmac};
+
+const SERVICE: &str = "bedrock";
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// `anthropic_version` Bedrock expects in Anthropic request bodies.
+pub const ANTHROPIC_BEDROCK_VERSION: &str = "bedrock-2023-05-31";
+
+/// Event-stream messages larger than this (16 MB, the protocol limit) are
+/// treated as corrupt.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Prelude (total length, headers length, prelude CRC) plus trailing CRC.
+const FRAME_OVERHEAD: usize = 16;
+
+// ── Credentials ────────────────────────────────────────────────────────────────
+
+/// IAM credentials, passed as the BYOK key in the form
+/// `ACCESS_KEY_ID:SECRET_ACCESS_KEY` or, for temporary credentials,
+/// `ACCESS_KEY_ID:SECRET_ACCESS_KEY:SESSION_TOKEN`.
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    pub fn parse(api_key: &str) -> Result<Self, String> {
+        let mut parts = api_key.trim().splitn(3, ':');
+        let access_key_id = parts.next().unwrap_or_default();
+        let secret_access_key = parts.next().unwrap_or_default();
+        if access_key_id.is_empty() || secret_access_key.is_empty() {
+            return Err(
+                "Bedrock credentials must be given as ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]"
+                    .into(),
+            );
+        }
+        Ok(Self {
+            access_key_id: access_key_id.to_owned(),
+            secret_access_key: secret_access_key.to_owned(),
+            session_token: parts.next().filter(|t| !t.is_empty()).map(String::from),
+        })
+    }
+}
+
+/// Checks `region` looks like an AWS region code (e.g. `us-east-1`).
+pub fn validate_region(region: &str) -> Result<(), String> {
+    let valid = !region.is_empty()
+        && region.len() <= 32
+        && region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !region.starts_with('-')
+        && !region.ends_with('-');
+    if valid { Ok(()) } else { Err(format!("invalid AWS region '{region}'")) }
+}
+
+/// Bedrock runtime endpoint for `region`.
+pub fn endpoint(region: &str) -> String {
+    format!("https://bedrock-runtime.{region}.amazonaws.com")
+}
+
+/// `InvokeModel` (or `InvokeModelWithResponseStream`) URL for `model` under
+/// `endpoint`. Model IDs contain `:`, which must be percent-encoded.
+pub fn invoke_url(endpoint: &str, model: &str, stream: bool) -> String {
+    let action = if stream { "invoke-with-response-stream" } else { "invoke" };
+    format!("{endpoint}/model/{}/{action}", uri_encode(model))
+}
+
+/// Extracts the region from an endpoint built by [`endpoint`].
+pub fn region_of(endpoint: &str) -> Option<&str> {
+    endpoint
+        .strip_prefix("https://bedrock-runtime.")?
+        .strip_suffix(".amazonaws.com")
+}
+
+// ── Signature Version 4 ────────────────────────────────────────────────────────
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes())
+}
+
+/// RFC 3986 encoding of everything but unreserved characters, as SigV4 requires.
+fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Computes the headers that sign a `POST` of `body` to `url`: `x-amz-date`,
+/// `authorization` and, for temporary credentials, `x-amz-security-token`.
+/// The request must be sent with `content-type: application/json`.
+pub fn sign_request(
+    creds: &Credentials,
+    region: &str,
+    url: &str,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid Bedrock URL: {e}"))?;
+    let host = parsed.host_str().ok_or("Bedrock URL has no host")?;
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut headers: Vec<(&'static str, String)> = vec![
+        ("content-type", "application/json".into()),
+        ("host", host.to_owned()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &creds.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    // Services other than S3 sign the path with each segment encoded twice.
+    let canonical_uri: String = parsed
+        .path()
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let authorization = authorization(creds, region, SERVICE, "POST", &canonical_uri, &headers, body, &amz_date);
+    let mut out: Vec<(&'static str, String)> = headers
+        .into_iter()
+        .filter(|(name, _)| matches!(*name, "x-amz-date" | "x-amz-security-token"))
+        .collect();
+    out.push(("authorization", authorization));
+    Ok(out)
+}
+
+/// `Authorization` header value for a request with `headers` already sorted
+/// by name.
+#[allow(clippy::too_many_arguments)]
+fn authorization(
+    creds: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    canonical_uri: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{method}\n{canonical_uri}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        sha256_hex(body)
+    );
+
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", creds.secret_access_key).as_bytes(), date);
+    let k_region = hmac_sha256(k_date.as_ref(), region);
+    let k_service = hmac_sha256(k_region.as_ref(), service);
+    let k_signing = hmac_sha256(k_service.as_ref(), "aws4_request");
+    let signature = hex(hmac_sha256(k_signing.as_ref(), &string_to_sign).as_ref());
+
+    format!(
+        "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        creds.access_key_id
+    )
+}
+
+// ── Event-stream framing ───────────────────────────────────────────────────────
+
+/// One decoded event-stream message. Only string headers are kept.
+pub struct EventMessage {
+    pub headers: Vec<(String, String)>,
+    pub payload: Vec<u8>,
+}
+
+impl EventMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The Anthropic event JSON carried by a `chunk` message, `None` for
+    /// other events, or the error Bedrock reported mid-stream.
+    pub fn chunk(&self) -> Result<Option<String>, String> {
+        let payload: serde_json::Value = serde_json::from_slice(&self.payload)
+            .map_err(|e| format!("malformed Bedrock event payload: {e}"))?;
+        match self.header(":message-type") {
+            Some("event") => {}
+            Some("exception") | Some("error") => {
+                let kind = self
+                    .header(":exception-type")
+                    .or_else(|| self.header(":error-code"))
+                    .unwrap_or("error");
+                let message = payload
+                    .get("message")
+                    .or_else(|| payload.get("Message"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("");
+                return Err(format!("Bedrock {kind}: {message}"));
+            }
+            _ => return Ok(None),
+        }
+        if self.header(":event-type") != Some("chunk") {
+            return Ok(None);
+        }
+        let Some(encoded) = payload.get("bytes").and_then(|b| b.as_str()) else {
+            return Ok(None);
+        };
+        let bytes = B64
+            .decode(encoded)
+            .map_err(|_| "Bedrock chunk is not valid base64".to_string())?;
+        String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|_| "Bedrock chunk is not valid UTF-8".to_string())
+    }
+}
+
+/// Incremental decoder for `application/vnd.amazon.eventstream` bodies.
+#[derive(Default)]
+pub struct EventStreamDecoder {
+    buf: Vec<u8>,
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+impl EventStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Returns the next complete message, or `None` until more bytes arrive.
+    pub fn next_message(&mut self) -> Result<Option<EventMessage>, String> {
+        if self.buf.len() < 12 {
+            return Ok(None);
+        }
+        let total = be_u32(&self.buf[0..4]) as usize;
+        let headers_len = be_u32(&self.buf[4..8]) as usize;
+        if crc32(&self.buf[0..8]) != be_u32(&self.buf[8..12]) {
+            return Err("Bedrock stream prelude checksum mismatch".into());
+        }
+        if total > MAX_MESSAGE_BYTES || total < FRAME_OVERHEAD + headers_len {
+            return Err(format!("Bedrock stream message has invalid length {total}"));
+        }
+        if self.buf.len() < total {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buf.drain(..total).collect();
+        if crc32(&frame[..total - 4]) != be_u32(&frame[total - 4..]) {
+            return Err("Bedrock stream message checksum mismatch".into());
+        }
+        let headers = parse_headers(&frame[12..12 + headers_len])?;
+        let payload = frame[12 + headers_len..total - 4].to_vec();
+        Ok(Some(EventMessage { headers, payload }))
+    }
+}
+
+fn parse_headers(mut data: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let truncated = || "Bedrock stream header is truncated".to_string();
+    let mut headers = Vec::new();
+    while !data.is_empty() {
+        let name_len = data[0] as usize;
+        let name = data.get(1..1 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        data = &data[1 + name_len..];
+        let (&kind, rest) = data.split_first().ok_or_else(truncated)?;
+        data = rest;
+
+        // Value sizes by type: bool true/false carry no bytes, byte, short,
+        // int, long, byte array / string (u16 length prefix), timestamp, UUID.
+        let fixed = match kind {
+            0 | 1 => Some(0),
+            2 => Some(1),
+            3 => Some(2),
+            4 => Some(4),
+            5 | 8 => Some(8),
+            9 => Some(16),
+            6 | 7 => None,
+            other => return Err(format!("unknown Bedrock stream header type {other}")),
+        };
+        let value_len = match fixed {
+            Some(n) => n,
+            None => {
+                let len = data.get(..2).ok_or_else(truncated)?;
+                data = &data[2..];
+                u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+        };
+        let value = data.get(..value_len).ok_or_else(truncated)?;
+        if kind == 7 {
+            headers.push((name, String::from_utf8_lossy(value).into_owned()));
+        }
+        data = &data[value_len..];
+    }
+    Ok(headers)
+}
+
+/// CRC-32 (IEEE 802.3), as used by the event-stream framing.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,664 @@
+//! Browser automation over the Chrome DevTools Protocol.
+//!
+//! Clicking at pixel positions inside a browser breaks as soon as the page
+//! scrolls or reflows. `browser_launch` starts Chrome (or Chromium / Edge)
+//! with remote debugging on a profile of its own, or `browser_attach`
+//! connects to one the user started with `--remote-debugging-port`; the
+//! other commands then drive its first tab by CSS selector — navigate,
+//! query the DOM, click, read text and capture an element.
+//!
+//! One browser is driven at a time. A browser the app launched is closed by
+//! `browser_close` and when the app exits; an attached one is only
+//! disconnected.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::audit;
+use crate::computer::Screenshot;
+
+const DEFAULT_PORT: u16 = 9222;
+/// How long a launched browser gets to open its debugging port.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+const STARTUP_POLL: Duration = Duration::from_millis(200);
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long `Browser.close` gets before a launched browser is killed.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_NAVIGATE_TIMEOUT_MS: u64 = 30_000;
+const MAX_NAVIGATE_TIMEOUT_MS: u64 = 120_000;
+const DEFAULT_QUERY_LIMIT: u32 = 50;
+const MAX_QUERY_LIMIT: u32 = 500;
+/// Text kept per element by `browser_query_dom`.
+const MAX_ELEMENT_TEXT_CHARS: usize = 500;
+/// Text returned by `browser_extract_text`.
+const MAX_TEXT_CHARS: usize = 200_000;
+
+type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+type Replies = HashMap<u64, oneshot::Sender<Result<Value, String>>>;
+type Pending = Arc<Mutex<Replies>>;
+
+// ── DevTools connection ────────────────────────────────────────────────────────
+
+/// A DevTools websocket to one tab. Replies are matched to calls by id;
+/// event names are broadcast for whoever waits on them.
+struct Cdp {
+    sink: WsSink,
+    pending: Pending,
+    events: broadcast::Sender<String>,
+    next_id: u64,
+    reader: tokio::task::JoinHandle<()>,
+}
+
+impl Cdp {
+    async fn connect(ws_url: &str) -> Result<Self, String> {
+        let (ws, _) = tokio_tungstenite::connect_async(ws_url)
+            .await
+            .map_err(|e| format!("can't connect to the browser: {e}"))?;
+        let (sink, mut stream) = ws.split();
+        let pending: Pending = Arc::default();
+        let (events, _) = broadcast::channel(64);
+        let reader = tokio::spawn({
+            let pending = Arc::clone(&pending);
+            let events = events.clone();
+            async move {
+                while let Some(Ok(msg)) = stream.next().await {
+                    let Message::Text(text) = msg else { continue };
+                    let Ok(msg) = serde_json::from_str::<Value>(&text) else { continue };
+                    if let Some(id) = msg.get("id").and_then(Value::as_u64) {
+                        let tx = pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+                        if let Some(tx) = tx {
+                            let result = match msg.get("error") {
+                                Some(err) => Err(err
+                                    .get("message")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("protocol error")
+                                    .to_owned()),
+                                None => Ok(msg.get("result").cloned().unwrap_or(Value::Null)),
+                            };
+                            let _ = tx.send(result);
+                        }
+                    } else if let Some(method) = msg.get("method").and_then(Value::as_str) {
+                        let _ = events.send(method.to_owned());
+                    }
+                }
+                // Fails the calls still waiting for a reply.
+                pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            }
+        });
+        Ok(Self { sink, pending, events, next_id: 1, reader })
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, Replies> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (tx, rx) = oneshot::channel();
+        self.pending().insert(id, tx);
+        let msg = json!({ "id": id, "method": method, "params": params });
+        if let Err(e) = self.sink.send(Message::Text(msg.to_string())).await {
+            self.pending().remove(&id);
+            return Err(format!("browser connection lost: {e}"));
+        }
+        match tokio::time::timeout(CALL_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result.map_err(|e| format!("{method} failed: {e}")),
+            Ok(Err(_)) => Err("browser connection closed".into()),
+            Err(_) => {
+                self.pending().remove(&id);
+                Err(format!("{method} timed out"))
+            }
+        }
+    }
+
+    /// Evaluates `expression` in the page, awaiting it when it's a promise,
+    /// and returns its value. A thrown error fails with its message.
+    async fn eval(&mut self, expression: &str) -> Result<Value, String> {
+        let result = self
+            .call(
+                "Runtime.evaluate",
+                json!({ "expression": expression, "returnByValue": true, "awaitPromise": true }),
+            )
+            .await?;
+        if let Some(ex) = result.get("exceptionDetails") {
+            let text = ex
+                .pointer("/exception/description")
+                .or_else(|| ex.get("text"))
+                .and_then(Value::as_str)
+                .unwrap_or("script error");
+            return Err(text.lines().next().unwrap_or(text).to_owned());
+        }
+        Ok(result.pointer("/result/value").cloned().unwrap_or(Value::Null))
+    }
+}
+
+impl Drop for Cdp {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+// ── State ──────────────────────────────────────────────────────────────────────
+
+struct Session {
+    cdp: Cdp,
+    /// Set when the app launched the browser.
+    child: Option<std::process::Child>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// The browser being driven, managed via `tauri::Builder::manage`.
+#[derive(Default)]
+pub struct Browser {
+    session: tokio::sync::Mutex<Option<Session>>,
+}
+
+impl Browser {
+    /// Closes a launched browser, or disconnects from an attached one;
+    /// returns whether there was one. Called at shutdown.
+    pub async fn close(&self) -> bool {
+        let Some(mut session) = self.session.lock().await.take() else {
+            return false;
+        };
+        if session.child.is_some() {
+            let close = session.cdp.call("Browser.close", json!({}));
+            let _ = tokio::time::timeout(CLOSE_TIMEOUT, close).await;
+        }
+        true
+    }
+}
+
+fn connected(session: &mut Option<Session>) -> Result<&mut Cdp, String> {
+    session
+        .as_mut()
+        .map(|s| &mut s.cdp)
+        .ok_or_else(|| "no browser — call browser_launch or browser_attach first".to_string())
+}
+
+// ── Response types ─────────────────────────────────────────────────────────────
+
+/// Result of `browser_launch` and `browser_attach`.
+#[derive(Serialize)]
+pub struct BrowserInfo {
+    pub port: u16,
+    /// e.g. `Chrome/126.0.6478.127`.
+    pub version: String,
+    /// The app launched it (and closes it again).
+    pub launched: bool,
+}
+
+/// Result of `browser_navigate`.
+#[derive(Serialize, Deserialize)]
+pub struct PageInfo {
+    pub url: String,
+    pub title: String,
+}
+
+/// An element matched by `browser_query_dom`.
+#[derive(Serialize, Deserialize)]
+pub struct DomElement {
+    pub tag: String,
+    /// Rendered text, cut to 500 characters.
+    pub text: String,
+    pub attributes: HashMap<String, String>,
+    /// Bounds in CSS pixels, relative to the viewport.
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub visible: bool,
+}
+
+/// Result of `browser_query_dom`.
+#[derive(Serialize, Deserialize)]
+pub struct DomQuery {
+    /// Number of matches, including those beyond `limit`.
+    pub total: u32,
+    pub elements: Vec<DomElement>,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+fn devtools_http() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .no_proxy()
+        .build()
+        .map_err(|e| format!("http client unavailable: {e}"))
+}
+
+/// The browser version on `port`, if DevTools answers there.
+async fn devtools_version(http: &reqwest::Client, port: u16) -> Option<String> {
+    let info: Value = http
+        .get(format!("http://127.0.0.1:{port}/json/version"))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    info.get("Browser").and_then(Value::as_str).map(str::to_owned)
+}
+
+/// Connects to the first tab on `port`, opening one if there is none.
+async fn connect_page(http: &reqwest::Client, port: u16) -> Result<Cdp, String> {
+    let targets: Vec<Value> = http
+        .get(format!("http://127.0.0.1:{port}/json/list"))
+        .send()
+        .await
+        .map_err(|e| format!("can't list browser tabs: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("can't list browser tabs: {e}"))?;
+    let page = match targets.into_iter().find(|t| t.get("type").and_then(Value::as_str) == Some("page")) {
+        Some(page) => page,
+        None => http
+            .put(format!("http://127.0.0.1:{port}/json/new?about:blank"))
+            .send()
+            .await
+            .map_err(|e| format!("can't open a browser tab: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("can't open a browser tab: {e}"))?,
+    };
+    let ws_url = page
+        .get("webSocketDebuggerUrl")
+        .and_then(Value::as_str)
+        .ok_or("the tab is already being debugged by another client")?;
+    let mut cdp = Cdp::connect(ws_url).await?;
+    // Load events for `browser_navigate`.
+    cdp.call("Page.enable", json!({})).await?;
+    Ok(cdp)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Finds an installed Chrome, Chromium or Edge.
+fn find_browser() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let candidates: Vec<PathBuf> = [
+        "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+        "/Applications/Chromium.app/Contents/MacOS/Chromium",
+        "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+
+    #[cfg(target_os = "windows")]
+    let candidates: Vec<PathBuf> = ["PROGRAMFILES", "PROGRAMFILES(X86)", "LOCALAPPDATA"]
+        .iter()
+        .filter_map(|var| std::env::var_os(var).map(PathBuf::from))
+        .flat_map(|base| {
+            [
+                base.join(r"Google\Chrome\Application\chrome.exe"),
+                base.join(r"Chromium\Application\chrome.exe"),
+                base.join(r"Microsoft\Edge\Application\msedge.exe"),
+            ]
+        })
+        .collect();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let candidates: Vec<PathBuf> =
+        ["google-chrome", "google-chrome-stable", "chromium", "chromium-browser", "microsoft-edge"]
+            .iter()
+            .filter_map(|name| on_path(name))
+            .collect();
+
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+fn selector_literal(selector: &str) -> Result<String, String> {
+    if selector.trim().is_empty() {
+        return Err("selector is empty".into());
+    }
+    serde_json::to_string(selector).map_err(|e| e.to_string())
+}
+
+/// Scrolls the element matching `selector` into view and returns its
+/// bounds, in viewport (`page = false`) or page coordinates.
+async fn element_rect(cdp: &mut Cdp, selector: &str, page: bool) -> Result<(f64, f64, f64, f64), String> {
+    let sel = selector_literal(selector)?;
+    let rect = cdp
+        .eval(&format!(
+            "(() => {{
+                const el = document.querySelector({sel});
+                if (!el) throw new Error('no element matches ' + {sel});
+                el.scrollIntoView({{ block: 'center', inline: 'center' }});
+                const r = el.getBoundingClientRect();
+                const [dx, dy] = {page} ? [window.scrollX, window.scrollY] : [0, 0];
+                return [r.x + dx, r.y + dy, r.width, r.height];
+            }})()"
+        ))
+        .await?;
+    let n = |i: usize| rect.get(i).and_then(Value::as_f64).unwrap_or(0.0);
+    let (x, y, width, height) = (n(0), n(1), n(2), n(3));
+    if width <= 0.0 || height <= 0.0 {
+        return Err(format!("the element matching {selector} is not visible"));
+    }
+    Ok((x, y, width, height))
+}
+
+fn clip_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((i, _)) => text[..i].to_owned(),
+        None => text.to_owned(),
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Starts Chrome (or Chromium / Edge) with remote debugging on `port`
+/// (default 9222) and a profile of its own under the app's data directory,
+/// and connects to it. `headless` runs it without a window.
+#[tauri::command]
+pub async fn browser_launch(
+    app: AppHandle,
+    browser: State<'_, Browser>,
+    port: Option<u16>,
+    headless: Option<bool>,
+    module: Option<String>,
+) -> Result<BrowserInfo, String> {
+    let args = json!({ "port": port, "headless": headless });
+    let result: Result<BrowserInfo, String> = async move {
+        let port = port.unwrap_or(DEFAULT_PORT);
+        let mut session = browser.session.lock().await;
+        if session.is_some() {
+            return Err("a browser is already connected — call browser_close first".into());
+        }
+        let http = devtools_http()?;
+        if devtools_version(&http, port).await.is_some() {
+            return Err(format!(
+                "a browser is already listening on port {port} — use browser_attach or another port"
+            ));
+        }
+        let exe = find_browser().ok_or("no Chrome, Chromium or Edge installation found")?;
+        let profile = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("app data dir unavailable: {e}"))?
+            .join("browser-profile");
+
+        let mut cmd = std::process::Command::new(&exe);
+        cmd.arg(format!("--remote-debugging-port={port}"))
+            .arg(format!("--user-data-dir={}", profile.display()))
+            .args(["--no-first-run", "--no-default-browser-check"])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        if headless.unwrap_or(false) {
+            cmd.arg("--headless=new");
+        }
+        cmd.arg("about:blank");
+        let mut child = cmd.spawn().map_err(|e| format!("browser launch failed: {e}"))?;
+
+        let started = std::time::Instant::now();
+        let version = loop {
+            if let Some(version) = devtools_version(&http, port).await {
+                break version;
+            }
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(format!("the browser exited during startup ({status})"));
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err("the browser didn't open its debugging port in time".into());
+            }
+            tokio::time::sleep(STARTUP_POLL).await;
+        };
+        let cdp = match connect_page(&http, port).await {
+            Ok(cdp) => cdp,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+        *session = Some(Session { cdp, child: Some(child) });
+        Ok(BrowserInfo { port, version, launched: true })
+    }
+    .await;
+    audit::record("browser_launch", args, module.as_deref(), &result);
+    result
+}
+
+/// Connects to a browser already running with `--remote-debugging-port`
+/// (default 9222) on this machine.
+#[tauri::command]
+pub async fn browser_attach(
+    browser: State<'_, Browser>,
+    port: Option<u16>,
+    module: Option<String>,
+) -> Result<BrowserInfo, String> {
+    let args = json!({ "port": port });
+    let result: Result<BrowserInfo, String> = async move {
+        let port = port.unwrap_or(DEFAULT_PORT);
+        let mut session = browser.session.lock().await;
+        if session.is_some() {
+            return Err("a browser is already connected — call browser_close first".into());
+        }
+        let http = devtools_http()?;
+        let version = devtools_version(&http, port)
+            .await
+            .ok_or_else(|| format!("no browser with remote debugging on port {port}"))?;
+        let cdp = connect_page(&http, port).await?;
+        *session = Some(Session { cdp, child: None });
+        Ok(BrowserInfo { port, version, launched: false })
+    }
+    .await;
+    audit::record("browser_attach", args, module.as_deref(), &result);
+    result
+}
+
+/// Closes the browser if the app launched it, or disconnects from it;
+/// returns whether one was connected.
+#[tauri::command]
+pub async fn browser_close(browser: State<'_, Browser>) -> Result<bool, String> {
+    Ok(browser.close().await)
+}
+
+/// Opens `url` (http or https) in the tab and waits for it to load, up to
+/// `timeout_ms` (default 30 s, at most 120 s).
+#[tauri::command]
+pub async fn browser_navigate(
+    browser: State<'_, Browser>,
+    url: String,
+    timeout_ms: Option<u64>,
+    module: Option<String>,
+) -> Result<PageInfo, String> {
+    let args = json!({ "url": url, "timeout_ms": timeout_ms });
+    let result: Result<PageInfo, String> = async move {
+        let timeout_ms = timeout_ms.unwrap_or(DEFAULT_NAVIGATE_TIMEOUT_MS);
+        if !(1..=MAX_NAVIGATE_TIMEOUT_MS).contains(&timeout_ms) {
+            return Err(format!("timeout_ms must be between 1 and {MAX_NAVIGATE_TIMEOUT_MS}"));
+        }
+        let parsed = tauri::Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("url scheme '{}' is not allowed", parsed.scheme()));
+        }
+        let mut session = browser.session.lock().await;
+        let cdp = connected(&mut session)?;
+
+        let mut events = cdp.events.subscribe();
+        let nav = cdp.call("Page.navigate", json!({ "url": parsed.as_str() })).await?;
+        if let Some(err) = nav.get("errorText").and_then(Value::as_str) {
+            return Err(format!("navigation failed: {err}"));
+        }
+        // Without a loader the navigation stayed in the same document
+        // (e.g. a #fragment), so there is no load to wait for.
+        if nav.get("loaderId").is_some() {
+            let loaded = async {
+                loop {
+                    match events.recv().await {
+                        Ok(method) if method == "Page.loadEventFired" => return Ok(()),
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err("browser connection closed".to_string())
+                        }
+                    }
+                }
+            };
+            tokio::time::timeout(Duration::from_millis(timeout_ms), loaded)
+                .await
+                .map_err(|_| "the page didn't finish loading in time".to_string())??;
+        }
+        let page = cdp.eval("({ url: location.href, title: document.title })").await?;
+        serde_json::from_value(page).map_err(|e| format!("unexpected page info: {e}"))
+    }
+    .await;
+    audit::record("browser_navigate", args, module.as_deref(), &result);
+    result
+}
+
+/// Lists the elements matching the CSS `selector`, in document order — at
+/// most `limit` (default 50, at most 500) — with their text, attributes and
+/// bounds.
+#[tauri::command]
+pub async fn browser_query_dom(
+    browser: State<'_, Browser>,
+    selector: String,
+    limit: Option<u32>,
+) -> Result<DomQuery, String> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    if !(1..=MAX_QUERY_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {MAX_QUERY_LIMIT}"));
+    }
+    let sel = selector_literal(&selector)?;
+    let mut session = browser.session.lock().await;
+    let cdp = connected(&mut session)?;
+    let found = cdp
+        .eval(&format!(
+            "(() => {{
+                const all = document.querySelectorAll({sel});
+                const elements = Array.from(all).slice(0, {limit}).map(el => {{
+                    const r = el.getBoundingClientRect();
+                    const style = getComputedStyle(el);
+                    const attributes = {{}};
+                    for (const a of el.attributes) attributes[a.name] = a.value.slice(0, {MAX_ELEMENT_TEXT_CHARS});
+                    return {{
+                        tag: el.tagName.toLowerCase(),
+                        text: (el.innerText ?? el.textContent ?? '').trim().slice(0, {MAX_ELEMENT_TEXT_CHARS}),
+                        attributes,
+                        x: r.x, y: r.y, width: r.width, height: r.height,
+                        visible: r.width > 0 && r.height > 0
+                            && style.visibility !== 'hidden' && style.display !== 'none',
+                    }};
+                }});
+                return {{ total: all.length, elements }};
+            }})()"
+        ))
+        .await?;
+    serde_json::from_value(found).map_err(|e| format!("unexpected query result: {e}"))
+}
+
+/// Scrolls the first element matching `selector` into view and clicks its
+/// centre with real mouse events, so the page sees the same events as a
+/// user's click.
+#[tauri::command]
+pub async fn browser_click_selector(
+    browser: State<'_, Browser>,
+    selector: String,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = json!({ "selector": selector });
+    let result: Result<(), String> = async move {
+        let mut session = browser.session.lock().await;
+        let cdp = connected(&mut session)?;
+        let (x, y, width, height) = element_rect(cdp, &selector, false).await?;
+        let (x, y) = (x + width / 2.0, y + height / 2.0);
+        cdp.call("Input.dispatchMouseEvent", json!({ "type": "mouseMoved", "x": x, "y": y }))
+            .await?;
+        for kind in ["mousePressed", "mouseReleased"] {
+            cdp.call(
+                "Input.dispatchMouseEvent",
+                json!({ "type": kind, "x": x, "y": y, "button": "left", "clickCount": 1 }),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+    .await;
+    audit::record("browser_click_selector", args, module.as_deref(), &result);
+    result
+}
+
+/// Returns the rendered text of the first element matching `selector`, or
+/// of the whole page without one, cut to 200 000 characters.
+#[tauri::command]
+pub async fn browser_extract_text(
+    browser: State<'_, Browser>,
+    selector: Option<String>,
+) -> Result<String, String> {
+    let sel = selector_literal(selector.as_deref().unwrap_or("body"))?;
+    let mut session = browser.session.lock().await;
+    let cdp = connected(&mut session)?;
+    let text = cdp
+        .eval(&format!(
+            "(() => {{
+                const el = document.querySelector({sel});
+                if (!el) throw new Error('no element matches ' + {sel});
+                return el.innerText ?? el.textContent ?? '';
+            }})()"
+        ))
+        .await?;
+    Ok(clip_chars(text.as_str().unwrap_or_default(), MAX_TEXT_CHARS))
+}
+
+/// Captures the first element matching `selector` as a PNG, scrolling it
+/// into view first. Parts outside the viewport are captured too.
+#[tauri::command]
+pub async fn browser_screenshot_element(
+    browser: State<'_, Browser>,
+    selector: String,
+) -> Result<Screenshot, String> {
+    let mut session = browser.session.lock().await;
+    let cdp = connected(&mut session)?;
+    let (x, y, width, height) = element_rect(cdp, &selector, true).await?;
+    let shot = cdp
+        .call(
+            "Page.captureScreenshot",
+            json!({
+                "format": "png",
+                "clip": { "x": x, "y": y, "width": width, "height": height, "scale": 1 },
+                "captureBeyondViewport": true,
+            }),
+        )
+        .await?;
+    let data = shot.get("data").and_then(Value::as_str).ok_or("the browser returned no image")?;
+    let png = B64.decode(data).map_err(|e| format!("malformed screenshot: {e}"))?;
+    let (width, height) = image::io::Reader::new(std::io::Cursor::new(&png))
+        .with_guessed_format()
+        .ok()
+        .and_then(|r| r.into_dimensions().ok())
+        .ok_or("malformed screenshot")?;
+    Ok(Screenshot { data_uri: format!("data:image/png;base64,{data}"), width, height })
+}
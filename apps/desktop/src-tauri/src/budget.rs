@@ -0,0 +1,256 @@
+//! Spend caps on the direct (BYOK) path.
+//!
+//! The user can cap the estimated daily and monthly spend of direct provider
+//! calls (`settings_set_spend_limits`). Before each call the spend recorded
+//! in the [`usage_ledger`] is checked against the caps; once one is reached
+//! the call is refused and `budget:exceeded` is emitted. With
+//! `confirm_over_limit` set the refusal can be lifted from the UI with
+//! `budget_confirm`, which lets calls through until the capped day or month
+//! is over.
+//!
+//! Spend comes from the ledger's estimates, and a call is only checked
+//! before it starts, so a cap can be overshot by the call that crosses it.
+//! While a cap is set and the ledger can't be read, calls are refused.
+
+use std::sync::Mutex;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::{settings, usage_ledger};
+
+const SPEND_LIMITS_KEY: &str = "spend_limits";
+
+/// Caps in USD; `None` means no cap.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct SpendLimits {
+    pub daily_usd: Option<f64>,
+    pub monthly_usd: Option<f64>,
+    /// Let the user go over a reached cap with `budget_confirm` instead of
+    /// refusing calls until the period ends.
+    #[serde(default)]
+    pub confirm_over_limit: bool,
+}
+
+/// Result of `budget_status` and payload of `budget:exceeded`.
+#[derive(Serialize, Clone)]
+pub struct BudgetStatus {
+    pub limits: SpendLimits,
+    /// Estimated, in USD, since local midnight.
+    pub spent_today: f64,
+    /// Estimated, in USD, since the first of the month (local time).
+    pub spent_this_month: f64,
+    /// `daily` or `monthly` when that cap is reached; when both are, the one
+    /// not yet confirmed (else `daily`).
+    pub exceeded: Option<&'static str>,
+    /// The user confirmed going over the `exceeded` cap.
+    pub confirmed: bool,
+}
+
+/// Periods the user confirmed going over, until they end.
+#[derive(Default)]
+struct Confirmed {
+    day: Option<NaiveDate>,
+    month: Option<(i32, u32)>,
+}
+
+static CONFIRMED: Mutex<Confirmed> = Mutex::new(Confirmed { day: None, month: None });
+
+pub fn load_limits(app: &AppHandle) -> SpendLimits {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(SPEND_LIMITS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn status(limits: SpendLimits) -> Result<BudgetStatus, String> {
+    let today = chrono::Local::now().date_naive();
+    let spent_today = usage_ledger::cost_since(usage_ledger::start_of(today))?;
+    let spent_this_month = usage_ledger::cost_since(usage_ledger::start_of(today.with_day(1).unwrap_or(today)))?;
+    let c = CONFIRMED.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(evaluate(limits, spent_today, spent_this_month, &c, today))
+}
+
+/// Checks the spend against `limits` on local day `today`.
+fn evaluate(
+    limits: SpendLimits,
+    spent_today: f64,
+    spent_this_month: f64,
+    c: &Confirmed,
+    today: NaiveDate,
+) -> BudgetStatus {
+    let allowed = limits.confirm_over_limit;
+    let (day_ok, month_ok) =
+        (allowed && c.day == Some(today), allowed && c.month == Some((today.year(), today.month())));
+    // (period, reached, confirmed), the unconfirmed one first.
+    let mut caps = [
+        ("daily", limits.daily_usd.is_some_and(|cap| spent_today >= cap), day_ok),
+        ("monthly", limits.monthly_usd.is_some_and(|cap| spent_this_month >= cap), month_ok),
+    ];
+    caps.sort_by_key(|&(_, _, confirmed)| confirmed);
+    let reached = caps.iter().find(|&&(_, reached, _)| reached);
+    BudgetStatus {
+        limits,
+        spent_today,
+        spent_this_month,
+        exceeded: reached.map(|&(period, _, _)| period),
+        confirmed: reached.is_some_and(|&(_, _, confirmed)| confirmed),
+    }
+}
+
+/// Records the user going over the caps `status` found reached.
+fn confirm(c: &mut Confirmed, status: &BudgetStatus, today: NaiveDate) {
+    let limits = status.limits;
+    if limits.daily_usd.is_some_and(|cap| status.spent_today >= cap) {
+        c.day = Some(today);
+    }
+    if limits.monthly_usd.is_some_and(|cap| status.spent_this_month >= cap) {
+        c.month = Some((today.year(), today.month()));
+    }
+}
+
+/// Fails, emitting `budget:exceeded`, when a spend cap is reached and the
+/// user hasn't confirmed going over it. Call before each direct provider
+/// call. Fails too when a cap is set and the ledger can't be read.
+pub fn ensure_within(app: &AppHandle) -> Result<(), String> {
+    let limits = load_limits(app);
+    if limits.daily_usd.is_none() && limits.monthly_usd.is_none() {
+        return Ok(());
+    }
+    let status = status(limits).map_err(|e| format!("can't check the spend limits: {e}"))?;
+    let Some(period) = status.exceeded.filter(|_| !status.confirmed) else {
+        return Ok(());
+    };
+    let (cap, spent) = match period {
+        "daily" => (limits.daily_usd, status.spent_today),
+        _ => (limits.monthly_usd, status.spent_this_month),
+    };
+    let _ = app.emit("budget:exceeded", status);
+    let mut err = format!(
+        "{period} spend limit of ${:.2} reached (${spent:.2} spent)",
+        cap.unwrap_or_default()
+    );
+    if limits.confirm_over_limit {
+        err.push_str(" — confirm to continue");
+    }
+    Err(err)
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the spend caps, or no caps when none are set.
+#[tauri::command]
+pub async fn settings_get_spend_limits(app: AppHandle) -> SpendLimits {
+    load_limits(&app)
+}
+
+/// Sets the daily and monthly spend caps in USD; `null` removes a cap.
+#[tauri::command]
+pub async fn settings_set_spend_limits(app: AppHandle, limits: SpendLimits) -> Result<(), String> {
+    for cap in [limits.daily_usd, limits.monthly_usd].into_iter().flatten() {
+        if !cap.is_finite() || cap <= 0.0 {
+            return Err(format!("spend limit must be a positive amount (got {cap})"));
+        }
+    }
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(limits).map_err(|e| e.to_string())?;
+    store.set(SPEND_LIMITS_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+/// Returns today's and this month's estimated direct-provider spend against
+/// the caps.
+#[tauri::command]
+pub async fn budget_status(app: AppHandle) -> Result<BudgetStatus, String> {
+    status(load_limits(&app))
+}
+
+/// Lets direct provider calls go over the reached cap(s) until the capped
+/// day or month ends. Only allowed when `confirm_over_limit` is set.
+#[tauri::command]
+pub async fn budget_confirm(app: AppHandle) -> Result<BudgetStatus, String> {
+    let limits = load_limits(&app);
+    if !limits.confirm_over_limit {
+        return Err("going over the spend limit isn't allowed (confirm_over_limit is off)".into());
+    }
+    let before = status(limits)?;
+    if before.exceeded.is_none() {
+        return Err("no spend limit has been reached".into());
+    }
+    confirm(&mut CONFIRMED.lock().unwrap_or_else(|e| e.into_inner()), &before, chrono::Local::now().date_naive());
+    status(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(daily: Option<f64>, monthly: Option<f64>, confirm_over_limit: bool) -> SpendLimits {
+        SpendLimits { daily_usd: daily, monthly_usd: monthly, confirm_over_limit }
+    }
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn no_cap_is_never_exceeded() {
+        let s = evaluate(SpendLimits::default(), 1e6, 1e6, &Confirmed::default(), day("2026-10-15"));
+        assert_eq!(s.exceeded, None);
+        assert!(!s.confirmed);
+    }
+
+    #[test]
+    fn a_cap_is_reached_at_its_amount() {
+        let today = day("2026-10-15");
+        let c = Confirmed::default();
+        assert_eq!(evaluate(limits(Some(5.0), None, false), 4.99, 4.99, &c, today).exceeded, None);
+        assert_eq!(evaluate(limits(Some(5.0), None, false), 5.0, 5.0, &c, today).exceeded, Some("daily"));
+        assert_eq!(evaluate(limits(None, Some(50.0), false), 5.0, 50.0, &c, today).exceeded, Some("monthly"));
+        // Both reached and neither confirmed: daily.
+        assert_eq!(evaluate(limits(Some(5.0), Some(50.0), false), 5.0, 50.0, &c, today).exceeded, Some("daily"));
+    }
+
+    #[test]
+    fn confirming_lifts_the_reached_caps_until_the_period_ends() {
+        let today = day("2026-10-15");
+        let caps = limits(Some(5.0), Some(50.0), true);
+        let mut c = Confirmed::default();
+        let before = evaluate(caps, 5.0, 20.0, &c, today);
+        confirm(&mut c, &before, today);
+        assert_eq!(c.day, Some(today));
+        assert_eq!(c.month, None);
+
+        let s = evaluate(caps, 6.0, 20.0, &c, today);
+        assert_eq!((s.exceeded, s.confirmed), (Some("daily"), true));
+        // The monthly cap wasn't confirmed, so it is the one reported.
+        let s = evaluate(caps, 6.0, 50.0, &c, today);
+        assert_eq!((s.exceeded, s.confirmed), (Some("monthly"), false));
+        // Tomorrow the daily cap applies again.
+        let s = evaluate(caps, 5.0, 20.0, &c, day("2026-10-16"));
+        assert_eq!((s.exceeded, s.confirmed), (Some("daily"), false));
+    }
+
+    #[test]
+    fn a_monthly_confirmation_ends_with_the_month() {
+        let caps = limits(None, Some(50.0), true);
+        let mut c = Confirmed::default();
+        let before = evaluate(caps, 0.0, 50.0, &c, day("2026-10-15"));
+        confirm(&mut c, &before, day("2026-10-15"));
+        assert!(evaluate(caps, 0.0, 60.0, &c, day("2026-10-31")).confirmed);
+        assert!(!evaluate(caps, 0.0, 60.0, &c, day("2026-11-01")).confirmed);
+    }
+
+    #[test]
+    fn a_confirmation_counts_only_while_allowed() {
+        let today = day("2026-10-15");
+        let c = Confirmed { day: Some(today), month: None };
+        let s = evaluate(limits(Some(5.0), None, false), 5.0, 5.0, &c, today);
+        assert_eq!((s.exceeded, s.confirmed), (Some("daily"), false));
+    }
+}
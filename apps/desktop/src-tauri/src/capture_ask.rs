@@ -0,0 +1,411 @@
+//! "Capture and ask": a global hotkey that sends a screen region to chat.
+//!
+//! Pressing the hotkey (see `settings_set_capture_ask`) captures the primary
+//! screen and covers it with a borderless, always-on-top overlay showing the
+//! capture, where the user drags the region to ask about — Esc or a right
+//! click cancels. The overlay page and image are served from Rust over the
+//! `capture:` URI scheme, and the selection comes back as a navigation that
+//! is intercepted rather than loaded, so the overlay needs no IPC access.
+//!
+//! The crop is sent to the main window as a `capture:ask` event with the
+//! configured prompt; the frontend opens a new chat or uses the current one,
+//! attaches the image (see `chat_send`'s `images`) and pre-fills the prompt.
+
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use screenshots::Screen;
+use serde::{Deserialize, Serialize};
+use tauri::http::{Request, Response};
+use tauri::{AppHandle, Emitter, Manager, UriSchemeContext, WebviewUrl, WebviewWindowBuilder, Wry};
+use tauri_plugin_store::StoreExt;
+
+use crate::hotkey::{Hotkey, HotkeyRegistration};
+use crate::{hotkey, resources, settings, shutdown};
+
+const CAPTURE_ASK_KEY: &str = "capture_ask";
+const HOTKEY_NAME: &str = "capture_ask";
+pub const SCHEME: &str = "capture";
+const OVERLAY_LABEL: &str = "capture-overlay";
+/// Selections narrower or shorter than this (in overlay pixels) are treated
+/// as stray clicks.
+const MIN_SELECTION: f64 = 4.0;
+
+/// Stored under `capture_ask` in the settings store.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CaptureAskConfig {
+    pub enabled: bool,
+    /// Accelerator such as `CmdOrCtrl+Shift+A`.
+    pub hotkey: String,
+    /// Prompt pre-filled next to the capture.
+    pub prompt: String,
+    /// Start a new chat instead of adding to the current one.
+    pub new_session: bool,
+}
+
+impl Default for CaptureAskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: "CmdOrCtrl+Shift+A".into(),
+            prompt: "What's in this screenshot?".into(),
+            new_session: false,
+        }
+    }
+}
+
+/// Payload of the `capture:ask` event.
+#[derive(Serialize, Clone)]
+pub struct CaptureAskEvent {
+    /// PNG data URI of the selected region.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    pub prompt: String,
+    pub new_session: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    hotkey: Option<HotkeyRegistration>,
+    /// The capture behind the open overlay.
+    screen: Option<image::RgbaImage>,
+    screen_png: Vec<u8>,
+}
+
+/// The registered hotkey and the capture in progress.
+#[derive(Default)]
+pub struct CaptureAsk(Mutex<Inner>);
+
+impl CaptureAsk {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub fn load_config(app: &AppHandle) -> CaptureAskConfig {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(CAPTURE_ASK_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Registers the configured hotkey at startup; a hotkey that can't be
+/// registered (e.g. no X11 display) is reported by [`hotkey::register`].
+pub fn start(app: &AppHandle) {
+    let _ = register(app, &load_config(app));
+}
+
+/// Unregisters the hotkey and closes an open overlay.
+pub fn stop(app: &AppHandle) {
+    app.state::<CaptureAsk>().lock().hotkey = None;
+    close_overlay(app);
+}
+
+/// Replaces the hotkey with one for `config`, or removes it when disabled.
+fn register(app: &AppHandle, config: &CaptureAskConfig) -> Result<(), String> {
+    let state = app.state::<CaptureAsk>();
+    state.lock().hotkey = None;
+    if !config.enabled {
+        hotkey::clear(app, HOTKEY_NAME);
+        return Ok(());
+    }
+    let handle = app.clone();
+    let registration = hotkey::register(app, HOTKEY_NAME, &config.hotkey, move || {
+        let app = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = begin(&app).await {
+                let _ = app.emit("capture:error", e);
+            }
+        });
+    })?;
+    state.lock().hotkey = Some(registration);
+    Ok(())
+}
+
+// ── Flow ───────────────────────────────────────────────────────────────────────
+
+/// Captures the primary screen and opens the selection overlay over it.
+async fn begin(app: &AppHandle) -> Result<(), String> {
+    shutdown::ensure_running()?;
+    if let Some(overlay) = app.get_webview_window(OVERLAY_LABEL) {
+        return overlay.set_focus().map_err(|e| e.to_string());
+    }
+
+    let handle = app.clone();
+    let (img, png, info) = tokio::task::spawn_blocking(move || {
+        let screens = Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
+        let screen = screens.into_iter().next().ok_or("no screens found")?;
+        let info = screen.display_info;
+        let scale = info.scale_factor.max(1.0);
+        resources::ensure_memory(
+            &handle,
+            resources::capture_memory_estimate(
+                (info.width as f32 * scale) as u32,
+                (info.height as f32 * scale) as u32,
+            ),
+        )?;
+        let img = screen.capture().map_err(|e| format!("capture failed: {e}"))?;
+        let png = encode_png(&img)?;
+        Ok::<_, String>((img, png, info))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))??;
+
+    {
+        let state = app.state::<CaptureAsk>();
+        let mut inner = state.lock();
+        inner.screen = Some(img);
+        inner.screen_png = png;
+    }
+
+    let handle = app.clone();
+    WebviewWindowBuilder::new(app, OVERLAY_LABEL, WebviewUrl::CustomProtocol(scheme_url("overlay")))
+        .title("Capture")
+        .position(f64::from(info.x), f64::from(info.y))
+        .inner_size(f64::from(info.width), f64::from(info.height))
+        .decorations(false)
+        .resizable(false)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(true)
+        .on_navigation(move |url| on_overlay_navigation(&handle, url))
+        .build()
+        .map_err(|e| format!("can't open the capture overlay: {e}"))?;
+    Ok(())
+}
+
+/// Custom schemes are served from `http://<scheme>.localhost` on Windows.
+fn scheme_url(path: &str) -> tauri::Url {
+    let url = if cfg!(windows) {
+        format!("http://{SCHEME}.localhost/{path}")
+    } else {
+        format!("{SCHEME}://localhost/{path}")
+    };
+    url.parse().expect("valid capture url")
+}
+
+/// Lets the overlay load its own page; `/select` and `/cancel` end the flow.
+fn on_overlay_navigation(app: &AppHandle, url: &tauri::Url) -> bool {
+    match url.path() {
+        "/overlay" => true,
+        "/select" => {
+            let selection = Selection::from_query(url);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = finish(&app, selection).await {
+                    let _ = app.emit("capture:error", e);
+                }
+            });
+            false
+        }
+        _ => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { close_overlay(&app) });
+            false
+        }
+    }
+}
+
+/// A dragged rectangle in overlay (CSS) pixels, with the overlay's size.
+#[derive(Debug, Default)]
+struct Selection {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    vw: f64,
+    vh: f64,
+}
+
+impl Selection {
+    fn from_query(url: &tauri::Url) -> Self {
+        let mut s = Self::default();
+        for (k, v) in url.query_pairs() {
+            let v = v.parse().unwrap_or(0.0);
+            match k.as_ref() {
+                "x" => s.x = v,
+                "y" => s.y = v,
+                "w" => s.w = v,
+                "h" => s.h = v,
+                "vw" => s.vw = v,
+                "vh" => s.vh = v,
+                _ => {}
+            }
+        }
+        s
+    }
+
+    /// The selection in image pixels, clamped to the image.
+    fn to_image_rect(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        if self.w < MIN_SELECTION || self.h < MIN_SELECTION || self.vw <= 0.0 || self.vh <= 0.0 {
+            return None;
+        }
+        let (sx, sy) = (f64::from(width) / self.vw, f64::from(height) / self.vh);
+        let x = (self.x * sx).clamp(0.0, f64::from(width)) as u32;
+        let y = (self.y * sy).clamp(0.0, f64::from(height)) as u32;
+        let w = ((self.w * sx) as u32).min(width - x);
+        let h = ((self.h * sy) as u32).min(height - y);
+        (w > 0 && h > 0).then_some((x, y, w, h))
+    }
+}
+
+/// Crops the capture to `selection` and hands it to the main window.
+async fn finish(app: &AppHandle, selection: Selection) -> Result<(), String> {
+    let screen = app.state::<CaptureAsk>().lock().screen.take();
+    close_overlay(app);
+    let Some(screen) = screen else {
+        return Ok(());
+    };
+    let Some((x, y, w, h)) = selection.to_image_rect(screen.width(), screen.height()) else {
+        return Ok(());
+    };
+
+    let png = tokio::task::spawn_blocking(move || {
+        encode_png(&image::imageops::crop_imm(&screen, x, y, w, h).to_image())
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))??;
+
+    let config = load_config(app);
+    let event = CaptureAskEvent {
+        image: format!("data:image/png;base64,{}", B64.encode(&png)),
+        width: w,
+        height: h,
+        prompt: config.prompt,
+        new_session: config.new_session,
+    };
+    if let Some(main) = app.get_webview_window("main") {
+        let _ = main.unminimize();
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+    app.emit_to("main", "capture:ask", event).map_err(|e| e.to_string())
+}
+
+fn close_overlay(app: &AppHandle) {
+    app.state::<CaptureAsk>().lock().screen_png = Vec::new();
+    if let Some(overlay) = app.get_webview_window(OVERLAY_LABEL) {
+        let _ = overlay.close();
+    }
+}
+
+fn encode_png(img: &image::RgbaImage) -> Result<Vec<u8>, String> {
+    use image::{ColorType, ImageEncoder};
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(img.as_raw(), img.width(), img.height(), ColorType::Rgba8)
+        .map_err(|e| format!("png encode failed: {e}"))?;
+    Ok(png)
+}
+
+// ── Overlay ────────────────────────────────────────────────────────────────────
+
+/// Serves the `capture:` scheme: the overlay page and the capture behind it.
+/// Only the overlay window may read the capture.
+pub fn protocol(ctx: UriSchemeContext<'_, Wry>, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let respond = |status: u16, content_type: &str, body: Vec<u8>| {
+        Response::builder()
+            .status(status)
+            .header("Content-Type", content_type)
+            .header("Cache-Control", "no-store")
+            .body(body)
+            .unwrap_or_default()
+    };
+    if ctx.webview_label() != OVERLAY_LABEL {
+        return respond(403, "text/plain", b"forbidden".to_vec());
+    }
+    match request.uri().path() {
+        "/overlay" => respond(200, "text/html; charset=utf-8", OVERLAY_HTML.as_bytes().to_vec()),
+        "/screen.png" => {
+            let png = ctx.app_handle().state::<CaptureAsk>().lock().screen_png.clone();
+            if png.is_empty() {
+                respond(404, "text/plain", b"no capture".to_vec())
+            } else {
+                respond(200, "image/png", png)
+            }
+        }
+        _ => respond(404, "text/plain", b"not found".to_vec()),
+    }
+}
+
+const OVERLAY_HTML: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><style>
+html, body { margin: 0; height: 100%; overflow: hidden; cursor: crosshair; user-select: none; }
+body { background: #000 url(screen.png) 0 0 / 100% 100% no-repeat; }
+#shade { position: fixed; inset: 0; background: rgba(0, 0, 0, .35); }
+#sel { position: fixed; display: none; border: 1px solid #4f8cff; background: url(screen.png) no-repeat;
+       box-shadow: 0 0 0 1px rgba(255, 255, 255, .6); }
+#hint { position: fixed; top: 16px; left: 50%; transform: translateX(-50%); padding: 6px 12px;
+        border-radius: 6px; background: rgba(0, 0, 0, .7); color: #fff; font: 13px system-ui, sans-serif; }
+</style></head><body>
+<div id="shade"></div><div id="sel"></div>
+<div id="hint">Drag to select a region · Esc to cancel</div>
+<script>
+const sel = document.getElementById('sel');
+let start = null, rect = null;
+function draw(e) {
+  const x = Math.min(start.x, e.clientX), y = Math.min(start.y, e.clientY);
+  rect = { x, y, w: Math.abs(e.clientX - start.x), h: Math.abs(e.clientY - start.y) };
+  Object.assign(sel.style, {
+    display: 'block', left: x + 'px', top: y + 'px', width: rect.w + 'px', height: rect.h + 'px',
+    backgroundSize: innerWidth + 'px ' + innerHeight + 'px', backgroundPosition: (-x - 1) + 'px ' + (-y - 1) + 'px',
+  });
+}
+function go(path) { location.href = path; }
+addEventListener('mousedown', e => { if (e.button === 0) { start = { x: e.clientX, y: e.clientY }; } });
+addEventListener('mousemove', e => { if (start) draw(e); });
+addEventListener('mouseup', e => {
+  if (!start || e.button !== 0) return;
+  draw(e);
+  start = null;
+  const q = new URLSearchParams({ ...rect, vw: innerWidth, vh: innerHeight });
+  go('/select?' + q);
+});
+addEventListener('contextmenu', e => { e.preventDefault(); go('/cancel'); });
+addEventListener('keydown', e => { if (e.key === 'Escape') go('/cancel'); });
+</script></body></html>"#;
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Starts the capture-and-ask flow without the hotkey (e.g. from a button).
+#[tauri::command]
+pub async fn capture_and_ask(app: AppHandle) -> Result<(), String> {
+    begin(&app).await
+}
+
+#[tauri::command]
+pub async fn settings_get_capture_ask(app: AppHandle) -> CaptureAskConfig {
+    load_config(&app)
+}
+
+/// Saves the capture-and-ask settings and re-registers the hotkey. Fails,
+/// without saving, when the hotkey can't be parsed or registered.
+#[tauri::command]
+pub async fn settings_set_capture_ask(
+    app: AppHandle,
+    config: CaptureAskConfig,
+) -> Result<(), String> {
+    let mut config = config;
+    config.hotkey = config.hotkey.trim().to_owned();
+    if config.enabled {
+        let hotkey = Hotkey::parse(&config.hotkey)?;
+        let quick = crate::quick_chat::load_config(&app);
+        if quick.quick_chat_enabled && Hotkey::parse(&quick.quick_chat).is_ok_and(|q| q == hotkey) {
+            return Err(format!("{hotkey} is already the quick chat hotkey"));
+        }
+    }
+    if config.prompt.trim().is_empty() {
+        config.prompt = CaptureAskConfig::default().prompt;
+    }
+    register(&app, &config)?;
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store.set(CAPTURE_ASK_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
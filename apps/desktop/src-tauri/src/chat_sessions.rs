@@ -0,0 +1,312 @@
+//! Conversation sessions.
+//!
+//! Chat history is keyed by an opaque session ID; the `sessions` table of
+//! the app's `memory.db` gives each one a title, timestamps, a message
+//! count and an archived flag, so conversations can be listed and managed.
+//! `chat_send` keeps the table current for the messages it sends under a
+//! session ID, and `session_record_messages` for messages appended
+//! elsewhere — both create the row for a session not seen before.
+//! `session_auto_title` names a session after its first messages. Deleting
+//! a session forgets its memory context; its usage stays in the ledger,
+//! unattributed, so spend caps and usage history are unaffected.
+
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::memory_context::MemoryContexts;
+use crate::usage_ledger;
+
+const MAX_TITLE_CHARS: usize = 200;
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1000;
+
+static SESSIONS: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+/// Opens (creating if needed) the sessions table in `dir`.
+pub fn open(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    let conn = Connection::open(dir.join(usage_ledger::DB_FILE))
+        .map_err(|e| format!("can't open sessions table: {e}"))?;
+    conn.busy_timeout(std::time::Duration::from_secs(2))
+        .map_err(|e| format!("can't open sessions table: {e}"))?;
+    init(&conn)?;
+    let _ = SESSIONS.set(Mutex::new(conn));
+    Ok(())
+}
+
+/// Creates the sessions table in `conn`. Deletes detach the session's usage
+/// ledger rows, so its table must exist too (see [`usage_ledger::init`]).
+pub fn init(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sessions (
+             id            TEXT    PRIMARY KEY,
+             title         TEXT    NOT NULL,
+             created_at    INTEGER NOT NULL,
+             updated_at    INTEGER NOT NULL,
+             message_count INTEGER NOT NULL DEFAULT 0,
+             archived      INTEGER NOT NULL DEFAULT 0
+         );
+         CREATE INDEX IF NOT EXISTS sessions_updated_at ON sessions (updated_at);",
+    )
+    .map_err(|e| format!("can't create sessions table: {e}"))
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let conn = SESSIONS.get().ok_or("sessions table is not open")?;
+    f(&conn.lock().unwrap_or_else(|e| e.into_inner()))
+        .map_err(|e| format!("sessions table error: {e}"))
+}
+
+/// One conversation.
+#[derive(Serialize)]
+pub struct ChatSession {
+    pub id: String,
+    pub title: String,
+    /// RFC 3339 timestamps.
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+    pub archived: bool,
+}
+
+const COLUMNS: &str = "id, title, created_at, updated_at, message_count, archived";
+
+fn session(row: &rusqlite::Row) -> rusqlite::Result<ChatSession> {
+    let time = |i: usize| -> rusqlite::Result<String> {
+        let ts: i64 = row.get(i)?;
+        Ok(chrono::DateTime::from_timestamp_millis(ts).unwrap_or_default().to_rfc3339())
+    };
+    Ok(ChatSession {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        created_at: time(2)?,
+        updated_at: time(3)?,
+        message_count: row.get(4)?,
+        archived: row.get(5)?,
+    })
+}
+
+fn get(conn: &Connection, id: &str) -> rusqlite::Result<Option<ChatSession>> {
+    conn.query_row(&format!("SELECT {COLUMNS} FROM sessions WHERE id = ?1"), [id], session)
+        .optional()
+}
+
+fn clean_title(title: &str) -> Result<String, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("title must not be empty".into());
+    }
+    Ok(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+/// Retitles session `id`; also used by `session_auto_title`.
+pub fn set_title(id: &str, title: &str) -> Result<(), String> {
+    let title = clean_title(title)?;
+    update(id, |conn| retitle(conn, id, &title))
+}
+
+/// Counts `count` messages appended to session `id`, creating it (titled
+/// `title`, or "New chat") when it doesn't exist yet.
+pub fn record_messages(id: &str, count: u32, title: Option<&str>) -> Result<ChatSession, String> {
+    let title = clean_title(title.unwrap_or("New chat"))?;
+    with_conn(|conn| record(conn, id, count, &title))?.ok_or_else(|| format!("unknown session '{id}'"))
+}
+
+/// Runs `f` and fails with "unknown session" when it changed no row.
+fn update(id: &str, f: impl FnOnce(&Connection) -> rusqlite::Result<usize>) -> Result<(), String> {
+    match with_conn(f)? {
+        0 => Err(format!("unknown session '{id}'")),
+        _ => Ok(()),
+    }
+}
+
+// ── Queries ────────────────────────────────────────────────────────────────────
+
+fn list(conn: &Connection, include_archived: bool, limit: u32) -> rusqlite::Result<Vec<ChatSession>> {
+    conn.prepare(&format!(
+        "SELECT {COLUMNS} FROM sessions WHERE ?1 OR archived = 0
+         ORDER BY updated_at DESC LIMIT ?2"
+    ))?
+    .query_map(params![include_archived, limit], session)?
+    .collect()
+}
+
+fn create(conn: &Connection, id: &str, title: &str) -> rusqlite::Result<Option<ChatSession>> {
+    let now = chrono::Utc::now().timestamp_millis();
+    conn.execute(
+        "INSERT INTO sessions (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+        params![id, title, now],
+    )?;
+    get(conn, id)
+}
+
+fn retitle(conn: &Connection, id: &str, title: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE sessions SET title = ?2, updated_at = ?3 WHERE id = ?1",
+        params![id, title, chrono::Utc::now().timestamp_millis()],
+    )
+}
+
+fn archive(conn: &Connection, id: &str, archived: bool) -> rusqlite::Result<usize> {
+    conn.execute("UPDATE sessions SET archived = ?2 WHERE id = ?1", params![id, archived])
+}
+
+/// Deletes the session's row. The usage recorded for it is kept — the
+/// budget sums the whole ledger — but no longer attributed to the session.
+fn delete(conn: &Connection, id: &str) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let deleted = tx.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+    if deleted > 0 {
+        tx.execute("UPDATE usage_ledger SET session_id = NULL WHERE session_id = ?1", [id])?;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+fn record(conn: &Connection, id: &str, count: u32, title: &str) -> rusqlite::Result<Option<ChatSession>> {
+    conn.execute(
+        "INSERT INTO sessions (id, title, created_at, updated_at, message_count)
+         VALUES (?1, ?2, ?3, ?3, ?4)
+         ON CONFLICT (id) DO UPDATE SET
+             updated_at = ?3, message_count = message_count + ?4",
+        params![id, title, chrono::Utc::now().timestamp_millis(), count],
+    )?;
+    get(conn, id)
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Lists sessions, most recently updated first; archived ones only with
+/// `include_archived`. `limit` defaults to 100 (at most 1000).
+#[tauri::command]
+pub fn session_list(
+    include_archived: Option<bool>,
+    limit: Option<u32>,
+) -> Result<Vec<ChatSession>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {MAX_LIST_LIMIT}"));
+    }
+    with_conn(|conn| list(conn, include_archived.unwrap_or(false), limit))
+}
+
+/// Creates a session titled `title` ("New chat" when omitted), with a new
+/// ID unless `id` is given.
+#[tauri::command]
+pub fn session_create(id: Option<String>, title: Option<String>) -> Result<ChatSession, String> {
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let title = clean_title(title.as_deref().unwrap_or("New chat"))?;
+    with_conn(|conn| create(conn, &id, &title))?.ok_or_else(|| format!("session '{id}' was not created"))
+}
+
+#[tauri::command]
+pub fn session_rename(id: String, title: String) -> Result<(), String> {
+    set_title(&id, &title)
+}
+
+/// Archives the session, or restores it with `archived: false`.
+#[tauri::command]
+pub fn session_archive(id: String, archived: Option<bool>) -> Result<(), String> {
+    update(&id, |conn| archive(conn, &id, archived.unwrap_or(true)))
+}
+
+/// Deletes the session's row and forgets its memory context. Its usage
+/// stays in the ledger without the session ID.
+#[tauri::command]
+pub fn session_delete(contexts: State<'_, MemoryContexts>, id: String) -> Result<(), String> {
+    update(&id, |conn| delete(conn, &id))?;
+    contexts.reset(&id);
+    Ok(())
+}
+
+/// Counts `count` messages appended to the session, creating it (titled
+/// `title`, or "New chat") when it doesn't exist yet.
+#[tauri::command]
+pub fn session_record_messages(
+    id: String,
+    count: u32,
+    title: Option<String>,
+) -> Result<ChatSession, String> {
+    record_messages(&id, count, title.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        usage_ledger::init(&conn).unwrap();
+        init(&conn).unwrap();
+        conn
+    }
+
+    fn ledger_rows(conn: &Connection, id: &str) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM usage_ledger WHERE session_id = ?1", [id], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn spend(conn: &Connection) -> (i64, f64) {
+        conn.query_row("SELECT COUNT(*), SUM(cost) FROM usage_ledger", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+    }
+
+    #[test]
+    fn recording_creates_then_counts() {
+        let conn = db();
+        let first = record(&conn, "s1", 2, "Trip plans").unwrap().unwrap();
+        assert_eq!((first.title.as_str(), first.message_count), ("Trip plans", 2));
+
+        let second = record(&conn, "s1", 1, "ignored").unwrap().unwrap();
+        assert_eq!((second.title.as_str(), second.message_count), ("Trip plans", 3));
+        assert_eq!(second.created_at, first.created_at);
+    }
+
+    #[test]
+    fn archived_sessions_are_listed_on_request() {
+        let conn = db();
+        create(&conn, "a", "A").unwrap();
+        create(&conn, "b", "B").unwrap();
+        assert_eq!(archive(&conn, "a", true).unwrap(), 1);
+        assert_eq!(archive(&conn, "missing", true).unwrap(), 0);
+
+        let ids = |all| list(&conn, all, 10).unwrap().into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids(false), ["b"]);
+        assert_eq!(ids(true).len(), 2);
+    }
+
+    #[test]
+    fn delete_keeps_the_spend_in_the_ledger() {
+        let conn = db();
+        record(&conn, "s1", 2, "New chat").unwrap();
+        for session in ["s1", "s1", "s2"] {
+            conn.execute(
+                "INSERT INTO usage_ledger
+                     (ts, provider, model, prompt_tokens, completion_tokens, cached_tokens, cost, session_id)
+                 VALUES (0, 'openai', 'gpt-4o', 10, 2, 0, 0.5, ?1)",
+                [session],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(delete(&conn, "s1").unwrap(), 1);
+        assert!(get(&conn, "s1").unwrap().is_none());
+        assert_eq!(spend(&conn), (3, 1.5));
+        assert_eq!(ledger_rows(&conn, "s1"), 0);
+        assert_eq!(ledger_rows(&conn, "s2"), 1);
+        // Unknown sessions change nothing.
+        assert_eq!(delete(&conn, "s2").unwrap(), 0);
+        assert_eq!(ledger_rows(&conn, "s2"), 1);
+    }
+
+    #[test]
+    fn titles_are_trimmed_and_capped() {
+        assert!(clean_title("  ").is_err());
+        assert_eq!(clean_title("  Hi ").unwrap(), "Hi");
+        assert_eq!(clean_title(&"x".repeat(300)).unwrap().chars().count(), MAX_TITLE_CHARS);
+    }
+}
@@ -12,17 +12,37 @@
 //!   System Settings → Privacy & Security → Accessibility → grant AI SuperApp.
 //! - **Screen capture** — Screen Recording access required.
 //!   System Settings → Privacy & Security → Screen Recording → grant AI SuperApp.
+//!
 //! Commands return a descriptive error string if permissions are not yet granted.
+//!
+//! Mouse and keyboard commands are refused while the screen is locked or the
+//! session is switched away (see `session`), and fail once more than the
+//! configured number of them run in a second; `computer_emergency_stop`
+//! (also bound to a global hotkey) halts them outright.
 
 use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
 use enigo::{
     Axis, Button, Coordinate,
     Direction::{Click, Press, Release},
-    Enigo, Key, Keyboard, Mouse, Settings,
+    Enigo, InputResult, Key, Keyboard, Mouse, Settings,
 };
 use screenshots::Screen;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::approval;
+use crate::audit;
+use crate::file_sandbox;
+use crate::resources;
+use crate::session;
+use crate::settings;
+use crate::shell_policy;
+use crate::text_input::{self, LayoutKey, TypeMethod};
 
 // ── Response types ─────────────────────────────────────────────────────────────
 
@@ -37,6 +57,31 @@ pub struct Screenshot {
   pub height: u32,
 }
 
+/// Result of `computer_screenshot`: a frame, or `{ "unchanged": true }` when
+/// `skip_unchanged` found the screen as it was last time.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ScreenshotResult {
+  Frame(Screenshot),
+  Unchanged { unchanged: bool },
+}
+
+/// Area of the primary screen, in physical pixels, top-left origin.
+#[derive(Deserialize, Clone, Copy)]
+pub struct ScreenRegion {
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+}
+
+/// A screen position, in physical pixels.
+#[derive(Deserialize, Clone, Copy)]
+pub struct ScreenPoint {
+  pub x: i32,
+  pub y: i32,
+}
+
 /// Primary screen dimensions in logical pixels.
 #[derive(Serialize)]
 pub struct ScreenSize {
@@ -51,12 +96,78 @@ pub struct MousePosition {
   pub y: i32,
 }
 
+/// An RGBA colour sampled from the screen.
+#[derive(Serialize)]
+pub struct PixelColor {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  pub a: u8,
+  /// `#rrggbb`.
+  pub hex: String,
+}
+
+/// Result of `computer_pixel_color`.
+#[derive(Serialize)]
+pub struct PixelProbe {
+  /// Colour of the pixel at the point.
+  pub color: PixelColor,
+  /// Mean colour of the square around it, when a `radius` was given.
+  pub average: Option<PixelColor>,
+}
+
+/// A top-level window that can be captured with `computer_screenshot_window`.
+#[derive(Serialize)]
+pub struct WindowInfo {
+  pub id: u32,
+  pub title: String,
+  pub app_name: String,
+  /// Bounds in physical pixels.
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+  pub minimized: bool,
+}
+
+/// Result of `computer_launch_app`.
+#[derive(Serialize)]
+pub struct LaunchedApp {
+  /// Process id of the launched app, when it could be determined.
+  pub pid: Option<u32>,
+  /// The app's window, when `wait_for_window` was set and one came up.
+  pub window: Option<WindowInfo>,
+  /// That window is the frontmost one.
+  pub frontmost: bool,
+}
+
 /// Result of a shell command execution.
 #[derive(Serialize)]
 pub struct ShellResult {
+  /// `-1` when the command was killed.
   pub exit_code: i32,
   pub stdout: String,
   pub stderr: String,
+  /// The command hit its timeout and was killed; the output is what it
+  /// wrote until then.
+  pub timed_out: bool,
+}
+
+/// Lines of a file, from `computer_read_file_range` or `computer_tail_file`.
+#[derive(Serialize)]
+pub struct FileLines {
+  pub content: String,
+  /// 1-based numbers of the first and last line returned; `0` when none
+  /// were, and for tails, whose line numbers are unknown.
+  pub start_line: u64,
+  pub end_line: u64,
+  /// Lines returned.
+  pub lines: u64,
+  /// More lines exist beyond the returned ones (after them for ranges,
+  /// before them for tails).
+  pub has_more: bool,
+  /// Output stopped at the 1 MB limit before all requested lines.
+  pub truncated: bool,
 }
 
 // ── Internal helpers ───────────────────────────────────────────────────────────
@@ -109,31 +220,186 @@ fn parse_key(s: &str) -> Key {
     }
 }
 
-/// Encodes an `image::RgbaImage` as a base64 PNG data URI.
-fn encode_screenshot(img: image::RgbaImage) -> Result<Screenshot, String> {
+/// Maps modifier names (`shift`, `ctrl`, `alt`, `cmd`, …) to keys,
+/// rejecting anything that isn't a modifier.
+fn parse_modifiers(names: &[String]) -> Result<Vec<Key>, String> {
+    names
+        .iter()
+        .map(|n| match parse_key(n) {
+            k @ (Key::Shift | Key::Control | Key::Alt | Key::Meta) => Ok(k),
+            _ => Err(format!("'{n}' is not a modifier (use shift, ctrl, alt or cmd)")),
+        })
+        .collect()
+}
+
+/// Runs `action` with `modifiers` held, releasing them in reverse order
+/// afterwards even when it fails.
+fn with_modifiers(
+    e: &mut Enigo,
+    modifiers: &[Key],
+    action: impl FnOnce(&mut Enigo) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut pressed = Vec::new();
+    let mut result = Ok(());
+    for &k in modifiers {
+        if let Err(err) = hold(e, Held::Key(k)) {
+            result = Err(format!("modifier press failed: {err}"));
+            break;
+        }
+        pressed.push(k);
+    }
+    if result.is_ok() {
+        result = action(e);
+    }
+    for &k in pressed.iter().rev() {
+        let released = let_go(e, Held::Key(k)).map_err(|err| format!("modifier release failed: {err}"));
+        result = result.and(released);
+    }
+    result
+}
+
+fn pixel_color([r, g, b, a]: [u8; 4]) -> PixelColor {
+    PixelColor { r, g, b, a, hex: format!("#{r:02x}{g:02x}{b:02x}") }
+}
+
+const DEFAULT_JPEG_QUALITY: u8 = 80;
+
+pub fn encode_png(img: &image::RgbaImage) -> Result<Vec<u8>, String> {
     use image::{ColorType, ImageEncoder};
-    let width = img.width();
-    let height = img.height();
     let mut png_bytes: Vec<u8> = Vec::new();
     image::codecs::png::PngEncoder::new(&mut png_bytes)
-        .write_image(img.as_raw(), width, height, ColorType::Rgba8)
+        .write_image(img.as_raw(), img.width(), img.height(), ColorType::Rgba8)
         .map_err(|e| format!("png encode failed: {e}"))?;
+    Ok(png_bytes)
+}
+
+fn encode_jpeg(img: &image::RgbaImage, quality: u8) -> Result<Vec<u8>, String> {
+    let rgb: Vec<u8> = img.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality)
+        .encode(&rgb, img.width(), img.height(), image::ColorType::Rgb8)
+        .map_err(|e| format!("jpeg encode failed: {e}"))?;
+    Ok(jpeg)
+}
+
+/// Encodes an `image::RgbaImage` as a base64 PNG data URI.
+fn encode_screenshot(img: image::RgbaImage) -> Result<Screenshot, String> {
+    let png_bytes = encode_png(&img)?;
     let data_uri = format!("data:image/png;base64,{}", B64.encode(&png_bytes));
-    Ok(Screenshot { data_uri, width, height })
+    Ok(Screenshot { data_uri, width: img.width(), height: img.height() })
+}
+
+/// Captures the primary screen, or `region` of it, after checking there is
+/// enough free memory to hold the capture. Blocking.
+pub fn capture(app: &AppHandle, region: Option<ScreenRegion>) -> Result<image::RgbaImage, String> {
+    let screens = Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
+    let screen = screens.into_iter().next().ok_or("no screens found")?;
+    match region {
+        Some(r) => {
+            resources::ensure_memory(app, resources::capture_memory_estimate(r.width, r.height))?;
+            screen
+                .capture_area(r.x, r.y, r.width, r.height)
+                .map_err(|e| format!("region capture failed: {e}"))
+        }
+        None => {
+            let info = screen.display_info;
+            let scale = info.scale_factor.max(1.0);
+            resources::ensure_memory(
+                app,
+                resources::capture_memory_estimate(
+                    (info.width as f32 * scale) as u32,
+                    (info.height as f32 * scale) as u32,
+                ),
+            )?;
+            screen.capture().map_err(|e| format!("capture failed: {e}"))
+        }
+    }
+}
+
+/// Side of the square tiles a frame is summarized by, in pixels.
+const FINGERPRINT_TILE: u32 = 32;
+/// Mean brightness change (0–255) below which a tile counts as unchanged,
+/// so compression noise and dithering don't register.
+const TILE_NOISE: u8 = 2;
+
+/// Mean brightness per tile of a capture, for cheap change detection.
+#[derive(PartialEq)]
+struct Fingerprint {
+    width: u32,
+    height: u32,
+    tiles: Vec<u8>,
+}
+
+/// Fingerprint of the last frame `computer_screenshot` returned with
+/// `skip_unchanged`.
+static LAST_FRAME: Mutex<Option<Fingerprint>> = Mutex::new(None);
+
+impl Fingerprint {
+    fn of(img: &image::RgbaImage) -> Self {
+        let cols = img.width().div_ceil(FINGERPRINT_TILE);
+        let rows = img.height().div_ceil(FINGERPRINT_TILE);
+        let mut sums = vec![(0u64, 0u64); (cols * rows) as usize];
+        for (x, y, p) in img.enumerate_pixels() {
+            let [r, g, b, _] = p.0;
+            let luma = (u64::from(r) * 299 + u64::from(g) * 587 + u64::from(b) * 114) / 1000;
+            let tile = &mut sums[((y / FINGERPRINT_TILE) * cols + x / FINGERPRINT_TILE) as usize];
+            tile.0 += luma;
+            tile.1 += 1;
+        }
+        Self {
+            width: img.width(),
+            height: img.height(),
+            tiles: sums.iter().map(|(sum, n)| (sum / (*n).max(1)) as u8).collect(),
+        }
+    }
+
+    /// Fraction of tiles that changed, `1.0` when the sizes differ.
+    fn changed_since(&self, prev: &Self) -> f64 {
+        if (self.width, self.height) != (prev.width, prev.height) || self.tiles.is_empty() {
+            return 1.0;
+        }
+        let changed = self
+            .tiles
+            .iter()
+            .zip(&prev.tiles)
+            .filter(|(a, b)| a.abs_diff(**b) > TILE_NOISE)
+            .count();
+        changed as f64 / self.tiles.len() as f64
+    }
 }
 
 // ── Screenshot commands ────────────────────────────────────────────────────────
 
 /// Captures the full primary screen and returns a base64 PNG data URI.
+/// Fails early when there isn't enough free memory to hold the capture.
 /// Requires Screen Recording permission on macOS.
+///
+/// With `skip_unchanged`, returns `{ "unchanged": true }` instead of a frame
+/// when at most `threshold` (a fraction, default `0`) of the screen changed
+/// since the last frame returned in this mode — for monitoring loops that
+/// would otherwise ship and process the same image again and again.
 #[tauri::command]
-pub async fn computer_screenshot() -> Result<Screenshot, String> {
-    tokio::task::spawn_blocking(|| {
-        let screens =
-            Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
-        let screen = screens.into_iter().next().ok_or("no screens found")?;
-        let img = screen.capture().map_err(|e| format!("capture failed: {e}"))?;
-        encode_screenshot(img)
+pub async fn computer_screenshot(
+    app: AppHandle,
+    skip_unchanged: Option<bool>,
+    threshold: Option<f64>,
+) -> Result<ScreenshotResult, String> {
+    let threshold = threshold.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("threshold must be between 0 and 1".into());
+    }
+    let skip_unchanged = skip_unchanged.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        let img = capture(&app, None)?;
+        if skip_unchanged {
+            let print = Fingerprint::of(&img);
+            let mut last = LAST_FRAME.lock().unwrap_or_else(|e| e.into_inner());
+            if last.as_ref().is_some_and(|prev| print.changed_since(prev) <= threshold) {
+                return Ok(ScreenshotResult::Unchanged { unchanged: true });
+            }
+            *last = Some(print);
+        }
+        encode_screenshot(img).map(ScreenshotResult::Frame)
     })
     .await
     .map_err(|e| format!("task panicked: {e}"))
@@ -145,18 +411,165 @@ pub async fn computer_screenshot() -> Result<Screenshot, String> {
 /// Requires Screen Recording permission on macOS.
 #[tauri::command]
 pub async fn computer_screenshot_region(
+    app: AppHandle,
     x: i32,
     y: i32,
     width: u32,
     height: u32,
 ) -> Result<Screenshot, String> {
+    tokio::task::spawn_blocking(move || {
+        let img = capture(&app, Some(ScreenRegion { x, y, width, height }))?;
+        encode_screenshot(img)
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Like `computer_screenshot` (or `computer_screenshot_region` with
+/// `region`), but returns the encoded image as raw bytes rather than a
+/// base64 data URI in JSON — a third smaller and without the serialization
+/// stall, for high-frequency capture loops. The frontend receives an
+/// `ArrayBuffer`; the size is in the image header.
+///
+/// `format` is `png` (default) or `jpeg`, with `quality` 1–100 (default 80).
+#[tauri::command]
+pub async fn computer_screenshot_raw(
+    app: AppHandle,
+    region: Option<ScreenRegion>,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<tauri::ipc::Response, String> {
+    let jpeg = match format.as_deref().unwrap_or("png") {
+        "png" => false,
+        "jpeg" | "jpg" => true,
+        other => return Err(format!("unsupported format '{other}' (use png or jpeg)")),
+    };
+    let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY);
+    if !(1..=100).contains(&quality) {
+        return Err("quality must be between 1 and 100".into());
+    }
+    tokio::task::spawn_blocking(move || {
+        let img = capture(&app, region)?;
+        let bytes = if jpeg { encode_jpeg(&img, quality)? } else { encode_png(&img)? };
+        Ok(tauri::ipc::Response::new(bytes))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Largest `radius` accepted by `computer_pixel_color`.
+const MAX_PROBE_RADIUS: u32 = 16;
+
+/// Samples the colour at (`x`, `y`) on the primary screen, in physical
+/// pixels — e.g. to notice a button turning enabled. With `radius`, also
+/// averages the square of `2 × radius + 1` pixels around the point, which
+/// is steadier against anti-aliasing. Requires Screen Recording permission
+/// on macOS.
+#[tauri::command]
+pub async fn computer_pixel_color(x: i32, y: i32, radius: Option<u32>) -> Result<PixelProbe, String> {
+    let radius = radius.unwrap_or(0);
+    if radius > MAX_PROBE_RADIUS {
+        return Err(format!("radius must be at most {MAX_PROBE_RADIUS}"));
+    }
     tokio::task::spawn_blocking(move || {
         let screens =
             Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
         let screen = screens.into_iter().next().ok_or("no screens found")?;
+        let side = radius * 2 + 1;
         let img = screen
-            .capture_area(x, y, width, height)
-            .map_err(|e| format!("region capture failed: {e}"))?;
+            .capture_area(x - radius as i32, y - radius as i32, side, side)
+            .map_err(|e| format!("pixel capture failed: {e}"))?;
+        let center = img
+            .get_pixel_checked(radius, radius)
+            .ok_or("point is off screen")?;
+        let average = (radius > 0).then(|| {
+            let mut sum = [0u64; 4];
+            for p in img.pixels() {
+                for (s, c) in sum.iter_mut().zip(p.0) {
+                    *s += u64::from(c);
+                }
+            }
+            let n = u64::from(img.width() * img.height()).max(1);
+            pixel_color(sum.map(|s| (s / n) as u8))
+        });
+        Ok(PixelProbe { color: pixel_color(center.0), average })
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+fn window_info(w: &xcap::Window) -> WindowInfo {
+    WindowInfo {
+        id: w.id(),
+        title: w.title().to_owned(),
+        app_name: w.app_name().to_owned(),
+        x: w.x(),
+        y: w.y(),
+        width: w.width(),
+        height: w.height(),
+        minimized: w.is_minimized(),
+    }
+}
+
+/// Lists the top-level windows, frontmost first.
+#[tauri::command]
+pub async fn computer_list_windows() -> Result<Vec<WindowInfo>, String> {
+    tokio::task::spawn_blocking(|| {
+        let windows =
+            xcap::Window::all().map_err(|e| format!("window list unavailable: {e}"))?;
+        Ok(windows.iter().map(window_info).collect())
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Captures one window's contents, cropped to its bounds, so other apps
+/// don't end up in the image. The window is picked by `window_id` (from
+/// `computer_list_windows`) or, failing that, as the frontmost one whose
+/// title contains `title_match` (case-insensitive). Minimized windows can't
+/// be captured. Requires Screen Recording permission on macOS.
+#[tauri::command]
+pub async fn computer_screenshot_window(
+    app: AppHandle,
+    window_id: Option<u32>,
+    title_match: Option<String>,
+) -> Result<Screenshot, String> {
+    let title_match = title_match
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty());
+    if window_id.is_none() && title_match.is_none() {
+        return Err("pass window_id or title_match".into());
+    }
+    tokio::task::spawn_blocking(move || {
+        let windows =
+            xcap::Window::all().map_err(|e| format!("window list unavailable: {e}"))?;
+        let window = match (window_id, &title_match) {
+            (Some(id), _) => windows.iter().find(|w| w.id() == id),
+            (None, Some(t)) => windows
+                .iter()
+                .filter(|w| w.title().to_lowercase().contains(t))
+                .find(|w| !w.is_minimized()),
+            (None, None) => None,
+        }
+        .ok_or("no matching window")?;
+        if window.is_minimized() {
+            return Err("the window is minimized".to_string());
+        }
+        resources::ensure_memory(
+            &app,
+            resources::capture_memory_estimate(window.width(), window.height()),
+        )?;
+        let img = window
+            .capture_image()
+            .map_err(|e| format!("window capture failed: {e}"))?;
+        // xcap builds against its own `image` version; move the pixels over.
+        let (width, height) = (img.width(), img.height());
+        let img = image::RgbaImage::from_raw(width, height, img.into_raw())
+            .ok_or("window capture returned a malformed image")?;
         encode_screenshot(img)
     })
     .await
@@ -203,65 +616,121 @@ pub async fn computer_mouse_position() -> Result<MousePosition, String> {
 /// Moves the mouse cursor to an absolute screen position.
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
-pub async fn computer_mouse_move(x: i32, y: i32) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        e.move_mouse(x, y, Coordinate::Abs)
-            .map_err(|e| format!("mouse move failed: {e}"))
+pub async fn computer_mouse_move(x: i32, y: i32, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "x": x, "y": y });
+    let result: Result<(), String> = async move {
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            e.move_mouse(x, y, Coordinate::Abs)
+                .map_err(|e| format!("mouse move failed: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_mouse_move", args, module.as_deref(), &result);
+    result
+}
+
+const MAX_CLICK_COUNT: u32 = 5;
+const DEFAULT_CLICK_INTERVAL_MS: u64 = 60;
+/// Kept below the shortest common OS double-click time.
+const MAX_CLICK_INTERVAL_MS: u64 = 400;
+
+/// Clicks `btn` `count` times, `interval` apart, with `modifiers` held.
+fn click_times(
+    x: Option<i32>,
+    y: Option<i32>,
+    btn: Button,
+    count: u32,
+    interval: std::time::Duration,
+    modifiers: &[Key],
+) -> Result<(), String> {
+    session::ensure_input_allowed()?;
+    let mut e =
+        Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+    if let (Some(cx), Some(cy)) = (x, y) {
+        e.move_mouse(cx, cy, Coordinate::Abs)
+            .map_err(|e| format!("move failed: {e}"))?;
+    }
+    with_modifiers(&mut e, modifiers, |e| {
+        for i in 1..=count {
+            if i > 1 {
+                std::thread::sleep(interval);
+            }
+            e.button(btn, Click)
+                .map_err(|e| format!("click {i} of {count} failed: {e}"))?;
+        }
+        Ok(())
     })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
 }
 
 /// Clicks a mouse button at the current position or at `(x, y)` if provided.
 /// `button`: `"left"` (default) | `"right"` | `"middle"`.
+/// `modifiers` (e.g. `["shift"]`, `["cmd"]`) are held during the click.
+/// `click_count` (default 1, at most 5) clicks in a row `interval_ms` apart
+/// (default 60, at most 400), so 2 double-clicks and 3 triple-clicks
+/// (selecting a line or paragraph).
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
 pub async fn computer_mouse_click(
     x: Option<i32>,
     y: Option<i32>,
     button: Option<String>,
+    modifiers: Option<Vec<String>>,
+    click_count: Option<u32>,
+    interval_ms: Option<u64>,
+    module: Option<String>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        if let (Some(cx), Some(cy)) = (x, y) {
-            e.move_mouse(cx, cy, Coordinate::Abs)
-                .map_err(|e| format!("move failed: {e}"))?;
+    let args = serde_json::json!({ "x": x, "y": y, "button": button, "modifiers": modifiers, "click_count": click_count, "interval_ms": interval_ms });
+    let result: Result<(), String> = async move {
+        let modifiers = parse_modifiers(&modifiers.unwrap_or_default())?;
+        let count = click_count.unwrap_or(1);
+        if !(1..=MAX_CLICK_COUNT).contains(&count) {
+            return Err(format!("click_count must be between 1 and {MAX_CLICK_COUNT}"));
+        }
+        let interval = interval_ms.unwrap_or(DEFAULT_CLICK_INTERVAL_MS);
+        if interval > MAX_CLICK_INTERVAL_MS {
+            return Err(format!("interval_ms must be at most {MAX_CLICK_INTERVAL_MS}"));
         }
         let btn = parse_button(button.as_deref().unwrap_or("left"));
-        e.button(btn, Click)
-            .map_err(|e| format!("click failed: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+        let interval = std::time::Duration::from_millis(interval);
+        tokio::task::spawn_blocking(move || click_times(x, y, btn, count, interval, &modifiers))
+            .await
+            .map_err(|e| format!("task panicked: {e}"))
+            .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_mouse_click", args, module.as_deref(), &result);
+    result
 }
 
 /// Double-clicks the left mouse button at the current position or at `(x, y)`.
+/// `modifiers` are held during both clicks, as in `computer_mouse_click`,
+/// which can also click other counts and intervals.
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
 pub async fn computer_mouse_double_click(
     x: Option<i32>,
     y: Option<i32>,
+    modifiers: Option<Vec<String>>,
+    module: Option<String>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        if let (Some(cx), Some(cy)) = (x, y) {
-            e.move_mouse(cx, cy, Coordinate::Abs)
-                .map_err(|e| format!("move failed: {e}"))?;
-        }
-        e.button(Button::Left, Click)
-            .map_err(|e| format!("first click failed: {e}"))?;
-        e.button(Button::Left, Click)
-            .map_err(|e| format!("second click failed: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+    let args = serde_json::json!({ "x": x, "y": y, "modifiers": modifiers });
+    let result: Result<(), String> = async move {
+        let modifiers = parse_modifiers(&modifiers.unwrap_or_default())?;
+        let interval = std::time::Duration::from_millis(DEFAULT_CLICK_INTERVAL_MS);
+        tokio::task::spawn_blocking(move || click_times(x, y, Button::Left, 2, interval, &modifiers))
+            .await
+            .map_err(|e| format!("task panicked: {e}"))
+            .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_mouse_double_click", args, module.as_deref(), &result);
+    result
 }
 
 /// Scrolls at the current or given position.
@@ -273,70 +742,345 @@ pub async fn computer_mouse_scroll(
     y: Option<i32>,
     delta_x: Option<i32>,
     delta_y: Option<i32>,
+    module: Option<String>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        if let (Some(cx), Some(cy)) = (x, y) {
-            e.move_mouse(cx, cy, Coordinate::Abs)
-                .map_err(|e| format!("move failed: {e}"))?;
-        }
-        if let Some(dy) = delta_y.filter(|v| *v != 0) {
-            e.scroll(dy, Axis::Vertical)
-                .map_err(|e| format!("vertical scroll failed: {e}"))?;
-        }
-        if let Some(dx) = delta_x.filter(|v| *v != 0) {
-            e.scroll(dx, Axis::Horizontal)
-                .map_err(|e| format!("horizontal scroll failed: {e}"))?;
-        }
-        Ok(())
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+    let args = serde_json::json!({ "x": x, "y": y, "delta_x": delta_x, "delta_y": delta_y });
+    let result: Result<(), String> = async move {
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            if let (Some(cx), Some(cy)) = (x, y) {
+                e.move_mouse(cx, cy, Coordinate::Abs)
+                    .map_err(|e| format!("move failed: {e}"))?;
+            }
+            if let Some(dy) = delta_y.filter(|v| *v != 0) {
+                e.scroll(dy, Axis::Vertical)
+                    .map_err(|e| format!("vertical scroll failed: {e}"))?;
+            }
+            if let Some(dx) = delta_x.filter(|v| *v != 0) {
+                e.scroll(dx, Axis::Horizontal)
+                    .map_err(|e| format!("horizontal scroll failed: {e}"))?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_mouse_scroll", args, module.as_deref(), &result);
+    result
 }
 
+const DEFAULT_DRAG_STEPS: u32 = 10;
+const MAX_DRAG_STEPS: u32 = 200;
+const DEFAULT_DRAG_STEP_DELAY_MS: u64 = 10;
+const MAX_DRAG_STEP_DELAY_MS: u64 = 1000;
+const MAX_DRAG_WAYPOINTS: usize = 100;
+/// Longest a drag may take, all steps and delays included.
+const MAX_DRAG_MS: u64 = 30_000;
+
 /// Drags the mouse from `(start_x, start_y)` to `(end_x, end_y)` while
-/// holding the left button — useful for selecting text or moving windows.
+/// holding `button` (`"left"` by default) — useful for selecting text,
+/// moving windows or drag-and-drop.
+///
+/// The path runs through `waypoints`, if any, and each leg is split into
+/// `steps` moves (default 10, at most 200) `step_delay_ms` apart (default
+/// 10, at most 1000), since many apps ignore a drag without intermediate
+/// mouse moves. The whole drag may take at most 30 seconds.
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn computer_mouse_drag(
     start_x: i32,
     start_y: i32,
     end_x: i32,
     end_y: i32,
+    button: Option<String>,
+    waypoints: Option<Vec<ScreenPoint>>,
+    steps: Option<u32>,
+    step_delay_ms: Option<u64>,
+    module: Option<String>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        e.move_mouse(start_x, start_y, Coordinate::Abs)
-            .map_err(|e| format!("move to start failed: {e}"))?;
-        e.button(Button::Left, Press)
-            .map_err(|e| format!("press failed: {e}"))?;
-        e.move_mouse(end_x, end_y, Coordinate::Abs)
-            .map_err(|e| format!("drag failed: {e}"))?;
-        e.button(Button::Left, Release)
-            .map_err(|e| format!("release failed: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+    let args = serde_json::json!({
+        "start": [start_x, start_y],
+        "end": [end_x, end_y],
+        "button": button,
+        "waypoints": waypoints.as_ref().map(Vec::len),
+        "steps": steps,
+        "step_delay_ms": step_delay_ms,
+    });
+    let result: Result<(), String> = async move {
+        let steps = steps.unwrap_or(DEFAULT_DRAG_STEPS);
+        if !(1..=MAX_DRAG_STEPS).contains(&steps) {
+            return Err(format!("steps must be between 1 and {MAX_DRAG_STEPS}"));
+        }
+        let delay = step_delay_ms.unwrap_or(DEFAULT_DRAG_STEP_DELAY_MS);
+        if delay > MAX_DRAG_STEP_DELAY_MS {
+            return Err(format!("step_delay_ms must be at most {MAX_DRAG_STEP_DELAY_MS}"));
+        }
+        let waypoints = waypoints.unwrap_or_default();
+        if waypoints.len() > MAX_DRAG_WAYPOINTS {
+            return Err(format!("too many waypoints (max {MAX_DRAG_WAYPOINTS})"));
+        }
+        let legs = waypoints.len() as u64 + 1;
+        if legs * u64::from(steps) * delay > MAX_DRAG_MS {
+            return Err(format!("the drag would take longer than {} seconds", MAX_DRAG_MS / 1000));
+        }
+        let btn = parse_button(button.as_deref().unwrap_or("left"));
+        let delay = std::time::Duration::from_millis(delay);
+
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            e.move_mouse(start_x, start_y, Coordinate::Abs)
+                .map_err(|e| format!("move to start failed: {e}"))?;
+            hold(&mut e, Held::Button(btn))
+                .map_err(|e| format!("press failed: {e}"))?;
+
+            let path = waypoints.into_iter().chain([ScreenPoint { x: end_x, y: end_y }]);
+            let mut from = ScreenPoint { x: start_x, y: start_y };
+            let mut dragged = Ok(());
+            'legs: for to in path {
+                for i in 1..=steps {
+                    std::thread::sleep(delay);
+                    let t = f64::from(i) / f64::from(steps);
+                    let x = from.x + (f64::from(to.x - from.x) * t).round() as i32;
+                    let y = from.y + (f64::from(to.y - from.y) * t).round() as i32;
+                    if let Err(err) = e.move_mouse(x, y, Coordinate::Abs) {
+                        dragged = Err(format!("drag failed: {err}"));
+                        break 'legs;
+                    }
+                }
+                from = to;
+            }
+            std::thread::sleep(delay);
+            let released = let_go(&mut e, Held::Button(btn))
+                .map_err(|e| format!("release failed: {e}"));
+            dragged.and(released)
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_mouse_drag", args, module.as_deref(), &result);
+    result
+}
+
+// ── Held input ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, PartialEq)]
+enum Held {
+    Key(Key),
+    Button(Button),
+}
+
+/// Keys and buttons pressed by a command and not yet released, so they can
+/// be let go if the app quits mid-gesture or input gets refused (see
+/// [`release_held_input`]). Includes keys held with `computer_key_down`.
+static HELD: Mutex<Vec<Held>> = Mutex::new(Vec::new());
+
+fn hold(e: &mut Enigo, input: Held) -> InputResult<()> {
+    match input {
+        Held::Key(k) => e.key(k, Press),
+        Held::Button(b) => e.button(b, Press),
+    }?;
+    HELD.lock().unwrap_or_else(|e| e.into_inner()).push(input);
+    Ok(())
+}
+
+fn let_go(e: &mut Enigo, input: Held) -> InputResult<()> {
+    {
+        let mut held = HELD.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(i) = held.iter().rposition(|h| *h == input) {
+            held.remove(i);
+        }
+    }
+    match input {
+        Held::Key(k) => e.key(k, Release),
+        Held::Button(b) => e.button(b, Release),
+    }
+}
+
+/// Releases every key and button a command left pressed, most recent
+/// first, and returns how many there were.
+pub fn release_held_input() -> Result<usize, String> {
+    let held = std::mem::take(&mut *HELD.lock().unwrap_or_else(|e| e.into_inner()));
+    if held.is_empty() {
+        return Ok(0);
+    }
+    let mut e = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+    for input in held.iter().rev() {
+        let _ = match *input {
+            Held::Key(k) => e.key(k, Release),
+            Held::Button(b) => e.button(b, Release),
+        };
+    }
+    Ok(held.len())
+}
+
+// ── Input rate limit ───────────────────────────────────────────────────────────
+
+/// Mouse and keyboard commands allowed per second unless configured.
+pub const DEFAULT_INPUT_RATE_LIMIT: u32 = 20;
+
+/// Commands allowed per second; 0 turns the limit off.
+static INPUT_RATE_LIMIT: AtomicU32 = AtomicU32::new(DEFAULT_INPUT_RATE_LIMIT);
+/// When the input commands of the last second were let through.
+static RECENT_INPUT: Mutex<VecDeque<std::time::Instant>> = Mutex::new(VecDeque::new());
+
+/// Sets how many mouse and keyboard commands may run per second (loaded
+/// from settings at startup); 0 turns the limit off.
+pub fn set_input_rate_limit(per_second: u32) {
+    INPUT_RATE_LIMIT.store(per_second, Ordering::Relaxed);
+}
+
+/// Counts one input command against the per-second cap, failing once the
+/// cap is reached — a runaway agent loop gets errors instead of flooding
+/// the desktop with clicks and keystrokes.
+pub fn throttle_input() -> Result<(), String> {
+    let limit = INPUT_RATE_LIMIT.load(Ordering::Relaxed);
+    if limit == 0 {
+        return Ok(());
+    }
+    let now = std::time::Instant::now();
+    let mut recent = RECENT_INPUT.lock().unwrap_or_else(|e| e.into_inner());
+    while recent.front().is_some_and(|t| now.duration_since(*t).as_secs() >= 1) {
+        recent.pop_front();
+    }
+    if recent.len() >= limit as usize {
+        return Err(format!("input refused: more than {limit} input commands per second"));
+    }
+    recent.push_back(now);
+    Ok(())
 }
 
 // ── Keyboard commands ──────────────────────────────────────────────────────────
 
+/// Time the focused app gets to read (or fill) the clipboard after the paste
+/// (or copy) shortcut before the previous contents are put back.
+const PASTE_SETTLE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Clipboard contents saved across a clipboard-typing paste or a selection copy.
+enum SavedClipboard {
+    Text(String),
+    Image(arboard::ImageData<'static>),
+    Empty,
+}
+
+fn save_clipboard(cb: &mut arboard::Clipboard) -> SavedClipboard {
+    match cb.get_text() {
+        Ok(t) => SavedClipboard::Text(t),
+        Err(_) => cb.get_image().map_or(SavedClipboard::Empty, SavedClipboard::Image),
+    }
+}
+
+fn restore_clipboard(cb: &mut arboard::Clipboard, saved: SavedClipboard) -> Result<(), String> {
+    match saved {
+        SavedClipboard::Text(t) => cb.set_text(t),
+        SavedClipboard::Image(img) => cb.set_image(img),
+        SavedClipboard::Empty => cb.clear(),
+    }
+    .map_err(|e| format!("clipboard restore failed: {e}"))
+}
+
+/// Presses Cmd+`key` on macOS, Ctrl+`key` elsewhere.
+fn clipboard_shortcut(e: &mut Enigo, key: char) -> InputResult<()> {
+    let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+    e.key(modifier, Press)
+        .and_then(|_| e.key(Key::Unicode(key), Click))
+        .and_then(|_| e.key(modifier, Release))
+}
+
+/// Pastes `text` with the platform paste shortcut, then restores whatever was
+/// on the clipboard before (text or image; other formats are cleared).
+fn paste_text(e: &mut Enigo, text: &str) -> Result<(), String> {
+    let mut cb = arboard::Clipboard::new().map_err(|e| format!("clipboard unavailable: {e}"))?;
+    let saved = save_clipboard(&mut cb);
+    cb.set_text(text)
+        .map_err(|e| format!("clipboard write failed: {e}"))?;
+
+    let pasted = clipboard_shortcut(e, 'v').map_err(|e| format!("paste failed: {e}"));
+    std::thread::sleep(PASTE_SETTLE);
+
+    let restored = restore_clipboard(&mut cb, saved);
+    pasted.and(restored)
+}
+
+/// Copies the focused app's text selection with the platform copy shortcut
+/// and returns it (`None` when nothing was selected), restoring the
+/// clipboard afterwards. Modifiers still held from a hotkey are released
+/// first so they don't turn the copy into another shortcut. Blocking.
+pub fn copy_selection() -> Result<Option<String>, String> {
+    session::ensure_input_allowed()?;
+    let mut e = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+    let mut cb = arboard::Clipboard::new().map_err(|e| format!("clipboard unavailable: {e}"))?;
+    let saved = save_clipboard(&mut cb);
+    cb.clear().map_err(|e| format!("clipboard write failed: {e}"))?;
+
+    let copied = [Key::Shift, Key::Alt, Key::Meta, Key::Control]
+        .into_iter()
+        .try_for_each(|k| e.key(k, Release))
+        .and_then(|_| clipboard_shortcut(&mut e, 'c'))
+        .map_err(|e| format!("copy failed: {e}"));
+    std::thread::sleep(PASTE_SETTLE);
+    let selection = cb.get_text().ok().filter(|t| !t.trim().is_empty());
+
+    let restored = restore_clipboard(&mut cb, saved);
+    copied.and(restored).map(|_| selection)
+}
+
 /// Types a UTF-8 string at the current keyboard focus.
+///
+/// `input_method` picks how:
+/// - `auto` (default) — keystrokes, but a clipboard paste when the text has
+///   CJK, Thai, Indic, combining or emoji characters, or an input method
+///   (IME) is composing, since either would garble keystrokes;
+/// - `keys` — always keystrokes;
+/// - `clipboard` — always a paste: much faster for large blocks, and
+///   unaffected by keyboard layout or IME.
+///
+/// Pastes restore the previous clipboard contents afterwards.
+/// `type_via_clipboard: true` is the same as `input_method: "clipboard"`.
+///
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
-pub async fn computer_key_type(text: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        e.text(&text).map_err(|e| format!("type failed: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+pub async fn computer_key_type(
+    text: String,
+    type_via_clipboard: Option<bool>,
+    input_method: Option<String>,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({
+        "chars": text.chars().count(),
+        "type_via_clipboard": type_via_clipboard,
+        "input_method": input_method,
+    });
+    let result: Result<(), String> = async move {
+        let method = if type_via_clipboard.unwrap_or(false) {
+            TypeMethod::Clipboard
+        } else {
+            TypeMethod::parse(input_method.as_deref().unwrap_or("auto"))?
+        };
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            if method.pastes(&text) {
+                return paste_text(&mut e, &text);
+            }
+            e.text(&text).map_err(|e| format!("type failed: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_key_type", args, module.as_deref(), &result);
+    result
 }
 
 /// Presses and releases a single key by name.
@@ -345,23 +1089,91 @@ pub async fn computer_key_type(text: String) -> Result<(), String> {
 /// `"delete"`, `"up"`, `"down"`, `"left"`, `"right"`, `"home"`, `"end"`,
 /// `"pageup"`, `"pagedown"`, `"f1"`–`"f12"`, `"ctrl"`, `"alt"`, `"shift"`,
 /// `"meta"` / `"cmd"`.
-/// Single characters (e.g. `"a"`, `"1"`) are passed through directly.
+/// Single characters (e.g. `"a"`, `"1"`) are pressed on the current keyboard
+/// layout; a dead key is followed by a space so the character itself comes
+/// out, and characters not on the layout are sent as Unicode input.
 ///
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
-pub async fn computer_key_press(key: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
-        e.key(parse_key(&key), Click)
-            .map_err(|e| format!("key press failed: {e}"))
+pub async fn computer_key_press(key: String, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "key": key });
+    let result: Result<(), String> = async move {
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            let pressed = match parse_key(&key) {
+                Key::Unicode(c) if c != '\0' => match text_input::layout_key(c) {
+                    LayoutKey::Direct(k) => e.key(k, Click),
+                    LayoutKey::Dead(k) => e.key(k, Click).and_then(|_| e.key(Key::Space, Click)),
+                    LayoutKey::Missing => e.text(&c.to_string()),
+                },
+                k => e.key(k, Click),
+            };
+            pressed.map_err(|e| format!("key press failed: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_key_press", args, module.as_deref(), &result);
+    result
+}
+
+const MAX_CHORDS: usize = 10;
+const DEFAULT_CHORD_DELAY_MS: u64 = 100;
+const MAX_CHORD_DELAY_MS: u64 = 2000;
+
+/// Chords that quit apps, close windows or end the session; pressing them
+/// needs the user's approval. Written as [`chord_label`] spells them.
+const DANGEROUS_CHORDS: &[&str] = &[
+    "cmd+q", "shift+cmd+q", "ctrl+cmd+q", "alt+cmd+escape", "alt+f4", "ctrl+alt+delete",
+];
+
+/// Canonical spelling of a chord: modifiers in the order ctrl, alt, shift,
+/// cmd, then the other keys, all lowercase and joined with `+`.
+fn chord_label(chord: &[String]) -> String {
+    let mut modifiers = Vec::new();
+    let mut others = Vec::new();
+    for name in chord {
+        match parse_key(name) {
+            Key::Control => modifiers.push((0, "ctrl".to_owned())),
+            Key::Alt => modifiers.push((1, "alt".to_owned())),
+            Key::Shift => modifiers.push((2, "shift".to_owned())),
+            Key::Meta => modifiers.push((3, "cmd".to_owned())),
+            Key::Escape => others.push("escape".to_owned()),
+            Key::Delete => others.push("delete".to_owned()),
+            Key::Return => others.push("enter".to_owned()),
+            _ => others.push(name.to_lowercase()),
+        }
+    }
+    modifiers.sort();
+    modifiers.dedup();
+    modifiers.into_iter().map(|(_, m)| m).chain(others).collect::<Vec<_>>().join("+")
+}
+
+/// Holds all keys of `chord` but the last, taps the last, then releases the
+/// held ones in reverse order.
+fn press_chord(e: &mut Enigo, chord: &[String]) -> Result<(), String> {
+    let Some((final_key, modifiers)) = chord.split_last() else {
+        return Err("a chord must not be empty".into());
+    };
+    let held: Vec<Key> = modifiers.iter().map(|k| parse_key(k)).collect();
+    let key = parse_key(final_key);
+    // A shortcut needs a real key; Unicode input can't carry modifiers.
+    if let Key::Unicode(c) = key {
+        if text_input::layout_key(c) == LayoutKey::Missing {
+            return Err(format!("'{final_key}' isn't on the current keyboard layout"));
+        }
+    }
+    with_modifiers(e, &held, |e| {
+        e.key(key, Click)
+            .map_err(|e| format!("key tap failed ({final_key}): {e}"))
     })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
 }
 
-/// Executes a multi-key shortcut.
+/// Executes a multi-key shortcut, or a sequence of them.
 ///
 /// All keys except the last are held as modifiers; the last key is tapped, then
 /// all modifiers are released in reverse order.
@@ -373,35 +1185,241 @@ pub async fn computer_key_press(key: String) -> Result<(), String> {
 /// // Select all:     ["ctrl", "a"]
 /// ```
 ///
+/// For chorded shortcuts pass `chords` instead of `keys`, e.g.
+/// `[["ctrl", "k"], ["ctrl", "s"]]`; they are pressed one after another,
+/// `chord_delay_ms` apart (default 100, at most 2000), in a single task so
+/// no other input lands in between. At most 10 chords.
+///
+/// Chords that quit or close things (Cmd+Q, Alt+F4, Ctrl+Alt+Delete, …) wait
+/// for the user's [approval](crate::approval) first.
+///
 /// Requires Accessibility permission on macOS.
 #[tauri::command]
-pub async fn computer_hotkey(keys: Vec<String>) -> Result<(), String> {
-    if keys.is_empty() {
-        return Err("keys must not be empty".into());
+pub async fn computer_hotkey(
+    app: AppHandle,
+    keys: Option<Vec<String>>,
+    chords: Option<Vec<Vec<String>>>,
+    chord_delay_ms: Option<u64>,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "keys": keys, "chords": chords, "chord_delay_ms": chord_delay_ms });
+    let result: Result<(), String> = async move {
+        let chords = match (keys, chords) {
+            (Some(keys), None) => vec![keys],
+            (None, Some(chords)) => chords,
+            _ => return Err("pass either keys or chords".into()),
+        };
+        if chords.is_empty() || chords.len() > MAX_CHORDS {
+            return Err(format!("between 1 and {MAX_CHORDS} chords are needed"));
+        }
+        if chords.iter().any(|c| c.is_empty()) {
+            return Err("keys must not be empty".into());
+        }
+        let delay = chord_delay_ms.unwrap_or(DEFAULT_CHORD_DELAY_MS);
+        if delay > MAX_CHORD_DELAY_MS {
+            return Err(format!("chord_delay_ms must be at most {MAX_CHORD_DELAY_MS}"));
+        }
+        let delay = std::time::Duration::from_millis(delay);
+        for label in chords.iter().map(|c| chord_label(c)) {
+            if DANGEROUS_CHORDS.contains(&label.as_str()) {
+                approval::require(&app, approval::Action::hotkey(&label)).await?;
+            }
+        }
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            for (i, chord) in chords.iter().enumerate() {
+                if i > 0 {
+                    std::thread::sleep(delay);
+                }
+                press_chord(&mut e, chord)?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
     }
-    tokio::task::spawn_blocking(move || {
-        let mut e =
-            Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+    .await;
+    audit::record("computer_hotkey", args, module.as_deref(), &result);
+    result
+}
+
+/// Parses a key name for `computer_key_down` / `computer_key_up`.
+fn parse_known_key(key: &str) -> Result<Key, String> {
+    match parse_key(key) {
+        Key::Unicode('\0') => Err(format!("unknown key '{key}'")),
+        k => Ok(k),
+    }
+}
+
+/// Presses `key` and keeps it down until `computer_key_up`, e.g. to hold
+/// Shift across several clicks or a movement key in a game. Held keys are
+/// released automatically when pressing fails, when input gets refused
+/// (screen lock, session change, agents paused) and when the app exits.
+/// Requires Accessibility permission on macOS.
+#[tauri::command]
+pub async fn computer_key_down(key: String, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "key": key });
+    let result: Result<(), String> = async move {
+        let k = parse_known_key(&key)?;
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            if HELD.lock().unwrap_or_else(|e| e.into_inner()).contains(&Held::Key(k)) {
+                return Err(format!("'{key}' is already held"));
+            }
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            if let Err(err) = hold(&mut e, Held::Key(k)) {
+                let _ = release_held_input();
+                return Err(format!("key press failed ({key}): {err}"));
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_key_down", args, module.as_deref(), &result);
+    result
+}
 
-        // Hold all modifiers, tap the final key, release modifiers in reverse.
-        let (modifiers, tail) = keys.split_at(keys.len() - 1);
-        let final_key = &tail[0];
+/// Releases a key pressed with `computer_key_down`. Releasing is always
+/// allowed, even while input is otherwise refused.
+#[tauri::command]
+pub async fn computer_key_up(key: String, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "key": key });
+    let result: Result<(), String> = async move {
+        let k = parse_known_key(&key)?;
+        tokio::task::spawn_blocking(move || {
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            let_go(&mut e, Held::Key(k)).map_err(|e| format!("key release failed ({key}): {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_key_up", args, module.as_deref(), &result);
+    result
+}
 
-        for k in modifiers {
-            e.key(parse_key(k), Press)
-                .map_err(|e| format!("modifier press failed ({k}): {e}"))?;
+/// Releases every held key and mouse button; returns how many there were.
+#[tauri::command]
+pub async fn computer_release_all() -> Result<usize, String> {
+    tokio::task::spawn_blocking(release_held_input)
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+}
+
+// ── Media commands ─────────────────────────────────────────────────────────────
+
+const MAX_MEDIA_STEPS: u32 = 20;
+/// Brightness change per step on Windows, in percent.
+#[cfg(target_os = "windows")]
+const BRIGHTNESS_STEP: u32 = 10;
+
+/// Sets the brightness of the built-in display through WMI, `steps` ×
+/// [`BRIGHTNESS_STEP`] up or down. External monitors don't expose it.
+#[cfg(target_os = "windows")]
+fn windows_brightness(up: bool, steps: u32) -> Result<(), String> {
+    let delta = i64::from(steps * BRIGHTNESS_STEP) * if up { 1 } else { -1 };
+    let script = format!(
+        "$c = (Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightness).CurrentBrightness; \
+         $v = [Math]::Max(0, [Math]::Min(100, $c + ({delta}))); \
+         Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightnessMethods | \
+         Invoke-CimMethod -MethodName WmiSetBrightness -Arguments @{{Timeout = 0; Brightness = $v}}"
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map_err(|e| format!("brightness change failed: {e}"))?;
+    if status.status.success() {
+        Ok(())
+    } else {
+        Err("brightness control is not available for this display".into())
+    }
+}
+
+/// The key `action` presses, or `None` for brightness on Windows, which
+/// has no brightness key.
+fn media_key(action: &str) -> Result<Option<Key>, String> {
+    let key = match action {
+        "volume_up" => Key::VolumeUp,
+        "volume_down" => Key::VolumeDown,
+        "mute" => Key::VolumeMute,
+        "play_pause" => Key::MediaPlayPause,
+        "next" => Key::MediaNextTrack,
+        "previous" => Key::MediaPrevTrack,
+        #[cfg(target_os = "macos")]
+        "brightness_up" => Key::BrightnessUp,
+        #[cfg(target_os = "macos")]
+        "brightness_down" => Key::BrightnessDown,
+        // XF86MonBrightnessUp / XF86MonBrightnessDown.
+        #[cfg(target_os = "linux")]
+        "brightness_up" => Key::Other(0x1008_ff02),
+        #[cfg(target_os = "linux")]
+        "brightness_down" => Key::Other(0x1008_ff03),
+        #[cfg(target_os = "windows")]
+        "brightness_up" | "brightness_down" => return Ok(None),
+        other => {
+            return Err(format!(
+                "unknown media action '{other}' (use volume_up, volume_down, mute, brightness_up, \
+                 brightness_down, play_pause, next or previous)"
+            ))
         }
-        e.key(parse_key(final_key), Click)
-            .map_err(|e| format!("key tap failed ({final_key}): {e}"))?;
-        for k in modifiers.iter().rev() {
-            e.key(parse_key(k), Release)
-                .map_err(|e| format!("modifier release failed ({k}): {e}"))?;
+    };
+    Ok(Some(key))
+}
+
+/// Presses a media key: `volume_up`, `volume_down`, `mute`,
+/// `brightness_up`, `brightness_down`, `play_pause`, `next` or `previous`.
+/// `steps` repeats volume and brightness changes (default 1, at most 20).
+///
+/// Brightness on Linux depends on the desktop handling the brightness keys;
+/// on Windows it goes through WMI and only works for built-in displays.
+///
+/// Requires Accessibility permission on macOS.
+#[tauri::command]
+pub async fn computer_media(action: String, steps: Option<u32>, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "action": action, "steps": steps });
+    let result: Result<(), String> = async move {
+        let steps = steps.unwrap_or(1);
+        if !(1..=MAX_MEDIA_STEPS).contains(&steps) {
+            return Err(format!("steps must be between 1 and {MAX_MEDIA_STEPS}"));
         }
-        Ok(())
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+        let action = action.trim().to_ascii_lowercase();
+        let key = media_key(&action)?;
+        let repeats = match action.as_str() {
+            "volume_up" | "volume_down" | "brightness_up" | "brightness_down" => steps,
+            _ => 1,
+        };
+        tokio::task::spawn_blocking(move || {
+            session::ensure_input_allowed()?;
+            let Some(key) = key else {
+                #[cfg(target_os = "windows")]
+                return windows_brightness(action == "brightness_up", repeats);
+                #[cfg(not(target_os = "windows"))]
+                return Ok(());
+            };
+            let mut e =
+                Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            for _ in 0..repeats {
+                e.key(key, Click).map_err(|e| format!("media key failed: {e}"))?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_media", args, module.as_deref(), &result);
+    result
 }
 
 // ── Clipboard commands ─────────────────────────────────────────────────────────
@@ -436,39 +1454,324 @@ pub async fn computer_clipboard_set(text: String) -> Result<(), String> {
 
 // ── OS commands ────────────────────────────────────────────────────────────────
 
-/// Launches an application by name.
+/// How long `computer_launch_app` waits for the app's window.
+const LAUNCH_WINDOW_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+const LAUNCH_WINDOW_POLL: std::time::Duration = std::time::Duration::from_millis(250);
+const MAX_LAUNCH_ARGS: usize = 64;
+
+/// Starts `app_name` with `args`. Returns the child's PID when the name
+/// could be run directly, `None` when it went through the OS launcher.
+fn launch(app_name: &str, args: &[String]) -> Result<Option<u32>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        // `open -a` resolves app bundles by name and opens `args` as
+        // documents in them. It exits once the app is up, so the PID is
+        // looked up afterwards.
+        let status = std::process::Command::new("open")
+            .args(["-a", app_name])
+            .args(args)
+            .status()
+            .map_err(|e| format!("launch error: {e}"))?;
+        if !status.success() {
+            return Err(format!("launch failed with exit code: {}", status.code().unwrap_or(-1)));
+        }
+        let pid = std::process::Command::new("pgrep")
+            .args(["-n", "-x", app_name])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse().ok());
+        Ok(pid)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        match std::process::Command::new(app_name).args(args).spawn() {
+            Ok(mut child) => {
+                let pid = child.id();
+                // Reap it when it exits so it doesn't linger as a zombie.
+                std::thread::spawn(move || child.wait());
+                Ok(Some(pid))
+            }
+            // Not an executable on PATH — fall back to the OS launcher,
+            // which also knows registered app names and desktop entries.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                #[cfg(target_os = "linux")]
+                let status = if args.is_empty() {
+                    std::process::Command::new("xdg-open").arg(app_name).status()
+                } else {
+                    return Err(format!("'{app_name}' is not an executable on PATH; args need one"));
+                };
+
+                #[cfg(target_os = "windows")]
+                let status = std::process::Command::new("cmd")
+                    .args(["/C", "start", "", app_name])
+                    .args(args)
+                    .status();
+
+                match status {
+                    Ok(s) if s.success() => Ok(None),
+                    Ok(s) => Err(format!("launch failed with exit code: {}", s.code().unwrap_or(-1))),
+                    Err(e) => Err(format!("launch error: {e}")),
+                }
+            }
+            Err(e) => Err(format!("launch error: {e}")),
+        }
+    }
+}
+
+/// Waits for a visible window whose app name contains `app_name`; returns
+/// it and whether it is frontmost.
+fn wait_for_app_window(app_name: &str) -> Option<(WindowInfo, bool)> {
+    let needle = std::path::Path::new(app_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if needle.is_empty() {
+        return None;
+    }
+    let deadline = std::time::Instant::now() + LAUNCH_WINDOW_TIMEOUT;
+    loop {
+        if let Ok(windows) = xcap::Window::all() {
+            let mut visible = windows.iter().filter(|w| !w.is_minimized()).peekable();
+            let front_id = visible.peek().map(|w| w.id());
+            if let Some(w) = visible.find(|w| w.app_name().to_lowercase().contains(&needle)) {
+                return Some((window_info(w), Some(w.id()) == front_id));
+            }
+        }
+        if std::time::Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(LAUNCH_WINDOW_POLL);
+    }
+}
+
+/// Launches an application by name or path, passing `args` — on macOS
+/// these are documents to open in it.
+///
+/// - **macOS** — uses `open -a <app> <args…>`
+/// - **Linux / Windows** — runs the executable directly; names that aren't
+///   on `PATH` go through `xdg-open` / `start "" <app>`, without a PID
 ///
-/// - **macOS / Linux** — uses `open -a <app>` / `xdg-open`
-/// - **Windows** — uses `start "" "<app>"`
+/// With `wait_for_window`, waits up to 15 s for a window of the app to
+/// appear and reports it, and whether it came to the front, so callers can
+/// tell the launch worked.
 ///
 /// Requires `computer.shell` permission (enforced by `SandboxedComputer`).
 #[tauri::command]
-pub async fn computer_launch_app(app_name: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        #[cfg(target_os = "macos")]
-        let status = std::process::Command::new("open")
-            .args(["-a", &app_name])
-            .status();
+pub async fn computer_launch_app(
+    app_name: String,
+    args: Option<Vec<String>>,
+    wait_for_window: Option<bool>,
+    module: Option<String>,
+) -> Result<LaunchedApp, String> {
+    let launch_args = args.unwrap_or_default();
+    let audit_args = serde_json::json!({
+        "app_name": app_name,
+        "args": launch_args,
+        "wait_for_window": wait_for_window,
+    });
+    let result: Result<LaunchedApp, String> = async move {
+        if app_name.trim().is_empty() {
+            return Err("app_name is empty".into());
+        }
+        if launch_args.len() > MAX_LAUNCH_ARGS {
+            return Err(format!("at most {MAX_LAUNCH_ARGS} args"));
+        }
+        tokio::task::spawn_blocking(move || {
+            let pid = launch(&app_name, &launch_args)?;
+            let found = if wait_for_window.unwrap_or(false) {
+                wait_for_app_window(&app_name)
+            } else {
+                None
+            };
+            let (window, frontmost) = found.map_or((None, false), |(w, front)| (Some(w), front));
+            Ok(LaunchedApp { pid, window, frontmost })
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_launch_app", audit_args, module.as_deref(), &result);
+    result
+}
 
-        #[cfg(target_os = "linux")]
-        let status = std::process::Command::new("xdg-open")
-            .arg(&app_name)
-            .status();
+/// Schemes `computer_open_url` accepts unless the `open_url_schemes` setting
+/// lists others.
+const DEFAULT_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+const URL_SCHEMES_KEY: &str = "open_url_schemes";
 
-        #[cfg(target_os = "windows")]
-        let status = std::process::Command::new("cmd")
-            .args(["/C", "start", "", &app_name])
-            .status();
+/// Opens `url` with the user's default handler — the browser for web links,
+/// the mail client for `mailto:`.
+///
+/// Only `http`, `https` and `mailto` are accepted unless the
+/// `open_url_schemes` setting replaces that list; `file:` and custom app
+/// schemes would otherwise let an agent launch arbitrary programs.
+///
+/// - **macOS** — `open <url>`
+/// - **Linux** — `xdg-open <url>`
+/// - **Windows** — `rundll32 url.dll,FileProtocolHandler <url>`, which unlike
+///   `start` doesn't pass the URL through `cmd`'s `&` / `|` parsing.
+#[tauri::command]
+pub async fn computer_open_url(app: AppHandle, url: String, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "url": url });
+    let result: Result<(), String> = async move {
+        let parsed = tauri::Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
+        let allowed: Option<Vec<String>> = app
+            .store(settings::SETTINGS_STORE)
+            .ok()
+            .and_then(|s| s.get(URL_SCHEMES_KEY))
+            .and_then(|v| serde_json::from_value(v).ok());
+        let scheme = parsed.scheme();
+        let permitted = match &allowed {
+            Some(list) => list.iter().any(|s| s.eq_ignore_ascii_case(scheme)),
+            None => DEFAULT_URL_SCHEMES.contains(&scheme),
+        };
+        if !permitted {
+            return Err(format!("url scheme '{scheme}' is not allowed"));
+        }
+        let url = parsed.to_string();
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            Ok(s) => Err(format!("launch failed with exit code: {}", s.code().unwrap_or(-1))),
-            Err(e) => Err(format!("launch error: {e}")),
+        tokio::task::spawn_blocking(move || {
+            #[cfg(target_os = "macos")]
+            let status = std::process::Command::new("open").arg(&url).status();
+
+            #[cfg(target_os = "linux")]
+            let status = std::process::Command::new("xdg-open").arg(&url).status();
+
+            #[cfg(target_os = "windows")]
+            let status = std::process::Command::new("rundll32")
+                .args(["url.dll,FileProtocolHandler", &url])
+                .status();
+
+            match status {
+                Ok(s) if s.success() => Ok(()),
+                Ok(s) => Err(format!("open failed with exit code: {}", s.code().unwrap_or(-1))),
+                Err(e) => Err(format!("open error: {e}")),
+            }
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_open_url", args, module.as_deref(), &result);
+    result
+}
+
+const DEFAULT_SHELL_TIMEOUT_SECS: u64 = 30;
+const MAX_SHELL_TIMEOUT_SECS: u64 = 3600;
+/// Output kept per stream; the rest is read and dropped.
+const MAX_SHELL_OUTPUT: usize = 10 * 1024 * 1024;
+/// How long to wait for the pipes to close once the command has exited or
+/// been killed — a background process it started may still hold them open.
+const PIPE_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// `/bin/sh -c` on Unix, `cmd /C` on Windows, in a process group of its
+/// own so [`kill_process_tree`] reaches everything it starts.
+pub fn shell_command(command: &str) -> tokio::process::Command {
+    #[cfg(unix)]
+    let cmd = {
+        use std::os::unix::process::CommandExt;
+        let mut cmd = std::process::Command::new("/bin/sh");
+        cmd.args(["-c", command]).process_group(0);
+        cmd
+    };
+    #[cfg(windows)]
+    let cmd = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", command])
+            .creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+        cmd
+    };
+    let mut cmd = tokio::process::Command::from(cmd);
+    cmd.kill_on_drop(true);
+    cmd
+}
+
+/// Variables kept when a command runs with an isolated environment — enough
+/// for the shell to find programs and the user's home.
+#[cfg(unix)]
+const ISOLATED_ENV_KEEP: &[&str] = &["PATH", "HOME", "USER", "LANG", "TMPDIR"];
+#[cfg(windows)]
+const ISOLATED_ENV_KEEP: &[&str] = &[
+    "PATH", "PATHEXT", "SystemRoot", "ComSpec", "USERPROFILE", "USERNAME", "TEMP", "TMP",
+];
+
+/// Applies a working directory and environment to a shell command. Without
+/// `inherit_env` (default `true`) the command starts from a minimal
+/// environment instead of the app's; `env` is added on top either way.
+pub fn configure_shell(
+    cmd: &mut tokio::process::Command,
+    cwd: Option<&str>,
+    env: Option<&std::collections::HashMap<String, String>>,
+    inherit_env: Option<bool>,
+) -> Result<(), String> {
+    if let Some(dir) = cwd {
+        if !std::path::Path::new(dir).is_dir() {
+            return Err(format!("working directory not found: {dir}"));
         }
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+        cmd.current_dir(dir);
+    }
+    if !inherit_env.unwrap_or(true) {
+        cmd.env_clear();
+        for key in ISOLATED_ENV_KEEP {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    for (key, value) in env.into_iter().flatten() {
+        if key.is_empty() || key.contains(['=', '\0']) || value.contains('\0') {
+            return Err(format!("invalid environment variable '{key}'"));
+        }
+        cmd.env(key, value);
+    }
+    Ok(())
+}
+
+/// Kills process `pid` and everything in its process group (Unix) or
+/// process tree (Windows).
+pub fn kill_process_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        extern "C" {
+            fn kill(pid: i32, sig: i32) -> i32;
+        }
+        const SIGKILL: i32 = 9;
+        // SAFETY: plain syscall; a negative pid addresses the group that
+        // `shell_command` put the child at the head of.
+        unsafe {
+            kill(-(pid as i32), SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .output();
+    }
+}
+
+/// Reads `pipe` into `buf` until it closes, keeping at most
+/// [`MAX_SHELL_OUTPUT`] bytes.
+async fn collect_output(
+    mut pipe: impl tokio::io::AsyncRead + Unpin,
+    buf: std::sync::Arc<Mutex<Vec<u8>>>,
+) {
+    use tokio::io::AsyncReadExt;
+    let mut chunk = [0u8; 8192];
+    while let Ok(n) = pipe.read(&mut chunk).await {
+        if n == 0 {
+            break;
+        }
+        let mut buf = buf.lock().unwrap_or_else(|e| e.into_inner());
+        let room = MAX_SHELL_OUTPUT.saturating_sub(buf.len());
+        buf.extend_from_slice(&chunk[..n.min(room)]);
+    }
 }
 
 /// Executes a shell command and returns its output.
@@ -476,100 +1779,359 @@ pub async fn computer_launch_app(app_name: String) -> Result<(), String> {
 /// - **macOS / Linux** — runs via `/bin/sh -c`
 /// - **Windows** — runs via `cmd /C`
 ///
-/// stdout and stderr are captured; the process is run with a 30-second timeout.
+/// stdout and stderr are captured (up to 10 MB each). A command still running
+/// after `timeout_secs` (default 30, at most 3600) is killed together with
+/// everything it started, and what it printed so far is returned with
+/// `timed_out` set.
+///
+/// `cwd` sets the working directory and `env` adds variables; with
+/// `inherit_env: false` the command doesn't see the app's environment
+/// beyond `PATH`, the home directory and a few other basics.
 /// Requires `computer.shell` permission (enforced by `SandboxedComputer`).
 ///
 /// # Security
 /// Only accepts commands explicitly authorised by the module permission system.
+/// Commands refused by the [shell policy](crate::shell_policy) fail without
+/// running; the others wait for the user's [approval](crate::approval).
 /// Never call this with unsanitised user input.
 #[tauri::command]
-pub async fn computer_run_shell(command: String) -> Result<ShellResult, String> {
-    tokio::task::spawn_blocking(move || {
-        #[cfg(unix)]
-        let output = std::process::Command::new("/bin/sh")
-            .args(["-c", &command])
-            .output();
+pub async fn computer_run_shell(
+    app: AppHandle,
+    command: String,
+    timeout_secs: Option<u64>,
+    cwd: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
+    inherit_env: Option<bool>,
+    module: Option<String>,
+) -> Result<ShellResult, String> {
+    let args = serde_json::json!({
+        "command": command,
+        "timeout_secs": timeout_secs,
+        "cwd": cwd,
+        "env": env.as_ref().map(|e| e.keys().collect::<Vec<_>>()),
+        "inherit_env": inherit_env,
+    });
+    let result: Result<ShellResult, String> = async move {
+        let timeout_secs = timeout_secs.unwrap_or(DEFAULT_SHELL_TIMEOUT_SECS);
+        if !(1..=MAX_SHELL_TIMEOUT_SECS).contains(&timeout_secs) {
+            return Err(format!("timeout must be between 1 and {MAX_SHELL_TIMEOUT_SECS} seconds"));
+        }
+        shell_policy::check(&app, &command)?;
+        approval::require(&app, approval::Action::shell(&command)).await?;
+        let mut cmd = shell_command(&command);
+        configure_shell(&mut cmd, cwd.as_deref(), env.as_ref(), inherit_env)?;
+        let mut child = cmd
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("shell error: {e}"))?;
 
-        #[cfg(windows)]
-        let output = std::process::Command::new("cmd")
-            .args(["/C", &command])
-            .output();
+        let stdout = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let stderr = std::sync::Arc::new(Mutex::new(Vec::new()));
+        // Read while the command runs, so it never blocks on a full pipe.
+        let readers = [
+            tokio::spawn(collect_output(child.stdout.take().ok_or("stdout not captured")?, stdout.clone())),
+            tokio::spawn(collect_output(child.stderr.take().ok_or("stderr not captured")?, stderr.clone())),
+        ];
 
-        match output {
-            Ok(o) => Ok(ShellResult {
-                exit_code: o.status.code().unwrap_or(-1),
-                stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
-                stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
-            }),
-            Err(e) => Err(format!("shell error: {e}")),
+        let timeout = std::time::Duration::from_secs(timeout_secs);
+        let (status, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(status) => (Some(status.map_err(|e| format!("shell error: {e}"))?), false),
+            Err(_) => {
+                if let Some(pid) = child.id() {
+                    kill_process_tree(pid);
+                }
+                let _ = child.kill().await;
+                (None, true)
+            }
+        };
+        let aborts: Vec<_> = readers.iter().map(|r| r.abort_handle()).collect();
+        if tokio::time::timeout(PIPE_DRAIN_TIMEOUT, futures_util::future::join_all(readers))
+            .await
+            .is_err()
+        {
+            aborts.iter().for_each(|a| a.abort());
         }
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+
+        let text = |buf: &Mutex<Vec<u8>>| {
+            String::from_utf8_lossy(&buf.lock().unwrap_or_else(|e| e.into_inner())).into_owned()
+        };
+        Ok(ShellResult {
+            exit_code: status.and_then(|s| s.code()).unwrap_or(-1),
+            stdout: text(&stdout),
+            stderr: text(&stderr),
+            timed_out,
+        })
+    }
+    .await;
+    audit::record("computer_run_shell", args, module.as_deref(), &result);
+    result
 }
 
 // ── File commands ──────────────────────────────────────────────────────────────
 
 /// Reads the full UTF-8 content of a file.
 /// Rejects paths larger than 10 MB to prevent accidental memory exhaustion.
+/// The path must be readable under the [file sandbox](crate::file_sandbox);
+/// relative paths resolve against its workspace.
 /// Requires `computer.files` permission (enforced by `SandboxedComputer`).
 #[tauri::command]
-pub async fn computer_read_file(path: String) -> Result<String, String> {
-    const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024; // 10 MB
+pub async fn computer_read_file(
+    app: AppHandle,
+    path: String,
+    module: Option<String>,
+) -> Result<String, String> {
+    let args = serde_json::json!({ "path": path });
+    let result: Result<String, String> = async move {
+        const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024; // 10 MB
 
-    tokio::task::spawn_blocking(move || {
-        let meta = std::fs::metadata(&path)
-            .map_err(|e| format!("file metadata error: {e}"))?;
-        if meta.len() > MAX_FILE_BYTES {
-            return Err(format!(
-                "file too large ({} bytes, max {} bytes)",
-                meta.len(),
-                MAX_FILE_BYTES
-            ));
-        }
-        std::fs::read_to_string(&path).map_err(|e| format!("file read error: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+        tokio::task::spawn_blocking(move || {
+            let path = file_sandbox::resolve(&app, &path, file_sandbox::Access::Read)?;
+            let meta = std::fs::metadata(&path)
+                .map_err(|e| format!("file metadata error: {e}"))?;
+            if meta.len() > MAX_FILE_BYTES {
+                return Err(format!(
+                    "file too large ({} bytes, max {} bytes)",
+                    meta.len(),
+                    MAX_FILE_BYTES
+                ));
+            }
+            std::fs::read_to_string(&path).map_err(|e| format!("file read error: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_read_file", args, module.as_deref(), &result);
+    result
 }
 
 /// Writes UTF-8 content to a file, creating parent directories as needed.
+/// Fails early when the target volume is nearly full. The path must be
+/// writable under the [file sandbox](crate::file_sandbox); outside its
+/// workspace the write waits for the user's [approval](crate::approval).
 /// Requires `computer.files` permission (enforced by `SandboxedComputer`).
 #[tauri::command]
-pub async fn computer_write_file(path: String, content: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        if let Some(parent) = std::path::Path::new(&path).parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("create dirs error: {e}"))?;
-        }
-        std::fs::write(&path, content.as_bytes())
-            .map_err(|e| format!("file write error: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+pub async fn computer_write_file(
+    app: AppHandle,
+    path: String,
+    content: String,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "path": path, "bytes": content.len() });
+    let result: Result<(), String> = async move {
+        let path = file_sandbox::writable_path(&app, &path).await?;
+        tokio::task::spawn_blocking(move || {
+            resources::ensure_disk_space(&app, &path, content.len() as u64)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("create dirs error: {e}"))?;
+            }
+            std::fs::write(&path, content.as_bytes())
+                .map_err(|e| format!("file write error: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_write_file", args, module.as_deref(), &result);
+    result
 }
 
 /// Appends UTF-8 content to a file, creating it if it does not exist.
+/// Fails early when the target volume is nearly full. The path must be
+/// writable under the [file sandbox](crate::file_sandbox); outside its
+/// workspace the write waits for the user's [approval](crate::approval).
 /// Requires `computer.files` permission (enforced by `SandboxedComputer`).
 #[tauri::command]
-pub async fn computer_append_file(path: String, content: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        if let Some(parent) = std::path::Path::new(&path).parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("create dirs error: {e}"))?;
-        }
-        let mut file = std::fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&path)
-            .map_err(|e| format!("file open error: {e}"))?;
-        file.write_all(content.as_bytes())
-            .map_err(|e| format!("file append error: {e}"))
-    })
-    .await
-    .map_err(|e| format!("task panicked: {e}"))
-    .and_then(|r| r)
+pub async fn computer_append_file(
+    app: AppHandle,
+    path: String,
+    content: String,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "path": path, "bytes": content.len() });
+    let result: Result<(), String> = async move {
+        let path = file_sandbox::writable_path(&app, &path).await?;
+        tokio::task::spawn_blocking(move || {
+            resources::ensure_disk_space(&app, &path, content.len() as u64)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("create dirs error: {e}"))?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&path)
+                .map_err(|e| format!("file open error: {e}"))?;
+            file.write_all(content.as_bytes())
+                .map_err(|e| format!("file append error: {e}"))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_append_file", args, module.as_deref(), &result);
+    result
+}
+
+/// Maximum bytes returned by `computer_read_file_range` and `computer_tail_file`.
+const MAX_LINES_BYTES: usize = 1024 * 1024;
+/// Maximum lines `computer_tail_file` returns.
+const MAX_TAIL_LINES: u64 = 10_000;
+
+/// Reads lines `start_line` to `end_line` (1-based, inclusive; to the end of
+/// the file when omitted) without loading the rest of the file, so big logs
+/// can be paged through. Output is capped at 1 MB; invalid UTF-8 is
+/// replaced. The path is checked like `computer_read_file`.
+/// Requires `computer.files` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_read_file_range(
+    app: AppHandle,
+    path: String,
+    start_line: u64,
+    end_line: Option<u64>,
+    module: Option<String>,
+) -> Result<FileLines, String> {
+    let args = serde_json::json!({ "path": path, "start_line": start_line, "end_line": end_line });
+    let result: Result<FileLines, String> = async move {
+        use std::io::BufRead;
+
+        if start_line == 0 || end_line.is_some_and(|end| end < start_line) {
+            return Err("lines are numbered from 1 and end_line must not precede start_line".into());
+        }
+        tokio::task::spawn_blocking(move || {
+            let path = file_sandbox::resolve(&app, &path, file_sandbox::Access::Read)?;
+            let file = std::fs::File::open(&path).map_err(|e| format!("file open error: {e}"))?;
+            let mut reader = std::io::BufReader::new(file);
+            let mut content = Vec::new();
+            let mut line = Vec::new();
+            let mut number = 0u64;
+            let mut lines = 0u64;
+            let mut truncated = false;
+            loop {
+                line.clear();
+                let n = reader.read_until(b'\n', &mut line).map_err(|e| format!("file read error: {e}"))?;
+                if n == 0 {
+                    break;
+                }
+                number += 1;
+                if number < start_line {
+                    continue;
+                }
+                if end_line.is_some_and(|end| number > end) {
+                    // One line past the range: there is more.
+                    return Ok(lines_result(content, start_line, lines, true, false));
+                }
+                if content.len() + line.len() > MAX_LINES_BYTES {
+                    truncated = true;
+                    break;
+                }
+                content.extend_from_slice(&line);
+                lines += 1;
+            }
+            Ok(lines_result(content, start_line, lines, truncated, truncated))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_read_file_range", args, module.as_deref(), &result);
+    result
+}
+
+fn lines_result(content: Vec<u8>, start_line: u64, lines: u64, has_more: bool, truncated: bool) -> FileLines {
+    FileLines {
+        content: String::from_utf8_lossy(&content).into_owned(),
+        start_line: if lines == 0 { 0 } else { start_line },
+        end_line: if lines == 0 { 0 } else { start_line + lines - 1 },
+        lines,
+        has_more,
+        truncated,
+    }
+}
+
+/// Returns the last `n` lines of a file (at most 10 000), reading backwards
+/// from the end so the file's size doesn't matter. Output is capped at
+/// 1 MB; invalid UTF-8 is replaced. The path is checked like
+/// `computer_read_file`.
+/// Requires `computer.files` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_tail_file(
+    app: AppHandle,
+    path: String,
+    n: u64,
+    module: Option<String>,
+) -> Result<FileLines, String> {
+    let args = serde_json::json!({ "path": path, "n": n });
+    let result: Result<FileLines, String> = async move {
+        use std::io::{Read, Seek, SeekFrom};
+
+        if !(1..=MAX_TAIL_LINES).contains(&n) {
+            return Err(format!("n must be between 1 and {MAX_TAIL_LINES}"));
+        }
+        tokio::task::spawn_blocking(move || {
+            const CHUNK: u64 = 64 * 1024;
+            let path = file_sandbox::resolve(&app, &path, file_sandbox::Access::Read)?;
+            let mut file = std::fs::File::open(&path).map_err(|e| format!("file open error: {e}"))?;
+            let len = file.metadata().map_err(|e| format!("file metadata error: {e}"))?.len();
+
+            // Read chunks backwards until the buffer holds n line breaks beyond
+            // a trailing one, the start of the file or the size cap.
+            let mut buf: Vec<u8> = Vec::new();
+            let mut pos = len;
+            let mut truncated = false;
+            let start = loop {
+                let body_end = buf.len() - usize::from(buf.last() == Some(&b'\n'));
+                let breaks: Vec<usize> = buf[..body_end]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| **b == b'\n')
+                    .map(|(i, _)| i)
+                    .collect();
+                if breaks.len() as u64 >= n {
+                    break breaks[breaks.len() - n as usize] + 1;
+                }
+                if pos == 0 {
+                    break 0;
+                }
+                if buf.len() >= MAX_LINES_BYTES {
+                    truncated = true;
+                    // Keep whole lines within the cap.
+                    let cut = buf.len() - MAX_LINES_BYTES;
+                    break breaks.iter().find(|&&i| i >= cut).map_or(buf.len(), |i| i + 1);
+                }
+                let step = CHUNK.min(pos);
+                pos -= step;
+                let mut chunk = vec![0u8; step as usize];
+                file.seek(SeekFrom::Start(pos)).map_err(|e| format!("file read error: {e}"))?;
+                file.read_exact(&mut chunk).map_err(|e| format!("file read error: {e}"))?;
+                chunk.extend_from_slice(&buf);
+                buf = chunk;
+            };
+
+            let content = &buf[start..];
+            let lines = content.split(|b| *b == b'\n').count() as u64
+                - u64::from(content.is_empty() || content.last() == Some(&b'\n'));
+            Ok(FileLines {
+                content: String::from_utf8_lossy(content).into_owned(),
+                start_line: 0,
+                end_line: 0,
+                lines,
+                has_more: pos > 0 || start > 0,
+                truncated,
+            })
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_tail_file", args, module.as_deref(), &result);
+    result
 }
@@ -0,0 +1,127 @@
+//! Recovery from context-length errors on the direct (BYOK) path.
+//!
+//! When a provider rejects a request because the prompt doesn't fit the
+//! model's context window, the call is retried instead of surfacing the raw
+//! 400: first on the larger-context model configured for the provider (see
+//! `settings_set_context_fallback_models`), then — for conversations — with
+//! the older half of the history condensed into a summary. Each switch is
+//! announced with a `context-fallback` event so the UI can tell the user.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::providers::ChatMessage;
+use crate::settings;
+
+const FALLBACK_MODELS_KEY: &str = "context_fallback_models";
+
+/// Provider error fragments that mean the prompt exceeded the context window
+/// (OpenAI and compatibles, Anthropic, Gemini, Cohere, Bedrock).
+const CONTEXT_LENGTH_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "prompt is too long",
+    "input is too long",
+    "too many tokens",
+    "exceeds the maximum number of tokens",
+    "input token count",
+    "reduce the length of the messages",
+];
+
+/// The older turns are summarized in at most this many tokens.
+pub const SUMMARY_MAX_TOKENS: u32 = 1024;
+
+pub const SUMMARY_PROMPT: &str = "Summarize the conversation below for the assistant that will continue it. \
+Keep facts, names, numbers, decisions and open questions; drop pleasantries. \
+Reply with the summary only.";
+
+/// Payload of the `context-fallback` event.
+#[derive(Serialize, Clone)]
+pub struct ContextFallback {
+    /// `larger_model` or `summarized`.
+    pub reason: &'static str,
+    pub from_model: String,
+    /// Model used for the retry.
+    pub model: String,
+    /// Earlier turns replaced by a summary (0 for `larger_model`).
+    pub summarized_turns: usize,
+}
+
+pub fn is_context_length_error(err: &str) -> bool {
+    let lower = err.to_ascii_lowercase();
+    CONTEXT_LENGTH_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+// ── Store helpers ──────────────────────────────────────────────────────────────
+
+/// Larger-context model per provider.
+pub fn load_fallback_models(app: &AppHandle) -> HashMap<String, String> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(FALLBACK_MODELS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The fallback model of `provider`, unless it is the model that just failed.
+pub fn fallback_model(app: &AppHandle, provider: &str, failed_model: &str) -> Option<String> {
+    load_fallback_models(app)
+        .remove(provider)
+        .filter(|m| m != failed_model)
+}
+
+// ── History condensing ─────────────────────────────────────────────────────────
+
+/// Splits `history` for condensing: the older half of the user/assistant
+/// turns as a transcript to summarize, the newer half to keep, and how many
+/// turns went into the transcript. `None` when there are fewer than two turns.
+pub fn split_history(history: &[ChatMessage]) -> Option<(String, Vec<ChatMessage>, usize)> {
+    let turns: Vec<&ChatMessage> = history.iter().filter(|m| m.role != "system").collect();
+    if turns.len() < 2 {
+        return None;
+    }
+    let split = turns.len() / 2;
+    let transcript = turns[..split]
+        .iter()
+        .map(|m| format!("{}: {}", m.role, m.content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let kept = turns[split..].iter().map(|m| (*m).clone()).collect();
+    Some((transcript, kept, split))
+}
+
+pub fn summary_directive(summary: &str) -> String {
+    format!("Summary of the earlier conversation:\n{}", summary.trim())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the larger-context fallback model configured per provider.
+#[tauri::command]
+pub async fn settings_get_context_fallback_models(app: AppHandle) -> HashMap<String, String> {
+    load_fallback_models(&app)
+}
+
+/// Replaces the larger-context fallback models, keyed by provider
+/// (e.g. `{ "openai": "gpt-4.1" }`); blank models are dropped.
+#[tauri::command]
+pub async fn settings_set_context_fallback_models(
+    app: AppHandle,
+    models: HashMap<String, String>,
+) -> Result<(), String> {
+    let models: HashMap<String, String> = models
+        .into_iter()
+        .map(|(p, m)| (p.trim().to_ascii_lowercase(), m.trim().to_owned()))
+        .filter(|(p, m)| !p.is_empty() && !m.is_empty())
+        .collect();
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&models).map_err(|e| e.to_string())?;
+    store.set(FALLBACK_MODELS_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
@@ -0,0 +1,135 @@
+//! `aisuperapp://` deep links.
+//!
+//! Links opened from a browser or another app bring up the main window and
+//! are handed to it as a `deeplink:open` event:
+//!
+//! - `aisuperapp://chat?prompt=…` — new chat, with the prompt pre-filled.
+//! - `aisuperapp://agent/run?id=…` — run the installed agent `id`.
+//!
+//! Anyone can craft these links, so everything is checked here before the
+//! frontend sees it: unknown routes are rejected, agent IDs are limited to a
+//! plain identifier, and prompts are stripped of control characters and
+//! capped in length. The frontend still asks before sending the prompt or
+//! starting the agent. Rejected links are reported on `deeplink:error`.
+//!
+//! On Windows and Linux a link opens a second process; the single-instance
+//! plugin forwards it to the running one.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+pub const SCHEME: &str = "aisuperapp";
+const MAIN_WINDOW: &str = "main";
+const MAX_PROMPT_CHARS: usize = 8_000;
+const MAX_ID_LEN: usize = 128;
+
+/// Payload of `deeplink:open`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    Chat { prompt: Option<String> },
+    AgentRun { id: String },
+}
+
+/// The last link the main window hasn't picked up, e.g. the one the app was
+/// launched with.
+#[derive(Default)]
+pub struct PendingDeepLink(Mutex<Option<DeepLink>>);
+
+/// First value of query parameter `name`, percent-decoded.
+fn param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned())
+}
+
+fn sanitize_prompt(raw: &str) -> Result<Option<String>, String> {
+    let prompt: String = raw
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .collect();
+    let prompt = prompt.trim();
+    if prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(format!("prompt is too long (max {MAX_PROMPT_CHARS} characters)"));
+    }
+    Ok((!prompt.is_empty()).then(|| prompt.to_owned()))
+}
+
+fn sanitize_id(raw: &str) -> Result<String, String> {
+    let id = raw.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err("invalid agent id".into());
+    }
+    Ok(id.to_owned())
+}
+
+/// Parses and validates a deep link.
+pub fn parse(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("unsupported scheme '{}'", url.scheme()));
+    }
+    // `aisuperapp://agent/run` has host `agent`; some launchers pass the
+    // route as a path instead (`aisuperapp:agent/run`).
+    let route = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+    match route.trim_matches('/').to_ascii_lowercase().as_str() {
+        "chat" => Ok(DeepLink::Chat {
+            prompt: param(url, "prompt").map(|p| sanitize_prompt(&p)).transpose()?.flatten(),
+        }),
+        "agent/run" => {
+            let id = param(url, "id").ok_or("missing agent id")?;
+            Ok(DeepLink::AgentRun { id: sanitize_id(&id)? })
+        }
+        _ => Err("unknown deep link".into()),
+    }
+}
+
+/// Validates `url` and hands it to the main window.
+fn open(app: &AppHandle, url: &Url) {
+    let link = match parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            let _ = app.emit("deeplink:error", e);
+            return;
+        }
+    };
+    *app.state::<PendingDeepLink>().0.lock().unwrap_or_else(|e| e.into_inner()) = Some(link.clone());
+    if let Some(main) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = main.unminimize();
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+    let _ = app.emit_to(MAIN_WINDOW, "deeplink:open", link);
+}
+
+/// Handles the link the app was launched with and listens for later ones.
+/// Call once at startup.
+pub fn start(app: &AppHandle) {
+    // Installers register the scheme; this covers dev and portable builds.
+    #[cfg(any(target_os = "linux", windows))]
+    let _ = app.deep_link().register_all();
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in &urls {
+            open(app, url);
+        }
+    }
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in &event.urls() {
+            open(&handle, url);
+        }
+    });
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns and clears the last `deeplink:open` payload, for a window that
+/// was still loading when it was emitted.
+#[tauri::command]
+pub fn deep_link_take_pending(app: AppHandle) -> Option<DeepLink> {
+    app.state::<PendingDeepLink>().0.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
@@ -0,0 +1,418 @@
+//! Virtual desktops (Spaces on macOS, workspaces on Linux).
+//!
+//! A window on another desktop doesn't show up in screenshots and can't be
+//! clicked, so an agent looking for it just fails. `computer_desktop_list`
+//! tells it how many desktops there are and which one is showing,
+//! `computer_desktop_switch` goes to another one and
+//! `computer_desktop_move_window` brings a window over.
+//!
+//! No platform has a complete public API for this:
+//! - **Linux** — EWMH through `wmctrl`; works with most X11 window managers.
+//! - **Windows** — desktops are read from Explorer's registry keys and
+//!   switched with Ctrl+Win+←/→. Moving other apps' windows isn't possible.
+//! - **macOS** — Spaces are read from the window server and switched with
+//!   Ctrl+←/→ (the default Mission Control shortcuts). Moving windows
+//!   between Spaces isn't possible since macOS 14.
+//!
+//! Desktops are numbered from 0 in the order the OS lists them.
+
+use std::time::Duration;
+
+use enigo::{
+    Direction::{Click, Press, Release},
+    Enigo, Key, Keyboard, Settings,
+};
+use serde::Serialize;
+
+use crate::{audit, session};
+
+/// Wait between desktop-switch shortcuts, for the slide animation.
+const SWITCH_STEP_DELAY: Duration = Duration::from_millis(400);
+
+/// Entry of `computer_desktop_list`.
+#[derive(Serialize, Clone, Debug)]
+pub struct VirtualDesktop {
+    pub index: usize,
+    /// The name the user gave it, when the OS keeps one.
+    pub name: Option<String>,
+    /// This desktop is showing.
+    pub current: bool,
+}
+
+/// Presses `modifiers` + `key` `times` times, pausing for the animation.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn press_repeatedly(modifiers: &[Key], key: Key, times: usize) -> Result<(), String> {
+    let mut e = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+    for i in 0..times {
+        if i > 0 {
+            std::thread::sleep(SWITCH_STEP_DELAY);
+        }
+        let mut pressed = Vec::new();
+        let mut result = Ok(());
+        for &m in modifiers {
+            match e.key(m, Press) {
+                Ok(()) => pressed.push(m),
+                Err(err) => {
+                    result = Err(format!("key press failed: {err}"));
+                    break;
+                }
+            }
+        }
+        if result.is_ok() {
+            result = e.key(key, Click).map_err(|e| format!("key press failed: {e}"));
+        }
+        for &m in pressed.iter().rev() {
+            let _ = e.key(m, Release);
+        }
+        result?;
+    }
+    Ok(())
+}
+
+/// Switches by pressing the "next / previous desktop" shortcut until
+/// `index` is reached from the current desktop.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn switch_by_shortcut(
+    desktops: &[VirtualDesktop],
+    index: usize,
+    modifiers: &[Key],
+) -> Result<(), String> {
+    let current = desktops
+        .iter()
+        .position(|d| d.current)
+        .ok_or("can't tell which desktop is showing")?;
+    if index > current {
+        press_repeatedly(modifiers, Key::RightArrow, index - current)
+    } else {
+        press_repeatedly(modifiers, Key::LeftArrow, current - index)
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Lists the virtual desktops and which one is showing.
+#[tauri::command]
+pub async fn computer_desktop_list() -> Result<Vec<VirtualDesktop>, String> {
+    tokio::task::spawn_blocking(platform::list)
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+}
+
+/// Shows desktop `index` (from `computer_desktop_list`). On Windows and
+/// macOS this presses the switch shortcut, so it needs input to be allowed
+/// and, on macOS, Accessibility permission.
+#[tauri::command]
+pub async fn computer_desktop_switch(index: usize, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "index": index });
+    let result: Result<(), String> = tokio::task::spawn_blocking(move || {
+        session::ensure_input_allowed()?;
+        let desktops = platform::list()?;
+        if index >= desktops.len() {
+            return Err(format!("there are only {} desktops", desktops.len()));
+        }
+        if desktops[index].current {
+            return Ok(());
+        }
+        platform::switch(&desktops, index)
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r);
+    audit::record("computer_desktop_switch", args, module.as_deref(), &result);
+    result
+}
+
+/// Moves the window `window_id` (from `computer_list_windows`) to desktop
+/// `index`. Linux only — see the module docs.
+#[tauri::command]
+pub async fn computer_desktop_move_window(
+    window_id: u32,
+    index: usize,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "window_id": window_id, "index": index });
+    let result: Result<(), String> = tokio::task::spawn_blocking(move || {
+        let desktops = platform::list()?;
+        if index >= desktops.len() {
+            return Err(format!("there are only {} desktops", desktops.len()));
+        }
+        platform::move_window(window_id, index)
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r);
+    audit::record("computer_desktop_move_window", args, module.as_deref(), &result);
+    result
+}
+
+// ── Platform queries ───────────────────────────────────────────────────────────
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::VirtualDesktop;
+
+    fn wmctrl(args: &[&str]) -> Result<String, String> {
+        let out = std::process::Command::new("wmctrl")
+            .args(args)
+            .output()
+            .map_err(|e| format!("virtual desktops need wmctrl: {e}"))?;
+        if !out.status.success() {
+            return Err(format!(
+                "wmctrl failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    }
+
+    /// Parses `wmctrl -d`, e.g.
+    /// `0  * DG: 3840x1080  VP: 0,0  WA: 0,27 1920x1053  Main`.
+    pub fn list() -> Result<Vec<VirtualDesktop>, String> {
+        let text = wmctrl(&["-d"])?;
+        Ok(text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let index = fields.next()?.parse().ok()?;
+                let current = fields.next()? == "*";
+                // The name is whatever follows the work area size.
+                let name = line
+                    .split_once("WA: ")
+                    .and_then(|(_, rest)| rest.splitn(3, ' ').nth(2))
+                    .map(str::trim)
+                    .filter(|n| !n.is_empty() && *n != "N/A")
+                    .map(str::to_owned);
+                Some(VirtualDesktop { index, name, current })
+            })
+            .collect())
+    }
+
+    pub fn switch(_desktops: &[VirtualDesktop], index: usize) -> Result<(), String> {
+        wmctrl(&["-s", &index.to_string()]).map(drop)
+    }
+
+    pub fn move_window(window_id: u32, index: usize) -> Result<(), String> {
+        let id = format!("{window_id:#x}");
+        wmctrl(&["-i", "-r", &id, "-t", &index.to_string()]).map(drop)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::VirtualDesktop;
+    use enigo::Key;
+
+    const EXPLORER: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer";
+
+    /// The value of `name` under `key`, as `reg query` prints it.
+    fn reg_value(key: &str, name: &str) -> Option<String> {
+        let out = std::process::Command::new("reg")
+            .args(["query", key, "/v", name])
+            .output()
+            .ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&out.stdout).into_owned();
+        text.lines().find_map(|line| {
+            let mut parts = line.trim().splitn(3, "    ");
+            (parts.next()? == name).then_some(())?;
+            parts.next()?;
+            Some(parts.next().unwrap_or("").trim().to_owned())
+        })
+    }
+
+    fn hex_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+            .collect()
+    }
+
+    /// Formats a GUID stored as 16 little-endian bytes.
+    fn guid(b: &[u8]) -> String {
+        format!(
+            "{{{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+            b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+        )
+    }
+
+    /// The current desktop is kept per logon session on Windows 10 and
+    /// directly under `VirtualDesktops` on Windows 11.
+    fn current_desktop() -> Option<String> {
+        let direct = reg_value(&format!(r"{EXPLORER}\VirtualDesktops"), "CurrentVirtualDesktop");
+        direct
+            .or_else(|| {
+                let session = std::process::id();
+                let mut id = 0u32;
+                // SAFETY: writes the session id of our own process.
+                unsafe { ProcessIdToSessionId(session, &mut id) };
+                reg_value(
+                    &format!(r"{EXPLORER}\SessionInfo\{id}\VirtualDesktops"),
+                    "CurrentVirtualDesktop",
+                )
+            })
+            .map(|hex| guid(&hex_bytes(&hex)))
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn ProcessIdToSessionId(pid: u32, session_id: *mut u32) -> i32;
+    }
+
+    pub fn list() -> Result<Vec<VirtualDesktop>, String> {
+        let ids = reg_value(&format!(r"{EXPLORER}\VirtualDesktops"), "VirtualDesktopIDs")
+            .map(|hex| hex_bytes(&hex))
+            .unwrap_or_default();
+        if ids.len() < 16 {
+            // Never created a second desktop: just the one.
+            return Ok(vec![VirtualDesktop { index: 0, name: None, current: true }]);
+        }
+        let current = current_desktop();
+        Ok(ids
+            .chunks_exact(16)
+            .map(guid)
+            .enumerate()
+            .map(|(index, id)| VirtualDesktop {
+                index,
+                name: reg_value(&format!(r"{EXPLORER}\VirtualDesktops\Desktops\{id}"), "Name")
+                    .filter(|n| !n.is_empty()),
+                current: current.as_deref() == Some(id.as_str()),
+            })
+            .collect())
+    }
+
+    pub fn switch(desktops: &[VirtualDesktop], index: usize) -> Result<(), String> {
+        super::switch_by_shortcut(desktops, index, &[Key::Control, Key::Meta])
+    }
+
+    pub fn move_window(_window_id: u32, _index: usize) -> Result<(), String> {
+        Err("Windows only lets apps move their own windows between desktops".into())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::VirtualDesktop;
+    use enigo::Key;
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+    /// `kCFNumberSInt64Type`.
+    const SINT64: i32 = 4;
+    /// Space `type` of full-screen apps, which Ctrl+←/→ also steps through.
+    const FULLSCREEN_SPACE: i64 = 4;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSMainConnectionID() -> i32;
+        fn CGSCopyManagedDisplaySpaces(connection: i32) -> CFTypeRef;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithBytes(
+            alloc: CFTypeRef,
+            bytes: *const u8,
+            len: isize,
+            encoding: u32,
+            external: u8,
+        ) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFArrayGetCount(array: CFTypeRef) -> isize;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, index: isize) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, kind: i32, value: *mut c_void) -> u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    /// Looks up `key` in `dict`; the value is borrowed from the dictionary.
+    unsafe fn get(dict: CFTypeRef, key: &str) -> CFTypeRef {
+        let cf_key =
+            CFStringCreateWithBytes(std::ptr::null(), key.as_ptr(), key.len() as isize, UTF8, 0);
+        if cf_key.is_null() {
+            return std::ptr::null();
+        }
+        let value = CFDictionaryGetValue(dict, cf_key);
+        CFRelease(cf_key);
+        value
+    }
+
+    unsafe fn number(dict: CFTypeRef, key: &str) -> Option<i64> {
+        let value = get(dict, key);
+        if value.is_null() {
+            return None;
+        }
+        let mut n = 0i64;
+        (CFNumberGetValue(value, SINT64, (&mut n as *mut i64).cast()) != 0).then_some(n)
+    }
+
+    /// Reads the Spaces of every display from the window server, in Mission
+    /// Control order. Space names aren't stored, so `name` is "Desktop n"
+    /// or "Full screen".
+    pub fn list() -> Result<Vec<VirtualDesktop>, String> {
+        // SAFETY: the copied array is owned by us and released below; the
+        // values read from it are borrowed and not used past that.
+        unsafe {
+            let displays = CGSCopyManagedDisplaySpaces(CGSMainConnectionID());
+            if displays.is_null() {
+                return Err("Spaces are unavailable".into());
+            }
+            let mut desktops = Vec::new();
+            let mut user_spaces = 0;
+            for d in 0..CFArrayGetCount(displays) {
+                let display = CFArrayGetValueAtIndex(displays, d);
+                let current = get(display, "Current Space");
+                let current_id =
+                    if current.is_null() { None } else { number(current, "ManagedSpaceID") };
+                let spaces = get(display, "Spaces");
+                if spaces.is_null() {
+                    continue;
+                }
+                for s in 0..CFArrayGetCount(spaces) {
+                    let space = CFArrayGetValueAtIndex(spaces, s);
+                    let id = number(space, "ManagedSpaceID");
+                    let name = if number(space, "type") == Some(FULLSCREEN_SPACE) {
+                        "Full screen".to_owned()
+                    } else {
+                        user_spaces += 1;
+                        format!("Desktop {user_spaces}")
+                    };
+                    desktops.push(VirtualDesktop {
+                        index: desktops.len(),
+                        name: Some(name),
+                        current: id.is_some() && id == current_id,
+                    });
+                }
+            }
+            CFRelease(displays);
+            Ok(desktops)
+        }
+    }
+
+    pub fn switch(desktops: &[VirtualDesktop], index: usize) -> Result<(), String> {
+        super::switch_by_shortcut(desktops, index, &[Key::Control])
+    }
+
+    pub fn move_window(_window_id: u32, _index: usize) -> Result<(), String> {
+        Err("macOS doesn't let apps move windows between Spaces".into())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::VirtualDesktop;
+
+    pub fn list() -> Result<Vec<VirtualDesktop>, String> {
+        Ok(vec![VirtualDesktop { index: 0, name: None, current: true }])
+    }
+
+    pub fn switch(_desktops: &[VirtualDesktop], _index: usize) -> Result<(), String> {
+        Err("virtual desktops are not supported on this platform".into())
+    }
+
+    pub fn move_window(_window_id: u32, _index: usize) -> Result<(), String> {
+        Err("virtual desktops are not supported on this platform".into())
+    }
+}
@@ -0,0 +1,136 @@
+//! Kill switch for computer use, and the input rate limit's settings.
+//!
+//! `computer_emergency_stop` — also bound to a global hotkey, registered by
+//! [`hotkey`](crate::hotkey), so it works while an agent is hammering the
+//! mouse — pauses agents as the tray toggle does (refusing further input
+//! and releasing held keys and buttons), fails pending approvals and emits
+//! `computer:emergency-stop`. Agents stay paused until resumed from the
+//! tray or with `agents_set_paused`.
+//!
+//! The same settings hold the per-second cap on mouse and keyboard commands
+//! enforced by [`computer::throttle_input`].
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::hotkey::{Hotkey, HotkeyRegistration};
+use crate::{approval, capture_ask, computer, hotkey, quick_chat, settings, tray};
+
+const INPUT_SAFETY_KEY: &str = "input_safety";
+const HOTKEY_NAME: &str = "emergency_stop";
+
+/// Stored under `input_safety` in the settings store.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct InputSafetyConfig {
+    /// Mouse and keyboard commands allowed per second; 0 for no limit.
+    pub max_input_per_second: u32,
+    pub emergency_stop_enabled: bool,
+    /// Accelerator such as `CmdOrCtrl+Shift+F12`.
+    pub emergency_stop_hotkey: String,
+}
+
+impl Default for InputSafetyConfig {
+    fn default() -> Self {
+        Self {
+            max_input_per_second: computer::DEFAULT_INPUT_RATE_LIMIT,
+            emergency_stop_enabled: true,
+            emergency_stop_hotkey: "CmdOrCtrl+Shift+F12".into(),
+        }
+    }
+}
+
+/// The registered emergency-stop hotkey.
+#[derive(Default)]
+pub struct EmergencyStop(Mutex<Option<HotkeyRegistration>>);
+
+impl EmergencyStop {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<HotkeyRegistration>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub fn load_config(app: &AppHandle) -> InputSafetyConfig {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(INPUT_SAFETY_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Applies the rate limit and registers the hotkey at startup; a hotkey
+/// that can't be registered is reported by [`hotkey::register`].
+pub fn start(app: &AppHandle) {
+    let config = load_config(app);
+    computer::set_input_rate_limit(config.max_input_per_second);
+    let _ = register(app, &config);
+}
+
+/// Unregisters the hotkey.
+pub fn stop(app: &AppHandle) {
+    *app.state::<EmergencyStop>().lock() = None;
+}
+
+fn register(app: &AppHandle, config: &InputSafetyConfig) -> Result<(), String> {
+    let state = app.state::<EmergencyStop>();
+    *state.lock() = None;
+    if !config.emergency_stop_enabled {
+        hotkey::clear(app, HOTKEY_NAME);
+        return Ok(());
+    }
+    let handle = app.clone();
+    let registration =
+        hotkey::register(app, HOTKEY_NAME, &config.emergency_stop_hotkey, move || halt(&handle))?;
+    *state.lock() = Some(registration);
+    Ok(())
+}
+
+fn halt(app: &AppHandle) {
+    tray::set_paused(app, true);
+    app.state::<approval::Approvals>().cancel_all();
+    let _ = app.emit("computer:emergency-stop", ());
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Stops computer use at once: agents are paused, held input is released
+/// and pending approvals fail.
+#[tauri::command]
+pub fn computer_emergency_stop(app: AppHandle) {
+    halt(&app);
+}
+
+#[tauri::command]
+pub async fn settings_get_input_safety(app: AppHandle) -> InputSafetyConfig {
+    load_config(&app)
+}
+
+/// Saves the rate limit and emergency-stop hotkey and applies them. Fails,
+/// without saving, when the hotkey can't be parsed or registered or is taken.
+#[tauri::command]
+pub async fn settings_set_input_safety(app: AppHandle, config: InputSafetyConfig) -> Result<(), String> {
+    let mut config = config;
+    config.emergency_stop_hotkey = config.emergency_stop_hotkey.trim().to_owned();
+    if config.emergency_stop_enabled {
+        let hotkey = Hotkey::parse(&config.emergency_stop_hotkey)?;
+        let capture = capture_ask::load_config(&app);
+        if capture.enabled && Hotkey::parse(&capture.hotkey).is_ok_and(|c| c == hotkey) {
+            return Err(format!("{hotkey} is already the capture-and-ask hotkey"));
+        }
+        let quick = quick_chat::load_config(&app);
+        if quick.quick_chat_enabled && Hotkey::parse(&quick.quick_chat).is_ok_and(|q| q == hotkey) {
+            return Err(format!("{hotkey} is already the quick-chat hotkey"));
+        }
+    }
+    register(&app, &config)?;
+    computer::set_input_rate_limit(config.max_input_per_second);
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store.set(INPUT_SAFETY_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
@@ -0,0 +1,78 @@
+//! Pre-flight cost estimates for direct (BYOK) calls.
+//!
+//! `ai_estimate` counts a prompt's tokens locally with the runtime's
+//! per-model encoders and prices them with the same table as the
+//! [`usage_ledger`], so the UI can warn before an expensive request without
+//! a provider round trip. Counts are estimates; providers add a few tokens
+//! of framing of their own.
+
+use agenthub_runtime::tokenizer::encoder_for;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::providers::{default_model, validate_messages, ChatMessage, LOCAL_OPENAI};
+use crate::{settings, usage_ledger};
+
+/// Chat-format framing added per message (role markers and separators).
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Result of [`ai_estimate`].
+#[derive(Serialize)]
+pub struct CostEstimate {
+    pub model: String,
+    /// Encoder the count came from (`o200k`, `cl100k` or one registered by
+    /// the host). Claude and Gemini counts use the closest OpenAI encoding.
+    pub tokenizer: String,
+    pub prompt_tokens: u32,
+    /// Of the prompt alone, in USD.
+    pub prompt_cost: f64,
+    /// Prompt plus `max_tokens` of output, in USD; `null` without `max_tokens`.
+    pub max_cost: Option<f64>,
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+/// Estimates the prompt tokens and cost of sending `input` (after `system`
+/// and the earlier `messages`) to `provider`'s `model` — the provider
+/// default when omitted. With `max_tokens`, `max_cost` adds the most the
+/// reply could cost.
+#[tauri::command]
+pub fn ai_estimate(
+    app: AppHandle,
+    input: String,
+    provider: String,
+    model: Option<String>,
+    system: Option<String>,
+    messages: Option<Vec<ChatMessage>>,
+    max_tokens: Option<u32>,
+) -> Result<CostEstimate, String> {
+    let history = messages.unwrap_or_default();
+    validate_messages(&history)?;
+    let model = model
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| match provider.as_str() {
+            "custom" => settings::load_custom_provider(&app)?.model,
+            LOCAL_OPENAI => Some("local".into()),
+            _ => None,
+        })
+        .unwrap_or_else(|| default_model(&provider).to_owned());
+
+    let encoder = encoder_for(&model);
+    let prompt_tokens: u32 = system
+        .iter()
+        .chain(history.iter().map(|m| &m.content))
+        .chain([&input])
+        .map(|text| encoder.count(text) + MESSAGE_OVERHEAD_TOKENS)
+        .sum();
+
+    let price = usage_ledger::pricing(&provider, &model);
+    Ok(CostEstimate {
+        tokenizer: encoder.name().to_owned(),
+        prompt_tokens,
+        prompt_cost: price.cost(u64::from(prompt_tokens), 0),
+        max_cost: max_tokens.map(|n| price.cost(u64::from(prompt_tokens), u64::from(n))),
+        input_per_1k: price.input_per_1k,
+        output_per_1k: price.output_per_1k,
+        model,
+    })
+}
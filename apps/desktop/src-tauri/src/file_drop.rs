@@ -0,0 +1,180 @@
+//! Dropping files onto other apps.
+//!
+//! Moving the mouse with a button held isn't enough to drop a file: the
+//! target only accepts a drop from a real drag session carrying the file.
+//! `computer_drop_file` opens a tiny borderless window, starts a native
+//! drag of the file from it (OLE on Windows, `NSDraggingSession` on macOS,
+//! GTK on Linux), then moves the synthetic mouse to the target and lets go
+//! — so web forms and chat apps that only take drops receive it like one
+//! made by hand.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use enigo::{Button, Coordinate, Direction, Enigo, Mouse, Settings};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::oneshot;
+
+use crate::file_sandbox::{self, Access};
+use crate::{audit, session};
+
+const WINDOW_LABEL: &str = "drop-source";
+const WINDOW_SIZE: f64 = 48.0;
+/// The drag starts at least this far from the target, so the source window
+/// doesn't cover it.
+const MIN_DRAG_DISTANCE: i32 = 120;
+const DRAG_STEPS: u32 = 20;
+const DRAG_STEP_DELAY: Duration = Duration::from_millis(15);
+/// Time for the window to appear, and for the drag session to start.
+const SETTLE: Duration = Duration::from_millis(250);
+/// Time over the target before letting go, so it can highlight and accept.
+const HOVER: Duration = Duration::from_millis(200);
+/// How long to wait for the drop to be reported after letting go.
+const DROP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the drag starts: the cursor, unless that's too close to the target.
+fn drag_start(e: &Enigo, x: i32, y: i32) -> (i32, i32) {
+    let (cx, cy) = e.location().unwrap_or((x - MIN_DRAG_DISTANCE, y));
+    if (cx - x).abs() + (cy - y).abs() >= MIN_DRAG_DISTANCE {
+        return (cx, cy);
+    }
+    let offset = MIN_DRAG_DISTANCE * 2;
+    (if x > offset { x - offset } else { x + offset }, y)
+}
+
+/// Moves from `from` to `to` with the left button held and lingers over
+/// the target.
+fn drag_to(e: &mut Enigo, from: (i32, i32), to: (i32, i32)) -> Result<(), String> {
+    for i in 1..=DRAG_STEPS {
+        std::thread::sleep(DRAG_STEP_DELAY);
+        let t = f64::from(i) / f64::from(DRAG_STEPS);
+        let x = from.0 + (f64::from(to.0 - from.0) * t).round() as i32;
+        let y = from.1 + (f64::from(to.1 - from.1) * t).round() as i32;
+        e.move_mouse(x, y, Coordinate::Abs).map_err(|e| format!("drag failed: {e}"))?;
+    }
+    std::thread::sleep(HOVER);
+    Ok(())
+}
+
+/// Starts the native drag of `path` from the source window, on the main
+/// thread. `done` receives whether the file was dropped.
+fn start_drag(app: &AppHandle, path: PathBuf, done: oneshot::Sender<bool>) -> Result<(), String> {
+    let window = app.get_webview_window(WINDOW_LABEL).ok_or("the drag source window is gone")?;
+    let icon = include_bytes!("../icons/32x32.png").to_vec();
+    let done = Mutex::new(Some(done));
+    window
+        .clone()
+        .run_on_main_thread(move || {
+            #[cfg(target_os = "linux")]
+            let source = window.gtk_window();
+            #[cfg(not(target_os = "linux"))]
+            let source = tauri::Result::Ok(window.clone());
+
+            let report = move |dropped: bool| {
+                if let Some(tx) = done.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    let _ = tx.send(dropped);
+                }
+            };
+            let Ok(source) = source else {
+                report(false);
+                return;
+            };
+            let _ = drag::start_drag(
+                &source,
+                drag::DragItem::Files(vec![path]),
+                drag::Image::Raw(icon),
+                move |result, _| report(matches!(result, drag::DragResult::Dropped)),
+                drag::Options::default(),
+            );
+        })
+        .map_err(|e| format!("can't start the drag: {e}"))
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Drags the file at `path` onto the screen position (`x`, `y`), in the
+/// same coordinates as `computer_mouse_move`, and drops it there. Fails
+/// when the target doesn't accept the drop. `path` goes through the file
+/// sandbox like a read. Requires Accessibility permission on macOS.
+#[tauri::command]
+pub async fn computer_drop_file(
+    app: AppHandle,
+    path: String,
+    x: i32,
+    y: i32,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "path": path, "x": x, "y": y });
+    let result: Result<(), String> = async move {
+        session::ensure_input_allowed()?;
+        let file = file_sandbox::resolve(&app, &path, Access::Read)?;
+        if !file.exists() {
+            return Err(format!("{} does not exist", file.display()));
+        }
+
+        let start = tokio::task::spawn_blocking(move || {
+            let e = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            Ok::<_, String>(drag_start(&e, x, y))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))??;
+
+        if let Some(stale) = app.get_webview_window(WINDOW_LABEL) {
+            let _ = stale.close();
+        }
+        let half = WINDOW_SIZE / 2.0;
+        let source = WebviewWindowBuilder::new(
+            &app,
+            WINDOW_LABEL,
+            WebviewUrl::External("about:blank".parse().map_err(|e| format!("{e}"))?),
+        )
+        .title("Drop file")
+        .position(f64::from(start.0) - half, f64::from(start.1) - half)
+        .inner_size(WINDOW_SIZE, WINDOW_SIZE)
+        .decorations(false)
+        .resizable(false)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .build()
+        .map_err(|e| format!("can't open the drag source window: {e}"))?;
+        tokio::time::sleep(SETTLE).await;
+
+        // Press inside the source window, start the drag there, then carry
+        // it over to the target.
+        let (tx, rx) = oneshot::channel();
+        let handle = app.clone();
+        let dragged = tokio::task::spawn_blocking(move || {
+            let mut e = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init: {e}"))?;
+            e.move_mouse(start.0, start.1, Coordinate::Abs)
+                .map_err(|e| format!("move to start failed: {e}"))?;
+            e.button(Button::Left, Direction::Press).map_err(|e| format!("press failed: {e}"))?;
+            let moved = start_drag(&handle, file, tx).and_then(|()| {
+                std::thread::sleep(SETTLE);
+                drag_to(&mut e, start, (x, y))
+            });
+            let released = e
+                .button(Button::Left, Direction::Release)
+                .map_err(|e| format!("release failed: {e}"));
+            moved.and(released)
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r);
+        let dropped = match dragged {
+            Ok(()) => match tokio::time::timeout(DROP_TIMEOUT, rx).await {
+                Ok(Ok(true)) => Ok(()),
+                Ok(_) => Err("the target didn't accept the drop".to_string()),
+                Err(_) => Err("the drop wasn't reported in time".to_string()),
+            },
+            Err(e) => Err(e),
+        };
+        let _ = source.close();
+        dropped
+    }
+    .await;
+    audit::record("computer_drop_file", args, module.as_deref(), &result);
+    result
+}
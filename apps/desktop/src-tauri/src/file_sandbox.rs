@@ -0,0 +1,350 @@
+//! Where the file commands (`computer_read_file`, `computer_write_file`,
+//! `computer_append_file`) may reach.
+//!
+//! Every path is resolved to its canonical form — symlinks followed, `.` and
+//! `..` gone — before it is checked, so a link inside an allowed folder
+//! pointing at `~/.ssh` is refused like `~/.ssh` itself. A path is allowed
+//! when it lies in:
+//!
+//! - the workspace (`Documents/AgentHub`, created on first use), which is
+//!   also where relative paths resolve;
+//! - one of the configured `roots`;
+//! - an explicit grant, read-only or read-write, for a file or folder.
+//!
+//! Inside the workspace and roots, credential folders such as `.ssh` and
+//! `.aws` stay off limits unless a grant covers them. The configuration is
+//! stored under `file_sandbox`.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::{approval, settings};
+
+const FILE_SANDBOX_KEY: &str = "file_sandbox";
+const WORKSPACE_DIR: &str = "AgentHub";
+const MAX_ENTRIES: usize = 100;
+/// Folders only reachable through a grant, even inside an allowed root.
+const PROTECTED_DIRS: &[&str] = &[".ssh", ".gnupg", ".aws", ".azure", ".kube", ".docker", ".config/gcloud"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Access to one file or folder (and everything below it).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PathGrant {
+    /// Canonical absolute path.
+    pub path: String,
+    /// Allow writing as well as reading.
+    pub write: bool,
+}
+
+/// Stored under `file_sandbox` in the settings store.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Folders allowed besides the workspace (canonical absolute paths).
+    pub roots: Vec<String>,
+    pub grants: Vec<PathGrant>,
+}
+
+/// Result of `file_sandbox_get`.
+#[derive(Serialize)]
+pub struct SandboxInfo {
+    pub workspace: String,
+    pub roots: Vec<String>,
+    pub grants: Vec<PathGrant>,
+}
+
+fn load_config(app: &AppHandle) -> SandboxConfig {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(FILE_SANDBOX_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save_config(app: &AppHandle, config: &SandboxConfig) -> Result<(), String> {
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    store.set(FILE_SANDBOX_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+/// The workspace folder, created if missing.
+pub fn workspace(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .document_dir()
+        .or_else(|_| app.path().home_dir())
+        .map_err(|e| format!("no documents folder: {e}"))?;
+    let dir = base.join(WORKSPACE_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create workspace error: {e}"))?;
+    dir.canonicalize().map_err(|e| format!("workspace unavailable: {e}"))
+}
+
+/// Canonical form of `path`, which need not exist yet: the longest existing
+/// prefix is canonicalized and the rest appended. The missing part may not
+/// contain `..`.
+fn canonical(path: &Path) -> Result<PathBuf, String> {
+    if let Ok(p) = path.canonicalize() {
+        return Ok(p);
+    }
+    let mut existing = path;
+    let mut rest = Vec::new();
+    // `symlink_metadata`, so a dangling link counts as existing and fails to
+    // canonicalize instead of being written through.
+    while existing.symlink_metadata().is_err() {
+        // `file_name` is `None` for a trailing `..`.
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            return Err(format!("invalid path '{}'", path.display()));
+        };
+        rest.push(name);
+        existing = parent;
+    }
+    let mut out = existing
+        .canonicalize()
+        .map_err(|e| format!("cannot resolve '{}': {e}", path.display()))?;
+    out.extend(rest.iter().rev());
+    Ok(out)
+}
+
+/// Whether `path` lies in a protected folder below `root`.
+pub fn is_protected(root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .take_while(|a| *a != root)
+        .any(|a| PROTECTED_DIRS.iter().any(|d| a.ends_with(d)))
+}
+
+/// Resolves `path` (relative paths against the workspace) and checks the
+/// sandbox allows `access` to it. Returns the canonical path to use.
+pub fn resolve(app: &AppHandle, path: &str, access: Access) -> Result<PathBuf, String> {
+    check(workspace(app)?, &load_config(app), path, access)
+}
+
+/// [`resolve`] against the canonical `workspace` and `config`.
+fn check(workspace: PathBuf, config: &SandboxConfig, path: &str, access: Access) -> Result<PathBuf, String> {
+    let requested = Path::new(path.trim());
+    if requested.as_os_str().is_empty() {
+        return Err("path must not be empty".into());
+    }
+    let full = if requested.is_absolute() { requested.to_path_buf() } else { workspace.join(requested) };
+    let target = canonical(&full)?;
+
+    let granted = config.grants.iter().any(|g| {
+        (g.write || access == Access::Read)
+            && Path::new(&g.path).canonicalize().is_ok_and(|p| target.starts_with(p))
+    });
+    if granted {
+        return Ok(target);
+    }
+    let roots = std::iter::once(workspace)
+        .chain(config.roots.iter().filter_map(|r| Path::new(r).canonicalize().ok()));
+    for root in roots {
+        if target.starts_with(&root) && !is_protected(&root, &target) {
+            return Ok(target);
+        }
+    }
+    Err(format!("'{}' is outside the folders the app may access", target.display()))
+}
+
+/// Resolves `path` for writing and, when it lies outside the workspace,
+/// waits for the user to approve the write.
+pub async fn writable_path(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let target = resolve(app, path, Access::Write)?;
+    if !in_workspace(app, &target) {
+        approval::require(app, approval::Action::file_write(&target)).await?;
+    }
+    Ok(target)
+}
+
+/// Whether the canonical `path` lies in the workspace.
+pub fn in_workspace(app: &AppHandle, path: &Path) -> bool {
+    workspace(app).is_ok_and(|w| path.starts_with(w))
+}
+
+/// Validates a user-supplied root or grant path into canonical form.
+fn canonical_entry(path: &str) -> Result<String, String> {
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("'{}' is not an absolute path", path.display()));
+    }
+    let p = path
+        .canonicalize()
+        .map_err(|e| format!("cannot resolve '{}': {e}", path.display()))?;
+    if p.parent().is_none() {
+        return Err("the filesystem root cannot be allowed as a whole".into());
+    }
+    Ok(p.to_string_lossy().into_owned())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the workspace, the allowed roots and the grants.
+#[tauri::command]
+pub async fn file_sandbox_get(app: AppHandle) -> Result<SandboxInfo, String> {
+    let config = load_config(&app);
+    Ok(SandboxInfo {
+        workspace: workspace(&app)?.to_string_lossy().into_owned(),
+        roots: config.roots,
+        grants: config.grants,
+    })
+}
+
+/// Replaces the allowed roots. Each must be an existing absolute folder.
+#[tauri::command]
+pub async fn file_sandbox_set_roots(app: AppHandle, roots: Vec<String>) -> Result<Vec<String>, String> {
+    if roots.len() > MAX_ENTRIES {
+        return Err(format!("too many roots (max {MAX_ENTRIES})"));
+    }
+    let mut canonical_roots = Vec::new();
+    for root in roots.iter().filter(|r| !r.trim().is_empty()) {
+        let root = canonical_entry(root)?;
+        if !Path::new(&root).is_dir() {
+            return Err(format!("'{root}' is not a folder"));
+        }
+        if !canonical_roots.contains(&root) {
+            canonical_roots.push(root);
+        }
+    }
+    let mut config = load_config(&app);
+    config.roots = canonical_roots.clone();
+    save_config(&app, &config)?;
+    Ok(canonical_roots)
+}
+
+/// Grants access to an existing file or folder, replacing any grant for the
+/// same path. Returns the grant as stored.
+#[tauri::command]
+pub async fn file_sandbox_grant(app: AppHandle, path: String, write: bool) -> Result<PathGrant, String> {
+    let grant = PathGrant { path: canonical_entry(&path)?, write };
+    let mut config = load_config(&app);
+    config.grants.retain(|g| g.path != grant.path);
+    if config.grants.len() >= MAX_ENTRIES {
+        return Err(format!("too many grants (max {MAX_ENTRIES})"));
+    }
+    config.grants.push(grant.clone());
+    save_config(&app, &config)?;
+    Ok(grant)
+}
+
+/// Removes the grant for `path`; returns whether there was one.
+#[tauri::command]
+pub async fn file_sandbox_revoke(app: AppHandle, path: String) -> Result<bool, String> {
+    let key = canonical_entry(&path).unwrap_or_else(|_| path.trim().to_owned());
+    let mut config = load_config(&app);
+    let before = config.grants.len();
+    config.grants.retain(|g| g.path != key);
+    if config.grants.len() == before {
+        return Ok(false);
+    }
+    save_config(&app, &config)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh folder under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("file-sandbox-{}", uuid::Uuid::new_v4().simple()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir.canonicalize().unwrap())
+        }
+
+        fn mkdir(&self, rel: &str) -> PathBuf {
+            let dir = self.0.join(rel);
+            std::fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn path(p: &Path) -> String {
+        p.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn relative_paths_resolve_in_the_workspace() {
+        let tmp = TempDir::new();
+        let workspace = tmp.mkdir("workspace");
+        let config = SandboxConfig::default();
+
+        let target = check(workspace.clone(), &config, "notes/todo.txt", Access::Write).unwrap();
+        assert_eq!(target, workspace.join("notes/todo.txt"));
+        assert!(check(workspace.clone(), &config, "  ", Access::Read).is_err());
+        assert!(check(workspace.clone(), &config, "../outside.txt", Access::Read).is_err());
+        assert!(check(workspace, &config, "missing/../../outside.txt", Access::Read).is_err());
+    }
+
+    #[test]
+    fn roots_allow_all_but_protected_folders() {
+        let tmp = TempDir::new();
+        let workspace = tmp.mkdir("workspace");
+        let home = tmp.mkdir("home");
+        tmp.mkdir("home/.ssh");
+        let config = SandboxConfig { roots: vec![path(&home)], grants: Vec::new() };
+
+        let file = path(&home.join("report.md"));
+        assert!(check(workspace.clone(), &config, &file, Access::Write).is_ok());
+        let key = path(&home.join(".ssh/id_ed25519"));
+        assert!(check(workspace.clone(), &config, &key, Access::Read).is_err());
+        let elsewhere = path(&tmp.mkdir("elsewhere").join("a.txt"));
+        assert!(check(workspace, &config, &elsewhere, Access::Read).is_err());
+    }
+
+    #[test]
+    fn grants_open_their_path_for_their_access() {
+        let tmp = TempDir::new();
+        let workspace = tmp.mkdir("workspace");
+        let ssh = tmp.mkdir("home/.ssh");
+        let config = SandboxConfig {
+            roots: vec![path(&tmp.0.join("home"))],
+            grants: vec![PathGrant { path: path(&ssh), write: false }],
+        };
+
+        let config_file = path(&ssh.join("config"));
+        assert!(check(workspace.clone(), &config, &config_file, Access::Read).is_ok());
+        assert!(check(workspace, &config, &config_file, Access::Write).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_checked_where_they_point() {
+        let tmp = TempDir::new();
+        let workspace = tmp.mkdir("workspace");
+        let secrets = tmp.mkdir("secrets");
+        std::fs::write(secrets.join("token"), "x").unwrap();
+        std::os::unix::fs::symlink(&secrets, workspace.join("link")).unwrap();
+        std::os::unix::fs::symlink(tmp.0.join("nowhere"), workspace.join("dangling")).unwrap();
+        let config = SandboxConfig::default();
+
+        assert!(check(workspace.clone(), &config, "link/token", Access::Read).is_err());
+        assert!(check(workspace, &config, "dangling/new.txt", Access::Write).is_err());
+    }
+
+    #[test]
+    fn protected_folders_are_only_those_below_the_root() {
+        let root = Path::new("/home/me");
+        assert!(is_protected(root, Path::new("/home/me/.ssh/id_rsa")));
+        assert!(is_protected(root, Path::new("/home/me/.config/gcloud/credentials.db")));
+        assert!(!is_protected(root, Path::new("/home/me/.config/app.toml")));
+        assert!(!is_protected(Path::new("/home/me/.ssh"), Path::new("/home/me/.ssh/config")));
+    }
+}
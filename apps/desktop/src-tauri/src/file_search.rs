@@ -0,0 +1,213 @@
+//! Finding files by name and content.
+//!
+//! `computer_search_files` walks a folder in Rust, so agents don't need
+//! `find`, `grep` or `Select-String` — which differ per platform and go
+//! through the shell policy — to locate files. The walk stays inside the
+//! [file sandbox](crate::file_sandbox): the root must be readable, symlinks
+//! are not followed and protected folders are skipped.
+
+use std::io::{BufRead, Read};
+use std::path::Path;
+
+use globset::{Glob, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::{audit, file_sandbox};
+
+const DEFAULT_MAX_RESULTS: usize = 100;
+const MAX_RESULTS: usize = 1000;
+/// Entries visited before the search gives up.
+const MAX_VISITED: usize = 200_000;
+/// Larger files are not searched for content.
+const MAX_SEARCH_FILE_BYTES: u64 = 10 * 1024 * 1024;
+/// Matched lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 500;
+/// Folders never descended into.
+const SKIP_DIRS: &[&str] = &[".git", "node_modules"];
+
+/// One hit of `computer_search_files`.
+#[derive(Serialize)]
+pub struct SearchMatch {
+    /// Absolute path.
+    pub path: String,
+    /// 1-based line of a content match; `None` when searching by name only.
+    pub line: Option<u64>,
+    /// The matching line (cut to 500 characters).
+    pub text: Option<String>,
+}
+
+/// Result of `computer_search_files`.
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub matches: Vec<SearchMatch>,
+    pub files_scanned: usize,
+    /// The search stopped at `max_results` or the walk limit; more may exist.
+    pub truncated: bool,
+}
+
+/// Matches `glob` against the file name, or the path relative to the root
+/// when it contains a `/`.
+struct NameFilter {
+    matcher: GlobMatcher,
+    whole_path: bool,
+}
+
+impl NameFilter {
+    fn new(glob: &str) -> Result<Self, String> {
+        let matcher = Glob::new(glob)
+            .map_err(|e| format!("invalid glob: {e}"))?
+            .compile_matcher();
+        Ok(Self { matcher, whole_path: glob.contains('/') })
+    }
+
+    fn matches(&self, root: &Path, path: &Path) -> bool {
+        if self.whole_path {
+            let rel = path.strip_prefix(root).unwrap_or(path);
+            self.matcher.is_match(rel.to_string_lossy().replace('\\', "/"))
+        } else {
+            path.file_name().is_some_and(|n| self.matcher.is_match(n))
+        }
+    }
+}
+
+/// Whether the file looks binary (a NUL in its first 8 KB).
+fn is_binary(path: &Path) -> bool {
+    let mut head = [0u8; 8192];
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return true;
+    };
+    let n = file.read(&mut head).unwrap_or(0);
+    head[..n].contains(&0)
+}
+
+/// Appends `path`'s lines matching `re` to `out`, up to `limit` in total.
+fn grep_file(path: &Path, re: &Regex, out: &mut Vec<SearchMatch>, limit: usize) {
+    let Ok(file) = std::fs::File::open(path) else {
+        return;
+    };
+    let mut reader = std::io::BufReader::new(file);
+    let mut line = Vec::new();
+    let mut number = 0u64;
+    while out.len() < limit {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        number += 1;
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\r', '\n']);
+        if re.is_match(text) {
+            out.push(SearchMatch {
+                path: path.to_string_lossy().into_owned(),
+                line: Some(number),
+                text: Some(text.chars().take(MAX_LINE_CHARS).collect()),
+            });
+        }
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Searches the files under `root` (the sandbox workspace by default).
+///
+/// `glob` filters by name (`*.rs`) or, when it contains `/`, by path relative
+/// to `root` (`src/**/*.ts`). `content_regex` returns the matching lines of
+/// text files up to 10 MB instead of just the files; `case_insensitive`
+/// applies to it. Stops after `max_results` hits (default 100, at most
+/// 1000). `.git` and `node_modules` are skipped.
+/// Requires `computer.files` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_search_files(
+    app: AppHandle,
+    root: Option<String>,
+    glob: Option<String>,
+    content_regex: Option<String>,
+    case_insensitive: Option<bool>,
+    max_results: Option<usize>,
+    module: Option<String>,
+) -> Result<SearchResult, String> {
+    let args = serde_json::json!({
+        "root": root,
+        "glob": glob,
+        "content_regex": content_regex,
+        "max_results": max_results,
+    });
+    let result: Result<SearchResult, String> = async move {
+        let limit = max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+        if !(1..=MAX_RESULTS).contains(&limit) {
+            return Err(format!("max_results must be between 1 and {MAX_RESULTS}"));
+        }
+        let names = glob.as_deref().map(str::trim).filter(|g| !g.is_empty()).map(NameFilter::new).transpose()?;
+        let content = content_regex
+            .as_deref()
+            .filter(|r| !r.is_empty())
+            .map(|r| {
+                RegexBuilder::new(r)
+                    .case_insensitive(case_insensitive.unwrap_or(false))
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| format!("invalid content_regex: {e}"))
+            })
+            .transpose()?;
+
+        tokio::task::spawn_blocking(move || {
+            let root = match root.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+                Some(r) => file_sandbox::resolve(&app, r, file_sandbox::Access::Read)?,
+                None => file_sandbox::workspace(&app)?,
+            };
+            if !root.is_dir() {
+                return Err(format!("'{}' is not a folder", root.display()));
+            }
+
+            let mut matches = Vec::new();
+            let mut files_scanned = 0;
+            let mut truncated = false;
+            let walk = WalkDir::new(&root).follow_links(false).into_iter().filter_entry(|e| {
+                let skipped = e.depth() > 0
+                    && e.file_type().is_dir()
+                    && e.file_name().to_str().is_some_and(|n| SKIP_DIRS.contains(&n));
+                !skipped && !file_sandbox::is_protected(&root, e.path())
+            });
+            for (visited, entry) in walk.enumerate() {
+                if visited >= MAX_VISITED || matches.len() >= limit {
+                    truncated = true;
+                    break;
+                }
+                let Ok(entry) = entry else { continue };
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let path = entry.path();
+                if names.as_ref().is_some_and(|n| !n.matches(&root, path)) {
+                    continue;
+                }
+                files_scanned += 1;
+                match &content {
+                    Some(re) => {
+                        let small = entry.metadata().is_ok_and(|m| m.len() <= MAX_SEARCH_FILE_BYTES);
+                        if small && !is_binary(path) {
+                            grep_file(path, re, &mut matches, limit);
+                        }
+                    }
+                    None => matches.push(SearchMatch {
+                        path: path.to_string_lossy().into_owned(),
+                        line: None,
+                        text: None,
+                    }),
+                }
+            }
+            truncated |= matches.len() >= limit;
+            Ok(SearchResult { matches, files_scanned, truncated })
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_search_files", args, module.as_deref(), &result);
+    result
+}
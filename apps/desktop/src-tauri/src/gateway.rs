@@ -0,0 +1,236 @@
+//! Gateway selection and failover for the default tenant.
+//!
+//! The default tenant is served by a prioritized list of gateway URLs:
+//! `CLOUD_GATEWAY_URL` (comma-separated for more than one), or the list set
+//! with `gateway_set_urls` / `gateway_set_url`, which is saved in
+//! `settings.json` and applied at once — no restart needed. A background
+//! monitor probes every URL's `/health` and routes requests to the first
+//! healthy one, so the app fails over when the primary goes down and moves
+//! back once it recovers. Each switch is announced on `gateway:changed`.
+//!
+//! Other tenants (see [`tenants`](crate::tenants)) keep their single URL.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::{settings, shutdown, tray, AppState};
+
+const GATEWAY_URLS_KEY: &str = "gateway_urls";
+const DEFAULT_GATEWAY_URL: &str = "http://localhost:3000";
+const MAX_GATEWAYS: usize = 8;
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One gateway URL and what its last probe found.
+#[derive(Serialize, Clone)]
+pub struct GatewayHealth {
+    pub url: String,
+    /// `None` until probed.
+    pub healthy: Option<bool>,
+}
+
+/// Result of `gateway_status`.
+#[derive(Serialize, Clone)]
+pub struct GatewayStatus {
+    /// URL requests go to.
+    pub active: String,
+    /// In priority order.
+    pub gateways: Vec<GatewayHealth>,
+}
+
+/// Payload of `gateway:changed`.
+#[derive(Serialize, Clone)]
+struct GatewayChanged {
+    url: String,
+    previous: String,
+    /// `failover`, `recovered` or `configured`.
+    reason: &'static str,
+}
+
+struct Pool {
+    gateways: Vec<GatewayHealth>,
+    active: usize,
+}
+
+/// The default tenant's gateways.
+pub struct Gateways(RwLock<Pool>);
+
+impl Gateways {
+    /// From `CLOUD_GATEWAY_URL`; a stored list replaces it at startup.
+    pub fn from_env() -> Self {
+        let urls: Vec<String> = std::env::var("CLOUD_GATEWAY_URL")
+            .unwrap_or_default()
+            .split(',')
+            .map(|u| u.trim().trim_end_matches('/').to_owned())
+            .filter(|u| !u.is_empty())
+            .collect();
+        let urls = if urls.is_empty() { vec![DEFAULT_GATEWAY_URL.to_owned()] } else { urls };
+        Self(RwLock::new(Self::pool(urls)))
+    }
+
+    fn pool(urls: Vec<String>) -> Pool {
+        Pool {
+            gateways: urls.into_iter().map(|url| GatewayHealth { url, healthy: None }).collect(),
+            active: 0,
+        }
+    }
+
+    /// URL of the gateway requests currently go to.
+    pub fn active(&self) -> String {
+        let pool = self.0.read().unwrap_or_else(|e| e.into_inner());
+        pool.gateways[pool.active].url.clone()
+    }
+
+    pub fn status(&self) -> GatewayStatus {
+        let pool = self.0.read().unwrap_or_else(|e| e.into_inner());
+        GatewayStatus { active: pool.gateways[pool.active].url.clone(), gateways: pool.gateways.clone() }
+    }
+
+    fn urls(&self) -> Vec<String> {
+        let pool = self.0.read().unwrap_or_else(|e| e.into_inner());
+        pool.gateways.iter().map(|g| g.url.clone()).collect()
+    }
+
+    /// Replaces the list, starting on its first URL. Returns the previous
+    /// active URL.
+    fn replace(&self, urls: Vec<String>) -> String {
+        let mut pool = self.0.write().unwrap_or_else(|e| e.into_inner());
+        let previous = pool.gateways[pool.active].url.clone();
+        *pool = Self::pool(urls);
+        previous
+    }
+
+    /// Records probe results (ignored if the list changed meanwhile) and
+    /// switches to the first healthy gateway. Returns the switch, if any.
+    fn apply_probe(&self, urls: &[String], healthy: &[bool]) -> Option<GatewayChanged> {
+        let mut pool = self.0.write().unwrap_or_else(|e| e.into_inner());
+        if pool.gateways.iter().map(|g| &g.url).ne(urls.iter()) {
+            return None;
+        }
+        for (g, ok) in pool.gateways.iter_mut().zip(healthy) {
+            g.healthy = Some(*ok);
+        }
+        // With none healthy, stay put rather than flap.
+        let best = healthy.iter().position(|ok| *ok)?;
+        if best == pool.active {
+            return None;
+        }
+        let previous = pool.gateways[pool.active].url.clone();
+        let reason = if best < pool.active { "recovered" } else { "failover" };
+        pool.active = best;
+        Some(GatewayChanged { url: pool.gateways[best].url.clone(), previous, reason })
+    }
+}
+
+fn normalize_urls(urls: &[String]) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = Vec::new();
+    for raw in urls.iter().filter(|u| !u.trim().is_empty()) {
+        let url = settings::normalize_base_url(raw)?;
+        if !out.contains(&url) {
+            out.push(url);
+        }
+    }
+    if out.is_empty() {
+        return Err("at least one gateway URL is required".into());
+    }
+    if out.len() > MAX_GATEWAYS {
+        return Err(format!("too many gateway URLs (max {MAX_GATEWAYS})"));
+    }
+    Ok(out)
+}
+
+fn load_stored(app: &AppHandle) -> Option<Vec<String>> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()?
+        .get(GATEWAY_URLS_KEY)
+        .and_then(|v| serde_json::from_value::<Vec<String>>(v).ok())
+        .filter(|urls| !urls.is_empty())
+}
+
+async fn probe(http: &reqwest::Client, url: &str) -> bool {
+    http.get(format!("{url}/health"))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|r| r.status().is_success())
+}
+
+async fn probe_all(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let urls = state.gateways.urls();
+    let healthy = futures_util::future::join_all(urls.iter().map(|u| probe(&state.http_client, u))).await;
+    if let Some(change) = state.gateways.apply_probe(&urls, &healthy) {
+        let _ = app.emit("gateway:changed", change);
+    }
+    tray::update_health(app, &state.gateways.status());
+}
+
+/// Applies the stored gateway list and starts the health monitor. Call once
+/// at startup.
+pub fn start(app: &AppHandle) {
+    if let Some(urls) = load_stored(app) {
+        app.state::<AppState>().gateways.replace(urls);
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while !shutdown::is_shutting_down() {
+            probe_all(&app).await;
+            tokio::select! {
+                _ = tokio::time::sleep(PROBE_INTERVAL) => {}
+                _ = shutdown::cancelled() => break,
+            }
+        }
+    });
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the active gateway and the health of each configured one.
+#[tauri::command]
+pub fn gateway_status(state: State<'_, AppState>) -> GatewayStatus {
+    state.gateways.status()
+}
+
+/// Replaces the default tenant's gateways with `urls`, in priority order,
+/// and switches to the first at once; saved across restarts.
+#[tauri::command]
+pub async fn gateway_set_urls(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    urls: Vec<String>,
+) -> Result<GatewayStatus, String> {
+    let urls = normalize_urls(&urls)?;
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    store.set(GATEWAY_URLS_KEY, serde_json::json!(urls));
+    store.save().map_err(|e| format!("failed to save settings: {e}"))?;
+
+    let url = urls[0].clone();
+    let previous = state.gateways.replace(urls);
+    if previous != url {
+        let _ = app.emit("gateway:changed", GatewayChanged { url, previous, reason: "configured" });
+    }
+    probe_all(&app).await;
+    Ok(state.gateways.status())
+}
+
+/// Switches the default tenant to gateway `url` without a restart, keeping
+/// the other configured gateways as fallbacks.
+#[tauri::command]
+pub async fn gateway_set_url(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<GatewayStatus, String> {
+    let url = settings::normalize_base_url(&url)?;
+    let urls: Vec<String> = std::iter::once(url.clone())
+        .chain(state.gateways.urls().into_iter().filter(|u| *u != url))
+        .take(MAX_GATEWAYS)
+        .collect();
+    gateway_set_urls(app, state, urls).await
+}
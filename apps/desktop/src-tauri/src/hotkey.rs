@@ -0,0 +1,177 @@
+//! System-wide hotkeys.
+//!
+//! Hotkeys are registered with the OS through tauri-plugin-global-shortcut —
+//! `RegisterHotKey` on Windows, Carbon hot keys on macOS and `XGrabKey` on
+//! X11 — so they fire without polling the keyboard and without macOS's
+//! Input Monitoring permission. Wayland has no global shortcut API, so there
+//! registration fails unless XWayland provides a display.
+//!
+//! Hotkeys are written like Tauri accelerators, e.g. `CmdOrCtrl+Shift+A`:
+//! one or more modifiers plus a key. The modifiers must match exactly, so
+//! `Ctrl+Shift+A` doesn't fire on `Ctrl+Alt+Shift+A`.
+//!
+//! The outcome of each hotkey's last registration is kept for
+//! `hotkeys_status`, and a failure is emitted as `hotkeys:error`, so the
+//! settings UI can show why a hotkey does nothing — also for failures at
+//! startup, before any window listened.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey(Shortcut);
+
+impl Hotkey {
+    pub fn parse(accelerator: &str) -> Result<Self, String> {
+        // The plugin doesn't know these aliases of Super.
+        let parts: Vec<&str> = accelerator
+            .split('+')
+            .map(str::trim)
+            .map(|part| match part.to_ascii_lowercase().as_str() {
+                "meta" | "win" => "Super",
+                _ => part,
+            })
+            .collect();
+        let shortcut: Shortcut = parts
+            .join("+")
+            .parse()
+            .map_err(|e| format!("invalid hotkey '{accelerator}': {e}"))?;
+        if shortcut.mods.is_empty() {
+            return Err(format!("hotkey '{accelerator}' needs at least one modifier"));
+        }
+        Ok(Self(shortcut))
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mods = self.0.mods;
+        for (m, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::SUPER, "Super"),
+        ] {
+            if mods.contains(m) {
+                write!(f, "{name}+")?;
+            }
+        }
+        let key = self.0.key.to_string();
+        let key = key.strip_prefix("Key").or_else(|| key.strip_prefix("Digit")).unwrap_or(&key);
+        f.write_str(key)
+    }
+}
+
+// ── Registration ───────────────────────────────────────────────────────────────
+
+/// A hotkey registered with the OS until dropped.
+pub struct HotkeyRegistration {
+    app: AppHandle,
+    shortcut: Shortcut,
+}
+
+impl Drop for HotkeyRegistration {
+    fn drop(&mut self) {
+        let _ = self.app.global_shortcut().unregister(self.shortcut);
+    }
+}
+
+/// Last registration of one hotkey, as returned by `hotkeys_status` and
+/// emitted on `hotkeys:error`.
+#[derive(Serialize, Clone)]
+pub struct HotkeyStatus {
+    /// `quick_chat`, `capture_ask` or `emergency_stop`.
+    pub name: &'static str,
+    pub hotkey: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Registration outcomes of the enabled hotkeys, by name.
+#[derive(Default)]
+pub struct HotkeyStatuses(Mutex<BTreeMap<&'static str, HotkeyStatus>>);
+
+impl HotkeyStatuses {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<&'static str, HotkeyStatus>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Registers `accelerator` as hotkey `name`; `on_press` runs on the event
+/// loop thread, so it should hand off anything slow. The outcome is
+/// recorded for `hotkeys_status`, and a failure emitted as `hotkeys:error`.
+pub fn register(
+    app: &AppHandle,
+    name: &'static str,
+    accelerator: &str,
+    on_press: impl Fn() + Send + Sync + 'static,
+) -> Result<HotkeyRegistration, String> {
+    let result = Hotkey::parse(accelerator).and_then(|hotkey| {
+        app.global_shortcut()
+            .on_shortcut(hotkey.0, move |_, _, event| {
+                if event.state == ShortcutState::Pressed {
+                    on_press();
+                }
+            })
+            .map_err(|e| format!("can't register hotkey {hotkey}: {e}"))?;
+        Ok(HotkeyRegistration { app: app.clone(), shortcut: hotkey.0 })
+    });
+    let status = HotkeyStatus {
+        name,
+        hotkey: accelerator.to_owned(),
+        registered: result.is_ok(),
+        error: result.as_ref().err().cloned(),
+    };
+    if !status.registered {
+        let _ = app.emit("hotkeys:error", status.clone());
+    }
+    app.state::<HotkeyStatuses>().lock().insert(name, status);
+    result
+}
+
+/// Forgets hotkey `name`'s status, once it is disabled.
+pub fn clear(app: &AppHandle, name: &str) {
+    app.state::<HotkeyStatuses>().lock().remove(name);
+}
+
+/// Whether each enabled hotkey is registered, and why not.
+#[tauri::command]
+pub fn hotkeys_status(app: AppHandle) -> Vec<HotkeyStatus> {
+    app.state::<HotkeyStatuses>().lock().values().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(accelerator: &str) -> String {
+        Hotkey::parse(accelerator).unwrap().to_string()
+    }
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        assert_eq!(parsed("Ctrl+Shift+A"), "Ctrl+Shift+A");
+        assert_eq!(parsed(" alt + shift + 1 "), "Alt+Shift+1");
+        assert_eq!(parsed("Control+Alt+Space"), "Ctrl+Alt+Space");
+        assert_eq!(parsed("Shift+Ctrl+F5"), "Ctrl+Shift+F5");
+    }
+
+    #[test]
+    fn meta_and_win_mean_super() {
+        assert_eq!(parsed("Meta+K"), "Super+K");
+        assert_eq!(parsed("Win+Shift+K"), "Shift+Super+K");
+        assert_eq!(Hotkey::parse("Super+K"), Hotkey::parse("meta+k"));
+    }
+
+    #[test]
+    fn rejects_bare_keys_and_unknown_names() {
+        assert!(Hotkey::parse("A").unwrap_err().contains("needs at least one modifier"));
+        assert!(Hotkey::parse("Ctrl+Nope").unwrap_err().starts_with("invalid hotkey"));
+        assert!(Hotkey::parse("").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Response-language setting.
+//!
+//! The user picks an output language once (Settings → Language) and every
+//! `chat_send` / `ai_generate` call injects a system directive for it. Models
+//! still drift back to English on short or technical prompts, so the output is
+//! checked with a lightweight script/stop-word detector and retried once with
+//! a stricter directive when it is clearly in the wrong language.
+
+use tauri::AppHandle;
+
+use crate::settings;
+
+const RESPONSE_LANGUAGE_KEY: &str = "response_language";
+
+/// Selectable output languages as `(code, English name)`.
+pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("th", "Thai"),
+    ("ru", "Russian"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("es", "Spanish"),
+];
+
+/// Below this many letters the detector doesn't guess.
+const MIN_LETTERS_FOR_DETECTION: usize = 20;
+
+// ── Store helpers ──────────────────────────────────────────────────────────────
+
+/// Normalises `vi-VN` / `VI` to `vi` and rejects unsupported languages.
+pub fn normalize(code: &str) -> Result<&'static str, String> {
+    let primary = code.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+    SUPPORTED_LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == primary)
+        .map(|(c, _)| *c)
+        .ok_or_else(|| {
+            let codes: Vec<&str> = SUPPORTED_LANGUAGES.iter().map(|(c, _)| *c).collect();
+            format!("unsupported response language '{code}' (supported: {})", codes.join(", "))
+        })
+}
+
+fn language_name(code: &str) -> &'static str {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, n)| *n)
+        .unwrap_or("English")
+}
+
+/// Returns the configured response language, or `None` to let the model decide.
+pub fn load_response_language(app: &AppHandle) -> Option<&'static str> {
+    normalize(&settings::get_string(app, RESPONSE_LANGUAGE_KEY)?).ok()
+}
+
+// ── Directives ─────────────────────────────────────────────────────────────────
+
+/// System directive injected into every provider call.
+pub fn directive(code: &str) -> String {
+    let name = language_name(code);
+    format!(
+        "Always respond in {name}, regardless of the language of the user's message, \
+         unless the user explicitly asks for a different language. \
+         Keep code, identifiers and quoted text unchanged."
+    )
+}
+
+/// Stricter directive used for the single retry after a wrong-language reply.
+pub fn strict_directive(code: &str) -> String {
+    let name = language_name(code);
+    format!(
+        "{} Your previous reply was not in {name}. Write the entire reply in {name}.",
+        directive(code)
+    )
+}
+
+// ── Detection ──────────────────────────────────────────────────────────────────
+
+/// Returns `false` only when `text` is confidently detected as a language
+/// other than `code`. Short, mixed, or structured (JSON) output passes.
+pub fn matches(code: &str, text: &str) -> bool {
+    if crate::partial_json::looks_like_json(text) {
+        return true;
+    }
+    match detect(text) {
+        Some(detected) => detected == code,
+        None => true,
+    }
+}
+
+/// Best-effort language detection by script, then by stop words for
+/// Latin-script languages. Fenced code blocks are ignored.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let prose = strip_code_blocks(text);
+
+    let (mut latin, mut vietnamese, mut han, mut kana, mut hangul, mut cyrillic, mut thai) =
+        (0usize, 0usize, 0usize, 0usize, 0usize, 0usize, 0usize);
+    for c in prose.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' => latin += 1,
+            'đ' | 'Đ' | 'ơ' | 'Ơ' | 'ư' | 'Ư' | 'ă' | 'Ă' | '\u{1EA0}'..='\u{1EF9}' => {
+                latin += 1;
+                vietnamese += 1;
+            }
+            '\u{00C0}'..='\u{024F}' => latin += 1,
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => hangul += 1,
+            '\u{0400}'..='\u{04FF}' => cyrillic += 1,
+            '\u{0E00}'..='\u{0E7F}' => thai += 1,
+            _ => {}
+        }
+    }
+
+    let total = latin + han + kana + hangul + cyrillic + thai;
+    if total < MIN_LETTERS_FOR_DETECTION {
+        return None;
+    }
+
+    // A script wins when it covers most of the letters.
+    let dominant = |n: usize| n * 2 > total;
+    if kana * 10 > total && dominant(kana + han) {
+        return Some("ja");
+    }
+    if dominant(han) {
+        return Some("zh");
+    }
+    if dominant(hangul) {
+        return Some("ko");
+    }
+    if dominant(cyrillic) {
+        return Some("ru");
+    }
+    if dominant(thai) {
+        return Some("th");
+    }
+    if !dominant(latin) {
+        return None;
+    }
+    // Vietnamese marks nearly every syllable; a few percent is conclusive.
+    if vietnamese * 30 > latin {
+        return Some("vi");
+    }
+    detect_latin(&prose)
+}
+
+const STOP_WORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "of", "to", "with", "that", "this", "you", "for"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "une", "pour", "avec", "que", "vous"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "mit", "ein", "eine", "sie", "für"]),
+    ("es", &["el", "los", "las", "y", "es", "del", "una", "para", "con", "que", "por"]),
+];
+
+/// Picks the Latin-script language with clearly the most stop-word hits.
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut scores: Vec<(&'static str, usize)> = STOP_WORDS
+        .iter()
+        .map(|(code, list)| (*code, words.iter().filter(|w| list.contains(&w.as_str())).count()))
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+
+    let (best, best_hits) = scores[0];
+    let runner_up = scores[1].1;
+    (best_hits >= 3 && best_hits >= runner_up * 2).then_some(best)
+}
+
+fn strip_code_blocks(text: &str) -> String {
+    text.split("```")
+        .step_by(2)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the configured response language code, or `null` when unset.
+#[tauri::command]
+pub async fn settings_get_response_language(app: AppHandle) -> Option<String> {
+    load_response_language(&app).map(String::from)
+}
+
+/// Sets (or with `null`, clears) the response language applied to every
+/// `chat_send` / `ai_generate` call. Accepts codes such as `vi` or `vi-VN`.
+#[tauri::command]
+pub async fn settings_set_response_language(
+    app: AppHandle,
+    language: Option<String>,
+) -> Result<(), String> {
+    let code = match language.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        Some(code) => Some(normalize(code)?),
+        None => None,
+    };
+    settings::set_string(&app, RESPONSE_LANGUAGE_KEY, code)
+}
@@ -1,4 +1,44 @@
 // lib.rs — re-exports the Tauri command modules for use as a library crate.
 // Required by Cargo.toml's [lib] section (used by tauri-build for mobile targets).
 
+pub mod agent_bundle;
+pub mod approval;
+pub mod audit;
+pub mod bedrock;
+pub mod browser;
+pub mod budget;
+pub mod chat_sessions;
 pub mod computer;
+pub mod desktops;
+pub mod file_drop;
+pub mod file_sandbox;
+pub mod file_search;
+pub mod language;
+pub mod live_view;
+pub mod local_openai;
+pub mod memory_context;
+pub mod memory_transfer;
+pub mod ocr;
+pub mod partial_json;
+pub mod permissions;
+pub mod processes;
+pub mod provider_keys;
+pub mod providers;
+pub mod redact;
+pub mod resources;
+pub mod response_format;
+pub mod retry;
+pub mod secrets;
+pub mod session;
+pub mod settings;
+pub mod shell_policy;
+pub mod shutdown_signal;
+pub mod stream;
+pub mod stream_usage;
+pub mod system;
+pub mod terminal;
+pub mod text_input;
+pub mod tool_calls;
+pub mod usage_ledger;
+pub mod vision;
+pub mod web;
@@ -0,0 +1,307 @@
+//! Live "what the agent sees" screen view.
+//!
+//! `computer_view_start` captures the primary screen (or a region of it, e.g.
+//! a window's bounds) at a low frame rate, downscales each frame and sends
+//! only the tiles that changed since the previous one, JPEG-encoded. A still
+//! screen costs nothing; a full keyframe is sent on start, when most of the
+//! screen changed, and periodically so late subscribers can catch up.
+//!
+//! # Events
+//! - `computer:view-frame:{id}` — a [`ViewFrame`]; draw its tiles over the
+//!   previous frame (a keyframe's single tile covers the whole frame).
+//! - `computer:view-end:{id}` — `{ error }` once the view stops, with the
+//!   capture error if it stopped on its own.
+//!
+//! Requires Screen Recording permission on macOS.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use image::imageops::FilterType;
+use image::RgbaImage;
+use screenshots::Screen;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::resources;
+
+const DEFAULT_FPS: f64 = 2.0;
+const MAX_FPS: f64 = 10.0;
+const MIN_FPS: f64 = 0.2;
+
+/// Frames are downscaled to at most this width unless the caller asks for
+/// another (clamped to `MIN_WIDTH..=MAX_WIDTH`).
+const DEFAULT_MAX_WIDTH: u32 = 960;
+const MIN_WIDTH: u32 = 160;
+const MAX_WIDTH: u32 = 1920;
+
+/// Side of the square tiles compared between frames, in downscaled pixels.
+const TILE: u32 = 64;
+
+/// A keyframe replaces the tile diff when more than this share of tiles changed.
+const KEYFRAME_CHANGED_RATIO: f64 = 0.5;
+
+/// A keyframe is sent at least this often.
+const KEYFRAME_INTERVAL: Duration = Duration::from_secs(10);
+
+const JPEG_QUALITY: u8 = 60;
+
+/// Maximum number of concurrently running views.
+const MAX_VIEWS: usize = 4;
+
+// ── State ──────────────────────────────────────────────────────────────────────
+
+/// Running views keyed by view ID (the flag is cleared to stop one), managed
+/// via `tauri::Builder::manage`.
+#[derive(Default)]
+pub struct LiveViews {
+    views: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl LiveViews {
+    /// Stops every running view; returns how many there were.
+    pub fn stop_all(&self) -> usize {
+        let Ok(mut map) = self.views.lock() else {
+            return 0;
+        };
+        let running = map.values().filter(|f| f.swap(false, Ordering::Relaxed)).count();
+        map.clear();
+        running
+    }
+}
+
+// ── Request / response types ───────────────────────────────────────────────────
+
+/// Screen area to stream, in physical pixels, top-left origin.
+#[derive(Deserialize, Clone, Copy)]
+pub struct ViewRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Handle returned when a view starts.
+#[derive(Serialize)]
+pub struct LiveViewInfo {
+    pub id: String,
+    pub fps: f64,
+}
+
+/// One changed rectangle of a frame.
+#[derive(Serialize, Clone)]
+pub struct ViewTile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// `data:image/jpeg;base64,…`
+    pub data_uri: String,
+}
+
+/// Payload of `computer:view-frame:{id}`.
+#[derive(Serialize, Clone)]
+pub struct ViewFrame {
+    pub seq: u64,
+    pub keyframe: bool,
+    /// Downscaled frame size; tile coordinates are in this space.
+    pub width: u32,
+    pub height: u32,
+    pub tiles: Vec<ViewTile>,
+}
+
+#[derive(Serialize, Clone)]
+struct ViewEnd {
+    error: Option<String>,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+fn capture(region: Option<ViewRegion>) -> Result<RgbaImage, String> {
+    let screens = Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
+    let screen = screens.into_iter().next().ok_or("no screens found")?;
+    match region {
+        Some(r) => screen
+            .capture_area(r.x, r.y, r.width, r.height)
+            .map_err(|e| format!("region capture failed: {e}")),
+        None => screen.capture().map_err(|e| format!("capture failed: {e}")),
+    }
+}
+
+/// Physical size of the primary screen.
+fn screen_size() -> Result<(u32, u32), String> {
+    let screens = Screen::all().map_err(|e| format!("screen capture unavailable: {e}"))?;
+    let info = screens.first().ok_or("no screens found")?.display_info;
+    let scale = info.scale_factor.max(1.0);
+    Ok(((info.width as f32 * scale) as u32, (info.height as f32 * scale) as u32))
+}
+
+fn downscale(img: RgbaImage, max_width: u32) -> RgbaImage {
+    if img.width() <= max_width {
+        return img;
+    }
+    let height = ((img.height() as u64 * max_width as u64) / img.width() as u64).max(1) as u32;
+    image::imageops::resize(&img, max_width, height, FilterType::Triangle)
+}
+
+fn encode_tile(frame: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> Result<ViewTile, String> {
+    let view = image::imageops::crop_imm(frame, x, y, width, height).to_image();
+    let rgb: Vec<u8> = view.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode(&rgb, width, height, image::ColorType::Rgb8)
+        .map_err(|e| format!("jpeg encode failed: {e}"))?;
+    Ok(ViewTile {
+        x,
+        y,
+        width,
+        height,
+        data_uri: format!("data:image/jpeg;base64,{}", B64.encode(&jpeg)),
+    })
+}
+
+fn tile_changed(prev: &RgbaImage, next: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> bool {
+    (y..y + height).any(|row| {
+        let start = ((row * next.width() + x) * 4) as usize;
+        let end = start + (width * 4) as usize;
+        prev.as_raw()[start..end] != next.as_raw()[start..end]
+    })
+}
+
+/// Changed tile rectangles of `next` relative to `prev` (same size), plus the
+/// total tile count.
+fn changed_tiles(prev: &RgbaImage, next: &RgbaImage) -> (Vec<(u32, u32, u32, u32)>, usize) {
+    let mut changed = Vec::new();
+    let mut total = 0;
+    for y in (0..next.height()).step_by(TILE as usize) {
+        for x in (0..next.width()).step_by(TILE as usize) {
+            let w = TILE.min(next.width() - x);
+            let h = TILE.min(next.height() - y);
+            total += 1;
+            if tile_changed(prev, next, x, y, w, h) {
+                changed.push((x, y, w, h));
+            }
+        }
+    }
+    (changed, total)
+}
+
+/// Captures and emits frames until `running` is cleared or capture fails.
+fn run_view(
+    app: &AppHandle,
+    id: &str,
+    running: &AtomicBool,
+    interval: Duration,
+    max_width: u32,
+    region: Option<ViewRegion>,
+) -> Result<(), String> {
+    let mut prev: Option<RgbaImage> = None;
+    let mut last_keyframe = Instant::now();
+    let mut seq = 0u64;
+
+    while running.load(Ordering::Relaxed) {
+        let started = Instant::now();
+        let frame = downscale(capture(region)?, max_width);
+        let (width, height) = frame.dimensions();
+
+        let diff = match &prev {
+            Some(p) if p.dimensions() == frame.dimensions() => Some(changed_tiles(p, &frame)),
+            _ => None,
+        };
+        let keyframe = match &diff {
+            None => true,
+            Some((changed, total)) => {
+                last_keyframe.elapsed() >= KEYFRAME_INTERVAL
+                    || changed.len() as f64 > *total as f64 * KEYFRAME_CHANGED_RATIO
+            }
+        };
+
+        let tiles = if keyframe {
+            last_keyframe = Instant::now();
+            vec![encode_tile(&frame, 0, 0, width, height)?]
+        } else {
+            diff.map(|(changed, _)| changed)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(x, y, w, h)| encode_tile(&frame, x, y, w, h))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        if !tiles.is_empty() {
+            let _ = app.emit(
+                &format!("computer:view-frame:{id}"),
+                ViewFrame { seq, keyframe, width, height, tiles },
+            );
+            seq += 1;
+        }
+        prev = Some(frame);
+
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Starts streaming the screen (or `region`) at `fps` frames per second
+/// (default 2, max 10), downscaled to at most `max_width` pixels wide
+/// (default 960). Returns the view ID used in event names.
+#[tauri::command]
+pub async fn computer_view_start(
+    app: AppHandle,
+    views: State<'_, LiveViews>,
+    fps: Option<f64>,
+    max_width: Option<u32>,
+    region: Option<ViewRegion>,
+) -> Result<LiveViewInfo, String> {
+    let fps = fps.unwrap_or(DEFAULT_FPS);
+    if !fps.is_finite() || !(MIN_FPS..=MAX_FPS).contains(&fps) {
+        return Err(format!("fps must be between {MIN_FPS} and {MAX_FPS} (got {fps})"));
+    }
+    if region.is_some_and(|r| r.width == 0 || r.height == 0) {
+        return Err("region must not be empty".into());
+    }
+    let max_width = max_width.unwrap_or(DEFAULT_MAX_WIDTH).clamp(MIN_WIDTH, MAX_WIDTH);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let mut map = views.views.lock().map_err(|_| "view registry poisoned")?;
+        map.retain(|_, flag| flag.load(Ordering::Relaxed));
+        if map.len() >= MAX_VIEWS {
+            return Err(format!("too many live views (max {MAX_VIEWS})"));
+        }
+        map.insert(id.clone(), Arc::clone(&running));
+    }
+
+    // A full-resolution capture plus the previous and current downscaled frames.
+    let (width, height) = match region {
+        Some(r) => (r.width, r.height),
+        None => screen_size()?,
+    };
+    resources::ensure_memory(&app, resources::capture_memory_estimate(width, height) * 2)
+        .inspect_err(|_| running.store(false, Ordering::Relaxed))?;
+
+    let interval = Duration::from_secs_f64(1.0 / fps);
+    let thread_id = id.clone();
+    std::thread::spawn(move || {
+        let error = run_view(&app, &thread_id, &running, interval, max_width, region).err();
+        running.store(false, Ordering::Relaxed);
+        let _ = app.emit(&format!("computer:view-end:{thread_id}"), ViewEnd { error });
+    });
+
+    Ok(LiveViewInfo { id, fps })
+}
+
+/// Stops a live view. Unknown or already-stopped IDs are ignored.
+#[tauri::command]
+pub async fn computer_view_stop(views: State<'_, LiveViews>, id: String) -> Result<(), String> {
+    let mut map = views.views.lock().map_err(|_| "view registry poisoned")?;
+    if let Some(flag) = map.remove(&id) {
+        flag.store(false, Ordering::Relaxed);
+    }
+    Ok(())
+}
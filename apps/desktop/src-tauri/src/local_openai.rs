@@ -0,0 +1,54 @@
+//! Detection of a local OpenAI-compatible server (LM Studio, llama.cpp's
+//! `llama-server`) for the `local-openai` provider.
+//!
+//! The server is found by probing `GET /v1/models` on the ports those tools
+//! listen on by default; no API key is needed.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Probed in order: LM Studio, then llama.cpp.
+pub const PROBE_PORTS: [u16; 2] = [1234, 8080];
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// A detected server.
+#[derive(Serialize, Clone)]
+pub struct LocalServer {
+    /// Base URL up to (not including) `/chat/completions`.
+    pub base_url: String,
+    pub port: u16,
+    /// Model IDs the server reports, in its order.
+    pub models: Vec<String>,
+}
+
+async fn probe(http: &reqwest::Client, port: u16) -> Option<LocalServer> {
+    let base_url = format!("http://127.0.0.1:{port}/v1");
+    let resp = http
+        .get(format!("{base_url}/models"))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()
+        .filter(|r| r.status().is_success())?;
+    let val: serde_json::Value = resp.json().await.ok()?;
+    // Anything answering with an OpenAI model list counts, even an empty one.
+    let models = val
+        .get("data")?
+        .as_array()?
+        .iter()
+        .filter_map(|m| m.get("id")?.as_str().map(String::from))
+        .collect();
+    Some(LocalServer { base_url, port, models })
+}
+
+/// Returns the first server answering on [`PROBE_PORTS`].
+pub async fn detect(http: &reqwest::Client) -> Option<LocalServer> {
+    for port in PROBE_PORTS {
+        if let Some(server) = probe(http, port).await {
+            return Some(server);
+        }
+    }
+    None
+}
@@ -1,22 +1,96 @@
 // Prevents an extra console window on Windows in release builds.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agent_bundle;
+mod agent_run;
+mod app_settings;
+mod approval;
+mod audit;
+mod bedrock;
+mod browser;
+mod budget;
+mod capture_ask;
+mod chat_sessions;
 mod computer;
+mod context_fallback;
+mod deep_link;
+mod desktops;
+mod emergency_stop;
+mod estimate;
+mod file_drop;
+mod file_sandbox;
+mod file_search;
+mod gateway;
+mod hotkey;
+mod language;
+mod live_view;
+mod local_openai;
+mod memory_context;
+mod memory_transfer;
+mod models;
+mod oauth;
+mod ocr;
+mod partial_json;
+mod permissions;
+mod processes;
+mod provider_keys;
+mod providers;
+mod quick_chat;
+mod redact;
+mod request_queue;
+mod resources;
+mod response_format;
+mod retry;
+mod secrets;
+mod session;
+mod settings;
+mod shell_policy;
+mod shutdown;
+mod shutdown_signal;
+mod skills;
+mod speech;
+mod stream;
+mod stream_usage;
+mod system;
+mod tenants;
+mod terminal;
+mod text_input;
+mod tool_calls;
+mod tray;
+mod updater;
+mod usage_ledger;
+mod vision;
+mod web;
 
 use std::collections::HashMap;
 
-use futures_util::StreamExt;
+use agenthub_runtime::calculator;
+use context_fallback::ContextFallback;
+use providers::{
+    append_system, call_provider_generate, call_provider_generate_in_language, call_provider_stream_in_language,
+    call_provider_stream_with_tools, direct_key, gateway_post, gateway_stream, validate_messages, CallUsage,
+    ChatMessage, GenerationParams, ProviderCall, LOCAL_OPENAI,
+};
+use request_queue::QueueGate;
+use response_format::ResponseFormat;
+use stream::StreamSink;
+use stream_usage::StreamUsage;
+use tool_calls::{PendingToolTurn, PendingToolTurns, ToolCall, ToolResult, ToolRound, ToolSpec};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_store::StoreExt;
 
 // ── Application state ──────────────────────────────────────────────────────────
 
-/// Shared, immutable application state injected via `tauri::Builder::manage`.
+/// Shared application state injected via `tauri::Builder::manage`.
 struct AppState {
-    gateway_url: String,
+    /// Gateways of the default tenant (see [`gateway`]); see [`tenants`] for
+    /// the others.
+    gateways: gateway::Gateways,
     /// `reqwest::Client` is cheaply cloneable and internally thread-safe.
     http_client: reqwest::Client,
+    /// Paces direct provider calls (see [`request_queue`]).
+    requests: request_queue::RequestQueue,
 }
 
 // ── Credential store helpers ───────────────────────────────────────────────────
@@ -24,393 +98,264 @@ struct AppState {
 const CRED_STORE: &str = "credentials.json";
 const TOKEN_KEY: &str = "access_token";
 
-fn load_token(app: &AppHandle) -> Option<String> {
-    app.store(CRED_STORE)
-        .ok()?
-        .get(TOKEN_KEY)?
-        .as_str()
-        .map(String::from)
+/// Credential-store key of a tenant's token. The default tenant keeps the
+/// original key so tokens saved before tenants existed still work.
+fn token_key(tenant: &str) -> String {
+    if tenant == tenants::DEFAULT_TENANT {
+        TOKEN_KEY.to_owned()
+    } else {
+        format!("{TOKEN_KEY}:{tenant}")
+    }
 }
 
-fn save_token(app: &AppHandle, token: &str) {
-    if let Ok(store) = app.store(CRED_STORE) {
-        store.set(TOKEN_KEY, serde_json::Value::String(token.to_owned()));
-        let _ = store.save();
-    }
+fn load_stored_token(app: &AppHandle, key: &str) -> Option<String> {
+    app.store(CRED_STORE).ok()?.get(key)?.as_str().map(String::from)
 }
 
-fn delete_token(app: &AppHandle) {
+fn delete_stored_token(app: &AppHandle, key: &str) {
     if let Ok(store) = app.store(CRED_STORE) {
-        store.delete(TOKEN_KEY);
-        let _ = store.save();
+        if store.delete(key) {
+            let _ = store.save();
+        }
     }
 }
 
-// ── SSE pipe helpers ───────────────────────────────────────────────────────────
-
-/// Maximum total output length accepted from the SSE stream (4 MB).
-const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
-
-/// Maximum line buffer size — a single SSE chunk should never exceed this (64 KB).
-const MAX_LINE_BYTES: usize = 64 * 1024;
-
-/// Reads an SSE `text/event-stream` response line-by-line, emitting each
-/// `data:` value as a Tauri event. Returns the full concatenated output.
-/// Used for the cloud gateway path (raw passthrough of `data:` lines).
-async fn pipe_sse(
-    app: &AppHandle,
-    resp: reqwest::Response,
-    event_name: &str,
-) -> Result<String, String> {
-    let mut full = String::new();
-    let mut buf = String::new();
-    let mut stream = resp.bytes_stream();
-
-    while let Some(item) = stream.next().await {
-        let bytes = item.map_err(|_| "stream read error".to_string())?;
-
-        if buf.len() + bytes.len() > MAX_LINE_BYTES {
-            return Err("SSE line buffer exceeded maximum size".to_string());
-        }
-        buf.push_str(&String::from_utf8_lossy(&bytes));
-
-        loop {
-            if let Some(nl) = buf.find('\n') {
-                let line = buf[..nl].trim_end_matches('\r').to_owned();
-                buf = buf[nl + 1..].to_owned();
-
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if data == "[DONE]" {
-                        return Ok(full);
-                    }
-                    if full.len() + data.len() > MAX_OUTPUT_BYTES {
-                        return Err("SSE output exceeded maximum allowed size".to_string());
-                    }
-                    full.push_str(data);
-                    let _ = app.emit(event_name, data);
-                }
-            } else {
-                break;
+/// Tokens live in the OS keychain. A token still in `credentials.json` (from
+/// before the keychain, or saved while it was unavailable) is moved over on
+/// first read; without a keychain the JSON store keeps serving it.
+fn load_secret(app: &AppHandle, key: &str) -> Option<String> {
+    match secrets::get(key) {
+        Ok(Some(token)) => Some(token),
+        Ok(None) => {
+            let token = load_stored_token(app, key)?;
+            if secrets::set(key, &token).is_ok() {
+                delete_stored_token(app, key);
             }
+            Some(token)
         }
+        Err(_) => load_stored_token(app, key),
     }
-
-    Ok(full)
 }
 
-/// Reads an SSE stream, applies `extract_fn` to each `data:` line, emits the
-/// extracted text chunk as a Tauri event, and returns the full concatenated output.
-/// Used for the direct provider path so only the text content is forwarded.
-async fn pipe_provider_sse<F>(
-    app: &AppHandle,
-    resp: reqwest::Response,
-    event_name: &str,
-    extract_fn: F,
-) -> Result<String, String>
-where
-    F: Fn(&str) -> Option<String>,
-{
-    let mut full = String::new();
-    let mut buf = String::new();
-    let mut stream = resp.bytes_stream();
-
-    while let Some(item) = stream.next().await {
-        let bytes = item.map_err(|_| "stream read error".to_string())?;
-
-        if buf.len() + bytes.len() > MAX_LINE_BYTES {
-            return Err("SSE line buffer exceeded maximum size".to_string());
-        }
-        buf.push_str(&String::from_utf8_lossy(&bytes));
-
-        loop {
-            if let Some(nl) = buf.find('\n') {
-                let line = buf[..nl].trim_end_matches('\r').to_owned();
-                buf = buf[nl + 1..].to_owned();
-
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if data == "[DONE]" {
-                        return Ok(full);
-                    }
-                    if let Some(chunk) = extract_fn(data) {
-                        if full.len() + chunk.len() > MAX_OUTPUT_BYTES {
-                            return Err("SSE output exceeded maximum allowed size".to_string());
-                        }
-                        full.push_str(&chunk);
-                        let _ = app.emit(event_name, &chunk);
-                    }
-                }
-            } else {
-                break;
-            }
-        }
+fn save_secret(app: &AppHandle, key: &str, token: &str) {
+    if secrets::set(key, token).is_ok() {
+        delete_stored_token(app, key);
+    } else if let Ok(store) = app.store(CRED_STORE) {
+        store.set(key, serde_json::Value::String(token.to_owned()));
+        let _ = store.save();
     }
-
-    Ok(full)
 }
 
-// ── Direct AI provider constants ───────────────────────────────────────────────
-
-const OPENAI_API_BASE:    &str = "https://api.openai.com/v1";
-const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
-const GROQ_API_BASE:      &str = "https://api.groq.com/openai/v1";
-const MISTRAL_API_BASE:   &str = "https://api.mistral.ai/v1";
-const GOOGLE_API_BASE:    &str = "https://generativelanguage.googleapis.com/v1beta";
-const ANTHROPIC_VERSION:  &str = "2023-06-01";
+fn delete_secret(app: &AppHandle, key: &str) {
+    let _ = secrets::delete(key);
+    delete_stored_token(app, key);
+}
 
-/// Returns the default model identifier for a given provider slug.
-fn default_model(provider: &str) -> &'static str {
-    match provider {
-        "anthropic"         => "claude-3-5-haiku-20241022",
-        "google" | "gemini" => "gemini-2.0-flash",
-        "groq"              => "llama3-8b-8192",
-        "mistral"           => "mistral-small-latest",
-        _                   => "gpt-4o-mini",  // openai + fallback
-    }
+fn load_token(app: &AppHandle, tenant: &str) -> Option<String> {
+    load_secret(app, &token_key(tenant))
 }
 
-/// Returns the OpenAI-compatible API base URL for a given provider slug.
-fn openai_compat_base(provider: &str) -> &'static str {
-    match provider {
-        "groq"    => GROQ_API_BASE,
-        "mistral" => MISTRAL_API_BASE,
-        _         => OPENAI_API_BASE,
-    }
+fn save_token(app: &AppHandle, tenant: &str, token: &str) {
+    save_secret(app, &token_key(tenant), token);
 }
 
-// ── SSE chunk extractors ───────────────────────────────────────────────────────
+/// Deletes the access token and the refresh token that goes with it.
+fn delete_token(app: &AppHandle, tenant: &str) {
+    delete_secret(app, &token_key(tenant));
+    oauth::delete_refresh_token(app, tenant);
+}
 
-/// Extracts the text delta from one OpenAI-style SSE `data:` line.
-fn extract_openai_chunk(data: &str) -> Option<String> {
-    let val: serde_json::Value = serde_json::from_str(data).ok()?;
-    val.pointer("/choices/0/delta/content")
-        .and_then(|v| v.as_str())
-        .filter(|s| !s.is_empty())
-        .map(String::from)
+/// Gateway URL and access token (empty when signed out) of `tenant`, the
+/// default tenant when `None`. An access token about to expire is refreshed
+/// first (see [`oauth`]).
+async fn gateway_for(
+    app: &AppHandle,
+    state: &AppState,
+    tenant: Option<&str>,
+) -> Result<(String, String), String> {
+    let tenant = tenants::resolve(app, state, tenant)?;
+    let token = oauth::fresh_token(app, &state.http_client, &tenant).await.unwrap_or_default();
+    Ok((tenant.gateway_url, token))
 }
 
-/// Extracts the text delta from one Anthropic SSE `data:` line.
-fn extract_anthropic_chunk(data: &str) -> Option<String> {
-    let val: serde_json::Value = serde_json::from_str(data).ok()?;
-    if val.get("type").and_then(|t| t.as_str()) != Some("content_block_delta") {
-        return None;
+/// Resolves the `custom` provider's endpoint and the Bedrock region from
+/// settings, and probes for the `local-openai` server (defaulting to its
+/// first model), returning `(base_url, model)`. Other providers pass `model`
+/// through unchanged.
+async fn resolve_endpoint(
+    app: &AppHandle,
+    http: &reqwest::Client,
+    provider: &str,
+    model: Option<String>,
+) -> Result<(Option<String>, Option<String>), String> {
+    if provider == LOCAL_OPENAI {
+        let server = local_openai::detect(http).await.ok_or_else(|| {
+            format!(
+                "no local OpenAI-compatible server found on ports {:?} — start LM Studio or llama-server",
+                local_openai::PROBE_PORTS
+            )
+        })?;
+        let model = model
+            .or_else(|| server.models.first().cloned())
+            .ok_or("the local server has no model loaded")?;
+        return Ok((Some(server.base_url), Some(model)));
     }
-    val.pointer("/delta/text")
-        .and_then(|v| v.as_str())
-        .filter(|s| !s.is_empty())
-        .map(String::from)
+    if provider == "bedrock" {
+        let region = settings::load_bedrock_region(app)
+            .ok_or("Bedrock needs an AWS region — set one in Settings → Providers")?;
+        return Ok((Some(bedrock::endpoint(&region)), model));
+    }
+    if provider != "custom" {
+        return Ok((None, model));
+    }
+    let custom = settings::load_custom_provider(app)
+        .ok_or("the custom provider has no base URL — set one in Settings → Providers")?;
+    let model = model
+        .or(custom.model)
+        .ok_or("the custom provider needs a model — pass one or set a default in Settings")?;
+    Ok((Some(custom.base_url), Some(model)))
 }
 
-/// Extracts the text delta from one Google Gemini SSE `data:` line.
-fn extract_google_chunk(data: &str) -> Option<String> {
-    let val: serde_json::Value = serde_json::from_str(data).ok()?;
-    val.pointer("/candidates/0/content/parts/0/text")
-        .and_then(|v| v.as_str())
-        .filter(|s| !s.is_empty())
-        .map(String::from)
+/// Streams one step of a tool-calling chat turn. When the model calls tools,
+/// the turn is parked under the request ID until `chat_submit_tool_results`.
+async fn run_tool_turn(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    gate: &QueueGate,
+    pending: &PendingToolTurns,
+    mut turn: PendingToolTurn,
+) -> Result<ChatResponse, String> {
+    let call = ProviderCall::new(&turn.provider, &turn.api_key, turn.model.as_deref(), &turn.message, &turn.params)
+        .with_base_url(turn.base_url.clone())
+        .with_system(turn.system.as_deref())
+        .with_history(&turn.history)
+        .with_images(&turn.images)
+        .with_tools(&turn.tools, &turn.rounds)
+        .with_session(turn.session_id.as_deref())
+        .with_gate(Some(gate));
+    let (output, calls) = call_provider_stream_with_tools(sink, http, &call).await?;
+
+    if !calls.is_empty() {
+        turn.rounds.push(ToolRound { text: output.clone(), calls: calls.clone(), results: Vec::new() });
+        pending.park(&sink.request_id, turn)?;
+    }
+    let usage = sink.usage();
+    Ok(ChatResponse { request_id: sink.request_id.clone(), output, tool_calls: calls, json: None, usage })
 }
 
-// ── Direct provider: streaming ─────────────────────────────────────────────────
+// ── Direct provider: context-length fallback ───────────────────────────────────
 
-/// Calls an AI provider's streaming endpoint directly, bypassing the cloud gateway.
-/// Emits each text chunk as `event_name` Tauri events and returns the full output.
-async fn call_provider_stream(
+/// [`call_provider_stream_in_language`], retried when the prompt overflows the
+/// model's context window: on the provider's fallback model, then with the
+/// older half of the history summarized (see [`context_fallback`]). Each retry
+/// is announced with a `context-fallback` event.
+async fn call_provider_stream_fitting(
     app: &AppHandle,
+    sink: &StreamSink,
     http: &reqwest::Client,
-    provider: &str,
-    api_key: &str,
-    model_override: Option<&str>,
-    message: &str,
-    event_name: &str,
+    call: &ProviderCall<'_>,
+    language: Option<&str>,
 ) -> Result<String, String> {
-    let model = model_override.unwrap_or_else(|| default_model(provider));
-
-    match provider {
-        "anthropic" => {
-            let resp = http
-                .post(format!("{}/messages", ANTHROPIC_API_BASE))
-                .header("x-api-key", api_key)
-                .header("anthropic-version", ANTHROPIC_VERSION)
-                .json(&serde_json::json!({
-                    "model": model,
-                    "max_tokens": 4096,
-                    "stream": true,
-                    "messages": [{ "role": "user", "content": message }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("Anthropic API error {status}: {body}"));
-            }
-            pipe_provider_sse(app, resp, event_name, extract_anthropic_chunk).await
-        }
-
-        "google" | "gemini" => {
-            let url = format!(
-                "{}/models/{}:streamGenerateContent?key={}&alt=sse",
-                GOOGLE_API_BASE, model, api_key
-            );
-            let resp = http
-                .post(&url)
-                .json(&serde_json::json!({
-                    "contents": [{ "parts": [{ "text": message }] }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("Google API error {status}: {body}"));
-            }
-            pipe_provider_sse(app, resp, event_name, extract_google_chunk).await
-        }
-
-        _ => {
-            // OpenAI, Groq, Mistral, and other OpenAI-compatible providers.
-            let base = openai_compat_base(provider);
-            let resp = http
-                .post(format!("{}/chat/completions", base))
-                .bearer_auth(api_key)
-                .json(&serde_json::json!({
-                    "model": model,
-                    "stream": true,
-                    "messages": [{ "role": "user", "content": message }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("{provider} API error {status}: {body}"));
-            }
-            pipe_provider_sse(app, resp, event_name, extract_openai_chunk).await
+    let err = match call_provider_stream_in_language(sink, http, &mut call.clone(), language).await {
+        Err(e) if context_fallback::is_context_length_error(&e) => e,
+        other => return other,
+    };
+    let larger = context_fallback::fallback_model(app, call.provider, call.model);
+    let mut retry = call.clone();
+    if let Some(model) = &larger {
+        sink.emit("context-fallback", ContextFallback {
+            reason: "larger_model",
+            from_model: call.model.into(),
+            model: model.clone(),
+            summarized_turns: 0,
+        });
+        retry.model = model;
+        match call_provider_stream_in_language(sink, http, &mut retry.clone(), language).await {
+            Err(e) if context_fallback::is_context_length_error(&e) => {}
+            other => return other,
         }
     }
+    let Some((history, system, summarized_turns)) = condense_history(http, &retry).await else {
+        return Err(err);
+    };
+    sink.emit("context-fallback", ContextFallback {
+        reason: "summarized",
+        from_model: call.model.into(),
+        model: retry.model.into(),
+        summarized_turns,
+    });
+    retry.history = &history;
+    retry.system = Some(system);
+    call_provider_stream_in_language(sink, http, &mut retry, language).await
 }
 
-// ── Direct provider: non-streaming generate ────────────────────────────────────
-
-/// Calls an AI provider's completion endpoint directly and returns `(output, tokens_used)`.
-async fn call_provider_generate(
+/// Buffered counterpart of [`call_provider_stream_fitting`] for single
+/// prompts: retries once on the provider's fallback model, announced on
+/// `ai:context-fallback`.
+async fn call_provider_generate_fitting(
+    app: &AppHandle,
     http: &reqwest::Client,
-    provider: &str,
-    api_key: &str,
-    model_override: Option<&str>,
-    input: &str,
-) -> Result<(String, i64), String> {
-    let model = model_override.unwrap_or_else(|| default_model(provider));
-
-    match provider {
-        "anthropic" => {
-            let resp = http
-                .post(format!("{}/messages", ANTHROPIC_API_BASE))
-                .header("x-api-key", api_key)
-                .header("anthropic-version", ANTHROPIC_VERSION)
-                .json(&serde_json::json!({
-                    "model": model,
-                    "max_tokens": 4096,
-                    "messages": [{ "role": "user", "content": input }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("Anthropic API error {status}: {body}"));
-            }
-            let val: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-            let text = val.pointer("/content/0/text")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_owned();
-            let tokens = val.pointer("/usage/input_tokens").and_then(|v| v.as_i64()).unwrap_or(0)
-                + val.pointer("/usage/output_tokens").and_then(|v| v.as_i64()).unwrap_or(0);
-            Ok((text, tokens))
-        }
-
-        "google" | "gemini" => {
-            let url = format!(
-                "{}/models/{}:generateContent?key={}",
-                GOOGLE_API_BASE, model, api_key
-            );
-            let resp = http
-                .post(&url)
-                .json(&serde_json::json!({
-                    "contents": [{ "parts": [{ "text": input }] }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("Google API error {status}: {body}"));
-            }
-            let val: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-            let text = val.pointer("/candidates/0/content/parts/0/text")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_owned();
-            let tokens = val.pointer("/usageMetadata/promptTokenCount").and_then(|v| v.as_i64()).unwrap_or(0)
-                + val.pointer("/usageMetadata/candidatesTokenCount").and_then(|v| v.as_i64()).unwrap_or(0);
-            Ok((text, tokens))
-        }
+    call: &ProviderCall<'_>,
+    language: Option<&str>,
+) -> Result<(String, CallUsage), String> {
+    let err = match call_provider_generate_in_language(http, &mut call.clone(), language).await {
+        Err(e) if context_fallback::is_context_length_error(&e) => e,
+        other => return other,
+    };
+    let Some(model) = context_fallback::fallback_model(app, call.provider, call.model) else {
+        return Err(err);
+    };
+    let _ = app.emit("ai:context-fallback", ContextFallback {
+        reason: "larger_model",
+        from_model: call.model.into(),
+        model: model.clone(),
+        summarized_turns: 0,
+    });
+    let mut retry = call.clone();
+    retry.model = &model;
+    call_provider_generate_in_language(http, &mut retry, language).await
+}
 
-        _ => {
-            let base = openai_compat_base(provider);
-            let resp = http
-                .post(format!("{}/chat/completions", base))
-                .bearer_auth(api_key)
-                .json(&serde_json::json!({
-                    "model": model,
-                    "messages": [{ "role": "user", "content": input }],
-                }))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if !resp.status().is_success() {
-                let status = resp.status().as_u16();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("{provider} API error {status}: {body}"));
-            }
-            let val: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-            let text = val.pointer("/choices/0/message/content")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_owned();
-            let tokens = val.pointer("/usage/prompt_tokens").and_then(|v| v.as_i64()).unwrap_or(0)
-                + val.pointer("/usage/completion_tokens").and_then(|v| v.as_i64()).unwrap_or(0);
-            Ok((text, tokens))
-        }
-    }
+/// Summarizes the older half of `call.history` with one buffered call on the
+/// same model. Returns the kept turns, the system prompt carrying the summary
+/// and the number of turns summarized; `None` when there is too little history
+/// or the summary call fails.
+async fn condense_history(
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+) -> Option<(Vec<ChatMessage>, String, usize)> {
+    let (transcript, kept, summarized) = context_fallback::split_history(call.history)?;
+    let params = GenerationParams {
+        max_tokens: Some(context_fallback::SUMMARY_MAX_TOKENS),
+        ..Default::default()
+    };
+    let summarize = ProviderCall::new(call.provider, call.api_key, Some(call.model), &transcript, &params)
+        .with_base_url(call.base_url.clone())
+        .with_system(Some(context_fallback::SUMMARY_PROMPT))
+        .with_session(call.session_id)
+        .with_gate(call.gate);
+    let (summary, _) = call_provider_generate(http, &summarize).await.ok()?;
+    let system = append_system(call.system.as_deref(), context_fallback::summary_directive(&summary));
+    Some((kept, system, summarized))
 }
 
 // ── Token commands (used by TypeScript TokenStore) ─────────────────────────────
 
+// `tenant` selects a gateway tenant (see `tenants`); the default when omitted.
+
 #[tauri::command]
-async fn get_token(app: AppHandle) -> Option<String> {
-    load_token(&app)
+async fn get_token(app: AppHandle, tenant: Option<String>) -> Option<String> {
+    load_token(&app, tenant.as_deref().unwrap_or(tenants::DEFAULT_TENANT))
 }
 
 #[tauri::command]
-async fn set_token(app: AppHandle, token: String) {
-    save_token(&app, &token);
+async fn set_token(app: AppHandle, token: String, tenant: Option<String>) {
+    save_token(&app, tenant.as_deref().unwrap_or(tenants::DEFAULT_TENANT), &token);
 }
 
 #[tauri::command]
-async fn clear_token(app: AppHandle) {
-    delete_token(&app);
+async fn clear_token(app: AppHandle, tenant: Option<String>) {
+    delete_token(&app, tenant.as_deref().unwrap_or(tenants::DEFAULT_TENANT));
 }
 
 // ── Auth commands ──────────────────────────────────────────────────────────────
@@ -418,27 +363,71 @@ async fn clear_token(app: AppHandle) {
 #[derive(Serialize)]
 struct AuthStatus {
     authenticated: bool,
+    /// `signed_out`, `authenticated`, `expired` (the token was rejected or is
+    /// past its expiry — sign in again) or `unverified` (the gateway couldn't
+    /// be reached to check it).
+    state: &'static str,
     user_id: Option<String>,
     plan: Option<String>,
 }
 
-/// Returns the current auth status by checking whether a token is persisted.
-/// Does not validate the token against the backend (non-blocking).
-#[tauri::command]
-async fn auth_status(app: AppHandle) -> AuthStatus {
-    AuthStatus {
-        authenticated: load_token(&app).is_some(),
-        user_id: None,
-        plan: None,
+impl AuthStatus {
+    fn new(state: &'static str) -> Self {
+        Self { authenticated: matches!(state, "authenticated" | "unverified"), state, user_id: None, plan: None }
     }
 }
 
-#[derive(Deserialize)]
-struct TokenResponse {
-    access_token: String,
+/// Returns the auth status of `tenant` (default when omitted). By default
+/// only the stored token is looked at — present, and not past its JWT
+/// expiry. With `validate` the token is refreshed if due and checked with the
+/// gateway's `/v1/auth/me`, which also fills in `user_id` and `plan`.
+#[tauri::command]
+async fn auth_status(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    tenant: Option<String>,
+    validate: Option<bool>,
+) -> Result<AuthStatus, String> {
+    let tenant = tenants::resolve(&app, &state, tenant.as_deref())?;
+    if !validate.unwrap_or(false) {
+        return Ok(AuthStatus::new(match load_token(&app, &tenant.id) {
+            None => "signed_out",
+            Some(t) if oauth::token_expiry(&t).is_some_and(|exp| exp <= chrono::Utc::now().timestamp()) => "expired",
+            Some(_) => "authenticated",
+        }));
+    }
+
+    let Some(token) = oauth::fresh_token(&app, &state.http_client, &tenant).await else {
+        return Ok(AuthStatus::new("signed_out"));
+    };
+    let resp = match state
+        .http_client
+        .get(format!("{}/v1/auth/me", tenant.gateway_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return Ok(AuthStatus::new("unverified")),
+    };
+    match resp.status().as_u16() {
+        401 | 403 => return Ok(AuthStatus::new("expired")),
+        s if !(200..300).contains(&s) => return Ok(AuthStatus::new("unverified")),
+        _ => {}
+    }
+    let me: serde_json::Value = resp.json().await.unwrap_or_default();
+    let field = |keys: &[&str]| keys.iter().find_map(|k| me.get(*k)?.as_str().map(String::from));
+    Ok(AuthStatus {
+        user_id: field(&["user_id", "sub", "id"]),
+        plan: field(&["plan", "tier"]),
+        ..AuthStatus::new("authenticated")
+    })
 }
 
-/// Exchanges client credentials for a JWT and persists it in the credential store.
+/// Exchanges client credentials for a JWT with `tenant`'s gateway (default
+/// when omitted) and persists it in the credential store under that tenant,
+/// with the refresh token when the gateway issues one. For a browser
+/// sign-in use `auth_device_start`.
 /// Returns an opaque error on failure — never reveals which field was wrong.
 #[tauri::command]
 async fn auth_login(
@@ -446,30 +435,32 @@ async fn auth_login(
     state: State<'_, AppState>,
     client_id: String,
     client_secret: String,
+    tenant: Option<String>,
 ) -> Result<(), String> {
+    let tenant = tenants::resolve(&app, &state, tenant.as_deref())?;
     let resp = state
         .http_client
-        .post(format!("{}/v1/auth/token", state.gateway_url))
+        .post(format!("{}/v1/auth/token", tenant.gateway_url))
         .json(&serde_json::json!({
             "client_id":     client_id,
             "client_secret": client_secret,
         }))
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(redact::error)?;
 
     if !resp.status().is_success() {
         return Err("invalid credentials".into());
     }
 
-    let body: TokenResponse = resp.json().await.map_err(|e| e.to_string())?;
-    save_token(&app, &body.access_token);
+    let grant: oauth::TokenGrant = resp.json().await.map_err(redact::error)?;
+    oauth::store_grant(&app, &tenant.id, &grant);
     Ok(())
 }
 
 #[tauri::command]
-async fn auth_logout(app: AppHandle) {
-    delete_token(&app);
+async fn auth_logout(app: AppHandle, tenant: Option<String>) {
+    delete_token(&app, tenant.as_deref().unwrap_or(tenants::DEFAULT_TENANT));
 }
 
 // ── Health command ─────────────────────────────────────────────────────────────
@@ -481,13 +472,19 @@ struct HealthReport {
     timestamp: String,
 }
 
-/// Probes the Go backend's /health/detailed endpoint.
-/// Always returns a HealthReport — never throws (degrades gracefully).
+/// Probes the Go backend's /health/detailed endpoint (of `tenant`'s gateway
+/// when given). Returns a HealthReport unless the tenant is unknown — a
+/// down or degraded backend is reported, not thrown.
 #[tauri::command]
-async fn health_check(state: State<'_, AppState>) -> Result<HealthReport, String> {
+async fn health_check(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    tenant: Option<String>,
+) -> Result<HealthReport, String> {
+    let tenant = tenants::resolve(&app, &state, tenant.as_deref())?;
     Ok(match state
         .http_client
-        .get(format!("{}/health/detailed", state.gateway_url))
+        .get(format!("{}/health/detailed", tenant.gateway_url))
         .send()
         .await
     {
@@ -506,60 +503,185 @@ async fn health_check(state: State<'_, AppState>) -> Result<HealthReport, String
 
 // ── Chat command ───────────────────────────────────────────────────────────────
 
+/// Result of a chat turn, carried by `chat:stream-done`.
 #[derive(Serialize)]
 struct ChatResponse {
+    request_id: String,
     output: String,
+    /// Tools the model called; answer with `chat_submit_tool_results`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+    /// `output` parsed and validated, when a `response_format` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json: Option<serde_json::Value>,
+    /// Tokens and finish reason, when the provider reported them (BYOK path).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<StreamUsage>,
 }
 
-/// Streams a chat completion.
-///
-/// When a BYOK `api_key` + `provider` are supplied the request goes directly to
-/// the AI provider — no cloud gateway is required. Falls back to the gateway
-/// only when no key is configured (managed-key / server-side billing path).
+/// Starts a chat turn and returns its request ID at once. With a BYOK key (`api_key`, or one
+/// stored for `provider`) the provider is called directly with `model`, `params`, `system`,
+/// `tools`, `images` and `response_format`; without one the turn goes through the gateway of
+/// `tenant`. Events on `chat:*:{request_id}` carry the chunks, retries, resumes and usage, and the
+/// turn ends with `chat:stream-done` ([`ChatResponse`]) or `chat:stream-failed`. BYOK requests are
+/// retried ([`retry`]), capped ([`budget`]), queued ([`request_queue`]) and recorded in the
+/// [`usage_ledger`] under `session_id`.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn chat_send(
     app: AppHandle,
-    state: State<'_, AppState>,
     message: String,
+    messages: Option<Vec<ChatMessage>>,
     api_key: Option<String>,
     provider: Option<String>,
     model: Option<String>,
-) -> Result<ChatResponse, String> {
-    // BYOK path — call the AI provider directly.
-    if let (Some(key), Some(prov)) = (api_key.as_deref(), provider.as_deref()) {
-        let output = call_provider_stream(
-            &app, &state.http_client, prov, key, model.as_deref(), &message, "chat:stream-chunk",
-        ).await?;
-        return Ok(ChatResponse { output });
+    params: Option<GenerationParams>,
+    system: Option<String>,
+    request_id: Option<String>,
+    session_id: Option<String>,
+    tenant: Option<String>,
+    tools: Option<Vec<ToolSpec>>,
+    images: Option<Vec<String>>,
+    response_format: Option<ResponseFormat>,
+) -> Result<StreamHandle, String> {
+    shutdown::ensure_running()?;
+    let language = language::load_response_language(&app);
+    let history = messages.unwrap_or_default();
+    validate_messages(&history)?;
+    let tools = tools.unwrap_or_default();
+    tool_calls::validate_tools(&tools)?;
+    let images = vision::load_all(&app, &images.unwrap_or_default())?;
+    if let Some(f) = &response_format {
+        f.validate()?;
+        if !tools.is_empty() {
+            return Err("response_format can't be combined with tools".into());
+        }
+    }
+    let params = params.unwrap_or_default();
+
+    let api_key = api_key.or_else(|| provider_keys::resolve(&app, provider.as_deref()?, None));
+    let direct = direct_key(api_key.as_deref(), provider.as_deref())
+        .zip(provider.clone())
+        .map(|(key, prov)| (key.to_owned(), prov));
+    if let Some((_, prov)) = &direct {
+        budget::ensure_within(&app)?;
+        params.validate()?;
+        if !images.is_empty() {
+            vision::check_provider(prov)?;
+        }
+    } else if !tools.is_empty() {
+        return Err("tool calling needs a direct provider key".into());
+    } else if !images.is_empty() {
+        return Err("image inputs need a direct provider key".into());
+    } else if response_format.is_some() {
+        return Err("response_format needs a direct provider key".into());
     }
 
-    // Managed-key path — route through the cloud gateway.
-    let token = load_token(&app).unwrap_or_default();
-    let mut body = serde_json::json!({ "capability": "general-chat", "input": message });
-    if let Some(k) = api_key  { body["api_key"]  = serde_json::Value::String(k); }
-    if let Some(p) = provider { body["provider"] = serde_json::Value::String(p); }
-
-    let resp = state
-        .http_client
-        .post(format!("{}/v1/ai/stream", state.gateway_url))
-        .bearer_auth(&token)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            if e.is_connect() {
-                "Cloud Gateway unreachable. Please check your internet connection or configure a local API key in the Dashboard.".to_string()
-            } else {
-                e.to_string()
+    let sink = StreamSink::new(&app, "chat", request_id);
+    let handle = StreamHandle { request_id: sink.request_id.clone() };
+    let session = session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let state = app.state::<AppState>();
+
+            // BYOK path — call the AI provider directly.
+            if let Some((key, prov)) = &direct {
+                let gate = QueueGate(app.clone());
+                let (base_url, model) = resolve_endpoint(&app, &state.http_client, prov, model).await?;
+                if !tools.is_empty() {
+                    // No language retry here: a rejected reply may already have called tools.
+                    let system = match language {
+                        Some(lang) => Some(append_system(system.as_deref(), language::directive(lang))),
+                        None => system,
+                    };
+                    let mut turn = PendingToolTurn::new(
+                        prov.clone(), key.clone(), model, base_url, message, images, history, system, params, tools,
+                    );
+                    turn.session_id = session_id;
+                    let pending = app.state::<PendingToolTurns>();
+                    return run_tool_turn(&sink, &state.http_client, &gate, &pending, turn).await;
+                }
+                let call = ProviderCall::new(prov, key, model.as_deref(), &message, &params)
+                    .with_base_url(base_url)
+                    .with_system(system.as_deref())
+                    .with_history(&history)
+                    .with_images(&images)
+                    .with_response_format(response_format.as_ref())
+                    .with_session(session_id.as_deref())
+                    .with_gate(Some(&gate));
+                let output =
+                    call_provider_stream_fitting(&app, &sink, &state.http_client, &call, language).await?;
+                let json = response_format.map(|f| f.parse(&output)).transpose()?;
+                let usage = sink.usage();
+                return Ok(ChatResponse { request_id: sink.request_id.clone(), output, tool_calls: Vec::new(), json, usage });
             }
-        })?;
 
-    if !resp.status().is_success() {
-        return Err(format!("stream error: HTTP {}", resp.status().as_u16()));
+            // Managed-key path — route through the cloud gateway.
+            let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
+            let mut body = serde_json::json!({ "capability": "general-chat", "input": message });
+            if !history.is_empty() { body["messages"] = serde_json::json!(history); }
+            if let Some(k) = api_key  { body["api_key"]  = serde_json::Value::String(k); }
+            if let Some(p) = provider { body["provider"] = serde_json::Value::String(p); }
+            if let Some(m) = model    { body["model"]    = serde_json::Value::String(m); }
+            if let Some(l) = language { body["response_language"] = l.into(); }
+
+            let output = gateway_stream(&sink, &state.http_client, &gateway, &token, &body).await?;
+            Ok(ChatResponse { request_id: sink.request_id.clone(), output, tool_calls: Vec::new(), json: None, usage: None })
+        }
+        .await;
+        if let (Ok(_), Some(id)) = (&result, &session) {
+            let _ = chat_sessions::record_messages(id, 2, None);
+        }
+        sink.finish(result);
+    });
+    Ok(handle)
+}
+
+/// Continues chat `request_id` after the model called tools (see
+/// `chat_send`): `results` must answer every call of the last round. Streams
+/// on the same request-scoped channels and ends like `chat_send`, including
+/// any further tool calls; the reply is counted in the chat session's row.
+#[tauri::command]
+async fn chat_submit_tool_results(
+    app: AppHandle,
+    pending: State<'_, PendingToolTurns>,
+    request_id: String,
+    results: Vec<ToolResult>,
+) -> Result<StreamHandle, String> {
+    shutdown::ensure_running()?;
+    budget::ensure_within(&app)?;
+    let mut turn = pending
+        .take(&request_id)
+        .ok_or_else(|| format!("no chat is waiting for tool results under '{request_id}'"))?;
+    let Some(round) = turn.rounds.last_mut() else {
+        return Err("no tool calls to answer".into());
+    };
+    if let Some(missing) = round.calls.iter().find(|c| !results.iter().any(|r| r.tool_call_id == c.id)) {
+        let err = format!("missing result for tool call '{}' ({})", missing.id, missing.name);
+        pending.park(&request_id, turn)?;
+        return Err(err);
+    }
+    round.results = results;
+    if turn.rounds.len() >= tool_calls::MAX_ROUNDS {
+        return Err(format!("too many tool-call rounds (max {})", tool_calls::MAX_ROUNDS));
     }
 
-    let output = pipe_sse(&app, resp, "chat:stream-chunk").await?;
-    Ok(ChatResponse { output })
+    let sink = StreamSink::new(&app, "chat", Some(request_id));
+    let handle = StreamHandle { request_id: sink.request_id.clone() };
+    let session = turn.session_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let state = app.state::<AppState>();
+            let pending = app.state::<PendingToolTurns>();
+            run_tool_turn(&sink, &state.http_client, &QueueGate(app.clone()), &pending, turn).await
+        }
+        .await;
+        if let (Ok(_), Some(id)) = (&result, &session) {
+            let _ = chat_sessions::record_messages(id, 1, None);
+        }
+        sink.finish(result);
+    });
+    Ok(handle)
 }
 
 // ── AI generate command ────────────────────────────────────────────────────────
@@ -576,14 +698,31 @@ struct AiBackendResponse {
 #[derive(Serialize)]
 struct AiGenerateResponse {
     output: String,
-    tokens_used: i64,
+    /// `tokens_used`, plus Anthropic's `cache_read_tokens` / `cache_write_tokens`.
+    #[serde(flatten)]
+    usage: CallUsage,
+    /// `output` parsed and validated, when a `response_format` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json: Option<serde_json::Value>,
 }
 
 /// Returns a buffered AI completion.
 ///
 /// When a BYOK `api_key` + `provider` are supplied the request goes directly to
-/// the AI provider — no cloud gateway is required.
+/// the AI provider — no cloud gateway is required; `provider = "custom"`,
+/// `"local-openai"` and `"bedrock"` work as in [`chat_send`]. `model` overrides the
+/// provider default on either path; `params` (as in [`chat_send`]),
+/// `system` (system prompt / persona) and `images` (as in
+/// [`chat_send`]) apply to the BYOK path. The configured response language is
+/// enforced on the BYOK path and forwarded to the gateway; `tenant` picks the
+/// gateway account. A BYOK prompt that
+/// overflows the model's context window is retried on the provider's fallback
+/// model, announced on `ai:context-fallback`. `response_format` works as in
+/// [`chat_send`]. On the Anthropic path `cache_read_tokens` and
+/// `cache_write_tokens` report prompt-cache use; otherwise they are 0.
+/// Transient provider errors are retried as in [`chat_send`], without events.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn ai_generate(
     app: AppHandle,
     state: State<'_, AppState>,
@@ -593,109 +732,248 @@ async fn ai_generate(
     api_key: Option<String>,
     provider: Option<String>,
     model: Option<String>,
+    params: Option<GenerationParams>,
+    system: Option<String>,
+    tenant: Option<String>,
+    images: Option<Vec<String>>,
+    response_format: Option<ResponseFormat>,
 ) -> Result<AiGenerateResponse, String> {
+    shutdown::ensure_running()?;
+    let _in_flight = shutdown::InFlight::enter();
+    let language = language::load_response_language(&app);
+    let images = vision::load_all(&app, &images.unwrap_or_default())?;
+    if let Some(f) = &response_format {
+        f.validate()?;
+    }
+
     // BYOK path — call the AI provider directly.
-    if let (Some(key), Some(prov)) = (api_key.as_deref(), provider.as_deref()) {
+    let api_key = api_key.or_else(|| provider_keys::resolve(&app, provider.as_deref()?, None));
+    let direct = direct_key(api_key.as_deref(), provider.as_deref());
+    if let (Some(key), Some(prov)) = (direct, provider.as_deref()) {
+        budget::ensure_within(&app)?;
+        let gate = QueueGate(app.clone());
         let prompt = match context.as_ref() {
             Some(_) => format!("[{capability}] {input}"),
             None    => input.clone(),
         };
-        let (output, tokens_used) =
-            call_provider_generate(&state.http_client, prov, key, model.as_deref(), &prompt).await?;
-        return Ok(AiGenerateResponse { output, tokens_used });
+        let params = params.unwrap_or_default();
+        params.validate()?;
+        let (base_url, model) = resolve_endpoint(&app, &state.http_client, prov, model.clone()).await?;
+        if !images.is_empty() {
+            vision::check_provider(prov)?;
+        }
+        let call = ProviderCall::new(prov, key, model.as_deref(), &prompt, &params)
+            .with_base_url(base_url)
+            .with_system(system.as_deref())
+            .with_images(&images)
+            .with_response_format(response_format.as_ref())
+            .with_gate(Some(&gate));
+        let (output, usage) =
+            call_provider_generate_fitting(&app, &state.http_client, &call, language).await?;
+        let json = response_format.map(|f| f.parse(&output)).transpose()?;
+        return Ok(AiGenerateResponse { output, usage, json });
     }
 
     // Managed-key path — route through the cloud gateway.
-    let token = load_token(&app).unwrap_or_default();
+    if !images.is_empty() {
+        return Err("image inputs need a direct provider key".into());
+    }
+    if response_format.is_some() {
+        return Err("response_format needs a direct provider key".into());
+    }
+    let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
     let mut body = serde_json::json!({ "capability": capability, "input": input });
     if let Some(ctx) = context  { body["context"]  = ctx; }
     if let Some(k)   = api_key  { body["api_key"]  = serde_json::Value::String(k); }
     if let Some(p)   = provider { body["provider"] = serde_json::Value::String(p); }
+    if let Some(m)   = model    { body["model"]    = serde_json::Value::String(m); }
+    if let Some(l)   = language { body["response_language"] = l.into(); }
 
-    let resp = state
-        .http_client
-        .post(format!("{}/v1/ai/generate", state.gateway_url))
-        .bearer_auth(&token)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            if e.is_connect() {
-                "Cloud Gateway unreachable. Please check your internet connection or configure a local API key in the Dashboard.".to_string()
-            } else {
-                e.to_string()
-            }
-        })?;
+    let resp = gateway_post(&state.http_client, &gateway, &token, "/v1/ai/generate", &body).await?;
 
     if !resp.status().is_success() {
         return Err(format!("generate error: HTTP {}", resp.status().as_u16()));
     }
 
-    let data: AiBackendResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let data: AiBackendResponse = resp.json().await.map_err(redact::error)?;
     Ok(AiGenerateResponse {
         output: data.output,
-        tokens_used: data.input_tokens + data.output_tokens,
+        usage: CallUsage::tokens(data.input_tokens, data.output_tokens),
+        json: None,
     })
 }
 
-/// Streams an AI completion for module use (ctx.ai.stream()).
+/// Messages of a session considered by [`session_auto_title`], and the
+/// characters kept from each.
+const AUTO_TITLE_MESSAGES: usize = 4;
+const AUTO_TITLE_MESSAGE_CHARS: usize = 500;
+const AUTO_TITLE_MAX_CHARS: usize = 60;
+
+/// Generates a short title for session `id` from its first `messages` and
+/// stores it in the sessions table; returns the title. Goes through
+/// [`ai_generate`], so `api_key`, `provider`, `model` and `tenant` pick the
+/// BYOK provider or gateway as there.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn session_auto_title(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    messages: Vec<String>,
+    api_key: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    tenant: Option<String>,
+) -> Result<String, String> {
+    let conversation: Vec<String> = messages
+        .iter()
+        .map(|m| m.trim())
+        .filter(|m| !m.is_empty())
+        .take(AUTO_TITLE_MESSAGES)
+        .map(|m| m.chars().take(AUTO_TITLE_MESSAGE_CHARS).collect())
+        .collect();
+    if conversation.is_empty() {
+        return Err("no messages to title the session from".into());
+    }
+    let params = GenerationParams { temperature: Some(0.3), max_tokens: Some(32), ..Default::default() };
+    let system = "You name chat conversations. Reply with a title of at most six words \
+                  that says what the conversation is about — no quotes, no trailing \
+                  punctuation, nothing else.";
+    let reply = ai_generate(
+        app,
+        state,
+        "general-chat".into(),
+        format!("Title this conversation:\n\n{}", conversation.join("\n---\n")),
+        None,
+        api_key,
+        provider,
+        model,
+        Some(params),
+        Some(system.into()),
+        tenant,
+        None,
+        None,
+    )
+    .await?;
+
+    let title: String = reply
+        .output
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '“' | '”' | '*' | '#') || c.is_whitespace())
+        .trim_end_matches(['.', '!', '?', ':'])
+        .chars()
+        .take(AUTO_TITLE_MAX_CHARS)
+        .collect();
+    if title.is_empty() {
+        return Err("the model returned an empty title".into());
+    }
+    chat_sessions::set_title(&id, &title)?;
+    Ok(title)
+}
+
+#[derive(Serialize)]
+struct StreamHandle {
+    request_id: String,
+}
+
+/// Starts streaming an AI completion for module use (ctx.ai.stream()) and
+/// returns its request ID at once.
 ///
 /// When a BYOK `api_key` + `provider` are supplied the request goes directly to
-/// the AI provider — no cloud gateway is required.
-/// Emits `ai:stream-chunk` events per token and `ai:stream-done` on completion.
+/// the AI provider — no cloud gateway is required; `provider = "custom"`,
+/// `"local-openai"` and `"bedrock"` work as in [`chat_send`]. `model` overrides the
+/// provider default on either path; `params` (as in [`chat_send`]) and
+/// `system` (system prompt / persona) apply to the BYOK path;
+/// `tenant` picks the gateway account.
+/// Emits `ai:stream-chunk:{request_id}` events per token (plus
+/// `ai:reasoning-chunk:{request_id}` for reasoning models), then
+/// `ai:stream-done:{request_id}`, or `ai:stream-failed:{request_id}` with the
+/// `error`. Without `request_id` events are also mirrored on the unscoped
+/// `ai:stream-chunk` / `ai:stream-done` / `ai:stream-failed` channels
+/// (deprecated). Context-length errors fall
+/// back as in [`ai_generate`], announced on `ai:context-fallback:{request_id}`.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn ai_stream(
     app: AppHandle,
-    state: State<'_, AppState>,
     capability: String,
     input: String,
     context: Option<serde_json::Value>,
     api_key: Option<String>,
     provider: Option<String>,
     model: Option<String>,
-) -> Result<(), String> {
-    // BYOK path — call the AI provider directly.
-    if let (Some(key), Some(prov)) = (api_key.as_deref(), provider.as_deref()) {
-        let prompt = match context.as_ref() {
-            Some(_) => format!("[{capability}] {input}"),
-            None    => input.clone(),
-        };
-        call_provider_stream(
-            &app, &state.http_client, prov, key, model.as_deref(), &prompt, "ai:stream-chunk",
-        ).await?;
-        let _ = app.emit("ai:stream-done", ());
-        return Ok(());
+    params: Option<GenerationParams>,
+    system: Option<String>,
+    request_id: Option<String>,
+    tenant: Option<String>,
+) -> Result<StreamHandle, String> {
+    shutdown::ensure_running()?;
+    let params = params.unwrap_or_default();
+    let api_key = api_key.or_else(|| provider_keys::resolve(&app, provider.as_deref()?, None));
+    let direct = direct_key(api_key.as_deref(), provider.as_deref())
+        .zip(provider.clone())
+        .map(|(key, prov)| (key.to_owned(), prov));
+    if direct.is_some() {
+        budget::ensure_within(&app)?;
+        params.validate()?;
     }
 
-    // Managed-key path — route through the cloud gateway.
-    let token = load_token(&app).unwrap_or_default();
-    let mut body = serde_json::json!({ "capability": capability, "input": input });
-    if let Some(ctx) = context  { body["context"]  = ctx; }
-    if let Some(k)   = api_key  { body["api_key"]  = serde_json::Value::String(k); }
-    if let Some(p)   = provider { body["provider"] = serde_json::Value::String(p); }
-
-    let resp = state
-        .http_client
-        .post(format!("{}/v1/ai/stream", state.gateway_url))
-        .bearer_auth(&token)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            if e.is_connect() {
-                "Cloud Gateway unreachable. Please check your internet connection or configure a local API key in the Dashboard.".to_string()
-            } else {
-                e.to_string()
+    let sink = StreamSink::new(&app, "ai", request_id);
+    let handle = StreamHandle { request_id: sink.request_id.clone() };
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let state = app.state::<AppState>();
+
+            // BYOK path — call the AI provider directly.
+            if let Some((key, prov)) = &direct {
+                let gate = QueueGate(app.clone());
+                let prompt = match context.as_ref() {
+                    Some(_) => format!("[{capability}] {input}"),
+                    None    => input.clone(),
+                };
+                let (base_url, model) = resolve_endpoint(&app, &state.http_client, prov, model).await?;
+                let call = ProviderCall::new(prov, key, model.as_deref(), &prompt, &params)
+                    .with_base_url(base_url)
+                    .with_system(system.as_deref())
+                    .with_gate(Some(&gate));
+                call_provider_stream_fitting(&app, &sink, &state.http_client, &call, None).await?;
+                return Ok(());
             }
-        })?;
 
-    if !resp.status().is_success() {
-        return Err(format!("stream error: HTTP {}", resp.status().as_u16()));
-    }
+            // Managed-key path — route through the cloud gateway.
+            let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
+            let mut body = serde_json::json!({ "capability": capability, "input": input });
+            if let Some(ctx) = context  { body["context"]  = ctx; }
+            if let Some(k)   = api_key  { body["api_key"]  = serde_json::Value::String(k); }
+            if let Some(p)   = provider { body["provider"] = serde_json::Value::String(p); }
+            if let Some(m)   = model    { body["model"]    = serde_json::Value::String(m); }
 
-    pipe_sse(&app, resp, "ai:stream-chunk").await?;
-    let _ = app.emit("ai:stream-done", ());
-    Ok(())
+            gateway_stream(&sink, &state.http_client, &gateway, &token, &body).await?;
+            Ok(())
+        }
+        .await;
+        sink.finish(result);
+    });
+    Ok(handle)
+}
+
+/// Evaluates a math / unit / date expression locally and deterministically —
+/// `2^10 / 3`, `60 mph to km/h`, `2024-01-31 + 1 month` — without a model
+/// round trip. Same evaluator as the runtime's built-in `calculate` skill.
+#[tauri::command]
+fn ai_calculate(expression: String) -> Result<calculator::Calculation, String> {
+    calculator::evaluate(&expression).map_err(redact::error)
+}
+
+/// Probes for a local OpenAI-compatible server (LM Studio on 1234,
+/// llama.cpp on 8080) and lists its models; `null` when none answers.
+/// Chat with it via `provider = "local-openai"` — no API key needed.
+#[tauri::command]
+async fn local_openai_detect(state: State<'_, AppState>) -> Result<Option<local_openai::LocalServer>, String> {
+    Ok(local_openai::detect(&state.http_client).await)
 }
 
 // ── Module commands ────────────────────────────────────────────────────────────
@@ -710,12 +988,13 @@ async fn modules_invoke_tool(
     module_id: String,
     tool_name: String,
     input: serde_json::Value,
+    tenant: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    let token = load_token(&app).unwrap_or_default();
+    let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
 
     let resp = state
         .http_client
-        .post(format!("{}/v1/modules/invoke", state.gateway_url))
+        .post(format!("{gateway}/v1/modules/invoke"))
         .bearer_auth(&token)
         .json(&serde_json::json!({
             "module_id": module_id,
@@ -724,13 +1003,13 @@ async fn modules_invoke_tool(
         }))
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(redact::error)?;
 
     if !resp.status().is_success() {
         return Err(format!("invoke error: HTTP {}", resp.status().as_u16()));
     }
 
-    resp.json().await.map_err(|e| e.to_string())
+    resp.json().await.map_err(redact::error)
 }
 
 // ── Agents commands ────────────────────────────────────────────────────────────
@@ -753,17 +1032,18 @@ pub struct IAgentRunUpdate {
 async fn agents_poll(
     app: AppHandle,
     state: State<'_, AppState>,
+    tenant: Option<String>,
 ) -> Result<Option<IAgentPollResult>, String> {
-    let token = load_token(&app).unwrap_or_default();
+    let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
     if token.is_empty() { return Ok(None); }
 
     let resp = state
         .http_client
-        .get(format!("{}/v1/agents/poll", state.gateway_url))
+        .get(format!("{gateway}/v1/agents/poll"))
         .bearer_auth(&token)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(redact::error)?;
 
     if resp.status() == reqwest::StatusCode::NO_CONTENT {
         return Ok(None);
@@ -773,7 +1053,7 @@ async fn agents_poll(
         return Err(format!("poll error: HTTP {}", resp.status().as_u16()));
     }
 
-    resp.json().await.map_err(|e| e.to_string())
+    resp.json().await.map_err(redact::error)
 }
 
 #[tauri::command]
@@ -782,17 +1062,18 @@ async fn agents_update_run(
     state: State<'_, AppState>,
     run_id: String,
     update: IAgentRunUpdate,
+    tenant: Option<String>,
 ) -> Result<(), String> {
-    let token = load_token(&app).unwrap_or_default();
+    let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
 
     let resp = state
         .http_client
-        .patch(format!("{}/v1/agents/runs/{}", state.gateway_url, run_id))
+        .patch(format!("{gateway}/v1/agents/runs/{run_id}"))
         .bearer_auth(&token)
         .json(&update)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(redact::error)?;
 
     if !resp.status().is_success() && resp.status() != reqwest::StatusCode::NO_CONTENT {
         return Err(format!("update run error: HTTP {}", resp.status().as_u16()));
@@ -803,26 +1084,80 @@ async fn agents_update_run(
 
 // ── Usage command ──────────────────────────────────────────────────────────────
 
-#[tauri::command]
-async fn usage_get(
-    app: AppHandle,
-    state: State<'_, AppState>,
+async fn fetch_usage(
+    http: &reqwest::Client,
+    gateway: &str,
+    token: &str,
 ) -> Result<serde_json::Value, String> {
-    let token = load_token(&app).unwrap_or_default();
-
-    let resp = state
-        .http_client
-        .get(format!("{}/v1/usage", state.gateway_url))
-        .bearer_auth(&token)
+    let resp = http
+        .get(format!("{gateway}/v1/usage"))
+        .bearer_auth(token)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(redact::error)?;
 
     if !resp.status().is_success() {
         return Err(format!("usage error: HTTP {}", resp.status().as_u16()));
     }
 
-    resp.json().await.map_err(|e| e.to_string())
+    resp.json().await.map_err(redact::error)
+}
+
+/// Returns the usage reported by `tenant`'s gateway (default when omitted).
+/// Direct provider calls aren't included; see `usage_get_local`.
+#[tauri::command]
+async fn usage_get(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    tenant: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let (gateway, token) = gateway_for(&app, &state, tenant.as_deref()).await?;
+    fetch_usage(&state.http_client, &gateway, &token).await
+}
+
+/// Usage of one tenant in [`usage_get_all`].
+#[derive(Serialize)]
+struct TenantUsage {
+    tenant_id: String,
+    name: String,
+    usage: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+/// Returns the usage of every signed-in tenant. A tenant whose gateway fails
+/// reports its `error` instead of failing the whole report.
+#[tauri::command]
+async fn usage_get_all(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<TenantUsage>, String> {
+    let tenants = tenants::list(&app, &state);
+    let tokens = futures_util::future::join_all(
+        tenants.iter().map(|t| oauth::fresh_token(&app, &state.http_client, t)),
+    )
+    .await;
+    let signed_in: Vec<(tenants::Tenant, String)> = tenants
+        .into_iter()
+        .zip(tokens)
+        .filter_map(|(t, token)| Some((t, token?)))
+        .collect();
+
+    let reports = futures_util::future::join_all(signed_in.iter().map(|(t, token)| {
+        fetch_usage(&state.http_client, &t.gateway_url, token)
+    }))
+    .await;
+
+    Ok(signed_in
+        .into_iter()
+        .zip(reports)
+        .map(|((t, _), report)| {
+            let (usage, error) = match report {
+                Ok(u)  => (Some(u), None),
+                Err(e) => (None, Some(e)),
+            };
+            TenantUsage { tenant_id: t.id, name: t.name, usage, error }
+        })
+        .collect())
 }
 
 // ── App command ────────────────────────────────────────────────────────────────
@@ -835,26 +1170,62 @@ fn app_version(app: AppHandle) -> String {
 // ── Entry point ────────────────────────────────────────────────────────────────
 
 fn main() {
-    let gateway_url = std::env::var("CLOUD_GATEWAY_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".into());
-
     let http_client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(120))
         .build()
         .expect("failed to build reqwest HTTP client");
 
     tauri::Builder::default()
+        // Must come first: a second launch hands its deep link over and exits.
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(win) = app.get_webview_window("main") {
+                let _ = win.unminimize();
+                let _ = win.set_focus();
+            }
+        }))
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .setup(|app| {
             // Auto-open devtools in debug builds so JS errors are immediately visible.
             #[cfg(debug_assertions)]
             if let Some(win) = app.get_webview_window("main") {
                 win.open_devtools();
             }
+            redact::configure(settings::load_redaction(app.handle()));
+            session::start_monitor(app.handle().clone());
+            capture_ask::start(app.handle());
+            quick_chat::start(app.handle());
+            emergency_stop::start(app.handle());
+            tray::start(app.handle())?;
+            gateway::start(app.handle());
+            deep_link::start(app.handle());
+            if let Ok(dir) = app.path().app_data_dir() {
+                let _ = usage_ledger::open(&dir);
+                let _ = audit::open(&dir);
+                let _ = chat_sessions::open(&dir);
+            }
             Ok(())
         })
-        .manage(AppState { gateway_url, http_client })
+        .manage(AppState { gateways: gateway::Gateways::from_env(), http_client, requests: Default::default() })
+        .manage(terminal::TerminalSessions::default())
+        .manage(processes::Processes::default())
+        .manage(browser::Browser::default())
+        .manage(approval::Approvals::default())
+        .manage(live_view::LiveViews::default())
+        .manage(skills::SkillRegistry::default())
+        .manage(PendingToolTurns::default())
+        .manage(memory_context::MemoryContexts::default())
+        .manage(hotkey::HotkeyStatuses::default())
+        .manage(capture_ask::CaptureAsk::default())
+        .manage(quick_chat::QuickChat::default())
+        .manage(emergency_stop::EmergencyStop::default())
+        .manage(tray::Tray::default())
+        .manage(deep_link::PendingDeepLink::default())
+        .manage(updater::Updates::default())
+        .register_uri_scheme_protocol(capture_ask::SCHEME, capture_ask::protocol)
         .invoke_handler(tauri::generate_handler![
             // token (used by TypeScript TokenStore)
             get_token,
@@ -864,24 +1235,127 @@ fn main() {
             auth_status,
             auth_login,
             auth_logout,
+            oauth::auth_device_start,
+            oauth::auth_device_cancel,
+            // default-tenant gateways
+            gateway::gateway_status,
+            gateway::gateway_set_url,
+            gateway::gateway_set_urls,
+            // gateway tenants
+            tenants::tenants_list,
+            tenants::tenants_add,
+            tenants::tenants_remove,
             // backend probes
             health_check,
             // AI
             chat_send,
+            chat_submit_tool_results,
             ai_generate,
             ai_stream,
+            ai_calculate,
+            estimate::ai_estimate,
+            local_openai_detect,
+            models::models_list,
             // modules
             modules_invoke_tool,
             // usage
             usage_get,
+            usage_get_all,
+            usage_ledger::usage_get_local,
+            usage_ledger::usage_get_session,
+            budget::budget_status,
+            budget::budget_confirm,
             // agents
             agents_poll,
             agents_update_run,
+            agent_bundle::agent_save,
+            agent_bundle::agent_export,
+            agent_bundle::agent_import,
+            agent_run::agent_run,
             // app
             app_version,
+            // settings
+            language::settings_get_response_language,
+            language::settings_set_response_language,
+            context_fallback::settings_get_context_fallback_models,
+            context_fallback::settings_set_context_fallback_models,
+            budget::settings_get_spend_limits,
+            budget::settings_set_spend_limits,
+            // text-to-speech
+            speech::ai_speak,
+            // capture and ask
+            capture_ask::capture_and_ask,
+            capture_ask::settings_get_capture_ask,
+            capture_ask::settings_set_capture_ask,
+            // quick chat
+            quick_chat::quick_chat_open,
+            quick_chat::quick_chat_take_pending,
+            quick_chat::hotkeys_get,
+            quick_chat::hotkeys_set,
+            hotkey::hotkeys_status,
+            // tray
+            tray::agents_set_paused,
+            tray::agents_get_paused,
+            // deep links
+            deep_link::deep_link_take_pending,
+            // updates
+            updater::update_check,
+            updater::update_download,
+            updater::update_install,
+            updater::settings_get_update_channel,
+            updater::settings_set_update_channel,
+            app_settings::settings_get,
+            app_settings::settings_set,
+            settings::settings_get_custom_provider,
+            settings::settings_set_custom_provider,
+            settings::settings_get_bedrock_region,
+            settings::settings_set_bedrock_region,
+            settings::settings_get_stream_idle_timeout,
+            settings::settings_set_stream_idle_timeout,
+            settings::settings_get_redaction,
+            settings::settings_set_redaction,
+            request_queue::settings_get_provider_rate_limits,
+            request_queue::settings_set_provider_rate_limits,
+            request_queue::request_queue_status,
+            redact::redact_text,
+            secrets::secret_set,
+            secrets::secret_get,
+            secrets::secret_delete,
+            provider_keys::provider_keys_set,
+            provider_keys::provider_keys_list,
+            provider_keys::provider_keys_delete,
+            skills::skill_register,
+            skills::skill_list,
+            skills::skill_invoke,
+            skills::skill_usage,
+            memory_context::memory_build_turn_context,
+            memory_context::memory_explain_context,
+            memory_context::memory_access_stats,
+            memory_context::memory_reset_session,
+            memory_context::memory_find_duplicates,
+            memory_context::memory_merge,
+            memory_transfer::memory_export,
+            memory_transfer::memory_import,
+            chat_sessions::session_list,
+            chat_sessions::session_create,
+            chat_sessions::session_rename,
+            chat_sessions::session_archive,
+            chat_sessions::session_delete,
+            chat_sessions::session_record_messages,
+            session_auto_title,
             // computer-use
             computer::computer_screenshot,
             computer::computer_screenshot_region,
+            computer::computer_screenshot_raw,
+            computer::computer_list_windows,
+            desktops::computer_desktop_list,
+            desktops::computer_desktop_switch,
+            desktops::computer_desktop_move_window,
+            computer::computer_screenshot_window,
+            computer::computer_pixel_color,
+            ocr::computer_ocr,
+            live_view::computer_view_start,
+            live_view::computer_view_stop,
             computer::computer_screen_size,
             computer::computer_mouse_position,
             computer::computer_mouse_move,
@@ -889,19 +1363,81 @@ fn main() {
             computer::computer_mouse_double_click,
             computer::computer_mouse_scroll,
             computer::computer_mouse_drag,
+            file_drop::computer_drop_file,
             computer::computer_key_type,
             computer::computer_key_press,
             computer::computer_hotkey,
+            computer::computer_key_down,
+            computer::computer_key_up,
+            computer::computer_release_all,
+            computer::computer_media,
+            emergency_stop::computer_emergency_stop,
+            emergency_stop::settings_get_input_safety,
+            emergency_stop::settings_set_input_safety,
+            audit::computer_audit_list,
+            audit::computer_audit_export,
+            approval::approval_respond,
+            approval::approval_list_always,
+            approval::approval_revoke_always,
             computer::computer_clipboard_get,
             computer::computer_clipboard_set,
+            // computer-use: session
+            session::computer_session_status,
+            session::computer_session_resume,
+            permissions::computer_permissions_status,
+            permissions::computer_permissions_request,
             // computer-use: OS
             computer::computer_launch_app,
+            computer::computer_open_url,
             computer::computer_run_shell,
+            processes::computer_process_spawn,
+            processes::computer_process_write,
+            processes::computer_process_kill,
+            processes::computer_process_list,
+            system::computer_system_info,
+            system::computer_lock_screen,
+            system::computer_sleep,
+            system::computer_keep_awake,
+            system::computer_system_process_list,
+            system::computer_system_process_kill,
+            browser::browser_launch,
+            browser::browser_attach,
+            browser::browser_close,
+            browser::browser_navigate,
+            browser::browser_query_dom,
+            browser::browser_click_selector,
+            browser::browser_extract_text,
+            browser::browser_screenshot_element,
+            shell_policy::shell_policy_get,
+            shell_policy::shell_policy_set,
+            shell_policy::shell_policy_check,
             // computer-use: files
             computer::computer_read_file,
             computer::computer_write_file,
             computer::computer_append_file,
+            computer::computer_read_file_range,
+            computer::computer_tail_file,
+            file_search::computer_search_files,
+            file_sandbox::file_sandbox_get,
+            file_sandbox::file_sandbox_set_roots,
+            file_sandbox::file_sandbox_grant,
+            file_sandbox::file_sandbox_revoke,
+            // terminal (PTY sessions)
+            terminal::terminal_open,
+            terminal::terminal_write,
+            terminal::terminal_read,
+            terminal::terminal_resize,
+            terminal::terminal_close,
+            // web
+            web::web_fetch,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if shutdown::on_exit_requested(app) {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
@@ -0,0 +1,192 @@
+//! Memory context for chat turns, and the audit trail behind it.
+//!
+//! The frontend owns the memory store; before each chat turn it passes the
+//! session's memories and the user's message to `memory_build_turn_context`,
+//! which ranks them by relevance, sends only new or changed ones after the
+//! first turn (see `MemoryManager::select_ranked_for_turn`), and records
+//! which memories went in with their scores. `memory_explain_context` then
+//! answers "why did the assistant know that?" for any recorded turn, so users
+//! can audit — and correct — what answers were based on.
+//!
+//! Memories can carry tags (a project or topic); passing `tags` limits a
+//! turn's context to memories that have all of them. Memories past their
+//! `expires_at` are left out and listed in the result, so the frontend can
+//! archive them.
+//!
+//! `memory_find_duplicates` spots memories that state the same fact under
+//! slightly different keys, and `memory_merge` consolidates them into one,
+//! carrying the merged keys' access records over to the survivor.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use agenthub_runtime::memory::{
+    find_duplicates, merge_entries, normalize_tags, DuplicateGroup, MemoryEntry, MemoryManager,
+    MemoryTier, MemoryTurnState, MergeStrategy,
+};
+use agenthub_runtime::memory_audit::{MemoryAccess, MemoryAccessLog, TurnRecord};
+use agenthub_runtime::tokenizer::DEFAULT_ESTIMATION_MODEL;
+use serde::Serialize;
+use tauri::State;
+
+const MAX_SESSIONS: usize = 256;
+const MAX_MEMORIES: usize = 1000;
+const DEFAULT_BUDGET_TOKENS: u32 = 1000;
+const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.75;
+
+#[derive(Default)]
+struct Inner {
+    states: HashMap<String, MemoryTurnState>,
+    log: MemoryAccessLog,
+}
+
+/// Per-session turn state and access records.
+#[derive(Default)]
+pub struct MemoryContexts(Mutex<Inner>);
+
+impl MemoryContexts {
+    /// Forgets what was sent to `session_id` and its records.
+    pub fn reset(&self, session_id: &str) {
+        let mut inner = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        inner.states.remove(session_id);
+        inner.log.clear(session_id);
+    }
+}
+
+/// Result of [`memory_build_turn_context`].
+#[derive(Serialize)]
+pub struct TurnContext {
+    /// Turn number to pass to [`memory_explain_context`].
+    pub turn: usize,
+    /// Text to add to the turn's system prompt; empty when nothing applies.
+    pub context: String,
+    /// Keys of the memories that have expired and were left out.
+    pub expired: Vec<String>,
+}
+
+/// Result of [`memory_merge`].
+#[derive(Serialize)]
+pub struct MergedMemory {
+    /// The consolidated memory, under the first merged memory's key.
+    pub memory: MemoryEntry,
+    /// Keys of the memories folded into it, for the frontend to remove and
+    /// record as its provenance.
+    pub merged_from: Vec<String>,
+}
+
+/// Builds the memory context of the next turn of `session_id` from
+/// `memories`, ranked against `query` (the user's message), and records it.
+/// `tier` defaults to `Full` and `budget_tokens` to 1000, counted with the
+/// tokenizer of `model` (the chat's model; GPT-4o's when omitted). With
+/// `tags`, only memories tagged with all of them are considered.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn memory_build_turn_context(
+    contexts: State<'_, MemoryContexts>,
+    session_id: String,
+    query: String,
+    memories: Vec<MemoryEntry>,
+    tier: Option<MemoryTier>,
+    budget_tokens: Option<u32>,
+    model: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<TurnContext, String> {
+    if memories.len() > MAX_MEMORIES {
+        return Err(format!("too many memories for one turn (max {MAX_MEMORIES})"));
+    }
+    let tier = tier.unwrap_or(MemoryTier::Full);
+    let budget_tokens = budget_tokens.unwrap_or(DEFAULT_BUDGET_TOKENS);
+    let model = model
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_ESTIMATION_MODEL.to_owned());
+
+    let mut inner = contexts.0.lock().unwrap_or_else(|e| e.into_inner());
+    if !inner.states.contains_key(&session_id) && inner.states.len() >= MAX_SESSIONS {
+        return Err(format!(
+            "too many sessions with memory context (max {MAX_SESSIONS}) — reset finished ones"
+        ));
+    }
+    let mut manager = MemoryManager::new();
+    let tags = tags.unwrap_or_default();
+    for mut entry in memories.into_iter().filter(|m| m.has_tags(&tags)) {
+        entry.tags = normalize_tags(entry.tags.iter().map(String::as_str));
+        manager.add(entry);
+    }
+    let expired = manager
+        .sweep_expired(chrono::Utc::now().timestamp())
+        .into_iter()
+        .map(|e| e.key)
+        .collect();
+    let state = inner.states.entry(session_id.clone()).or_default();
+    let selection = manager.select_ranked_for_turn(tier, budget_tokens, &model, &query, state);
+    let turn = inner.log.record(&session_id, &query, tier, budget_tokens, selection.memories);
+    Ok(TurnContext { turn, context: selection.context, expired })
+}
+
+/// Which memories were in the context of `turn` of `session_id` (the latest
+/// turn when omitted), with each one's relevance score, rank and whether it
+/// was written out, carried from an earlier turn, truncated or left out.
+#[tauri::command]
+pub fn memory_explain_context(
+    contexts: State<'_, MemoryContexts>,
+    session_id: String,
+    turn: Option<usize>,
+) -> Option<TurnRecord> {
+    let inner = contexts.0.lock().unwrap_or_else(|e| e.into_inner());
+    inner.log.explain(&session_id, turn).cloned()
+}
+
+/// How often each memory was in `session_id`'s context, most used first.
+#[tauri::command]
+pub fn memory_access_stats(contexts: State<'_, MemoryContexts>, session_id: String) -> Vec<MemoryAccess> {
+    let inner = contexts.0.lock().unwrap_or_else(|e| e.into_inner());
+    inner.log.access(&session_id)
+}
+
+/// Forgets what was sent to `session_id` and its records — call when the
+/// conversation is cleared or its history truncated, so memories are sent
+/// in full again.
+#[tauri::command]
+pub fn memory_reset_session(contexts: State<'_, MemoryContexts>, session_id: String) {
+    contexts.reset(&session_id);
+}
+
+/// Groups `memories` that look like the same fact — similar keys and
+/// values, scoring at least `threshold` (0–1, default 0.75).
+#[tauri::command]
+pub fn memory_find_duplicates(
+    memories: Vec<MemoryEntry>,
+    threshold: Option<f64>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    if memories.len() > MAX_MEMORIES {
+        return Err(format!("too many memories to compare (max {MAX_MEMORIES})"));
+    }
+    let threshold = threshold.unwrap_or(DEFAULT_DUPLICATE_THRESHOLD).clamp(0.0, 1.0);
+    Ok(find_duplicates(&memories, threshold))
+}
+
+/// Consolidates `memories` into one under the first one's key, keeping the
+/// value `strategy` picks (the first one's by default), and files the other
+/// keys' access records under it.
+#[tauri::command]
+pub fn memory_merge(
+    contexts: State<'_, MemoryContexts>,
+    memories: Vec<MemoryEntry>,
+    strategy: Option<MergeStrategy>,
+) -> Result<MergedMemory, String> {
+    if memories.len() < 2 {
+        return Err("merging needs at least two memories".into());
+    }
+    let memory = merge_entries(&memories, strategy.unwrap_or_default())
+        .ok_or("merging needs at least two memories")?;
+    let merged_from: Vec<String> = memories
+        .into_iter()
+        .map(|m| m.key)
+        .filter(|k| *k != memory.key)
+        .collect();
+    let mut inner = contexts.0.lock().unwrap_or_else(|e| e.into_inner());
+    for key in &merged_from {
+        inner.log.rename(key, &memory.key);
+    }
+    Ok(MergedMemory { memory, merged_from })
+}
@@ -0,0 +1,203 @@
+//! Exporting and importing memories.
+//!
+//! `memory_export` writes the memories the frontend passes (and, when
+//! given, conversation history) to a versioned JSON dump, for moving to
+//! another machine or sharing a curated set. `memory_import` reads a dump
+//! back and merges it into the frontend's current memories by key, which
+//! is the memory's identity; the frontend then stores the result. Both
+//! paths go through the [file sandbox](crate::file_sandbox), and exports
+//! outside its workspace need the user's approval.
+
+use agenthub_runtime::memory::MemoryEntry;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{file_sandbox, resources};
+
+/// Format version written by this build; newer dumps are rejected.
+const DUMP_VERSION: u32 = 1;
+
+/// Dump files larger than this (50 MB) are rejected on import.
+const MAX_DUMP_BYTES: u64 = 50 * 1024 * 1024;
+
+// ── File format ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize)]
+struct MemoryDump {
+    version: u32,
+    /// Unix time (seconds) of the export.
+    exported_at: i64,
+    memories: Vec<MemoryEntry>,
+    /// Conversation messages, as the frontend stores them; absent unless
+    /// exported with history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<Vec<serde_json::Value>>,
+}
+
+/// What to do with an imported memory whose key already exists.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStrategy {
+    /// Keep the existing memory.
+    #[default]
+    KeepExisting,
+    /// Replace it with the imported one.
+    Overwrite,
+    /// Drop every existing memory and take the dump as is.
+    Replace,
+}
+
+/// Result of [`memory_import`].
+#[derive(Serialize)]
+pub struct ImportedMemories {
+    /// The merged memories, for the frontend to store.
+    pub memories: Vec<MemoryEntry>,
+    /// History from the dump, when it has any.
+    pub history: Option<Vec<serde_json::Value>>,
+    pub added: usize,
+    pub updated: usize,
+    /// Imported memories dropped as duplicates.
+    pub skipped: usize,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+/// Merges `imported` into `existing` by key. Duplicate keys within the dump
+/// count once, the last one winning.
+fn merge(
+    existing: Vec<MemoryEntry>,
+    imported: Vec<MemoryEntry>,
+    strategy: ImportStrategy,
+) -> ImportedMemories {
+    let mut memories = if strategy == ImportStrategy::Replace { Vec::new() } else { existing };
+    let existing_len = memories.len();
+    let (mut added, mut updated, mut skipped) = (0, 0, 0);
+    for entry in imported {
+        match memories.iter().position(|m| m.key == entry.key) {
+            None => {
+                memories.push(entry);
+                added += 1;
+            }
+            Some(i) if i >= existing_len => {
+                memories[i] = entry;
+                skipped += 1;
+            }
+            Some(_) if strategy == ImportStrategy::KeepExisting => skipped += 1,
+            Some(i) => {
+                memories[i] = entry;
+                updated += 1;
+            }
+        }
+    }
+    ImportedMemories { memories, history: None, added, updated, skipped }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Writes `memories`, and `history` when given, to `path` as a versioned
+/// JSON dump. `path` must be writable under the file sandbox.
+#[tauri::command]
+pub async fn memory_export(
+    app: AppHandle,
+    path: String,
+    memories: Vec<MemoryEntry>,
+    history: Option<Vec<serde_json::Value>>,
+) -> Result<usize, String> {
+    let count = memories.len();
+    let dump = MemoryDump {
+        version: DUMP_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        memories,
+        history,
+    };
+    let file = serde_json::to_vec_pretty(&dump)
+        .map_err(|e| format!("dump serialization failed: {e}"))?;
+    let path = file_sandbox::writable_path(&app, &path).await?;
+
+    tokio::task::spawn_blocking(move || {
+        resources::ensure_disk_space(&app, &path, file.len() as u64)?;
+        std::fs::write(&path, file).map_err(|e| format!("write failed: {e}"))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)?;
+    Ok(count)
+}
+
+/// Reads the dump at `path` and merges its memories into `existing` by key
+/// according to `merge_strategy` (`keep_existing` by default). `path` must
+/// be readable under the file sandbox.
+#[tauri::command]
+pub async fn memory_import(
+    app: AppHandle,
+    path: String,
+    existing: Vec<MemoryEntry>,
+    merge_strategy: Option<ImportStrategy>,
+) -> Result<ImportedMemories, String> {
+    let bytes = tokio::task::spawn_blocking(move || {
+        let path = file_sandbox::resolve(&app, &path, file_sandbox::Access::Read)?;
+        let meta = std::fs::metadata(&path).map_err(|e| format!("stat failed: {e}"))?;
+        if meta.len() > MAX_DUMP_BYTES {
+            return Err(format!("dump too large: {} bytes (max {MAX_DUMP_BYTES})", meta.len()));
+        }
+        std::fs::read(&path).map_err(|e| format!("read failed: {e}"))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)?;
+
+    let dump: MemoryDump =
+        serde_json::from_slice(&bytes).map_err(|e| format!("not a memory dump: {e}"))?;
+    if dump.version > DUMP_VERSION {
+        return Err(format!(
+            "memory dump version {} is newer than this app supports ({DUMP_VERSION})",
+            dump.version
+        ));
+    }
+    let mut result = merge(existing, dump.memories, merge_strategy.unwrap_or_default());
+    result.history = dump.history;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agenthub_runtime::memory::MemoryTier;
+
+    fn entry(key: &str, value: &str) -> MemoryEntry {
+        MemoryEntry { key: key.into(), value: value.into(), tier: MemoryTier::Full, tags: Vec::new(), expires_at: None }
+    }
+
+    fn values(merged: &ImportedMemories) -> Vec<(&str, &str)> {
+        merged.memories.iter().map(|m| (m.key.as_str(), m.value.as_str())).collect()
+    }
+
+    fn existing() -> Vec<MemoryEntry> {
+        vec![entry("home", "Hanoi"), entry("editor", "vim")]
+    }
+
+    fn imported() -> Vec<MemoryEntry> {
+        vec![entry("editor", "helix"), entry("lang", "Rust"), entry("lang", "Rust 2021")]
+    }
+
+    #[test]
+    fn keep_existing_only_adds_new_keys() {
+        let merged = merge(existing(), imported(), ImportStrategy::KeepExisting);
+        assert_eq!(values(&merged), [("home", "Hanoi"), ("editor", "vim"), ("lang", "Rust 2021")]);
+        assert_eq!((merged.added, merged.updated, merged.skipped), (1, 0, 2));
+    }
+
+    #[test]
+    fn overwrite_updates_existing_keys() {
+        let merged = merge(existing(), imported(), ImportStrategy::Overwrite);
+        assert_eq!(values(&merged), [("home", "Hanoi"), ("editor", "helix"), ("lang", "Rust 2021")]);
+        assert_eq!((merged.added, merged.updated, merged.skipped), (1, 1, 1));
+    }
+
+    #[test]
+    fn replace_takes_the_dump_as_is() {
+        let merged = merge(existing(), imported(), ImportStrategy::Replace);
+        assert_eq!(values(&merged), [("editor", "helix"), ("lang", "Rust 2021")]);
+        assert_eq!((merged.added, merged.updated, merged.skipped), (2, 0, 1));
+    }
+}
@@ -0,0 +1,236 @@
+//! Model listing for the direct (BYOK) providers, for the UI's model picker.
+//!
+//! Each provider's list endpoint reports different metadata: Gemini and
+//! Cohere give context windows and capabilities, some OpenAI-compatible
+//! servers (Groq, Mistral, OpenRouter) add context windows, and OpenAI and
+//! Anthropic give little beyond the ID. Missing capability flags are inferred
+//! from well-known model ID patterns; unknown context windows stay `null`.
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::providers::{
+    openai_compat_base, ANTHROPIC_API_BASE, ANTHROPIC_VERSION, GOOGLE_API_BASE, LOCAL_OPENAI,
+};
+use crate::{local_openai, redact, settings, AppState};
+
+const COHERE_MODELS_URL: &str = "https://api.cohere.com/v1/models";
+
+/// Every current Claude model accepts 200k input tokens; the models endpoint
+/// doesn't say.
+const ANTHROPIC_CONTEXT_WINDOW: u64 = 200_000;
+
+/// What a model can do.
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct ModelCapabilities {
+    pub chat: bool,
+    pub vision: bool,
+    pub tools: bool,
+    pub reasoning: bool,
+    pub embedding: bool,
+}
+
+/// One entry of [`models_list`].
+#[derive(Serialize)]
+pub struct ModelInfo {
+    /// ID to pass as `model`.
+    pub id: String,
+    pub display_name: Option<String>,
+    /// Input context window in tokens, when known.
+    pub context_window: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub capabilities: ModelCapabilities,
+}
+
+impl ModelInfo {
+    fn new(id: String) -> Self {
+        let capabilities = infer_capabilities(&id);
+        Self { id, display_name: None, context_window: None, max_output_tokens: None, capabilities }
+    }
+}
+
+/// ID fragments of models that aren't chat models.
+const NON_CHAT_HINTS: &[&str] = &["embed", "whisper", "tts", "dall-e", "rerank", "moderation", "guard"];
+
+const VISION_HINTS: &[&str] = &[
+    "vision", "gpt-4o", "gpt-4.1", "gpt-5", "claude-3", "claude-sonnet", "claude-opus", "gemini",
+    "pixtral", "llava",
+];
+
+const TOOLS_HINTS: &[&str] = &[
+    "gpt-4", "gpt-5", "gpt-3.5", "claude", "gemini", "mistral", "command-r", "llama-3", "llama3",
+    "qwen", "grok",
+];
+
+const REASONING_HINTS: &[&str] = &["reason", "thinking", "-r1", "qwq"];
+
+/// Capability guess from common model naming.
+fn infer_capabilities(id: &str) -> ModelCapabilities {
+    let id = id.to_ascii_lowercase();
+    let has = |hints: &[&str]| hints.iter().any(|h| id.contains(h));
+    let chat = !has(NON_CHAT_HINTS);
+    // OpenAI's o-series: o1, o3-mini, o4-mini, …
+    let o_series = id.starts_with('o') && id.chars().nth(1).is_some_and(|c| c.is_ascii_digit());
+    ModelCapabilities {
+        chat,
+        vision: chat && has(VISION_HINTS),
+        tools: chat && has(TOOLS_HINTS),
+        reasoning: chat && (o_series || has(REASONING_HINTS)),
+        embedding: id.contains("embed"),
+    }
+}
+
+fn u64_at(val: &serde_json::Value, key: &str) -> Option<u64> {
+    val.get(key).and_then(|v| v.as_u64())
+}
+
+async fn get_json(req: reqwest::RequestBuilder, provider: &str) -> Result<serde_json::Value, String> {
+    let resp = req.send().await.map_err(redact::error)?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(redact::redact(&format!("{provider} models error {status}: {body}")));
+    }
+    resp.json().await.map_err(redact::error)
+}
+
+/// `GET /models` on an OpenAI-compatible API.
+fn parse_openai_models(val: &serde_json::Value) -> Vec<ModelInfo> {
+    let Some(data) = val.get("data").and_then(|d| d.as_array()) else {
+        return Vec::new();
+    };
+    data.iter()
+        .filter_map(|m| {
+            let mut info = ModelInfo::new(m.get("id")?.as_str()?.to_owned());
+            info.display_name = m.get("name").and_then(|n| n.as_str()).map(String::from);
+            // Groq, OpenRouter / LM Studio and Mistral name this differently.
+            info.context_window = u64_at(m, "context_window")
+                .or_else(|| u64_at(m, "context_length"))
+                .or_else(|| u64_at(m, "max_context_length"));
+            if let Some(caps) = m.get("capabilities").filter(|c| c.is_object()) {
+                let flag = |k: &str| caps.get(k).and_then(|v| v.as_bool());
+                info.capabilities.chat = flag("completion_chat").unwrap_or(info.capabilities.chat);
+                info.capabilities.tools = flag("function_calling").unwrap_or(info.capabilities.tools);
+                info.capabilities.vision = flag("vision").unwrap_or(info.capabilities.vision);
+            }
+            Some(info)
+        })
+        .collect()
+}
+
+fn parse_anthropic_models(val: &serde_json::Value) -> Vec<ModelInfo> {
+    let Some(data) = val.get("data").and_then(|d| d.as_array()) else {
+        return Vec::new();
+    };
+    data.iter()
+        .filter_map(|m| {
+            let mut info = ModelInfo::new(m.get("id")?.as_str()?.to_owned());
+            info.display_name = m.get("display_name").and_then(|n| n.as_str()).map(String::from);
+            info.context_window = Some(ANTHROPIC_CONTEXT_WINDOW);
+            Some(info)
+        })
+        .collect()
+}
+
+fn parse_google_models(val: &serde_json::Value) -> Vec<ModelInfo> {
+    let Some(models) = val.get("models").and_then(|d| d.as_array()) else {
+        return Vec::new();
+    };
+    models
+        .iter()
+        .filter_map(|m| {
+            let name = m.get("name")?.as_str()?;
+            let mut info = ModelInfo::new(name.strip_prefix("models/").unwrap_or(name).to_owned());
+            info.display_name = m.get("displayName").and_then(|n| n.as_str()).map(String::from);
+            info.context_window = u64_at(m, "inputTokenLimit");
+            info.max_output_tokens = u64_at(m, "outputTokenLimit");
+            let methods: Vec<&str> = m
+                .get("supportedGenerationMethods")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|s| s.as_str()).collect())
+                .unwrap_or_default();
+            info.capabilities.chat = methods.contains(&"generateContent");
+            info.capabilities.embedding = methods.iter().any(|m| m.starts_with("embed"));
+            info.capabilities.reasoning = m.get("thinking").and_then(|t| t.as_bool()).unwrap_or(false);
+            Some(info)
+        })
+        .collect()
+}
+
+fn parse_cohere_models(val: &serde_json::Value) -> Vec<ModelInfo> {
+    let Some(models) = val.get("models").and_then(|d| d.as_array()) else {
+        return Vec::new();
+    };
+    models
+        .iter()
+        .filter_map(|m| {
+            let mut info = ModelInfo::new(m.get("name")?.as_str()?.to_owned());
+            info.context_window = u64_at(m, "context_length");
+            let list = |key: &str| -> Vec<&str> {
+                m.get(key)
+                    .and_then(|v| v.as_array())
+                    .map(|a| a.iter().filter_map(|s| s.as_str()).collect())
+                    .unwrap_or_default()
+            };
+            let endpoints = list("endpoints");
+            let features = list("features");
+            info.capabilities.chat = endpoints.contains(&"chat");
+            info.capabilities.embedding = endpoints.contains(&"embed");
+            info.capabilities.tools = features.iter().any(|f| f.contains("tools"));
+            info.capabilities.vision = features.contains(&"vision") || info.capabilities.vision;
+            Some(info)
+        })
+        .collect()
+}
+
+// ── Command ────────────────────────────────────────────────────────────────────
+
+/// Lists the models `provider` offers to `api_key`, with context window and
+/// capability flags where known. `custom` and `local-openai` need no key.
+/// Bedrock isn't supported (its model catalogue is a separate AWS API).
+#[tauri::command]
+pub async fn models_list(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    provider: String,
+    api_key: Option<String>,
+) -> Result<Vec<ModelInfo>, String> {
+    let http = &state.http_client;
+    let key = api_key.as_deref().unwrap_or("");
+    let mut models = match provider.as_str() {
+        "anthropic" => {
+            let req = http
+                .get(format!("{ANTHROPIC_API_BASE}/models?limit=1000"))
+                .header("x-api-key", key)
+                .header("anthropic-version", ANTHROPIC_VERSION);
+            parse_anthropic_models(&get_json(req, "Anthropic").await?)
+        }
+        "google" | "gemini" => {
+            let req = http.get(format!("{GOOGLE_API_BASE}/models?pageSize=1000&key={key}"));
+            parse_google_models(&get_json(req, "Google").await?)
+        }
+        "cohere" => {
+            let req = http.get(format!("{COHERE_MODELS_URL}?page_size=1000")).bearer_auth(key);
+            parse_cohere_models(&get_json(req, "Cohere").await?)
+        }
+        "bedrock" => return Err("listing Bedrock models is not supported — enter a model ID".into()),
+        other => {
+            // OpenAI-compatible, including `custom` and `local-openai`.
+            let base = match other {
+                "custom" => settings::load_custom_provider(&app)
+                    .ok_or("the custom provider has no base URL — set one in Settings → Providers")?
+                    .base_url,
+                LOCAL_OPENAI => local_openai::detect(http)
+                    .await
+                    .ok_or("no local OpenAI-compatible server found")?
+                    .base_url,
+                _ => openai_compat_base(other).to_owned(),
+            };
+            let req = http.get(format!("{base}/models"));
+            let req = if key.is_empty() { req } else { req.bearer_auth(key) };
+            parse_openai_models(&get_json(req, other).await?)
+        }
+    };
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
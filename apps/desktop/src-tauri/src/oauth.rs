@@ -0,0 +1,309 @@
+//! Device-code sign-in and access-token refresh for the cloud gateway.
+//!
+//! `auth_device_start` asks the tenant's gateway for a device code (RFC 8628)
+//! and returns the user code to show; the code is also announced on
+//! `auth:device-code`. The token endpoint is then polled in the background
+//! until the user approves, denies or lets the code expire, and the outcome
+//! is emitted on `auth:device-done`.
+//!
+//! Refresh tokens — from this flow, or from `auth_login` when the gateway
+//! issues one — sit next to the access token in the credential store.
+//! [`fresh_token`] swaps an access token that is about to expire (per its
+//! JWT `exp` claim) for a new one before a gateway call, so sessions no
+//! longer lapse silently.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{redact, shutdown, tenants, AppState};
+
+const REFRESH_TOKEN_KEY: &str = "refresh_token";
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// Access tokens are refreshed this close to their expiry.
+const REFRESH_MARGIN_SECS: i64 = 60;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Added to the poll interval on each `slow_down`.
+const SLOW_DOWN_STEP: Duration = Duration::from_secs(5);
+
+/// Tokens issued by the gateway's token endpoint.
+#[derive(Deserialize)]
+pub struct TokenGrant {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+/// Error body of the token endpoint (RFC 6749 §5.2).
+#[derive(Deserialize)]
+struct OAuthError {
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// Result of `auth_device_start` and payload of `auth:device-code`.
+#[derive(Serialize, Clone)]
+pub struct DeviceCode {
+    pub tenant: String,
+    /// Code the user enters at `verification_uri`.
+    pub user_code: String,
+    pub verification_uri: String,
+    /// `verification_uri` with the code filled in, when the gateway offers one.
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until the code expires.
+    pub expires_in: u64,
+}
+
+/// Payload of `auth:device-done`.
+#[derive(Serialize, Clone)]
+struct DeviceDone {
+    tenant: String,
+    authenticated: bool,
+    /// `access_denied`, `expired_token`, `cancelled` or another failure.
+    error: Option<String>,
+}
+
+// ── Token storage ──────────────────────────────────────────────────────────────
+
+fn refresh_token_key(tenant: &str) -> String {
+    if tenant == tenants::DEFAULT_TENANT {
+        REFRESH_TOKEN_KEY.to_owned()
+    } else {
+        format!("{REFRESH_TOKEN_KEY}:{tenant}")
+    }
+}
+
+pub fn delete_refresh_token(app: &AppHandle, tenant: &str) {
+    crate::delete_secret(app, &refresh_token_key(tenant));
+}
+
+/// Persists a grant. Without a new refresh token the stored one is kept, as
+/// gateways that don't rotate refresh tokens leave it out.
+pub fn store_grant(app: &AppHandle, tenant: &str, grant: &TokenGrant) {
+    crate::save_token(app, tenant, &grant.access_token);
+    if let Some(refresh) = &grant.refresh_token {
+        crate::save_secret(app, &refresh_token_key(tenant), refresh);
+    }
+}
+
+/// `exp` claim of a JWT access token; `None` for opaque tokens.
+pub fn token_expiry(token: &str) -> Option<i64> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims.get("exp")?.as_i64()
+}
+
+fn expires_soon(token: &str) -> bool {
+    token_expiry(token).is_some_and(|exp| exp - chrono::Utc::now().timestamp() < REFRESH_MARGIN_SECS)
+}
+
+// ── Refresh ────────────────────────────────────────────────────────────────────
+
+/// Serializes refreshes, so concurrent calls don't spend a rotating refresh
+/// token twice.
+fn refresh_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+async fn refresh(app: &AppHandle, http: &reqwest::Client, tenant: &tenants::Tenant) -> Result<String, String> {
+    let key = refresh_token_key(&tenant.id);
+    let refresh_token = crate::load_secret(app, &key).ok_or("no refresh token")?;
+    let resp = http
+        .post(format!("{}/v1/auth/token", tenant.gateway_url))
+        .json(&serde_json::json!({
+            "grant_type":    "refresh_token",
+            "refresh_token": refresh_token,
+        }))
+        .send()
+        .await
+        .map_err(redact::error)?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        // A rejected refresh token won't work next time either.
+        if resp.json::<OAuthError>().await.is_ok_and(|e| e.error == "invalid_grant") {
+            crate::delete_secret(app, &key);
+        }
+        return Err(format!("token refresh failed: HTTP {}", status.as_u16()));
+    }
+    let grant: TokenGrant = resp.json().await.map_err(redact::error)?;
+    store_grant(app, &tenant.id, &grant);
+    Ok(grant.access_token)
+}
+
+/// The stored access token of `tenant`, refreshed first when it expires
+/// within a minute and a refresh token is stored. If the refresh fails the
+/// old token is returned and the gateway's 401 reports it.
+pub async fn fresh_token(app: &AppHandle, http: &reqwest::Client, tenant: &tenants::Tenant) -> Option<String> {
+    let token = crate::load_token(app, &tenant.id)?;
+    if !expires_soon(&token) {
+        return Some(token);
+    }
+    let _guard = refresh_lock().lock().await;
+    // Another call may have refreshed it while this one waited.
+    let token = crate::load_token(app, &tenant.id)?;
+    if !expires_soon(&token) {
+        return Some(token);
+    }
+    Some(refresh(app, http, tenant).await.unwrap_or(token))
+}
+
+// ── Device flow ────────────────────────────────────────────────────────────────
+
+/// Running device flow per tenant; a new start or a cancel replaces the ID,
+/// which ends the old poll loop.
+fn device_flows() -> &'static Mutex<HashMap<String, String>> {
+    static FLOWS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    FLOWS.get_or_init(Default::default)
+}
+
+fn is_current(tenant: &str, flow: &str) -> bool {
+    device_flows()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(tenant)
+        .is_some_and(|id| id == flow)
+}
+
+/// Polls until the device code is approved, denied or expired.
+async fn poll_device(
+    app: &AppHandle,
+    http: &reqwest::Client,
+    tenant: &tenants::Tenant,
+    flow: &str,
+    code: &DeviceCodeResponse,
+) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = code.interval.map_or(DEFAULT_POLL_INTERVAL, Duration::from_secs);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = shutdown::cancelled() => return Err("cancelled".into()),
+        }
+        if !is_current(&tenant.id, flow) {
+            return Err("cancelled".into());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err("expired_token".into());
+        }
+        let resp = http
+            .post(format!("{}/v1/auth/token", tenant.gateway_url))
+            .json(&serde_json::json!({
+                "grant_type":  DEVICE_GRANT,
+                "device_code": code.device_code,
+            }))
+            .send()
+            .await
+            .map_err(redact::error)?;
+
+        if resp.status().is_success() {
+            let grant: TokenGrant = resp.json().await.map_err(redact::error)?;
+            store_grant(app, &tenant.id, &grant);
+            return Ok(());
+        }
+        let status = resp.status();
+        match resp.json::<OAuthError>().await.map(|e| e.error) {
+            Ok(e) if e == "authorization_pending" => {}
+            Ok(e) if e == "slow_down" => interval += SLOW_DOWN_STEP,
+            Ok(e) => return Err(e),
+            Err(_) => return Err(format!("device login failed: HTTP {}", status.as_u16())),
+        }
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Starts a device-code sign-in with `tenant`'s gateway (default when
+/// omitted) and returns the code for the user to enter. Completion is
+/// reported on `auth:device-done`; starting again replaces a pending flow.
+#[tauri::command]
+pub async fn auth_device_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    tenant: Option<String>,
+) -> Result<DeviceCode, String> {
+    shutdown::ensure_running()?;
+    let tenant = tenants::resolve(&app, &state, tenant.as_deref())?;
+    let resp = state
+        .http_client
+        .post(format!("{}/v1/auth/device/code", tenant.gateway_url))
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .map_err(redact::error)?;
+    if !resp.status().is_success() {
+        return Err(format!("device login unavailable: HTTP {}", resp.status().as_u16()));
+    }
+    let code: DeviceCodeResponse = resp.json().await.map_err(redact::error)?;
+
+    let flow = uuid::Uuid::new_v4().to_string();
+    device_flows()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(tenant.id.clone(), flow.clone());
+
+    let shown = DeviceCode {
+        tenant: tenant.id.clone(),
+        user_code: code.user_code.clone(),
+        verification_uri: code.verification_uri.clone(),
+        verification_uri_complete: code.verification_uri_complete.clone(),
+        expires_in: code.expires_in,
+    };
+    let _ = app.emit("auth:device-code", shown.clone());
+
+    let http = state.http_client.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = poll_device(&app, &http, &tenant, &flow, &code).await;
+        {
+            let mut flows = device_flows().lock().unwrap_or_else(|e| e.into_inner());
+            if flows.get(&tenant.id) != Some(&flow) {
+                // Replaced by a newer flow, which reports for itself.
+                return;
+            }
+            flows.remove(&tenant.id);
+        }
+        let _ = app.emit("auth:device-done", DeviceDone {
+            tenant: tenant.id,
+            authenticated: result.is_ok(),
+            error: result.err(),
+        });
+    });
+    Ok(shown)
+}
+
+/// Stops a pending device-code sign-in of `tenant` (default when omitted).
+#[tauri::command]
+pub async fn auth_device_cancel(app: AppHandle, tenant: Option<String>) {
+    let tenant = tenant.unwrap_or_else(|| tenants::DEFAULT_TENANT.into());
+    let removed = device_flows()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&tenant)
+        .is_some();
+    if removed {
+        let _ = app.emit("auth:device-done", DeviceDone {
+            tenant,
+            authenticated: false,
+            error: Some("cancelled".into()),
+        });
+    }
+}
@@ -0,0 +1,138 @@
+//! Text recognition over screen captures.
+//!
+//! `computer_ocr` reads the text off the primary screen (or a region of it)
+//! with Tesseract, which is much faster and cheaper than asking a vision
+//! model. Each word comes back with its bounding box in screen coordinates
+//! (physical pixels, like `computer_screenshot_region`), so an agent can
+//! click on what it read.
+//!
+//! Tesseract 4 or newer must be installed with its `tesseract` command on
+//! `PATH` (`brew install tesseract`, `apt install tesseract-ocr`, or the
+//! UB Mannheim installer on Windows).
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::computer::{self, ScreenRegion};
+
+/// Words recognized with less confidence than this (0–100) are dropped.
+const MIN_CONFIDENCE: f32 = 30.0;
+/// Tesseract's TSV level for single words.
+const WORD_LEVEL: &str = "5";
+
+/// One recognized word.
+#[derive(Serialize)]
+pub struct OcrWord {
+    pub text: String,
+    /// Bounding box in screen coordinates (physical pixels).
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// 0–100.
+    pub confidence: f32,
+}
+
+/// Result of `computer_ocr`.
+#[derive(Serialize)]
+pub struct OcrResult {
+    /// All words, one line of text per recognized line.
+    pub text: String,
+    pub words: Vec<OcrWord>,
+}
+
+fn validate_lang(lang: &str) -> Result<(), String> {
+    let valid = !lang.is_empty()
+        && lang.len() <= 64
+        && lang.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+'));
+    if !valid {
+        return Err(format!("invalid OCR language '{lang}'"));
+    }
+    Ok(())
+}
+
+/// Runs `tesseract` on a PNG and returns its TSV output.
+fn tesseract(png: &[u8], lang: &str) -> Result<String, String> {
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "-l", lang, "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("OCR unavailable (is tesseract installed and on PATH?): {e}"))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(png).map_err(|e| format!("tesseract write failed: {e}"))?;
+    }
+    let out = child.wait_with_output().map_err(|e| format!("tesseract failed: {e}"))?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(format!("tesseract failed: {}", err.trim()));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Parses Tesseract's TSV, shifting boxes by the capture's `origin`.
+fn parse_tsv(tsv: &str, origin: (i32, i32)) -> OcrResult {
+    let mut words = Vec::new();
+    let mut text = String::new();
+    let mut line_key = None;
+    // Columns: level page block par line word left top width height conf text
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != WORD_LEVEL {
+            continue;
+        }
+        let word = cols[11].trim();
+        let confidence: f32 = cols[10].parse().unwrap_or(-1.0);
+        if word.is_empty() || confidence < MIN_CONFIDENCE {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<i64>().unwrap_or(0);
+        let key = (cols[2], cols[3], cols[4]);
+        if line_key.is_some_and(|k| k != key) {
+            text.push('\n');
+        } else if line_key.is_some() {
+            text.push(' ');
+        }
+        line_key = Some(key);
+        text.push_str(word);
+        words.push(OcrWord {
+            text: word.to_owned(),
+            x: origin.0 + num(6) as i32,
+            y: origin.1 + num(7) as i32,
+            width: num(8) as u32,
+            height: num(9) as u32,
+            confidence,
+        });
+    }
+    OcrResult { text, words }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Recognizes the text on the primary screen, or in `region` of it.
+/// `lang` is a Tesseract language (`eng` by default; e.g. `eng+vie`), whose
+/// data must be installed. Requires Screen Recording permission on macOS.
+#[tauri::command]
+pub async fn computer_ocr(
+    app: AppHandle,
+    region: Option<ScreenRegion>,
+    lang: Option<String>,
+) -> Result<OcrResult, String> {
+    let lang = lang.map(|l| l.trim().to_owned()).unwrap_or_else(|| "eng".into());
+    validate_lang(&lang)?;
+    tokio::task::spawn_blocking(move || {
+        let img = computer::capture(&app, region)?;
+        let png = computer::encode_png(&img)?;
+        drop(img);
+        let tsv = tesseract(&png, &lang)?;
+        let origin = region.map_or((0, 0), |r| (r.x, r.y));
+        Ok(parse_tsv(&tsv, origin))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
@@ -0,0 +1,284 @@
+//! Best-effort parsing of incomplete JSON documents.
+//!
+//! Structured-output streams arrive as text deltas, so the accumulated output
+//! is usually an unterminated JSON document (`{"rows":[{"a":1},{"a"`). This
+//! module closes open strings and containers and drops dangling tokens so the
+//! UI can render the object assembled so far instead of waiting for `[DONE]`.
+//!
+//! [`PartialJson`] keeps its scanner state between deltas, so each one is
+//! scanned once however long the document grows.
+
+/// Returns `true` when the accumulated stream output looks like a JSON
+/// document (optionally wrapped in a Markdown code fence).
+pub fn looks_like_json(text: &str) -> bool {
+    matches!(strip_fence(text).chars().next(), Some('{') | Some('['))
+}
+
+/// A possibly-truncated JSON document, fed one delta at a time.
+#[derive(Default)]
+pub struct PartialJson {
+    /// Deltas so far; no longer appended once the document is known not to
+    /// be JSON.
+    text: String,
+    /// Where the document starts in `text`, once past any leading
+    /// whitespace and code fence.
+    start: Option<usize>,
+    not_json: bool,
+    /// Bytes of `text` already scanned.
+    scanned: usize,
+    closers: Vec<char>,
+    in_string: bool,
+    escaped: bool,
+    /// Last position in `text` where the prefix can be closed into valid
+    /// JSON, paired with the closers required at that point.
+    checkpoint: Option<(usize, String)>,
+    /// End of the top-level value once it is closed.
+    end: Option<usize>,
+}
+
+impl PartialJson {
+    /// Appends the next delta and scans it.
+    pub fn push(&mut self, delta: &str) {
+        if self.not_json || self.end.is_some() {
+            return;
+        }
+        self.text.push_str(delta);
+        let start = match self.start {
+            Some(start) => start,
+            None => match locate(&self.text) {
+                Locate::Found(start) => {
+                    self.start = Some(start);
+                    self.scanned = start;
+                    start
+                }
+                Locate::Pending => return,
+                Locate::NotJson => {
+                    self.not_json = true;
+                    self.text = String::new();
+                    return;
+                }
+            },
+        };
+        debug_assert!(self.scanned >= start);
+
+        for (i, c) in self.text[self.scanned..].char_indices() {
+            let i = self.scanned + i;
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if c == '\\' {
+                    self.escaped = true;
+                } else if c == '"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match c {
+                '"' => self.in_string = true,
+                '{' | '[' => {
+                    self.closers.push(if c == '{' { '}' } else { ']' });
+                    self.checkpoint = Some((i + 1, closing(&self.closers)));
+                }
+                '}' | ']' => {
+                    self.closers.pop();
+                    if self.closers.is_empty() {
+                        // A complete top-level value; anything after it is noise.
+                        self.end = Some(i + 1);
+                        return;
+                    }
+                    self.checkpoint = Some((i + 1, closing(&self.closers)));
+                }
+                ',' => self.checkpoint = Some((i, closing(&self.closers))),
+                _ => {}
+            }
+        }
+        self.scanned = self.text.len();
+    }
+
+    /// The object assembled so far; `None` when the text is not a JSON
+    /// object/array or nothing meaningful can be recovered yet.
+    pub fn value(&self) -> Option<serde_json::Value> {
+        let start = self.start?;
+        if let Some(end) = self.end {
+            return serde_json::from_str(&self.text[start..end]).ok();
+        }
+
+        // Greedy attempt: keep the trailing partial value (e.g. a half-streamed
+        // string) so long text fields render progressively.
+        let mut greedy = self.text[start..].trim_end().to_owned();
+        if self.in_string {
+            if self.escaped {
+                greedy.pop();
+            }
+            trim_partial_unicode_escape(&mut greedy);
+            greedy.push('"');
+        } else if greedy.ends_with(',') {
+            greedy.pop();
+        }
+        greedy.push_str(&closing(&self.closers));
+        if let Ok(v) = serde_json::from_str(&greedy) {
+            return Some(v);
+        }
+
+        let (end, tail) = self.checkpoint.as_ref()?;
+        let mut candidate = self.text[start..*end].trim_end().to_owned();
+        if candidate.ends_with(',') {
+            candidate.pop();
+        }
+        candidate.push_str(tail);
+        serde_json::from_str(&candidate).ok()
+    }
+}
+
+enum Locate {
+    Found(usize),
+    /// Only whitespace or part of a code fence so far.
+    Pending,
+    NotJson,
+}
+
+/// Finds the start of the document, like [`strip_fence`] but telling a
+/// fence still arriving from text that isn't JSON.
+fn locate(text: &str) -> Locate {
+    let t = text.trim_start();
+    if t.is_empty() || "```".starts_with(t) {
+        return Locate::Pending;
+    }
+    let body = match t.strip_prefix("```") {
+        Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_alphanumeric()).trim_start(),
+        None => t,
+    };
+    match body.chars().next() {
+        Some('{' | '[') => Locate::Found(text.len() - body.len()),
+        None => Locate::Pending,
+        Some(_) => Locate::NotJson,
+    }
+}
+
+/// Strips leading whitespace and an optional opening ```` ```json ```` fence.
+fn strip_fence(text: &str) -> &str {
+    let t = text.trim_start();
+    match t.strip_prefix("```") {
+        Some(rest) => rest
+            .trim_start_matches(|c: char| c.is_ascii_alphanumeric())
+            .trim_start(),
+        None => t,
+    }
+}
+
+fn closing(closers: &[char]) -> String {
+    closers.iter().rev().collect()
+}
+
+/// Drops a trailing `\uXXX` escape that has not received all four hex digits.
+fn trim_partial_unicode_escape(s: &mut String) {
+    let bytes = s.as_bytes();
+    for digits in 0..4 {
+        let Some(start) = bytes.len().checked_sub(digits + 2) else {
+            break;
+        };
+        if &bytes[start..start + 2] == b"\\u"
+            && bytes[start + 2..].iter().all(u8::is_ascii_hexdigit)
+        {
+            s.truncate(start);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_partial(text: &str) -> Option<serde_json::Value> {
+        let mut doc = PartialJson::default();
+        doc.push(text);
+        doc.value()
+    }
+
+    /// Feeds `text` one character at a time, checking each prefix against
+    /// a fresh parse of it.
+    fn streamed(text: &str) -> Option<serde_json::Value> {
+        let mut doc = PartialJson::default();
+        for (i, c) in text.char_indices() {
+            doc.push(c.encode_utf8(&mut [0; 4]));
+            let prefix = &text[..i + c.len_utf8()];
+            assert_eq!(doc.value(), parse_partial(prefix), "after {prefix:?}");
+        }
+        doc.value()
+    }
+
+    #[test]
+    fn complete_documents_parse_as_is() {
+        assert_eq!(parse_partial(r#"{"a":[1,2],"b":"c"}"#), Some(json!({"a": [1, 2], "b": "c"})));
+        assert_eq!(parse_partial("[]"), Some(json!([])));
+        // Text after the top-level value is ignored.
+        assert_eq!(parse_partial(r#"{"a":1} and more"#), Some(json!({"a": 1})));
+    }
+
+    #[test]
+    fn truncated_strings_render_progressively() {
+        assert_eq!(parse_partial(r#"{"title":"Hello, wor"#), Some(json!({"title": "Hello, wor"})));
+        // A dangling key has no value yet, so it is dropped.
+        assert_eq!(parse_partial(r#"{"a":1,"tit"#), Some(json!({"a": 1})));
+        assert_eq!(parse_partial(r#"{"a":1,"title":"#), Some(json!({"a": 1})));
+    }
+
+    #[test]
+    fn truncated_escapes_are_dropped() {
+        assert_eq!(parse_partial(r#"{"a":"line\"#), Some(json!({"a": "line"})));
+        assert_eq!(parse_partial(r#"{"a":"quote \" and\n"#), Some(json!({"a": "quote \" and\n"})));
+        assert_eq!(parse_partial(r#"{"a":"caf\u00"#), Some(json!({"a": "caf"})));
+        assert_eq!(parse_partial(r#"{"a":"café"#), Some(json!({"a": "café"})));
+        // Brackets inside strings aren't structure.
+        assert_eq!(parse_partial(r#"{"a":"[{\"","b"#), Some(json!({"a": "[{\""})));
+    }
+
+    #[test]
+    fn unclosed_containers_are_closed() {
+        assert_eq!(parse_partial(r#"{"rows":[{"a":1},{"a""#), Some(json!({"rows": [{"a": 1}, {}]})));
+        assert_eq!(parse_partial(r#"[[1,2],[3,"#), Some(json!([[1, 2], [3]])));
+        assert_eq!(parse_partial(r#"{"a":{"b":{"c":[tr"#), Some(json!({"a": {"b": {"c": []}}})));
+        assert_eq!(parse_partial(r#"{"n":12"#), Some(json!({"n": 12})));
+        assert_eq!(parse_partial("{"), Some(json!({})));
+    }
+
+    #[test]
+    fn code_fences_are_skipped() {
+        assert_eq!(parse_partial("```json\n{\"a\":1}\n```"), Some(json!({"a": 1})));
+        assert_eq!(parse_partial("```\n[1,"), Some(json!([1])));
+        assert_eq!(parse_partial("  ```json\n  {\"a\":\"b"), Some(json!({"a": "b"})));
+        assert_eq!(parse_partial("```json"), None);
+    }
+
+    #[test]
+    fn text_that_isnt_json_gives_nothing() {
+        assert_eq!(parse_partial(""), None);
+        assert_eq!(parse_partial("Here is the JSON: {\"a\":1}"), None);
+        assert_eq!(parse_partial("```python\nprint(1)"), None);
+        assert_eq!(parse_partial("\"just a string\""), None);
+        assert!(!looks_like_json("Sure! {\"a\":1}"));
+        assert!(looks_like_json("\n```json\n["));
+    }
+
+    #[test]
+    fn deltas_give_the_same_result_as_the_whole_text() {
+        assert_eq!(
+            streamed("```json\n{\"rows\":[{\"name\":\"caf\\u00e9 \\\"x\\\"\",\"n\":[1,2]},{\"name\":\"b\"}]}\n```"),
+            Some(json!({"rows": [{"name": "café \"x\"", "n": [1, 2]}, {"name": "b"}]}))
+        );
+        assert_eq!(streamed("Not JSON {\"a\":1}"), None);
+    }
+
+    #[test]
+    fn deltas_are_scanned_once() {
+        let mut doc = PartialJson::default();
+        doc.push("{\"items\":[");
+        for i in 0..1000 {
+            doc.push(&format!("{i},"));
+        }
+        assert_eq!(doc.scanned, doc.text.len());
+        assert_eq!(doc.value().unwrap()["items"].as_array().unwrap().len(), 1000);
+    }
+}
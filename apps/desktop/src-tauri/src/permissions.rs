@@ -0,0 +1,182 @@
+//! OS permissions computer use depends on.
+//!
+//! On macOS, synthetic input needs the Accessibility permission and screen
+//! capture needs Screen Recording; without them enigo and the capture crates
+//! fail with errors that don't say why. `computer_permissions_status` lets
+//! the UI check up front, and `computer_permissions_request` shows the
+//! system prompt and opens the matching System Settings pane.
+//!
+//! Other platforms have no such permissions and report `not_required`.
+
+use serde::{Deserialize, Serialize};
+
+/// A permission computer use needs.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    /// Mouse and keyboard input.
+    Accessibility,
+    /// Screenshots, OCR and window capture.
+    ScreenRecording,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    /// The platform doesn't gate this capability.
+    NotRequired,
+}
+
+/// Result of `computer_permissions_status`.
+#[derive(Serialize)]
+pub struct PermissionsStatus {
+    pub accessibility: PermissionState,
+    pub screen_recording: PermissionState,
+}
+
+fn status() -> PermissionsStatus {
+    PermissionsStatus {
+        accessibility: platform::state(PermissionKind::Accessibility),
+        screen_recording: platform::state(PermissionKind::ScreenRecording),
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Reports whether each permission computer use needs is granted.
+#[tauri::command]
+pub async fn computer_permissions_status() -> Result<PermissionsStatus, String> {
+    tokio::task::spawn_blocking(status)
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+}
+
+/// Asks for `kind`: shows the system prompt the first time, and opens its
+/// System Settings pane while it's still missing. Returns the status
+/// afterwards. macOS applies a Screen Recording grant only after the app
+/// restarts, so it keeps reading `denied` until then.
+#[tauri::command]
+pub async fn computer_permissions_request(kind: PermissionKind) -> Result<PermissionsStatus, String> {
+    tokio::task::spawn_blocking(move || {
+        platform::request(kind)?;
+        Ok(status())
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+// ── Platform queries ───────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{PermissionKind, PermissionState};
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        static kAXTrustedCheckOptionPrompt: CFTypeRef;
+        fn AXIsProcessTrusted() -> u8;
+        fn AXIsProcessTrustedWithOptions(options: CFTypeRef) -> u8;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> u8;
+        fn CGRequestScreenCaptureAccess() -> u8;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFBooleanTrue: CFTypeRef;
+        static kCFTypeDictionaryKeyCallBacks: c_void;
+        static kCFTypeDictionaryValueCallBacks: c_void;
+        fn CFDictionaryCreate(
+            alloc: CFTypeRef,
+            keys: *const CFTypeRef,
+            values: *const CFTypeRef,
+            count: isize,
+            key_callbacks: *const c_void,
+            value_callbacks: *const c_void,
+        ) -> CFTypeRef;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    fn granted(yes: bool) -> PermissionState {
+        if yes {
+            PermissionState::Granted
+        } else {
+            PermissionState::Denied
+        }
+    }
+
+    pub fn state(kind: PermissionKind) -> PermissionState {
+        // SAFETY: plain queries without arguments.
+        unsafe {
+            match kind {
+                PermissionKind::Accessibility => granted(AXIsProcessTrusted() != 0),
+                PermissionKind::ScreenRecording => granted(CGPreflightScreenCaptureAccess() != 0),
+            }
+        }
+    }
+
+    /// Shows the Accessibility prompt, which also lists the app in System
+    /// Settings so the user only has to flip its switch.
+    fn prompt_accessibility() -> bool {
+        // SAFETY: the options dictionary is created here with static keys and
+        // values and released after the call.
+        unsafe {
+            let keys = [kAXTrustedCheckOptionPrompt];
+            let values = [kCFBooleanTrue];
+            let options = CFDictionaryCreate(
+                std::ptr::null(),
+                keys.as_ptr(),
+                values.as_ptr(),
+                1,
+                &kCFTypeDictionaryKeyCallBacks,
+                &kCFTypeDictionaryValueCallBacks,
+            );
+            if options.is_null() {
+                return AXIsProcessTrusted() != 0;
+            }
+            let trusted = AXIsProcessTrustedWithOptions(options) != 0;
+            CFRelease(options);
+            trusted
+        }
+    }
+
+    pub fn request(kind: PermissionKind) -> Result<(), String> {
+        let (granted, pane) = match kind {
+            PermissionKind::Accessibility => (prompt_accessibility(), "Privacy_Accessibility"),
+            // SAFETY: no arguments; shows the prompt at most once per app.
+            PermissionKind::ScreenRecording => {
+                (unsafe { CGRequestScreenCaptureAccess() } != 0, "Privacy_ScreenCapture")
+            }
+        };
+        if !granted {
+            std::process::Command::new("open")
+                .arg(format!("x-apple.systempreferences:com.apple.preference.security?{pane}"))
+                .status()
+                .map_err(|e| format!("could not open System Settings: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::{PermissionKind, PermissionState};
+
+    pub fn state(_kind: PermissionKind) -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn request(_kind: PermissionKind) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! Long-running background processes with streaming output.
+//!
+//! `computer_run_shell` waits for its command to finish, which never happens
+//! for dev servers, watchers or `tail -f`. A process started with
+//! `computer_process_spawn` runs in the background instead: its output
+//! streams as events while it runs, input can be written to it, and it is
+//! killed — with everything it started — by `computer_process_kill` or when
+//! the app exits. Unlike a [`terminal`](crate::terminal) session there is no
+//! PTY, so stdout and stderr stay apart.
+//!
+//! # Events
+//! - `process:output:{id}` — `{ stream: "stdout" | "stderr", data }` as it arrives.
+//! - `process:exit:{id}` — `{ exit_code }` once the process ends.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::ChildStdin;
+
+use crate::{approval, audit, computer, shell_policy};
+use crate::terminal::drain_utf8;
+
+/// Maximum number of processes running at once.
+const MAX_RUNNING: usize = 16;
+/// Finished processes kept for `computer_process_list` before the oldest
+/// are forgotten.
+const MAX_FINISHED: usize = 32;
+/// How long the exit event waits for output still in the pipes.
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+// ── State ──────────────────────────────────────────────────────────────────────
+
+struct ManagedProcess {
+    pid: Option<u32>,
+    command: String,
+    started_at: String,
+    /// `None` after `close_stdin`.
+    stdin: Arc<tokio::sync::Mutex<Option<ChildStdin>>>,
+    /// `None` while running; then the exit code, itself `None` when killed
+    /// by a signal.
+    exit: Arc<Mutex<Option<Option<i32>>>>,
+}
+
+impl ManagedProcess {
+    fn exit(&self) -> Option<Option<i32>> {
+        *self.exit.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Spawned processes keyed by ID, managed via `tauri::Builder::manage`.
+#[derive(Default)]
+pub struct Processes {
+    procs: Mutex<HashMap<String, ManagedProcess>>,
+}
+
+impl Processes {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ManagedProcess>> {
+        self.procs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Kills every running process and its children; returns how many were
+    /// running.
+    pub fn kill_all(&self) -> usize {
+        let procs: Vec<ManagedProcess> = self.lock().drain().map(|(_, p)| p).collect();
+        let running: Vec<&ManagedProcess> = procs.iter().filter(|p| p.exit().is_none()).collect();
+        for p in &running {
+            if let Some(pid) = p.pid {
+                computer::kill_process_tree(pid);
+            }
+        }
+        running.len()
+    }
+
+    /// Refuses another process once [`MAX_RUNNING`] are running.
+    fn check_room(procs: &HashMap<String, ManagedProcess>) -> Result<(), String> {
+        if procs.values().filter(|p| p.exit().is_none()).count() >= MAX_RUNNING {
+            return Err(format!("too many running processes (max {MAX_RUNNING})"));
+        }
+        Ok(())
+    }
+
+    /// Forgets the oldest finished processes beyond [`MAX_FINISHED`].
+    fn prune(procs: &mut HashMap<String, ManagedProcess>) {
+        let mut finished: Vec<(String, String)> = procs
+            .iter()
+            .filter(|(_, p)| p.exit().is_some())
+            .map(|(id, p)| (p.started_at.clone(), id.clone()))
+            .collect();
+        if finished.len() <= MAX_FINISHED {
+            return;
+        }
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED] {
+            procs.remove(id);
+        }
+    }
+}
+
+// ── Response types ─────────────────────────────────────────────────────────────
+
+/// Handle returned by `computer_process_spawn`.
+#[derive(Serialize)]
+pub struct ProcessInfo {
+    pub id: String,
+    pub pid: Option<u32>,
+}
+
+/// Entry of `computer_process_list`.
+#[derive(Serialize)]
+pub struct ProcessSummary {
+    pub id: String,
+    pub pid: Option<u32>,
+    pub command: String,
+    /// RFC 3339 timestamp.
+    pub started_at: String,
+    pub running: bool,
+    /// `None` while running or when killed by a signal.
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Serialize, Clone)]
+struct ProcessOutput {
+    stream: &'static str,
+    data: String,
+}
+
+#[derive(Serialize, Clone)]
+struct ProcessExit {
+    exit_code: Option<i32>,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+/// Emits `pipe`'s output as `process:output:{id}` events until it closes.
+async fn pump(app: AppHandle, id: String, stream: &'static str, mut pipe: impl AsyncRead + Unpin) {
+    let event = format!("process:output:{id}");
+    let mut raw = [0u8; 8192];
+    let mut carry: Vec<u8> = Vec::new();
+    while let Ok(n) = pipe.read(&mut raw).await {
+        if n == 0 {
+            break;
+        }
+        carry.extend_from_slice(&raw[..n]);
+        let data = drain_utf8(&mut carry);
+        if !data.is_empty() {
+            let _ = app.emit(&event, ProcessOutput { stream, data });
+        }
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Starts `command` in the background through the shell (`/bin/sh -c` or
+/// `cmd /C`) and returns its handle. `cwd`, `env` and `inherit_env` work as
+/// in `computer_run_shell`. Output streams as `process:output:{id}` events.
+/// Commands refused by the [shell policy](crate::shell_policy) fail without
+/// running; the others wait for the user's [approval](crate::approval).
+/// Requires `computer.shell` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_process_spawn(
+    app: AppHandle,
+    processes: State<'_, Processes>,
+    command: String,
+    cwd: Option<String>,
+    env: Option<HashMap<String, String>>,
+    inherit_env: Option<bool>,
+    module: Option<String>,
+) -> Result<ProcessInfo, String> {
+    let args = serde_json::json!({
+        "command": command,
+        "cwd": cwd,
+        "env": env.as_ref().map(|e| e.keys().collect::<Vec<_>>()),
+        "inherit_env": inherit_env,
+    });
+    let result: Result<ProcessInfo, String> = async move {
+        if command.trim().is_empty() {
+            return Err("command must not be empty".into());
+        }
+        Processes::check_room(&processes.lock())?;
+        shell_policy::check(&app, &command)?;
+        approval::require(&app, approval::Action::shell(&command)).await?;
+
+        let mut cmd = computer::shell_command(&command);
+        computer::configure_shell(&mut cmd, cwd.as_deref(), env.as_ref(), inherit_env)?;
+        // The child must outlive this command; it is killed explicitly instead.
+        cmd.kill_on_drop(false);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let exit = Arc::new(Mutex::new(None));
+        let (mut child, pid, readers) = {
+            let mut procs = processes.lock();
+            // Others may have started while this one waited for approval;
+            // checking under the lock that records it keeps the cap exact.
+            Processes::check_room(&procs)?;
+            let mut child = cmd
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("process spawn failed: {e}"))?;
+            let pid = child.id();
+            let readers = [
+                child.stdout.take().map(|p| tokio::spawn(pump(app.clone(), id.clone(), "stdout", p))),
+                child.stderr.take().map(|p| tokio::spawn(pump(app.clone(), id.clone(), "stderr", p))),
+            ];
+            procs.insert(id.clone(), ManagedProcess {
+                pid,
+                command,
+                started_at: chrono::Utc::now().to_rfc3339(),
+                stdin: Arc::new(tokio::sync::Mutex::new(child.stdin.take())),
+                exit: Arc::clone(&exit),
+            });
+            Processes::prune(&mut procs);
+            (child, pid, readers)
+        };
+
+        let event_id = id.clone();
+        tokio::spawn(async move {
+            let exit_code = child.wait().await.ok().and_then(|s| s.code());
+            let readers: Vec<_> = readers.into_iter().flatten().collect();
+            let aborts: Vec<_> = readers.iter().map(|r| r.abort_handle()).collect();
+            if tokio::time::timeout(PIPE_DRAIN_TIMEOUT, futures_util::future::join_all(readers))
+                .await
+                .is_err()
+            {
+                aborts.iter().for_each(|a| a.abort());
+            }
+            *exit.lock().unwrap_or_else(|e| e.into_inner()) = Some(exit_code);
+            let _ = app.emit(&format!("process:exit:{event_id}"), ProcessExit { exit_code });
+        });
+
+        Ok(ProcessInfo { id, pid })
+    }
+    .await;
+    audit::record("computer_process_spawn", args, module.as_deref(), &result);
+    result
+}
+
+/// Writes `data` to the process's stdin; with `close_stdin`, closes it
+/// afterwards so the process sees end of input.
+#[tauri::command]
+pub async fn computer_process_write(
+    processes: State<'_, Processes>,
+    id: String,
+    data: String,
+    close_stdin: Option<bool>,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "id": id, "bytes": data.len(), "close_stdin": close_stdin });
+    let result: Result<(), String> = async move {
+        let stdin = {
+            let procs = processes.lock();
+            let p = procs.get(&id).ok_or("unknown process")?;
+            if p.exit().is_some() {
+                return Err("the process has exited".into());
+            }
+            Arc::clone(&p.stdin)
+        };
+        let mut stdin = stdin.lock().await;
+        let pipe = stdin.as_mut().ok_or("stdin is closed")?;
+        pipe.write_all(data.as_bytes())
+            .await
+            .map_err(|e| format!("process write failed: {e}"))?;
+        pipe.flush().await.map_err(|e| format!("process write failed: {e}"))?;
+        if close_stdin.unwrap_or(false) {
+            *stdin = None;
+        }
+        Ok(())
+    }
+    .await;
+    audit::record("computer_process_write", args, module.as_deref(), &result);
+    result
+}
+
+/// Kills the process and everything it started (if still running) and
+/// forgets it.
+#[tauri::command]
+pub async fn computer_process_kill(
+    processes: State<'_, Processes>,
+    id: String,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "id": id });
+    let result: Result<(), String> = async move {
+        let p = processes.lock().remove(&id).ok_or("unknown process")?;
+        if let (None, Some(pid)) = (p.exit(), p.pid) {
+            computer::kill_process_tree(pid);
+        }
+        Ok(())
+    }
+    .await;
+    audit::record("computer_process_kill", args, module.as_deref(), &result);
+    result
+}
+
+/// Lists the spawned processes, running and recently finished, oldest first.
+#[tauri::command]
+pub fn computer_process_list(processes: State<'_, Processes>) -> Vec<ProcessSummary> {
+    let mut list: Vec<ProcessSummary> = processes
+        .lock()
+        .iter()
+        .map(|(id, p)| {
+            let exit = p.exit();
+            ProcessSummary {
+                id: id.clone(),
+                pid: p.pid,
+                command: p.command.clone(),
+                started_at: p.started_at.clone(),
+                running: exit.is_none(),
+                exit_code: exit.flatten(),
+            }
+        })
+        .collect();
+    list.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(exit: Option<Option<i32>>) -> ManagedProcess {
+        ManagedProcess {
+            pid: None,
+            command: "sleep 60".into(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            stdin: Arc::new(tokio::sync::Mutex::new(None)),
+            exit: Arc::new(Mutex::new(exit)),
+        }
+    }
+
+    #[test]
+    fn only_running_processes_count_against_the_cap() {
+        let mut procs = HashMap::new();
+        for i in 0..MAX_RUNNING - 1 {
+            procs.insert(format!("running-{i}"), process(None));
+        }
+        procs.insert("done".into(), process(Some(Some(0))));
+        assert!(Processes::check_room(&procs).is_ok());
+        procs.insert("last".into(), process(None));
+        assert!(Processes::check_room(&procs).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! BYOK provider keys kept on the Rust side.
+//!
+//! Keys are stored per provider, optionally under a label ("work",
+//! "personal"), in the OS credential store (see [`secrets`]). Only an index
+//! of which keys exist — with the last four characters as a hint — lives in
+//! `settings.json`, and the frontend never needs to hold the values.
+//!
+//! Chat and generate commands called without an `api_key` use the stored key
+//! of their `provider`: the unlabeled one, else the most recently added —
+//! unless `use_stored_provider_keys` is turned off in the settings, which
+//! sends them to the gateway instead.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{secrets, settings};
+
+const INDEX_KEY: &str = "provider_keys";
+const USE_STORED_KEYS_KEY: &str = "use_stored_provider_keys";
+const MAX_KEYS: usize = 64;
+const MAX_LABEL_LEN: usize = 40;
+
+/// A stored key, without its value.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProviderKeyInfo {
+    pub provider: String,
+    pub label: Option<String>,
+    /// Last four characters of the key, to tell keys apart.
+    pub hint: String,
+    /// RFC 3339 timestamp.
+    pub added_at: String,
+}
+
+impl ProviderKeyInfo {
+    fn matches(&self, provider: &str, label: Option<&str>) -> bool {
+        self.provider == provider && self.label.as_deref() == label
+    }
+}
+
+fn secret_name(provider: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("provider_key:{provider}:{label}"),
+        None => format!("provider_key:{provider}"),
+    }
+}
+
+fn load_index(app: &AppHandle) -> Vec<ProviderKeyInfo> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(INDEX_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(app: &AppHandle, index: &[ProviderKeyInfo]) -> Result<(), String> {
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(index).map_err(|e| e.to_string())?;
+    store.set(INDEX_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+fn normalize_label(label: Option<String>) -> Result<Option<String>, String> {
+    let Some(label) = label.map(|l| l.trim().to_owned()).filter(|l| !l.is_empty()) else {
+        return Ok(None);
+    };
+    if label.len() > MAX_LABEL_LEN || label.contains(':') {
+        return Err(format!("key label must be at most {MAX_LABEL_LEN} characters without ':'"));
+    }
+    Ok(Some(label))
+}
+
+/// Whether calls without an `api_key` may use the stored keys (default on).
+pub fn stored_keys_enabled(app: &AppHandle) -> bool {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(USE_STORED_KEYS_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+}
+
+/// The stored key of `provider` under `label`; without a label, the
+/// unlabeled key or else the most recently added one. `None` while stored
+/// keys are turned off.
+pub fn resolve(app: &AppHandle, provider: &str, label: Option<&str>) -> Option<String> {
+    if !stored_keys_enabled(app) {
+        return None;
+    }
+    let index = load_index(app);
+    let entry = match label {
+        Some(label) => index.iter().find(|k| k.matches(provider, Some(label))),
+        None => index
+            .iter()
+            .find(|k| k.matches(provider, None))
+            .or_else(|| index.iter().rev().find(|k| k.provider == provider)),
+    }?;
+    secrets::get(&secret_name(&entry.provider, entry.label.as_deref())).ok().flatten()
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Stores (or replaces) the key of `provider`, optionally under `label`.
+#[tauri::command]
+pub async fn provider_keys_set(
+    app: AppHandle,
+    provider: String,
+    key: String,
+    label: Option<String>,
+) -> Result<ProviderKeyInfo, String> {
+    let provider = provider.trim().to_ascii_lowercase();
+    let key = key.trim().to_owned();
+    if provider.is_empty() || key.is_empty() {
+        return Err("provider and key must not be empty".into());
+    }
+    let label = normalize_label(label)?;
+
+    let mut index = load_index(&app);
+    index.retain(|k| !k.matches(&provider, label.as_deref()));
+    if index.len() >= MAX_KEYS {
+        return Err(format!("too many stored keys (max {MAX_KEYS})"));
+    }
+    let hint = key.chars().skip(key.chars().count().saturating_sub(4)).collect();
+    let name = secret_name(&provider, label.as_deref());
+    tokio::task::spawn_blocking(move || secrets::set(&name, &key))
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)?;
+
+    let info = ProviderKeyInfo {
+        provider,
+        label,
+        hint,
+        added_at: chrono::Utc::now().to_rfc3339(),
+    };
+    index.push(info.clone());
+    save_index(&app, &index)?;
+    Ok(info)
+}
+
+/// Lists the stored keys (without their values), optionally for one provider.
+#[tauri::command]
+pub async fn provider_keys_list(app: AppHandle, provider: Option<String>) -> Vec<ProviderKeyInfo> {
+    let mut index = load_index(&app);
+    if let Some(p) = provider.map(|p| p.trim().to_ascii_lowercase()) {
+        index.retain(|k| k.provider == p);
+    }
+    index
+}
+
+/// Deletes the key of `provider` under `label` (the unlabeled key when `None`).
+#[tauri::command]
+pub async fn provider_keys_delete(
+    app: AppHandle,
+    provider: String,
+    label: Option<String>,
+) -> Result<(), String> {
+    let provider = provider.trim().to_ascii_lowercase();
+    let label = normalize_label(label)?;
+    let mut index = load_index(&app);
+    let before = index.len();
+    index.retain(|k| !k.matches(&provider, label.as_deref()));
+    if index.len() == before {
+        return Err(format!("no stored key for '{provider}'"));
+    }
+    let name = secret_name(&provider, label.as_deref());
+    tokio::task::spawn_blocking(move || secrets::delete(&name))
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)?;
+    save_index(&app, &index)
+}
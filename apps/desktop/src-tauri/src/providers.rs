@@ -0,0 +1,1045 @@
+//! Direct (BYOK) provider calls.
+//!
+//! Each provider's request — endpoint, auth and body, with the generation
+//! parameters mapped to its native fields — is built by
+//! [`provider_request`]; [`call_provider_stream`] pipes the reply into a
+//! [`StreamSink`] and [`call_provider_generate`] returns it buffered. Both
+//! record the call in the [`usage_ledger`]. The managed-key path's gateway
+//! requests are at the end.
+
+use serde::{Deserialize, Serialize};
+
+use crate::response_format::{self, ResponseFormat};
+use crate::retry::SendWithRetry;
+use crate::stream::{
+    pipe_bedrock_stream, pipe_provider_sse, pipe_sse, StreamSink, STREAM_READ_ERROR,
+};
+use crate::stream_usage::StreamUsage;
+use crate::tool_calls::{self, ToolCall, ToolCallAccumulator, ToolRound, ToolSpec};
+use crate::vision::{self, Image};
+use crate::{bedrock, language, redact, usage_ledger};
+
+// ── Direct AI provider constants ───────────────────────────────────────────────
+
+pub const OPENAI_API_BASE:     &str = "https://api.openai.com/v1";
+pub const ANTHROPIC_API_BASE:  &str = "https://api.anthropic.com/v1";
+const COHERE_API_BASE:     &str = "https://api.cohere.com/v2";
+const GROQ_API_BASE:       &str = "https://api.groq.com/openai/v1";
+const MISTRAL_API_BASE:    &str = "https://api.mistral.ai/v1";
+const DEEPSEEK_API_BASE:   &str = "https://api.deepseek.com/v1";
+const XAI_API_BASE:        &str = "https://api.x.ai/v1";
+const PERPLEXITY_API_BASE: &str = "https://api.perplexity.ai";
+pub const GOOGLE_API_BASE:     &str = "https://generativelanguage.googleapis.com/v1beta";
+pub const ANTHROPIC_VERSION:   &str = "2023-06-01";
+
+/// Returns the default model identifier for a given provider slug.
+pub fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "anthropic"         => "claude-3-5-haiku-20241022",
+        "bedrock"           => "anthropic.claude-3-5-haiku-20241022-v1:0",
+        "cohere"            => "command-r-08-2024",
+        "google" | "gemini" => "gemini-2.0-flash",
+        "groq"              => "llama3-8b-8192",
+        "mistral"           => "mistral-small-latest",
+        "deepseek"          => "deepseek-chat",
+        "xai"               => "grok-2-latest",
+        "perplexity"        => "sonar",
+        _                   => "gpt-4o-mini",  // openai + fallback
+    }
+}
+
+/// Returns the OpenAI-compatible API base URL for a given provider slug.
+pub fn openai_compat_base(provider: &str) -> &'static str {
+    match provider {
+        "groq"       => GROQ_API_BASE,
+        "mistral"    => MISTRAL_API_BASE,
+        "deepseek"   => DEEPSEEK_API_BASE,
+        "xai"        => XAI_API_BASE,
+        "perplexity" => PERPLEXITY_API_BASE,
+        _            => OPENAI_API_BASE,
+    }
+}
+
+// ── SSE chunk extractors ───────────────────────────────────────────────────────
+
+/// Extracts the text delta from one OpenAI-style SSE `data:` line.
+pub fn extract_openai_chunk(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    val.pointer("/choices/0/delta/content")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Extracts the reasoning delta (`reasoning_content`, sent by
+/// `deepseek-reasoner` and some self-hosted servers) from one OpenAI-style
+/// SSE `data:` line.
+pub fn extract_openai_reasoning(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    val.pointer("/choices/0/delta/reasoning_content")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Extracts the source URLs Perplexity attaches to its chunks (`citations`;
+/// the final chunk carries the complete list).
+pub fn extract_perplexity_citations(data: &str) -> Option<serde_json::Value> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    val.get("citations")
+        .filter(|c| c.as_array().is_some_and(|a| !a.is_empty()))
+        .cloned()
+}
+
+/// Extracts the text delta from one Anthropic SSE `data:` line.
+pub fn extract_anthropic_chunk(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    if val.get("type").and_then(|t| t.as_str()) != Some("content_block_delta") {
+        return None;
+    }
+    val.pointer("/delta/text")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Extracts the forced tool's input JSON delta from one Anthropic SSE
+/// `data:` line (see [`response_format`]).
+pub fn extract_anthropic_json_chunk(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    if val.pointer("/delta/type").and_then(|t| t.as_str()) != Some("input_json_delta") {
+        return None;
+    }
+    val.pointer("/delta/partial_json")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Text of a buffered Anthropic (or Bedrock) reply; with a response format,
+/// the forced tool's input as JSON.
+fn anthropic_output(val: &serde_json::Value, json_mode: bool) -> String {
+    let blocks = val.get("content").and_then(|c| c.as_array()).map(Vec::as_slice).unwrap_or_default();
+    if json_mode {
+        return blocks
+            .iter()
+            .find(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+            .and_then(|b| b.get("input"))
+            .map(|input| input.to_string())
+            .unwrap_or_default();
+    }
+    blocks
+        .first()
+        .and_then(|b| b.get("text"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_owned()
+}
+
+/// Extracts the text delta from one Cohere v2 SSE `data:` line
+/// (`content-delta` events; the rest carry no text).
+pub fn extract_cohere_chunk(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    if val.get("type").and_then(|t| t.as_str()) != Some("content-delta") {
+        return None;
+    }
+    val.pointer("/delta/message/content/text")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Extracts the text delta from one Google Gemini SSE `data:` line.
+pub fn extract_google_chunk(data: &str) -> Option<String> {
+    let val: serde_json::Value = serde_json::from_str(data).ok()?;
+    val.pointer("/candidates/0/content/parts/0/text")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+// ── Direct provider: request building ──────────────────────────────────────────
+
+/// Optional sampling parameters for the direct (BYOK) provider path.
+/// Unset fields fall back to each provider's defaults, and ones a provider
+/// lacks are left out of its request (Anthropic has no penalties,
+/// Perplexity no stop sequences).
+#[derive(Deserialize, Default, Clone)]
+pub struct GenerationParams {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<i64>,
+    /// Sequences that end the reply when generated.
+    #[serde(default)]
+    pub stop: Vec<String>,
+    pub frequency_penalty: Option<f64>,
+    pub presence_penalty: Option<f64>,
+}
+
+/// Anthropic requires `max_tokens`; used when the caller doesn't set one.
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Roughly Anthropic's 1024-token minimum for a cacheable prompt prefix;
+/// shorter system prompts aren't marked.
+const ANTHROPIC_CACHE_MIN_CHARS: usize = 4000;
+
+/// OpenAI's limit on stop sequences, the lowest among the providers.
+const MAX_STOP_SEQUENCES: usize = 4;
+
+impl GenerationParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err(format!("temperature must be between 0 and 2 (got {t})"));
+            }
+        }
+        if let Some(p) = self.top_p {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("top_p must be between 0 and 1 (got {p})"));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be greater than 0".into());
+        }
+        if self.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(format!("at most {MAX_STOP_SEQUENCES} stop sequences are allowed"));
+        }
+        if self.stop.iter().any(|s| s.is_empty()) {
+            return Err("stop sequences must not be empty".into());
+        }
+        for (name, value) in [("frequency_penalty", self.frequency_penalty), ("presence_penalty", self.presence_penalty)] {
+            if let Some(v) = value {
+                if !(-2.0..=2.0).contains(&v) {
+                    return Err(format!("{name} must be between -2 and 2 (got {v})"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One prior turn of a multi-turn conversation (`role`: user, assistant, or system).
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+pub fn validate_messages(messages: &[ChatMessage]) -> Result<(), String> {
+    for (i, m) in messages.iter().enumerate() {
+        if !matches!(m.role.as_str(), "user" | "assistant" | "system") {
+            return Err(format!(
+                "messages[{i}].role must be user, assistant or system (got '{}')",
+                m.role
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Held while one request's reply is read; dropping it admits the next.
+pub type Admission = Box<dyn Send>;
+
+/// Admits each outbound request of a [`ProviderCall`] — every resume,
+/// retry and fallback included — e.g. through a rate-limit queue.
+pub trait RequestGate: Send + Sync {
+    fn admit<'a>(
+        &'a self,
+        provider: &'a str,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Admission, String>> + Send + 'a>>;
+}
+
+/// Everything needed to issue one direct provider call.
+#[derive(Clone)]
+pub struct ProviderCall<'a> {
+    pub provider: &'a str,
+    pub api_key: &'a str,
+    pub model: &'a str,
+    pub input: &'a str,
+    pub params: &'a GenerationParams,
+    /// System instruction, mapped to each provider's native field.
+    pub system: Option<String>,
+    /// Earlier user/assistant turns sent before `input`.
+    pub history: &'a [ChatMessage],
+    /// Overrides the provider's API base: the `custom` provider's endpoint,
+    /// a local server's, or the regional Bedrock endpoint.
+    pub base_url: Option<String>,
+    /// Tools the model may call (see [`tool_calls`]).
+    pub tools: &'a [ToolSpec],
+    /// Earlier tool calls of this turn and their results, sent after `input`.
+    pub tool_rounds: &'a [ToolRound],
+    /// Images attached to `input` (see [`vision`]).
+    pub images: &'a [Image],
+    /// JSON output requested of the model (see [`response_format`]).
+    pub response_format: Option<&'a ResponseFormat>,
+    /// Chat session the call is made for, for the [`usage_ledger`].
+    pub session_id: Option<&'a str>,
+    /// Admits each request sent for the call; none are gated without one.
+    pub gate: Option<&'a dyn RequestGate>,
+}
+
+impl<'a> ProviderCall<'a> {
+    pub fn new(
+        provider: &'a str,
+        api_key: &'a str,
+        model_override: Option<&'a str>,
+        input: &'a str,
+        params: &'a GenerationParams,
+    ) -> Self {
+        let model = model_override.unwrap_or_else(|| default_model(provider));
+        Self {
+            provider, api_key, model, input, params,
+            system: None, history: &[], base_url: None, tools: &[], tool_rounds: &[], images: &[],
+            response_format: None, session_id: None, gate: None,
+        }
+    }
+
+    /// Requests JSON output; the JSON directive joins the system prompt.
+    /// Call after [`with_system`](Self::with_system).
+    pub fn with_response_format(mut self, format: Option<&'a ResponseFormat>) -> Self {
+        if format.is_some() {
+            self.system = Some(append_system(self.system.as_deref(), response_format::JSON_DIRECTIVE.into()));
+        }
+        self.response_format = format;
+        self
+    }
+
+    pub fn with_tools(mut self, tools: &'a [ToolSpec], rounds: &'a [ToolRound]) -> Self {
+        self.tools = tools;
+        self.tool_rounds = rounds;
+        self
+    }
+
+    pub fn with_images(mut self, images: &'a [Image]) -> Self {
+        self.images = images;
+        self
+    }
+
+    pub fn with_session(mut self, session_id: Option<&'a str>) -> Self {
+        self.session_id = session_id;
+        self
+    }
+
+    pub fn with_gate(mut self, gate: Option<&'a dyn RequestGate>) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// The provider's API base, or `base_url` when set.
+    pub fn api_base(&self) -> &str {
+        self.base_url.as_deref().unwrap_or_else(|| match self.provider {
+            "anthropic"         => ANTHROPIC_API_BASE,
+            "cohere"            => COHERE_API_BASE,
+            "google" | "gemini" => GOOGLE_API_BASE,
+            other               => openai_compat_base(other),
+        })
+    }
+
+    /// Sets the caller's system prompt / persona; blank prompts are ignored.
+    pub fn with_system(mut self, system: Option<&str>) -> Self {
+        self.system = system.map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+        self
+    }
+
+    /// Sets the prior turns. `system` entries are folded into the system
+    /// prompt since Anthropic and Gemini only accept one, out of band.
+    pub fn with_history(mut self, history: &'a [ChatMessage]) -> Self {
+        for m in history.iter().filter(|m| m.role == "system") {
+            self.system = Some(append_system(self.system.as_deref(), m.content.clone()));
+        }
+        self.history = history;
+        self
+    }
+
+    /// The conversation as `(role, text)` ending with `input`, with
+    /// consecutive same-role turns merged and leading assistant turns
+    /// dropped — Anthropic and Gemini require strictly alternating turns
+    /// starting with the user.
+    fn turns(&self) -> Vec<(&'static str, String)> {
+        let all = self
+            .history
+            .iter()
+            .filter_map(|m| match m.role.as_str() {
+                "user"      => Some(("user", m.content.as_str())),
+                "assistant" => Some(("assistant", m.content.as_str())),
+                _           => None,
+            })
+            .chain(std::iter::once(("user", self.input)));
+
+        let mut turns: Vec<(&'static str, String)> = Vec::new();
+        for (role, text) in all {
+            match turns.last_mut() {
+                Some((last, buf)) if *last == role => {
+                    buf.push_str("\n\n");
+                    buf.push_str(text);
+                }
+                None if role == "assistant" => {}
+                _ => turns.push((role, text.to_owned())),
+            }
+        }
+        turns
+    }
+}
+
+/// Provider slug of a local OpenAI-compatible server found by probing.
+pub const LOCAL_OPENAI: &str = "local-openai";
+
+/// The key for the direct (BYOK) path, if the request takes it: the caller's
+/// `api_key`, or an empty one for the keyless `local-openai` provider.
+pub fn direct_key<'a>(api_key: Option<&'a str>, provider: Option<&str>) -> Option<&'a str> {
+    api_key.or((provider == Some(LOCAL_OPENAI)).then_some(""))
+}
+
+/// Appends `directive` to the caller's system prompt (if any).
+pub fn append_system(base: Option<&str>, directive: String) -> String {
+    match base {
+        Some(b) => format!("{b}\n\n{directive}"),
+        None    => directive,
+    }
+}
+
+/// Builds an OpenAI-compatible `/chat/completions` body.
+pub fn openai_body(call: &ProviderCall, stream: bool) -> serde_json::Value {
+    let mut messages = Vec::new();
+    if let Some(sys) = &call.system {
+        messages.push(serde_json::json!({ "role": "system", "content": sys }));
+    }
+    let turns = call.turns();
+    let last = turns.len() - 1;
+    for (i, (role, text)) in turns.into_iter().enumerate() {
+        let content = if i == last && !call.images.is_empty() {
+            vision::openai_content(&text, call.images)
+        } else {
+            text.into()
+        };
+        messages.push(serde_json::json!({ "role": role, "content": content }));
+    }
+    messages.extend(tool_calls::openai_round_messages(call.tool_rounds));
+    let mut body = serde_json::json!({ "model": call.model, "messages": messages });
+    if stream { body["stream"] = serde_json::Value::Bool(true); }
+    // Other compatible servers send usage unasked, or reject the option.
+    if stream && matches!(call.provider, "openai" | "groq" | "deepseek" | "xai") {
+        body["stream_options"] = serde_json::json!({ "include_usage": true });
+    }
+    if !call.tools.is_empty() { body["tools"] = tool_calls::openai_tools(call.tools); }
+    if let Some(f) = call.response_format { body["response_format"] = f.openai(); }
+
+    let p = call.params;
+    if let Some(t) = p.temperature { body["temperature"] = t.into(); }
+    if let Some(t) = p.top_p       { body["top_p"]       = t.into(); }
+    if let Some(m) = p.max_tokens  { body["max_tokens"]  = m.into(); }
+    if let Some(s) = p.seed {
+        // Mistral names the field differently; the rest follow OpenAI.
+        let key = if call.provider == "mistral" { "random_seed" } else { "seed" };
+        body[key] = s.into();
+    }
+    if !p.stop.is_empty() && call.provider != "perplexity" { body["stop"] = serde_json::json!(p.stop); }
+    if let Some(f) = p.frequency_penalty { body["frequency_penalty"] = f.into(); }
+    if let Some(f) = p.presence_penalty  { body["presence_penalty"]  = f.into(); }
+    body
+}
+
+/// Builds an Anthropic `/messages` body. Anthropic has no `seed` or penalty
+/// parameters; `stop` becomes `stop_sequences`.
+pub fn anthropic_body(call: &ProviderCall, stream: bool) -> serde_json::Value {
+    let p = call.params;
+    let turns = call.turns();
+    let last = turns.len() - 1;
+    let mut messages: Vec<serde_json::Value> = turns
+        .into_iter()
+        .enumerate()
+        .map(|(i, (role, text))| {
+            let content = if i == last && !call.images.is_empty() {
+                vision::anthropic_content(&text, call.images)
+            } else {
+                text.into()
+            };
+            serde_json::json!({ "role": role, "content": content })
+        })
+        .collect();
+    messages.extend(tool_calls::anthropic_round_messages(call.tool_rounds));
+    let mut body = serde_json::json!({
+        "model": call.model,
+        "max_tokens": p.max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+        "messages": messages,
+    });
+    if stream { body["stream"] = serde_json::Value::Bool(true); }
+    if !call.tools.is_empty() { body["tools"] = tool_calls::anthropic_tools(call.tools); }
+    if let Some(f) = call.response_format {
+        let (tools, choice) = f.anthropic_tool();
+        body["tools"] = tools;
+        body["tool_choice"] = choice;
+    }
+    if let Some(sys) = &call.system { body["system"] = anthropic_system(sys); }
+    if let Some(t) = p.temperature { body["temperature"] = t.into(); }
+    if let Some(t) = p.top_p       { body["top_p"]       = t.into(); }
+    if !p.stop.is_empty()          { body["stop_sequences"] = serde_json::json!(p.stop); }
+    body
+}
+
+/// Anthropic's system prompt, marked for prompt caching when long enough to
+/// be cached — long system prompts (memory context, personas) are otherwise
+/// billed in full every turn.
+fn anthropic_system(system: &str) -> serde_json::Value {
+    if system.len() < ANTHROPIC_CACHE_MIN_CHARS {
+        return system.into();
+    }
+    serde_json::json!([{ "type": "text", "text": system, "cache_control": { "type": "ephemeral" } }])
+}
+
+/// Builds a Bedrock `InvokeModel` body for an Anthropic model: the Anthropic
+/// body with the model moved into the URL and Bedrock's API version. Prompt
+/// caching depends on the Bedrock model, so the system prompt stays plain.
+pub fn bedrock_body(call: &ProviderCall) -> serde_json::Value {
+    let mut body = anthropic_body(call, false);
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("model");
+        obj.insert("anthropic_version".into(), bedrock::ANTHROPIC_BEDROCK_VERSION.into());
+        if let Some(sys) = &call.system {
+            obj.insert("system".into(), sys.as_str().into());
+        }
+    }
+    body
+}
+
+/// Builds a Cohere v2 `/chat` body. The system prompt is a `system` message,
+/// `top_p` is called `p` and `stop` `stop_sequences`; penalties are clamped
+/// to Cohere's 0–1 range.
+pub fn cohere_body(call: &ProviderCall, stream: bool) -> serde_json::Value {
+    let mut messages = Vec::new();
+    if let Some(sys) = &call.system {
+        messages.push(serde_json::json!({ "role": "system", "content": sys }));
+    }
+    for (role, text) in call.turns() {
+        messages.push(serde_json::json!({ "role": role, "content": text }));
+    }
+    let mut body = serde_json::json!({ "model": call.model, "messages": messages });
+    if stream { body["stream"] = serde_json::Value::Bool(true); }
+
+    let p = call.params;
+    if let Some(t) = p.temperature { body["temperature"] = t.into(); }
+    if let Some(t) = p.top_p       { body["p"]           = t.into(); }
+    if let Some(m) = p.max_tokens  { body["max_tokens"]  = m.into(); }
+    if let Some(s) = p.seed        { body["seed"]        = s.into(); }
+    if !p.stop.is_empty()          { body["stop_sequences"] = serde_json::json!(p.stop); }
+    if let Some(f) = p.frequency_penalty { body["frequency_penalty"] = f.clamp(0.0, 1.0).into(); }
+    if let Some(f) = p.presence_penalty  { body["presence_penalty"]  = f.clamp(0.0, 1.0).into(); }
+    if let Some(f) = call.response_format { body["response_format"] = f.cohere(); }
+    body
+}
+
+/// Builds a Gemini `generateContent` / `streamGenerateContent` body.
+pub fn google_body(call: &ProviderCall) -> serde_json::Value {
+    // Gemini calls the assistant role "model".
+    let turns = call.turns();
+    let last = turns.len() - 1;
+    let contents: Vec<serde_json::Value> = turns
+        .into_iter()
+        .enumerate()
+        .map(|(i, (role, text))| {
+            let role = if role == "assistant" { "model" } else { role };
+            let parts = if i == last {
+                vision::google_parts(&text, call.images)
+            } else {
+                serde_json::json!([{ "text": text }])
+            };
+            serde_json::json!({ "role": role, "parts": parts })
+        })
+        .collect();
+    let mut body = serde_json::json!({ "contents": contents });
+    if let Some(sys) = &call.system {
+        body["systemInstruction"] = serde_json::json!({ "parts": [{ "text": sys }] });
+    }
+
+    let p = call.params;
+    let mut config = serde_json::Map::new();
+    if let Some(t) = p.temperature { config.insert("temperature".into(),     t.into()); }
+    if let Some(t) = p.top_p       { config.insert("topP".into(),            t.into()); }
+    if let Some(m) = p.max_tokens  { config.insert("maxOutputTokens".into(), m.into()); }
+    if let Some(s) = p.seed        { config.insert("seed".into(),            s.into()); }
+    if !p.stop.is_empty()          { config.insert("stopSequences".into(),   serde_json::json!(p.stop)); }
+    if let Some(f) = p.frequency_penalty { config.insert("frequencyPenalty".into(), f.into()); }
+    if let Some(f) = p.presence_penalty  { config.insert("presencePenalty".into(),  f.into()); }
+    if let Some(f) = call.response_format { f.google_config(&mut config); }
+    if !config.is_empty() {
+        body["generationConfig"] = serde_json::Value::Object(config);
+    }
+    body
+}
+
+/// Starts a `/chat/completions` request; self-hosted servers often need no
+/// key, so an empty one sends no `Authorization` header.
+fn openai_request(http: &reqwest::Client, call: &ProviderCall) -> reqwest::RequestBuilder {
+    let req = http.post(format!("{}/chat/completions", call.api_base()));
+    if call.api_key.is_empty() { req } else { req.bearer_auth(call.api_key) }
+}
+
+/// Starts `call`'s request to its provider's streaming (`stream`) or
+/// buffered endpoint: URL, auth and body.
+pub fn provider_request(
+    http: &reqwest::Client,
+    call: &ProviderCall,
+    stream: bool,
+) -> Result<reqwest::RequestBuilder, String> {
+    let base = call.api_base();
+    let req = match call.provider {
+        "anthropic" => http
+            .post(format!("{base}/messages"))
+            .header("x-api-key", call.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&anthropic_body(call, stream)),
+        "bedrock" => return bedrock_request(http, call, stream),
+        "cohere" => http
+            .post(format!("{base}/chat"))
+            .bearer_auth(call.api_key)
+            .json(&cohere_body(call, stream)),
+        "google" | "gemini" => {
+            let url = if stream {
+                format!("{base}/models/{}:streamGenerateContent?key={}&alt=sse", call.model, call.api_key)
+            } else {
+                format!("{base}/models/{}:generateContent?key={}", call.model, call.api_key)
+            };
+            http.post(url).json(&google_body(call))
+        }
+        // OpenAI, Groq, Mistral, DeepSeek, xAI, Perplexity, custom, and other OpenAI-compatible providers.
+        _ => openai_request(http, call).json(&openai_body(call, stream)),
+    };
+    Ok(req)
+}
+
+/// Starts a SigV4-signed Bedrock `InvokeModel` request. Only Anthropic
+/// models are supported; other Bedrock families use different bodies.
+fn bedrock_request(
+    http: &reqwest::Client,
+    call: &ProviderCall,
+    stream: bool,
+) -> Result<reqwest::RequestBuilder, String> {
+    if !call.model.contains("anthropic.") {
+        return Err(format!("Bedrock model '{}' is not supported — use an Anthropic model", call.model));
+    }
+    let endpoint = call.base_url.as_deref().ok_or("Bedrock endpoint is not configured")?;
+    let region = bedrock::region_of(endpoint).ok_or("Bedrock endpoint has no region")?;
+    let creds = bedrock::Credentials::parse(call.api_key)?;
+
+    let url = bedrock::invoke_url(endpoint, call.model, stream);
+    let body = serde_json::to_vec(&bedrock_body(call)).map_err(redact::error)?;
+    let signed = bedrock::sign_request(&creds, region, &url, &body, chrono::Utc::now())?;
+
+    let mut req = http
+        .post(url)
+        .header("content-type", "application/json")
+        .header("accept", if stream { "application/vnd.amazon.eventstream" } else { "application/json" });
+    for (name, value) in signed {
+        req = req.header(name, value);
+    }
+    Ok(req.body(body))
+}
+
+/// Name of `provider` in API error messages.
+fn api_name(provider: &str) -> &str {
+    match provider {
+        "anthropic"         => "Anthropic",
+        "bedrock"           => "Bedrock",
+        "cohere"            => "Cohere",
+        "google" | "gemini" => "Google",
+        "perplexity"        => "Perplexity",
+        other               => other,
+    }
+}
+
+/// Sends `call`'s request (see [`provider_request`]), retrying transient
+/// errors with their waits announced on `sink` and admitting each send
+/// through the call's gate. An error status fails with the provider's reply,
+/// redacted. The admission is returned with the response, to be held until
+/// the reply is read.
+async fn send_provider_request(
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+    stream: bool,
+    sink: Option<&StreamSink>,
+) -> Result<(reqwest::Response, Option<Admission>), String> {
+    let (resp, admission) = provider_request(http, call, stream)?
+        .send_with_retry(sink, call.gate.map(|gate| (gate, call.provider)))
+        .await?;
+
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(redact::redact(&format!("{} API error {status}: {body}", api_name(call.provider))));
+    }
+    Ok((resp, admission))
+}
+
+// ── Direct provider: streaming ─────────────────────────────────────────────────
+
+/// Times a dropped stream is resumed before its error is returned.
+const MAX_STREAM_RESUMES: u32 = 2;
+
+/// New user turn of a resumed stream, after the reply received so far.
+const RESUME_PROMPT: &str = "Your previous reply was cut off by a network error. Continue it exactly \
+where it stopped, without repeating any of it or mentioning the interruption.";
+
+/// Payload of `stream-resumed`.
+#[derive(Serialize, Clone)]
+struct StreamResumed {
+    attempt: u32,
+    /// Characters of the reply received before the drop.
+    received_chars: usize,
+}
+
+/// Calls an AI provider's streaming endpoint directly, bypassing the cloud gateway.
+/// Emits each text chunk through `sink` and returns the full output.
+///
+/// A stream whose connection drops midway is re-issued with the reply so far
+/// as an assistant turn and asked to continue; the continuation streams on
+/// through the same `sink` after a `stream-resumed` event, and the returned
+/// output is the stitched whole. JSON replies aren't resumed.
+pub async fn call_provider_stream(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut history = call.history.to_vec();
+    let mut resumes = 0;
+    loop {
+        sink.take_received();
+        let mut attempt = call.clone();
+        if !output.is_empty() {
+            attempt.history = &history;
+            attempt.input = RESUME_PROMPT;
+        }
+        match call_provider_stream_once(sink, http, &attempt).await {
+            Ok(rest) => {
+                output.push_str(&rest);
+                return Ok(output);
+            }
+            Err(e) if e == STREAM_READ_ERROR && call.response_format.is_none() && resumes < MAX_STREAM_RESUMES => {
+                let partial = sink.take_received();
+                if output.is_empty() && !partial.is_empty() {
+                    history.push(ChatMessage { role: "user".into(), content: call.input.into() });
+                    history.push(ChatMessage { role: "assistant".into(), content: String::new() });
+                }
+                if let Some(reply) = history.last_mut().filter(|_| !partial.is_empty()) {
+                    reply.content.push_str(&partial);
+                }
+                output.push_str(&partial);
+                resumes += 1;
+                sink.emit("stream-resumed", StreamResumed { attempt: resumes, received_chars: output.chars().count() });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Adds a finished stream's usage to `sink` and the [`usage_ledger`].
+fn record_stream_usage(sink: &StreamSink, call: &ProviderCall, usage: &StreamUsage) {
+    usage_ledger::record(&usage_ledger::UsageEntry {
+        provider: call.provider,
+        model: call.model,
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        cached_tokens: usage.cache_read_tokens,
+        session_id: call.session_id,
+    });
+    sink.add_usage(usage);
+}
+
+/// One request of [`call_provider_stream`].
+async fn call_provider_stream_once(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+) -> Result<String, String> {
+    let usage = std::sync::Mutex::new(StreamUsage::default());
+    let track = |f: fn(&mut StreamUsage, &str), data: &str| {
+        f(&mut usage.lock().unwrap_or_else(|e| e.into_inner()), data);
+    };
+    let (resp, _admission) = send_provider_request(http, call, true, Some(sink)).await?;
+
+    let output = match call.provider {
+        "anthropic" | "bedrock" => {
+            let text = if call.response_format.is_some() { extract_anthropic_json_chunk } else { extract_anthropic_chunk };
+            let extract = |data: &str| {
+                track(StreamUsage::push_anthropic, data);
+                text(data)
+            };
+            if call.provider == "bedrock" {
+                pipe_bedrock_stream(sink, resp, extract).await?
+            } else {
+                pipe_provider_sse(sink, resp, extract, None).await?
+            }
+        }
+
+        "cohere" => {
+            let extract = |data: &str| {
+                track(StreamUsage::push_cohere, data);
+                extract_cohere_chunk(data)
+            };
+            pipe_provider_sse(sink, resp, extract, None).await?
+        }
+
+        "google" | "gemini" => {
+            let extract = |data: &str| {
+                track(StreamUsage::push_google, data);
+                extract_google_chunk(data)
+            };
+            pipe_provider_sse(sink, resp, extract, None).await?
+        }
+
+        "perplexity" => {
+            // OpenAI-style deltas; the search sources are emitted once, at the end.
+            let citations = std::sync::Mutex::new(None);
+            let extract = |data: &str| {
+                if let Some(c) = extract_perplexity_citations(data) {
+                    *citations.lock().unwrap_or_else(|e| e.into_inner()) = Some(c);
+                }
+                track(StreamUsage::push_openai, data);
+                extract_openai_chunk(data)
+            };
+            let output = pipe_provider_sse(sink, resp, extract, None).await?;
+            if let Some(c) = citations.into_inner().unwrap_or_else(|e| e.into_inner()) {
+                sink.emit("citations", &c);
+            }
+            output
+        }
+
+        _ => {
+            let extract = |data: &str| {
+                track(StreamUsage::push_openai, data);
+                extract_openai_chunk(data)
+            };
+            pipe_provider_sse(sink, resp, extract, Some(extract_openai_reasoning)).await?
+        }
+    };
+
+    record_stream_usage(sink, call, &usage.into_inner().unwrap_or_else(|e| e.into_inner()));
+    Ok(output)
+}
+
+/// Streams `call` with its tools. Returns the text plus the tool calls the
+/// model made, each also emitted as a `tool-call` event once assembled.
+pub async fn call_provider_stream_with_tools(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+) -> Result<(String, Vec<ToolCall>), String> {
+    let provider = call.provider;
+    if matches!(provider, "bedrock" | "cohere" | "google" | "gemini" | "perplexity") {
+        return Err(format!("tool calling is not supported for provider '{provider}'"));
+    }
+    let acc = std::sync::Mutex::new(ToolCallAccumulator::default());
+    let push = |f: fn(&mut ToolCallAccumulator, &str), data: &str| {
+        f(&mut acc.lock().unwrap_or_else(|e| e.into_inner()), data);
+    };
+    let usage = std::sync::Mutex::new(StreamUsage::default());
+    let track = |f: fn(&mut StreamUsage, &str), data: &str| {
+        f(&mut usage.lock().unwrap_or_else(|e| e.into_inner()), data);
+    };
+    let (resp, _admission) = send_provider_request(http, call, true, Some(sink)).await?;
+
+    let output = if provider == "anthropic" {
+        let extract = |data: &str| {
+            push(ToolCallAccumulator::push_anthropic, data);
+            track(StreamUsage::push_anthropic, data);
+            extract_anthropic_chunk(data)
+        };
+        pipe_provider_sse(sink, resp, extract, None).await?
+    } else {
+        let extract = |data: &str| {
+            push(ToolCallAccumulator::push_openai, data);
+            track(StreamUsage::push_openai, data);
+            extract_openai_chunk(data)
+        };
+        pipe_provider_sse(sink, resp, extract, Some(extract_openai_reasoning)).await?
+    };
+
+    record_stream_usage(sink, call, &usage.into_inner().unwrap_or_else(|e| e.into_inner()));
+    let calls = acc.into_inner().unwrap_or_else(|e| e.into_inner()).finish();
+    for c in &calls {
+        sink.emit("tool-call", c);
+    }
+    Ok((output, calls))
+}
+
+// ── Direct provider: non-streaming generate ────────────────────────────────────
+
+/// Tokens used by a direct call. Anthropic counts cached prompt tokens apart
+/// from `input_tokens`; `tokens_used` includes them.
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct CallUsage {
+    #[serde(rename = "tokens_used")]
+    pub total: i64,
+    /// Prompt tokens read from Anthropic's prompt cache.
+    #[serde(rename = "cache_read_tokens")]
+    pub cache_read: i64,
+    /// Prompt tokens written to Anthropic's prompt cache.
+    #[serde(rename = "cache_write_tokens")]
+    pub cache_write: i64,
+    /// Output tokens; the rest of `total` is the prompt.
+    #[serde(skip)]
+    pub completion: i64,
+}
+
+impl CallUsage {
+    pub fn tokens(prompt: i64, completion: i64) -> Self {
+        Self { total: prompt + completion, completion, ..Default::default() }
+    }
+
+    /// From an Anthropic `usage` object, as in a reply or `message_start`.
+    fn anthropic(usage: &serde_json::Value) -> Self {
+        let get = |k: &str| usage.get(k).and_then(|v| v.as_i64()).unwrap_or(0);
+        let (cache_read, cache_write) = (get("cache_read_input_tokens"), get("cache_creation_input_tokens"));
+        let prompt = get("input_tokens") + cache_read + cache_write;
+        Self { cache_read, cache_write, ..Self::tokens(prompt, get("output_tokens")) }
+    }
+}
+
+impl std::ops::Add for CallUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            total: self.total + other.total,
+            cache_read: self.cache_read + other.cache_read,
+            cache_write: self.cache_write + other.cache_write,
+            completion: self.completion + other.completion,
+        }
+    }
+}
+
+/// Calls an AI provider's completion endpoint directly and returns `(output, usage)`.
+/// The call is recorded in the [`usage_ledger`].
+pub async fn call_provider_generate(
+    http: &reqwest::Client,
+    call: &ProviderCall<'_>,
+) -> Result<(String, CallUsage), String> {
+    let (resp, _admission) = send_provider_request(http, call, false, None).await?;
+    let val: serde_json::Value = resp.json().await.map_err(redact::error)?;
+    let text = |pointer: &str| val.pointer(pointer).and_then(|v| v.as_str()).unwrap_or("").to_owned();
+
+    let (output, usage) = match call.provider {
+        "anthropic" | "bedrock" => {
+            (anthropic_output(&val, call.response_format.is_some()), CallUsage::anthropic(&val["usage"]))
+        }
+        "cohere" => {
+            let tokens = |k: &str| val["usage"]["tokens"][k].as_i64().unwrap_or(0);
+            (text("/message/content/0/text"), CallUsage::tokens(tokens("input_tokens"), tokens("output_tokens")))
+        }
+        "google" | "gemini" => {
+            let tokens = |k: &str| val["usageMetadata"][k].as_i64().unwrap_or(0);
+            (
+                text("/candidates/0/content/parts/0/text"),
+                CallUsage::tokens(tokens("promptTokenCount"), tokens("candidatesTokenCount")),
+            )
+        }
+        _ => {
+            let tokens = |k: &str| val["usage"][k].as_i64().unwrap_or(0);
+            (text("/choices/0/message/content"), CallUsage::tokens(tokens("prompt_tokens"), tokens("completion_tokens")))
+        }
+    };
+
+    usage_ledger::record(&usage_ledger::UsageEntry {
+        provider: call.provider,
+        model: call.model,
+        prompt_tokens: usage.total - usage.completion,
+        completion_tokens: usage.completion,
+        cached_tokens: usage.cache_read,
+        session_id: call.session_id,
+    });
+    Ok((output, usage))
+}
+
+// ── Direct provider: response language ─────────────────────────────────────────
+
+/// Streams `call` with the response-language directive (if any) and retries
+/// once with a stricter directive when the reply is in the wrong language.
+/// A `stream-reset` event tells listeners to discard the rejected chunks.
+pub async fn call_provider_stream_in_language(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    call: &mut ProviderCall<'_>,
+    language: Option<&str>,
+) -> Result<String, String> {
+    let Some(lang) = language else {
+        return call_provider_stream(sink, http, call).await;
+    };
+    let base = call.system.take();
+    call.system = Some(append_system(base.as_deref(), language::directive(lang)));
+    let output = call_provider_stream(sink, http, call).await?;
+    // JSON keys and values aren't a fair sample of the reply's language.
+    if call.response_format.is_some() || language::matches(lang, &output) {
+        return Ok(output);
+    }
+    sink.emit("stream-reset", ());
+    call.system = Some(append_system(base.as_deref(), language::strict_directive(lang)));
+    call_provider_stream(sink, http, call).await
+}
+
+/// Buffered counterpart of [`call_provider_stream_in_language`]; tokens of
+/// both attempts are counted.
+pub async fn call_provider_generate_in_language(
+    http: &reqwest::Client,
+    call: &mut ProviderCall<'_>,
+    language: Option<&str>,
+) -> Result<(String, CallUsage), String> {
+    let Some(lang) = language else {
+        return call_provider_generate(http, call).await;
+    };
+    let base = call.system.take();
+    call.system = Some(append_system(base.as_deref(), language::directive(lang)));
+    let (output, tokens) = call_provider_generate(http, call).await?;
+    if call.response_format.is_some() || language::matches(lang, &output) {
+        return Ok((output, tokens));
+    }
+    call.system = Some(append_system(base.as_deref(), language::strict_directive(lang)));
+    let (output, retry_tokens) = call_provider_generate(http, call).await?;
+    Ok((output, tokens + retry_tokens))
+}
+
+// ── Managed-key path: cloud gateway ────────────────────────────────────────────
+
+/// Posts `body` to the gateway's `path` (e.g. `/v1/ai/stream`) with the
+/// user's access token.
+pub async fn gateway_post(
+    http: &reqwest::Client,
+    gateway: &str,
+    token: &str,
+    path: &str,
+    body: &serde_json::Value,
+) -> Result<reqwest::Response, String> {
+    http.post(format!("{gateway}{path}"))
+        .bearer_auth(token)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_connect() {
+                "Cloud Gateway unreachable. Please check your internet connection or configure a local API key in the Dashboard.".to_string()
+            } else {
+                redact::error(e)
+            }
+        })
+}
+
+/// Streams a completion from the gateway's `/v1/ai/stream` into `sink`;
+/// returns the full output.
+pub async fn gateway_stream(
+    sink: &StreamSink,
+    http: &reqwest::Client,
+    gateway: &str,
+    token: &str,
+    body: &serde_json::Value,
+) -> Result<String, String> {
+    let resp = gateway_post(http, gateway, token, "/v1/ai/stream", body).await?;
+    if !resp.status().is_success() {
+        return Err(format!("stream error: HTTP {}", resp.status().as_u16()));
+    }
+    pipe_sse(sink, resp).await
+}
@@ -0,0 +1,201 @@
+//! Quick chat: a global hotkey that summons a compact chat window.
+//!
+//! The hotkey (see `hotkeys_set`, registered like the capture-and-ask one by
+//! [`hotkey`](crate::hotkey)) opens a small, always-on-top window on the
+//! frontend's `#/quick-chat` route, or focuses it when it is already open;
+//! pressed again while the window has focus, it hides it. Each summon emits
+//! `quickchat:open` to that window.
+//!
+//! With `capture_selection` on, the text selected in the app that had focus
+//! is copied first (the clipboard is restored afterwards) and sent along in
+//! the event, so "explain this" works without copy-pasting. This sends a
+//! copy shortcut to that app, so it is off by default.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_store::StoreExt;
+
+use crate::hotkey::{Hotkey, HotkeyRegistration};
+use crate::{computer, hotkey, settings, shutdown};
+
+const HOTKEYS_KEY: &str = "hotkeys";
+const HOTKEY_NAME: &str = "quick_chat";
+pub const WINDOW_LABEL: &str = "quick-chat";
+const WINDOW_ROUTE: &str = "index.html#/quick-chat";
+const WINDOW_SIZE: (f64, f64) = (480.0, 560.0);
+
+/// Stored under `hotkeys` in the settings store.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HotkeysConfig {
+    pub quick_chat_enabled: bool,
+    /// Accelerator such as `CmdOrCtrl+Shift+Space`.
+    pub quick_chat: String,
+    /// Copy the focused app's selection into `quickchat:open`.
+    pub capture_selection: bool,
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        Self {
+            quick_chat_enabled: false,
+            quick_chat: "CmdOrCtrl+Shift+Space".into(),
+            capture_selection: false,
+        }
+    }
+}
+
+/// Payload of `quickchat:open`.
+#[derive(Serialize, Clone)]
+pub struct QuickChatOpen {
+    /// Text selected in the previously focused app, when captured.
+    pub selection: Option<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    hotkey: Option<HotkeyRegistration>,
+    /// Last summon, for a window that wasn't listening yet.
+    pending: Option<QuickChatOpen>,
+}
+
+/// The registered hotkey and the summon the window hasn't picked up.
+#[derive(Default)]
+pub struct QuickChat(Mutex<Inner>);
+
+impl QuickChat {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub fn load_config(app: &AppHandle) -> HotkeysConfig {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(HOTKEYS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Registers the configured hotkey at startup; a hotkey that can't be
+/// registered is reported by [`hotkey::register`].
+pub fn start(app: &AppHandle) {
+    let _ = register(app, &load_config(app));
+}
+
+/// Unregisters the hotkey and closes the window.
+pub fn stop(app: &AppHandle) {
+    app.state::<QuickChat>().lock().hotkey = None;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
+
+fn register(app: &AppHandle, config: &HotkeysConfig) -> Result<(), String> {
+    let state = app.state::<QuickChat>();
+    state.lock().hotkey = None;
+    if !config.quick_chat_enabled {
+        hotkey::clear(app, HOTKEY_NAME);
+        return Ok(());
+    }
+    let handle = app.clone();
+    let registration = hotkey::register(app, HOTKEY_NAME, &config.quick_chat, move || {
+        let app = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = summon(&app).await {
+                let _ = app.emit("quickchat:error", e);
+            }
+        });
+    })?;
+    state.lock().hotkey = Some(registration);
+    Ok(())
+}
+
+/// Opens or focuses the window — or hides it when it already has focus.
+async fn summon(app: &AppHandle) -> Result<(), String> {
+    shutdown::ensure_running()?;
+    let existing = app.get_webview_window(WINDOW_LABEL);
+    if let Some(window) = &existing {
+        if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) {
+            return window.hide().map_err(|e| e.to_string());
+        }
+    }
+
+    // Copy before our window takes focus from the app holding the selection.
+    let selection = if load_config(app).capture_selection {
+        tokio::task::spawn_blocking(computer::copy_selection)
+            .await
+            .map_err(|e| format!("task panicked: {e}"))?
+            .unwrap_or_else(|e| {
+                let _ = app.emit("quickchat:error", e);
+                None
+            })
+    } else {
+        None
+    };
+    let event = QuickChatOpen { selection };
+    app.state::<QuickChat>().lock().pending = Some(event.clone());
+
+    let window = match existing {
+        Some(window) => window,
+        None => WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App(WINDOW_ROUTE.into()))
+            .title("Quick chat")
+            .inner_size(WINDOW_SIZE.0, WINDOW_SIZE.1)
+            .resizable(true)
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .center()
+            .focused(true)
+            .build()
+            .map_err(|e| format!("can't open the quick chat window: {e}"))?,
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    app.emit_to(WINDOW_LABEL, "quickchat:open", event).map_err(|e| e.to_string())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Opens the quick chat window without the hotkey (e.g. from a menu).
+#[tauri::command]
+pub async fn quick_chat_open(app: AppHandle) -> Result<(), String> {
+    summon(&app).await
+}
+
+/// Returns and clears the last `quickchat:open` payload, for a window that
+/// was still loading when it was emitted.
+#[tauri::command]
+pub fn quick_chat_take_pending(app: AppHandle) -> Option<QuickChatOpen> {
+    app.state::<QuickChat>().lock().pending.take()
+}
+
+#[tauri::command]
+pub async fn hotkeys_get(app: AppHandle) -> HotkeysConfig {
+    load_config(&app)
+}
+
+/// Saves the global hotkeys and re-registers them. Fails, without saving,
+/// when a hotkey can't be parsed or registered.
+#[tauri::command]
+pub async fn hotkeys_set(app: AppHandle, hotkeys: HotkeysConfig) -> Result<(), String> {
+    let mut config = hotkeys;
+    config.quick_chat = config.quick_chat.trim().to_owned();
+    if config.quick_chat_enabled {
+        let hotkey = Hotkey::parse(&config.quick_chat)?;
+        let capture = crate::capture_ask::load_config(&app);
+        if capture.enabled && Hotkey::parse(&capture.hotkey).is_ok_and(|c| c == hotkey) {
+            return Err(format!("{hotkey} is already the capture-and-ask hotkey"));
+        }
+    }
+    register(&app, &config)?;
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store.set(HOTKEYS_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
@@ -0,0 +1,321 @@
+//! Per-provider queue for direct (BYOK) calls.
+//!
+//! Each provider gets a lane that lets at most `max_concurrent` calls run at
+//! once and, when `requests_per_minute` is set, starts no more than that many
+//! in any 60-second window. Calls over either limit wait their turn in order
+//! instead of all hitting the provider and coming back with 429s. Limits are
+//! configured per provider with `settings_set_provider_rate_limits`; every
+//! change in a lane's depth is announced on `ai:queue-depth`.
+//!
+//! A permit covers one outbound request and is held until its reply is read.
+//! Direct calls take theirs through [`QueueGate`], which also checks the
+//! spending budget (see [`budget`]) before each request, so a resumed stream,
+//! a language retry, a fallback model or a history summary each waits its turn
+//! and stops once the budget is spent.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::providers::{Admission, RequestGate};
+use crate::{budget, settings, shutdown, AppState};
+
+const RATE_LIMITS_KEY: &str = "provider_rate_limits";
+
+pub const DEFAULT_MAX_CONCURRENT: u32 = 4;
+const MAX_CONCURRENT_LIMIT: u32 = 64;
+const MAX_REQUESTS_PER_MINUTE: u32 = 10_000;
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits of one provider.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ProviderLimits {
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: u32,
+    /// `None` for no per-minute limit.
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+}
+
+fn default_max_concurrent() -> u32 {
+    DEFAULT_MAX_CONCURRENT
+}
+
+impl Default for ProviderLimits {
+    fn default() -> Self {
+        Self { max_concurrent: DEFAULT_MAX_CONCURRENT, requests_per_minute: None }
+    }
+}
+
+impl ProviderLimits {
+    fn validate(&self, provider: &str) -> Result<(), String> {
+        if !(1..=MAX_CONCURRENT_LIMIT).contains(&self.max_concurrent) {
+            return Err(format!(
+                "max_concurrent for '{provider}' must be between 1 and {MAX_CONCURRENT_LIMIT}"
+            ));
+        }
+        if self.requests_per_minute.is_some_and(|n| !(1..=MAX_REQUESTS_PER_MINUTE).contains(&n)) {
+            return Err(format!(
+                "requests_per_minute for '{provider}' must be between 1 and {MAX_REQUESTS_PER_MINUTE}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Payload of `ai:queue-depth`.
+#[derive(Serialize, Clone)]
+pub struct QueueDepth {
+    pub provider: String,
+    /// Calls waiting for a slot.
+    pub queued: usize,
+    pub running: usize,
+    #[serde(flatten)]
+    pub limits: ProviderLimits,
+}
+
+struct Lane {
+    limits: ProviderLimits,
+    slots: Arc<Semaphore>,
+    /// Start times of the calls in the last minute, oldest first.
+    started: Mutex<VecDeque<Instant>>,
+    queued: AtomicUsize,
+    running: AtomicUsize,
+}
+
+impl Lane {
+    fn new(limits: ProviderLimits) -> Self {
+        Self {
+            limits,
+            slots: Arc::new(Semaphore::new(limits.max_concurrent as usize)),
+            started: Mutex::new(VecDeque::new()),
+            queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records a start now, or returns how long until one fits the window.
+    fn try_start(&self) -> Option<Duration> {
+        let rpm = self.limits.requests_per_minute?;
+        let now = Instant::now();
+        let mut started = self.started.lock().unwrap_or_else(|e| e.into_inner());
+        while started.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) {
+            started.pop_front();
+        }
+        if started.len() < rpm as usize {
+            started.push_back(now);
+            return None;
+        }
+        started.front().map(|t| WINDOW.saturating_sub(now.duration_since(*t)))
+    }
+
+    fn depth(&self, provider: &str) -> QueueDepth {
+        QueueDepth {
+            provider: provider.to_owned(),
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            limits: self.limits,
+        }
+    }
+}
+
+/// Lanes by provider, created on first use from the stored limits.
+#[derive(Default)]
+pub struct RequestQueue {
+    lanes: Mutex<HashMap<String, Arc<Lane>>>,
+}
+
+/// A running call's slot; frees it when dropped.
+pub struct RequestPermit {
+    app: AppHandle,
+    provider: String,
+    lane: Arc<Lane>,
+    _slot: OwnedSemaphorePermit,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.lane.running.fetch_sub(1, Ordering::Relaxed);
+        let _ = self.app.emit("ai:queue-depth", self.lane.depth(&self.provider));
+    }
+}
+
+/// Takes a call off the queue count when it starts or is abandoned.
+struct Queued<'a>(&'a Lane);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl RequestQueue {
+    fn lane(&self, app: &AppHandle, provider: &str) -> Arc<Lane> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let lane = lanes.entry(provider.to_owned()).or_insert_with(|| {
+            let limits = load_limits(app).remove(provider).unwrap_or_default();
+            Arc::new(Lane::new(limits))
+        });
+        Arc::clone(lane)
+    }
+
+    /// Waits until `provider` has a free slot and its per-minute budget
+    /// allows another call. Fails when the app shuts down while waiting.
+    pub async fn acquire(&self, app: &AppHandle, provider: &str) -> Result<RequestPermit, String> {
+        let lane = self.lane(app, provider);
+        lane.queued.fetch_add(1, Ordering::Relaxed);
+        let queued = Queued(&lane);
+        let _ = app.emit("ai:queue-depth", lane.depth(provider));
+
+        let wait = async {
+            let slot = Arc::clone(&lane.slots)
+                .acquire_owned()
+                .await
+                .map_err(|_| "request queue closed".to_string())?;
+            while let Some(delay) = lane.try_start() {
+                tokio::time::sleep(delay).await;
+            }
+            Ok::<_, String>(slot)
+        };
+        let slot = tokio::select! {
+            slot = wait => slot?,
+            _ = shutdown::cancelled() => return Err("the app is shutting down".into()),
+        };
+
+        drop(queued);
+        lane.running.fetch_add(1, Ordering::Relaxed);
+        let _ = app.emit("ai:queue-depth", lane.depth(provider));
+        Ok(RequestPermit { app: app.clone(), provider: provider.to_owned(), lane, _slot: slot })
+    }
+
+    /// Current depth of every lane used so far.
+    pub fn depths(&self) -> Vec<QueueDepth> {
+        let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut depths: Vec<QueueDepth> = lanes.iter().map(|(p, lane)| lane.depth(p)).collect();
+        depths.sort_by(|a, b| a.provider.cmp(&b.provider));
+        depths
+    }
+
+    /// Drops the lanes so the next calls pick up new limits. Calls already
+    /// queued or running finish under the old ones.
+    fn reset(&self) {
+        self.lanes.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+// ── Gate ───────────────────────────────────────────────────────────────────────
+
+/// Admits a direct call's requests once the budget allows them and their
+/// provider's lane has a slot (see [`ProviderCall::with_gate`]).
+///
+/// [`ProviderCall::with_gate`]: crate::providers::ProviderCall::with_gate
+pub struct QueueGate(pub AppHandle);
+
+impl RequestGate for QueueGate {
+    fn admit<'a>(
+        &'a self,
+        provider: &'a str,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Admission, String>> + Send + 'a>> {
+        Box::pin(async move {
+            budget::ensure_within(&self.0)?;
+            let permit = self.0.state::<AppState>().requests.acquire(&self.0, provider).await?;
+            Ok(Box::new(permit) as Admission)
+        })
+    }
+}
+
+// ── Store helpers ──────────────────────────────────────────────────────────────
+
+/// Configured limits by provider; providers not listed use the defaults.
+pub fn load_limits(app: &AppHandle) -> HashMap<String, ProviderLimits> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(RATE_LIMITS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the request limits configured per provider.
+#[tauri::command]
+pub async fn settings_get_provider_rate_limits(app: AppHandle) -> HashMap<String, ProviderLimits> {
+    load_limits(&app)
+}
+
+/// Replaces the request limits, keyed by provider (e.g.
+/// `{ "openai": { "max_concurrent": 2, "requests_per_minute": 60 } }`).
+/// Providers left out get 4 concurrent calls and no per-minute limit.
+#[tauri::command]
+pub async fn settings_set_provider_rate_limits(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    limits: HashMap<String, ProviderLimits>,
+) -> Result<(), String> {
+    let limits: HashMap<String, ProviderLimits> = limits
+        .into_iter()
+        .map(|(p, l)| (p.trim().to_ascii_lowercase(), l))
+        .filter(|(p, _)| !p.is_empty())
+        .collect();
+    for (provider, l) in &limits {
+        l.validate(provider)?;
+    }
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&limits).map_err(|e| e.to_string())?;
+    store.set(RATE_LIMITS_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))?;
+    state.requests.reset();
+    Ok(())
+}
+
+/// Returns how many calls are queued and running per provider.
+#[tauri::command]
+pub fn request_queue_status(state: State<'_, AppState>) -> Vec<QueueDepth> {
+    state.requests.depths()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(requests_per_minute: Option<u32>) -> Lane {
+        Lane::new(ProviderLimits { max_concurrent: 1, requests_per_minute })
+    }
+
+    #[test]
+    fn without_a_per_minute_limit_every_start_fits() {
+        let lane = lane(None);
+        assert!((0..100).all(|_| lane.try_start().is_none()));
+    }
+
+    #[test]
+    fn starts_over_the_limit_wait_for_the_oldest_to_leave_the_window() {
+        let lane = lane(Some(2));
+        assert_eq!(lane.try_start(), None);
+        assert_eq!(lane.try_start(), None);
+        let wait = lane.try_start().unwrap();
+        assert!(wait > WINDOW - Duration::from_secs(1) && wait <= WINDOW, "{wait:?}");
+        // A refused start isn't counted.
+        assert_eq!(lane.started.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn starts_older_than_the_window_are_forgotten() {
+        let lane = lane(Some(1));
+        let Some(old) = Instant::now().checked_sub(WINDOW + Duration::from_secs(1)) else {
+            return;
+        };
+        lane.started.lock().unwrap().push_back(old);
+        assert_eq!(lane.try_start(), None);
+        assert_eq!(lane.started.lock().unwrap().len(), 1);
+        assert!(lane.try_start().is_some());
+    }
+}
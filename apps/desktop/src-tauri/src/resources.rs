@@ -0,0 +1,153 @@
+//! Low-disk and low-memory guardrails.
+//!
+//! Commands that write to disk (files, screenshots, exports) or allocate large
+//! buffers (full-screen captures) call into this module first so they fail
+//! early with an actionable error instead of corrupting a store mid-write or
+//! getting the process OOM-killed.
+//!
+//! When resources are merely running low (but the operation can still
+//! proceed) a `system:resource-warning` event is emitted so the UI can nudge
+//! the user before things start failing.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use sysinfo::{Disks, System};
+use tauri::{AppHandle, Emitter};
+
+/// Writes are refused when they would leave less than this free (256 MB).
+const MIN_FREE_DISK_BYTES: u64 = 256 * 1024 * 1024;
+
+/// A warning is emitted when a write leaves less than this free (2 GB).
+const WARN_FREE_DISK_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Allocations are refused when they would leave less than this available (128 MB).
+const MIN_AVAILABLE_MEMORY_BYTES: u64 = 128 * 1024 * 1024;
+
+/// A warning is emitted when an allocation leaves less than this available (512 MB).
+const WARN_AVAILABLE_MEMORY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Payload of `system:resource-warning`.
+#[derive(Serialize, Clone)]
+pub struct ResourceWarning {
+    /// `"disk"` or `"memory"`.
+    pub kind: &'static str,
+    /// Mount point of the affected volume (disk warnings only).
+    pub location: Option<String>,
+    pub available_bytes: u64,
+    pub required_bytes: u64,
+    pub message: String,
+}
+
+fn warn(app: &AppHandle, warning: ResourceWarning) {
+    let _ = app.emit("system:resource-warning", warning);
+}
+
+fn format_mb(bytes: u64) -> String {
+    format!("{} MB", bytes / (1024 * 1024))
+}
+
+/// Returns the closest existing ancestor of `path` (the file itself usually
+/// doesn't exist yet when we're about to write it).
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut current = Some(path);
+    while let Some(p) = current {
+        if let Ok(canonical) = p.canonicalize() {
+            return Some(canonical);
+        }
+        current = p.parent();
+    }
+    None
+}
+
+/// Returns `(mount_point, available_bytes)` for the volume holding `path`.
+fn volume_for(path: &Path) -> Option<(PathBuf, u64)> {
+    let target = existing_ancestor(path)?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|d| target.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| (d.mount_point().to_path_buf(), d.available_space()))
+}
+
+/// Fails when writing `needed_bytes` under `path` would leave the volume
+/// nearly full. Unknown volumes (e.g. network mounts) are allowed through.
+pub fn ensure_disk_space(app: &AppHandle, path: &Path, needed_bytes: u64) -> Result<(), String> {
+    let Some((mount, available)) = volume_for(path) else {
+        return Ok(());
+    };
+    let location = mount.display().to_string();
+
+    if available < needed_bytes.saturating_add(MIN_FREE_DISK_BYTES) {
+        let message = format!(
+            "not enough disk space on {location}: {} free, {} needed — free up space and retry",
+            format_mb(available),
+            format_mb(needed_bytes.saturating_add(MIN_FREE_DISK_BYTES)),
+        );
+        warn(app, ResourceWarning {
+            kind: "disk",
+            location: Some(location),
+            available_bytes: available,
+            required_bytes: needed_bytes,
+            message: message.clone(),
+        });
+        return Err(message);
+    }
+
+    if available < needed_bytes.saturating_add(WARN_FREE_DISK_BYTES) {
+        warn(app, ResourceWarning {
+            kind: "disk",
+            message: format!("disk space is running low on {location} ({} free)", format_mb(available)),
+            location: Some(location),
+            available_bytes: available,
+            required_bytes: needed_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Fails when allocating roughly `needed_bytes` would exhaust available memory.
+pub fn ensure_memory(app: &AppHandle, needed_bytes: u64) -> Result<(), String> {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    let available = sys.available_memory();
+    if available == 0 {
+        // Platform didn't report memory — don't block on missing data.
+        return Ok(());
+    }
+
+    if available < needed_bytes.saturating_add(MIN_AVAILABLE_MEMORY_BYTES) {
+        let message = format!(
+            "not enough free memory: {} available, ~{} needed — close other applications or capture a smaller region",
+            format_mb(available),
+            format_mb(needed_bytes.saturating_add(MIN_AVAILABLE_MEMORY_BYTES)),
+        );
+        warn(app, ResourceWarning {
+            kind: "memory",
+            location: None,
+            available_bytes: available,
+            required_bytes: needed_bytes,
+            message: message.clone(),
+        });
+        return Err(message);
+    }
+
+    if available < needed_bytes.saturating_add(WARN_AVAILABLE_MEMORY_BYTES) {
+        warn(app, ResourceWarning {
+            kind: "memory",
+            location: None,
+            available_bytes: available,
+            required_bytes: needed_bytes,
+            message: format!("available memory is running low ({})", format_mb(available)),
+        });
+    }
+    Ok(())
+}
+
+/// Rough peak memory of capturing and encoding a `width`×`height` RGBA frame:
+/// the raw frame, the PNG buffer, and its base64 data URI.
+pub fn capture_memory_estimate(width: u32, height: u32) -> u64 {
+    (width as u64) * (height as u64) * 4 * 3
+}
@@ -0,0 +1,137 @@
+//! Structured (JSON) output on the direct provider path.
+//!
+//! A `response_format` asks the model for JSON in each provider's own way:
+//! `response_format` for OpenAI-compatible providers and Cohere,
+//! `responseMimeType` / `responseJsonSchema` for Gemini, and for Anthropic
+//! (and Bedrock) a forced call of one tool whose input schema is the
+//! requested one. Not every provider enforces the schema, so the reply is
+//! parsed — tolerating a Markdown code fence — and validated here as well
+//! (see `JsonSchema::validate_deep`).
+
+use agenthub_runtime::skill::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Name of the schema (and of Anthropic's forced tool) when none is given.
+const DEFAULT_NAME: &str = "response";
+const MAX_NAME_LEN: usize = 64;
+
+/// Added to the system prompt: OpenAI's JSON mode requires the word, and
+/// providers without native support still follow it.
+pub const JSON_DIRECTIVE: &str = "Reply with a single JSON value only — no prose, no code fences.";
+
+/// `{ "type": "json_object" }` for any JSON object, or
+/// `{ "type": "json_schema", "schema": {...}, "name"?: "...", "strict"?: bool }`
+/// for JSON matching `schema`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    JsonObject,
+    JsonSchema {
+        schema: Value,
+        #[serde(default)]
+        name: Option<String>,
+        /// OpenAI's strict mode (default `true`); its schemas must then
+        /// list every property as required and set `additionalProperties: false`.
+        #[serde(default)]
+        strict: Option<bool>,
+    },
+}
+
+impl ResponseFormat {
+    pub fn validate(&self) -> Result<(), String> {
+        let Self::JsonSchema { schema, name, .. } = self else {
+            return Ok(());
+        };
+        if schema.get("type").and_then(Value::as_str) != Some("object") {
+            return Err("response_format schema must describe an object (\"type\": \"object\")".into());
+        }
+        if let Some(name) = name {
+            let valid = !name.is_empty()
+                && name.len() <= MAX_NAME_LEN
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid {
+                return Err(format!(
+                    "response_format name must be 1-{MAX_NAME_LEN} letters, digits, '_' or '-'"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::JsonSchema { name: Some(name), .. } => name,
+            _ => DEFAULT_NAME,
+        }
+    }
+
+    fn schema(&self) -> Option<&Value> {
+        match self {
+            Self::JsonSchema { schema, .. } => Some(schema),
+            Self::JsonObject => None,
+        }
+    }
+
+    /// OpenAI-compatible `response_format`.
+    pub fn openai(&self) -> Value {
+        match self {
+            Self::JsonObject => json!({ "type": "json_object" }),
+            Self::JsonSchema { schema, strict, .. } => json!({
+                "type": "json_schema",
+                "json_schema": { "name": self.name(), "schema": schema, "strict": strict.unwrap_or(true) },
+            }),
+        }
+    }
+
+    /// Cohere v2 `response_format`.
+    pub fn cohere(&self) -> Value {
+        match self.schema() {
+            Some(schema) => json!({ "type": "json_object", "json_schema": schema }),
+            None => json!({ "type": "json_object" }),
+        }
+    }
+
+    /// Anthropic `tools` and `tool_choice` forcing the answer into one tool call.
+    pub fn anthropic_tool(&self) -> (Value, Value) {
+        let input_schema = self.schema().cloned().unwrap_or_else(|| json!({ "type": "object" }));
+        let tools = json!([{
+            "name": self.name(),
+            "description": "Give the final answer as this tool's input.",
+            "input_schema": input_schema,
+        }]);
+        (tools, json!({ "type": "tool", "name": self.name() }))
+    }
+
+    /// Adds Gemini's JSON output fields to `generationConfig`.
+    pub fn google_config(&self, config: &mut serde_json::Map<String, Value>) {
+        config.insert("responseMimeType".into(), "application/json".into());
+        if let Some(schema) = self.schema() {
+            config.insert("responseJsonSchema".into(), schema.clone());
+        }
+    }
+
+    /// Parses the model's reply and checks it against the format.
+    pub fn parse(&self, output: &str) -> Result<Value, String> {
+        let text = strip_fence(output.trim());
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| format!("the model's reply is not valid JSON: {e}"))?;
+        match self.schema() {
+            Some(schema) => JsonSchema::new(schema.clone())
+                .validate_deep(&value)
+                .map_err(|e| format!("the model's reply doesn't match the response_format schema: {e}"))?,
+            None if !value.is_object() => return Err("the model's reply is not a JSON object".into()),
+            None => {}
+        }
+        Ok(value)
+    }
+}
+
+/// The body of a ```` ```json ```` fence, or `text` unchanged.
+fn strip_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let body = rest.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
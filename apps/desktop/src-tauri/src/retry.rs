@@ -0,0 +1,203 @@
+//! Retries of transient provider errors on the direct (BYOK) path.
+//!
+//! Rate limits (429), overload (Anthropic's 529) and 5xx gateway errors are
+//! usually over within seconds, so a request that hits one is sent again
+//! instead of surfacing the raw error — after the provider's `Retry-After`
+//! when it gives one, otherwise after an exponential backoff, for at most
+//! [`MAX_ATTEMPTS`] sends. Connection failures are retried the same way.
+//! Streams announce each wait with a `retry` event on their sink; the last
+//! response is returned as-is, so callers report its error as before.
+//!
+//! Only the initial request is retried — a stream that fails midway has
+//! already emitted chunks.
+//!
+//! A request with a [`RequestGate`] is admitted again before each send, so
+//! every retry waits its turn in the provider's queue and is refused once
+//! the budget is spent; the admission is released during the wait.
+
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+
+use crate::providers::{Admission, RequestGate};
+use crate::redact;
+use crate::shutdown_signal;
+use crate::stream::StreamSink;
+
+/// Sends per request, the first included.
+pub const MAX_ATTEMPTS: u32 = 4;
+/// Backoff before the first retry; doubled for each one after.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait before a retry. A longer `Retry-After` isn't waited out:
+/// the error is returned instead.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Payload of the `retry` event.
+#[derive(Serialize, Clone)]
+struct RetryProgress {
+    /// The send about to be made (2 for the first retry).
+    attempt: u32,
+    max_attempts: u32,
+    /// HTTP status that caused the retry; `None` for a connection failure.
+    status: Option<u16>,
+    delay_ms: u64,
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Delay asked for by the provider: `retry-after-ms` (OpenAI), or
+/// `Retry-After` as seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Exponential backoff for the retry after `attempt` sends, with up to 25%
+/// jitter so concurrent streams don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_DELAY.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_DELAY);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    delay + delay.mul_f64(f64::from(nanos % 1000) / 4000.0)
+}
+
+/// A gate and the provider whose requests it admits.
+pub type Gate<'a> = (&'a dyn RequestGate, &'a str);
+
+/// Sends a provider request, retrying transient failures. With a `gate`,
+/// each send is admitted first and the last admission is returned with the
+/// response, to be held until the reply is read.
+pub async fn send(
+    req: RequestBuilder,
+    sink: Option<&StreamSink>,
+    gate: Option<Gate<'_>>,
+) -> Result<(Response, Option<Admission>), String> {
+    let reply = |result: reqwest::Result<Response>, admission| {
+        result.map(|resp| (resp, admission)).map_err(redact::error)
+    };
+    let mut attempt = 1;
+    loop {
+        let admission = match gate {
+            Some((gate, provider)) => Some(gate.admit(provider).await?),
+            None => None,
+        };
+        // Bodies here are always buffered, so the clone only fails for streams.
+        let Some(next) = req.try_clone().filter(|_| attempt < MAX_ATTEMPTS) else {
+            return reply(req.send().await, admission);
+        };
+        let result = next.send().await;
+        let (status, delay) = match &result {
+            Ok(resp) if is_retryable(resp.status()) => {
+                let delay = retry_after(resp.headers()).unwrap_or_else(|| backoff(attempt));
+                if delay > MAX_DELAY {
+                    return reply(result, admission);
+                }
+                (Some(resp.status().as_u16()), delay)
+            }
+            Err(e) if e.is_connect() || e.is_timeout() => (None, backoff(attempt)),
+            _ => return reply(result, admission),
+        };
+        drop(admission);
+        attempt += 1;
+        if let Some(sink) = sink {
+            let delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+            sink.emit("retry", RetryProgress { attempt, max_attempts: MAX_ATTEMPTS, status, delay_ms });
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_signal::cancelled() => return reply(result, None),
+        }
+    }
+}
+
+/// [`send`] as a method, to keep provider request chains readable.
+pub trait SendWithRetry {
+    fn send_with_retry<'a>(
+        self,
+        sink: Option<&'a StreamSink>,
+        gate: Option<Gate<'a>>,
+    ) -> impl Future<Output = Result<(Response, Option<Admission>), String>> + Send + 'a;
+}
+
+impl SendWithRetry for RequestBuilder {
+    fn send_with_retry<'a>(
+        self,
+        sink: Option<&'a StreamSink>,
+        gate: Option<Gate<'a>>,
+    ) -> impl Future<Output = Result<(Response, Option<Admission>), String>> + Send + 'a {
+        send(self, sink, gate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(retry_after(&headers(&[("retry-after", "7")])), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after(&headers(&[("retry-after", " 1.5 ")])), Some(Duration::from_millis(1500)));
+        assert_eq!(retry_after(&headers(&[("retry-after-ms", "250")])), Some(Duration::from_millis(250)));
+        // The millisecond header wins.
+        let both = headers(&[("retry-after", "7"), ("retry-after-ms", "250")]);
+        assert_eq!(retry_after(&both), Some(Duration::from_millis(250)));
+        assert_eq!(retry_after(&headers(&[("retry-after", "-1")])), None);
+        assert_eq!(retry_after(&headers(&[("retry-after", "soon")])), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let at = chrono::Utc::now() + chrono::Duration::seconds(20);
+        let delay = retry_after(&headers(&[("retry-after", &at.to_rfc2822())])).unwrap();
+        assert!(delay > Duration::from_secs(18) && delay <= Duration::from_secs(20), "{delay:?}");
+        // A date already past means retry now.
+        let past = headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        assert_eq!(retry_after(&past), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_bounded_jitter() {
+        for attempt in 1..=40 {
+            let base = BASE_DELAY.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_DELAY);
+            let delay = backoff(attempt);
+            assert!(delay >= base && delay <= base + base / 4, "attempt {attempt}: {delay:?}");
+        }
+        assert!(backoff(1) < Duration::from_millis(1250));
+        assert!(backoff(3) >= Duration::from_secs(4));
+        assert!(backoff(40) <= MAX_DELAY + MAX_DELAY / 4);
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retried() {
+        for status in [408, 429, 500, 502, 503, 504, 529] {
+            assert!(is_retryable(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+        for status in [200, 400, 401, 403, 404, 413, 501] {
+            assert!(!is_retryable(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+    }
+}
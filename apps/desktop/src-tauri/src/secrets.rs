@@ -0,0 +1,247 @@
+//! Secrets in the OS credential store — macOS Keychain, Windows Credential
+//! Manager, or the freedesktop Secret Service (libsecret) on Linux.
+//!
+//! Gateway access tokens and provider API keys are kept here instead of in
+//! plain JSON. Each secret is a generic password under the app's service
+//! name, with the secret's name as the account.
+//!
+//! On Linux the Secret Service is reached through `secret-tool` (part of
+//! libsecret), which reads the value from stdin so it never shows up in the
+//! process list. Where no credential store is available (a headless Linux
+//! box without a keyring daemon) the functions return an error and callers
+//! fall back to the app's JSON store.
+
+/// Service name every secret is filed under.
+pub const SERVICE: &str = "com.agenthub.desktop";
+
+const MAX_NAME_LEN: usize = 128;
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("secret name must be 1–{MAX_NAME_LEN} characters"));
+    }
+    if name.chars().any(|c| c.is_control()) {
+        return Err("secret name must not contain control characters".into());
+    }
+    Ok(())
+}
+
+// ── macOS ──────────────────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+mod backend {
+    use security_framework::passwords;
+
+    /// `errSecItemNotFound`
+    const NOT_FOUND: i32 = -25300;
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        passwords::set_generic_password(super::SERVICE, name, value.as_bytes())
+            .map_err(|e| format!("keychain write failed: {e}"))
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        match passwords::get_generic_password(super::SERVICE, name) {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| "keychain item is not valid UTF-8".into()),
+            Err(e) if e.code() == NOT_FOUND => Ok(None),
+            Err(e) => Err(format!("keychain read failed: {e}")),
+        }
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        match passwords::delete_generic_password(super::SERVICE, name) {
+            Err(e) if e.code() != NOT_FOUND => Err(format!("keychain delete failed: {e}")),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ── Windows ────────────────────────────────────────────────────────────────────
+
+#[cfg(windows)]
+mod backend {
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE,
+        CRED_TYPE_GENERIC,
+    };
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Credential Manager has a single namespace; prefix with the service.
+    fn target(name: &str) -> Vec<u16> {
+        wide(&format!("{}:{name}", super::SERVICE))
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        let mut target = target(name);
+        let mut user = wide(name);
+        let mut blob = value.as_bytes().to_vec();
+        // SAFETY: every pointer refers to a live, NUL-terminated buffer owned
+        // by this frame; CredWriteW copies them before returning.
+        let ok = unsafe {
+            let mut cred: CREDENTIALW = std::mem::zeroed();
+            cred.Type = CRED_TYPE_GENERIC;
+            cred.TargetName = target.as_mut_ptr();
+            cred.UserName = user.as_mut_ptr();
+            cred.CredentialBlobSize = blob.len() as u32;
+            cred.CredentialBlob = blob.as_mut_ptr();
+            cred.Persist = CRED_PERSIST_LOCAL_MACHINE;
+            CredWriteW(&cred, 0)
+        };
+        if ok == 0 {
+            return Err(format!("credential write failed (error {})", unsafe { GetLastError() }));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        let target = target(name);
+        let mut cred: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: `target` is NUL-terminated; on success `cred` points to a
+        // credential allocated by the system, released with CredFree below.
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut cred) == 0 {
+                let err = GetLastError();
+                if err == ERROR_NOT_FOUND {
+                    return Ok(None);
+                }
+                return Err(format!("credential read failed (error {err})"));
+            }
+            let blob = std::slice::from_raw_parts(
+                (*cred).CredentialBlob,
+                (*cred).CredentialBlobSize as usize,
+            );
+            let value = String::from_utf8(blob.to_vec());
+            CredFree(cred as *const _);
+            value.map(Some).map_err(|_| "credential is not valid UTF-8".into())
+        }
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        let target = target(name);
+        // SAFETY: `target` is NUL-terminated.
+        unsafe {
+            if CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) == 0 {
+                let err = GetLastError();
+                if err != ERROR_NOT_FOUND {
+                    return Err(format!("credential delete failed (error {err})"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// ── Linux / BSD ────────────────────────────────────────────────────────────────
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod backend {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    fn secret_tool(args: &[&str], name: &str, stdin: Option<&str>) -> Result<std::process::Output, String> {
+        let mut child = Command::new("secret-tool")
+            .args(args)
+            .args(["service", super::SERVICE, "account", name])
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("secret service unavailable (is libsecret installed?): {e}"))?;
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input.as_bytes())
+                .map_err(|e| format!("secret-tool write failed: {e}"))?;
+        }
+        child.wait_with_output().map_err(|e| format!("secret-tool failed: {e}"))
+    }
+
+    fn failure(action: &str, out: &std::process::Output) -> String {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        format!("secret service {action} failed: {}", stderr.trim())
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        let label = format!("--label=AgentHub: {name}");
+        let out = secret_tool(&["store", &label], name, Some(value))?;
+        if !out.status.success() {
+            return Err(failure("write", &out));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        let out = secret_tool(&["lookup"], name, None)?;
+        if out.status.success() {
+            return String::from_utf8(out.stdout)
+                .map(Some)
+                .map_err(|_| "secret is not valid UTF-8".into());
+        }
+        // `lookup` exits 1 without output when nothing matches.
+        if out.stderr.is_empty() {
+            return Ok(None);
+        }
+        Err(failure("read", &out))
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        let out = secret_tool(&["clear"], name, None)?;
+        if !out.status.success() && !out.stderr.is_empty() {
+            return Err(failure("delete", &out));
+        }
+        Ok(())
+    }
+}
+
+// ── Public API ─────────────────────────────────────────────────────────────────
+
+/// Stores `value` under `name`, replacing any earlier value.
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    validate_name(name)?;
+    backend::set(name, value)
+}
+
+/// The value stored under `name`, `None` when there is none.
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    validate_name(name)?;
+    backend::get(name)
+}
+
+/// Removes `name`; removing a missing secret is not an error.
+pub fn delete(name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    backend::delete(name)
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Stores a secret (e.g. a provider API key) in the OS credential store.
+#[tauri::command]
+pub async fn secret_set(name: String, value: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || set(&name, &value))
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+}
+
+/// Reads a secret from the OS credential store.
+#[tauri::command]
+pub async fn secret_get(name: String) -> Result<Option<String>, String> {
+    tokio::task::spawn_blocking(move || get(&name))
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+}
+
+/// Deletes a secret from the OS credential store.
+#[tauri::command]
+pub async fn secret_delete(name: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || delete(&name))
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+}
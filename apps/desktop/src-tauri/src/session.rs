@@ -0,0 +1,350 @@
+//! OS session awareness for computer use.
+//!
+//! A background monitor polls the desktop session for screen lock, fast user
+//! switching (another user's session in the foreground) and remote-desktop
+//! connections, and emits `system:session-changed` whenever any of them
+//! changes. Input injection is refused while the session is not interactive —
+//! keystrokes meant for an automation must never land in a lock screen or in
+//! someone else's session.
+//!
+//! Any change also pauses computer-use input until the UI calls
+//! `computer_session_resume`, so an agent doesn't carry on typing the moment
+//! the screen unlocks, possibly into a different context than it left.
+//! Input is likewise refused while the user has paused agents (see
+//! [`set_agents_paused`]). Keys and buttons left held are released on both.
+//! Input that is allowed still counts against the per-second cap (see
+//! [`computer::throttle_input`]).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::computer;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static AGENTS_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// State of the desktop session this app runs in.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionState {
+    /// The screen is locked (or the secure desktop is showing).
+    pub locked: bool,
+    /// This session owns the display — `false` after a fast user switch.
+    pub active: bool,
+    /// The session is driven over remote desktop.
+    pub remote: bool,
+}
+
+impl SessionState {
+    /// Used when the platform can't be queried; never blocks input.
+    const UNKNOWN: Self = Self { locked: false, active: true, remote: false };
+
+    fn interactive(&self) -> bool {
+        self.active && !self.locked
+    }
+}
+
+/// Payload of `system:session-changed` and result of `computer_session_status`.
+#[derive(Serialize, Clone)]
+pub struct SessionStatus {
+    #[serde(flatten)]
+    pub state: SessionState,
+    /// Computer-use input is on hold until `computer_session_resume`.
+    pub paused: bool,
+}
+
+struct Tracker {
+    /// Last polled state; `None` until the monitor's first poll.
+    state: Option<SessionState>,
+    paused: bool,
+}
+
+fn tracker() -> &'static Mutex<Tracker> {
+    static TRACKER: OnceLock<Mutex<Tracker>> = OnceLock::new();
+    TRACKER.get_or_init(|| Mutex::new(Tracker { state: None, paused: false }))
+}
+
+fn current_state() -> SessionState {
+    let cached = tracker().lock().unwrap_or_else(|e| e.into_inner()).state;
+    cached.unwrap_or_else(platform::query)
+}
+
+/// Whether the user has paused agents.
+pub fn agents_paused() -> bool {
+    AGENTS_PAUSED.load(Ordering::Relaxed)
+}
+
+/// Pauses or resumes agents; returns whether the state changed.
+pub fn set_agents_paused(paused: bool) -> bool {
+    AGENTS_PAUSED.swap(paused, Ordering::Relaxed) != paused
+}
+
+// ── Monitor ────────────────────────────────────────────────────────────────────
+
+/// Starts polling the session on a background thread. Call once at startup.
+pub fn start_monitor(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let state = platform::query();
+        let changed = {
+            let mut t = tracker().lock().unwrap_or_else(|e| e.into_inner());
+            let changed = t.state.is_some_and(|prev| prev != state);
+            if changed {
+                t.paused = true;
+            }
+            t.state = Some(state);
+            changed.then_some(t.paused)
+        };
+        if let Some(paused) = changed {
+            // Keys held with `computer_key_down` must not stay down meanwhile.
+            let _ = computer::release_held_input();
+            let _ = app.emit("system:session-changed", SessionStatus { state, paused });
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Fails unless synthetic mouse / keyboard input may be sent right now.
+pub fn ensure_input_allowed() -> Result<(), String> {
+    if agents_paused() {
+        return Err("input refused: agents are paused".into());
+    }
+    let state = current_state();
+    if state.locked {
+        return Err("input refused: the screen is locked".into());
+    }
+    if !state.active {
+        return Err("input refused: another user session is in the foreground".into());
+    }
+    if tracker().lock().unwrap_or_else(|e| e.into_inner()).paused {
+        return Err(
+            "computer use is paused after a session change (lock, user switch or remote \
+             connection) — resume it to continue"
+                .into(),
+        );
+    }
+    computer::throttle_input()
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Returns the current session state and whether computer use is paused.
+#[tauri::command]
+pub async fn computer_session_status() -> SessionStatus {
+    tokio::task::spawn_blocking(|| SessionStatus {
+        state: current_state(),
+        paused: tracker().lock().unwrap_or_else(|e| e.into_inner()).paused,
+    })
+    .await
+    .unwrap_or(SessionStatus { state: SessionState::UNKNOWN, paused: false })
+}
+
+/// Lifts the pause set by a session change. Fails while the session is still
+/// locked or switched away.
+#[tauri::command]
+pub async fn computer_session_resume() -> Result<(), String> {
+    tokio::task::spawn_blocking(|| {
+        let state = platform::query();
+        if !state.interactive() {
+            return Err("the session is not interactive yet".to_string());
+        }
+        let mut t = tracker().lock().unwrap_or_else(|e| e.into_inner());
+        t.state = Some(state);
+        t.paused = false;
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+// ── Platform queries ───────────────────────────────────────────────────────────
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::SessionState;
+
+    /// Reads the logind session (`LockedHint`, `Active`, `Remote`). Systems
+    /// without logind report an unknown, interactive session.
+    pub fn query() -> SessionState {
+        let id = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".into());
+        let Ok(out) = std::process::Command::new("loginctl")
+            .args(["show-session", &id, "-p", "LockedHint", "-p", "Active", "-p", "Remote"])
+            .output()
+        else {
+            return SessionState::UNKNOWN;
+        };
+        if !out.status.success() {
+            return SessionState::UNKNOWN;
+        }
+
+        let text = String::from_utf8_lossy(&out.stdout);
+        let prop = |name: &str| {
+            text.lines()
+                .find_map(|l| l.strip_prefix(name)?.strip_prefix('='))
+                .map(|v| v.trim() == "yes")
+        };
+        SessionState {
+            locked: prop("LockedHint").unwrap_or(false),
+            active: prop("Active").unwrap_or(true),
+            remote: prop("Remote").unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SessionState;
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> CFTypeRef;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithBytes(
+            alloc: CFTypeRef,
+            bytes: *const u8,
+            len: isize,
+            encoding: u32,
+            external: u8,
+        ) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFGetTypeID(cf: CFTypeRef) -> usize;
+        fn CFBooleanGetTypeID() -> usize;
+        fn CFBooleanGetValue(boolean: CFTypeRef) -> u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    /// Reads a boolean from the session dictionary; `None` when absent.
+    unsafe fn bool_key(dict: CFTypeRef, key: &str) -> Option<bool> {
+        let cf_key = CFStringCreateWithBytes(
+            std::ptr::null(),
+            key.as_ptr(),
+            key.len() as isize,
+            UTF8,
+            0,
+        );
+        if cf_key.is_null() {
+            return None;
+        }
+        let value = CFDictionaryGetValue(dict, cf_key);
+        CFRelease(cf_key);
+        if value.is_null() || CFGetTypeID(value) != CFBooleanGetTypeID() {
+            return None;
+        }
+        Some(CFBooleanGetValue(value) != 0)
+    }
+
+    /// Reads the window-server session. macOS does not report screen
+    /// sharing here, so `remote` is always `false`.
+    pub fn query() -> SessionState {
+        // SAFETY: the dictionary is owned by us (Copy rule) and released
+        // below; keys are created and released in `bool_key`.
+        unsafe {
+            let dict = CGSessionCopyCurrentDictionary();
+            if dict.is_null() {
+                // No window-server session: running outside the console.
+                return SessionState { locked: false, active: false, remote: false };
+            }
+            let state = SessionState {
+                locked: bool_key(dict, "CGSSessionScreenIsLocked").unwrap_or(false),
+                active: bool_key(dict, "kCGSSessionOnConsoleKey").unwrap_or(true),
+                remote: false,
+            };
+            CFRelease(dict);
+            state
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::SessionState;
+    use std::ffi::c_void;
+
+    const SM_REMOTESESSION: i32 = 0x1000;
+    const UOI_NAME: i32 = 2;
+    const DESKTOP_READOBJECTS: u32 = 0x0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn WTSGetActiveConsoleSessionId() -> u32;
+        fn GetCurrentProcessId() -> u32;
+        fn ProcessIdToSessionId(pid: u32, session_id: *mut u32) -> i32;
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetSystemMetrics(index: i32) -> i32;
+        fn OpenInputDesktop(flags: u32, inherit: i32, access: u32) -> *mut c_void;
+        fn CloseDesktop(desktop: *mut c_void) -> i32;
+        fn GetUserObjectInformationW(
+            object: *mut c_void,
+            index: i32,
+            info: *mut c_void,
+            len: u32,
+            needed: *mut u32,
+        ) -> i32;
+    }
+
+    /// `true` unless the input desktop is the normal `Default` desktop —
+    /// the lock screen and UAC prompts run on the secure `Winlogon` desktop,
+    /// which this process can't open.
+    unsafe fn input_desktop_locked() -> bool {
+        let desktop = OpenInputDesktop(0, 0, DESKTOP_READOBJECTS);
+        if desktop.is_null() {
+            return true;
+        }
+        let mut name = [0u16; 64];
+        let mut needed = 0u32;
+        let ok = GetUserObjectInformationW(
+            desktop,
+            UOI_NAME,
+            name.as_mut_ptr().cast(),
+            (name.len() * 2) as u32,
+            &mut needed,
+        );
+        CloseDesktop(desktop);
+        if ok == 0 {
+            return true;
+        }
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        !String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case("Default")
+    }
+
+    pub fn query() -> SessionState {
+        // SAFETY: plain Win32 queries; the desktop handle is closed in
+        // `input_desktop_locked`.
+        unsafe {
+            let remote = GetSystemMetrics(SM_REMOTESESSION) != 0;
+            let mut own = 0u32;
+            let console = WTSGetActiveConsoleSessionId();
+            let on_console =
+                ProcessIdToSessionId(GetCurrentProcessId(), &mut own) == 0 || own == console;
+            SessionState {
+                locked: input_desktop_locked(),
+                active: remote || on_console,
+                remote,
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::SessionState;
+
+    pub fn query() -> SessionState {
+        SessionState::UNKNOWN
+    }
+}
@@ -0,0 +1,195 @@
+//! User settings persisted in `settings.json`.
+//!
+//! Holds the small helpers other modules use to read and write individual
+//! settings, plus the commands for the custom OpenAI-compatible provider
+//! (vLLM, LiteLLM, Ollama, corporate proxies), the AWS Bedrock region, the
+//! stream stall timeout and secret redaction. `app_settings` offers all
+//! of them, and the other general preferences, as one typed struct.
+
+use std::time::Duration;
+
+use reqwest::Url;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{bedrock, redact};
+
+pub const SETTINGS_STORE: &str = "settings.json";
+
+const CUSTOM_BASE_URL_KEY: &str = "custom_base_url";
+const CUSTOM_MODEL_KEY: &str = "custom_model";
+const BEDROCK_REGION_KEY: &str = "bedrock_region";
+const STREAM_IDLE_TIMEOUT_KEY: &str = "stream_idle_timeout_secs";
+const REDACTION_KEY: &str = "redaction";
+
+/// Default and bounds of the stream stall timeout, in seconds.
+pub const DEFAULT_STREAM_IDLE_TIMEOUT_SECS: u64 = 30;
+pub const MIN_STREAM_IDLE_TIMEOUT_SECS: u64 = 5;
+pub const MAX_STREAM_IDLE_TIMEOUT_SECS: u64 = 120;
+
+// ── Store helpers ──────────────────────────────────────────────────────────────
+
+pub fn get_string(app: &AppHandle, key: &str) -> Option<String> {
+    app.store(SETTINGS_STORE)
+        .ok()?
+        .get(key)?
+        .as_str()
+        .map(String::from)
+}
+
+/// Sets `key`, or removes it when `value` is `None`.
+pub fn set_string(app: &AppHandle, key: &str, value: Option<&str>) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    match value {
+        Some(v) => store.set(key, serde_json::Value::String(v.to_owned())),
+        None => {
+            store.delete(key);
+        }
+    }
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+// ── Custom provider ────────────────────────────────────────────────────────────
+
+/// Endpoint of the `custom` provider.
+#[derive(Serialize, Clone)]
+pub struct CustomProvider {
+    /// Base URL up to (not including) `/chat/completions`, e.g. `http://localhost:8000/v1`.
+    pub base_url: String,
+    /// Model used when a request doesn't name one.
+    pub model: Option<String>,
+}
+
+pub fn normalize_base_url(raw: &str) -> Result<String, String> {
+    let url = Url::parse(raw.trim()).map_err(|e| format!("invalid base URL: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("base URL must be http or https (got '{}')", url.scheme()));
+    }
+    Ok(url.as_str().trim_end_matches('/').to_owned())
+}
+
+pub fn load_custom_provider(app: &AppHandle) -> Option<CustomProvider> {
+    Some(CustomProvider {
+        base_url: get_string(app, CUSTOM_BASE_URL_KEY)?,
+        model: get_string(app, CUSTOM_MODEL_KEY),
+    })
+}
+
+/// Returns the configured custom provider, or `null` when none is set.
+#[tauri::command]
+pub async fn settings_get_custom_provider(app: AppHandle) -> Option<CustomProvider> {
+    load_custom_provider(&app)
+}
+
+/// Configures (or with a `null` base URL, removes) the `custom` provider.
+#[tauri::command]
+pub async fn settings_set_custom_provider(
+    app: AppHandle,
+    base_url: Option<String>,
+    model: Option<String>,
+) -> Result<(), String> {
+    let Some(raw) = base_url.filter(|u| !u.trim().is_empty()) else {
+        set_string(&app, CUSTOM_BASE_URL_KEY, None)?;
+        return set_string(&app, CUSTOM_MODEL_KEY, None);
+    };
+    let base_url = normalize_base_url(&raw)?;
+    let model = model.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    set_string(&app, CUSTOM_BASE_URL_KEY, Some(&base_url))?;
+    set_string(&app, CUSTOM_MODEL_KEY, model)
+}
+
+// ── AWS Bedrock ────────────────────────────────────────────────────────────────
+
+pub fn load_bedrock_region(app: &AppHandle) -> Option<String> {
+    get_string(app, BEDROCK_REGION_KEY)
+}
+
+/// Returns the AWS region used by the `bedrock` provider, or `null` when unset.
+#[tauri::command]
+pub async fn settings_get_bedrock_region(app: AppHandle) -> Option<String> {
+    load_bedrock_region(&app)
+}
+
+/// Sets (or with `null`, clears) the AWS region used by the `bedrock` provider.
+#[tauri::command]
+pub async fn settings_set_bedrock_region(app: AppHandle, region: Option<String>) -> Result<(), String> {
+    let region = region.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if let Some(r) = region {
+        bedrock::validate_region(r)?;
+    }
+    set_string(&app, BEDROCK_REGION_KEY, region)
+}
+
+// ── Streaming ──────────────────────────────────────────────────────────────────
+
+/// How long a response stream may go without receiving any bytes (keep-alive
+/// comments count) before it is aborted as stalled.
+pub fn load_stream_idle_timeout(app: &AppHandle) -> Duration {
+    let secs = app
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(STREAM_IDLE_TIMEOUT_KEY))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_STREAM_IDLE_TIMEOUT_SECS);
+    Duration::from_secs(secs.clamp(MIN_STREAM_IDLE_TIMEOUT_SECS, MAX_STREAM_IDLE_TIMEOUT_SECS))
+}
+
+/// Returns the stream stall timeout in seconds.
+#[tauri::command]
+pub async fn settings_get_stream_idle_timeout(app: AppHandle) -> u64 {
+    load_stream_idle_timeout(&app).as_secs()
+}
+
+/// Sets (or with `null`, resets to the default) the stream stall timeout in
+/// seconds, between 5 and 120.
+#[tauri::command]
+pub async fn settings_set_stream_idle_timeout(app: AppHandle, secs: Option<u64>) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    match secs {
+        Some(s) if !(MIN_STREAM_IDLE_TIMEOUT_SECS..=MAX_STREAM_IDLE_TIMEOUT_SECS).contains(&s) => {
+            return Err(format!(
+                "stream idle timeout must be between {MIN_STREAM_IDLE_TIMEOUT_SECS} and \
+                 {MAX_STREAM_IDLE_TIMEOUT_SECS} seconds (got {s})"
+            ));
+        }
+        Some(s) => store.set(STREAM_IDLE_TIMEOUT_KEY, serde_json::Value::from(s)),
+        None => {
+            store.delete(STREAM_IDLE_TIMEOUT_KEY);
+        }
+    }
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+// ── Redaction ──────────────────────────────────────────────────────────────────
+
+pub fn load_redaction(app: &AppHandle) -> redact::RedactionConfig {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(REDACTION_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Returns what is masked in errors and log lines.
+#[tauri::command]
+pub async fn settings_get_redaction(app: AppHandle) -> redact::RedactionConfig {
+    load_redaction(&app)
+}
+
+/// Sets what is masked in errors and log lines; applies immediately.
+#[tauri::command]
+pub async fn settings_set_redaction(app: AppHandle, config: redact::RedactionConfig) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store.set(REDACTION_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))?;
+    redact::configure(config);
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Orderly shutdown.
+//!
+//! The first exit request is held back (`prevent_exit`) while [`run`] winds
+//! the app down in order, then exits for real:
+//!
+//! 1. Refuse new work and announce `app:shutdown`, so the frontend can save
+//!    drafts and open chats. Parked tool turns are dropped — they hold API
+//!    keys and aren't persisted — and their request IDs are listed in the
+//!    event so those chats can be marked interrupted.
+//! 2. Stop producers: the capture, quick-chat and emergency-stop hotkeys,
+//!    their windows, and live views. Commands waiting for an approval fail.
+//! 3. Cancel in-flight streams — reads fail with "cancelled" — and wait up
+//!    to [`DRAIN_TIMEOUT`] for their commands to return.
+//! 4. Release keys and mouse buttons a computer-use command left pressed,
+//!    and let the display sleep again.
+//! 5. Kill terminal sessions and background processes, and close the
+//!    browser `browser_launch` started.
+//! 6. Flush the plugin stores to disk.
+//!
+//! A step that fails doesn't stop the ones after it; an exit requested again
+//! during shutdown waits for it to finish.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::tool_calls::PendingToolTurns;
+use crate::{
+    approval, browser, capture_ask, computer, emergency_stop, live_view, processes, quick_chat,
+    settings, shutdown_signal, system, terminal,
+};
+
+pub use crate::shutdown_signal::{cancelled, ensure_running, is_shutting_down, InFlight};
+
+/// Longest wait for in-flight streams to return after being cancelled.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Stores flushed on exit.
+const STORES: &[&str] = &[settings::SETTINGS_STORE, "credentials.json", "agents.json"];
+
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Payload of `app:shutdown`.
+#[derive(Serialize, Clone)]
+struct ShutdownStarted {
+    /// Request IDs of chats that were waiting for tool results.
+    interrupted_tool_turns: Vec<String>,
+}
+
+/// Handles an exit request: `true` when the exit should be held back while
+/// [`run`] goes first. The coordinator's own exit passes through.
+pub fn on_exit_requested(app: &AppHandle) -> bool {
+    if FINISHED.load(Ordering::Relaxed) {
+        return false;
+    }
+    if shutdown_signal::begin() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            run(&app).await;
+            FINISHED.store(true, Ordering::Relaxed);
+            app.exit(0);
+        });
+    }
+    true
+}
+
+/// Winds the app down; see the module docs for the order.
+async fn run(app: &AppHandle) {
+    let interrupted_tool_turns = app.state::<PendingToolTurns>().drain();
+    let _ = app.emit("app:shutdown", ShutdownStarted { interrupted_tool_turns });
+
+    capture_ask::stop(app);
+    quick_chat::stop(app);
+    emergency_stop::stop(app);
+    app.state::<live_view::LiveViews>().stop_all();
+    app.state::<approval::Approvals>().cancel_all();
+
+    shutdown_signal::cancel_waiters();
+    let started = Instant::now();
+    while shutdown_signal::in_flight() > 0 && started.elapsed() < DRAIN_TIMEOUT {
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+
+    let _ = tokio::task::spawn_blocking(computer::release_held_input).await;
+    system::release_keep_awake();
+    app.state::<terminal::TerminalSessions>().close_all();
+    app.state::<processes::Processes>().kill_all();
+    app.state::<browser::Browser>().close().await;
+
+    for name in STORES {
+        if let Some(store) = app.get_store(name) {
+            let _ = store.save();
+        }
+    }
+}
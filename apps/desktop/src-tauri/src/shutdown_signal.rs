@@ -0,0 +1,71 @@
+//! The shutdown flag and in-flight stream count.
+//!
+//! Split from [`shutdown`](crate::shutdown), which drives the shutdown
+//! itself, so the streaming code in the library can stop work and hold it
+//! back without depending on the app's windows and hotkeys.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+fn notify() -> &'static tokio::sync::Notify {
+    static NOTIFY: OnceLock<tokio::sync::Notify> = OnceLock::new();
+    NOTIFY.get_or_init(tokio::sync::Notify::new)
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::Relaxed)
+}
+
+/// Marks shutdown as started; `false` when it already had.
+pub fn begin() -> bool {
+    !SHUTTING_DOWN.swap(true, Ordering::Relaxed)
+}
+
+/// Fails once shutdown has started, for commands that start new work.
+pub fn ensure_running() -> Result<(), String> {
+    if is_shutting_down() {
+        return Err("the app is shutting down".into());
+    }
+    Ok(())
+}
+
+/// Resolves once shutdown starts.
+pub async fn cancelled() {
+    loop {
+        // Created before the check so a `notify_waiters` in between isn't missed.
+        let notified = notify().notified();
+        if is_shutting_down() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Wakes every [`cancelled`] waiter; call after [`begin`].
+pub fn cancel_waiters() {
+    notify().notify_waiters();
+}
+
+/// Streams currently counted by an [`InFlight`].
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// Counts a running stream until dropped.
+pub struct InFlight(());
+
+impl InFlight {
+    pub fn enter() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+        Self(())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,316 @@
+//! One-off runtime skill calls, without compiling an agent.
+//!
+//! Modules register skill definitions with `skill_register` and call them with
+//! `skill_invoke`, which runs the skill through the runtime's
+//! [`SkillExecutor`]: input and output are validated against the skill's
+//! schemas, repeated inputs are answered from its cache, and every call's
+//! tokens and cost are recorded — against the chat session that launched
+//! the call, when given. The built-in `calculate` skill is always
+//! registered.
+//!
+//! Calls run one at a time — the executor and its cache are shared.
+
+use std::sync::{Arc, Mutex};
+
+use agenthub_runtime::calculator;
+use agenthub_runtime::provider::{
+    model_cost_per_1k, LLMRequest, ModelProvider, ModelResponse, ProviderError, TokenUsage,
+};
+use agenthub_runtime::skill::{ResponseMode, SkillDefinition};
+use agenthub_runtime::skill_executor::SkillExecutor;
+use agenthub_runtime::token_optimizer::{
+    estimate_tokens, ConversationUsage, TokenBreakdown, TokenTracker,
+};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::providers::{call_provider_generate, direct_key, GenerationParams, ProviderCall};
+use crate::request_queue::QueueGate;
+use crate::{budget, provider_keys, redact, resolve_endpoint, AppState};
+
+const MAX_SKILLS: usize = 256;
+
+struct SkillRuntime {
+    executor: SkillExecutor,
+    skills: Vec<SkillDefinition>,
+    tracker: TokenTracker,
+}
+
+/// Registered skills and the executor that runs them.
+pub struct SkillRegistry(Mutex<SkillRuntime>);
+
+impl Default for SkillRegistry {
+    fn default() -> Self {
+        let mut executor = SkillExecutor::new();
+        calculator::register(&mut executor);
+        Self(Mutex::new(SkillRuntime {
+            executor,
+            skills: vec![calculator::skill_definition()],
+            tracker: TokenTracker::new(),
+        }))
+    }
+}
+
+/// Outcome of [`skill_invoke`].
+#[derive(Serialize)]
+pub struct SkillInvokeResult {
+    /// Validated output.
+    pub output: serde_json::Value,
+    /// Answered from the executor's cache without a model call.
+    pub cached: bool,
+    pub model: String,
+    pub total_tokens: u32,
+    pub cost: f64,
+}
+
+/// Spend of all skill calls so far.
+#[derive(Serialize)]
+pub struct SkillUsage {
+    pub calls: usize,
+    pub total_tokens: u32,
+    pub total_cost: f64,
+    /// Spend of the calls launched from the session asked about.
+    pub session: Option<SessionSkillUsage>,
+}
+
+/// Skill spend attributed to one chat session.
+#[derive(Serialize)]
+pub struct SessionSkillUsage {
+    pub session_id: String,
+    pub calls: u32,
+    pub total_tokens: u32,
+    pub total_cost: f64,
+}
+
+impl From<ConversationUsage> for SessionSkillUsage {
+    fn from(u: ConversationUsage) -> Self {
+        Self {
+            session_id: u.conversation_id,
+            calls: u.skill_calls,
+            total_tokens: u.total_tokens,
+            total_cost: u.total_cost,
+        }
+    }
+}
+
+/// Bridges the runtime's blocking [`ModelProvider`] to the async provider
+/// calls; only used from a blocking task.
+pub(crate) struct DirectProvider {
+    http: reqwest::Client,
+    runtime: tokio::runtime::Handle,
+    /// Admits each model call through the budget and request queue.
+    gate: QueueGate,
+    provider: String,
+    api_key: String,
+    base_url: Option<String>,
+    /// Replaces the model the runtime asks for.
+    model: Option<String>,
+    /// Chat session the calls are made for, for the usage ledger.
+    session_id: Option<String>,
+}
+
+impl DirectProvider {
+    /// Resolves the key and endpoint of `provider`; fails when it has no
+    /// key. `model` is kept as is, the provider default when omitted.
+    pub(crate) async fn resolve(
+        app: &AppHandle,
+        state: &AppState,
+        provider: String,
+        model: Option<String>,
+        api_key: Option<String>,
+        session_id: Option<String>,
+    ) -> Result<Self, String> {
+        let api_key = api_key.or_else(|| provider_keys::resolve(app, &provider, None));
+        let key = direct_key(api_key.as_deref(), Some(&provider))
+            .ok_or_else(|| format!("no API key for '{provider}' — pass one or store it with provider_keys_set"))?
+            .to_owned();
+        let (base_url, model) = resolve_endpoint(app, &state.http_client, &provider, model).await?;
+        let model = model.unwrap_or_else(|| crate::providers::default_model(&provider).to_owned());
+        Ok(Self {
+            http: state.http_client.clone(),
+            runtime: tokio::runtime::Handle::current(),
+            gate: QueueGate(app.clone()),
+            provider,
+            api_key: key,
+            base_url,
+            model: Some(model),
+            session_id,
+        })
+    }
+
+    /// Lets the runtime pick the model per call instead.
+    pub(crate) fn routed(mut self) -> Self {
+        self.model = None;
+        self
+    }
+
+    pub(crate) fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+}
+
+impl ModelProvider for DirectProvider {
+    fn call_model(&self, request: LLMRequest) -> Result<ModelResponse, ProviderError> {
+        let params = GenerationParams {
+            max_tokens: (request.max_tokens > 0).then_some(request.max_tokens),
+            ..Default::default()
+        };
+        let model = self.model.as_deref().unwrap_or(&request.model);
+        let call = ProviderCall::new(&self.provider, &self.api_key, Some(model), &request.user_content, &params)
+            .with_base_url(self.base_url.clone())
+            .with_system(Some(&request.system_prompt))
+            .with_session(self.session_id.as_deref())
+            .with_gate(Some(&self.gate));
+        let (content, usage) = self
+            .runtime
+            .block_on(call_provider_generate(&self.http, &call))
+            .map_err(ProviderError::CallFailed)?;
+        // Providers report a total here; split it with an estimate.
+        let total_tokens = u32::try_from(usage.total).unwrap_or(0);
+        let completion_tokens = estimate_tokens(&content).min(total_tokens);
+        Ok(ModelResponse {
+            content,
+            usage: TokenUsage {
+                prompt_tokens: total_tokens - completion_tokens,
+                completion_tokens,
+                total_tokens,
+                cached_prompt_tokens: u32::try_from(usage.cache_read).unwrap_or(0),
+            },
+            model: Arc::from(model),
+        })
+    }
+}
+
+fn default_system_prompt(skill: &SkillDefinition) -> String {
+    format!(
+        "You are the '{}' skill. Reply with a single JSON object matching this schema, and nothing else:\n{}",
+        skill.id,
+        serde_json::to_string(&skill.output_schema.schema).unwrap_or_default()
+    )
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Registers (or replaces) a skill for [`skill_invoke`].
+#[tauri::command]
+pub fn skill_register(registry: State<'_, SkillRegistry>, definition: SkillDefinition) -> Result<(), String> {
+    if definition.id.trim().is_empty() {
+        return Err("skill id must not be empty".into());
+    }
+    let mut rt = registry.0.lock().unwrap_or_else(|e| e.into_inner());
+    rt.skills.retain(|s| s.id != definition.id);
+    if rt.skills.len() >= MAX_SKILLS {
+        return Err(format!("too many registered skills (max {MAX_SKILLS})"));
+    }
+    rt.skills.push(definition);
+    Ok(())
+}
+
+/// IDs of the registered skills.
+#[tauri::command]
+pub fn skill_list(registry: State<'_, SkillRegistry>) -> Vec<String> {
+    let rt = registry.0.lock().unwrap_or_else(|e| e.into_inner());
+    rt.skills.iter().map(|s| s.id.clone()).collect()
+}
+
+/// Runs registered skill `skill_id` once on `input`.
+///
+/// Deterministic skills run locally; LLM skills call `provider` directly with
+/// `api_key` (or the key stored for the provider, see `provider_keys_set`)
+/// and `model` (the provider default when omitted). `system` replaces the
+/// default prompt, which asks for JSON matching the skill's output schema.
+/// The call's usage is attributed to chat session `session_id`, when given.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn skill_invoke(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    registry: State<'_, SkillRegistry>,
+    skill_id: String,
+    input: serde_json::Value,
+    provider: Option<String>,
+    model: Option<String>,
+    api_key: Option<String>,
+    system: Option<String>,
+    session_id: Option<String>,
+) -> Result<SkillInvokeResult, String> {
+    let skill = {
+        let rt = registry.0.lock().unwrap_or_else(|e| e.into_inner());
+        rt.skills
+            .iter()
+            .find(|s| s.id == skill_id)
+            .cloned()
+            .ok_or_else(|| format!("unknown skill '{skill_id}'"))?
+    };
+
+    let (provider, model) = if skill.is_deterministic() {
+        (None, Arc::from("local"))
+    } else {
+        let prov = provider.ok_or("an LLM skill needs a provider")?;
+        budget::ensure_within(&app)?;
+        let direct = DirectProvider::resolve(&app, &state, prov, model, api_key, session_id.clone()).await?;
+        let model: Arc<str> = Arc::from(direct.model().unwrap_or_default());
+        (Some(direct), model)
+    };
+    let prompt: Arc<str> = Arc::from(system.unwrap_or_else(|| default_system_prompt(&skill)));
+
+    let registry = registry.inner();
+    tokio::task::block_in_place(|| {
+        let mut rt = registry.0.lock().unwrap_or_else(|e| e.into_inner());
+        let no_provider = NoProvider;
+        let provider: &dyn ModelProvider = match &provider {
+            Some(p) => p,
+            None => &no_provider,
+        };
+        let result = rt
+            .executor
+            .execute(&skill, &input, ResponseMode::StrictJson, provider, &prompt, &model)
+            .map_err(redact::error)?;
+
+        let total_tokens = result.usage.total_tokens;
+        let cost = total_tokens as f64 / 1000.0 * model_cost_per_1k(&model);
+        rt.tracker.record(TokenBreakdown {
+            skill_id: skill.id.clone(),
+            model: model.to_string(),
+            prompt_tokens: result.usage.prompt_tokens,
+            context_tokens: 0,
+            memory_tokens: 0,
+            schema_tokens: 0,
+            response_tokens: result.usage.completion_tokens,
+            total_tokens,
+            cached_tokens: 0,
+            cache_savings: 0.0,
+            cost,
+            conversation_id: session_id,
+        });
+        Ok(SkillInvokeResult {
+            output: result.output,
+            cached: result.cached,
+            model: model.to_string(),
+            total_tokens,
+            cost,
+        })
+    })
+}
+
+/// Tokens and cost of all `skill_invoke` calls since startup, and of those
+/// launched from chat session `session_id` when given.
+#[tauri::command]
+pub fn skill_usage(registry: State<'_, SkillRegistry>, session_id: Option<String>) -> SkillUsage {
+    let rt = registry.0.lock().unwrap_or_else(|e| e.into_inner());
+    SkillUsage {
+        calls: rt.tracker.records().len(),
+        total_tokens: rt.tracker.total_tokens(),
+        total_cost: rt.tracker.total_cost(),
+        session: session_id.map(|id| rt.tracker.conversation_usage(&id).into()),
+    }
+}
+
+/// Provider for deterministic skills, which never reach it.
+struct NoProvider;
+
+impl ModelProvider for NoProvider {
+    fn call_model(&self, _request: LLMRequest) -> Result<ModelResponse, ProviderError> {
+        Err(ProviderError::CallFailed("deterministic skills don't call a model".into()))
+    }
+}
@@ -0,0 +1,218 @@
+//! Text-to-speech for reading responses aloud.
+//!
+//! `ai_speak` synthesizes with OpenAI (`/audio/speech`) or ElevenLabs using
+//! the caller's key or the stored provider key. By default the audio is
+//! saved as an MP3 under the app cache and its path returned, for an
+//! `<audio>` element or the OS player. With `stream` set, raw PCM is
+//! forwarded as it arrives instead — `ai:speech-chunk:{request_id}` events
+//! carrying base64 16-bit little-endian mono samples at [`PCM_SAMPLE_RATE`],
+//! then `ai:speech-done:{request_id}` — so playback can start before
+//! synthesis finishes.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::providers::OPENAI_API_BASE;
+use crate::stream::{next_bytes, StreamSink};
+use crate::{provider_keys, redact, shutdown, AppState};
+
+const ELEVENLABS_API_BASE: &str = "https://api.elevenlabs.io/v1";
+
+/// OpenAI's `input` limit; ElevenLabs accepts more but bills per character.
+pub const MAX_TEXT_CHARS: usize = 4096;
+/// Sample rate of streamed PCM, from both providers.
+pub const PCM_SAMPLE_RATE: u32 = 24_000;
+/// Largest audio kept in memory for a file result (25 MB).
+const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+/// Saved speech older than this is removed on the next call.
+const FILE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const OPENAI_DEFAULT_MODEL: &str = "gpt-4o-mini-tts";
+const OPENAI_DEFAULT_VOICE: &str = "alloy";
+const ELEVENLABS_DEFAULT_MODEL: &str = "eleven_multilingual_v2";
+/// "Rachel", one of ElevenLabs' premade voices.
+const ELEVENLABS_DEFAULT_VOICE: &str = "21m00Tcm4TlvDq8bNYZ";
+
+/// Result of [`ai_speak`].
+#[derive(Serialize)]
+pub struct Speech {
+    /// Saved MP3; `None` when streamed.
+    pub path: Option<String>,
+    /// ID of the `ai:speech-*` events when streamed.
+    pub request_id: Option<String>,
+    /// `mp3`, or `pcm_s16le` when streamed.
+    pub format: &'static str,
+    /// Sample rate of streamed PCM.
+    pub sample_rate: Option<u32>,
+}
+
+/// Payload of `ai:speech-chunk`.
+#[derive(Serialize, Clone)]
+struct SpeechChunk {
+    /// Base64 PCM; always a whole number of samples.
+    pcm: String,
+}
+
+fn request(
+    http: &reqwest::Client,
+    provider: &str,
+    key: &str,
+    text: &str,
+    voice: Option<&str>,
+    model: Option<&str>,
+    pcm: bool,
+) -> Result<reqwest::RequestBuilder, String> {
+    match provider {
+        "openai" => Ok(http
+            .post(format!("{OPENAI_API_BASE}/audio/speech"))
+            .bearer_auth(key)
+            .json(&serde_json::json!({
+                "model": model.unwrap_or(OPENAI_DEFAULT_MODEL),
+                "voice": voice.unwrap_or(OPENAI_DEFAULT_VOICE),
+                "input": text,
+                "response_format": if pcm { "pcm" } else { "mp3" },
+            }))),
+        "elevenlabs" => {
+            let voice = voice.unwrap_or(ELEVENLABS_DEFAULT_VOICE);
+            if !voice.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("invalid ElevenLabs voice ID '{voice}'"));
+            }
+            let format = if pcm { "pcm_24000" } else { "mp3_44100_128" };
+            Ok(http
+                .post(format!("{ELEVENLABS_API_BASE}/text-to-speech/{voice}/stream?output_format={format}"))
+                .header("xi-api-key", key)
+                .json(&serde_json::json!({
+                    "text": text,
+                    "model_id": model.unwrap_or(ELEVENLABS_DEFAULT_MODEL),
+                })))
+        }
+        other => Err(format!("text-to-speech isn't supported for provider '{other}' (use openai or elevenlabs)")),
+    }
+}
+
+/// Where saved speech goes; stale files are removed on the way.
+fn speech_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("cache directory unavailable: {e}"))?
+        .join("speech");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        let now = SystemTime::now();
+        for entry in entries.flatten() {
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|t| now.duration_since(t).unwrap_or_default() > FILE_TTL);
+            if stale {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    Ok(dir)
+}
+
+/// Forwards the PCM body as `speech-chunk` events, holding back a trailing
+/// odd byte so every chunk ends on a sample boundary.
+async fn pipe_pcm(sink: &StreamSink, resp: reqwest::Response) -> Result<(), String> {
+    let mut stream = resp.bytes_stream();
+    let mut carry: Option<u8> = None;
+    while let Some(bytes) = next_bytes(sink, &mut stream).await? {
+        let mut buf = Vec::with_capacity(bytes.len() + 1);
+        buf.extend(carry.take());
+        buf.extend_from_slice(&bytes);
+        if buf.len() % 2 == 1 {
+            carry = buf.pop();
+        }
+        if !buf.is_empty() {
+            sink.emit("speech-chunk", SpeechChunk { pcm: B64.encode(&buf) });
+        }
+    }
+    Ok(())
+}
+
+/// Reads `text` aloud with `provider` (`openai`, the default, or
+/// `elevenlabs`). `voice` is an OpenAI voice name or an ElevenLabs voice ID.
+/// Returns the path of an MP3, or — with `stream` — a `request_id` whose
+/// `ai:speech-chunk` events carry PCM as it is synthesized.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ai_speak(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    text: String,
+    voice: Option<String>,
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    stream: Option<bool>,
+    request_id: Option<String>,
+) -> Result<Speech, String> {
+    shutdown::ensure_running()?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("nothing to speak".into());
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        return Err(format!("text is too long to speak (max {MAX_TEXT_CHARS} characters)"));
+    }
+    let provider = provider.map(|p| p.trim().to_ascii_lowercase()).unwrap_or_else(|| "openai".into());
+    let key = api_key
+        .filter(|k| !k.trim().is_empty())
+        .or_else(|| provider_keys::resolve(&app, &provider, None))
+        .ok_or_else(|| format!("no {provider} API key — add one in Settings"))?;
+    let stream = stream.unwrap_or(false);
+
+    let resp = request(
+        &state.http_client,
+        &provider,
+        &key,
+        text,
+        voice.as_deref().map(str::trim).filter(|v| !v.is_empty()),
+        model.as_deref(),
+        stream,
+    )?
+    .send()
+    .await
+    .map_err(redact::error)?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(redact::redact(&format!("{provider} TTS error {status}: {body}")));
+    }
+
+    if stream {
+        let sink = StreamSink::new(&app, "ai", request_id);
+        pipe_pcm(&sink, resp).await?;
+        sink.emit("speech-done", ());
+        return Ok(Speech {
+            path: None,
+            request_id: Some(sink.request_id),
+            format: "pcm_s16le",
+            sample_rate: Some(PCM_SAMPLE_RATE),
+        });
+    }
+
+    if resp.content_length().is_some_and(|n| n > MAX_AUDIO_BYTES as u64) {
+        return Err("speech audio is too large".into());
+    }
+    let audio = resp.bytes().await.map_err(redact::error)?;
+    if audio.len() > MAX_AUDIO_BYTES {
+        return Err("speech audio is too large".into());
+    }
+    let path = speech_dir(&app)?.join(format!("{}.mp3", uuid::Uuid::new_v4()));
+    tokio::fs::write(&path, &audio)
+        .await
+        .map_err(|e| format!("can't save speech to {}: {e}", path.display()))?;
+    Ok(Speech {
+        path: Some(path.to_string_lossy().into_owned()),
+        request_id: None,
+        format: "mp3",
+        sample_rate: None,
+    })
+}
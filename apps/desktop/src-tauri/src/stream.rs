@@ -0,0 +1,366 @@
+//! Streaming to the frontend: request-scoped events and response bodies.
+//!
+//! A [`StreamSink`] carries the events of one streaming request; the pipes
+//! read a provider's or the gateway's response body into it — SSE `data:`
+//! lines ([`pipe_sse`], [`pipe_provider_sse`]) or Bedrock's binary event
+//! stream ([`pipe_bedrock_stream`]) — and return the full output.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::stream_usage::StreamUsage;
+use crate::{bedrock, partial_json, settings, shutdown_signal};
+
+// ── Stream events ──────────────────────────────────────────────────────────────
+
+/// Payload of a request-scoped stream event.
+#[derive(Serialize, Clone)]
+struct StreamEvent<'a> {
+    request_id: &'a str,
+    data: serde_json::Value,
+}
+
+/// Where a sink's events go: the app's event bus, or a recorder in tests.
+pub trait EventTarget: Send + Sync {
+    fn emit_event(&self, event: &str, payload: serde_json::Value);
+}
+
+impl<R: Runtime> EventTarget for AppHandle<R> {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+/// Routes the events of one streaming request.
+///
+/// Every event is emitted on a request-scoped channel
+/// (`<namespace>:<kind>:<request_id>`, e.g. `chat:stream-chunk:1b9d…`) with
+/// the ID in the payload, so concurrent streams never interleave.
+///
+/// When the caller gave no request ID, the bare payload is also mirrored on
+/// the unscoped channel (`chat:stream-chunk`) for listeners that predate
+/// request IDs. That path is deprecated: concurrent streams mix there.
+pub struct StreamSink {
+    target: Arc<dyn EventTarget>,
+    namespace: &'static str,
+    pub request_id: String,
+    /// Whether events are mirrored on the unscoped channels.
+    mirror: bool,
+    /// Stall timeout of the stream piped into this sink (see [`next_bytes`]).
+    idle_timeout: std::time::Duration,
+    /// Text emitted since the last [`take_received`](Self::take_received),
+    /// kept so a dropped stream can be resumed.
+    received: std::sync::Mutex<String>,
+    /// Usage reported by the streams piped into this sink, summed.
+    usage: std::sync::Mutex<Option<StreamUsage>>,
+    /// Keeps shutdown waiting while the stream runs.
+    _in_flight: shutdown_signal::InFlight,
+}
+
+impl StreamSink {
+    /// Uses the caller-supplied ID (so the UI can subscribe before invoking)
+    /// or generates a fresh one.
+    pub fn new(app: &AppHandle, namespace: &'static str, request_id: Option<String>) -> Self {
+        let idle_timeout = settings::load_stream_idle_timeout(app);
+        Self::with_target(Arc::new(app.clone()), namespace, request_id, idle_timeout)
+    }
+
+    /// Like [`new`](Self::new), emitting to `target` with a given stall timeout.
+    pub fn with_target(
+        target: Arc<dyn EventTarget>,
+        namespace: &'static str,
+        request_id: Option<String>,
+        idle_timeout: std::time::Duration,
+    ) -> Self {
+        let request_id = request_id.filter(|id| is_valid_request_id(id));
+        let mirror = request_id.is_none();
+        Self {
+            target,
+            namespace,
+            request_id: request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            mirror,
+            idle_timeout,
+            received: Default::default(),
+            usage: Default::default(),
+            _in_flight: shutdown_signal::InFlight::enter(),
+        }
+    }
+
+    pub fn take_received(&self) -> String {
+        std::mem::take(&mut self.received.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Adds one stream's usage and emits the running total as `stream-usage`.
+    pub fn add_usage(&self, usage: &StreamUsage) {
+        if usage.is_empty() {
+            return;
+        }
+        let mut total = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        total.get_or_insert_with(Default::default).merge(usage);
+        self.emit("stream-usage", total.clone());
+    }
+
+    pub fn usage(&self) -> Option<StreamUsage> {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn emit<T: Serialize>(&self, kind: &str, data: T) {
+        let Ok(data) = serde_json::to_value(data) else {
+            return;
+        };
+        let scoped = format!("{}:{}:{}", self.namespace, kind, self.request_id);
+        let event = StreamEvent { request_id: &self.request_id, data: data.clone() };
+        if let Ok(payload) = serde_json::to_value(event) {
+            self.target.emit_event(&scoped, payload);
+        }
+        if self.mirror {
+            self.target.emit_event(&format!("{}:{}", self.namespace, kind), data);
+        }
+    }
+
+    /// Ends the request: its result on `stream-done`, or the error on
+    /// `stream-failed`. For commands that return the request ID before the
+    /// stream runs.
+    pub fn finish<T: Serialize>(&self, result: Result<T, String>) {
+        match result {
+            Ok(done) => self.emit("stream-done", done),
+            Err(error) => self.emit("stream-failed", StreamFailed { error }),
+        }
+    }
+}
+
+/// Payload of `stream-failed`.
+#[derive(Serialize, Clone)]
+struct StreamFailed {
+    error: String,
+}
+
+/// Tauri event names only allow alphanumerics and `-`, `/`, `:`, `_`.
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// ── SSE pipe helpers ───────────────────────────────────────────────────────────
+
+/// Maximum total output length accepted from the SSE stream (4 MB).
+pub const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Maximum line buffer size — a single SSE chunk should never exceed this (64 KB).
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Error of a response body that broke off mid-stream.
+pub const STREAM_READ_ERROR: &str = "stream read error";
+
+/// Payload of `stream-error`.
+#[derive(Serialize, Clone)]
+struct StreamError {
+    /// `"stalled"`.
+    reason: &'static str,
+    error: String,
+    idle_timeout_secs: u64,
+}
+
+/// Waits for the next chunk of a response body. Fails once nothing at all —
+/// not even an SSE `:` keep-alive comment — arrived for `sink.idle_timeout`,
+/// so a hung provider doesn't hold the UI until the 120 s request timeout;
+/// the stall is also announced as a `stream-error` event on the sink, since
+/// the invoking command may be retried or fall back before it returns.
+pub async fn next_bytes<S, B>(sink: &StreamSink, stream: &mut S) -> Result<Option<B>, String>
+where
+    S: futures_util::Stream<Item = reqwest::Result<B>> + Unpin,
+{
+    let next = tokio::select! {
+        next = tokio::time::timeout(sink.idle_timeout, stream.next()) => next,
+        _ = shutdown_signal::cancelled() => return Err("stream cancelled: the app is shutting down".into()),
+    };
+    match next {
+        Ok(Some(item)) => item.map(Some).map_err(|_| STREAM_READ_ERROR.to_string()),
+        Ok(None) => Ok(None),
+        Err(_) => {
+            let idle_timeout_secs = sink.idle_timeout.as_secs();
+            let error = format!("stream stalled: no data received for {idle_timeout_secs} s");
+            sink.emit("stream-error", StreamError { reason: "stalled", error: error.clone(), idle_timeout_secs });
+            Err(error)
+        }
+    }
+}
+
+/// Value of an SSE `data:` field line (the single space after the colon is
+/// optional). Comment lines (`: keep-alive`) and other fields yield `None`.
+pub fn sse_data(line: &str) -> Option<&str> {
+    let value = line.strip_prefix("data:")?;
+    Some(value.strip_prefix(' ').unwrap_or(value))
+}
+
+/// Reads an SSE `text/event-stream` response line-by-line, emitting each
+/// `data:` value as a `stream-chunk` event. Returns the full concatenated output.
+/// Used for the cloud gateway path (raw passthrough of `data:` lines).
+pub async fn pipe_sse(sink: &StreamSink, resp: reqwest::Response) -> Result<String, String> {
+    let mut full = String::new();
+    let mut buf = String::new();
+    let mut stream = resp.bytes_stream();
+
+    while let Some(bytes) = next_bytes(sink, &mut stream).await? {
+
+        if buf.len() + bytes.len() > MAX_LINE_BYTES {
+            return Err("SSE line buffer exceeded maximum size".to_string());
+        }
+        buf.push_str(&String::from_utf8_lossy(&bytes));
+
+        while let Some(nl) = buf.find('\n') {
+            let line = buf[..nl].trim_end_matches('\r').to_owned();
+            buf = buf[nl + 1..].to_owned();
+
+            if let Some(data) = sse_data(&line) {
+                if data == "[DONE]" {
+                    return Ok(full);
+                }
+                if full.len() + data.len() > MAX_OUTPUT_BYTES {
+                    return Err("SSE output exceeded maximum allowed size".to_string());
+                }
+                full.push_str(data);
+                sink.emit("stream-chunk", data);
+            }
+        }
+    }
+
+    Ok(full)
+}
+
+/// Up to this size (256 KB) the partial object is rebuilt after every chunk;
+/// past it, once the output has grown by an eighth, so that rebuilding it
+/// stays linear in the size of the output.
+const PARTIAL_OBJECT_EVERY_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Object assembled so far from a structured-output stream.
+#[derive(Default)]
+struct PartialObject {
+    json: partial_json::PartialJson,
+    /// Last one emitted.
+    last: Option<serde_json::Value>,
+    /// Output length when it was last rebuilt.
+    built_at: usize,
+}
+
+/// Reads an SSE stream, applies `extract_fn` to each `data:` line, emits the
+/// extracted text chunk as a `stream-chunk` event, and returns the full
+/// concatenated output. Used for the direct provider path so only the text
+/// content is forwarded.
+///
+/// When the output is a JSON document (structured output), the best-effort
+/// object assembled so far is also emitted as a `partial-object` event
+/// (e.g. `chat:partial-object`) whenever it changes.
+///
+/// `reasoning_fn` extracts reasoning traces (DeepSeek-style
+/// `reasoning_content`), which are emitted as `reasoning-chunk` events and
+/// kept out of the returned output.
+pub async fn pipe_provider_sse<F>(
+    sink: &StreamSink,
+    resp: reqwest::Response,
+    extract_fn: F,
+    reasoning_fn: Option<fn(&str) -> Option<String>>,
+) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut full = String::new();
+    let mut buf = String::new();
+    let mut stream = resp.bytes_stream();
+
+    let mut partial = PartialObject::default();
+    let mut reasoning_len = 0usize;
+
+    while let Some(bytes) = next_bytes(sink, &mut stream).await? {
+
+        if buf.len() + bytes.len() > MAX_LINE_BYTES {
+            return Err("SSE line buffer exceeded maximum size".to_string());
+        }
+        buf.push_str(&String::from_utf8_lossy(&bytes));
+
+        while let Some(nl) = buf.find('\n') {
+            let line = buf[..nl].trim_end_matches('\r').to_owned();
+            buf = buf[nl + 1..].to_owned();
+
+            if let Some(data) = sse_data(&line) {
+                if data == "[DONE]" {
+                    return Ok(full);
+                }
+                if let Some(reasoning) = reasoning_fn.and_then(|f| f(data)) {
+                    reasoning_len += reasoning.len();
+                    if reasoning_len > MAX_OUTPUT_BYTES {
+                        return Err("SSE reasoning exceeded maximum allowed size".to_string());
+                    }
+                    sink.emit("reasoning-chunk", &reasoning);
+                }
+                if let Some(chunk) = extract_fn(data) {
+                    emit_chunk(sink, &mut full, &mut partial, &chunk)?;
+                }
+            }
+        }
+    }
+
+    Ok(full)
+}
+
+/// Appends one extracted text chunk to `full` and emits it, plus the updated
+/// `partial-object` when the output is a JSON document.
+fn emit_chunk(
+    sink: &StreamSink,
+    full: &mut String,
+    partial: &mut PartialObject,
+    chunk: &str,
+) -> Result<(), String> {
+    if full.len() + chunk.len() > MAX_OUTPUT_BYTES {
+        return Err("SSE output exceeded maximum allowed size".to_string());
+    }
+    full.push_str(chunk);
+    sink.emit("stream-chunk", chunk);
+    sink.received.lock().unwrap_or_else(|e| e.into_inner()).push_str(chunk);
+
+    partial.json.push(chunk);
+    if full.len() > PARTIAL_OBJECT_EVERY_CHUNK_BYTES && full.len() - partial.built_at < full.len() / 8 {
+        return Ok(());
+    }
+    partial.built_at = full.len();
+    if let Some(obj) = partial.json.value() {
+        if partial.last.as_ref() != Some(&obj) {
+            sink.emit("partial-object", &obj);
+            partial.last = Some(obj);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a Bedrock `invoke-with-response-stream` body (binary event-stream
+/// framing around Anthropic streaming events) and emits it like
+/// [`pipe_provider_sse`] does.
+pub async fn pipe_bedrock_stream<F>(
+    sink: &StreamSink,
+    resp: reqwest::Response,
+    extract: F,
+) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut full = String::new();
+    let mut partial = PartialObject::default();
+    let mut decoder = bedrock::EventStreamDecoder::new();
+    let mut stream = resp.bytes_stream();
+
+    while let Some(bytes) = next_bytes(sink, &mut stream).await? {
+        decoder.push(&bytes);
+
+        while let Some(message) = decoder.next_message()? {
+            if let Some(chunk) = message.chunk()?.as_deref().and_then(&extract) {
+                emit_chunk(sink, &mut full, &mut partial, &chunk)?;
+            }
+        }
+    }
+
+    Ok(full)
+}
@@ -0,0 +1,131 @@
+//! Token usage and finish reason of a streamed reply.
+//!
+//! Providers report these in-band, in their last events: OpenAI-compatible
+//! providers in a final chunk (with `stream_options.include_usage`),
+//! Anthropic — and Bedrock's Anthropic models — split across
+//! `message_start` and `message_delta`, Gemini as `usageMetadata` on each
+//! chunk, and Cohere in `message-end`. Each `push_*` folds one SSE `data:`
+//! value into a [`StreamUsage`].
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct StreamUsage {
+    /// Includes prompt tokens served from (or written to) a prompt cache.
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    /// The provider's own value (`stop`, `end_turn`, `length`, `MAX_TOKENS`, ...).
+    pub finish_reason: Option<String>,
+}
+
+fn int(v: &Value, pointer: &str) -> Option<i64> {
+    v.pointer(pointer).and_then(Value::as_i64)
+}
+
+fn text(v: &Value, pointer: &str) -> Option<String> {
+    v.pointer(pointer).and_then(Value::as_str).map(str::to_owned)
+}
+
+impl StreamUsage {
+    /// Whether the provider reported anything.
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens == 0 && self.finish_reason.is_none()
+    }
+
+    /// Adds a later attempt of the same reply (a resume or a retry): tokens
+    /// add up, the finish reason is the last one given.
+    pub fn merge(&mut self, other: &Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        if other.finish_reason.is_some() {
+            self.finish_reason.clone_from(&other.finish_reason);
+        }
+    }
+
+    fn set_tokens(&mut self, prompt: i64, completion: i64, total: Option<i64>) {
+        self.prompt_tokens = prompt;
+        self.completion_tokens = completion;
+        self.total_tokens = total.unwrap_or(prompt + completion);
+    }
+
+    pub fn push_openai(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<Value>(data) else { return };
+        if let Some(reason) = text(&val, "/choices/0/finish_reason") {
+            self.finish_reason = Some(reason);
+        }
+        if val.get("usage").is_some_and(Value::is_object) {
+            self.set_tokens(
+                int(&val, "/usage/prompt_tokens").unwrap_or(0),
+                int(&val, "/usage/completion_tokens").unwrap_or(0),
+                int(&val, "/usage/total_tokens"),
+            );
+            self.cache_read_tokens = int(&val, "/usage/prompt_tokens_details/cached_tokens").unwrap_or(0);
+        }
+    }
+
+    pub fn push_anthropic(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<Value>(data) else { return };
+        match val.get("type").and_then(Value::as_str) {
+            Some("message_start") => {
+                // Its `output_tokens` is a placeholder; `message_delta` has the count.
+                let (read, write) = (
+                    int(&val, "/message/usage/cache_read_input_tokens").unwrap_or(0),
+                    int(&val, "/message/usage/cache_creation_input_tokens").unwrap_or(0),
+                );
+                let prompt = int(&val, "/message/usage/input_tokens").unwrap_or(0) + read + write;
+                self.set_tokens(prompt, 0, None);
+                self.cache_read_tokens = read;
+                self.cache_write_tokens = write;
+            }
+            Some("message_delta") => {
+                if let Some(reason) = text(&val, "/delta/stop_reason") {
+                    self.finish_reason = Some(reason);
+                }
+                if let Some(output) = int(&val, "/usage/output_tokens") {
+                    self.set_tokens(self.prompt_tokens, output, None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn push_google(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<Value>(data) else { return };
+        if let Some(reason) = text(&val, "/candidates/0/finishReason") {
+            self.finish_reason = Some(reason);
+        }
+        if val.get("usageMetadata").is_some_and(Value::is_object) {
+            self.set_tokens(
+                int(&val, "/usageMetadata/promptTokenCount").unwrap_or(0),
+                int(&val, "/usageMetadata/candidatesTokenCount").unwrap_or(0),
+                int(&val, "/usageMetadata/totalTokenCount"),
+            );
+            self.cache_read_tokens = int(&val, "/usageMetadata/cachedContentTokenCount").unwrap_or(0);
+        }
+    }
+
+    pub fn push_cohere(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<Value>(data) else { return };
+        if val.get("type").and_then(Value::as_str) != Some("message-end") {
+            return;
+        }
+        self.finish_reason = text(&val, "/delta/finish_reason");
+        let tokens = ["/delta/usage/tokens", "/delta/usage/billed_units"]
+            .into_iter()
+            .find_map(|p| val.pointer(p).filter(|v| v.is_object()));
+        if let Some(tokens) = tokens {
+            self.set_tokens(
+                int(tokens, "/input_tokens").unwrap_or(0),
+                int(tokens, "/output_tokens").unwrap_or(0),
+                None,
+            );
+        }
+    }
+}
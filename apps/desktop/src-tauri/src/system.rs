@@ -0,0 +1,652 @@
+//! The machine an agent runs on: its processes and resources.
+//!
+//! `computer_system_info` reports the OS, CPU load, memory, disks, battery
+//! and uptime, so agents can decide things like not starting a long build on
+//! 5% battery without parsing shell output. `computer_lock_screen` and
+//! `computer_sleep` lock or suspend the machine, and `computer_keep_awake`
+//! keeps the display from sleeping during long agent runs until it is
+//! turned off again or the app exits.
+//!
+//! Unlike [`processes`](crate::processes), which only knows what this app
+//! spawned, `computer_system_process_list` lists every process the OS
+//! reports, so an agent can spot a runaway program (one it launched with
+//! `computer_launch_app`, say) and stop it with
+//! `computer_system_process_kill`. Killing a process this app didn't start
+//! waits for the user's [approval](crate::approval), and the app itself can't
+//! be killed this way.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use sysinfo::{Disks, Pid, ProcessRefreshKind, ProcessesToUpdate, Signal, System};
+use tauri::AppHandle;
+
+use crate::{approval, audit};
+
+/// Held while `computer_keep_awake(true)` is in effect.
+static KEEP_AWAKE: Mutex<Option<platform::KeepAwake>> = Mutex::new(None);
+
+/// Ancestors walked when checking whether a process descends from the app.
+const MAX_ANCESTRY: usize = 64;
+
+/// Result of `computer_system_info`.
+#[derive(Serialize)]
+pub struct SystemInfo {
+    /// e.g. `macOS 14.5 Sonoma`, `Windows 11 Pro`, `Linux 24.04 Ubuntu`.
+    pub os: String,
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub arch: String,
+    pub hostname: Option<String>,
+    pub uptime_secs: u64,
+    pub cpu: CpuInfo,
+    pub memory: MemoryInfo,
+    pub disks: Vec<DiskInfo>,
+    /// `None` on machines without a battery.
+    pub battery: Option<BatteryInfo>,
+}
+
+#[derive(Serialize)]
+pub struct CpuInfo {
+    pub cores: usize,
+    /// Overall usage in percent, 0–100.
+    pub usage: f32,
+    /// 1, 5 and 15 minute load averages; zero on Windows.
+    pub load_average: [f64; 3],
+}
+
+#[derive(Serialize)]
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct DiskInfo {
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub removable: bool,
+}
+
+#[derive(Serialize)]
+pub struct BatteryInfo {
+    /// Charge in percent, 0–100.
+    pub percent: u8,
+    /// On external power.
+    pub charging: bool,
+}
+
+/// Entry of `computer_system_process_list`.
+#[derive(Serialize)]
+pub struct SystemProcess {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    /// Percent of one core, so it can exceed 100 on multi-core machines.
+    pub cpu: f32,
+    /// Resident memory in bytes.
+    pub memory: u64,
+    /// Started by this app, directly or through one of its children.
+    pub spawned_by_app: bool,
+}
+
+fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing().with_cpu().with_memory()
+}
+
+/// Whether `pid` is a descendant of this app's process.
+fn descends_from_app(sys: &System, pid: Pid) -> bool {
+    let Ok(own) = sysinfo::get_current_pid() else {
+        return false;
+    };
+    let mut current = sys.process(pid).and_then(|p| p.parent());
+    for _ in 0..MAX_ANCESTRY {
+        match current {
+            Some(p) if p == own => return true,
+            Some(p) => current = sys.process(p).and_then(|p| p.parent()),
+            None => return false,
+        }
+    }
+    false
+}
+
+fn system_info() -> SystemInfo {
+    let mut sys = System::new();
+    sys.refresh_cpu_usage();
+    // Usage is the difference between two refreshes.
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu_usage();
+    sys.refresh_memory();
+
+    let load = System::load_average();
+    let disks = Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|d| DiskInfo {
+            mount_point: d.mount_point().display().to_string(),
+            total_bytes: d.total_space(),
+            available_bytes: d.available_space(),
+            removable: d.is_removable(),
+        })
+        .collect();
+    SystemInfo {
+        os: System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_owned()),
+        os_version: System::os_version(),
+        kernel_version: System::kernel_version(),
+        arch: std::env::consts::ARCH.to_owned(),
+        hostname: System::host_name(),
+        uptime_secs: System::uptime(),
+        cpu: CpuInfo {
+            cores: sys.cpus().len(),
+            usage: sys.global_cpu_usage(),
+            load_average: [load.one, load.five, load.fifteen],
+        },
+        memory: MemoryInfo {
+            total_bytes: sys.total_memory(),
+            available_bytes: sys.available_memory(),
+            swap_total_bytes: sys.total_swap(),
+            swap_used_bytes: sys.used_swap(),
+        },
+        disks,
+        battery: platform::battery(),
+    }
+}
+
+/// Lets the display sleep again if `computer_keep_awake` held it; called at
+/// shutdown.
+pub fn release_keep_awake() {
+    KEEP_AWAKE.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Reports the OS, CPU load, memory, disks, battery and uptime. CPU usage
+/// is sampled over a short interval, so the call takes about a quarter
+/// second.
+#[tauri::command]
+pub async fn computer_system_info() -> Result<SystemInfo, String> {
+    tokio::task::spawn_blocking(system_info)
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+}
+
+/// Lists the running processes, busiest first. CPU usage is measured over
+/// a short sampling interval, so the call takes about a quarter second.
+/// Requires `computer.shell` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_system_process_list() -> Result<Vec<SystemProcess>, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+        // CPU usage is the difference between two refreshes.
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+
+        let mut list: Vec<SystemProcess> = sys
+            .processes()
+            .iter()
+            .map(|(&pid, p)| SystemProcess {
+                pid: pid.as_u32(),
+                parent_pid: p.parent().map(|p| p.as_u32()),
+                name: p.name().to_string_lossy().into_owned(),
+                cpu: p.cpu_usage(),
+                memory: p.memory(),
+                spawned_by_app: descends_from_app(&sys, pid),
+            })
+            .collect();
+        list.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(b.memory.cmp(&a.memory)));
+        Ok(list)
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Stops the process `pid` — politely (SIGTERM) unless `force`, which kills
+/// it outright. Windows has no polite signal, so it is always forced there.
+/// Processes the app didn't start wait for the user's approval first.
+/// Requires `computer.shell` permission (enforced by `SandboxedComputer`).
+#[tauri::command]
+pub async fn computer_system_process_kill(
+    app: AppHandle,
+    pid: u32,
+    force: Option<bool>,
+    module: Option<String>,
+) -> Result<(), String> {
+    let args = serde_json::json!({ "pid": pid, "force": force });
+    let result: Result<(), String> = async move {
+        let target = Pid::from_u32(pid);
+        if sysinfo::get_current_pid().is_ok_and(|own| own == target) {
+            return Err("can't kill the app itself".into());
+        }
+        let (name, own_child) = tokio::task::spawn_blocking(move || {
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+            let name = sys.process(target).map(|p| p.name().to_string_lossy().into_owned());
+            (name, descends_from_app(&sys, target))
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))?;
+        let name = name.ok_or("no such process")?;
+        if !own_child {
+            approval::require(&app, approval::Action::process_kill(&name, pid)).await?;
+        }
+
+        let force = force.unwrap_or(false);
+        tokio::task::spawn_blocking(move || {
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[target]),
+                true,
+                ProcessRefreshKind::nothing(),
+            );
+            let process = sys.process(target).ok_or("the process has already exited")?;
+            let sent = if force {
+                process.kill()
+            } else {
+                process.kill_with(Signal::Term).unwrap_or_else(|| process.kill())
+            };
+            if sent {
+                Ok(())
+            } else {
+                Err(format!("could not kill {name} ({pid})"))
+            }
+        })
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r)
+    }
+    .await;
+    audit::record("computer_system_process_kill", args, module.as_deref(), &result);
+    result
+}
+
+/// Locks the screen.
+#[tauri::command]
+pub async fn computer_lock_screen(module: Option<String>) -> Result<(), String> {
+    let result: Result<(), String> = tokio::task::spawn_blocking(platform::lock_screen)
+        .await
+        .map_err(|e| format!("task panicked: {e}"))
+        .and_then(|r| r);
+    audit::record("computer_lock_screen", serde_json::json!({}), module.as_deref(), &result);
+    result
+}
+
+/// Puts the machine to sleep. Returns once the request is handed to the OS,
+/// which may still refuse (e.g. while another app inhibits sleep).
+#[tauri::command]
+pub async fn computer_sleep(module: Option<String>) -> Result<(), String> {
+    let result: Result<(), String> = tokio::task::spawn_blocking(|| {
+        release_keep_awake();
+        platform::sleep()
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r);
+    audit::record("computer_sleep", serde_json::json!({}), module.as_deref(), &result);
+    result
+}
+
+/// Keeps the display (and so the machine) from going to sleep while
+/// `enabled`, e.g. for the length of a long agent run. The hold ends with
+/// `computer_keep_awake(false)` or when the app exits.
+#[tauri::command]
+pub async fn computer_keep_awake(enabled: bool, module: Option<String>) -> Result<(), String> {
+    let args = serde_json::json!({ "enabled": enabled });
+    let result: Result<(), String> = tokio::task::spawn_blocking(move || {
+        let mut held = KEEP_AWAKE.lock().unwrap_or_else(|e| e.into_inner());
+        if !enabled {
+            held.take();
+        } else if held.is_none() {
+            *held = Some(platform::KeepAwake::acquire()?);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r);
+    audit::record("computer_keep_awake", args, module.as_deref(), &result);
+    result
+}
+
+// ── Platform queries ───────────────────────────────────────────────────────────
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::BatteryInfo;
+
+    fn run(program: &str, args: &[&str], what: &str) -> Result<(), String> {
+        match std::process::Command::new(program).args(args).status() {
+            Ok(s) if s.success() => Ok(()),
+            Ok(s) => Err(format!("{what} failed with exit code: {}", s.code().unwrap_or(-1))),
+            Err(e) => Err(format!("{what} unavailable ({program}): {e}")),
+        }
+    }
+
+    /// Asks logind to lock this session; the desktop's screen locker
+    /// does the rest.
+    pub fn lock_screen() -> Result<(), String> {
+        match std::env::var("XDG_SESSION_ID") {
+            Ok(id) => run("loginctl", &["lock-session", &id], "lock"),
+            Err(_) => run("loginctl", &["lock-session"], "lock"),
+        }
+    }
+
+    pub fn sleep() -> Result<(), String> {
+        run("systemctl", &["suspend"], "sleep")
+    }
+
+    /// A `systemd-inhibit` idle/sleep lock, held for as long as its child
+    /// process lives.
+    pub struct KeepAwake(std::process::Child);
+
+    impl KeepAwake {
+        pub fn acquire() -> Result<Self, String> {
+            std::process::Command::new("systemd-inhibit")
+                .args([
+                    "--what=idle:sleep",
+                    "--who=AgentHub",
+                    "--why=Agent run in progress",
+                    "--mode=block",
+                    "sleep",
+                    "infinity",
+                ])
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .map(Self)
+                .map_err(|e| format!("keep awake unavailable (systemd-inhibit): {e}"))
+        }
+    }
+
+    impl Drop for KeepAwake {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Reads the first battery under `/sys/class/power_supply`.
+    pub fn battery() -> Option<BatteryInfo> {
+        let supplies = std::fs::read_dir("/sys/class/power_supply").ok()?;
+        supplies.flatten().find_map(|entry| {
+            let dir = entry.path();
+            let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+            if read("type")?.trim() != "Battery" {
+                return None;
+            }
+            let percent: u8 = read("capacity")?.trim().parse().ok()?;
+            let status = read("status").unwrap_or_default();
+            Some(BatteryInfo {
+                percent: percent.min(100),
+                charging: matches!(status.trim(), "Charging" | "Full" | "Not charging"),
+            })
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::BatteryInfo;
+    use std::ffi::{c_char, c_void};
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+    const RTLD_LAZY: i32 = 0x1;
+    /// `kIOPMAssertionLevelOn`.
+    const ASSERTION_ON: u32 = 255;
+    const LOGIN_FRAMEWORK: &[u8] =
+        b"/System/Library/PrivateFrameworks/login.framework/Versions/Current/login\0";
+
+    extern "C" {
+        fn dlopen(path: *const c_char, mode: i32) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            kind: CFTypeRef,
+            level: u32,
+            name: CFTypeRef,
+            id: *mut u32,
+        ) -> i32;
+        fn IOPMAssertionRelease(id: u32) -> i32;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithBytes(
+            alloc: CFTypeRef,
+            bytes: *const u8,
+            len: isize,
+            encoding: u32,
+            external: u8,
+        ) -> CFTypeRef;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    fn pmset(arg: &str, what: &str) -> Result<(), String> {
+        match std::process::Command::new("pmset").arg(arg).status() {
+            Ok(s) if s.success() => Ok(()),
+            Ok(s) => Err(format!("{what} failed with exit code: {}", s.code().unwrap_or(-1))),
+            Err(e) => Err(format!("{what} error: {e}")),
+        }
+    }
+
+    /// Locks through `SACLockScreenImmediate`, what the Lock Screen menu
+    /// item uses. If the private framework is missing, sleeps the display
+    /// instead, which locks when a password is required after sleep.
+    pub fn lock_screen() -> Result<(), String> {
+        // SAFETY: the path and symbol name are NUL-terminated; the symbol,
+        // when present, takes no arguments.
+        unsafe {
+            let handle = dlopen(LOGIN_FRAMEWORK.as_ptr().cast(), RTLD_LAZY);
+            if !handle.is_null() {
+                let sym = dlsym(handle, b"SACLockScreenImmediate\0".as_ptr().cast());
+                if !sym.is_null() {
+                    let lock: extern "C" fn() -> i32 = std::mem::transmute(sym);
+                    if lock() == 0 {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        pmset("displaysleepnow", "lock")
+    }
+
+    pub fn sleep() -> Result<(), String> {
+        pmset("sleepnow", "sleep")
+    }
+
+    unsafe fn cf_string(s: &str) -> CFTypeRef {
+        CFStringCreateWithBytes(std::ptr::null(), s.as_ptr(), s.len() as isize, UTF8, 0)
+    }
+
+    /// A `PreventUserIdleDisplaySleep` power assertion.
+    pub struct KeepAwake(u32);
+
+    impl KeepAwake {
+        pub fn acquire() -> Result<Self, String> {
+            // SAFETY: both strings are created here and released after the
+            // call, which copies them.
+            unsafe {
+                let kind = cf_string("PreventUserIdleDisplaySleep");
+                let name = cf_string("AgentHub agent run in progress");
+                if kind.is_null() || name.is_null() {
+                    for s in [kind, name] {
+                        if !s.is_null() {
+                            CFRelease(s);
+                        }
+                    }
+                    return Err("keep awake failed: out of memory".into());
+                }
+                let mut id = 0u32;
+                let status = IOPMAssertionCreateWithName(kind, ASSERTION_ON, name, &mut id);
+                CFRelease(kind);
+                CFRelease(name);
+                if status != 0 {
+                    return Err(format!("keep awake failed: IOKit error {status:#x}"));
+                }
+                Ok(Self(id))
+            }
+        }
+    }
+
+    impl Drop for KeepAwake {
+        fn drop(&mut self) {
+            // SAFETY: releases the assertion created in `acquire`.
+            unsafe {
+                IOPMAssertionRelease(self.0);
+            }
+        }
+    }
+
+    /// Parses `pmset -g batt`, e.g.
+    /// `Now drawing from 'Battery Power'` /
+    /// ` -InternalBattery-0 (id=…)	76%; discharging; 4:10 remaining`.
+    pub fn battery() -> Option<BatteryInfo> {
+        let out = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        let text = String::from_utf8_lossy(&out.stdout);
+        let line = text.lines().find(|l| l.contains("InternalBattery"))?;
+        let percent = line
+            .split(|c: char| c.is_whitespace() || c == ';')
+            .find_map(|w| w.strip_suffix('%')?.parse::<u8>().ok())?;
+        Some(BatteryInfo {
+            percent: percent.min(100),
+            charging: text.contains("'AC Power'"),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::BatteryInfo;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    /// `BatteryFlag` bit for "no system battery".
+    const NO_BATTERY: u8 = 128;
+    const UNKNOWN: u8 = 255;
+
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    const ES_DISPLAY_REQUIRED: u32 = 0x0000_0002;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn LockWorkStation() -> i32;
+    }
+
+    #[link(name = "powrprof")]
+    extern "system" {
+        fn SetSuspendState(hibernate: u8, force: u8, wakeup_events_disabled: u8) -> u8;
+    }
+
+    pub fn lock_screen() -> Result<(), String> {
+        // SAFETY: no arguments.
+        if unsafe { LockWorkStation() } == 0 {
+            return Err(format!("lock failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    pub fn sleep() -> Result<(), String> {
+        // SAFETY: plain flags; sleeps rather than hibernates, without
+        // forcing apps and with wake events left enabled.
+        if unsafe { SetSuspendState(0, 0, 0) } == 0 {
+            return Err(format!("sleep failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Execution state applies per thread, so a dedicated thread sets it
+    /// and clears it again once the sender is dropped.
+    pub struct KeepAwake(#[allow(dead_code)] std::sync::mpsc::Sender<()>);
+
+    impl KeepAwake {
+        pub fn acquire() -> Result<Self, String> {
+            let (tx, rx) = std::sync::mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let flags = ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED;
+                // SAFETY: plain flags for the current thread.
+                let ok = unsafe { SetThreadExecutionState(flags) } != 0;
+                let _ = ready_tx.send(ok);
+                if ok {
+                    // Returns once the sender is dropped.
+                    let _ = rx.recv();
+                    // SAFETY: as above.
+                    unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+                }
+            });
+            match ready_rx.recv() {
+                Ok(true) => Ok(Self(tx)),
+                _ => Err("keep awake failed".into()),
+            }
+        }
+    }
+
+    pub fn battery() -> Option<BatteryInfo> {
+        let mut status = SystemPowerStatus::default();
+        // SAFETY: fills the struct we pass in.
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+        if status.battery_flag & NO_BATTERY != 0
+            || status.battery_flag == UNKNOWN
+            || status.battery_life_percent == UNKNOWN
+        {
+            return None;
+        }
+        Some(BatteryInfo {
+            percent: status.battery_life_percent.min(100),
+            charging: status.ac_line_status == 1,
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::BatteryInfo;
+
+    pub fn battery() -> Option<BatteryInfo> {
+        None
+    }
+
+    pub fn lock_screen() -> Result<(), String> {
+        Err("locking the screen is not supported on this platform".into())
+    }
+
+    pub fn sleep() -> Result<(), String> {
+        Err("sleep is not supported on this platform".into())
+    }
+
+    pub struct KeepAwake;
+
+    impl KeepAwake {
+        pub fn acquire() -> Result<Self, String> {
+            Err("keep awake is not supported on this platform".into())
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Cloud-gateway tenants.
+//!
+//! Besides the default gateway (`CLOUD_GATEWAY_URL`, with failover — see
+//! [`gateway`](crate::gateway)), users can sign in to additional gateway
+//! accounts — a company tenant next to a personal one — and pick one per
+//! conversation. Each tenant has its own access token in the
+//! credential store and reports its own usage; gateway commands take an
+//! optional `tenant` ID and fall back to the default tenant.
+//!
+//! The tenant list lives in `settings.json`; the default tenant is implicit
+//! and keeps the original token key, so existing logins carry over.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::{settings, AppState};
+
+/// ID of the built-in tenant backed by `CLOUD_GATEWAY_URL`.
+pub const DEFAULT_TENANT: &str = "default";
+
+const TENANTS_KEY: &str = "gateway_tenants";
+const MAX_TENANTS: usize = 16;
+
+/// A gateway account.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    pub gateway_url: String,
+}
+
+/// A tenant plus whether a token is stored for it.
+#[derive(Serialize)]
+pub struct TenantInfo {
+    #[serde(flatten)]
+    pub tenant: Tenant,
+    pub authenticated: bool,
+}
+
+fn default_tenant(state: &AppState) -> Tenant {
+    Tenant {
+        id: DEFAULT_TENANT.into(),
+        name: "Default".into(),
+        gateway_url: state.gateways.active(),
+    }
+}
+
+fn load_stored(app: &AppHandle) -> Vec<Tenant> {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(TENANTS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save_stored(app: &AppHandle, tenants: &[Tenant]) -> Result<(), String> {
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(tenants).map_err(|e| e.to_string())?;
+    store.set(TENANTS_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))
+}
+
+/// All tenants, default first.
+pub fn list(app: &AppHandle, state: &AppState) -> Vec<Tenant> {
+    std::iter::once(default_tenant(state)).chain(load_stored(app)).collect()
+}
+
+/// Looks up `id` (the default tenant when `None`).
+pub fn resolve(app: &AppHandle, state: &AppState, id: Option<&str>) -> Result<Tenant, String> {
+    match id.filter(|id| *id != DEFAULT_TENANT) {
+        None => Ok(default_tenant(state)),
+        Some(id) => load_stored(app)
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("unknown tenant '{id}'")),
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Lists the gateway tenants, default first.
+#[tauri::command]
+pub async fn tenants_list(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<TenantInfo>, String> {
+    Ok(list(&app, &state)
+        .into_iter()
+        .map(|tenant| TenantInfo {
+            authenticated: crate::load_token(&app, &tenant.id).is_some(),
+            tenant,
+        })
+        .collect())
+}
+
+/// Adds a gateway tenant; sign in to it with `auth_login` and its ID.
+#[tauri::command]
+pub async fn tenants_add(app: AppHandle, name: String, gateway_url: String) -> Result<Tenant, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("tenant name must not be empty".into());
+    }
+    let gateway_url = settings::normalize_base_url(&gateway_url)?;
+
+    let mut tenants = load_stored(&app);
+    if tenants.len() >= MAX_TENANTS {
+        return Err(format!("too many tenants (max {MAX_TENANTS})"));
+    }
+    if tenants.iter().any(|t| t.name.eq_ignore_ascii_case(name)) {
+        return Err(format!("a tenant named '{name}' already exists"));
+    }
+    let tenant = Tenant {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_owned(),
+        gateway_url,
+    };
+    tenants.push(tenant.clone());
+    save_stored(&app, &tenants)?;
+    Ok(tenant)
+}
+
+/// Removes a tenant and its stored token. The default tenant can't be removed.
+#[tauri::command]
+pub async fn tenants_remove(app: AppHandle, id: String) -> Result<(), String> {
+    if id == DEFAULT_TENANT {
+        return Err("the default tenant can't be removed".into());
+    }
+    let mut tenants = load_stored(&app);
+    let before = tenants.len();
+    tenants.retain(|t| t.id != id);
+    if tenants.len() == before {
+        return Err(format!("unknown tenant '{id}'"));
+    }
+    save_stored(&app, &tenants)?;
+    crate::delete_token(&app, &id);
+    Ok(())
+}
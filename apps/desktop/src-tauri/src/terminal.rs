@@ -0,0 +1,493 @@
+//! Interactive terminal sessions backed by a real PTY.
+//!
+//! Unlike `computer_run_shell`, which buffers a one-shot command until it
+//! exits, a terminal session keeps a shell (or any interactive program — ssh,
+//! REPLs, installers) alive behind a pseudo-terminal so agents can drive it
+//! keystroke by keystroke and the UI can render it in a terminal panel.
+//!
+//! # Events
+//! - `terminal:output:{id}` — UTF-8 output chunk (`String`) as it arrives.
+//! - `terminal:exit:{id}` — `{ exit_code }` once the program terminates.
+//!
+//! Output is also buffered (bounded) so pollers can use `terminal_read`
+//! instead of listening to events.
+//!
+//! # Policy
+//! The program a session runs passes the shell policy and approval when it
+//! is opened, and so does every line submitted to it afterwards: input is
+//! followed keystroke by keystroke, and before a newline is forwarded the
+//! line it submits is checked like a `computer_run_shell` command. A line
+//! refused there is cleared with Ctrl-U instead of being submitted. Lines
+//! edited with escape sequences (cursor keys, history) or completion can't
+//! be followed, so they are refused too; Ctrl-C or Ctrl-U starts a fresh
+//! line. Opening a session and each submitted line are recorded in the
+//! audit log (see [`audit`]) under the caller's `module`.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{approval, audit, redact, shell_policy};
+
+/// Maximum unread output retained per session for `terminal_read` (1 MB).
+/// Older output is discarded first; event listeners still receive everything.
+const MAX_BUFFERED_BYTES: usize = 1024 * 1024;
+
+/// Maximum number of concurrently open sessions.
+const MAX_SESSIONS: usize = 16;
+
+// ── State ──────────────────────────────────────────────────────────────────────
+
+struct TerminalSession {
+    master: Box<dyn MasterPty + Send>,
+    /// Shared so writes can block without holding the session registry.
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    child: Arc<Mutex<Box<dyn Child + Send + Sync>>>,
+    /// Output received since the last `terminal_read`.
+    pending: Arc<Mutex<String>>,
+    /// Held across a whole write, approval included, so writes don't
+    /// interleave.
+    line: Arc<tokio::sync::Mutex<TypedLine>>,
+}
+
+/// The line being typed into a session, as far as it can be followed.
+#[derive(Default)]
+struct TypedLine {
+    text: String,
+    /// Edited in ways that can't be followed.
+    opaque: bool,
+}
+
+impl TypedLine {
+    fn push(&mut self, input: &str) {
+        for c in input.chars() {
+            match c {
+                '\u{7f}' | '\u{8}' => {
+                    self.text.pop();
+                }
+                // Ctrl-C and Ctrl-U discard the line.
+                '\u{3}' | '\u{15}' => *self = Self::default(),
+                c if c.is_control() => self.opaque = true,
+                c => self.text.push(c),
+            }
+        }
+    }
+
+    /// Takes the submitted line; `None` when it is blank.
+    fn submit(&mut self) -> Option<Self> {
+        let line = std::mem::take(self);
+        (line.opaque || !line.text.trim().is_empty()).then_some(line)
+    }
+
+    /// The line as a command, or why it can't be checked.
+    fn command(&self) -> Result<&str, String> {
+        if self.opaque {
+            return Err("can't check a line edited with cursor keys, history or completion — \
+                        clear it with Ctrl-U and type it out in full"
+                .into());
+        }
+        Ok(self.text.trim())
+    }
+}
+
+/// Open terminal sessions keyed by session ID, managed via `tauri::Builder::manage`.
+#[derive(Default)]
+pub struct TerminalSessions {
+    sessions: Mutex<HashMap<String, TerminalSession>>,
+}
+
+impl TerminalSessions {
+    /// Kills every session's program and releases the sessions; returns how
+    /// many were open.
+    pub fn close_all(&self) -> usize {
+        let sessions: Vec<TerminalSession> = match self.sessions.lock() {
+            Ok(mut map) => map.drain().map(|(_, s)| s).collect(),
+            Err(_) => return 0,
+        };
+        for session in &sessions {
+            if let Ok(mut child) = session.child.lock() {
+                if matches!(child.try_wait(), Ok(None)) {
+                    let _ = child.kill();
+                }
+            }
+        }
+        sessions.len()
+    }
+}
+
+// ── Response types ─────────────────────────────────────────────────────────────
+
+/// Handle returned when a session is opened.
+#[derive(Serialize)]
+pub struct TerminalInfo {
+    pub id: String,
+    pub pid: Option<u32>,
+}
+
+/// Output drained by `terminal_read`.
+#[derive(Serialize)]
+pub struct TerminalOutput {
+    pub output: String,
+    /// `true` once the program has exited.
+    pub exited: bool,
+    pub exit_code: Option<u32>,
+}
+
+#[derive(Serialize, Clone)]
+struct TerminalExit {
+    /// `None` when the status could not be collected (e.g. killed on close).
+    exit_code: Option<u32>,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+fn pty_size(cols: Option<u16>, rows: Option<u16>) -> PtySize {
+    PtySize {
+        rows: rows.unwrap_or(24).max(1),
+        cols: cols.unwrap_or(80).max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Decodes as much of `bytes` as forms complete UTF-8, leaving a trailing
+/// partial code point in `bytes` for the next read.
+pub fn drain_utf8(bytes: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        // Genuinely invalid bytes — decode lossily rather than stalling.
+        Err(_) => bytes.len(),
+    };
+    let text = String::from_utf8_lossy(&bytes[..valid]).into_owned();
+    bytes.drain(..valid);
+    text
+}
+
+fn push_bounded(buf: &mut String, chunk: &str) {
+    buf.push_str(chunk);
+    if buf.len() > MAX_BUFFERED_BYTES {
+        let mut cut = buf.len() - MAX_BUFFERED_BYTES;
+        while !buf.is_char_boundary(cut) {
+            cut += 1;
+        }
+        buf.drain(..cut);
+    }
+}
+
+/// The command line a session runs, for the shell policy and the approval
+/// prompt; the login shell when no program is given.
+fn command_line(command: Option<&str>, args: &[String]) -> String {
+    let program = match command {
+        Some(program) => program.to_owned(),
+        None => std::env::var(if cfg!(windows) { "COMSPEC" } else { "SHELL" })
+            .unwrap_or_else(|_| if cfg!(windows) { "cmd.exe" } else { "sh" }.into()),
+    };
+    std::iter::once(program)
+        .chain(args.iter().map(|a| {
+            if a.is_empty() || a.contains(char::is_whitespace) {
+                format!("\"{a}\"")
+            } else {
+                a.clone()
+            }
+        }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Polls for the exit status after the PTY reached EOF. The program normally
+/// exits right after closing its terminal, so this gives up after ~2 seconds.
+fn wait_for_exit(child: &Mutex<Box<dyn Child + Send + Sync>>) -> Option<u32> {
+    for _ in 0..40 {
+        if let Ok(Some(status)) = child.lock().ok()?.try_wait() {
+            return Some(status.exit_code());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    None
+}
+
+/// Pumps PTY output into events and the pending buffer until EOF.
+fn spawn_reader(
+    app: AppHandle,
+    id: String,
+    mut reader: Box<dyn Read + Send>,
+    pending: Arc<Mutex<String>>,
+    child: Arc<Mutex<Box<dyn Child + Send + Sync>>>,
+) {
+    std::thread::spawn(move || {
+        let mut raw = [0u8; 8192];
+        let mut carry: Vec<u8> = Vec::new();
+        loop {
+            match reader.read(&mut raw) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    carry.extend_from_slice(&raw[..n]);
+                    let text = drain_utf8(&mut carry);
+                    if text.is_empty() {
+                        continue;
+                    }
+                    if let Ok(mut p) = pending.lock() {
+                        push_bounded(&mut p, &text);
+                    }
+                    let _ = app.emit(&format!("terminal:output:{id}"), text);
+                }
+            }
+        }
+        let exit_code = wait_for_exit(&child);
+        let _ = app.emit(&format!("terminal:exit:{id}"), TerminalExit { exit_code });
+    });
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Opens a PTY session running `command` (default: the user's login shell).
+///
+/// `cols` / `rows` default to 80×24. Output streams as `terminal:output:{id}`
+/// events. Requires `computer.shell` permission (enforced by `SandboxedComputer`),
+/// and the command line must pass the shell policy and be approved, as for
+/// `computer_run_shell`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn terminal_open(
+    app: AppHandle,
+    terminals: State<'_, TerminalSessions>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    cwd: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    module: Option<String>,
+) -> Result<TerminalInfo, String> {
+    let args = args.unwrap_or_default();
+    let line = command_line(command.as_deref(), &args);
+    let summary = serde_json::json!({ "command": line, "cwd": cwd });
+    let result = open_session(app, &terminals, command, args, &line, cwd, pty_size(cols, rows)).await;
+    audit::record("terminal_open", summary, module.as_deref(), &result);
+    result
+}
+
+async fn open_session(
+    app: AppHandle,
+    terminals: &TerminalSessions,
+    command: Option<String>,
+    args: Vec<String>,
+    line: &str,
+    cwd: Option<String>,
+    size: PtySize,
+) -> Result<TerminalInfo, String> {
+    if terminals.sessions.lock().map_err(|_| "terminal registry poisoned")?.len() >= MAX_SESSIONS {
+        return Err(format!("too many open terminal sessions (max {MAX_SESSIONS})"));
+    }
+    shell_policy::check(&app, line)?;
+    approval::require(&app, approval::Action::shell(line)).await?;
+
+    let pair = native_pty_system()
+        .openpty(size)
+        .map_err(|e| format!("pty open failed: {e}"))?;
+
+    let mut cmd = match command.as_deref() {
+        Some(program) => {
+            let mut c = CommandBuilder::new(program);
+            c.args(args);
+            c
+        }
+        None => CommandBuilder::new_default_prog(),
+    };
+    if let Some(dir) = cwd {
+        cmd.cwd(dir);
+    }
+
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| redact::error(format!("terminal spawn failed: {e}")))?;
+    // The slave end belongs to the child now; keeping it open would prevent EOF.
+    drop(pair.slave);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("pty reader failed: {e}"))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("pty writer failed: {e}"))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let pid = child.process_id();
+    let child = Arc::new(Mutex::new(child));
+    let pending = Arc::new(Mutex::new(String::new()));
+    let line = Arc::default();
+
+    spawn_reader(app, id.clone(), reader, Arc::clone(&pending), Arc::clone(&child));
+
+    terminals
+        .sessions
+        .lock()
+        .map_err(|_| "terminal registry poisoned")?
+        .insert(
+            id.clone(),
+            TerminalSession { master: pair.master, writer: Arc::new(Mutex::new(writer)), child, pending, line },
+        );
+
+    Ok(TerminalInfo { id, pid })
+}
+
+/// Writes raw input (keystrokes, pasted text, control sequences such as
+/// `"\u0003"` for Ctrl-C) to the session. Each line the input submits must
+/// pass the shell policy and be approved first (see the module docs); a
+/// refused line fails the write after the input before it was sent.
+/// Submitted lines are audited; other keystrokes aren't.
+#[tauri::command]
+pub async fn terminal_write(
+    app: AppHandle,
+    terminals: State<'_, TerminalSessions>,
+    id: String,
+    data: String,
+    module: Option<String>,
+) -> Result<(), String> {
+    let (writer, line) = {
+        let sessions = terminals.sessions.lock().map_err(|_| "terminal registry poisoned")?;
+        let session = sessions.get(&id).ok_or("unknown terminal session")?;
+        (Arc::clone(&session.writer), Arc::clone(&session.line))
+    };
+    let mut line = line.lock().await;
+    let mut rest = data.as_str();
+    while let Some(end) = rest.find(['\r', '\n']) {
+        let (typed, newline) = rest.split_at(end);
+        line.push(typed);
+        write_input(&writer, typed).await?;
+        if let Some(submitted) = line.submit() {
+            let checked = match submitted.command() {
+                Ok(command) => check_line(&app, command).await,
+                Err(e) => Err(e),
+            };
+            let summary = serde_json::json!({ "id": id, "line": submitted.text });
+            audit::record("terminal_write", summary, module.as_deref(), &checked);
+            if let Err(e) = checked {
+                write_input(&writer, "\u{15}").await?;
+                return Err(e);
+            }
+        }
+        write_input(&writer, &newline[..1]).await?;
+        rest = &newline[1..];
+    }
+    line.push(rest);
+    write_input(&writer, rest).await
+}
+
+/// Checks a line submitted to a terminal session like a shell command.
+async fn check_line(app: &AppHandle, line: &str) -> Result<(), String> {
+    shell_policy::check(app, line)?;
+    approval::require(app, approval::Action::shell(line)).await
+}
+
+async fn write_input(writer: &Arc<Mutex<Box<dyn Write + Send>>>, input: &str) -> Result<(), String> {
+    if input.is_empty() {
+        return Ok(());
+    }
+    let (writer, input) = (Arc::clone(writer), input.to_owned());
+    // A program that stops reading its input blocks the write; only this
+    // session's writer is held meanwhile.
+    tokio::task::spawn_blocking(move || {
+        let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer
+            .write_all(input.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("terminal write failed: {e}"))
+    })
+    .await
+    .map_err(|e| format!("task panicked: {e}"))
+    .and_then(|r| r)
+}
+
+/// Drains and returns output received since the previous read.
+#[tauri::command]
+pub async fn terminal_read(
+    terminals: State<'_, TerminalSessions>,
+    id: String,
+) -> Result<TerminalOutput, String> {
+    let mut sessions = terminals.sessions.lock().map_err(|_| "terminal registry poisoned")?;
+    let session = sessions.get_mut(&id).ok_or("unknown terminal session")?;
+
+    let exit_code = match session.child.lock().map(|mut c| c.try_wait()) {
+        Ok(Ok(Some(status))) => Some(status.exit_code()),
+        _ => None,
+    };
+
+    let output = session
+        .pending
+        .lock()
+        .map(|mut p| std::mem::take(&mut *p))
+        .unwrap_or_default();
+    Ok(TerminalOutput { output, exited: exit_code.is_some(), exit_code })
+}
+
+/// Resizes the PTY; the program receives `SIGWINCH` on unix.
+#[tauri::command]
+pub async fn terminal_resize(
+    terminals: State<'_, TerminalSessions>,
+    id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    let sessions = terminals.sessions.lock().map_err(|_| "terminal registry poisoned")?;
+    let session = sessions.get(&id).ok_or("unknown terminal session")?;
+    session
+        .master
+        .resize(pty_size(Some(cols), Some(rows)))
+        .map_err(|e| format!("terminal resize failed: {e}"))
+}
+
+/// Kills the program (if still running) and releases the session.
+#[tauri::command]
+pub async fn terminal_close(
+    terminals: State<'_, TerminalSessions>,
+    id: String,
+) -> Result<(), String> {
+    let session = terminals
+        .sessions
+        .lock()
+        .map_err(|_| "terminal registry poisoned")?
+        .remove(&id)
+        .ok_or("unknown terminal session")?;
+    let mut child = session.child.lock().map_err(|_| "terminal child poisoned")?;
+    if matches!(child.try_wait(), Ok(None)) {
+        child.kill().map_err(|e| format!("terminal kill failed: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submitted(input: &str) -> Result<Option<String>, String> {
+        let mut line = TypedLine::default();
+        line.push(input);
+        line.submit().map(|l| l.command().map(str::to_owned)).transpose()
+    }
+
+    #[test]
+    fn follows_typing_and_erasing() {
+        assert_eq!(submitted("ls -la").unwrap().as_deref(), Some("ls -la"));
+        let erased = format!("rm -rf ~{}ls", "\u{7f}".repeat(8));
+        assert_eq!(submitted(&erased).unwrap().as_deref(), Some("ls"));
+        assert_eq!(submitted("rm -rf ~\u{15}pwd").unwrap().as_deref(), Some("pwd"));
+        assert_eq!(submitted("  ").unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_lines_it_cannot_follow() {
+        // Up arrow recalls history; Tab completes.
+        assert!(submitted("\u{1b}[A").is_err());
+        assert!(submitted("rm -rf /ho\t").is_err());
+        // A fresh line after Ctrl-C is followed again.
+        assert_eq!(submitted("\u{1b}[A\u{3}echo hi").unwrap().as_deref(), Some("echo hi"));
+    }
+}
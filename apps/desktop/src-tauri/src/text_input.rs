@@ -0,0 +1,211 @@
+//! Keyboard-layout and input-method awareness for typed text.
+//!
+//! Key names like `"z"` or `"^"` are characters, but the keys that produce
+//! them depend on the layout: on many European layouts `^` and `` ` `` are
+//! dead keys that wait for the next key, and some characters aren't on the
+//! layout at all. [`layout_key`] says how the current layout produces a
+//! character so callers can complete dead keys or fall back to Unicode
+//! input.
+//!
+//! Text typed key by key also goes through an active input method (IME) —
+//! Chinese, Japanese or Korean composition turns it into something else, and
+//! those scripts can't be typed key by key anyway. [`TypeMethod::Auto`]
+//! pastes such text through the clipboard instead, restoring what was there.
+
+use enigo::Key;
+
+/// How `computer_key_type` enters text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeMethod {
+    /// Keystrokes, unless the text or the active input method needs a paste.
+    Auto,
+    /// Always keystrokes.
+    Keys,
+    /// Always a clipboard paste.
+    Clipboard,
+}
+
+impl TypeMethod {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "keys" => Ok(Self::Keys),
+            "clipboard" => Ok(Self::Clipboard),
+            other => Err(format!("unknown input method '{other}' (use auto, keys or clipboard)")),
+        }
+    }
+
+    /// Whether `text` should be pasted rather than typed.
+    pub fn pastes(self, text: &str) -> bool {
+        match self {
+            Self::Keys => false,
+            Self::Clipboard => true,
+            Self::Auto => text.chars().any(needs_ime) || platform::ime_active(),
+        }
+    }
+}
+
+/// Characters that are normally entered through an input method, or that
+/// combine with the one before — typing them key by key goes wrong.
+fn needs_ime(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F            // combining diacritical marks
+        | 0x0E00..=0x0E7F          // Thai
+        | 0x0900..=0x0DFF          // Indic scripts
+        | 0x1100..=0x11FF          // Hangul Jamo
+        | 0x2E80..=0x9FFF          // CJK radicals, kana, CJK ideographs
+        | 0xA960..=0xA97F          // Hangul Jamo extended
+        | 0xAC00..=0xD7FF          // Hangul syllables
+        | 0xF900..=0xFAFF          // CJK compatibility ideographs
+        | 0xFF00..=0xFFEF          // half- and full-width forms
+        | 0x1_0000..               // emoji and other astral characters
+    )
+}
+
+/// How the current keyboard layout produces a character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub enum LayoutKey {
+    /// A key (with modifiers as needed) types it.
+    Direct(Key),
+    /// A dead key: it types the character only when followed by a space.
+    Dead(Key),
+    /// Not on the layout; only Unicode input or a paste can enter it.
+    Missing,
+}
+
+/// Looks `c` up on the layout of the focused window. Platforms where enigo
+/// already maps characters through the layout report every character as
+/// [`LayoutKey::Direct`].
+pub fn layout_key(c: char) -> LayoutKey {
+    platform::layout_key(c)
+}
+
+// ── Platform queries ───────────────────────────────────────────────────────────
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::LayoutKey;
+    use enigo::Key;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        GetKeyboardLayout, MapVirtualKeyExW, VkKeyScanExW, MAPVK_VK_TO_CHAR,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    /// Chinese, Japanese and Korean primary language ids; their layouts are
+    /// input methods.
+    const IME_LANGUAGES: [u16; 3] = [0x04, 0x11, 0x12];
+
+    /// Layout of the focused window's thread — each thread has its own.
+    fn foreground_layout() -> isize {
+        // SAFETY: plain queries; a null window yields thread 0, which
+        // `GetKeyboardLayout` reads as the current thread.
+        unsafe {
+            let thread = GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+            GetKeyboardLayout(thread) as isize
+        }
+    }
+
+    pub fn ime_active() -> bool {
+        let language = (foreground_layout() & 0xFFFF) as u16;
+        IME_LANGUAGES.contains(&(language & 0x3FF))
+    }
+
+    pub fn layout_key(c: char) -> LayoutKey {
+        let mut units = [0u16; 2];
+        if c.encode_utf16(&mut units).len() != 1 {
+            return LayoutKey::Missing;
+        }
+        let layout = foreground_layout();
+        // SAFETY: plain lookups on a layout handle from the system.
+        unsafe {
+            let scan = VkKeyScanExW(units[0], layout as _);
+            if scan == -1 {
+                return LayoutKey::Missing;
+            }
+            let vk = (scan & 0xFF) as u32;
+            // The high bit of the mapped character marks a dead key.
+            if MapVirtualKeyExW(vk, MAPVK_VK_TO_CHAR, layout as _) & 0x8000_0000 != 0 {
+                LayoutKey::Dead(Key::Unicode(c))
+            } else {
+                LayoutKey::Direct(Key::Unicode(c))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::LayoutKey;
+    use enigo::Key;
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        static kTISPropertyInputSourceType: CFTypeRef;
+        static kTISTypeKeyboardLayout: CFTypeRef;
+        fn TISCopyCurrentKeyboardInputSource() -> CFTypeRef;
+        fn TISGetInputSourceProperty(source: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFEqual(a: CFTypeRef, b: CFTypeRef) -> u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    /// An input source that isn't a plain keyboard layout is an input
+    /// method (Pinyin, Kotoeri, 2-Set Korean, …).
+    pub fn ime_active() -> bool {
+        // SAFETY: the copied source is released below; the property is
+        // borrowed from it.
+        unsafe {
+            let source = TISCopyCurrentKeyboardInputSource();
+            if source.is_null() {
+                return false;
+            }
+            let kind = TISGetInputSourceProperty(source, kTISPropertyInputSourceType);
+            let ime = !kind.is_null() && CFEqual(kind, kTISTypeKeyboardLayout) == 0;
+            CFRelease(source);
+            ime
+        }
+    }
+
+    /// enigo posts characters as Unicode strings here, independent of the
+    /// layout.
+    pub fn layout_key(c: char) -> LayoutKey {
+        LayoutKey::Direct(Key::Unicode(c))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::LayoutKey;
+    use enigo::Key;
+
+    /// Asks IBus or Fcitx 5, whichever is running, whether an input method
+    /// (rather than a plain XKB layout) is composing.
+    pub fn ime_active() -> bool {
+        let output = |program: &str, args: &[&str]| {
+            std::process::Command::new(program)
+                .args(args)
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
+        };
+        if let Some(engine) = output("ibus", &["engine"]) {
+            return !engine.is_empty() && !engine.starts_with("xkb:");
+        }
+        // `fcitx5-remote` prints 2 while an input method is active.
+        output("fcitx5-remote", &[]).is_some_and(|state| state == "2")
+    }
+
+    /// enigo remaps a spare keycode to each character's keysym here, so
+    /// the layout doesn't matter.
+    pub fn layout_key(c: char) -> LayoutKey {
+        LayoutKey::Direct(Key::Unicode(c))
+    }
+}
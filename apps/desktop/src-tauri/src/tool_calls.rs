@@ -0,0 +1,349 @@
+//! Function / tool calling on the direct (BYOK) chat path.
+//!
+//! `chat_send` accepts `tools` (name, description, JSON-Schema parameters).
+//! While the reply streams, tool-call deltas — OpenAI-style `tool_calls`
+//! fragments per index, Anthropic `tool_use` blocks with `input_json_delta`
+//! fragments — are assembled here, and each finished call is emitted as a
+//! `chat:tool-call` event. The turn is then parked under its request ID until
+//! the frontend runs the tools and submits their results with
+//! `chat_submit_tool_results`, which continues the conversation on the same
+//! request ID (possibly with further tool calls).
+//!
+//! Supported for OpenAI-compatible providers and Anthropic.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::vision::Image;
+use crate::providers::{ChatMessage, GenerationParams};
+
+/// Parked turns are dropped after this long without results.
+const PENDING_TTL: Duration = Duration::from_secs(15 * 60);
+const MAX_PENDING: usize = 64;
+/// Tool-call rounds per request; stops runaway tool loops.
+pub const MAX_ROUNDS: usize = 16;
+const MAX_TOOLS: usize = 128;
+
+/// A tool the model may call.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ToolSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// JSON Schema of the arguments object.
+    #[serde(default = "empty_object_schema")]
+    pub parameters: serde_json::Value,
+}
+
+fn empty_object_schema() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+pub fn validate_tools(tools: &[ToolSpec]) -> Result<(), String> {
+    if tools.len() > MAX_TOOLS {
+        return Err(format!("too many tools (max {MAX_TOOLS})"));
+    }
+    for (i, t) in tools.iter().enumerate() {
+        let valid = !t.name.is_empty()
+            && t.name.len() <= 64
+            && t.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(format!("tools[{i}].name must be 1–64 characters of a-z, 0-9, _ or -"));
+        }
+        if !t.parameters.is_object() {
+            return Err(format!("tools[{i}].parameters must be a JSON Schema object"));
+        }
+    }
+    Ok(())
+}
+
+/// A call the model made, as emitted on `chat:tool-call`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// Arguments as the JSON text the model produced.
+    pub arguments: String,
+}
+
+impl ToolCall {
+    /// Arguments as a JSON object; `{}` when the model produced invalid JSON.
+    pub fn arguments_json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.arguments)
+            .ok()
+            .filter(|v: &serde_json::Value| v.is_object())
+            .unwrap_or_else(|| serde_json::json!({}))
+    }
+}
+
+/// Output of one tool call, submitted by the frontend.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ToolResult {
+    pub tool_call_id: String,
+    pub content: String,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+/// One assistant turn that ended in tool calls, and the results sent back.
+#[derive(Clone)]
+pub struct ToolRound {
+    /// Text the model streamed before calling the tools.
+    pub text: String,
+    pub calls: Vec<ToolCall>,
+    pub results: Vec<ToolResult>,
+}
+
+// ── Delta accumulation ─────────────────────────────────────────────────────────
+
+/// Assembles tool calls from streamed deltas, keyed by the provider's index.
+#[derive(Default)]
+pub struct ToolCallAccumulator {
+    calls: BTreeMap<u64, ToolCall>,
+}
+
+impl ToolCallAccumulator {
+    /// Reads the `choices[0].delta.tool_calls` fragments of one OpenAI-style
+    /// SSE `data:` line. The first fragment of a call carries its ID and name;
+    /// later ones append to the arguments.
+    pub fn push_openai(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<serde_json::Value>(data) else {
+            return;
+        };
+        let Some(deltas) = val.pointer("/choices/0/delta/tool_calls").and_then(|v| v.as_array()) else {
+            return;
+        };
+        // Some servers send each call whole, without an index.
+        let next = self.calls.len() as u64;
+        for (pos, delta) in deltas.iter().enumerate() {
+            let index = delta
+                .get("index")
+                .and_then(|i| i.as_u64())
+                .unwrap_or(next + pos as u64);
+            let call = self.entry(index);
+            if let Some(id) = delta.get("id").and_then(|v| v.as_str()) {
+                call.id = id.to_owned();
+            }
+            if let Some(name) = delta.pointer("/function/name").and_then(|v| v.as_str()) {
+                call.name.push_str(name);
+            }
+            if let Some(args) = delta.pointer("/function/arguments").and_then(|v| v.as_str()) {
+                call.arguments.push_str(args);
+            }
+        }
+    }
+
+    /// Reads one Anthropic SSE `data:` line: `content_block_start` of a
+    /// `tool_use` block opens a call, `input_json_delta`s fill its input.
+    pub fn push_anthropic(&mut self, data: &str) {
+        let Ok(val) = serde_json::from_str::<serde_json::Value>(data) else {
+            return;
+        };
+        let Some(index) = val.get("index").and_then(|i| i.as_u64()) else {
+            return;
+        };
+        match val.get("type").and_then(|t| t.as_str()) {
+            Some("content_block_start") => {
+                let block = &val["content_block"];
+                if block.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
+                    return;
+                }
+                let call = self.entry(index);
+                call.id = block.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_owned();
+                call.name = block.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_owned();
+            }
+            Some("content_block_delta") => {
+                if let (Some(call), Some(json)) = (
+                    self.calls.get_mut(&index),
+                    val.pointer("/delta/partial_json").and_then(|v| v.as_str()),
+                ) {
+                    call.arguments.push_str(json);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn entry(&mut self, index: u64) -> &mut ToolCall {
+        self.calls.entry(index).or_insert_with(|| ToolCall {
+            id: String::new(),
+            name: String::new(),
+            arguments: String::new(),
+        })
+    }
+
+    /// The assembled calls in index order. Calls without a name are dropped;
+    /// missing IDs are generated and empty arguments become `{}`.
+    pub fn finish(self) -> Vec<ToolCall> {
+        self.calls
+            .into_values()
+            .filter(|c| !c.name.is_empty())
+            .map(|mut c| {
+                if c.id.is_empty() {
+                    c.id = format!("call_{}", uuid::Uuid::new_v4().simple());
+                }
+                if c.arguments.trim().is_empty() {
+                    c.arguments = "{}".into();
+                }
+                c
+            })
+            .collect()
+    }
+}
+
+// ── Request bodies ─────────────────────────────────────────────────────────────
+
+pub fn openai_tools(tools: &[ToolSpec]) -> serde_json::Value {
+    tools
+        .iter()
+        .map(|t| {
+            let mut function = serde_json::json!({ "name": t.name, "parameters": t.parameters });
+            if let Some(d) = &t.description {
+                function["description"] = d.as_str().into();
+            }
+            serde_json::json!({ "type": "function", "function": function })
+        })
+        .collect()
+}
+
+pub fn anthropic_tools(tools: &[ToolSpec]) -> serde_json::Value {
+    tools
+        .iter()
+        .map(|t| {
+            let mut tool = serde_json::json!({ "name": t.name, "input_schema": t.parameters });
+            if let Some(d) = &t.description {
+                tool["description"] = d.as_str().into();
+            }
+            tool
+        })
+        .collect()
+}
+
+/// OpenAI messages for the tool rounds: the assistant's `tool_calls`, then
+/// one `tool` message per result.
+pub fn openai_round_messages(rounds: &[ToolRound]) -> Vec<serde_json::Value> {
+    let mut messages = Vec::new();
+    for round in rounds {
+        let calls: Vec<serde_json::Value> = round
+            .calls
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "id": c.id,
+                    "type": "function",
+                    "function": { "name": c.name, "arguments": c.arguments },
+                })
+            })
+            .collect();
+        let content = if round.text.is_empty() { serde_json::Value::Null } else { round.text.as_str().into() };
+        messages.push(serde_json::json!({ "role": "assistant", "content": content, "tool_calls": calls }));
+        for r in &round.results {
+            messages.push(serde_json::json!({ "role": "tool", "tool_call_id": r.tool_call_id, "content": r.content }));
+        }
+    }
+    messages
+}
+
+/// Anthropic messages for the tool rounds: an assistant turn with
+/// `tool_use` blocks, then a user turn with the `tool_result` blocks.
+pub fn anthropic_round_messages(rounds: &[ToolRound]) -> Vec<serde_json::Value> {
+    let mut messages = Vec::new();
+    for round in rounds {
+        let mut content = Vec::new();
+        if !round.text.is_empty() {
+            content.push(serde_json::json!({ "type": "text", "text": round.text }));
+        }
+        for c in &round.calls {
+            content.push(serde_json::json!({
+                "type": "tool_use", "id": c.id, "name": c.name, "input": c.arguments_json(),
+            }));
+        }
+        messages.push(serde_json::json!({ "role": "assistant", "content": content }));
+        let results: Vec<serde_json::Value> = round
+            .results
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "type": "tool_result", "tool_use_id": r.tool_call_id,
+                    "content": r.content, "is_error": r.is_error,
+                })
+            })
+            .collect();
+        messages.push(serde_json::json!({ "role": "user", "content": results }));
+    }
+    messages
+}
+
+// ── Parked turns ───────────────────────────────────────────────────────────────
+
+/// A chat turn waiting for tool results.
+pub struct PendingToolTurn {
+    pub provider: String,
+    pub api_key: String,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    pub message: String,
+    pub images: Vec<Image>,
+    pub history: Vec<ChatMessage>,
+    pub system: Option<String>,
+    pub params: GenerationParams,
+    pub tools: Vec<ToolSpec>,
+    pub rounds: Vec<ToolRound>,
+    /// Chat session the turn belongs to, for the usage ledger.
+    pub session_id: Option<String>,
+    parked_at: Instant,
+}
+
+impl PendingToolTurn {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        provider: String,
+        api_key: String,
+        model: Option<String>,
+        base_url: Option<String>,
+        message: String,
+        images: Vec<Image>,
+        history: Vec<ChatMessage>,
+        system: Option<String>,
+        params: GenerationParams,
+        tools: Vec<ToolSpec>,
+    ) -> Self {
+        Self {
+            provider, api_key, model, base_url, message, images, history, system, params, tools,
+            rounds: Vec::new(),
+            session_id: None,
+            parked_at: Instant::now(),
+        }
+    }
+}
+
+/// Turns waiting for `chat_submit_tool_results`, keyed by request ID.
+#[derive(Default)]
+pub struct PendingToolTurns(Mutex<HashMap<String, PendingToolTurn>>);
+
+impl PendingToolTurns {
+    pub fn park(&self, request_id: &str, mut turn: PendingToolTurn) -> Result<(), String> {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        map.retain(|_, t| t.parked_at.elapsed() < PENDING_TTL);
+        if map.len() >= MAX_PENDING && !map.contains_key(request_id) {
+            return Err(format!("too many chats waiting for tool results (max {MAX_PENDING})"));
+        }
+        turn.parked_at = Instant::now();
+        map.insert(request_id.to_owned(), turn);
+        Ok(())
+    }
+
+    /// Drops every parked turn and returns their request IDs.
+    pub fn drain(&self) -> Vec<String> {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        map.drain().map(|(id, _)| id).collect()
+    }
+
+    pub fn take(&self, request_id: &str) -> Option<PendingToolTurn> {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        map.remove(request_id).filter(|t| t.parked_at.elapsed() < PENDING_TTL)
+    }
+}
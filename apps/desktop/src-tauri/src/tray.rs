@@ -0,0 +1,171 @@
+//! System tray icon with quick actions.
+//!
+//! The menu shows the gateway's health (kept current by the
+//! [`gateway`](crate::gateway) monitor) and offers:
+//!
+//! - **New chat** — brings up the main window and emits `tray:new-chat` to it.
+//! - **Take screenshot & ask** — starts the capture-and-ask flow.
+//! - **Pause agents** — a toggle; while on, computer-use input is refused and
+//!   `agents:paused` tells the frontend to hold its agent loops. Also
+//!   available as `agents_set_paused`.
+//! - **Open AgentHub** and **Quit**, which goes through the orderly shutdown.
+//!
+//! Clicking the icon itself shows the main window.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::gateway::GatewayStatus;
+use crate::{capture_ask, computer, session};
+
+const TRAY_ID: &str = "main";
+const MAIN_WINDOW: &str = "main";
+
+/// Payload of `agents:paused`.
+#[derive(Serialize, Clone)]
+pub struct AgentsPaused {
+    pub paused: bool,
+}
+
+struct Items {
+    health: MenuItem<Wry>,
+    pause: CheckMenuItem<Wry>,
+}
+
+/// Menu items updated after the tray is built.
+#[derive(Default)]
+pub struct Tray(Mutex<Option<Items>>);
+
+impl Tray {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Items>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Builds the tray icon. Call once at startup.
+pub fn start(app: &AppHandle) -> tauri::Result<()> {
+    let health = MenuItem::with_id(app, "health", "Gateway: checking…", false, None::<&str>)?;
+    let new_chat = MenuItem::with_id(app, "new-chat", "New chat", true, None::<&str>)?;
+    let capture = MenuItem::with_id(app, "capture", "Take screenshot & ask", true, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(
+        app,
+        "pause-agents",
+        "Pause agents",
+        true,
+        session::agents_paused(),
+        None::<&str>,
+    )?;
+    let open = MenuItem::with_id(app, "open", "Open AgentHub", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[
+        &health,
+        &PredefinedMenuItem::separator(app)?,
+        &new_chat,
+        &capture,
+        &pause,
+        &PredefinedMenuItem::separator(app)?,
+        &open,
+        &quit,
+    ])?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("AgentHub")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    *app.state::<Tray>().lock() = Some(Items { health, pause });
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "new-chat" => {
+            show_main(app);
+            let _ = app.emit_to(MAIN_WINDOW, "tray:new-chat", ());
+        }
+        "capture" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = capture_ask::capture_and_ask(app.clone()).await {
+                    let _ = app.emit("capture:error", e);
+                }
+            });
+        }
+        // The check mark has already toggled itself.
+        "pause-agents" => set_paused(app, !session::agents_paused()),
+        "open" => show_main(app),
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(main) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = main.unminimize();
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+}
+
+/// Pauses or resumes agents and updates the menu toggle.
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    let changed = session::set_agents_paused(paused);
+    if let Some(items) = app.state::<Tray>().lock().as_ref() {
+        let _ = items.pause.set_checked(paused);
+    }
+    if changed {
+        if paused {
+            tauri::async_runtime::spawn_blocking(computer::release_held_input);
+        }
+        let _ = app.emit("agents:paused", AgentsPaused { paused });
+    }
+}
+
+/// Shows the active gateway's last probe in the menu.
+pub fn update_health(app: &AppHandle, status: &GatewayStatus) {
+    let healthy = status
+        .gateways
+        .iter()
+        .find(|g| g.url == status.active)
+        .and_then(|g| g.healthy);
+    let text = match healthy {
+        Some(true) => "Gateway: online",
+        Some(false) => "Gateway: unreachable",
+        None => "Gateway: checking…",
+    };
+    if let Some(items) = app.state::<Tray>().lock().as_ref() {
+        let _ = items.health.set_text(text);
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Pauses or resumes agents, as the tray's "Pause agents" toggle does.
+#[tauri::command]
+pub fn agents_set_paused(app: AppHandle, paused: bool) {
+    set_paused(&app, paused);
+}
+
+#[tauri::command]
+pub fn agents_get_paused() -> AgentsPaused {
+    AgentsPaused { paused: session::agents_paused() }
+}
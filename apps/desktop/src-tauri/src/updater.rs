@@ -0,0 +1,223 @@
+//! App updates.
+//!
+//! `update_check` asks the default tenant's gateway for the newest release
+//! on the configured channel (`stable` or `beta`, see
+//! `settings_set_update_channel`); `update_download` fetches it, reporting
+//! progress on `update:progress`, and `update_install` verifies its
+//! signature against the `pubkey` in `tauri.conf.json`, installs it and
+//! restarts the app.
+//!
+//! Updates fail closed: until a release build sets `pubkey` (and turns on
+//! `createUpdaterArtifacts` to sign the packages), every update command
+//! fails with "updates are not configured" instead of reaching the feed.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State, Url};
+use tauri_plugin_store::StoreExt;
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::{settings, shutdown, AppState};
+
+const UPDATE_CHANNEL_KEY: &str = "update_channel";
+/// Progress events are emitted at most every this many bytes.
+const PROGRESS_STEP: u64 = 256 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+        }
+    }
+}
+
+/// Result of `update_check`.
+#[derive(Serialize, Clone)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// Release date, when the feed has one.
+    pub date: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Payload of `update:progress`.
+#[derive(Serialize, Clone)]
+struct UpdateProgress {
+    downloaded: u64,
+    /// `None` when the server doesn't send a length.
+    total: Option<u64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Found by the last `update_check`.
+    update: Option<Update>,
+    /// Downloaded package of `update`.
+    package: Option<Vec<u8>>,
+}
+
+/// The update found and downloaded so far.
+#[derive(Default)]
+pub struct Updates(Mutex<Inner>);
+
+impl Updates {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Forgets the found update, e.g. after a channel switch.
+    pub fn clear(&self) {
+        *self.lock() = Inner::default();
+    }
+}
+
+pub fn load_channel(app: &AppHandle) -> UpdateChannel {
+    app.store(settings::SETTINGS_STORE)
+        .ok()
+        .and_then(|s| s.get(UPDATE_CHANNEL_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Fails unless `tauri.conf.json` has the public key updates are signed with.
+fn ensure_configured(app: &AppHandle) -> Result<(), String> {
+    let pubkey = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|u| u.get("pubkey"))
+        .and_then(|k| k.as_str())
+        .unwrap_or_default();
+    if pubkey.trim().is_empty() {
+        return Err("updates are not configured".into());
+    }
+    Ok(())
+}
+
+/// Release feed of `channel`; the updater fills in the placeholders.
+fn endpoint(gateway: &str, channel: UpdateChannel) -> Result<Url, String> {
+    let url = format!("{gateway}/v1/updates/{}/", channel.as_str())
+        + "{{target}}/{{arch}}/{{current_version}}";
+    url.parse().map_err(|e| format!("invalid update URL: {e}"))
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Checks the configured channel for a newer version. Returns `null` when
+/// the app is up to date; fails when updates are not configured.
+#[tauri::command]
+pub async fn update_check(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    updates: State<'_, Updates>,
+) -> Result<Option<UpdateInfo>, String> {
+    shutdown::ensure_running()?;
+    ensure_configured(&app)?;
+    let channel = load_channel(&app);
+    let update = app
+        .updater_builder()
+        .endpoints(vec![endpoint(&state.gateways.active(), channel)?])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| format!("updater unavailable: {e}"))?
+        .check()
+        .await
+        .map_err(|e| format!("update check failed: {e}"))?;
+
+    let info = update.as_ref().map(|u| UpdateInfo {
+        version: u.version.clone(),
+        current_version: u.current_version.clone(),
+        channel,
+        date: u.date.map(|d| d.to_string()),
+        notes: u.body.clone(),
+    });
+    let mut inner = updates.lock();
+    inner.update = update;
+    inner.package = None;
+    Ok(info)
+}
+
+/// Downloads the update found by `update_check`, emitting `update:progress`
+/// as it goes. Fails when there is none, or the app shuts down meanwhile.
+#[tauri::command]
+pub async fn update_download(app: AppHandle, updates: State<'_, Updates>) -> Result<(), String> {
+    shutdown::ensure_running()?;
+    ensure_configured(&app)?;
+    let update = updates.lock().update.clone().ok_or("no update available — run update_check first")?;
+
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+    let handle = app.clone();
+    let download = update.download(
+        move |chunk, total| {
+            downloaded += chunk as u64;
+            if downloaded - reported >= PROGRESS_STEP || total == Some(downloaded) {
+                reported = downloaded;
+                let _ = handle.emit("update:progress", UpdateProgress { downloaded, total });
+            }
+        },
+        || {},
+    );
+    let package = tokio::select! {
+        package = download => package.map_err(|e| format!("update download failed: {e}"))?,
+        _ = shutdown::cancelled() => return Err("the app is shutting down".into()),
+    };
+
+    let mut inner = updates.lock();
+    // A newer check may have replaced the update meanwhile.
+    if inner.update.as_ref().is_some_and(|u| u.version == update.version) {
+        inner.package = Some(package);
+    }
+    Ok(())
+}
+
+/// Installs the downloaded update and restarts into it.
+#[tauri::command]
+pub async fn update_install(app: AppHandle, updates: State<'_, Updates>) -> Result<(), String> {
+    shutdown::ensure_running()?;
+    ensure_configured(&app)?;
+    let (update, package) = {
+        let mut inner = updates.lock();
+        let package = inner.package.take().ok_or("no update downloaded — run update_download first")?;
+        (inner.update.clone().ok_or("no update available")?, package)
+    };
+    update
+        .install(package)
+        .map_err(|e| format!("update install failed: {e}"))?;
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn settings_get_update_channel(app: AppHandle) -> UpdateChannel {
+    load_channel(&app)
+}
+
+/// Switches the release channel; the next `update_check` uses it.
+#[tauri::command]
+pub async fn settings_set_update_channel(
+    app: AppHandle,
+    updates: State<'_, Updates>,
+    channel: UpdateChannel,
+) -> Result<(), String> {
+    let store = app
+        .store(settings::SETTINGS_STORE)
+        .map_err(|e| format!("settings store unavailable: {e}"))?;
+    let value = serde_json::to_value(channel).map_err(|e| e.to_string())?;
+    store.set(UPDATE_CHANNEL_KEY, value);
+    store.save().map_err(|e| format!("failed to save settings: {e}"))?;
+    updates.clear();
+    Ok(())
+}
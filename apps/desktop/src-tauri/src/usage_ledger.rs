@@ -0,0 +1,419 @@
+//! Local ledger of direct (BYOK) provider usage.
+//!
+//! Calls made with the user's own keys never reach the gateway, so its
+//! `usage_get` can't see them. Every direct call is recorded here instead —
+//! provider, model, prompt and completion tokens, estimated cost — in the
+//! app's `memory.db`, and [`usage_get_local`] sums it per day or week.
+//! Costs use the runtime's price table (`model_pricing`) with its
+//! prompt-cache discount, so they are estimates, not invoices.
+//!
+//! Calls made for a chat session — its messages, and the skills and agents
+//! launched from it — carry the session's ID, and [`usage_get_session`]
+//! sums them per session.
+
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use agenthub_runtime::provider::{cached_token_discount, model_pricing, ModelPricing};
+use chrono::Datelike;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// File name of the app's SQLite database, under the app data directory.
+pub const DB_FILE: &str = "memory.db";
+
+/// Buckets returned when the caller doesn't say how many.
+const DEFAULT_DAYS: u32 = 30;
+const DEFAULT_WEEKS: u32 = 12;
+const MAX_BUCKETS: u32 = 366;
+
+static LEDGER: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+/// One direct provider call.
+pub struct UsageEntry<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    /// Includes `cached_tokens`.
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Prompt tokens served from a prompt cache.
+    pub cached_tokens: i64,
+    /// Chat session the call was made for.
+    pub session_id: Option<&'a str>,
+}
+
+impl UsageEntry<'_> {
+    /// Estimated cost in USD.
+    pub fn cost(&self) -> f64 {
+        let price = pricing(self.provider, self.model);
+        let tokens = |n: i64| u64::try_from(n).unwrap_or(0);
+        let savings = self.cached_tokens as f64 / 1000.0 * price.input_per_1k * cached_token_discount(self.model);
+        (price.cost(tokens(self.prompt_tokens), tokens(self.completion_tokens)) - savings).max(0.0)
+    }
+}
+
+/// Price of `model` on `provider`; models served by a local server are free.
+pub fn pricing(provider: &str, model: &str) -> ModelPricing {
+    if provider == "local-openai" {
+        return ModelPricing { input_per_1k: 0.0, output_per_1k: 0.0 };
+    }
+    model_pricing(model)
+}
+
+/// Opens (creating if needed) the ledger in `dir`. Until it is open, calls
+/// aren't recorded.
+pub fn open(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    let conn = Connection::open(dir.join(DB_FILE)).map_err(|e| format!("can't open usage ledger: {e}"))?;
+    // The sessions table and the audit log write to the same file.
+    conn.busy_timeout(std::time::Duration::from_secs(2))
+        .map_err(|e| format!("can't open usage ledger: {e}"))?;
+    init(&conn)?;
+    let _ = LEDGER.set(Mutex::new(conn));
+    Ok(())
+}
+
+/// Creates the ledger table in `conn`, upgrading an older one.
+pub fn init(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS usage_ledger (
+             id                INTEGER PRIMARY KEY,
+             ts                INTEGER NOT NULL,
+             provider          TEXT    NOT NULL,
+             model             TEXT    NOT NULL,
+             prompt_tokens     INTEGER NOT NULL,
+             completion_tokens INTEGER NOT NULL,
+             cached_tokens     INTEGER NOT NULL,
+             cost              REAL    NOT NULL,
+             session_id        TEXT
+         );
+         CREATE INDEX IF NOT EXISTS usage_ledger_ts ON usage_ledger (ts);",
+    )
+    .map_err(|e| format!("can't create usage ledger: {e}"))?;
+    // Ledgers from before sessions were tracked lack the column.
+    let has_session = conn
+        .prepare("SELECT 1 FROM pragma_table_info('usage_ledger') WHERE name = 'session_id'")
+        .and_then(|mut stmt| stmt.exists([]))
+        .map_err(|e| format!("can't read usage ledger: {e}"))?;
+    if !has_session {
+        conn.execute_batch("ALTER TABLE usage_ledger ADD COLUMN session_id TEXT;")
+            .map_err(|e| format!("can't upgrade usage ledger: {e}"))?;
+    }
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS usage_ledger_session ON usage_ledger (session_id);")
+        .map_err(|e| format!("can't create usage ledger: {e}"))
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let conn = LEDGER.get().ok_or("usage ledger is not open")?;
+    f(&conn.lock().unwrap_or_else(|e| e.into_inner())).map_err(|e| format!("usage ledger error: {e}"))
+}
+
+/// Records a call. Calls that reported no tokens are skipped, and a failed
+/// write never fails the call it describes; it is logged to stderr, since
+/// the spend caps then undercount.
+pub fn record(entry: &UsageEntry) {
+    if entry.prompt_tokens + entry.completion_tokens == 0 {
+        return;
+    }
+    let Some(conn) = LEDGER.get() else {
+        return;
+    };
+    let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = insert(&conn, entry, chrono::Utc::now().timestamp()) {
+        eprintln!("usage ledger: {} call on {} not recorded: {e}", entry.provider, entry.model);
+    }
+}
+
+fn insert(conn: &Connection, entry: &UsageEntry, ts: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO usage_ledger
+             (ts, provider, model, prompt_tokens, completion_tokens, cached_tokens, cost, session_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            ts,
+            entry.provider,
+            entry.model,
+            entry.prompt_tokens,
+            entry.completion_tokens,
+            entry.cached_tokens,
+            entry.cost(),
+            entry.session_id,
+        ],
+    )
+}
+
+/// Estimated cost in USD of the calls recorded since `ts` (Unix seconds).
+pub fn cost_since(ts: i64) -> Result<f64, String> {
+    with_conn(|conn| spent_since(conn, ts))
+}
+
+fn spent_since(conn: &Connection, ts: i64) -> rusqlite::Result<f64> {
+    conn.query_row("SELECT COALESCE(SUM(cost), 0.0) FROM usage_ledger WHERE ts >= ?1", [ts], |row| row.get(0))
+}
+
+/// Unix time of local midnight at the start of `day`.
+pub fn start_of(day: chrono::NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map_or(0, |t| t.timestamp())
+}
+
+#[derive(Serialize, Default)]
+pub struct UsageTotals {
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cached_tokens: i64,
+    /// Estimated, in USD.
+    pub cost: f64,
+}
+
+/// One day or week of [`LocalUsage`].
+#[derive(Serialize)]
+pub struct UsageBucket {
+    /// The day, or the Monday starting the week, as `YYYY-MM-DD` (local time).
+    pub start: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Totals of one provider and model over the whole range.
+#[derive(Serialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Result of [`usage_get_local`].
+#[derive(Serialize)]
+pub struct LocalUsage {
+    /// `daily` or `weekly`.
+    pub period: &'static str,
+    /// First day covered, `YYYY-MM-DD` (local time).
+    pub since: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    /// Oldest first; days or weeks without calls are left out.
+    pub buckets: Vec<UsageBucket>,
+    /// Most expensive first.
+    pub by_model: Vec<ModelUsage>,
+}
+
+const SUMS: &str = "COUNT(*), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0), \
+                    COALESCE(SUM(cached_tokens), 0), COALESCE(SUM(cost), 0.0)";
+
+fn totals(row: &rusqlite::Row, first: usize) -> rusqlite::Result<UsageTotals> {
+    Ok(UsageTotals {
+        calls: row.get(first)?,
+        prompt_tokens: row.get(first + 1)?,
+        completion_tokens: row.get(first + 2)?,
+        cached_tokens: row.get(first + 3)?,
+        cost: row.get(first + 4)?,
+    })
+}
+
+/// Result of [`usage_get_session`].
+#[derive(Serialize)]
+pub struct SessionUsage {
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    /// Most expensive first.
+    pub by_model: Vec<ModelUsage>,
+}
+
+/// Returns direct-provider usage recorded on this device, summed per day
+/// (`period = "daily"`, the default, over the last 30 days) or per week
+/// (`"weekly"`, Monday to Sunday, over the last 12 weeks). `count` changes
+/// how many days or weeks are covered.
+#[tauri::command]
+pub fn usage_get_local(period: Option<String>, count: Option<u32>) -> Result<LocalUsage, String> {
+    let (period, bucket, default_count, unit) = match period.as_deref().unwrap_or("daily") {
+        "daily" => ("daily", DAY_BUCKET, DEFAULT_DAYS, 1),
+        "weekly" => ("weekly", WEEK_BUCKET, DEFAULT_WEEKS, 7),
+        other => return Err(format!("unknown usage period '{other}' (use daily or weekly)")),
+    };
+    let count = count.unwrap_or(default_count);
+    if !(1..=MAX_BUCKETS).contains(&count) {
+        return Err(format!("count must be between 1 and {MAX_BUCKETS}"));
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let mut first = today - chrono::Duration::days(i64::from((count - 1) * unit));
+    if unit == 7 {
+        first -= chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    }
+    with_conn(|conn| local_usage(conn, period, bucket, first))
+}
+
+/// Day of a call, `YYYY-MM-DD` in local time.
+const DAY_BUCKET: &str = "date(ts, 'unixepoch', 'localtime')";
+/// Monday starting the week of a call.
+const WEEK_BUCKET: &str = "date(ts, 'unixepoch', 'localtime', '-6 days', 'weekday 1')";
+
+/// Usage since local day `first`, summed per `bucket`.
+fn local_usage(
+    conn: &Connection,
+    period: &'static str,
+    bucket: &str,
+    first: chrono::NaiveDate,
+) -> rusqlite::Result<LocalUsage> {
+    let since = start_of(first);
+    let totals_all = conn.query_row(
+        &format!("SELECT {SUMS} FROM usage_ledger WHERE ts >= ?1"),
+        [since],
+        |row| totals(row, 0),
+    )?;
+    let buckets = conn
+        .prepare(&format!(
+            "SELECT {bucket} AS start, {SUMS} FROM usage_ledger WHERE ts >= ?1 GROUP BY start ORDER BY start"
+        ))?
+        .query_map([since], |row| Ok(UsageBucket { start: row.get(0)?, totals: totals(row, 1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let by_model = conn
+        .prepare(&format!(
+            "SELECT provider, model, {SUMS} FROM usage_ledger WHERE ts >= ?1 \
+             GROUP BY provider, model ORDER BY SUM(cost) DESC"
+        ))?
+        .query_map([since], |row| {
+            Ok(ModelUsage { provider: row.get(0)?, model: row.get(1)?, totals: totals(row, 2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(LocalUsage {
+        period,
+        since: first.format("%Y-%m-%d").to_string(),
+        totals: totals_all,
+        buckets,
+        by_model,
+    })
+}
+
+/// Returns the direct-provider usage of chat session `session_id`: its
+/// messages and the skills and agents launched from it.
+#[tauri::command]
+pub fn usage_get_session(session_id: String) -> Result<SessionUsage, String> {
+    with_conn(|conn| session_usage(conn, &session_id))
+}
+
+fn session_usage(conn: &Connection, session_id: &str) -> rusqlite::Result<SessionUsage> {
+    let totals_all = conn.query_row(
+        &format!("SELECT {SUMS} FROM usage_ledger WHERE session_id = ?1"),
+        [session_id],
+        |row| totals(row, 0),
+    )?;
+    let by_model = conn
+        .prepare(&format!(
+            "SELECT provider, model, {SUMS} FROM usage_ledger WHERE session_id = ?1 \
+             GROUP BY provider, model ORDER BY SUM(cost) DESC"
+        ))?
+        .query_map([session_id], |row| {
+            Ok(ModelUsage { provider: row.get(0)?, model: row.get(1)?, totals: totals(row, 2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(SessionUsage { totals: totals_all, by_model })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        conn
+    }
+
+    fn entry<'a>(provider: &'a str, model: &'a str, tokens: (i64, i64, i64), session: &'a str) -> UsageEntry<'a> {
+        let (prompt_tokens, completion_tokens, cached_tokens) = tokens;
+        UsageEntry { provider, model, prompt_tokens, completion_tokens, cached_tokens, session_id: Some(session) }
+    }
+
+    fn day(s: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Noon, so a daylight-saving change can't move the call to another day.
+    fn noon(s: &str) -> i64 {
+        start_of(day(s)) + 12 * 3600
+    }
+
+    /// Calls on Monday and Wednesday of one week, the Monday after, and a
+    /// week earlier than all of them.
+    fn seed(conn: &Connection) {
+        insert(conn, &entry("openai", "gpt-4o", (1000, 1000, 0), "a"), noon("2026-10-12")).unwrap();
+        insert(conn, &entry("openai", "gpt-4o-mini", (1000, 0, 0), "a"), noon("2026-10-14")).unwrap();
+        insert(conn, &entry("local-openai", "llama3", (500, 500, 0), "b"), noon("2026-10-19")).unwrap();
+        insert(conn, &entry("openai", "gpt-4o", (1000, 0, 0), "b"), noon("2026-10-05")).unwrap();
+    }
+
+    #[test]
+    fn cost_uses_the_price_table() {
+        assert!((entry("openai", "gpt-4o", (1000, 1000, 0), "a").cost() - 0.0125).abs() < 1e-12);
+        assert_eq!(entry("local-openai", "gpt-4o", (1000, 1000, 0), "a").cost(), 0.0);
+        assert_eq!(entry("openai", "gpt-4o", (0, 0, 0), "a").cost(), 0.0);
+    }
+
+    #[test]
+    fn cost_discounts_cached_tokens() {
+        // Half the input price is saved on each cached token.
+        let cached = entry("openai", "gpt-4o", (1000, 0, 1000), "a").cost();
+        assert!((cached - 0.00125).abs() < 1e-12);
+        // Claude models save 90%.
+        let full = entry("anthropic", "claude-3-5-haiku", (1000, 0, 0), "a").cost();
+        let cached = entry("anthropic", "claude-3-5-haiku", (1000, 0, 1000), "a").cost();
+        assert!((cached - full * 0.1).abs() < 1e-12);
+        // More cached tokens than prompt tokens never goes below zero.
+        assert_eq!(entry("openai", "gpt-4o", (0, 0, 5000), "a").cost(), 0.0);
+    }
+
+    #[test]
+    fn daily_buckets_are_local_days() {
+        let conn = db();
+        seed(&conn);
+        let usage = local_usage(&conn, "daily", DAY_BUCKET, day("2026-10-12")).unwrap();
+        assert_eq!(usage.since, "2026-10-12");
+        assert_eq!(usage.totals.calls, 3);
+        assert_eq!(usage.totals.prompt_tokens, 2500);
+        let starts: Vec<_> = usage.buckets.iter().map(|b| b.start.as_str()).collect();
+        assert_eq!(starts, ["2026-10-12", "2026-10-14", "2026-10-19"]);
+        assert!((usage.buckets[0].totals.cost - 0.0125).abs() < 1e-12);
+        assert_eq!(usage.buckets[2].totals.cost, 0.0);
+    }
+
+    #[test]
+    fn weekly_buckets_start_on_monday() {
+        let conn = db();
+        seed(&conn);
+        let usage = local_usage(&conn, "weekly", WEEK_BUCKET, day("2026-10-12")).unwrap();
+        let buckets: Vec<_> = usage.buckets.iter().map(|b| (b.start.as_str(), b.totals.calls)).collect();
+        assert_eq!(buckets, [("2026-10-12", 2), ("2026-10-19", 1)]);
+        // Most expensive model first.
+        let models: Vec<_> = usage.by_model.iter().map(|m| m.model.as_str()).collect();
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini", "llama3"]);
+    }
+
+    #[test]
+    fn spend_since_includes_the_boundary() {
+        let conn = db();
+        seed(&conn);
+        let all = spent_since(&conn, 0).unwrap();
+        assert!((spent_since(&conn, noon("2026-10-12")).unwrap() - (all - 0.0025)).abs() < 1e-12);
+        assert_eq!(spent_since(&conn, noon("2026-10-20")).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn session_usage_sums_one_session() {
+        let conn = db();
+        seed(&conn);
+        let usage = session_usage(&conn, "a").unwrap();
+        assert_eq!(usage.totals.calls, 2);
+        assert_eq!(usage.totals.completion_tokens, 1000);
+        let models: Vec<_> = usage.by_model.iter().map(|m| m.model.as_str()).collect();
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini"]);
+
+        let none = session_usage(&conn, "missing").unwrap();
+        assert_eq!(none.totals.calls, 0);
+        assert!(none.by_model.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Image inputs for multimodal prompts on the direct (BYOK) path.
+//!
+//! `chat_send` and `ai_generate` take `images` as strings: a data URI (what
+//! `computer_screenshot` returns), bare base64, or a path to an image file.
+//! Each is loaded into an [`Image`] — the format is sniffed from the bytes, so
+//! only PNG, JPEG, GIF and WebP are accepted — and attached to the new user
+//! turn as OpenAI `image_url` parts, Anthropic `image` blocks or Gemini
+//! `inlineData` parts.
+//!
+//! Image files are read through the [`file_sandbox`], like any other file
+//! the app opens on a caller's behalf.
+
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use tauri::AppHandle;
+
+use crate::file_sandbox::{self, Access};
+
+const MAX_IMAGES: usize = 16;
+/// Per decoded image; providers enforce their own, often lower, limits.
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// A decoded, validated image.
+#[derive(Clone)]
+pub struct Image {
+    /// e.g. `image/png`.
+    pub media_type: &'static str,
+    /// Base64 of the image bytes.
+    pub data: String,
+}
+
+impl Image {
+    pub fn data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
+fn sniff_media_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// The sandboxed path of image file `input`, or `None` when it names no
+/// file (and so is taken for base64).
+fn image_file(app: &AppHandle, input: &str) -> Result<Option<PathBuf>, String> {
+    match file_sandbox::resolve(app, input, Access::Read) {
+        Ok(path) => Ok(path.is_file().then_some(path)),
+        Err(e) if Path::new(input).exists() => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Loads one image from a data URI, bare base64 or a file path.
+pub fn load(app: &AppHandle, input: &str) -> Result<Image, String> {
+    let input = input.trim();
+    let bytes = if let Some(rest) = input.strip_prefix("data:") {
+        let (_, b64) = rest
+            .split_once(";base64,")
+            .ok_or("image data URI must be base64-encoded")?;
+        B64.decode(b64.trim()).map_err(|e| format!("invalid image base64: {e}"))?
+    } else if let Some(path) = image_file(app, input)? {
+        let meta = std::fs::metadata(&path).map_err(|e| format!("cannot read image '{input}': {e}"))?;
+        if meta.len() > MAX_IMAGE_BYTES as u64 {
+            return Err(format!("image '{input}' exceeds {} MB", MAX_IMAGE_BYTES / (1024 * 1024)));
+        }
+        std::fs::read(&path).map_err(|e| format!("cannot read image '{input}': {e}"))?
+    } else {
+        B64.decode(input).map_err(|_| format!("'{input}' is neither an image file nor base64"))?
+    };
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(format!("image exceeds {} MB", MAX_IMAGE_BYTES / (1024 * 1024)));
+    }
+    let media_type = sniff_media_type(&bytes).ok_or("unsupported image format (use PNG, JPEG, GIF or WebP)")?;
+    Ok(Image { media_type, data: B64.encode(&bytes) })
+}
+
+/// Loads the `images` of a command.
+pub fn load_all(app: &AppHandle, inputs: &[String]) -> Result<Vec<Image>, String> {
+    if inputs.len() > MAX_IMAGES {
+        return Err(format!("too many images (max {MAX_IMAGES})"));
+    }
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(i, s)| load(app, s).map_err(|e| format!("images[{i}]: {e}")))
+        .collect()
+}
+
+/// Errors for providers whose request body has no image support here.
+pub fn check_provider(provider: &str) -> Result<(), String> {
+    if provider == "cohere" {
+        return Err(format!("provider '{provider}' does not accept images"));
+    }
+    Ok(())
+}
+
+// ── Request content ────────────────────────────────────────────────────────────
+
+/// OpenAI `content`: the text followed by one `image_url` part per image.
+pub fn openai_content(text: &str, images: &[Image]) -> serde_json::Value {
+    let mut parts = vec![serde_json::json!({ "type": "text", "text": text })];
+    parts.extend(images.iter().map(|img| {
+        serde_json::json!({ "type": "image_url", "image_url": { "url": img.data_uri() } })
+    }));
+    serde_json::Value::Array(parts)
+}
+
+/// Anthropic `content`: image blocks first, as Anthropic recommends, then the text.
+pub fn anthropic_content(text: &str, images: &[Image]) -> serde_json::Value {
+    let mut blocks: Vec<serde_json::Value> = images
+        .iter()
+        .map(|img| {
+            serde_json::json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": img.media_type, "data": img.data },
+            })
+        })
+        .collect();
+    blocks.push(serde_json::json!({ "type": "text", "text": text }));
+    serde_json::Value::Array(blocks)
+}
+
+/// Gemini `parts`: the text followed by one `inlineData` part per image.
+pub fn google_parts(text: &str, images: &[Image]) -> serde_json::Value {
+    let mut parts = vec![serde_json::json!({ "text": text })];
+    parts.extend(images.iter().map(|img| {
+        serde_json::json!({ "inlineData": { "mimeType": img.media_type, "data": img.data } })
+    }));
+    serde_json::Value::Array(parts)
+}
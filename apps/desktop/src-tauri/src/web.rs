@@ -0,0 +1,537 @@
+//! Lightweight web page fetching for chat and agents.
+//!
+//! `web_fetch` downloads a single page over HTTP(S) and returns either the
+//! readable article text (readability-style: boilerplate stripped, the
+//! densest content block kept) or the raw HTML. It is deliberately not a
+//! browser — no JavaScript, no cookies — so it is cheap enough to call from
+//! any skill that wants to cite live content.
+//!
+//! Only public hosts are fetched: a host given as, or resolving to, a
+//! loopback, private, link-local or unique-local address (such as a cloud
+//! metadata endpoint at 169.254.169.254) is refused, on the first request
+//! and on every redirect.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use kuchikiki::traits::TendrilSink;
+use kuchikiki::NodeRef;
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::redact;
+
+/// Default download cap (2 MB) when the caller doesn't pass `max_bytes`.
+const DEFAULT_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// Hard download cap (10 MB) regardless of `max_bytes`.
+const MAX_FETCH_BYTES: usize = 10 * 1024 * 1024;
+
+/// `robots.txt` files larger than this (512 KB) are treated as absent.
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+const FETCH_TIMEOUT_SECS: u64 = 20;
+const MAX_REDIRECTS: usize = 5;
+
+/// Product token matched against `User-agent:` lines in robots.txt.
+const ROBOTS_AGENT: &str = "AgentHub";
+const USER_AGENT: &str = concat!("AgentHub/", env!("CARGO_PKG_VERSION"), " (+web_fetch)");
+
+/// Elements that never hold article content.
+const BOILERPLATE_SELECTOR: &str =
+    "script, style, noscript, template, svg, iframe, form, nav, header, footer, aside";
+
+// ── Response types ─────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct WebPage {
+    /// Final URL after redirects.
+    pub url: String,
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub title: Option<String>,
+    /// Readable text (`mode = "text"`) or raw HTML (`mode = "html"`).
+    pub content: String,
+    /// `true` when the body was cut at `max_bytes`.
+    pub truncated: bool,
+}
+
+// ── Internal helpers ───────────────────────────────────────────────────────────
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }))
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+        .map_err(|e| format!("http client unavailable: {e}"))
+}
+
+/// Whether `ip` is reachable from the public internet, as opposed to this
+/// machine, its local network or the cloud instance it runs on.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                // "This network" and carrier-grade NAT.
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // Unique-local fc00::/7 and link-local fe80::/10.
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Checks the scheme of `url`, and its host when that is an IP address;
+/// host names are checked when [`PublicResolver`] resolves them.
+fn check_url(url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported url scheme '{}'", url.scheme()));
+    }
+    let host = url.host_str().unwrap_or_default();
+    let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() else {
+        return Ok(());
+    };
+    if !is_public(ip) {
+        return Err(format!("{ip} is a private or local address"));
+    }
+    Ok(())
+}
+
+/// Resolves host names with the system resolver, dropping addresses that
+/// aren't public. Resolving at connect time means a host can't pass a check
+/// and then be re-pointed at a private address.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host, 0)).await?.filter(|addr| is_public(addr.ip())).collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} resolves to a private or local address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Formats a failed request with its innermost cause, which is where the
+/// reason a host was refused ends up.
+fn fetch_error(e: reqwest::Error) -> String {
+    let mut cause = std::error::Error::source(&e);
+    while let Some(source) = cause.and_then(|c| c.source()) {
+        cause = Some(source);
+    }
+    match cause {
+        Some(cause) => redact::error(format!("fetch failed: {e}: {cause}")),
+        None => redact::error(format!("fetch failed: {e}")),
+    }
+}
+
+/// Reads at most `limit` bytes of the body; returns `(bytes, truncated)`.
+async fn read_capped(resp: reqwest::Response, limit: usize) -> Result<(Vec<u8>, bool), String> {
+    let mut body = Vec::new();
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| redact::error(format!("download failed: {e}")))?;
+        let room = limit - body.len();
+        if chunk.len() >= room {
+            body.extend_from_slice(&chunk[..room]);
+            // Exactly reaching the cap only counts as truncated if more follows.
+            let truncated = chunk.len() > room || stream.next().await.is_some();
+            return Ok((body, truncated));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+/// Returns whether robots.txt at the URL's origin allows fetching its path.
+/// A missing or unreadable robots.txt allows everything.
+async fn robots_allowed(http: &reqwest::Client, url: &Url) -> bool {
+    let Ok(robots_url) = url.join("/robots.txt") else {
+        return true;
+    };
+    let resp = match http.get(robots_url).send().await {
+        Ok(r) if r.status().is_success() => r,
+        _ => return true,
+    };
+    let Ok((body, truncated)) = read_capped(resp, MAX_ROBOTS_BYTES).await else {
+        return true;
+    };
+    if truncated {
+        return true;
+    }
+    let mut path = url.path().to_owned();
+    if let Some(q) = url.query() {
+        path.push('?');
+        path.push_str(q);
+    }
+    is_path_allowed(&String::from_utf8_lossy(&body), ROBOTS_AGENT, &path)
+}
+
+/// Evaluates robots.txt rules for `agent`: the group naming the agent wins
+/// over `*`, the longest matching rule wins, and `Allow` wins ties.
+fn is_path_allowed(robots: &str, agent: &str, path: &str) -> bool {
+    let agent = agent.to_ascii_lowercase();
+    let mut specific: Vec<(bool, String)> = Vec::new();
+    let mut wildcard: Vec<(bool, String)> = Vec::new();
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        match key.as_str() {
+            "user-agent" => {
+                // A user-agent line after rules starts a new group.
+                if in_rules {
+                    group_agents.clear();
+                    in_rules = false;
+                }
+                group_agents.push(value.to_ascii_lowercase());
+            }
+            "allow" | "disallow" => {
+                in_rules = true;
+                // An empty Disallow means "allow everything" — no rule.
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = (key == "allow", value.to_owned());
+                if group_agents.iter().any(|a| agent.contains(a.as_str()) && a != "*") {
+                    specific.push(rule.clone());
+                }
+                if group_agents.iter().any(|a| a == "*") {
+                    wildcard.push(rule);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let rules = if specific.is_empty() { &wildcard } else { &specific };
+    rules
+        .iter()
+        .filter(|(_, pattern)| robots_match(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .map_or(true, |(allow, _)| *allow)
+}
+
+/// Matches a robots.txt path pattern supporting `*` and a trailing `$`.
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some((first, tail)) = parts.split_first() else {
+        return true;
+    };
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let Some((last, middle)) = tail.split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    if anchored { rest.ends_with(last) } else { rest.contains(last) }
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div" | "section" | "article" | "main" | "br" | "hr" | "li" | "ul" | "ol"
+            | "tr" | "table" | "pre" | "blockquote" | "figure" | "figcaption"
+            | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "dd" | "dt"
+    )
+}
+
+/// Appends the text of `node` with block elements on their own lines.
+fn collect_text(node: &NodeRef, out: &mut String) {
+    for child in node.children() {
+        if let Some(text) = child.as_text() {
+            out.push_str(&text.borrow());
+        } else if let Some(el) = child.as_element() {
+            let name = el.name.local.as_ref();
+            let block = is_block(name);
+            // Line-level elements start a line; other blocks form paragraphs.
+            let line = matches!(name, "li" | "br" | "tr" | "dt" | "dd");
+            if block {
+                out.push('\n');
+            }
+            if name == "li" {
+                out.push_str("- ");
+            }
+            collect_text(&child, out);
+            if block && !line {
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Collapses runs of whitespace and keeps at most one blank line between blocks.
+fn normalize_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(&line);
+        blank_run = 0;
+    }
+    out
+}
+
+/// Picks the element holding the article: an explicit `<article>`/`<main>`,
+/// otherwise the parent whose direct `<p>` children carry the most text.
+fn content_root(doc: &NodeRef) -> NodeRef {
+    if let Ok(el) = doc.select_first("article, main, [role=main]") {
+        return el.as_node().clone();
+    }
+    let mut scores: Vec<(NodeRef, usize)> = Vec::new();
+    if let Ok(paragraphs) = doc.select("p") {
+        for p in paragraphs {
+            let Some(parent) = p.as_node().parent() else { continue };
+            let len = p.text_contents().trim().len();
+            match scores.iter_mut().find(|(n, _)| *n == parent) {
+                Some((_, score)) => *score += len,
+                None => scores.push((parent, len)),
+            }
+        }
+    }
+    scores
+        .into_iter()
+        .max_by_key(|(_, score)| *score)
+        .map(|(n, _)| n)
+        .or_else(|| doc.select_first("body").ok().map(|b| b.as_node().clone()))
+        .unwrap_or_else(|| doc.clone())
+}
+
+/// Returns `(title, readable_text)` for an HTML document.
+fn extract_readable(html: &str) -> (Option<String>, String) {
+    let doc = kuchikiki::parse_html().one(html).document_node;
+
+    let title = doc
+        .select_first("meta[property='og:title']")
+        .ok()
+        .and_then(|m| m.attributes.borrow().get("content").map(String::from))
+        .or_else(|| doc.select_first("title").ok().map(|t| t.text_contents()))
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty());
+
+    if let Ok(junk) = doc.select(BOILERPLATE_SELECTOR) {
+        // Collect first — detaching while iterating would skip siblings.
+        let junk: Vec<_> = junk.collect();
+        for el in junk {
+            el.as_node().detach();
+        }
+    }
+
+    let mut text = String::new();
+    collect_text(&content_root(&doc), &mut text);
+    (title, normalize_whitespace(&text))
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Downloads `url` and returns its readable text (`mode = "text"`, default)
+/// or raw HTML (`mode = "html"`).
+///
+/// The body is capped at `max_bytes` (default 2 MB, max 10 MB). robots.txt is
+/// honoured unless `respect_robots` is `false`. Only `http`/`https` URLs are
+/// accepted, and only for public hosts. Requires `network.fetch` permission
+/// (enforced by the module sandbox).
+#[tauri::command]
+pub async fn web_fetch(
+    url: String,
+    mode: Option<String>,
+    max_bytes: Option<usize>,
+    respect_robots: Option<bool>,
+) -> Result<WebPage, String> {
+    let raw_html = match mode.as_deref().unwrap_or("text") {
+        "text" => false,
+        "html" => true,
+        other => return Err(format!("unknown mode '{other}' (expected text or html)")),
+    };
+    let url = Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
+    check_url(&url)?;
+    let limit = max_bytes.unwrap_or(DEFAULT_MAX_BYTES).clamp(1, MAX_FETCH_BYTES);
+
+    let http = client()?;
+    if respect_robots.unwrap_or(true) && !robots_allowed(&http, &url).await {
+        return Err(format!("fetching {url} is disallowed by the site's robots.txt"));
+    }
+
+    let resp = http
+        .get(url)
+        .send()
+        .await
+        .map_err(fetch_error)?;
+    let status = resp.status().as_u16();
+    if !resp.status().is_success() {
+        return Err(format!("fetch failed: HTTP {status}"));
+    }
+    let final_url = resp.url().to_string();
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let mime = content_type.as_deref().unwrap_or("text/html").to_ascii_lowercase();
+    let is_html = mime.contains("html") || mime.contains("xml");
+    if !is_html && !mime.starts_with("text/") && !mime.contains("json") {
+        return Err(format!("unsupported content type '{mime}' (expected HTML or text)"));
+    }
+
+    let (body, truncated) = read_capped(resp, limit).await?;
+    let body = String::from_utf8_lossy(&body).into_owned();
+
+    let (title, content) = if raw_html || !is_html {
+        (None, body)
+    } else {
+        extract_readable(&body)
+    };
+
+    Ok(WebPage { url: final_url, status, content_type, title, content, truncated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn only_public_addresses_pass() {
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "100.64.0.1",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip(private)), "{private}");
+        }
+        for public in ["93.184.216.34", "8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(is_public(ip(public)), "{public}");
+        }
+    }
+
+    #[test]
+    fn urls_with_private_hosts_are_refused() {
+        let check = |s: &str| check_url(&Url::parse(s).unwrap());
+        assert!(check("https://example.com/").is_ok());
+        assert!(check("http://93.184.216.34/").is_ok());
+        assert!(check("http://169.254.169.254/latest/meta-data/").is_err());
+        assert!(check("http://[::1]:8080/").is_err());
+        // Other spellings of an IPv4 address are normalized by the parser.
+        assert!(check("http://0x7f.1/").is_err());
+        assert!(check("http://2130706433/").is_err());
+        assert!(check("file:///etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn fetching_a_local_host_fails_before_connecting() {
+        let fetch = |url: &str| web_fetch(url.into(), None, None, Some(false));
+        let err = fetch("http://127.0.0.1:9/").await.err().unwrap();
+        assert!(err.contains("private or local"), "{err}");
+        let err = fetch("http://localhost:9/").await.err().unwrap();
+        assert!(err.contains("private or local"), "{err}");
+    }
+
+    #[test]
+    fn robots_longest_rule_wins() {
+        let robots = "User-agent: *\nDisallow: /private\nAllow: /private/open\n";
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/"));
+        assert!(!is_path_allowed(robots, ROBOTS_AGENT, "/private/secret"));
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/private/open/page"));
+    }
+
+    #[test]
+    fn robots_specific_group_overrides_the_wildcard() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: AgentHub\nDisallow: /admin\n";
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/docs"));
+        assert!(!is_path_allowed(robots, ROBOTS_AGENT, "/admin/users"));
+        assert!(!is_path_allowed(robots, "OtherBot", "/docs"));
+    }
+
+    #[test]
+    fn robots_empty_disallow_and_comments() {
+        let robots = "# crawl everything\nUser-agent: *\nDisallow:\n";
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/anything"));
+        assert!(is_path_allowed("", ROBOTS_AGENT, "/anything"));
+        let robots = "User-agent: *\nDisallow: /tmp # scratch\n";
+        assert!(!is_path_allowed(robots, ROBOTS_AGENT, "/tmp/x"));
+    }
+
+    #[test]
+    fn robots_ties_go_to_allow() {
+        let robots = "User-agent: *\nDisallow: /page\nAllow: /page\n";
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/page"));
+    }
+
+    #[test]
+    fn robots_wildcards_and_anchors() {
+        assert!(robots_match("/*.pdf$", "/files/report.pdf"));
+        assert!(!robots_match("/*.pdf$", "/files/report.pdf?download=1"));
+        assert!(robots_match("/search?q=*", "/search?q=rust"));
+        assert!(robots_match("/a*b*c", "/a-x-b-y-c-z"));
+        assert!(!robots_match("/a*b*c", "/a-x-c-y-b"));
+        assert!(robots_match("/exact$", "/exact"));
+        assert!(!robots_match("/exact$", "/exact/more"));
+        let robots = "User-agent: *\nDisallow: /*.pdf$\n";
+        assert!(!is_path_allowed(robots, ROBOTS_AGENT, "/doc.pdf"));
+        assert!(is_path_allowed(robots, ROBOTS_AGENT, "/doc.pdf?x"));
+    }
+}
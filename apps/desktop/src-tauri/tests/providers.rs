@@ -0,0 +1,334 @@
+//! The direct (BYOK) provider calls and the gateway stream, run against the
+//! test harness's mock servers over real HTTP.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use agenthub_test_harness::{mocks, MockServer, Reply, Route, SseEvent};
+use ai_super_app_lib::providers::{
+    call_provider_generate, call_provider_stream, call_provider_stream_with_tools, gateway_stream,
+    Admission, ChatMessage, GenerationParams, ProviderCall, RequestGate,
+};
+use ai_super_app_lib::stream::{EventTarget, StreamSink, STREAM_READ_ERROR};
+use ai_super_app_lib::tool_calls::ToolSpec;
+use serde_json::{json, Value};
+
+/// Records the events a sink emits.
+#[derive(Default)]
+struct Recorder(Mutex<Vec<(String, Value)>>);
+
+impl EventTarget for Recorder {
+    fn emit_event(&self, event: &str, payload: Value) {
+        self.0.lock().unwrap().push((event.to_owned(), payload));
+    }
+}
+
+impl Recorder {
+    /// Payloads of the request-scoped `kind` events, in order.
+    fn scoped(&self, kind: &str) -> Vec<Value> {
+        let prefix = format!("chat:{kind}:");
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(event, _)| event.starts_with(&prefix))
+            .map(|(_, payload)| payload["data"].clone())
+            .collect()
+    }
+
+    fn chunks(&self) -> Vec<String> {
+        self.scoped("stream-chunk").iter().filter_map(|c| c.as_str().map(String::from)).collect()
+    }
+}
+
+fn sink() -> (Arc<Recorder>, StreamSink) {
+    let recorder = Arc::new(Recorder::default());
+    let sink = StreamSink::with_target(recorder.clone(), "chat", Some("req-1".into()), Duration::from_secs(5));
+    (recorder, sink)
+}
+
+/// Admits the first `allow` requests and refuses the rest.
+struct CountingGate {
+    allow: usize,
+    asked: AtomicUsize,
+}
+
+impl RequestGate for CountingGate {
+    fn admit<'a>(&'a self, _provider: &'a str) -> Pin<Box<dyn Future<Output = Result<Admission, String>> + Send + 'a>> {
+        Box::pin(async move {
+            if self.asked.fetch_add(1, Ordering::SeqCst) >= self.allow {
+                return Err("spend limit reached".into());
+            }
+            Ok(Box::new(()) as Admission)
+        })
+    }
+}
+
+fn call<'a>(provider: &'a str, key: &'a str, server: &MockServer, params: &'a GenerationParams) -> ProviderCall<'a> {
+    ProviderCall::new(provider, key, Some("test-model"), "Say hello", params).with_base_url(Some(server.url()))
+}
+
+#[tokio::test]
+async fn streams_each_provider_through_the_sink() {
+    let chunks = ["Hel", "lo", " there"];
+    let cases = [
+        ("openai", mocks::openai(mocks::openai_stream(&chunks))),
+        ("groq", mocks::openai(mocks::openai_stream(&chunks))),
+        ("anthropic", mocks::anthropic(mocks::anthropic_stream(&chunks))),
+        ("gemini", mocks::gemini(mocks::gemini_stream(&chunks))),
+    ];
+    for (provider, routes) in cases {
+        let server = MockServer::start(routes);
+        let (events, sink) = sink();
+        let params = GenerationParams::default();
+
+        let output = call_provider_stream(&sink, &reqwest::Client::new(), &call(provider, "sk-test", &server, &params))
+            .await
+            .unwrap_or_else(|e| panic!("{provider}: {e}"));
+
+        assert_eq!(output, "Hello there", "{provider}");
+        assert_eq!(events.chunks(), chunks, "{provider}");
+        let usage = sink.usage().unwrap_or_else(|| panic!("{provider}: no usage"));
+        assert_eq!(usage.prompt_tokens, 12, "{provider}");
+        assert!(!events.scoped("stream-usage").is_empty(), "{provider}");
+    }
+}
+
+#[tokio::test]
+async fn sends_each_provider_its_auth_and_body() {
+    let params = GenerationParams { temperature: Some(0.5), max_tokens: Some(64), ..Default::default() };
+    let history = [
+        ChatMessage { role: "user".into(), content: "Hi".into() },
+        ChatMessage { role: "assistant".into(), content: "Hello!".into() },
+    ];
+
+    let server = MockServer::start(mocks::openai(mocks::openai_stream(&["ok"])));
+    let openai = call("openai", "sk-test", &server, &params).with_system(Some("Be brief.")).with_history(&history);
+    call_provider_stream(&sink().1, &reqwest::Client::new(), &openai).await.unwrap();
+    let req = &server.requests()[0];
+    assert_eq!(req.path, "/chat/completions");
+    assert_eq!(req.header("authorization"), Some("Bearer sk-test"));
+    let body = req.json();
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["stream_options"]["include_usage"], true);
+    assert_eq!(body["temperature"], 0.5);
+    assert_eq!(body["max_tokens"], 64);
+    let roles: Vec<&str> = body["messages"].as_array().unwrap().iter().map(|m| m["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+
+    let server = MockServer::start(mocks::anthropic(mocks::anthropic_stream(&["ok"])));
+    let anthropic = call("anthropic", "sk-ant", &server, &params).with_system(Some("Be brief."));
+    call_provider_stream(&sink().1, &reqwest::Client::new(), &anthropic).await.unwrap();
+    let req = &server.requests()[0];
+    assert_eq!(req.path, "/messages");
+    assert_eq!(req.header("x-api-key"), Some("sk-ant"));
+    assert_eq!(req.header("anthropic-version"), Some("2023-06-01"));
+    assert_eq!(req.header("authorization"), None);
+    let body = req.json();
+    assert_eq!(body["system"], "Be brief.");
+    assert_eq!(body["max_tokens"], 64);
+    assert_eq!(body["messages"][0]["content"], "Say hello");
+
+    let server = MockServer::start(mocks::gemini(mocks::gemini_stream(&["ok"])));
+    let gemini = call("gemini", "g-key", &server, &params);
+    call_provider_stream(&sink().1, &reqwest::Client::new(), &gemini).await.unwrap();
+    let req = &server.requests()[0];
+    assert_eq!(req.path, "/models/test-model:streamGenerateContent?key=g-key&alt=sse");
+    assert_eq!(req.json()["generationConfig"]["maxOutputTokens"], 64);
+}
+
+#[tokio::test]
+async fn keyless_servers_get_no_authorization_header() {
+    let server = MockServer::start(mocks::openai(mocks::openai_stream(&["ok"])));
+    let params = GenerationParams::default();
+    call_provider_stream(&sink().1, &reqwest::Client::new(), &call("local-openai", "", &server, &params))
+        .await
+        .unwrap();
+    assert_eq!(server.requests()[0].header("authorization"), None);
+}
+
+#[tokio::test]
+async fn skips_malformed_chunks_and_keep_alives() {
+    let server = MockServer::start(mocks::openai(mocks::with_malformed_chunk(mocks::openai_stream(&["a", "b"]))));
+    let (events, sink) = sink();
+    let params = GenerationParams::default();
+
+    let output = call_provider_stream(&sink, &reqwest::Client::new(), &call("openai", "sk", &server, &params))
+        .await
+        .unwrap();
+
+    assert_eq!(output, "ab");
+    assert_eq!(events.chunks(), ["a", "b"]);
+}
+
+#[tokio::test]
+async fn resumes_a_dropped_stream_then_gives_up() {
+    let server = MockServer::start(mocks::openai(mocks::aborted_after(mocks::openai_stream(&["Once", " upon"]), 1)));
+    let (events, sink) = sink();
+    let params = GenerationParams::default();
+
+    let err = call_provider_stream(&sink, &reqwest::Client::new(), &call("openai", "sk", &server, &params))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err, STREAM_READ_ERROR);
+    // The first request and two resumes.
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(events.scoped("stream-resumed").len(), 2);
+    // A resume replays the reply so far as an assistant turn.
+    let messages = requests[1].json()["messages"].clone();
+    assert_eq!(messages[0], json!({ "role": "user", "content": "Say hello" }));
+    assert_eq!(messages[1], json!({ "role": "assistant", "content": "Once" }));
+    assert_eq!(messages[2]["role"], "user");
+}
+
+#[tokio::test]
+async fn admits_each_resume_through_the_gate() {
+    let server = MockServer::start(mocks::openai(mocks::aborted_after(mocks::openai_stream(&["Once", " upon"]), 1)));
+    let (events, sink) = sink();
+    let params = GenerationParams::default();
+    let gate = CountingGate { allow: 2, asked: AtomicUsize::new(0) };
+
+    let call = call("openai", "sk", &server, &params).with_gate(Some(&gate));
+    let err = call_provider_stream(&sink, &reqwest::Client::new(), &call).await.unwrap_err();
+
+    // The first request and one resume go out; the second resume is refused.
+    assert_eq!(err, "spend limit reached");
+    assert_eq!(gate.asked.load(Ordering::SeqCst), 3);
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(events.scoped("stream-resumed").len(), 2);
+}
+
+#[tokio::test]
+async fn error_status_surfaces_the_provider_message() {
+    let server = MockServer::start(vec![Route::post("/messages", Reply::error(400, "max_tokens is too large"))]);
+    let params = GenerationParams::default();
+
+    let err = call_provider_stream(&sink().1, &reqwest::Client::new(), &call("anthropic", "sk", &server, &params))
+        .await
+        .unwrap_err();
+
+    assert!(err.starts_with("Anthropic API error 400"), "{err}");
+    assert!(err.contains("max_tokens is too large"), "{err}");
+}
+
+#[tokio::test]
+async fn stalled_stream_fails_with_a_stream_error_event() {
+    let events = vec![SseEvent::Data(json!({ "choices": [{ "delta": { "content": "a" } }] }).to_string())];
+    let mut stalled = events.clone();
+    stalled.push(SseEvent::Pause(Duration::from_secs(2)));
+    stalled.extend(mocks::openai_stream(&["b"]));
+    let server = MockServer::start(mocks::openai(stalled));
+    let recorder = Arc::new(Recorder::default());
+    let sink = StreamSink::with_target(recorder.clone(), "chat", None, Duration::from_millis(300));
+    let params = GenerationParams::default();
+
+    let err = call_provider_stream(&sink, &reqwest::Client::new(), &call("openai", "sk", &server, &params))
+        .await
+        .unwrap_err();
+
+    assert!(err.starts_with("stream stalled"), "{err}");
+    let prefix = format!("chat:stream-error:{}", sink.request_id);
+    assert!(recorder.0.lock().unwrap().iter().any(|(event, _)| *event == prefix));
+}
+
+#[tokio::test]
+async fn assembles_streamed_tool_calls() {
+    let delta = |d: Value| SseEvent::Data(json!({ "choices": [{ "index": 0, "delta": d }] }).to_string());
+    let server = MockServer::start(mocks::openai(vec![
+        delta(json!({ "tool_calls": [{ "index": 0, "id": "call_1", "function": { "name": "lookup", "arguments": "{\"q\":" } }] })),
+        delta(json!({ "tool_calls": [{ "index": 0, "function": { "arguments": "\"rust\"}" } }] })),
+        SseEvent::Data("[DONE]".into()),
+    ]));
+    let (events, sink) = sink();
+    let params = GenerationParams::default();
+    let tools = [ToolSpec { name: "lookup".into(), description: None, parameters: json!({ "type": "object" }) }];
+
+    let (_, calls) = call_provider_stream_with_tools(
+        &sink,
+        &reqwest::Client::new(),
+        &call("openai", "sk", &server, &params).with_tools(&tools, &[]),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(calls.len(), 1);
+    assert_eq!((calls[0].id.as_str(), calls[0].name.as_str()), ("call_1", "lookup"));
+    assert_eq!(calls[0].arguments_json(), json!({ "q": "rust" }));
+    assert_eq!(events.scoped("tool-call").len(), 1);
+    assert_eq!(server.requests()[0].json()["tools"][0]["function"]["name"], "lookup");
+}
+
+#[tokio::test]
+async fn generates_buffered_replies() {
+    let cases = [
+        (
+            "openai",
+            "/chat/completions",
+            json!({ "choices": [{ "message": { "content": "Hi" } }], "usage": { "prompt_tokens": 10, "completion_tokens": 2 } }),
+        ),
+        (
+            "anthropic",
+            "/messages",
+            json!({ "content": [{ "type": "text", "text": "Hi" }], "usage": { "input_tokens": 10, "output_tokens": 2 } }),
+        ),
+        (
+            "gemini",
+            "/models/",
+            json!({
+                "candidates": [{ "content": { "parts": [{ "text": "Hi" }] } }],
+                "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 2 },
+            }),
+        ),
+    ];
+    for (provider, path, reply) in cases {
+        let server = MockServer::start(vec![Route::post(path, Reply::json(reply))]);
+        let params = GenerationParams::default();
+
+        let (output, usage) = call_provider_generate(&reqwest::Client::new(), &call(provider, "sk", &server, &params))
+            .await
+            .unwrap_or_else(|e| panic!("{provider}: {e}"));
+
+        assert_eq!(output, "Hi", "{provider}");
+        assert_eq!((usage.total, usage.completion), (12, 2), "{provider}");
+        assert_ne!(server.requests()[0].json()["stream"], true, "{provider}");
+    }
+}
+
+#[tokio::test]
+async fn gateway_streams_with_the_access_token() {
+    let server = MockServer::start(mocks::gateway("tok", &["Hel", "lo"]));
+    let (events, sink) = sink();
+    let body = json!({ "capability": "general-chat", "input": "hi" });
+
+    let output = gateway_stream(&sink, &reqwest::Client::new(), &server.url(), "tok", &body).await.unwrap();
+    assert_eq!(output, "Hello");
+    assert_eq!(events.chunks(), ["Hel", "lo"]);
+    assert_eq!(server.requests()[0].json(), body);
+
+    let err = gateway_stream(&sink, &reqwest::Client::new(), &server.url(), "wrong", &body).await.unwrap_err();
+    assert_eq!(err, "stream error: HTTP 401");
+}
+
+#[test]
+fn only_unscoped_requests_are_mirrored() {
+    let (scoped, sink) = sink();
+    sink.emit("stream-chunk", "a");
+    sink.finish(Err::<(), _>("boom".into()));
+    let events: Vec<String> = scoped.0.lock().unwrap().iter().map(|(event, _)| event.clone()).collect();
+    assert_eq!(events, ["chat:stream-chunk:req-1", "chat:stream-failed:req-1"]);
+    assert_eq!(scoped.scoped("stream-failed"), [json!({ "error": "boom" })]);
+
+    let legacy = Arc::new(Recorder::default());
+    let sink = StreamSink::with_target(legacy.clone(), "chat", None, Duration::from_secs(5));
+    sink.finish(Ok("done"));
+    let events = legacy.0.lock().unwrap().clone();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, format!("chat:stream-done:{}", sink.request_id));
+    assert_eq!(events[1], ("chat:stream-done".to_owned(), json!("done")));
+}
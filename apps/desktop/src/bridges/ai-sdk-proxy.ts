@@ -83,11 +83,13 @@ export class AiSdkProxy implements IAiClient {
 
   /**
    * Tauri streaming — starts the Rust `ai_stream` command (which emits
-   * `ai:stream-chunk` events as it reads the SSE response) and yields each
-   * chunk as it arrives using an async generator backed by a bounded buffer.
+   * `ai:stream-chunk:{requestId}` events as it reads the SSE response) and
+   * yields each chunk as it arrives using an async generator backed by a
+   * bounded buffer.
    *
    * Listeners are registered BEFORE invoking to avoid any race condition.
-   * Errors from the Rust command are propagated to the generator consumer.
+   * Errors from the Rust command, or `ai:stream-failed:{requestId}` once it
+   * has returned, are propagated to the generator consumer.
    */
   private async *_tauriStream(request: IAiGenerateRequest): AsyncIterable<string> {
     const { invoke } = await import('@tauri-apps/api/core')
@@ -97,19 +99,28 @@ export class AiSdkProxy implements IAiClient {
     let done = false
     let streamError: Error | null = null
     let wakeUp: (() => void) | null = null
+    const requestId = crypto.randomUUID()
 
-    const unlistenChunk = await listen<string>('ai:stream-chunk', (e) => {
-      buffer.push(e.payload)
+    const unlistenChunk = await listen<{ data: string }>(`ai:stream-chunk:${requestId}`, (e) => {
+      buffer.push(e.payload.data)
       if (wakeUp !== null) { wakeUp(); wakeUp = null }
     })
 
-    const unlistenDone = await listen<undefined>('ai:stream-done', () => {
+    const unlistenDone = await listen<undefined>(`ai:stream-done:${requestId}`, () => {
       done = true
       if (wakeUp !== null) { wakeUp(); wakeUp = null }
     })
 
-    // Fire-and-forget — Rust emits events while this generator consumes them.
+    const unlistenFailed = await listen<{ data: { error: string } }>(`ai:stream-failed:${requestId}`, (e) => {
+      streamError = new Error(e.payload.data.error)
+      done = true
+      if (wakeUp !== null) { wakeUp(); wakeUp = null }
+    })
+
+    // Returns once the stream has started — Rust emits events while this
+    // generator consumes them.
     const invokePromise = invoke('ai_stream', {
+      requestId,
       capability: request.capability,
       input: request.input,
       ...(request.context ? { context: request.context } : {}),
@@ -139,6 +150,7 @@ export class AiSdkProxy implements IAiClient {
     } finally {
       unlistenChunk()
       unlistenDone()
+      unlistenFailed()
       await invokePromise
     }
   }
@@ -108,23 +108,57 @@ async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T
   return tauriInvoke<T>(cmd, args)
 }
 
+/** Payload of a request-scoped stream event (`chat:stream-chunk:{id}`, …). */
+interface IStreamEvent<T> {
+  request_id: string
+  data: T
+}
+
+/** `onStream` handlers; every chat sent through this bridge feeds them. */
+const chatStreamHandlers = new Set<(chunk: string) => void>()
+
 const tauriBridge: IDesktopBridge = {
   // ── Chat ─────────────────────────────────────────────────────────────────
   chat: {
-    send: (message: string, options?: IAiRequestOptions) =>
-      invoke<{ output: string }>('chat_send', {
-        message,
-        ...(options?.apiKey ? { apiKey: options.apiKey } : {}),
-        ...(options?.provider ? { provider: options.provider } : {}),
-        ...(options?.model ? { model: options.model } : {}),
-      }),
+    // `chat_send` returns at once; the reply streams on channels scoped to
+    // the request ID, which are subscribed before invoking.
+    send: async (message: string, options?: IAiRequestOptions) => {
+      const { listen } = await import('@tauri-apps/api/event')
+      const requestId = crypto.randomUUID()
+      let resolveDone!: (res: { output: string }) => void
+      let rejectDone!: (err: Error) => void
+      const done = new Promise<{ output: string }>((resolve, reject) => {
+        resolveDone = resolve
+        rejectDone = reject
+      })
+      const unlisten = await Promise.all([
+        listen<IStreamEvent<string>>(`chat:stream-chunk:${requestId}`, (e) => {
+          chatStreamHandlers.forEach((handler) => { handler(e.payload.data) })
+        }),
+        listen<IStreamEvent<{ output: string }>>(`chat:stream-done:${requestId}`, (e) => {
+          resolveDone(e.payload.data)
+        }),
+        listen<IStreamEvent<{ error: string }>>(`chat:stream-failed:${requestId}`, (e) => {
+          rejectDone(new Error(e.payload.data.error))
+        }),
+      ])
+      try {
+        await invoke('chat_send', {
+          message,
+          requestId,
+          ...(options?.apiKey ? { apiKey: options.apiKey } : {}),
+          ...(options?.provider ? { provider: options.provider } : {}),
+          ...(options?.model ? { model: options.model } : {}),
+        })
+        return await done
+      } finally {
+        unlisten.forEach((fn) => { fn() })
+      }
+    },
 
     onStream: (handler) => {
-      let unlisten: (() => void) | null = null
-      void import('@tauri-apps/api/event').then(({ listen }) =>
-        listen<string>('chat:stream-chunk', (e) => { handler(e.payload) }),
-      ).then((fn) => { unlisten = fn })
-      return () => { unlisten?.() }
+      chatStreamHandlers.add(handler)
+      return () => { chatStreamHandlers.delete(handler) }
     },
   },
 
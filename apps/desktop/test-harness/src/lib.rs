@@ -0,0 +1,20 @@
+//! End-to-end test harness: mock OpenAI-compatible, Anthropic and Gemini SSE
+//! servers and a fake cloud gateway, plus a runtime [`ModelProvider`] that
+//! talks to them over real HTTP.
+//!
+//! ```no_run
+//! use agenthub_test_harness::{mocks, MockServer, Protocol, StreamingProvider};
+//!
+//! let server = MockServer::start(mocks::openai(mocks::openai_stream(&["Hel", "lo"])));
+//! let provider = StreamingProvider::new(Protocol::OpenAi, server.url(), "sk-test");
+//! // … run skills against `provider`, then inspect `server.requests()`.
+//! ```
+//!
+//! [`ModelProvider`]: agenthub_runtime::provider::ModelProvider
+
+pub mod mocks;
+pub mod provider;
+pub mod server;
+
+pub use provider::{Protocol, StreamingProvider};
+pub use server::{MockServer, RecordedRequest, Reply, Route, SseEvent};
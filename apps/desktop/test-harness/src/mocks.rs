@@ -0,0 +1,128 @@
+//! Canned replies in each upstream's wire format, matching what the desktop
+//! app sends and parses: OpenAI-compatible `/chat/completions`, Anthropic
+//! `/messages`, Gemini `streamGenerateContent` and the cloud gateway.
+
+use serde_json::json;
+
+use crate::server::{Reply, Route, SseEvent};
+
+/// An OpenAI-style stream of `chunks`, ending with `[DONE]`.
+pub fn openai_stream(chunks: &[&str]) -> Vec<SseEvent> {
+    let mut events: Vec<SseEvent> = chunks
+        .iter()
+        .map(|c| SseEvent::Data(json!({ "choices": [{ "index": 0, "delta": { "content": c } }] }).to_string()))
+        .collect();
+    events.push(SseEvent::Data(
+        json!({
+            "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": chunks.len() },
+        })
+        .to_string(),
+    ));
+    events.push(SseEvent::Data("[DONE]".into()));
+    events
+}
+
+/// An Anthropic `/messages` stream of `chunks`, with its named events.
+pub fn anthropic_stream(chunks: &[&str]) -> Vec<SseEvent> {
+    let event = |event: &str, data: serde_json::Value| SseEvent::Event {
+        event: event.into(),
+        data: data.to_string(),
+    };
+    let mut events = vec![
+        event(
+            "message_start",
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 12, "output_tokens": 0 } } }),
+        ),
+        event(
+            "content_block_start",
+            json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        ),
+    ];
+    events.extend(chunks.iter().map(|c| {
+        event(
+            "content_block_delta",
+            json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": c } }),
+        )
+    }));
+    events.push(event("content_block_stop", json!({ "type": "content_block_stop", "index": 0 })));
+    events.push(event(
+        "message_delta",
+        json!({ "type": "message_delta", "usage": { "output_tokens": chunks.len() } }),
+    ));
+    events.push(event("message_stop", json!({ "type": "message_stop" })));
+    events
+}
+
+/// A Gemini `streamGenerateContent?alt=sse` stream of `chunks`.
+pub fn gemini_stream(chunks: &[&str]) -> Vec<SseEvent> {
+    chunks
+        .iter()
+        .map(|c| {
+            SseEvent::Data(
+                json!({
+                    "candidates": [{ "content": { "role": "model", "parts": [{ "text": c }] } }],
+                    "usageMetadata": { "promptTokenCount": 12, "candidatesTokenCount": 1 },
+                })
+                .to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The gateway's `/v1/ai/stream` format: raw text chunks, then `[DONE]`.
+pub fn gateway_stream(chunks: &[&str]) -> Vec<SseEvent> {
+    let mut events: Vec<SseEvent> = chunks.iter().map(|c| SseEvent::Data((*c).into())).collect();
+    events.push(SseEvent::Data("[DONE]".into()));
+    events
+}
+
+/// Inserts a keep-alive comment and a line of invalid JSON after the first
+/// event — clients must skip both.
+pub fn with_malformed_chunk(mut events: Vec<SseEvent>) -> Vec<SseEvent> {
+    let at = 1.min(events.len());
+    events.insert(at, SseEvent::Raw(": keep-alive\n\n".into()));
+    events.insert(at + 1, SseEvent::Data("{\"choices\": [{\"delta\": ".into()));
+    events
+}
+
+/// Cuts the stream after `keep` events without ending it.
+pub fn aborted_after(mut events: Vec<SseEvent>, keep: usize) -> Vec<SseEvent> {
+    events.truncate(keep);
+    events.push(SseEvent::Abort);
+    events
+}
+
+/// An OpenAI-compatible provider.
+pub fn openai(events: Vec<SseEvent>) -> Vec<Route> {
+    vec![Route::post("/chat/completions", Reply::Sse(events))]
+}
+
+/// The Anthropic API.
+pub fn anthropic(events: Vec<SseEvent>) -> Vec<Route> {
+    vec![Route::post("/messages", Reply::Sse(events))]
+}
+
+/// The Gemini API, for any model.
+pub fn gemini(events: Vec<SseEvent>) -> Vec<Route> {
+    vec![Route::post("/models/", Reply::Sse(events))]
+}
+
+/// The cloud gateway: `/v1/ai/stream`, `/v1/ai/generate` and `/v1/usage`,
+/// each requiring `token` as the bearer token.
+pub fn gateway(token: &str, chunks: &[&str]) -> Vec<Route> {
+    let output: String = chunks.concat();
+    vec![
+        Route::post("/v1/ai/stream", Reply::Sse(gateway_stream(chunks))).with_bearer(token),
+        Route::post(
+            "/v1/ai/generate",
+            Reply::json(json!({ "output": output, "input_tokens": 12, "output_tokens": chunks.len() })),
+        )
+        .with_bearer(token),
+        Route::get(
+            "/v1/usage",
+            Reply::json(json!({ "tokens_used": 0, "tokens_limit": 100000, "plan": "free" })),
+        )
+        .with_bearer(token),
+    ]
+}
@@ -0,0 +1,148 @@
+//! A runtime [`ModelProvider`] that streams from a (mock) upstream over HTTP,
+//! parsing each wire format the way the desktop app does: unparseable `data:`
+//! lines and comments are skipped, `[DONE]` ends the stream, and a body that
+//! breaks off mid-stream is an error.
+
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+use agenthub_runtime::provider::{LLMRequest, ModelProvider, ModelResponse, ProviderError, TokenUsage};
+use agenthub_runtime::token_optimizer::estimate_tokens;
+use serde_json::json;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wire format of the upstream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    OpenAi,
+    Anthropic,
+    Gemini,
+    /// The cloud gateway's `/v1/ai/stream`.
+    Gateway,
+}
+
+pub struct StreamingProvider {
+    protocol: Protocol,
+    base_url: String,
+    api_key: String,
+    http: reqwest::blocking::Client,
+}
+
+impl StreamingProvider {
+    pub fn new(protocol: Protocol, base_url: impl Into<String>, api_key: &str) -> Self {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("http client");
+        Self {
+            protocol,
+            base_url: base_url.into(),
+            api_key: api_key.to_owned(),
+            http,
+        }
+    }
+
+    fn request(&self, req: &LLMRequest) -> reqwest::blocking::RequestBuilder {
+        let (system, user) = (req.system_prompt.as_ref(), req.user_content.as_str());
+        match self.protocol {
+            Protocol::OpenAi => self
+                .http
+                .post(format!("{}/chat/completions", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&json!({
+                    "model": req.model.as_ref(),
+                    "stream": true,
+                    "max_tokens": req.max_tokens,
+                    "messages": [
+                        { "role": "system", "content": system },
+                        { "role": "user", "content": user },
+                    ],
+                })),
+            Protocol::Anthropic => self
+                .http
+                .post(format!("{}/messages", self.base_url))
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", "2023-06-01")
+                .json(&json!({
+                    "model": req.model.as_ref(),
+                    "stream": true,
+                    "max_tokens": req.max_tokens,
+                    "system": system,
+                    "messages": [{ "role": "user", "content": user }],
+                })),
+            Protocol::Gemini => self
+                .http
+                .post(format!(
+                    "{}/models/{}:streamGenerateContent?key={}&alt=sse",
+                    self.base_url, req.model, self.api_key
+                ))
+                .json(&json!({
+                    "systemInstruction": { "parts": [{ "text": system }] },
+                    "contents": [{ "role": "user", "parts": [{ "text": user }] }],
+                })),
+            Protocol::Gateway => self
+                .http
+                .post(format!("{}/v1/ai/stream", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&json!({ "capability": "general-chat", "input": user, "model": req.model.as_ref() })),
+        }
+    }
+
+    /// The text delta of one `data:` payload, if it carries one.
+    fn extract(&self, data: &str) -> Option<String> {
+        if self.protocol == Protocol::Gateway {
+            return Some(data.to_owned());
+        }
+        let val: serde_json::Value = serde_json::from_str(data).ok()?;
+        let text = match self.protocol {
+            Protocol::OpenAi => val.pointer("/choices/0/delta/content"),
+            Protocol::Anthropic => val.pointer("/delta/text"),
+            Protocol::Gemini => val.pointer("/candidates/0/content/parts/0/text"),
+            Protocol::Gateway => None,
+        }?;
+        text.as_str().filter(|s| !s.is_empty()).map(String::from)
+    }
+}
+
+impl ModelProvider for StreamingProvider {
+    fn call_model(&self, request: LLMRequest) -> Result<ModelResponse, ProviderError> {
+        let resp = self
+            .request(&request)
+            .send()
+            .map_err(|e| ProviderError::CallFailed(e.to_string()))?;
+        let status = resp.status().as_u16();
+        if status != 200 {
+            let body = resp.text().unwrap_or_default();
+            return Err(ProviderError::CallFailed(format!("API error {status}: {body}")));
+        }
+
+        let mut content = String::new();
+        for line in BufReader::new(resp).lines() {
+            let line = line.map_err(|e| ProviderError::CallFailed(format!("stream aborted: {e}")))?;
+            // One optional space after the colon, as the SSE spec (and the app) reads it.
+            let Some(data) = line.strip_prefix("data:").map(|d| d.strip_prefix(' ').unwrap_or(d)) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                break;
+            }
+            if let Some(text) = self.extract(data) {
+                content.push_str(&text);
+            }
+        }
+
+        let prompt_tokens = estimate_tokens(&request.system_prompt) + estimate_tokens(&request.user_content);
+        let completion_tokens = estimate_tokens(&content);
+        Ok(ModelResponse {
+            content,
+            usage: TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+                cached_prompt_tokens: 0,
+            },
+            model: request.model,
+        })
+    }
+}
@@ -0,0 +1,260 @@
+//! A scripted HTTP/1.1 server on `127.0.0.1`.
+//!
+//! Each connection serves one request and is closed. Streaming replies use
+//! chunked transfer encoding, so a stream cut short with [`SseEvent::Abort`]
+//! is seen by the client as a broken body rather than a clean end.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// One step of a scripted SSE stream.
+#[derive(Clone, Debug)]
+pub enum SseEvent {
+    /// `data: …` followed by a blank line.
+    Data(String),
+    /// `event: …` and `data: …`, as Anthropic sends them.
+    Event { event: String, data: String },
+    /// Written as-is — for malformed lines and keep-alive comments.
+    Raw(String),
+    Pause(Duration),
+    /// Closes the connection without ending the chunked body.
+    Abort,
+}
+
+/// What a route answers.
+#[derive(Clone, Debug)]
+pub enum Reply {
+    Json { status: u16, body: serde_json::Value },
+    Sse(Vec<SseEvent>),
+}
+
+impl Reply {
+    pub fn json(body: serde_json::Value) -> Self {
+        Reply::Json { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Reply::Json {
+            status,
+            body: serde_json::json!({ "error": { "message": message } }),
+        }
+    }
+}
+
+/// Answers requests whose method matches and whose path (without the query)
+/// starts with `path`. An optional bearer token must be presented.
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub method: &'static str,
+    pub path: String,
+    pub bearer: Option<String>,
+    pub reply: Reply,
+}
+
+impl Route {
+    pub fn post(path: &str, reply: Reply) -> Self {
+        Self { method: "POST", path: path.to_owned(), bearer: None, reply }
+    }
+
+    pub fn get(path: &str, reply: Reply) -> Self {
+        Self { method: "GET", path: path.to_owned(), bearer: None, reply }
+    }
+
+    pub fn with_bearer(mut self, token: &str) -> Self {
+        self.bearer = Some(token.to_owned());
+        self
+    }
+}
+
+/// A request the server received.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path including the query string.
+    pub path: String,
+    /// Header names are lower-cased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The body parsed as JSON; `Null` when it isn't JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+}
+
+/// A running mock server; stopped on drop.
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let routes = Arc::new(routes);
+
+        let thread = {
+            let (requests, stop) = (requests.clone(), stop.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let (routes, requests) = (routes.clone(), requests.clone());
+                    thread::spawn(move || {
+                        let _ = serve(stream, &routes, &requests);
+                    });
+                }
+            })
+        };
+        Self { addr, requests, stop, thread: Some(thread) }
+    }
+
+    /// Base URL, e.g. `http://127.0.0.1:54321`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    routes: &[Route],
+    requests: &Mutex<Vec<RecordedRequest>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(request) = read_request(&mut reader)? else {
+        return Ok(());
+    };
+    requests.lock().unwrap_or_else(|e| e.into_inner()).push(request.clone());
+
+    let mut out = stream;
+    let path = request.path.split('?').next().unwrap_or_default();
+    let Some(route) = routes
+        .iter()
+        .find(|r| r.method == request.method && path.starts_with(&r.path))
+    else {
+        return write_json(&mut out, 404, &serde_json::json!({ "error": { "message": "no route" } }));
+    };
+    if let Some(token) = &route.bearer {
+        if request.header("authorization") != Some(&format!("Bearer {token}")) {
+            return write_json(&mut out, 401, &serde_json::json!({ "error": { "message": "unauthorized" } }));
+        }
+    }
+    match &route.reply {
+        Reply::Json { status, body } => write_json(&mut out, *status, body),
+        Reply::Sse(events) => write_sse(&mut out, events),
+    }
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> std::io::Result<Option<RecordedRequest>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+
+    let mut headers = Vec::new();
+    let mut header_bytes = 0;
+    loop {
+        line.clear();
+        header_bytes += reader.read_line(&mut line)?;
+        if line.trim_end().is_empty() || header_bytes > MAX_HEADER_BYTES {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+        }
+    }
+    let len = headers
+        .iter()
+        .find(|(n, _)| n == "content-length")
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(RecordedRequest { method, path, headers, body }))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        _ => "Status",
+    }
+}
+
+fn write_json(out: &mut TcpStream, status: u16, body: &serde_json::Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    write!(
+        out,
+        "HTTP/1.1 {status} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    )?;
+    out.flush()
+}
+
+fn write_chunk(out: &mut TcpStream, data: &str) -> std::io::Result<()> {
+    write!(out, "{:x}\r\n{data}\r\n", data.len())?;
+    out.flush()
+}
+
+fn write_sse(out: &mut TcpStream, events: &[SseEvent]) -> std::io::Result<()> {
+    write!(
+        out,
+        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n"
+    )?;
+    for event in events {
+        match event {
+            SseEvent::Data(data) => write_chunk(out, &format!("data: {data}\n\n"))?,
+            SseEvent::Event { event, data } => write_chunk(out, &format!("event: {event}\ndata: {data}\n\n"))?,
+            SseEvent::Raw(raw) => write_chunk(out, raw)?,
+            SseEvent::Pause(d) => thread::sleep(*d),
+            SseEvent::Abort => return out.shutdown(std::net::Shutdown::Both),
+        }
+    }
+    write!(out, "0\r\n\r\n")?;
+    out.flush()
+}
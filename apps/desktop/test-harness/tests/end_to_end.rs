@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use agenthub_runtime::provider::{LLMRequest, ModelProvider, ProviderError};
+use agenthub_runtime::skill::{JsonSchema, ResponseMode, SkillDefinition, SkillExecutionMode};
+use agenthub_runtime::skill_executor::{SkillExecError, SkillExecutor};
+use agenthub_test_harness::{mocks, MockServer, Protocol, Reply, Route, SseEvent, StreamingProvider};
+use serde_json::json;
+
+fn answer_skill() -> SkillDefinition {
+    SkillDefinition {
+        id: "answer".into(),
+        input_schema: JsonSchema::new(json!({
+            "type": "object",
+            "properties": { "question": { "type": "string" } },
+            "required": ["question"],
+        })),
+        output_schema: JsonSchema::new(json!({
+            "type": "object",
+            "properties": { "answer": { "type": "string" } },
+            "required": ["answer"],
+        })),
+        execution_mode: SkillExecutionMode::LLM,
+        max_output_tokens: 200,
+        compact_keys: None,
+        prompt_variants: Vec::new(),
+    }
+}
+
+/// The JSON reply `{"answer":"42"}`, split across stream chunks.
+const ANSWER_CHUNKS: &[&str] = &["{\"ans", "wer\": ", "\"42\"}"];
+
+fn run_skill(provider: &dyn ModelProvider) -> Result<serde_json::Value, SkillExecError> {
+    let mut executor = SkillExecutor::new();
+    let prompt: Arc<str> = Arc::from("Answer as JSON.");
+    let model: Arc<str> = Arc::from("test-model");
+    executor
+        .execute(
+            &answer_skill(),
+            &json!({ "question": "meaning of life" }),
+            ResponseMode::StrictJson,
+            provider,
+            &prompt,
+            &model,
+        )
+        .map(|r| r.output)
+}
+
+fn request(text: &str) -> LLMRequest {
+    LLMRequest {
+        system_prompt: Arc::from("Be brief."),
+        user_content: text.into(),
+        max_tokens: 50,
+        model: Arc::from("test-model"),
+    }
+}
+
+#[test]
+fn runs_a_skill_against_each_provider() {
+    let cases = [
+        (Protocol::OpenAi, mocks::openai(mocks::openai_stream(ANSWER_CHUNKS))),
+        (Protocol::Anthropic, mocks::anthropic(mocks::anthropic_stream(ANSWER_CHUNKS))),
+        (Protocol::Gemini, mocks::gemini(mocks::gemini_stream(ANSWER_CHUNKS))),
+    ];
+    for (protocol, routes) in cases {
+        let server = MockServer::start(routes);
+        let provider = StreamingProvider::new(protocol, server.url(), "sk-test");
+        let output = run_skill(&provider).unwrap_or_else(|e| panic!("{protocol:?}: {e}"));
+        assert_eq!(output, json!({ "answer": "42" }), "{protocol:?}");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1, "{protocol:?}");
+        let body = requests[0].json();
+        assert!(body.to_string().contains("meaning of life"), "{protocol:?}: {body}");
+    }
+}
+
+#[test]
+fn sends_provider_credentials() {
+    let server = MockServer::start(mocks::anthropic(mocks::anthropic_stream(&["hi"])));
+    StreamingProvider::new(Protocol::Anthropic, server.url(), "sk-ant")
+        .call_model(request("hello"))
+        .expect("anthropic call");
+    let req = &server.requests()[0];
+    assert_eq!(req.header("x-api-key"), Some("sk-ant"));
+    assert_eq!(req.json()["system"], "Be brief.");
+
+    let server = MockServer::start(mocks::gemini(mocks::gemini_stream(&["hi"])));
+    StreamingProvider::new(Protocol::Gemini, server.url(), "g-key")
+        .call_model(request("hello"))
+        .expect("gemini call");
+    let req = &server.requests()[0];
+    assert!(req.path.starts_with("/models/test-model:streamGenerateContent?key=g-key"), "{}", req.path);
+}
+
+#[test]
+fn skips_malformed_chunks_and_keep_alives() {
+    let server = MockServer::start(mocks::openai(mocks::with_malformed_chunk(mocks::openai_stream(
+        ANSWER_CHUNKS,
+    ))));
+    let provider = StreamingProvider::new(Protocol::OpenAi, server.url(), "sk-test");
+    assert_eq!(run_skill(&provider).expect("skill output"), json!({ "answer": "42" }));
+}
+
+#[test]
+fn aborted_stream_is_a_provider_error() {
+    let events = mocks::aborted_after(mocks::openai_stream(ANSWER_CHUNKS), 2);
+    let server = MockServer::start(mocks::openai(events));
+    let provider = StreamingProvider::new(Protocol::OpenAi, server.url(), "sk-test");
+    match run_skill(&provider) {
+        Err(SkillExecError::Provider(ProviderError::CallFailed(msg))) => {
+            assert!(msg.contains("stream aborted"), "{msg}");
+        }
+        other => panic!("expected an aborted stream, got {other:?}"),
+    }
+}
+
+#[test]
+fn slow_chunks_still_arrive_in_order() {
+    let mut events = mocks::openai_stream(&["a", "b"]);
+    events.insert(1, SseEvent::Pause(Duration::from_millis(50)));
+    let server = MockServer::start(mocks::openai(events));
+    let reply = StreamingProvider::new(Protocol::OpenAi, server.url(), "sk-test")
+        .call_model(request("hello"))
+        .expect("slow stream");
+    assert_eq!(reply.content, "ab");
+}
+
+#[test]
+fn error_status_surfaces_the_body() {
+    let server = MockServer::start(vec![Route::post(
+        "/chat/completions",
+        Reply::error(400, "This model's maximum context length is 8192 tokens"),
+    )]);
+    let err = StreamingProvider::new(Protocol::OpenAi, server.url(), "sk-test")
+        .call_model(request("hello"))
+        .expect_err("400 should fail");
+    let msg = err.to_string();
+    assert!(msg.contains("API error 400") && msg.contains("maximum context length"), "{msg}");
+}
+
+#[test]
+fn gateway_requires_the_access_token() {
+    let server = MockServer::start(mocks::gateway("tok-1", &["Hello", " there"]));
+
+    let err = StreamingProvider::new(Protocol::Gateway, server.url(), "wrong")
+        .call_model(request("hi"))
+        .expect_err("bad token should fail");
+    assert!(err.to_string().contains("401"), "{err}");
+
+    let reply = StreamingProvider::new(Protocol::Gateway, server.url(), "tok-1")
+        .call_model(request("hi"))
+        .expect("gateway stream");
+    assert_eq!(reply.content, "Hello there");
+    assert_eq!(server.requests()[1].json()["capability"], "general-chat");
+}
+
+#[test]
+fn unknown_routes_are_not_found() {
+    let server = MockServer::start(mocks::gateway("tok-1", &["x"]));
+    let resp = reqwest::blocking::get(format!("{}/v1/nowhere", server.url())).expect("request");
+    assert_eq!(resp.status().as_u16(), 404);
+
+    let usage: serde_json::Value = reqwest::blocking::Client::new()
+        .get(format!("{}/v1/usage", server.url()))
+        .bearer_auth("tok-1")
+        .send()
+        .and_then(|r| r.json())
+        .expect("usage");
+    assert_eq!(usage["plan"], "free");
+}
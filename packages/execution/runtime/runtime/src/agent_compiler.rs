@@ -168,6 +168,7 @@ mod tests {
                 execution_mode: SkillExecutionMode::LLM,
                 max_output_tokens: 500,
                 compact_keys: None,
+                prompt_variants: Vec::new(),
             },
             SkillDefinition {
                 id: "summarize".into(),
@@ -176,6 +177,7 @@ mod tests {
                 execution_mode: SkillExecutionMode::LLM,
                 max_output_tokens: 300,
                 compact_keys: None,
+                prompt_variants: Vec::new(),
             },
         ];
 
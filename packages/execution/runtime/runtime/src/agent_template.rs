@@ -16,6 +16,7 @@ pub struct AgentTemplate {
     pub output_schema: serde_json::Value,
 }
 
+#[derive(Default)]
 pub struct TemplateRegistry {
     templates: AHashMap<String, AgentTemplate>,
 }
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Skill output fields larger than this (8 KB serialized) are moved into the
+/// artifact store and replaced by a reference.
+pub const DEFAULT_INLINE_LIMIT_BYTES: usize = 8 * 1024;
+
+/// Key marking a JSON object as an artifact reference.
+pub const ARTIFACT_REF_KEY: &str = "$artifact";
+
+const SUMMARY_MAX_ITEMS: usize = 3;
+const SUMMARY_MAX_STRING: usize = 120;
+const SUMMARY_MAX_DEPTH: usize = 3;
+const SUMMARY_MAX_BYTES: usize = 1024;
+
+/// A by-reference handle to a stored artifact. Serialized into skill inputs
+/// in place of the full value, so prompts only carry the compact summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactRef {
+    /// Hex SHA-256 of the serialized content.
+    #[serde(rename = "$artifact")]
+    pub id: String,
+    pub size_bytes: usize,
+    /// Shape-preserving preview: leading items, truncated strings.
+    pub summary: serde_json::Value,
+}
+
+impl ArtifactRef {
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    /// Parses a reference previously produced by [`ArtifactRef::to_value`].
+    pub fn from_value(value: &serde_json::Value) -> Option<Self> {
+        value.get(ARTIFACT_REF_KEY)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
+/// Content-addressed store for large skill outputs. Identical content is
+/// stored once.
+pub struct ArtifactStore {
+    items: AHashMap<String, Arc<serde_json::Value>>,
+    inline_limit: usize,
+}
+
+impl Default for ArtifactStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArtifactStore {
+    pub fn new() -> Self {
+        Self::with_inline_limit(DEFAULT_INLINE_LIMIT_BYTES)
+    }
+
+    pub fn with_inline_limit(inline_limit: usize) -> Self {
+        Self {
+            items: AHashMap::new(),
+            inline_limit,
+        }
+    }
+
+    pub fn put(&mut self, value: serde_json::Value) -> ArtifactRef {
+        let bytes = serde_json::to_vec(&value).unwrap_or_default();
+        let id = hex(&Sha256::digest(&bytes));
+        let reference = ArtifactRef {
+            id: id.clone(),
+            size_bytes: bytes.len(),
+            summary: summarize(&value),
+        };
+        self.items.entry(id).or_insert_with(|| Arc::new(value));
+        reference
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<serde_json::Value>> {
+        self.items.get(id).cloned()
+    }
+
+    /// Replaces `value` with the full artifact when it is a reference.
+    pub fn resolve(&self, value: &serde_json::Value) -> Option<Arc<serde_json::Value>> {
+        ArtifactRef::from_value(value).and_then(|r| self.get(&r.id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.items
+            .values()
+            .map(|v| serde_json::to_vec(v.as_ref()).map(|b| b.len()).unwrap_or(0))
+            .sum()
+    }
+
+    /// Moves oversized parts of a skill output into the store. Object fields
+    /// are externalized individually so field-level dependencies keep working;
+    /// an object made of many small fields is stored whole.
+    pub fn externalize(&mut self, value: serde_json::Value) -> serde_json::Value {
+        let limit = self.inline_limit;
+        match value {
+            serde_json::Value::Object(obj) => {
+                let mut out = serde_json::Map::with_capacity(obj.len());
+                let mut moved = false;
+                for (k, v) in obj {
+                    let v = if serialized_len(&v) > limit {
+                        moved = true;
+                        self.put(v).to_value()
+                    } else {
+                        v
+                    };
+                    out.insert(k, v);
+                }
+                let out = serde_json::Value::Object(out);
+                if !moved && serialized_len(&out) > limit {
+                    self.put(out).to_value()
+                } else {
+                    out
+                }
+            }
+            other if serialized_len(&other) > limit => self.put(other).to_value(),
+            other => other,
+        }
+    }
+}
+
+fn serialized_len(value: &serde_json::Value) -> usize {
+    serde_json::to_vec(value).map(|b| b.len()).unwrap_or(0)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Builds a compact preview that keeps the value's shape.
+pub fn summarize(value: &serde_json::Value) -> serde_json::Value {
+    let summary = summarize_at(value, 0);
+    if serialized_len(&summary) <= SUMMARY_MAX_BYTES {
+        return summary;
+    }
+    match value {
+        serde_json::Value::Object(obj) => serde_json::json!({
+            "keys": obj.keys().take(32).collect::<Vec<_>>(),
+        }),
+        serde_json::Value::Array(arr) => serde_json::json!({ "items": arr.len() }),
+        _ => summarize_at(value, SUMMARY_MAX_DEPTH),
+    }
+}
+
+fn summarize_at(value: &serde_json::Value, depth: usize) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) if s.len() > SUMMARY_MAX_STRING => {
+            let mut end = SUMMARY_MAX_STRING;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            serde_json::Value::String(format!("{}… ({} bytes)", &s[..end], s.len()))
+        }
+        serde_json::Value::Array(arr) if depth >= SUMMARY_MAX_DEPTH => {
+            serde_json::Value::String(format!("[{} items]", arr.len()))
+        }
+        serde_json::Value::Object(obj) if depth >= SUMMARY_MAX_DEPTH => {
+            serde_json::Value::String(format!("{{{} keys}}", obj.len()))
+        }
+        serde_json::Value::Array(arr) => {
+            let mut items: Vec<serde_json::Value> = arr
+                .iter()
+                .take(SUMMARY_MAX_ITEMS)
+                .map(|v| summarize_at(v, depth + 1))
+                .collect();
+            if arr.len() > SUMMARY_MAX_ITEMS {
+                items.push(serde_json::Value::String(format!(
+                    "… {} more",
+                    arr.len() - SUMMARY_MAX_ITEMS
+                )));
+            }
+            serde_json::Value::Array(items)
+        }
+        serde_json::Value::Object(obj) => serde_json::Value::Object(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), summarize_at(v, depth + 1)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dataset(n: usize) -> serde_json::Value {
+        json!((0..n).map(|i| json!({"row": i, "text": "lorem ipsum dolor"})).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn small_outputs_stay_inline() {
+        let mut store = ArtifactStore::new();
+        let v = json!({"summary": "short"});
+        assert_eq!(store.externalize(v.clone()), v);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn large_fields_become_references() {
+        let mut store = ArtifactStore::with_inline_limit(256);
+        let out = store.externalize(json!({"results": dataset(100), "count": 100}));
+        assert_eq!(out["count"], 100);
+        let reference = ArtifactRef::from_value(&out["results"]).expect("should be a ref");
+        assert!(reference.size_bytes > 256);
+        assert_eq!(reference.summary.as_array().map(|a| a.len()), Some(SUMMARY_MAX_ITEMS + 1));
+        let full = store.resolve(&out["results"]).expect("should resolve");
+        assert_eq!(full.as_array().map(|a| a.len()), Some(100));
+    }
+
+    #[test]
+    fn identical_content_is_stored_once() {
+        let mut store = ArtifactStore::with_inline_limit(64);
+        let a = store.put(dataset(50));
+        let b = store.put(dataset(50));
+        assert_eq!(a.id, b.id);
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use crate::agent_compiler::{AgentCompiler, UserAgentConfig};
+use crate::agent_template::{AgentTemplate, TemplateRegistry};
+use crate::skill::SkillDefinition;
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+pub const RUNTIME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A shareable agent: its config plus everything it references, so it
+/// compiles on another install without that install having the same
+/// templates or skills registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentBundle {
+    pub format_version: u32,
+    /// Runtime that produced the bundle; the major version must match.
+    pub runtime_version: String,
+    pub agent: UserAgentConfig,
+    pub template: AgentTemplate,
+    /// `AgentTemplate::system_instruction` is not serialized, so it travels here.
+    pub system_instruction: String,
+    pub skills: Vec<SkillDefinition>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("unsupported bundle format {found} (this runtime reads up to {supported})")]
+    UnsupportedFormat { found: u32, supported: u32 },
+    #[error("bundle built for runtime {found}, incompatible with {current}")]
+    IncompatibleRuntime { found: String, current: String },
+    #[error("template mismatch: agent uses '{agent}', bundle carries '{bundled}'")]
+    TemplateMismatch { agent: String, bundled: String },
+    #[error("skill '{0}' is selected but not included in the bundle")]
+    MissingSkill(String),
+    #[error("bundled agent does not compile: {0}")]
+    Compile(String),
+    #[error("malformed bundle: {0}")]
+    Malformed(String),
+}
+
+impl AgentBundle {
+    /// Collects the template and the selected skills out of `skill_defs`.
+    pub fn new(
+        agent: UserAgentConfig,
+        template: &AgentTemplate,
+        skill_defs: &[SkillDefinition],
+    ) -> Result<Self, BundleError> {
+        let skills = agent
+            .selected_skills
+            .iter()
+            .map(|id| {
+                skill_defs
+                    .iter()
+                    .find(|s| s.id == *id)
+                    .cloned()
+                    .ok_or_else(|| BundleError::MissingSkill(id.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bundle = Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            runtime_version: RUNTIME_VERSION.to_owned(),
+            system_instruction: template.system_instruction.to_string(),
+            template: template.clone(),
+            agent,
+            skills,
+        };
+        bundle.validate()?;
+        Ok(bundle)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, BundleError> {
+        serde_json::from_slice(bytes).map_err(|e| BundleError::Malformed(e.to_string()))
+    }
+
+    /// Deterministic serialization; this is what gets signed.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// The template with its system instruction restored.
+    pub fn template(&self) -> AgentTemplate {
+        AgentTemplate {
+            system_instruction: Arc::from(self.system_instruction.as_str()),
+            ..self.template.clone()
+        }
+    }
+
+    /// Checks the bundle is readable by this runtime and that the agent
+    /// compiles against the bundled template and skills alone.
+    pub fn validate(&self) -> Result<(), BundleError> {
+        if self.format_version == 0 || self.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedFormat {
+                found: self.format_version,
+                supported: BUNDLE_FORMAT_VERSION,
+            });
+        }
+        if major(&self.runtime_version) != major(RUNTIME_VERSION) {
+            return Err(BundleError::IncompatibleRuntime {
+                found: self.runtime_version.clone(),
+                current: RUNTIME_VERSION.to_owned(),
+            });
+        }
+        if self.agent.base_template != self.template.id {
+            return Err(BundleError::TemplateMismatch {
+                agent: self.agent.base_template.clone(),
+                bundled: self.template.id.clone(),
+            });
+        }
+        if let Some(missing) = self
+            .agent
+            .selected_skills
+            .iter()
+            .find(|id| !self.skills.iter().any(|s| s.id == **id))
+        {
+            return Err(BundleError::MissingSkill(missing.clone()));
+        }
+
+        let mut registry = TemplateRegistry::new();
+        registry.register(self.template());
+        AgentCompiler::compile(&self.agent, &registry, &self.skills)
+            .map(|_| ())
+            .map_err(|e| BundleError::Compile(e.to_string()))
+    }
+}
+
+fn major(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryTier;
+    use crate::skill::{JsonSchema, ResponseMode, SkillExecutionMode};
+    use serde_json::json;
+
+    fn template() -> AgentTemplate {
+        AgentTemplate {
+            id: "research".into(),
+            allowed_skills: vec!["search".into()],
+            default_memory_tier: MemoryTier::Delta,
+            response_mode: ResponseMode::StrictJson,
+            max_budget: 5000,
+            system_instruction: Arc::from("Research agent."),
+            output_schema: json!({"type": "object"}),
+        }
+    }
+
+    fn skills() -> Vec<SkillDefinition> {
+        vec![SkillDefinition {
+            id: "search".into(),
+            input_schema: JsonSchema::new(json!({"type": "object"})),
+            output_schema: JsonSchema::new(json!({"type": "object"})),
+            execution_mode: SkillExecutionMode::LLM,
+            max_output_tokens: 500,
+            compact_keys: None,
+            prompt_variants: Vec::new(),
+        }]
+    }
+
+    fn config(skills: Vec<&str>) -> UserAgentConfig {
+        UserAgentConfig {
+            name: "shared".into(),
+            base_template: "research".into(),
+            selected_skills: skills.into_iter().map(String::from).collect(),
+            memory_tier_override: None,
+            budget_limit: None,
+            skill_dependencies: vec![],
+        }
+    }
+
+    #[test]
+    fn round_trips_with_system_instruction() {
+        let bundle = AgentBundle::new(config(vec!["search"]), &template(), &skills())
+            .expect("should bundle");
+        let restored = AgentBundle::from_json(&bundle.canonical_bytes()).expect("should parse");
+        assert!(restored.validate().is_ok());
+        assert_eq!(&*restored.template().system_instruction, "Research agent.");
+        assert_eq!(restored.canonical_bytes(), bundle.canonical_bytes());
+    }
+
+    #[test]
+    fn rejects_missing_skill() {
+        let err = AgentBundle::new(config(vec!["search", "delete"]), &template(), &skills());
+        assert!(matches!(err, Err(BundleError::MissingSkill(s)) if s == "delete"));
+    }
+
+    #[test]
+    fn rejects_incompatible_versions() {
+        let mut bundle = AgentBundle::new(config(vec!["search"]), &template(), &skills())
+            .expect("should bundle");
+        bundle.format_version = BUNDLE_FORMAT_VERSION + 1;
+        assert!(matches!(bundle.validate(), Err(BundleError::UnsupportedFormat { .. })));
+        bundle.format_version = BUNDLE_FORMAT_VERSION;
+        bundle.runtime_version = "999.0.0".into();
+        assert!(matches!(bundle.validate(), Err(BundleError::IncompatibleRuntime { .. })));
+    }
+}
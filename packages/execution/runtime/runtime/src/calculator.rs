@@ -0,0 +1,1148 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+
+use crate::skill::{JsonSchema, SkillDefinition, SkillExecutionMode};
+use crate::skill_executor::{SkillExecError, SkillExecutor};
+
+/// ID of the built-in calculator skill.
+pub const CALCULATOR_SKILL_ID: &str = "calculate";
+
+const MAX_EXPRESSION_LEN: usize = 1024;
+const MAX_NESTING: usize = 64;
+const MAX_UNIT_POWER: f64 = 12.0;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Exponents of length, mass, time, temperature and information (bytes).
+type Dim = [i8; 5];
+
+const NONE: Dim = [0, 0, 0, 0, 0];
+const LENGTH: Dim = [1, 0, 0, 0, 0];
+const AREA: Dim = [2, 0, 0, 0, 0];
+const VOLUME: Dim = [3, 0, 0, 0, 0];
+const MASS: Dim = [0, 1, 0, 0, 0];
+const TIME: Dim = [0, 0, 1, 0, 0];
+const TEMPERATURE: Dim = [0, 0, 0, 1, 0];
+const DATA: Dim = [0, 0, 0, 0, 1];
+const SPEED: Dim = [1, 0, -1, 0, 0];
+
+const BASE_UNITS: [&str; 5] = ["m", "kg", "s", "K", "B"];
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum CalcError {
+    #[error("syntax error: {0}")]
+    Syntax(String),
+    #[error("unknown name '{0}'")]
+    UnknownName(String),
+    #[error("incompatible units: {0} and {1}")]
+    Incompatible(String, String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+    #[error("{0}")]
+    Domain(String),
+}
+
+/// An evaluated expression.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Calculation {
+    /// Numeric result expressed in `unit`; `None` for dates.
+    pub value: Option<f64>,
+    pub unit: Option<String>,
+    /// ISO 8601 result of date arithmetic.
+    pub date: Option<String>,
+    /// Human-readable result, e.g. `5.3 km` or `2024-03-01`.
+    pub text: String,
+}
+
+// ── Units ──────────────────────────────────────────────────────────────────────
+
+struct UnitDef {
+    names: &'static [&'static str],
+    /// SI value of one unit: `si = (value + offset) * factor`.
+    factor: f64,
+    offset: f64,
+    dim: Dim,
+    /// Calendar length for month / year units, so date arithmetic lands on
+    /// the same day of the month instead of an average-length offset.
+    months: i64,
+}
+
+const fn unit(names: &'static [&'static str], factor: f64, dim: Dim) -> UnitDef {
+    UnitDef { names, factor, offset: 0.0, dim, months: 0 }
+}
+
+const YEAR_SECONDS: f64 = 31_556_952.0; // Gregorian average
+
+static UNITS: &[UnitDef] = &[
+    // length
+    unit(&["m", "meter", "meters", "metre", "metres"], 1.0, LENGTH),
+    unit(&["km", "kilometer", "kilometers", "kilometre", "kilometres"], 1000.0, LENGTH),
+    unit(&["cm", "centimeter", "centimeters"], 0.01, LENGTH),
+    unit(&["mm", "millimeter", "millimeters"], 0.001, LENGTH),
+    unit(&["um", "micrometer", "micrometers"], 1e-6, LENGTH),
+    unit(&["nm", "nanometer", "nanometers"], 1e-9, LENGTH),
+    unit(&["mi", "mile", "miles"], 1609.344, LENGTH),
+    unit(&["yd", "yard", "yards"], 0.9144, LENGTH),
+    unit(&["ft", "foot", "feet"], 0.3048, LENGTH),
+    unit(&["in", "inch", "inches"], 0.0254, LENGTH),
+    unit(&["nmi"], 1852.0, LENGTH),
+    // area and volume
+    unit(&["ha", "hectare", "hectares"], 10_000.0, AREA),
+    unit(&["acre", "acres"], 4046.8564224, AREA),
+    unit(&["l", "L", "liter", "liters", "litre", "litres"], 0.001, VOLUME),
+    unit(&["ml", "mL", "milliliter", "milliliters"], 1e-6, VOLUME),
+    unit(&["gal", "gallon", "gallons"], 0.003785411784, VOLUME),
+    // mass
+    unit(&["kg", "kilogram", "kilograms"], 1.0, MASS),
+    unit(&["g", "gram", "grams"], 0.001, MASS),
+    unit(&["mg", "milligram", "milligrams"], 1e-6, MASS),
+    unit(&["t", "tonne", "tonnes"], 1000.0, MASS),
+    unit(&["lb", "lbs", "pound", "pounds"], 0.45359237, MASS),
+    unit(&["oz", "ounce", "ounces"], 0.028349523125, MASS),
+    // time
+    unit(&["s", "sec", "secs", "second", "seconds"], 1.0, TIME),
+    unit(&["ms", "millisecond", "milliseconds"], 0.001, TIME),
+    unit(&["min", "mins", "minute", "minutes"], 60.0, TIME),
+    unit(&["h", "hr", "hrs", "hour", "hours"], 3600.0, TIME),
+    unit(&["d", "day", "days"], 86_400.0, TIME),
+    unit(&["wk", "week", "weeks"], 604_800.0, TIME),
+    UnitDef { names: &["month", "months"], factor: YEAR_SECONDS / 12.0, offset: 0.0, dim: TIME, months: 1 },
+    UnitDef { names: &["yr", "year", "years"], factor: YEAR_SECONDS, offset: 0.0, dim: TIME, months: 12 },
+    // speed
+    unit(&["mph"], 0.44704, SPEED),
+    unit(&["kph", "kmh"], 1000.0 / 3600.0, SPEED),
+    unit(&["kn", "knot", "knots"], 1852.0 / 3600.0, SPEED),
+    // temperature
+    UnitDef { names: &["K", "kelvin"], factor: 1.0, offset: 0.0, dim: TEMPERATURE, months: 0 },
+    UnitDef { names: &["C", "°C", "celsius"], factor: 1.0, offset: 273.15, dim: TEMPERATURE, months: 0 },
+    UnitDef { names: &["F", "°F", "fahrenheit"], factor: 5.0 / 9.0, offset: 459.67, dim: TEMPERATURE, months: 0 },
+    // information
+    unit(&["B", "byte", "bytes"], 1.0, DATA),
+    unit(&["bit", "bits"], 0.125, DATA),
+    unit(&["KB", "kB"], 1e3, DATA),
+    unit(&["MB"], 1e6, DATA),
+    unit(&["GB"], 1e9, DATA),
+    unit(&["TB"], 1e12, DATA),
+    unit(&["KiB"], 1024.0, DATA),
+    unit(&["MiB"], 1_048_576.0, DATA),
+    unit(&["GiB"], 1_073_741_824.0, DATA),
+    unit(&["TiB"], 1_099_511_627_776.0, DATA),
+];
+
+fn find_unit(name: &str) -> Option<&'static UnitDef> {
+    UNITS.iter().find(|u| u.names.contains(&name))
+}
+
+/// The unit a quantity is shown in.
+#[derive(Debug, Clone)]
+struct DisplayUnit {
+    name: String,
+    factor: f64,
+    offset: f64,
+}
+
+impl DisplayUnit {
+    fn of(def: &UnitDef) -> Self {
+        Self { name: def.names[0].to_owned(), factor: def.factor, offset: def.offset }
+    }
+}
+
+// ── Values ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Quantity {
+    si: f64,
+    dim: Dim,
+    unit: Option<DisplayUnit>,
+    /// Set for literal month / year counts (`3 months`).
+    months: Option<i64>,
+}
+
+impl Quantity {
+    fn number(v: f64) -> Self {
+        Self { si: v, dim: NONE, unit: None, months: None }
+    }
+
+    fn has_offset(&self) -> bool {
+        self.unit.as_ref().is_some_and(|u| u.offset != 0.0)
+    }
+
+    fn displayed(&self) -> f64 {
+        match &self.unit {
+            Some(u) => self.si / u.factor - u.offset,
+            None => self.si,
+        }
+    }
+
+    fn with_displayed(mut self, v: f64) -> Self {
+        self.si = match &self.unit {
+            Some(u) => (v + u.offset) * u.factor,
+            None => v,
+        };
+        self.months = None;
+        self
+    }
+
+    fn describe(&self) -> String {
+        match &self.unit {
+            Some(u) => u.name.clone(),
+            None if self.dim == NONE => "a plain number".into(),
+            None => si_unit_name(self.dim),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Num(Quantity),
+    /// Seconds since the Unix epoch (UTC); `time` when a time of day was given.
+    Date { secs: i64, time: bool },
+}
+
+impl Value {
+    fn describe(&self) -> String {
+        match self {
+            Value::Num(q) => q.describe(),
+            Value::Date { .. } => "a date".into(),
+        }
+    }
+}
+
+fn si_unit_name(dim: Dim) -> String {
+    let part = |sign: i8| {
+        dim.iter()
+            .zip(BASE_UNITS)
+            .filter(|(e, _)| **e * sign > 0)
+            .map(|(e, name)| match e.abs() {
+                1 => name.to_owned(),
+                n => format!("{name}^{n}"),
+            })
+            .collect::<Vec<_>>()
+            .join("*")
+    };
+    let (num, den) = (part(1), part(-1));
+    match (num.is_empty(), den.is_empty()) {
+        (_, true) => num,
+        (true, false) => format!("1/{den}"),
+        (false, false) => format!("{num}/{den}"),
+    }
+}
+
+// ── Dates ──────────────────────────────────────────────────────────────────────
+
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn add_months(secs: i64, months: i64) -> i64 {
+    let days = secs.div_euclid(SECONDS_PER_DAY);
+    let time = secs.rem_euclid(SECONDS_PER_DAY);
+    let (y, m, d) = civil_from_days(days);
+    let index = y * 12 + (m - 1) + months;
+    let (y, m) = (index.div_euclid(12), index.rem_euclid(12) + 1);
+    let d = d.min(days_in_month(y, m));
+    days_from_civil(y, m, d) * SECONDS_PER_DAY + time
+}
+
+fn format_date(secs: i64, time: bool) -> String {
+    let (y, m, d) = civil_from_days(secs.div_euclid(SECONDS_PER_DAY));
+    if !time {
+        return format!("{y:04}-{m:02}-{d:02}");
+    }
+    let t = secs.rem_euclid(SECONDS_PER_DAY);
+    format!("{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z", t / 3600, t / 60 % 60, t % 60)
+}
+
+/// Parses `YYYY-MM-DD` with an optional `THH:MM[:SS]`.
+fn parse_date(s: &str) -> Result<i64, CalcError> {
+    let invalid = || CalcError::InvalidDate(s.to_owned());
+    let (date, time) = s.split_once('T').map_or((s, None), |(d, t)| (d, Some(t)));
+    let mut parts = date.split('-').map(|p| p.parse::<i64>().map_err(|_| invalid()));
+    let (y, m, d) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(y), Some(m), Some(d)) => (y?, m?, d?),
+        _ => return Err(invalid()),
+    };
+    if !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+        return Err(invalid());
+    }
+    let mut secs = days_from_civil(y, m, d) * SECONDS_PER_DAY;
+    if let Some(t) = time {
+        let fields: Vec<i64> = t
+            .split(':')
+            .map(|p| p.parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let (h, min, sec) = match fields.as_slice() {
+            [h, min] => (*h, *min, 0),
+            [h, min, sec] => (*h, *min, *sec),
+            _ => return Err(invalid()),
+        };
+        if h > 23 || min > 59 || sec > 59 {
+            return Err(invalid());
+        }
+        secs += h * 3600 + min * 60 + sec;
+    }
+    Ok(secs)
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// ── Lexer ──────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Date(String),
+    Op(char),
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '°'
+}
+
+fn looks_like_date(chars: &[char]) -> usize {
+    let digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    let is = |i: usize, c: char| chars.get(i) == Some(&c);
+    let date = (0..4).all(digit) && is(4, '-') && digit(5) && digit(6) && is(7, '-') && digit(8) && digit(9);
+    if !date || digit(10) {
+        return 0;
+    }
+    if is(10, 'T') && digit(11) && digit(12) && is(13, ':') && digit(14) && digit(15) {
+        if is(16, ':') && digit(17) && digit(18) {
+            return 19;
+        }
+        return 16;
+    }
+    10
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let date_len = looks_like_date(&chars[i..]);
+            if date_len > 0 {
+                tokens.push(Token::Date(chars[i..i + date_len].iter().collect()));
+                i += date_len;
+                continue;
+            }
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            // Exponent, only when digits follow (so `2e` stays `2 e`).
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let n = text
+                .parse::<f64>()
+                .map_err(|_| CalcError::Syntax(format!("invalid number '{text}'")))?;
+            tokens.push(Token::Num(n));
+        } else if is_ident_start(c) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if "+-*/^%(),×÷".contains(c) {
+            let op = match c {
+                '×' => '*',
+                '÷' => '/',
+                other => other,
+            };
+            tokens.push(Token::Op(op));
+            i += 1;
+        } else {
+            return Err(CalcError::Syntax(format!("unexpected character '{c}'")));
+        }
+    }
+    Ok(tokens)
+}
+
+// ── Parser / evaluator ─────────────────────────────────────────────────────────
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+fn is_conversion_keyword(name: &str) -> bool {
+    matches!(name, "to" | "in" | "as" | "into")
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn eat_op(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// `in` is the inch unit unless it introduces a conversion (`5 ft in m`).
+    fn at_conversion(&self) -> bool {
+        match self.peek_ident() {
+            Some("in") => matches!(
+                self.peek_at(1),
+                Some(Token::Ident(next)) if find_unit(next).is_some()
+            ),
+            Some(kw) => is_conversion_keyword(kw),
+            None => false,
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(CalcError::Syntax("expression is nested too deeply".into()));
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Value, CalcError> {
+        self.enter()?;
+        let mut value = self.additive()?;
+        if self.at_conversion() {
+            self.pos += 1;
+            let (target, dim) = self.unit_expression()?;
+            value = convert(value, target, dim)?;
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn additive(&mut self) -> Result<Value, CalcError> {
+        let mut left = self.term()?;
+        loop {
+            if self.eat_op('+') {
+                let right = self.term()?;
+                left = add(left, right, 1.0)?;
+            } else if self.eat_op('-') {
+                let right = self.term()?;
+                left = add(left, right, -1.0)?;
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, CalcError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('*')) => '*',
+                Some(Token::Op('/')) => '/',
+                Some(Token::Ident(s)) if s == "of" => '*',
+                Some(Token::Ident(s)) if s == "mod" => '%',
+                // Implicit multiplication: `2(3 + 4)`.
+                Some(Token::Op('(')) => {
+                    let right = self.unary()?;
+                    left = multiply(left, right)?;
+                    continue;
+                }
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = match op {
+                '*' => multiply(left, right)?,
+                '/' => divide(left, right)?,
+                _ => modulo(left, right)?,
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value, CalcError> {
+        if self.eat_op('-') {
+            self.enter()?;
+            let v = negate(self.unary()?)?;
+            self.depth -= 1;
+            Ok(v)
+        } else if self.eat_op('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Value, CalcError> {
+        let base = self.postfix()?;
+        if self.eat_op('^') {
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return power(base, exponent);
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<Value, CalcError> {
+        let mut value = self.primary()?;
+        // A unit right after a plain number belongs to it, so `5 in in cm`
+        // reads as inches converted to centimetres.
+        let plain = matches!(&value, Value::Num(q) if q.dim == NONE && q.unit.is_none());
+        if let Some(def) = self.peek_ident().and_then(find_unit).filter(|_| plain) {
+            self.pos += 1;
+            let power = self.unit_power()?;
+            value = apply_unit(value, def, power)?;
+        }
+        if self.eat_op('%') {
+            value = divide(value, Value::Num(Quantity::number(100.0)))?;
+        }
+        Ok(value)
+    }
+
+    /// Optional integer exponent after a unit (`km^2`, `s^-1`).
+    fn unit_power(&mut self) -> Result<i8, CalcError> {
+        if self.peek() != Some(&Token::Op('^')) {
+            return Ok(1);
+        }
+        let negative = self.peek_at(1) == Some(&Token::Op('-'));
+        let n_at = if negative { 2 } else { 1 };
+        let Some(Token::Num(n)) = self.peek_at(n_at).cloned() else {
+            return Ok(1);
+        };
+        if n.fract() != 0.0 || n > MAX_UNIT_POWER {
+            return Err(CalcError::Syntax(format!("unit power must be a small integer (got {n})")));
+        }
+        self.pos += n_at + 1;
+        Ok(if negative { -(n as i8) } else { n as i8 })
+    }
+
+    fn primary(&mut self) -> Result<Value, CalcError> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Value::Num(Quantity::number(n))),
+            Some(Token::Date(s)) => Ok(Value::Date { secs: parse_date(&s)?, time: s.contains('T') }),
+            Some(Token::Op('(')) => {
+                let v = self.expression()?;
+                if !self.eat_op(')') {
+                    return Err(CalcError::Syntax("missing ')'".into()));
+                }
+                Ok(v)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::Op('(')) {
+                    self.pos += 1;
+                    let args = self.arguments()?;
+                    return call(&name, args);
+                }
+                match name.as_str() {
+                    "pi" | "π" => Ok(Value::Num(Quantity::number(std::f64::consts::PI))),
+                    "e" => Ok(Value::Num(Quantity::number(std::f64::consts::E))),
+                    "tau" => Ok(Value::Num(Quantity::number(std::f64::consts::TAU))),
+                    "today" => Ok(Value::Date {
+                        secs: now_secs().div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY,
+                        time: false,
+                    }),
+                    "now" => Ok(Value::Date { secs: now_secs(), time: true }),
+                    _ => match find_unit(&name) {
+                        // A bare unit is one of it: `km/h`, `per hour`.
+                        Some(def) => {
+                            let power = self.unit_power()?;
+                            apply_unit(Value::Num(Quantity::number(1.0)), def, power)
+                        }
+                        None => Err(CalcError::UnknownName(name)),
+                    },
+                }
+            }
+            Some(Token::Op(op)) => Err(CalcError::Syntax(format!("unexpected '{op}'"))),
+            None => Err(CalcError::Syntax("unexpected end of expression".into())),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Value>, CalcError> {
+        let mut args = Vec::new();
+        if self.eat_op(')') {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.eat_op(')') {
+                return Ok(args);
+            }
+            if !self.eat_op(',') {
+                return Err(CalcError::Syntax("expected ',' or ')' in argument list".into()));
+            }
+        }
+    }
+
+    /// Target of a conversion: `km`, `km/h`, `m^2`, `kg*m/s^2`.
+    fn unit_expression(&mut self) -> Result<(DisplayUnit, Dim), CalcError> {
+        let (mut unit, mut dim) = self.unit_factor()?;
+        loop {
+            let divide = if self.eat_op('*') {
+                false
+            } else if self.eat_op('/') {
+                true
+            } else {
+                return Ok((unit, dim));
+            };
+            let (next, next_dim) = self.unit_factor()?;
+            if unit.offset != 0.0 || next.offset != 0.0 {
+                return Err(CalcError::Domain("temperature units can't be combined".into()));
+            }
+            let sep = if divide { '/' } else { '*' };
+            unit = DisplayUnit {
+                name: format!("{}{sep}{}", unit.name, next.name),
+                factor: if divide { unit.factor / next.factor } else { unit.factor * next.factor },
+                offset: 0.0,
+            };
+            dim = combine(dim, next_dim, if divide { -1 } else { 1 });
+        }
+    }
+
+    fn unit_factor(&mut self) -> Result<(DisplayUnit, Dim), CalcError> {
+        let name = match self.next() {
+            Some(Token::Ident(name)) => name,
+            other => return Err(CalcError::Syntax(format!("expected a unit, found {other:?}"))),
+        };
+        let def = find_unit(&name).ok_or(CalcError::UnknownName(name))?;
+        let power = self.unit_power()?;
+        Ok(powered(def, power))
+    }
+}
+
+fn combine(a: Dim, b: Dim, sign: i8) -> Dim {
+    let mut out = a;
+    for (o, e) in out.iter_mut().zip(b) {
+        *o += e * sign;
+    }
+    out
+}
+
+fn powered(def: &UnitDef, power: i8) -> (DisplayUnit, Dim) {
+    let mut dim = NONE;
+    for (d, e) in dim.iter_mut().zip(def.dim) {
+        *d = e * power;
+    }
+    let mut unit = DisplayUnit::of(def);
+    if power != 1 {
+        unit.name = format!("{}^{power}", unit.name);
+        unit.factor = def.factor.powi(power as i32);
+        unit.offset = 0.0;
+    }
+    (unit, dim)
+}
+
+fn num(v: Value) -> Result<Quantity, CalcError> {
+    match v {
+        Value::Num(q) => Ok(q),
+        Value::Date { .. } => Err(CalcError::Domain("dates only support adding and subtracting durations".into())),
+    }
+}
+
+fn no_temperature_arithmetic(q: &Quantity) -> Result<(), CalcError> {
+    if q.has_offset() {
+        return Err(CalcError::Domain(
+            "arithmetic on °C / °F is ambiguous — convert to K first".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn apply_unit(value: Value, def: &UnitDef, power: i8) -> Result<Value, CalcError> {
+    let q = num(value)?;
+    if q.dim != NONE || q.unit.is_some() {
+        return Err(CalcError::Syntax(format!("unexpected unit '{}'", def.names[0])));
+    }
+    let n = q.si;
+    let (unit, dim) = powered(def, power);
+    let months = (def.months != 0 && power == 1 && n.fract() == 0.0).then(|| n as i64 * def.months);
+    Ok(Value::Num(Quantity { si: (n + unit.offset) * unit.factor, dim, unit: Some(unit), months }))
+}
+
+fn add(left: Value, right: Value, sign: f64) -> Result<Value, CalcError> {
+    match (left, right) {
+        (Value::Num(a), Value::Num(b)) => {
+            if a.dim != b.dim {
+                return Err(CalcError::Incompatible(a.describe(), b.describe()));
+            }
+            no_temperature_arithmetic(&a)?;
+            no_temperature_arithmetic(&b)?;
+            let months = match (a.months, b.months) {
+                (Some(x), Some(y)) => Some(x + y * sign as i64),
+                _ => None,
+            };
+            Ok(Value::Num(Quantity { si: a.si + sign * b.si, dim: a.dim, unit: a.unit.or(b.unit), months }))
+        }
+        (Value::Date { secs, time }, Value::Num(d)) => {
+            // Adding hours or minutes to a plain date gives a date-time.
+            let partial_day = d.months.is_none() && d.si % SECONDS_PER_DAY as f64 != 0.0;
+            Ok(Value::Date { secs: shift(secs, &d, sign)?, time: time || partial_day })
+        }
+        (Value::Num(d), Value::Date { secs, time }) if sign > 0.0 => Ok(Value::Date { secs: shift(secs, &d, 1.0)?, time }),
+        (Value::Date { secs: a, .. }, Value::Date { secs: b, .. }) if sign < 0.0 => {
+            let days = find_unit("days").map(DisplayUnit::of);
+            Ok(Value::Num(Quantity { si: (a - b) as f64, dim: TIME, unit: days, months: None }))
+        }
+        (l, r) => Err(CalcError::Incompatible(l.describe(), r.describe())),
+    }
+}
+
+fn shift(secs: i64, duration: &Quantity, sign: f64) -> Result<i64, CalcError> {
+    if duration.dim != TIME {
+        return Err(CalcError::Incompatible("a date".into(), duration.describe()));
+    }
+    Ok(match duration.months {
+        Some(m) => add_months(secs, m * sign as i64),
+        None => secs + (sign * duration.si).round() as i64,
+    })
+}
+
+fn multiply(left: Value, right: Value) -> Result<Value, CalcError> {
+    let (a, b) = (num(left)?, num(right)?);
+    no_temperature_arithmetic(&a)?;
+    no_temperature_arithmetic(&b)?;
+    let dim = combine(a.dim, b.dim, 1);
+    let months = match (&a, &b) {
+        (x, y) if x.dim == NONE && x.unit.is_none() && x.si.fract() == 0.0 => y.months.map(|m| m * x.si as i64),
+        (x, y) if y.dim == NONE && y.unit.is_none() && y.si.fract() == 0.0 => x.months.map(|m| m * y.si as i64),
+        _ => None,
+    };
+    let unit = match (a.unit, b.unit) {
+        _ if dim == NONE => None,
+        (Some(u), None) | (None, Some(u)) => Some(u),
+        (Some(x), Some(y)) => Some(DisplayUnit {
+            name: if x.name == y.name { format!("{}^2", x.name) } else { format!("{}*{}", x.name, y.name) },
+            factor: x.factor * y.factor,
+            offset: 0.0,
+        }),
+        (None, None) => None,
+    };
+    Ok(Value::Num(Quantity { si: a.si * b.si, dim, unit, months }))
+}
+
+fn divide(left: Value, right: Value) -> Result<Value, CalcError> {
+    let (a, b) = (num(left)?, num(right)?);
+    if b.si == 0.0 {
+        return Err(CalcError::DivisionByZero);
+    }
+    let plain_divisor = b.dim == NONE && b.unit.is_none();
+    if !plain_divisor {
+        no_temperature_arithmetic(&a)?;
+        no_temperature_arithmetic(&b)?;
+    }
+    if plain_divisor && a.has_offset() {
+        return Ok(Value::Num(a.clone().with_displayed(a.displayed() / b.si)));
+    }
+    let dim = combine(a.dim, b.dim, -1);
+    let unit = match (a.unit, b.unit) {
+        _ if dim == NONE => None,
+        (u, None) => u,
+        (None, Some(y)) => Some(DisplayUnit { name: format!("1/{}", y.name), factor: 1.0 / y.factor, offset: 0.0 }),
+        (Some(x), Some(y)) => Some(DisplayUnit {
+            name: format!("{}/{}", x.name, y.name),
+            factor: x.factor / y.factor,
+            offset: 0.0,
+        }),
+    };
+    Ok(Value::Num(Quantity { si: a.si / b.si, dim, unit, months: None }))
+}
+
+fn modulo(left: Value, right: Value) -> Result<Value, CalcError> {
+    let (a, b) = (num(left)?, num(right)?);
+    if a.dim != b.dim {
+        return Err(CalcError::Incompatible(a.describe(), b.describe()));
+    }
+    no_temperature_arithmetic(&a)?;
+    if b.si == 0.0 {
+        return Err(CalcError::DivisionByZero);
+    }
+    Ok(Value::Num(Quantity { si: a.si.rem_euclid(b.si), dim: a.dim, unit: a.unit, months: None }))
+}
+
+fn negate(value: Value) -> Result<Value, CalcError> {
+    let q = num(value)?;
+    let months = q.months.map(|m| -m);
+    let v = q.displayed();
+    let mut out = q.with_displayed(-v);
+    out.months = months;
+    Ok(Value::Num(out))
+}
+
+fn power(base: Value, exponent: Value) -> Result<Value, CalcError> {
+    let (b, e) = (num(base)?, num(exponent)?);
+    if e.dim != NONE {
+        return Err(CalcError::Domain("exponents must be plain numbers".into()));
+    }
+    let n = e.si;
+    if b.dim == NONE {
+        return Ok(Value::Num(Quantity::number(b.si.powf(n))));
+    }
+    no_temperature_arithmetic(&b)?;
+    if n.fract() != 0.0 || n.abs() > MAX_UNIT_POWER {
+        return Err(CalcError::Domain("quantities with units can only be raised to small integer powers".into()));
+    }
+    let p = n as i8;
+    let mut dim = NONE;
+    for (d, x) in dim.iter_mut().zip(b.dim) {
+        *d = x * p;
+    }
+    let unit = b.unit.map(|u| DisplayUnit {
+        name: format!("{}^{p}", u.name),
+        factor: u.factor.powi(p as i32),
+        offset: 0.0,
+    });
+    Ok(Value::Num(Quantity { si: b.si.powi(p as i32), dim, unit, months: None }))
+}
+
+fn convert(value: Value, target: DisplayUnit, dim: Dim) -> Result<Value, CalcError> {
+    let q = num(value)?;
+    if q.dim != dim {
+        return Err(CalcError::Incompatible(q.describe(), target.name));
+    }
+    Ok(Value::Num(Quantity { si: q.si, dim, unit: Some(target), months: None }))
+}
+
+fn plain(args: &[Value], name: &str) -> Result<Vec<f64>, CalcError> {
+    args.iter()
+        .map(|a| match a {
+            Value::Num(q) if q.dim == NONE => Ok(q.si),
+            other => Err(CalcError::Domain(format!("{name}() takes plain numbers, not {}", other.describe()))),
+        })
+        .collect()
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value, CalcError> {
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(CalcError::Syntax(format!("{name}() takes {n} argument(s), got {}", args.len())))
+        }
+    };
+    let unary = |f: fn(f64) -> f64| -> Result<Value, CalcError> {
+        arity(1)?;
+        Ok(Value::Num(Quantity::number(f(plain(&args, name)?[0]))))
+    };
+    match name {
+        "sqrt" => {
+            arity(1)?;
+            let q = num(args[0].clone())?;
+            if q.dim.iter().any(|e| e % 2 != 0) {
+                return Err(CalcError::Domain(format!("can't take the square root of {}", q.describe())));
+            }
+            if q.si < 0.0 {
+                return Err(CalcError::Domain("square root of a negative number".into()));
+            }
+            let mut dim = q.dim;
+            dim.iter_mut().for_each(|e| *e /= 2);
+            Ok(Value::Num(Quantity { si: q.si.sqrt(), dim, unit: None, months: None }))
+        }
+        "abs" | "floor" | "ceil" | "round" => {
+            if !(1..=2).contains(&args.len()) || (name != "round" && args.len() != 1) {
+                return Err(CalcError::Syntax(format!("{name}() takes 1 argument")));
+            }
+            let digits = match args.get(1) {
+                Some(d) => plain(std::slice::from_ref(d), name)?[0],
+                None => 0.0,
+            };
+            let q = num(args[0].clone())?;
+            let v = q.displayed();
+            let scale = 10f64.powi(digits.clamp(-12.0, 12.0) as i32);
+            let out = match name {
+                "abs" => v.abs(),
+                "floor" => v.floor(),
+                "ceil" => v.ceil(),
+                _ => (v * scale).round() / scale,
+            };
+            Ok(Value::Num(q.with_displayed(out)))
+        }
+        "min" | "max" => {
+            let mut best: Option<Quantity> = None;
+            for a in args {
+                let q = num(a)?;
+                best = Some(match best {
+                    None => q,
+                    Some(b) if b.dim != q.dim => return Err(CalcError::Incompatible(b.describe(), q.describe())),
+                    Some(b) if (name == "min") == (q.si < b.si) => q,
+                    Some(b) => b,
+                });
+            }
+            best.map(Value::Num)
+                .ok_or_else(|| CalcError::Syntax(format!("{name}() needs at least one argument")))
+        }
+        "pow" => {
+            let [b, e]: [Value; 2] = args
+                .try_into()
+                .map_err(|_| CalcError::Syntax("pow() takes 2 arguments".into()))?;
+            power(b, e)
+        }
+        "ln" => unary(f64::ln),
+        "log" | "log10" => unary(f64::log10),
+        "log2" => unary(f64::log2),
+        "exp" => unary(f64::exp),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" => unary(f64::asin),
+        "acos" => unary(f64::acos),
+        "atan" => unary(f64::atan),
+        _ => Err(CalcError::UnknownName(name.to_owned())),
+    }
+}
+
+// ── Formatting ─────────────────────────────────────────────────────────────────
+
+/// Rounds to 12 significant digits, hiding floating-point noise
+/// (`0.1 + 0.2` is `0.3`).
+fn format_number(v: f64) -> String {
+    if v == 0.0 {
+        return "0".into();
+    }
+    let abs = v.abs();
+    if !(1e-6..1e15).contains(&abs) {
+        let s = format!("{v:.11e}");
+        let (mantissa, exp) = s.split_once('e').unwrap_or((&s, "0"));
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        return format!("{mantissa}e{exp}");
+    }
+    let int_digits = abs.log10().floor() as i32 + 1;
+    let decimals = (12 - int_digits).clamp(0, 12) as usize;
+    let s = format!("{v:.decimals$}");
+    let s = if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.') } else { &s };
+    if s == "-0" { "0".into() } else { s.to_owned() }
+}
+
+fn finish(value: Value) -> Result<Calculation, CalcError> {
+    match value {
+        Value::Date { secs, time } => {
+            let text = format_date(secs, time);
+            Ok(Calculation { value: None, unit: None, date: Some(text.clone()), text })
+        }
+        Value::Num(q) => {
+            let v = q.displayed();
+            if !v.is_finite() {
+                return Err(CalcError::Domain("result is not a finite number".into()));
+            }
+            let unit = match &q.unit {
+                Some(u) => Some(u.name.clone()),
+                None if q.dim != NONE => Some(si_unit_name(q.dim)),
+                None => None,
+            };
+            let number = format_number(v);
+            let text = match &unit {
+                Some(u) => format!("{number} {u}"),
+                None => number.clone(),
+            };
+            Ok(Calculation { value: number.parse().ok(), unit, date: None, text })
+        }
+    }
+}
+
+/// Evaluates `expression`: arithmetic (`+ - * / ^ mod %`, functions such as
+/// `sqrt`, `round`, `log`), unit conversion (`60 mph to km/h`, `20% of 150`)
+/// and date arithmetic (`2024-01-31 + 1 month`, `2024-12-25 - today`).
+pub fn evaluate(expression: &str) -> Result<Calculation, CalcError> {
+    if expression.len() > MAX_EXPRESSION_LEN {
+        return Err(CalcError::Syntax(format!("expression longer than {MAX_EXPRESSION_LEN} bytes")));
+    }
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err(CalcError::Syntax("empty expression".into()));
+    }
+    let mut parser = Parser { tokens, pos: 0, depth: 0 };
+    let value = parser.expression()?;
+    if let Some(t) = parser.peek() {
+        return Err(CalcError::Syntax(format!("unexpected {t:?}")));
+    }
+    finish(value)
+}
+
+// ── Skill ──────────────────────────────────────────────────────────────────────
+
+/// Definition of the deterministic `calculate` skill
+/// (`{"expression"}` → `{"value", "unit", "date", "text"}`).
+pub fn skill_definition() -> SkillDefinition {
+    SkillDefinition {
+        id: CALCULATOR_SKILL_ID.into(),
+        input_schema: JsonSchema::new(serde_json::json!({
+            "type": "object",
+            "required": ["expression"],
+            "properties": {"expression": {"type": "string"}}
+        })),
+        output_schema: JsonSchema::new(serde_json::json!({
+            "type": "object",
+            "required": ["text"],
+            "properties": {
+                "value": {"type": ["number", "null"]},
+                "unit": {"type": ["string", "null"]},
+                "date": {"type": ["string", "null"]},
+                "text": {"type": "string"}
+            }
+        })),
+        execution_mode: SkillExecutionMode::Deterministic,
+        max_output_tokens: 100,
+        compact_keys: None,
+        prompt_variants: Vec::new(),
+    }
+}
+
+/// Installs the `calculate` handler on `executor`.
+pub fn register(executor: &mut SkillExecutor) {
+    executor.register_deterministic(CALCULATOR_SKILL_ID, |input| {
+        let expression = input
+            .get("expression")
+            .and_then(|e| e.as_str())
+            .ok_or_else(|| SkillExecError::DeterministicError("expression must be a string".into()))?;
+        let result = evaluate(expression).map_err(|e| SkillExecError::DeterministicError(e.to_string()))?;
+        serde_json::to_value(result).map_err(|e| SkillExecError::DeterministicError(e.to_string()))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(expr: &str) -> String {
+        evaluate(expr).map(|c| c.text).unwrap_or_else(|e| format!("error: {e}"))
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(text("1 + 2 * 3"), "7");
+        assert_eq!(text("(1 + 2) * 3"), "9");
+        assert_eq!(text("-2^2"), "-4");
+        assert_eq!(text("2^3^2"), "512");
+        assert_eq!(text("0.1 + 0.2"), "0.3");
+        assert_eq!(text("10 mod 3"), "1");
+        assert_eq!(text("20% of 150"), "30");
+        assert_eq!(text("sqrt(16) + round(2.345, 2)"), "6.35");
+        assert_eq!(text("2(3 + 4)"), "14");
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::DivisionByZero));
+    }
+
+    #[test]
+    fn unit_conversion() {
+        assert_eq!(text("5 km + 300 m"), "5.3 km");
+        assert_eq!(text("60 mph to km/h"), "96.56064 km/h");
+        assert_eq!(text("100 F to C"), "37.7777777778 C");
+        assert_eq!(text("-40 C in F"), "-40 F");
+        assert_eq!(text("5 ft in in"), "60 in");
+        assert_eq!(text("12 in in cm"), "30.48 cm");
+        assert_eq!(text("3 m * 2 m"), "6 m^2");
+        assert_eq!(text("1 GiB to MB"), "1073.741824 MB");
+        assert_eq!(text("1 km / 1 m"), "1000");
+        assert!(matches!(evaluate("5 kg + 2 m"), Err(CalcError::Incompatible(..))));
+        assert!(matches!(evaluate("20 C + 5 C"), Err(CalcError::Domain(_))));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(text("2024-01-31 + 1 month"), "2024-02-29");
+        assert_eq!(text("2024-03-01 - 2024-02-01"), "29 d");
+        assert_eq!(text("2023-12-25 + 2 weeks"), "2024-01-08");
+        assert_eq!(text("2024-02-29 + 1 year"), "2025-02-28");
+        assert_eq!(text("2024-01-01T10:30 + 90 min"), "2024-01-01T12:00:00Z");
+        assert!(matches!(evaluate("2024-02-30"), Err(CalcError::InvalidDate(_))));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(matches!(evaluate("foo + 1"), Err(CalcError::UnknownName(_))));
+        assert!(matches!(evaluate("1 +"), Err(CalcError::Syntax(_))));
+        assert!(evaluate(&"(".repeat(200)).is_err());
+    }
+
+    #[test]
+    fn runs_as_a_deterministic_skill() {
+        use crate::provider::{LLMRequest, ModelProvider, ModelResponse, ProviderError};
+        use crate::skill::ResponseMode;
+        use std::sync::Arc;
+
+        struct NoCalls;
+        impl ModelProvider for NoCalls {
+            fn call_model(&self, _: LLMRequest) -> Result<ModelResponse, ProviderError> {
+                Err(ProviderError::CallFailed("no LLM round trip expected".into()))
+            }
+        }
+
+        let mut executor = SkillExecutor::new();
+        register(&mut executor);
+        let out = executor
+            .execute(
+                &skill_definition(),
+                &serde_json::json!({"expression": "2 h to min"}),
+                ResponseMode::StrictJson,
+                &NoCalls,
+                &Arc::from(""),
+                &Arc::from("local"),
+            )
+            .expect("should evaluate");
+        assert_eq!(out.output["text"], "120 min");
+        assert_eq!(out.output["value"], 120.0);
+    }
+}
@@ -1,12 +1,17 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::agent_compiler::CompiledAgent;
-use crate::memory::{MemoryManager, estimate_memory_tokens};
-use crate::provider::{ModelProvider, model_cost_per_1k};
-use crate::skill_executor::SkillExecutor;
+use crate::artifact::{ArtifactRef, ArtifactStore};
+use crate::memory::{MemoryManager, estimate_memory_tokens_for};
+use crate::prompt_variant::{PromptVariantStore, VariantOutcome, VariantReport};
+use crate::provider::{ModelProvider, cached_token_discount, model_cost_per_1k};
+use crate::run_trace::{RunTrace, RunTraceStore, SkillTrace, TraceStage};
+use crate::skill_executor::{SkillExecError, SkillExecutor};
 use crate::token_optimizer::{
-    DeltaContextEngine, PredictiveEstimator, SemanticCompressor, StaticPromptCache,
-    TokenBreakdown, TokenTracker, ToolSchemaCache, estimate_tokens,
+    ConversationUsage, DeltaContextEngine, PredictiveEstimator, SemanticCompressor,
+    StaticPromptCache, TokenBreakdown, TokenEstimate, TokenTracker, ToolSchemaCache,
+    estimate_tokens_for,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -17,6 +22,8 @@ pub enum ExecutionError {
     GraphError(String),
     #[error("budget exhausted: used {used}, limit {limit}")]
     BudgetExhausted { used: u32, limit: u32 },
+    #[error("replay error: {0}")]
+    ReplayError(String),
 }
 
 pub struct ExecutionEngine {
@@ -26,6 +33,11 @@ pub struct ExecutionEngine {
     delta_engine: DeltaContextEngine,
     compressor: SemanticCompressor,
     tracker: TokenTracker,
+    artifacts: ArtifactStore,
+    conversation_id: Option<String>,
+    trace_run_id: Option<String>,
+    traces: RunTraceStore,
+    variants: PromptVariantStore,
 }
 
 impl ExecutionEngine {
@@ -37,24 +49,118 @@ impl ExecutionEngine {
             delta_engine: DeltaContextEngine::new(),
             compressor: SemanticCompressor::new(200),
             tracker: TokenTracker::new(),
+            artifacts: ArtifactStore::new(),
+            conversation_id: None,
+            trace_run_id: None,
+            traces: RunTraceStore::new(),
+            variants: PromptVariantStore::new(),
         }
     }
 
+    /// Replaces the artifact store, e.g. to change the inline size limit.
+    pub fn with_artifacts(mut self, artifacts: ArtifactStore) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
+
+    /// Attributes the token usage of subsequent runs to a chat conversation,
+    /// so agents launched from a chat show up in that conversation's spend.
+    /// Pass `None` to stop attributing.
+    pub fn attribute_to_conversation(&mut self, conversation_id: Option<String>) {
+        self.conversation_id = conversation_id;
+    }
+
+    /// Records a full trace (prompt, input delta, raw response and every
+    /// post-processing stage of each skill) of subsequent runs under `run_id`.
+    /// Tracing is off by default; pass `None` to turn it off again.
+    pub fn trace_run(&mut self, run_id: Option<String>) {
+        self.trace_run_id = run_id;
+    }
+
     pub fn execute(
         &mut self,
         agent: &CompiledAgent,
         memory: &MemoryManager,
         provider: &dyn ModelProvider,
     ) -> Result<ExecutionResult, ExecutionError> {
-        let order = agent
-            .graph
-            .topological_order()
-            .map_err(|e| ExecutionError::GraphError(e.to_string()))?;
+        let order = schedule(agent)?;
+        self.run_steps(agent, memory, provider, &order, Vec::new())
+    }
 
+    /// Re-runs traced run `run_id` starting at `skill_id`. Earlier steps are
+    /// not called again: their recorded outputs are fed to the remaining
+    /// skills as-is, so a bad step can be retried (e.g. after editing its
+    /// prompt or skill definition) without paying for the ones before it.
+    pub fn execute_from(
+        &mut self,
+        run_id: &str,
+        skill_id: &str,
+        agent: &CompiledAgent,
+        memory: &MemoryManager,
+        provider: &dyn ModelProvider,
+    ) -> Result<ExecutionResult, ExecutionError> {
+        let order = schedule(agent)?;
+        let trace = self
+            .traces
+            .get(run_id)
+            .ok_or_else(|| ExecutionError::ReplayError(format!("no trace for run '{run_id}'")))?;
+        let start = order.iter().position(|id| *id == skill_id).ok_or_else(|| {
+            ExecutionError::ReplayError(format!("skill '{skill_id}' is not part of this agent"))
+        })?;
+        let prefix = order[..start]
+            .iter()
+            .filter(|id| agent.skills.iter().any(|s| s.id == **id))
+            .map(|id| {
+                trace.step(id).cloned().ok_or_else(|| {
+                    ExecutionError::ReplayError(format!(
+                        "run '{run_id}' has no recorded output for skill '{id}'"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Otherwise an unchanged input would be answered from the cache.
+        self.skill_executor.clear_cache();
+        self.run_steps(agent, memory, provider, &order[start..], prefix)
+    }
+
+    /// The recorded call of `skill_id` in traced run `run_id`.
+    pub fn agent_run_inspect(&self, run_id: &str, skill_id: &str) -> Option<&SkillTrace> {
+        self.traces.inspect(run_id, skill_id)
+    }
+
+    pub fn traces(&self) -> &RunTraceStore {
+        &self.traces
+    }
+
+    /// Pass rate, tokens and latency of each prompt variant of `skill_id`
+    /// tried so far, best first.
+    pub fn variant_report(&self, skill_id: &str) -> Vec<VariantReport> {
+        self.variants.report(skill_id)
+    }
+
+    /// Runs `order`, treating `replayed` as already-completed steps.
+    fn run_steps(
+        &mut self,
+        agent: &CompiledAgent,
+        memory: &MemoryManager,
+        provider: &dyn ModelProvider,
+        order: &[&str],
+        replayed: Vec<SkillTrace>,
+    ) -> Result<ExecutionResult, ExecutionError> {
         let mut budget_remaining = agent.budget;
         let mut outputs = ahash::AHashMap::new();
+        for step in &replayed {
+            self.delta_engine.store(&step.skill_id, step.output.clone());
+            outputs.insert(step.skill_id.clone(), step.output.clone());
+            budget_remaining = budget_remaining.saturating_sub(step.total_tokens);
+        }
+        let mut trace = self.trace_run_id.as_deref().map(|id| RunTrace {
+            run_id: id.to_owned(),
+            steps: replayed,
+        });
 
-        for skill_id in &order {
+        for skill_id in order {
             let skill = match agent.skills.iter().find(|s| s.id == *skill_id) {
                 Some(s) => s,
                 None => continue,
@@ -77,28 +183,54 @@ impl ExecutionEngine {
 
             let delta = self.delta_engine.compute_delta(&deps);
             let delta_str = serde_json::to_string(&delta).unwrap_or_default();
-            let delta_tokens = estimate_tokens(&delta_str);
-
-            let mem_text = memory.select_and_trim(agent.memory_tier, budget_remaining / 4);
-            let mem_tokens = estimate_memory_tokens(&mem_text);
 
             let schema_hash = ToolSchemaCache::schema_hash(skill_id);
             let schema_json =
                 serde_json::to_string(&skill.output_schema.schema).unwrap_or_default();
             let _cached_schema = self.schema_cache.get_or_insert(schema_hash, &schema_json);
-            let schema_tokens = estimate_tokens(&schema_json);
 
-            let prompt_text = agent.system_instruction.as_ref();
-            let cached_prompt = self.prompt_cache.get_or_compile(skill_id, prompt_text);
-            let prompt_tokens = estimate_tokens(&cached_prompt);
+            let variant = if skill.is_deterministic() {
+                None
+            } else {
+                self.variants.sample(skill)
+            };
+            let cached_prompt = match variant {
+                Some(v) => self
+                    .prompt_cache
+                    .get_or_compile(&format!("{skill_id}#{}", v.id), &v.system_prompt),
+                None => self
+                    .prompt_cache
+                    .get_or_compile(skill_id, &agent.system_instruction),
+            };
 
-            let est = PredictiveEstimator::estimate_call(
-                prompt_tokens,
-                delta_tokens,
-                mem_tokens,
-                schema_tokens,
-                skill.max_output_tokens,
-            );
+            // Memory and every count depend on the model's tokenizer.
+            let estimate = |model: &str| {
+                let mem_text =
+                    memory.select_and_trim(agent.memory_tier, budget_remaining / 4, model);
+                PredictiveEstimator::estimate_call(
+                    estimate_tokens_for(model, &cached_prompt),
+                    estimate_tokens_for(model, &delta_str),
+                    estimate_memory_tokens_for(model, &mem_text),
+                    estimate_tokens_for(model, &schema_json),
+                    skill.max_output_tokens,
+                )
+            };
+
+            // Routing needs a count before the model is known: take the
+            // full-size model's, then count again for the model picked.
+            let model: Arc<str> = if skill.is_deterministic() {
+                Arc::from("local")
+            } else {
+                select_model(budget_remaining, estimate(ROUTING_MODEL).total)
+            };
+            let est = estimate(&model);
+            let TokenEstimate {
+                prompt: prompt_tokens,
+                context: delta_tokens,
+                memory: mem_tokens,
+                schema: schema_tokens,
+                ..
+            } = est;
 
             if est.total > budget_remaining {
                 let suggestions =
@@ -112,36 +244,83 @@ impl ExecutionEngine {
                 }
             }
 
-            let model: Arc<str> = if skill.is_deterministic() {
-                Arc::from("local")
-            } else {
-                select_model(budget_remaining, est.total)
-            };
-
-            let input = if delta.as_object().map_or(true, |o| o.is_empty()) {
+            let input = if delta.as_object().is_none_or(|o| o.is_empty()) {
                 serde_json::json!({"input": "start"})
             } else {
                 flatten_delta(&delta)
             };
 
-            let result = execute_skill(
-                &mut self.skill_executor,
+            let started = Instant::now();
+            let result = self.skill_executor.execute(
                 skill,
                 &input,
                 agent.response_mode,
                 provider,
                 &cached_prompt,
                 &model,
-            )?;
+            );
+            if let Some(v) = variant {
+                // Cache hits and provider failures say nothing about the prompt.
+                let outcome = match &result {
+                    Ok(r) if r.cached => None,
+                    Ok(r) => Some((true, r.usage.total_tokens)),
+                    Err(SkillExecError::Provider(_)) => None,
+                    Err(_) => Some((false, 0)),
+                };
+                if let Some((passed, total_tokens)) = outcome {
+                    self.variants.record(skill_id, &v.id, VariantOutcome {
+                        passed,
+                        total_tokens,
+                        latency: started.elapsed(),
+                    });
+                }
+            }
+            let result = result.map_err(|e| ExecutionError::SkillError(e.to_string()))?;
 
-            let mut compressed = self.compressor.compress(&result.output);
-            skill.output_schema.strip_unknown_fields(&mut compressed);
+            let validated = trace.as_ref().map(|_| result.output.clone());
+
+            // Large results are passed downstream by reference; only their
+            // summary reaches later prompts.
+            let output = self.artifacts.externalize(result.output);
+            let mut compressed = self.compressor.compress(&output);
+            if ArtifactRef::from_value(&compressed).is_none() {
+                skill.output_schema.strip_unknown_fields(&mut compressed);
+            }
+
+            if let (Some(trace), Some(validated)) = (trace.as_mut(), validated) {
+                let stage = |name: &str, value: serde_json::Value| TraceStage {
+                    name: name.to_owned(),
+                    value,
+                };
+                trace.steps.push(SkillTrace {
+                    skill_id: skill_id.to_string(),
+                    model: model.to_string(),
+                    system_prompt: cached_prompt.to_string(),
+                    prompt_variant: variant.map(|v| v.id.clone()),
+                    input_delta: delta,
+                    input,
+                    raw_response: result.raw_response,
+                    cached: result.cached,
+                    stages: vec![
+                        stage("validated", validated),
+                        stage("externalized", output),
+                        stage("compressed", compressed.clone()),
+                    ],
+                    output: compressed.clone(),
+                    prompt_tokens: result.usage.prompt_tokens,
+                    completion_tokens: result.usage.completion_tokens,
+                    total_tokens: result.usage.total_tokens,
+                });
+            }
 
             self.delta_engine.store(skill_id, compressed.clone());
             outputs.insert(skill_id.to_string(), compressed);
 
             let usage_total = result.usage.total_tokens;
-            let cost = (usage_total as f64 / 1000.0) * model_cost_per_1k(&model);
+            let cached_tokens = result.usage.cached_prompt_tokens;
+            let price = model_cost_per_1k(&model);
+            let cache_savings = (cached_tokens as f64 / 1000.0) * price * cached_token_discount(&model);
+            let cost = (usage_total as f64 / 1000.0) * price - cache_savings;
 
             self.tracker.record(TokenBreakdown {
                 skill_id: skill_id.to_string(),
@@ -152,45 +331,64 @@ impl ExecutionEngine {
                 schema_tokens,
                 response_tokens: result.usage.completion_tokens,
                 total_tokens: usage_total,
+                cached_tokens,
+                cache_savings,
                 cost,
+                conversation_id: self.conversation_id.clone(),
             });
 
             budget_remaining = budget_remaining.saturating_sub(usage_total);
         }
 
+        if let Some(trace) = trace {
+            self.traces.insert(trace);
+        }
+
         Ok(ExecutionResult {
             outputs,
             report: self.tracker.report(),
             total_cost: self.tracker.total_cost(),
             total_tokens: self.tracker.total_tokens(),
+            conversation: self
+                .conversation_id
+                .as_deref()
+                .map(|id| self.tracker.conversation_usage(id)),
         })
     }
 
     pub fn tracker(&self) -> &TokenTracker {
         &self.tracker
     }
+
+    /// Full values behind the `$artifact` references in run outputs.
+    pub fn artifacts(&self) -> &ArtifactStore {
+        &self.artifacts
+    }
 }
 
-fn execute_skill(
-    executor: &mut SkillExecutor,
-    skill: &crate::skill::SkillDefinition,
-    input: &serde_json::Value,
-    response_mode: crate::skill::ResponseMode,
-    provider: &dyn ModelProvider,
-    prompt: &Arc<str>,
-    model: &Arc<str>,
-) -> Result<crate::skill_executor::SkillExecResult, ExecutionError> {
-    executor
-        .execute(skill, input, response_mode, provider, prompt, model)
-        .map_err(|e| ExecutionError::SkillError(e.to_string()))
+/// Execution order: dependencies first, then skills that share a system
+/// prompt and model grouped together so provider prompt caches stay warm
+/// (deterministic skills run locally and would only split the groups).
+fn schedule(agent: &CompiledAgent) -> Result<Vec<&str>, ExecutionError> {
+    let prompt = agent.system_instruction.as_ref();
+    agent
+        .graph
+        .grouped_order(|id| {
+            let local = agent.skills.iter().any(|s| s.id == id && s.is_deterministic());
+            (local, prompt)
+        })
+        .map_err(|e| ExecutionError::GraphError(e.to_string()))
 }
 
+/// Model [`select_model`] picks when the budget allows.
+const ROUTING_MODEL: &str = "gpt-4o";
+
 fn select_model(budget_remaining: u32, estimated_cost: u32) -> Arc<str> {
     let ratio = estimated_cost as f64 / budget_remaining.max(1) as f64;
     if ratio > 0.5 {
         Arc::from("gpt-4o-mini")
     } else {
-        Arc::from("gpt-4o")
+        Arc::from(ROUTING_MODEL)
     }
 }
 
@@ -218,6 +416,7 @@ pub struct ExecutionResult {
     pub report: String,
     pub total_cost: f64,
     pub total_tokens: u32,
+    pub conversation: Option<ConversationUsage>,
 }
 
 #[cfg(test)]
@@ -227,7 +426,7 @@ mod tests {
     use crate::agent_template::{AgentTemplate, TemplateRegistry};
     use crate::memory::{MemoryEntry, MemoryTier};
     use crate::provider::{LLMRequest, ModelResponse, ProviderError, TokenUsage};
-    use crate::skill::{JsonSchema, ResponseMode, SkillDefinition, SkillExecutionMode};
+    use crate::skill::{JsonSchema, PromptVariant, ResponseMode, SkillDefinition, SkillExecutionMode};
     use serde_json::json;
 
     struct MockProvider;
@@ -245,6 +444,7 @@ mod tests {
                     prompt_tokens: 50,
                     completion_tokens: 30,
                     total_tokens: 80,
+                    cached_prompt_tokens: 0,
                 },
                 model: req.model,
             })
@@ -271,6 +471,7 @@ mod tests {
                 execution_mode: SkillExecutionMode::LLM,
                 max_output_tokens: 500,
                 compact_keys: None,
+                prompt_variants: Vec::new(),
             },
             SkillDefinition {
                 id: "summarize".into(),
@@ -279,6 +480,7 @@ mod tests {
                 execution_mode: SkillExecutionMode::LLM,
                 max_output_tokens: 300,
                 compact_keys: None,
+                prompt_variants: Vec::new(),
             },
         ];
 
@@ -302,6 +504,8 @@ mod tests {
             key: "context".into(),
             value: "previous research data".into(),
             tier: MemoryTier::Delta,
+            tags: Vec::new(),
+            expires_at: None,
         });
 
         (agent, mem)
@@ -320,6 +524,149 @@ mod tests {
         assert!(r.outputs.contains_key("search"));
         assert!(r.outputs.contains_key("summarize"));
         assert!(r.total_tokens > 0);
+        assert!(r.conversation.is_none());
+    }
+
+    #[test]
+    fn attributes_usage_to_conversation() {
+        let (agent, mem) = setup_compiled_agent();
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        engine.attribute_to_conversation(Some("conv-42".into()));
+
+        let r = engine.execute(&agent, &mem, &MockProvider).expect("should succeed");
+        let conv = r.conversation.expect("conversation usage should be present");
+        assert_eq!(conv.conversation_id, "conv-42");
+        assert_eq!(conv.skill_calls, 2);
+        assert_eq!(conv.total_tokens, r.total_tokens);
+    }
+
+    #[test]
+    fn passes_large_outputs_by_reference() {
+        let (agent, mem) = setup_compiled_agent();
+        let mut engine = ExecutionEngine::new(SkillExecutor::new())
+            .with_artifacts(ArtifactStore::with_inline_limit(16));
+
+        let r = engine.execute(&agent, &mem, &MockProvider).expect("should succeed");
+        let results = &r.outputs["search"]["results"];
+        assert!(ArtifactRef::from_value(results).is_some());
+        let full = engine.artifacts().resolve(results).expect("artifact should be stored");
+        assert_eq!(*full, json!(["result1", "result2"]));
+    }
+
+    struct CountingProvider(std::cell::Cell<u32>);
+
+    impl ModelProvider for CountingProvider {
+        fn call_model(&self, req: LLMRequest) -> Result<ModelResponse, ProviderError> {
+            self.0.set(self.0.get() + 1);
+            MockProvider.call_model(req)
+        }
+    }
+
+    #[test]
+    fn records_trace_only_when_enabled() {
+        let (agent, mem) = setup_compiled_agent();
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        engine.execute(&agent, &mem, &MockProvider).expect("should succeed");
+        assert!(engine.traces().is_empty());
+
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        engine.trace_run(Some("run-1".into()));
+        engine.execute(&agent, &mem, &MockProvider).expect("should succeed");
+        let step = engine.agent_run_inspect("run-1", "summarize").expect("should be traced");
+        assert_eq!(step.system_prompt, "Research agent.");
+        assert_eq!(step.input_delta["search"]["results"], json!(["result1", "result2"]));
+        assert_eq!(step.raw_response.as_deref(), Some(r#"{"summary":"compressed summary"}"#));
+        assert_eq!(step.stage("validated"), Some(&json!({"summary": "compressed summary"})));
+        assert_eq!(step.output, json!({"summary": "compressed summary"}));
+    }
+
+    #[test]
+    fn re_executes_from_step() {
+        let (agent, mem) = setup_compiled_agent();
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        engine.trace_run(Some("run-1".into()));
+        engine.execute(&agent, &mem, &MockProvider).expect("should succeed");
+
+        let provider = CountingProvider(std::cell::Cell::new(0));
+        let r = engine
+            .execute_from("run-1", "summarize", &agent, &mem, &provider)
+            .expect("should replay");
+        assert_eq!(provider.0.get(), 1);
+        assert_eq!(r.outputs["search"]["results"], json!(["result1", "result2"]));
+        assert_eq!(r.outputs["summarize"]["summary"], "compressed summary");
+        let trace = engine.traces().get("run-1").expect("should be re-recorded");
+        assert_eq!(trace.steps.len(), 2);
+
+        let err = engine.execute_from("run-2", "summarize", &agent, &mem, &provider);
+        assert!(matches!(err, Err(ExecutionError::ReplayError(_))));
+    }
+
+    struct CachingProvider;
+
+    impl ModelProvider for CachingProvider {
+        fn call_model(&self, req: LLMRequest) -> Result<ModelResponse, ProviderError> {
+            let mut resp = MockProvider.call_model(req)?;
+            resp.usage.cached_prompt_tokens = 40;
+            Ok(resp)
+        }
+    }
+
+    #[test]
+    fn records_prompt_cache_savings() {
+        let (agent, mem) = setup_compiled_agent();
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        let r = engine.execute(&agent, &mem, &CachingProvider).expect("should succeed");
+
+        let records = engine.tracker().records();
+        assert!(records.iter().all(|b| b.cached_tokens == 40 && b.cache_savings > 0.0));
+        let full_price: f64 = records
+            .iter()
+            .map(|b| b.total_tokens as f64 / 1000.0 * model_cost_per_1k(&b.model))
+            .sum();
+        assert!((full_price - r.total_cost - engine.tracker().total_cache_savings()).abs() < 1e-12);
+        assert!(r.report.contains("cached=40"));
+    }
+
+    struct VariantProvider;
+
+    impl ModelProvider for VariantProvider {
+        fn call_model(&self, req: LLMRequest) -> Result<ModelResponse, ProviderError> {
+            let mut resp = MockProvider.call_model(req.clone())?;
+            if req.system_prompt.contains("Ramble") {
+                resp.content = r#""free text""#.into();
+            }
+            Ok(resp)
+        }
+    }
+
+    #[test]
+    fn tracks_prompt_variant_outcomes() {
+        let (mut agent, mem) = setup_compiled_agent();
+        let search = agent.skills.iter_mut().find(|s| s.id == "search").expect("search skill");
+        search.prompt_variants = vec![
+            PromptVariant { id: "strict".into(), system_prompt: "Answer in JSON.".into(), weight: 1 },
+            PromptVariant { id: "loose".into(), system_prompt: "Ramble.".into(), weight: 1 },
+        ];
+
+        let mut engine = ExecutionEngine::new(SkillExecutor::new());
+        engine.trace_run(Some("run-1".into()));
+        for _ in 0..20 {
+            engine.skill_executor.clear_cache();
+            let _ = engine.execute(&agent, &mem, &VariantProvider);
+        }
+
+        let report = engine.variant_report("search");
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.iter().map(|r| r.runs).sum::<u32>(), 20);
+        assert_eq!(report[0].variant_id, "strict");
+        assert_eq!(report[0].pass_rate, 1.0);
+        assert_eq!(report[0].avg_tokens, 80.0);
+        assert_eq!(report[1].pass_rate, 0.0);
+        assert!(engine.variant_report("summarize").is_empty());
+
+        let step = engine.agent_run_inspect("run-1", "search").expect("should be traced");
+        assert_eq!(step.prompt_variant.as_deref(), Some("strict"));
+        assert_eq!(step.system_prompt, "Answer in JSON.");
     }
 
     #[test]
@@ -342,6 +689,7 @@ mod tests {
             execution_mode: SkillExecutionMode::LLM,
             max_output_tokens: 500,
             compact_keys: None,
+            prompt_variants: Vec::new(),
         }];
 
         let config = UserAgentConfig {
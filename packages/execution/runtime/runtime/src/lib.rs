@@ -1,9 +1,16 @@
 pub mod agent_compiler;
 pub mod agent_template;
+pub mod artifact;
+pub mod bundle;
+pub mod calculator;
 pub mod execution_engine;
 pub mod memory;
+pub mod memory_audit;
+pub mod prompt_variant;
 pub mod provider;
+pub mod run_trace;
 pub mod skill;
 pub mod skill_executor;
 pub mod skill_graph;
 pub mod token_optimizer;
+pub mod tokenizer;
@@ -29,6 +29,7 @@ impl ModelProvider for MockProvider {
                 prompt_tokens,
                 completion_tokens,
                 total_tokens: prompt_tokens + completion_tokens,
+                cached_prompt_tokens: 0,
             },
             model: req.model,
         })
@@ -67,6 +68,7 @@ fn main() {
             execution_mode: SkillExecutionMode::LLM,
             max_output_tokens: 500,
             compact_keys: None,
+            prompt_variants: Vec::new(),
         },
         SkillDefinition {
             id: "summarize".into(),
@@ -87,6 +89,7 @@ fn main() {
             execution_mode: SkillExecutionMode::LLM,
             max_output_tokens: 300,
             compact_keys: None,
+            prompt_variants: Vec::new(),
         },
     ];
 
@@ -135,6 +138,8 @@ fn main() {
         key: "prior_research".into(),
         value: "Agent orchestration patterns include DAG, pipeline, and event-driven.".into(),
         tier: MemoryTier::Delta,
+        tags: Vec::new(),
+        expires_at: None,
     });
 
     let provider = MockProvider;
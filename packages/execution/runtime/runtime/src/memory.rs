@@ -1,26 +1,256 @@
+use std::hash::{Hash, Hasher};
+
+use ahash::AHashSet;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use crate::tokenizer::count_tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum MemoryTier {
+    #[default]
     None,
     CompressedSummary,
     Delta,
     Full,
 }
 
-impl Default for MemoryTier {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
     pub key: String,
     pub value: String,
     pub tier: MemoryTier,
+    /// Project or topic labels, normalized by [`normalize_tags`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix time (seconds) after which the memory no longer applies, for
+    /// temporary facts like "traveling next week"; `None` keeps it. Whoever
+    /// stores the memory sets it (the desktop app's frontend owns that
+    /// store); the runtime only honours it.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl MemoryEntry {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// Whether the entry carries every tag in `tags` (compared normalized);
+    /// an empty filter matches everything.
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        let own = normalize_tags(self.tags.iter().map(String::as_str));
+        normalize_tags(tags.iter().map(String::as_str))
+            .iter()
+            .all(|t| own.binary_search(t).is_ok())
+    }
+}
+
+/// Trims and lowercases tags, collapses inner whitespace to `-`, and drops
+/// empty ones and duplicates; the result is sorted.
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut out: Vec<String> = tags
+        .into_iter()
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Memories already injected into earlier turns of one session, so the next
+/// turn's context only carries what is new or changed. Keep one per
+/// conversation; entries are keyed by memory key and a hash of the value.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTurnState {
+    sent: Vec<(String, u64)>,
+}
+
+impl MemoryTurnState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys injected so far, in injection order.
+    pub fn sent_keys(&self) -> impl Iterator<Item = &str> {
+        self.sent.iter().map(|(k, _)| k.as_str())
+    }
+
+    /// Forgets everything sent, e.g. after the history was truncated.
+    pub fn reset(&mut self) {
+        self.sent.clear();
+    }
+
+    fn is_current(&self, entry: &MemoryEntry) -> bool {
+        let hash = value_hash(&entry.value);
+        self.sent.iter().any(|(k, h)| *k == entry.key && *h == hash)
+    }
+
+    fn mark_sent(&mut self, entry: &MemoryEntry) {
+        let hash = value_hash(&entry.value);
+        match self.sent.iter_mut().find(|(k, _)| *k == entry.key) {
+            Some(slot) => slot.1 = hash,
+            None => self.sent.push((entry.key.clone(), hash)),
+        }
+    }
+}
+
+/// What happened to one memory when a turn's context was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InjectionStatus {
+    /// Written out in full.
+    Injected,
+    /// Only named in the header; sent in full on an earlier turn.
+    Carried,
+    /// Cut off at the budget, so sent again next turn.
+    Truncated,
+    /// Didn't fit the budget.
+    Omitted,
+}
+
+/// One memory's part in a turn's context, for explaining it afterwards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InjectedMemory {
+    pub key: String,
+    /// Relevance to the turn's query, 0–1 (see [`relevance`]).
+    pub score: f64,
+    /// Position by score, 1 being the most relevant; ties keep insertion order.
+    pub rank: usize,
+    pub status: InjectionStatus,
+}
+
+/// A turn's memory context and how each memory ended up in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySelection {
+    pub context: String,
+    /// Every memory, in rank order.
+    pub memories: Vec<InjectedMemory>,
+}
+
+fn words(text: &str) -> AHashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Cosine similarity of the word sets of `entry` (key and value) and
+/// `query`: 1 when they share every word, 0 when they share none.
+pub fn relevance(entry: &MemoryEntry, query: &str) -> f64 {
+    cosine(&words(&format!("{} {}", entry.key, entry.value)), &words(query))
+}
+
+fn cosine(a: &AHashSet<String>, b: &AHashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / ((a.len() * b.len()) as f64).sqrt()
+}
+
+/// How alike two memories are, 0–1: the mean of the word similarity of
+/// their keys (so `home_city` and `Home city` match) and of their values.
+pub fn similarity(a: &MemoryEntry, b: &MemoryEntry) -> f64 {
+    let key = cosine(&words(&a.key), &words(&b.key));
+    let value = cosine(&words(&a.value), &words(&b.value));
+    (key + value) / 2.0
+}
+
+/// Memories that look like the same fact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Keys in the order the memories were given.
+    pub keys: Vec<String>,
+    /// Highest [`similarity`] between two members.
+    pub score: f64,
+}
+
+/// Groups memories whose [`similarity`] reaches `threshold`, transitively;
+/// memories without a match are left out.
+pub fn find_duplicates(entries: &[MemoryEntry], threshold: f64) -> Vec<DuplicateGroup> {
+    let mut group_of: Vec<usize> = (0..entries.len()).collect();
+    let mut best = vec![0.0_f64; entries.len()];
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            let score = similarity(&entries[i], &entries[j]);
+            if score < threshold {
+                continue;
+            }
+            let (from, to) = (group_of[j], group_of[i]);
+            for g in group_of.iter_mut().filter(|g| **g == from) {
+                *g = to;
+            }
+            best[to] = best[to].max(best[from]).max(score);
+        }
+    }
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    for (root, score) in best.iter().enumerate().filter(|(_, s)| **s > 0.0) {
+        let keys: Vec<String> = (0..entries.len())
+            .filter(|&i| group_of[i] == root)
+            .map(|i| entries[i].key.clone())
+            .collect();
+        if keys.len() > 1 {
+            groups.push(DuplicateGroup { keys, score: *score });
+        }
+    }
+    groups
+}
+
+/// Which value a merged memory keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// The first memory's value.
+    #[default]
+    KeepFirst,
+    /// The longest value.
+    KeepLongest,
+    /// Every distinct value, one per line.
+    Combine,
+}
+
+/// Consolidates `entries` into one memory under the first one's key and
+/// tier. Tags are united; it expires only when all of them do, at the
+/// latest expiry. `None` when `entries` is empty.
+pub fn merge_entries(entries: &[MemoryEntry], strategy: MergeStrategy) -> Option<MemoryEntry> {
+    let first = entries.first()?;
+    let value = match strategy {
+        MergeStrategy::KeepFirst => first.value.clone(),
+        MergeStrategy::KeepLongest => entries
+            .iter()
+            .map(|e| &e.value)
+            .fold(&first.value, |longest, v| if v.len() > longest.len() { v } else { longest })
+            .clone(),
+        MergeStrategy::Combine => {
+            let mut values: Vec<&str> = Vec::new();
+            for e in entries {
+                if !values.contains(&e.value.as_str()) {
+                    values.push(&e.value);
+                }
+            }
+            values.join("\n")
+        }
+    };
+    let expires_at = entries
+        .iter()
+        .map(|e| e.expires_at)
+        .try_fold(i64::MIN, |latest, at| at.map(|at| latest.max(at)));
+    Some(MemoryEntry {
+        key: first.key.clone(),
+        value,
+        tier: first.tier,
+        tags: normalize_tags(entries.iter().flat_map(|e| e.tags.iter().map(String::as_str))),
+        expires_at,
+    })
+}
+
+fn value_hash(value: &str) -> u64 {
+    let mut hasher = ahash::AHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
+#[derive(Default)]
 pub struct MemoryManager {
     entries: Vec<MemoryEntry>,
 }
@@ -34,64 +264,176 @@ impl MemoryManager {
         self.entries.push(entry);
     }
 
-    pub fn select_and_trim(&self, tier: MemoryTier, budget_tokens: u32) -> String {
-        if tier == MemoryTier::None || budget_tokens == 0 {
-            return String::new();
-        }
+    /// `key:value` lines for the memories that fit the tier's share of
+    /// `budget_tokens`, counted with `model`'s tokenizer.
+    pub fn select_and_trim(&self, tier: MemoryTier, budget_tokens: u32, model: &str) -> String {
+        let mut result = String::new();
+        fill(&mut result, self.entries.iter(), tier_budget(tier, budget_tokens), model);
+        result
+    }
 
-        let effective_budget = match tier {
-            MemoryTier::None => return String::new(),
-            MemoryTier::CompressedSummary => budget_tokens / 4,
-            MemoryTier::Delta => budget_tokens / 2,
-            MemoryTier::Full => budget_tokens,
-        };
+    /// Like [`select_and_trim`](Self::select_and_trim) for the next turn of
+    /// a session whose history already contains the memories recorded in
+    /// `state`: those are only named in a header, and just new or changed
+    /// entries are written out. Entries that had to be truncated are not
+    /// recorded, so they are sent again in full next turn.
+    pub fn select_for_turn(
+        &self,
+        tier: MemoryTier,
+        budget_tokens: u32,
+        model: &str,
+        state: &mut MemoryTurnState,
+    ) -> String {
+        self.select_ranked_for_turn(tier, budget_tokens, model, "", state).context
+    }
 
-        let max_chars = (effective_budget as usize) * 4;
-        let mut result = String::with_capacity(max_chars.min(4096));
+    /// [`select_for_turn`](Self::select_for_turn) with the new or changed
+    /// memories ordered by [`relevance`] to `query` (the turn's user message),
+    /// so the most relevant ones are written first when the budget is tight.
+    /// Also reports what happened to every memory.
+    pub fn select_ranked_for_turn(
+        &self,
+        tier: MemoryTier,
+        budget_tokens: u32,
+        model: &str,
+        query: &str,
+        state: &mut MemoryTurnState,
+    ) -> MemorySelection {
+        let budget = tier_budget(tier, budget_tokens);
+        let mut ranked: Vec<(&MemoryEntry, f64)> =
+            self.entries.iter().map(|e| (e, relevance(e, query))).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let explain = |status: &dyn Fn(&MemoryEntry) -> InjectionStatus| {
+            ranked
+                .iter()
+                .enumerate()
+                .map(|(i, (e, score))| InjectedMemory {
+                    key: e.key.clone(),
+                    score: *score,
+                    rank: i + 1,
+                    status: status(e),
+                })
+                .collect()
+        };
+        if budget == 0 {
+            return MemorySelection {
+                context: String::new(),
+                memories: explain(&|_| InjectionStatus::Omitted),
+            };
+        }
+        // Drop memories deleted since, so the header doesn't name them.
+        state.sent.retain(|(k, _)| self.entries.iter().any(|e| e.key == *k));
 
-        for entry in &self.entries {
-            let segment = format!("{}:{}\n", entry.key, entry.value);
-            if result.len() + segment.len() > max_chars {
-                let remaining = max_chars.saturating_sub(result.len());
-                if remaining > 0 {
-                    let safe = safe_truncate(&segment, remaining);
-                    result.push_str(safe);
-                }
-                break;
-            }
-            result.push_str(&segment);
+        let current: Vec<&MemoryEntry> = self.entries.iter().filter(|e| state.is_current(e)).collect();
+        let fresh: Vec<&MemoryEntry> = ranked.iter().map(|(e, _)| *e).filter(|e| !state.is_current(e)).collect();
+        let mut result = String::new();
+        if !current.is_empty() {
+            let keys: Vec<&str> = current.iter().map(|e| e.key.as_str()).collect();
+            let header = format!("(memories from earlier turns still apply: {})\n", keys.join(", "));
+            result.push_str(truncate_to_tokens(&header, budget, model));
         }
-        result
+        let (complete, truncated) = fill(&mut result, fresh.iter().copied(), budget, model);
+        let memories = explain(&|e| match fresh.iter().position(|f| std::ptr::eq(*f, e)) {
+            None => InjectionStatus::Carried,
+            Some(i) if i < complete => InjectionStatus::Injected,
+            Some(i) if i == complete && truncated => InjectionStatus::Truncated,
+            Some(_) => InjectionStatus::Omitted,
+        });
+        for entry in &fresh[..complete] {
+            state.mark_sent(entry);
+        }
+        MemorySelection { context: result, memories }
     }
 
     pub fn entries(&self) -> &[MemoryEntry] {
         &self.entries
     }
+
+    /// Removes the memories expired at `now` (Unix seconds) and returns
+    /// them, so the caller can archive them.
+    pub fn sweep_expired(&mut self, now: i64) -> Vec<MemoryEntry> {
+        let (expired, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|e| e.is_expired(now));
+        self.entries = kept;
+        expired
+    }
+}
+
+/// Tokens of `budget_tokens` the tier may spend on memories.
+fn tier_budget(tier: MemoryTier, budget_tokens: u32) -> u32 {
+    match tier {
+        MemoryTier::None => 0,
+        MemoryTier::CompressedSummary => budget_tokens / 4,
+        MemoryTier::Delta => budget_tokens / 2,
+        MemoryTier::Full => budget_tokens,
+    }
+}
+
+/// Appends `key:value` lines until `budget` tokens of `model`, truncating
+/// the first entry that doesn't fit. Returns how many entries were written
+/// in full, and whether part of the next one was written.
+fn fill<'a>(
+    result: &mut String,
+    entries: impl Iterator<Item = &'a MemoryEntry>,
+    budget: u32,
+    model: &str,
+) -> (usize, bool) {
+    let mut used = count_tokens(model, result);
+    let mut complete = 0;
+    for entry in entries {
+        let segment = format!("{}:{}\n", entry.key, entry.value);
+        let tokens = count_tokens(model, &segment);
+        if used + tokens > budget {
+            let part = truncate_to_tokens(&segment, budget.saturating_sub(used), model);
+            result.push_str(part);
+            return (complete, !part.is_empty());
+        }
+        result.push_str(&segment);
+        used += tokens;
+        complete += 1;
+    }
+    (complete, false)
 }
 
-fn safe_truncate(s: &str, max_bytes: usize) -> &str {
-    if s.len() <= max_bytes {
+/// The longest prefix of `s`, cut at a char boundary, that is at most
+/// `budget` tokens of `model`.
+fn truncate_to_tokens<'a>(s: &'a str, budget: u32, model: &str) -> &'a str {
+    if count_tokens(model, s) <= budget {
         return s;
     }
-    let mut end = max_bytes;
-    while end > 0 && !s.is_char_boundary(end) {
-        end -= 1;
+    let bounds: Vec<usize> = s.char_indices().map(|(i, _)| i).collect();
+    // Counts grow with the prefix (up to merges at the cut), so bisect.
+    let (mut lo, mut hi) = (0, bounds.len() - 1);
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if count_tokens(model, &s[..bounds[mid]]) <= budget {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
     }
-    &s[..end]
+    &s[..bounds[lo]]
 }
 
 pub fn estimate_memory_tokens(text: &str) -> u32 {
-    (text.len() / 4) as u32
+    estimate_memory_tokens_for(crate::tokenizer::DEFAULT_ESTIMATION_MODEL, text)
+}
+
+pub fn estimate_memory_tokens_for(model: &str, text: &str) -> u32 {
+    count_tokens(model, text)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const MODEL: &str = "gpt-4o";
+
     #[test]
     fn none_returns_empty() {
         let mgr = MemoryManager::new();
-        assert!(mgr.select_and_trim(MemoryTier::None, 1000).is_empty());
+        assert!(mgr.select_and_trim(MemoryTier::None, 1000, MODEL).is_empty());
     }
 
     #[test]
@@ -101,9 +443,12 @@ mod tests {
             key: "k".into(),
             value: "x".repeat(2000),
             tier: MemoryTier::Full,
+            tags: Vec::new(),
+            expires_at: None,
         });
-        let result = mgr.select_and_trim(MemoryTier::Full, 100);
-        assert!(result.len() <= 400);
+        let result = mgr.select_and_trim(MemoryTier::Full, 100, MODEL);
+        assert!(!result.is_empty());
+        assert!(count_tokens(MODEL, &result) <= 100);
     }
 
     #[test]
@@ -113,8 +458,134 @@ mod tests {
             key: "k".into(),
             value: "x".repeat(2000),
             tier: MemoryTier::CompressedSummary,
+            tags: Vec::new(),
+            expires_at: None,
         });
-        let result = mgr.select_and_trim(MemoryTier::CompressedSummary, 400);
-        assert!(result.len() <= 400);
+        let result = mgr.select_and_trim(MemoryTier::CompressedSummary, 400, MODEL);
+        assert!(!result.is_empty());
+        assert!(count_tokens(MODEL, &result) <= 100);
+    }
+
+    fn entry(key: &str, value: &str) -> MemoryEntry {
+        MemoryEntry {
+            key: key.into(),
+            value: value.into(),
+            tier: MemoryTier::Full,
+            tags: Vec::new(),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn later_turns_only_carry_new_or_changed_memories() {
+        let mut mgr = MemoryManager::new();
+        mgr.add(entry("name", "Ada"));
+        mgr.add(entry("lang", "Rust"));
+        let mut state = MemoryTurnState::new();
+
+        let first = mgr.select_for_turn(MemoryTier::Full, 1000, MODEL, &mut state);
+        assert_eq!(first, mgr.select_and_trim(MemoryTier::Full, 1000, MODEL));
+
+        let unchanged = mgr.select_for_turn(MemoryTier::Full, 1000, MODEL, &mut state);
+        assert_eq!(unchanged, "(memories from earlier turns still apply: name, lang)\n");
+
+        mgr.entries[1].value = "Go".into();
+        mgr.add(entry("city", "Paris"));
+        let changed = mgr.select_for_turn(MemoryTier::Full, 1000, MODEL, &mut state);
+        assert_eq!(changed, "(memories from earlier turns still apply: name)\nlang:Go\ncity:Paris\n");
+
+        mgr.entries.remove(0);
+        let removed = mgr.select_for_turn(MemoryTier::Full, 1000, MODEL, &mut state);
+        assert_eq!(removed, "(memories from earlier turns still apply: lang, city)\n");
+        assert_eq!(state.sent_keys().collect::<Vec<_>>(), ["lang", "city"]);
+    }
+
+    #[test]
+    fn truncated_memories_are_resent() {
+        let mut mgr = MemoryManager::new();
+        mgr.add(entry("short", "a"));
+        mgr.add(entry("long", &"x".repeat(100)));
+        let mut state = MemoryTurnState::new();
+
+        mgr.select_for_turn(MemoryTier::Full, 10, MODEL, &mut state);
+        assert_eq!(state.sent_keys().collect::<Vec<_>>(), ["short"]);
+        let next = mgr.select_for_turn(MemoryTier::Full, 1000, MODEL, &mut state);
+        assert!(next.ends_with(&format!("long:{}\n", "x".repeat(100))));
+    }
+
+    #[test]
+    fn ranks_memories_by_relevance_and_explains_them() {
+        let mut mgr = MemoryManager::new();
+        mgr.add(entry("diet", "vegetarian, no nuts"));
+        mgr.add(entry("home_city", "Lisbon"));
+        mgr.add(entry("employer", "Acme Corp, works on billing"));
+        let mut state = MemoryTurnState::new();
+
+        // Budget for one entry: the relevant one goes first, the next is cut.
+        let first = mgr.select_ranked_for_turn(MemoryTier::Full, 10, MODEL, "restaurants in Lisbon?", &mut state);
+        assert!(first.context.starts_with("home_city:Lisbon\n"), "{}", first.context);
+        let m = &first.memories;
+        assert_eq!((m[0].key.as_str(), m[0].rank, m[0].status), ("home_city", 1, InjectionStatus::Injected));
+        assert!(m[0].score > 0.0 && m[1].score == 0.0);
+        assert_eq!(m[1].status, InjectionStatus::Truncated);
+        assert_eq!(m[2].status, InjectionStatus::Omitted);
+
+        let second = mgr.select_ranked_for_turn(MemoryTier::Full, 1000, MODEL, "billing question", &mut state);
+        assert_eq!(second.memories[0].key, "employer");
+        let city = second.memories.iter().find(|m| m.key == "home_city").unwrap();
+        assert_eq!(city.status, InjectionStatus::Carried);
+    }
+
+    #[test]
+    fn tags_are_normalized_and_filter_entries() {
+        assert_eq!(normalize_tags([" Work ", "side  project", "work", ""]), ["side-project", "work"]);
+
+        let mut tagged = entry("deadline", "Friday");
+        tagged.tags = vec!["Work".into(), "billing".into()];
+        assert!(tagged.has_tags(&[]));
+        assert!(tagged.has_tags(&["work".into()]));
+        assert!(tagged.has_tags(&["BILLING".into(), "work".into()]));
+        assert!(!tagged.has_tags(&["work".into(), "home".into()]));
+        assert!(!entry("name", "Ada").has_tags(&["work".into()]));
+    }
+
+    #[test]
+    fn expired_memories_are_swept() {
+        let mut mgr = MemoryManager::new();
+        let mut trip = entry("travel", "in Tokyo next week");
+        trip.expires_at = Some(1_060);
+        mgr.add(trip);
+        mgr.add(entry("name", "Ada"));
+
+        assert!(mgr.sweep_expired(1_059).is_empty());
+        let swept = mgr.sweep_expired(1_060);
+        assert_eq!(swept.len(), 1);
+        assert_eq!(swept[0].key, "travel");
+        assert_eq!(mgr.entries().len(), 1);
+        assert_eq!(mgr.entries()[0].key, "name");
+    }
+
+    #[test]
+    fn finds_and_merges_duplicates() {
+        let mut city = entry("home_city", "Lisbon");
+        city.tags = vec!["personal".into()];
+        let mut again = entry("Home city", "lives in Lisbon");
+        again.tags = vec!["travel".into()];
+        let entries = [city, entry("employer", "Acme"), again];
+
+        let groups = find_duplicates(&entries, 0.6);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].keys, ["home_city", "Home city"]);
+        assert!(groups[0].score >= 0.6);
+        assert!(find_duplicates(&entries, 0.99).is_empty());
+
+        let dupes = [entries[0].clone(), entries[2].clone()];
+        let merged = merge_entries(&dupes, MergeStrategy::KeepLongest).unwrap();
+        assert_eq!((merged.key.as_str(), merged.value.as_str()), ("home_city", "lives in Lisbon"));
+        assert_eq!(merged.tags, ["personal", "travel"]);
+        assert_eq!(merged.expires_at, None);
+        let combined = merge_entries(&dupes, MergeStrategy::Combine).unwrap();
+        assert_eq!(combined.value, "Lisbon\nlives in Lisbon");
+        assert!(merge_entries(&[], MergeStrategy::KeepFirst).is_none());
     }
 }
@@ -0,0 +1,210 @@
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{InjectedMemory, InjectionStatus, MemoryTier};
+
+/// Turns kept per session; older ones are dropped first.
+pub const MAX_TURNS_PER_SESSION: usize = 500;
+
+/// The memories behind one turn of a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnRecord {
+    /// 0-based turn number within the session.
+    pub turn: usize,
+    /// The user message the memories were ranked against.
+    pub query: String,
+    pub tier: MemoryTier,
+    pub budget_tokens: u32,
+    /// Every memory with its score, rank and status, in rank order.
+    pub memories: Vec<InjectedMemory>,
+}
+
+impl TurnRecord {
+    /// Memories the model saw this turn, in full or named as still applying.
+    pub fn in_context(&self) -> impl Iterator<Item = &InjectedMemory> {
+        self.memories.iter().filter(|m| {
+            matches!(
+                m.status,
+                InjectionStatus::Injected | InjectionStatus::Carried | InjectionStatus::Truncated
+            )
+        })
+    }
+}
+
+/// How often one memory was in a session's context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryAccess {
+    pub key: String,
+    /// Turns it was in context, counting carried and truncated turns.
+    pub turns: u32,
+    pub last_turn: usize,
+    pub best_score: f64,
+}
+
+#[derive(Debug, Default)]
+struct SessionLog {
+    next_turn: usize,
+    turns: Vec<TurnRecord>,
+}
+
+/// Records, per session and turn, which memories were put into the context.
+#[derive(Debug, Default)]
+pub struct MemoryAccessLog {
+    sessions: AHashMap<String, SessionLog>,
+}
+
+impl MemoryAccessLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the next turn of `session_id` and returns its number.
+    pub fn record(
+        &mut self,
+        session_id: &str,
+        query: &str,
+        tier: MemoryTier,
+        budget_tokens: u32,
+        memories: Vec<InjectedMemory>,
+    ) -> usize {
+        let log = self.sessions.entry(session_id.to_owned()).or_default();
+        let turn = log.next_turn;
+        log.next_turn += 1;
+        if log.turns.len() >= MAX_TURNS_PER_SESSION {
+            log.turns.remove(0);
+        }
+        log.turns.push(TurnRecord {
+            turn,
+            query: query.to_owned(),
+            tier,
+            budget_tokens,
+            memories,
+        });
+        turn
+    }
+
+    /// The record of `turn`, or of the latest turn when `None`.
+    pub fn explain(&self, session_id: &str, turn: Option<usize>) -> Option<&TurnRecord> {
+        let turns = &self.sessions.get(session_id)?.turns;
+        match turn {
+            Some(n) => turns.iter().find(|t| t.turn == n),
+            None => turns.last(),
+        }
+    }
+
+    /// Memories that were in context in `session_id`, most used first.
+    pub fn access(&self, session_id: &str) -> Vec<MemoryAccess> {
+        let Some(log) = self.sessions.get(session_id) else {
+            return Vec::new();
+        };
+        let mut by_key: AHashMap<&str, MemoryAccess> = AHashMap::new();
+        for record in &log.turns {
+            for m in record.in_context() {
+                let access = by_key.entry(m.key.as_str()).or_insert_with(|| MemoryAccess {
+                    key: m.key.clone(),
+                    turns: 0,
+                    last_turn: record.turn,
+                    best_score: 0.0,
+                });
+                access.turns += 1;
+                access.last_turn = record.turn;
+                access.best_score = access.best_score.max(m.score);
+            }
+        }
+        let mut access: Vec<MemoryAccess> = by_key.into_values().collect();
+        access.sort_by(|a, b| b.turns.cmp(&a.turns).then_with(|| a.key.cmp(&b.key)));
+        access
+    }
+
+    /// Files `from`'s records under `to` in every session, after the two
+    /// memories were merged, so `to`'s access stats include both. In turns
+    /// that had both, the better-placed record is kept.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        for record in self.sessions.values_mut().flat_map(|log| log.turns.iter_mut()) {
+            let Some(i) = record.memories.iter().position(|m| m.key == from) else {
+                continue;
+            };
+            match record.memories.iter().position(|m| m.key == to) {
+                Some(j) if record.memories[j].rank <= record.memories[i].rank => {
+                    record.memories.remove(i);
+                }
+                Some(j) => {
+                    record.memories.remove(j);
+                    let i = if j < i { i - 1 } else { i };
+                    record.memories[i].key = to.to_owned();
+                }
+                None => record.memories[i].key = to.to_owned(),
+            }
+        }
+    }
+
+    /// Forgets `session_id`'s records.
+    pub fn clear(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryEntry, MemoryManager, MemoryTurnState};
+
+    #[test]
+    fn explains_turns_and_counts_access() {
+        let mut mgr = MemoryManager::new();
+        for (key, value) in [("name", "Ada"), ("lang", "Rust"), ("city", "Paris")] {
+            mgr.add(MemoryEntry {
+                key: key.into(),
+                value: value.into(),
+                tier: MemoryTier::Full,
+                tags: Vec::new(),
+                expires_at: None,
+            });
+        }
+        let mut state = MemoryTurnState::new();
+        let mut log = MemoryAccessLog::new();
+
+        for query in ["what does Ada write, Rust?", "weather in Paris"] {
+            let sel = mgr.select_ranked_for_turn(MemoryTier::Full, 1000, "gpt-4o", query, &mut state);
+            log.record("s1", query, MemoryTier::Full, 1000, sel.memories);
+        }
+
+        let first = log.explain("s1", Some(0)).expect("turn 0");
+        assert_eq!(first.query, "what does Ada write, Rust?");
+        assert!(first.memories.iter().all(|m| m.status == InjectionStatus::Injected));
+        let latest = log.explain("s1", None).expect("latest turn");
+        assert_eq!(latest.turn, 1);
+        assert_eq!(latest.memories[0].key, "city");
+        assert!(latest.memories.iter().all(|m| m.status == InjectionStatus::Carried));
+        assert!(log.explain("s1", Some(7)).is_none());
+        assert!(log.explain("other", None).is_none());
+
+        let access = log.access("s1");
+        assert_eq!(access.len(), 3);
+        assert!(access.iter().all(|a| a.turns == 2 && a.last_turn == 1));
+        assert!(access.iter().find(|a| a.key == "city").unwrap().best_score > 0.0);
+
+        log.rename("lang", "name");
+        let access = log.access("s1");
+        assert_eq!(access.len(), 2);
+        assert_eq!(access.iter().find(|a| a.key == "name").unwrap().turns, 2);
+
+        log.clear("s1");
+        assert_eq!(log.session_count(), 0);
+    }
+
+    #[test]
+    fn keeps_turn_numbers_after_dropping_old_turns() {
+        let mut log = MemoryAccessLog::new();
+        for _ in 0..MAX_TURNS_PER_SESSION + 3 {
+            log.record("s", "q", MemoryTier::Delta, 100, Vec::new());
+        }
+        assert!(log.explain("s", Some(2)).is_none());
+        assert_eq!(log.explain("s", Some(3)).map(|t| t.turn), Some(3));
+        assert_eq!(log.explain("s", None).map(|t| t.turn), Some(MAX_TURNS_PER_SESSION + 2));
+    }
+}
@@ -0,0 +1,180 @@
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use ahash::AHashMap;
+use serde::Serialize;
+
+use crate::skill::{PromptVariant, SkillDefinition};
+
+/// Result of one uncached call made with a prompt variant.
+#[derive(Debug, Clone, Copy)]
+pub struct VariantOutcome {
+    /// The response parsed and passed the output schema.
+    pub passed: bool,
+    pub total_tokens: u32,
+    pub latency: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct VariantStats {
+    runs: u32,
+    passes: u32,
+    pass_tokens: u64,
+    latency_ms: u64,
+}
+
+/// Aggregated outcomes of one variant of a skill.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantReport {
+    pub variant_id: String,
+    pub runs: u32,
+    pub passes: u32,
+    pub pass_rate: f64,
+    /// Mean total tokens of passing calls (failed calls report no usage).
+    pub avg_tokens: f64,
+    pub avg_latency_ms: f64,
+}
+
+/// Samples prompt variants and records how each one performs, keyed by
+/// skill and variant ID.
+#[derive(Debug, Default)]
+pub struct PromptVariantStore {
+    draws: AHashMap<String, u64>,
+    stats: AHashMap<(String, String), VariantStats>,
+}
+
+impl PromptVariantStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks a variant of `skill` by weight, or `None` when it declares no
+    /// variant with a non-zero weight. The draw is a hash of the skill ID and
+    /// a per-skill counter, so a sequence of runs is reproducible.
+    pub fn sample<'a>(&mut self, skill: &'a SkillDefinition) -> Option<&'a PromptVariant> {
+        let total: u64 = skill.prompt_variants.iter().map(|v| u64::from(v.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let draw = self.draws.entry(skill.id.clone()).or_default();
+        let mut hasher = ahash::AHasher::default();
+        skill.id.hash(&mut hasher);
+        draw.hash(&mut hasher);
+        *draw += 1;
+
+        let mut point = hasher.finish() % total;
+        skill.prompt_variants.iter().find(|v| {
+            let weight = u64::from(v.weight);
+            if point < weight {
+                return true;
+            }
+            point -= weight;
+            false
+        })
+    }
+
+    pub fn record(&mut self, skill_id: &str, variant_id: &str, outcome: VariantOutcome) {
+        let stats = self
+            .stats
+            .entry((skill_id.to_owned(), variant_id.to_owned()))
+            .or_default();
+        stats.runs += 1;
+        stats.latency_ms += outcome.latency.as_millis() as u64;
+        if outcome.passed {
+            stats.passes += 1;
+            stats.pass_tokens += u64::from(outcome.total_tokens);
+        }
+    }
+
+    /// Recorded variants of `skill_id`, best first: highest pass rate, then
+    /// fewest tokens.
+    pub fn report(&self, skill_id: &str) -> Vec<VariantReport> {
+        let mut report: Vec<VariantReport> = self
+            .stats
+            .iter()
+            .filter(|((skill, _), _)| skill == skill_id)
+            .map(|((_, variant), s)| VariantReport {
+                variant_id: variant.clone(),
+                runs: s.runs,
+                passes: s.passes,
+                pass_rate: s.passes as f64 / s.runs.max(1) as f64,
+                avg_tokens: s.pass_tokens as f64 / s.passes.max(1) as f64,
+                avg_latency_ms: s.latency_ms as f64 / s.runs.max(1) as f64,
+            })
+            .collect();
+        report.sort_by(|a, b| {
+            b.pass_rate
+                .total_cmp(&a.pass_rate)
+                .then(a.avg_tokens.total_cmp(&b.avg_tokens))
+                .then_with(|| a.variant_id.cmp(&b.variant_id))
+        });
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skill::{JsonSchema, SkillExecutionMode};
+    use serde_json::json;
+
+    fn skill(variants: &[(&str, u32)]) -> SkillDefinition {
+        SkillDefinition {
+            id: "summarize".into(),
+            input_schema: JsonSchema::new(json!({"type": "object"})),
+            output_schema: JsonSchema::new(json!({"type": "object"})),
+            execution_mode: SkillExecutionMode::LLM,
+            max_output_tokens: 100,
+            compact_keys: None,
+            prompt_variants: variants
+                .iter()
+                .map(|(id, weight)| PromptVariant {
+                    id: (*id).into(),
+                    system_prompt: format!("Prompt {id}."),
+                    weight: *weight,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn samples_by_weight() {
+        let mut store = PromptVariantStore::new();
+        assert!(store.sample(&skill(&[])).is_none());
+        assert!(store.sample(&skill(&[("a", 0)])).is_none());
+
+        let s = skill(&[("a", 1), ("b", 1), ("retired", 0)]);
+        let mut counts = AHashMap::new();
+        for _ in 0..200 {
+            let v = store.sample(&s).expect("should pick a variant");
+            *counts.entry(v.id.as_str()).or_insert(0) += 1;
+        }
+        assert!(counts.get("retired").is_none());
+        assert!(counts["a"] > 50 && counts["b"] > 50, "{counts:?}");
+    }
+
+    #[test]
+    fn reports_best_variant_first() {
+        let mut store = PromptVariantStore::new();
+        let outcome = |passed, total_tokens, ms| VariantOutcome {
+            passed,
+            total_tokens,
+            latency: Duration::from_millis(ms),
+        };
+        store.record("summarize", "a", outcome(true, 100, 20));
+        store.record("summarize", "a", outcome(false, 0, 40));
+        store.record("summarize", "b", outcome(true, 80, 10));
+        store.record("other", "c", outcome(true, 10, 10));
+
+        let report = store.report("summarize");
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].variant_id, "b");
+        assert_eq!(report[1].pass_rate, 0.5);
+        assert_eq!(report[1].avg_tokens, 100.0);
+        assert_eq!(report[1].avg_latency_ms, 30.0);
+    }
+}
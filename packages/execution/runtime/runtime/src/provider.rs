@@ -13,6 +13,9 @@ pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens the provider served from its prompt cache (billed at a
+    /// discount); 0 when the provider doesn't report it.
+    pub cached_prompt_tokens: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +49,81 @@ pub fn model_cost_per_1k(model: &str) -> f64 {
     }
 }
 
+/// List price of a model in USD per 1k tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+impl ModelPricing {
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.input_per_1k + completion_tokens as f64 * self.output_per_1k) / 1000.0
+    }
+}
+
+/// `(model-name prefix, input, output)`; the first matching prefix wins, so
+/// longer names come before the ones they extend.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.00015, 0.0006),
+    ("gpt-4o", 0.0025, 0.01),
+    ("gpt-4.1-nano", 0.0001, 0.0004),
+    ("gpt-4.1-mini", 0.0004, 0.0016),
+    ("gpt-4.1", 0.002, 0.008),
+    ("gpt-4-turbo", 0.01, 0.03),
+    ("gpt-3.5-turbo", 0.0005, 0.0015),
+    ("o1-mini", 0.0011, 0.0044),
+    ("o3-mini", 0.0011, 0.0044),
+    ("o4-mini", 0.0011, 0.0044),
+    ("o1", 0.015, 0.06),
+    ("o3", 0.002, 0.008),
+    ("claude-3-5-haiku", 0.0008, 0.004),
+    ("claude-3-haiku", 0.00025, 0.00125),
+    ("claude-3-opus", 0.015, 0.075),
+    ("claude-opus", 0.015, 0.075),
+    ("claude-3-5-sonnet", 0.003, 0.015),
+    ("claude-3-7-sonnet", 0.003, 0.015),
+    ("claude-sonnet", 0.003, 0.015),
+    ("gemini-1.5-flash", 0.000075, 0.0003),
+    ("gemini-1.5-pro", 0.00125, 0.005),
+    ("gemini-2.0-flash", 0.0001, 0.0004),
+    ("gemini-2.5-flash", 0.0003, 0.0025),
+    ("gemini-2.5-pro", 0.00125, 0.01),
+    ("command-r-plus", 0.0025, 0.01),
+    ("command-r", 0.00015, 0.0006),
+    ("mistral-large", 0.002, 0.006),
+    ("mistral-small", 0.0002, 0.0006),
+    ("deepseek-chat", 0.00027, 0.0011),
+    ("deepseek-reasoner", 0.00055, 0.00219),
+    ("grok-2", 0.002, 0.01),
+    ("llama3-8b", 0.00005, 0.00008),
+    ("sonar", 0.001, 0.001),
+];
+
+/// Input and output price of `model`. Vendor-qualified names
+/// (`openai/gpt-4o`, Bedrock's `anthropic.claude-…`) are priced as the bare
+/// model; unknown models fall back to [`model_cost_per_1k`] for both.
+pub fn model_pricing(model: &str) -> ModelPricing {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let name = name.split_once("anthropic.").map_or(name, |(_, rest)| rest);
+    match PRICES.iter().find(|(prefix, _, _)| name.starts_with(prefix)) {
+        Some(&(_, input_per_1k, output_per_1k)) => ModelPricing { input_per_1k, output_per_1k },
+        None => {
+            let price = model_cost_per_1k(model);
+            ModelPricing { input_per_1k: price, output_per_1k: price }
+        }
+    }
+}
+
+/// Fraction of the input price saved on prompt-cache hits.
+pub fn cached_token_discount(model: &str) -> f64 {
+    if model.starts_with("claude") {
+        0.9
+    } else {
+        0.5
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,4 +134,18 @@ mod tests {
         assert!((model_cost_per_1k("gpt-4o-mini") - 0.00015).abs() < f64::EPSILON);
         assert!((model_cost_per_1k("unknown") - 0.005).abs() < f64::EPSILON);
     }
+
+    #[test]
+    fn pricing_lookup() {
+        assert!((model_pricing("gpt-4o-mini").input_per_1k - 0.00015).abs() < f64::EPSILON);
+        assert!((model_pricing("gpt-4o-2024-08-06").output_per_1k - 0.01).abs() < f64::EPSILON);
+        assert!((model_pricing("o3-mini").input_per_1k - 0.0011).abs() < f64::EPSILON);
+        assert_eq!(
+            model_pricing("anthropic.claude-3-5-haiku-20241022-v1:0"),
+            model_pricing("claude-3-5-haiku-20241022")
+        );
+        assert!((model_pricing("openai/gpt-4.1-mini").input_per_1k - 0.0004).abs() < f64::EPSILON);
+        assert!((model_pricing("unknown").output_per_1k - 0.005).abs() < f64::EPSILON);
+        assert!((model_pricing("gpt-4o").cost(1000, 1000) - 0.0125).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,142 @@
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+
+/// One intermediate value in a skill's post-processing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceStage {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+/// Everything that went into and came out of one skill call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillTrace {
+    pub skill_id: String,
+    pub model: String,
+    /// System prompt exactly as sent.
+    pub system_prompt: String,
+    /// Prompt variant sampled for this call, if the skill declares any.
+    #[serde(default)]
+    pub prompt_variant: Option<String>,
+    /// Dependency outputs selected for this skill, keyed by source skill.
+    pub input_delta: serde_json::Value,
+    /// User content sent to the model (the flattened delta).
+    pub input: serde_json::Value,
+    /// Unparsed model output; `None` for deterministic or cached calls.
+    pub raw_response: Option<String>,
+    pub cached: bool,
+    /// Post-processing in order: `validated`, `externalized`, `compressed`.
+    pub stages: Vec<TraceStage>,
+    /// Value handed to downstream skills.
+    pub output: serde_json::Value,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl SkillTrace {
+    pub fn stage(&self, name: &str) -> Option<&serde_json::Value> {
+        self.stages.iter().find(|s| s.name == name).map(|s| &s.value)
+    }
+}
+
+/// The skill calls of one agent run, in execution order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunTrace {
+    pub run_id: String,
+    pub steps: Vec<SkillTrace>,
+}
+
+impl RunTrace {
+    pub fn new(run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_owned(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn step(&self, skill_id: &str) -> Option<&SkillTrace> {
+        self.steps.iter().find(|s| s.skill_id == skill_id)
+    }
+
+    pub fn total_tokens(&self) -> u32 {
+        self.steps.iter().map(|s| s.total_tokens).sum()
+    }
+}
+
+/// Traces of recent runs, keyed by run ID. Only runs started with tracing
+/// enabled are recorded.
+#[derive(Debug, Default)]
+pub struct RunTraceStore {
+    runs: AHashMap<String, RunTrace>,
+}
+
+impl RunTraceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `trace`, replacing any earlier trace of the same run.
+    pub fn insert(&mut self, trace: RunTrace) {
+        self.runs.insert(trace.run_id.clone(), trace);
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<&RunTrace> {
+        self.runs.get(run_id)
+    }
+
+    pub fn inspect(&self, run_id: &str, skill_id: &str) -> Option<&SkillTrace> {
+        self.get(run_id)?.step(skill_id)
+    }
+
+    pub fn remove(&mut self, run_id: &str) -> Option<RunTrace> {
+        self.runs.remove(run_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn step(skill_id: &str, total_tokens: u32) -> SkillTrace {
+        SkillTrace {
+            skill_id: skill_id.into(),
+            model: "gpt-4o".into(),
+            system_prompt: "Agent.".into(),
+            prompt_variant: None,
+            input_delta: json!({}),
+            input: json!({"input": "start"}),
+            raw_response: Some("{}".into()),
+            cached: false,
+            stages: vec![TraceStage { name: "validated".into(), value: json!({}) }],
+            output: json!({}),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            total_tokens,
+        }
+    }
+
+    #[test]
+    fn inspects_steps_by_run_and_skill() {
+        let mut store = RunTraceStore::new();
+        let mut trace = RunTrace::new("run-1");
+        trace.steps.push(step("search", 80));
+        trace.steps.push(step("summarize", 40));
+        store.insert(trace);
+
+        assert_eq!(store.get("run-1").map(|t| t.total_tokens()), Some(120));
+        assert!(store.inspect("run-1", "summarize").is_some());
+        assert!(store.inspect("run-1", "missing").is_none());
+        assert!(store.inspect("run-2", "search").is_none());
+        assert_eq!(store.inspect("run-1", "search").and_then(|s| s.stage("validated")), Some(&json!({})));
+    }
+}
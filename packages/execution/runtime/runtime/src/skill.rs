@@ -6,18 +6,13 @@ pub enum SkillExecutionMode {
     LLM,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ResponseMode {
+    #[default]
     StrictJson,
     CompactJson,
 }
 
-impl Default for ResponseMode {
-    fn default() -> Self {
-        Self::StrictJson
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonSchema {
     pub schema: serde_json::Value,
@@ -58,6 +53,16 @@ impl JsonSchema {
         Ok(())
     }
 
+    /// Validates `value` against the whole schema, recursively: `type`,
+    /// `enum`, `const`, `required`, `properties`, `additionalProperties`,
+    /// `items`, `anyOf`, and the length and range bounds. Unlike
+    /// [`validate`](Self::validate), extra fields are only rejected when
+    /// `additionalProperties` is `false`. Other keywords (`$ref`, `format`,
+    /// `pattern`, ...) are ignored. Errors name the offending path.
+    pub fn validate_deep(&self, value: &serde_json::Value) -> Result<(), SchemaError> {
+        check(&self.schema, value, "$")
+    }
+
     pub fn strip_unknown_fields(&self, value: &mut serde_json::Value) {
         if let Some(obj_schema) = self.schema.as_object() {
             if let Some(props) = obj_schema.get("properties") {
@@ -71,11 +76,120 @@ impl JsonSchema {
     }
 
     pub fn estimate_tokens(&self) -> u32 {
+        self.estimate_tokens_for(crate::tokenizer::DEFAULT_ESTIMATION_MODEL)
+    }
+
+    pub fn estimate_tokens_for(&self, model: &str) -> u32 {
         let s = serde_json::to_string(&self.schema).unwrap_or_default();
-        (s.len() / 4) as u32
+        crate::tokenizer::count_tokens(model, &s)
+    }
+}
+
+fn type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
     }
 }
 
+fn has_type(value: &serde_json::Value, ty: &str) -> bool {
+    let actual = type_name(value);
+    actual == ty || (ty == "number" && actual == "integer")
+}
+
+fn check(schema: &serde_json::Value, value: &serde_json::Value, path: &str) -> Result<(), SchemaError> {
+    use serde_json::Value;
+    let Some(schema) = schema.as_object() else {
+        // `true` (or any non-object) accepts everything; `false` nothing.
+        return match schema {
+            Value::Bool(false) => Err(SchemaError::Constraint(format!("{path}: no value allowed"))),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let allowed: Vec<&str> = match ty {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            return Err(SchemaError::TypeMismatch(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            )));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            return Err(SchemaError::Constraint(format!("{path}: {value} is not one of {}", Value::Array(options.clone()))));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            return Err(SchemaError::Constraint(format!("{path}: expected {expected}")));
+        }
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        if !options.iter().any(|s| check(s, value, path).is_ok()) {
+            return Err(SchemaError::Constraint(format!("{path}: matches none of anyOf")));
+        }
+    }
+
+    let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+    match value {
+        Value::Object(obj) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                if let Some(field) = required.iter().filter_map(Value::as_str).find(|f| !obj.contains_key(*f)) {
+                    return Err(SchemaError::MissingField(format!("{path}.{field}")));
+                }
+            }
+            let props = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+            for (key, v) in obj {
+                let child = format!("{path}.{key}");
+                match (props.and_then(|p| p.get(key)), additional) {
+                    (Some(s), _) => check(s, v, &child)?,
+                    (None, Some(Value::Bool(false))) => return Err(SchemaError::UnknownField(child)),
+                    (None, Some(s)) => check(s, v, &child)?,
+                    (None, None) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as f64;
+            if bound("minItems").is_some_and(|m| len < m) || bound("maxItems").is_some_and(|m| len > m) {
+                return Err(SchemaError::Constraint(format!("{path}: {} items is out of bounds", items.len())));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{i}]"))?;
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count() as f64;
+            if bound("minLength").is_some_and(|m| len < m) || bound("maxLength").is_some_and(|m| len > m) {
+                return Err(SchemaError::Constraint(format!("{path}: length {len} is out of bounds")));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(0.0);
+            if bound("minimum").is_some_and(|m| n < m) || bound("maximum").is_some_and(|m| n > m) {
+                return Err(SchemaError::Constraint(format!("{path}: {n} is out of range")));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SchemaError {
     #[error("missing required field: {0}")]
@@ -84,6 +198,8 @@ pub enum SchemaError {
     UnknownField(String),
     #[error("type mismatch: {0}")]
     TypeMismatch(String),
+    #[error("constraint violated: {0}")]
+    Constraint(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,6 +211,23 @@ pub struct SkillDefinition {
     pub max_output_tokens: u32,
     #[serde(default)]
     pub compact_keys: Option<serde_json::Value>,
+    /// Alternative system prompts to A/B test. When non-empty, each LLM call
+    /// samples one by weight instead of using the agent's system instruction.
+    #[serde(default)]
+    pub prompt_variants: Vec<PromptVariant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptVariant {
+    pub id: String,
+    pub system_prompt: String,
+    /// Relative sampling weight; 0 retires the variant.
+    #[serde(default = "default_variant_weight")]
+    pub weight: u32,
+}
+
+fn default_variant_weight() -> u32 {
+    1
 }
 
 impl SkillDefinition {
@@ -135,6 +268,41 @@ mod tests {
         assert!(s.validate(&bad).is_err());
     }
 
+    #[test]
+    fn validates_nested_values() {
+        let s = JsonSchema::new(json!({
+            "type": "object",
+            "required": ["items"],
+            "additionalProperties": false,
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "qty"],
+                        "properties": {
+                            "name": { "type": "string", "minLength": 1 },
+                            "qty": { "type": "integer", "minimum": 1 },
+                            "unit": { "enum": ["kg", "pcs"] }
+                        }
+                    }
+                },
+                "note": { "type": ["string", "null"] }
+            }
+        }));
+        assert!(s.validate_deep(&json!({ "items": [{ "name": "apple", "qty": 3, "unit": "kg" }], "note": null })).is_ok());
+
+        let err = |v| s.validate_deep(&v).unwrap_err().to_string();
+        assert_eq!(err(json!({ "items": [{ "name": "apple" }] })), "missing required field: $.items[0].qty");
+        assert!(err(json!({ "items": [{ "name": "apple", "qty": 1.5 }] })).contains("$.items[0].qty: expected integer"));
+        assert!(err(json!({ "items": [{ "name": "a", "qty": 1, "unit": "lb" }] })).contains("$.items[0].unit"));
+        assert!(err(json!({ "items": [] })).contains("out of bounds"));
+        assert_eq!(err(json!({ "items": [{ "name": "a", "qty": 1 }], "x": 1 })), "unknown field: $.x");
+        // Without `additionalProperties: false`, extra fields pass.
+        assert!(s.validate_deep(&json!({ "items": [{ "name": "a", "qty": 1, "color": "red" }] })).is_ok());
+    }
+
     #[test]
     fn strips_unknown() {
         let s = test_schema();
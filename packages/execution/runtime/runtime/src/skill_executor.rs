@@ -20,6 +20,7 @@ pub enum SkillExecError {
     FreeTextRejected,
 }
 
+#[derive(Default)]
 pub struct SkillInputCache {
     cache: AHashMap<u64, serde_json::Value>,
 }
@@ -39,6 +40,10 @@ impl SkillInputCache {
         self.cache.insert(hash, value);
     }
 
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
     pub fn input_hash(skill_id: &str, input: &str) -> u64 {
         use std::hash::{Hash, Hasher};
         let mut hasher = ahash::AHasher::default();
@@ -48,9 +53,13 @@ impl SkillInputCache {
     }
 }
 
+type DeterministicHandler =
+    Box<dyn Fn(&serde_json::Value) -> Result<serde_json::Value, SkillExecError> + Send + Sync>;
+
+#[derive(Default)]
 pub struct SkillExecutor {
     cache: SkillInputCache,
-    deterministic_handlers: AHashMap<String, Box<dyn Fn(&serde_json::Value) -> Result<serde_json::Value, SkillExecError>>>,
+    deterministic_handlers: AHashMap<String, DeterministicHandler>,
 }
 
 impl SkillExecutor {
@@ -63,12 +72,18 @@ impl SkillExecutor {
 
     pub fn register_deterministic<F>(&mut self, skill_id: &str, handler: F)
     where
-        F: Fn(&serde_json::Value) -> Result<serde_json::Value, SkillExecError> + 'static,
+        F: Fn(&serde_json::Value) -> Result<serde_json::Value, SkillExecError> + Send + Sync + 'static,
     {
         self.deterministic_handlers
             .insert(skill_id.to_owned(), Box::new(handler));
     }
 
+    /// Drops cached outputs so the next call of every skill reaches the
+    /// provider again.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
     pub fn execute(
         &mut self,
         skill: &SkillDefinition,
@@ -86,6 +101,7 @@ impl SkillExecutor {
                 output: cached.clone(),
                 usage: TokenUsage::default(),
                 cached: true,
+                raw_response: None,
             });
         }
 
@@ -94,7 +110,7 @@ impl SkillExecutor {
             .validate(input)
             .map_err(|e| SkillExecError::SchemaViolation(e.to_string()))?;
 
-        let (mut output, usage) = match skill.execution_mode {
+        let (mut output, usage, raw_response) = match skill.execution_mode {
             SkillExecutionMode::Deterministic => {
                 let handler = self
                     .deterministic_handlers
@@ -106,7 +122,7 @@ impl SkillExecutor {
                         ))
                     })?;
                 let result = handler(input)?;
-                (result, TokenUsage::default())
+                (result, TokenUsage::default(), None)
             }
             SkillExecutionMode::LLM => {
                 let request = LLMRequest {
@@ -119,7 +135,7 @@ impl SkillExecutor {
                 let parsed: serde_json::Value = serde_json::from_str(&response.content)
                     .map_err(|e| SkillExecError::JsonParse(e.to_string()))?;
                 reject_free_text(&parsed)?;
-                (parsed, response.usage)
+                (parsed, response.usage, Some(response.content))
             }
         };
 
@@ -155,6 +171,7 @@ impl SkillExecutor {
             output,
             usage,
             cached: false,
+            raw_response,
         })
     }
 }
@@ -164,6 +181,8 @@ pub struct SkillExecResult {
     pub output: serde_json::Value,
     pub usage: TokenUsage,
     pub cached: bool,
+    /// Unparsed provider output, for LLM skills that reached the provider.
+    pub raw_response: Option<String>,
 }
 
 fn reject_free_text(value: &serde_json::Value) -> Result<(), SkillExecError> {
@@ -209,6 +228,7 @@ mod tests {
                     prompt_tokens: 10,
                     completion_tokens: 20,
                     total_tokens: 30,
+                    cached_prompt_tokens: 0,
                 },
                 model: Arc::from("mock"),
             })
@@ -231,6 +251,7 @@ mod tests {
             execution_mode: SkillExecutionMode::LLM,
             max_output_tokens: 500,
             compact_keys: None,
+            prompt_variants: Vec::new(),
         }
     }
 
@@ -250,6 +271,7 @@ mod tests {
             execution_mode: SkillExecutionMode::Deterministic,
             max_output_tokens: 100,
             compact_keys: None,
+            prompt_variants: Vec::new(),
         }
     }
 
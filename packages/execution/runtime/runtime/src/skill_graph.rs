@@ -46,6 +46,18 @@ impl SkillGraph {
     }
 
     pub fn topological_order(&self) -> Result<Vec<&str>, GraphError> {
+        self.grouped_order(|_| ())
+    }
+
+    /// A topological order that, whenever several skills are ready, prefers
+    /// one with the same `key` as the skill just scheduled — so skills sharing
+    /// a system prompt and model run back to back and the provider's prompt
+    /// cache stays warm. With a constant key this is `topological_order`.
+    pub fn grouped_order<K, F>(&self, key: F) -> Result<Vec<&str>, GraphError>
+    where
+        K: PartialEq,
+        F: Fn(&str) -> K,
+    {
         use ahash::AHashMap;
         let n = self.nodes.len();
         let mut in_degree: AHashMap<&str, usize> = AHashMap::with_capacity(n);
@@ -74,7 +86,14 @@ impl SkillGraph {
 
         let mut order = Vec::with_capacity(n);
 
-        while let Some(current) = queue.pop() {
+        let mut last_key: Option<K> = None;
+        while !queue.is_empty() {
+            let pick = last_key
+                .as_ref()
+                .and_then(|k| queue.iter().rposition(|id| key(id) == *k))
+                .unwrap_or(queue.len() - 1);
+            let current = queue.remove(pick);
+            last_key = Some(key(current));
             order.push(current);
             if let Some(neighbors) = adj.get(current) {
                 let mut next_ready = Vec::new();
@@ -203,4 +222,27 @@ mod tests {
         assert_eq!(order[0], "a");
         assert_eq!(order[3], "d");
     }
+
+    #[test]
+    fn grouped_order_keeps_keys_together() {
+        let node = |id: &str, deps: &[&str]| SkillNode {
+            skill_id: id.into(),
+            dependencies: deps
+                .iter()
+                .map(|d| DependencySpec { source_skill: (*d).into(), fields: vec![] })
+                .collect(),
+        };
+        let graph = SkillGraph::new(vec![
+            node("fetch", &[]),
+            node("llm_a", &["fetch"]),
+            node("local_b", &["fetch"]),
+            node("llm_c", &["fetch"]),
+            node("local_d", &["fetch"]),
+        ]);
+        let key = |id: &str| id.starts_with("llm");
+        let order = graph.grouped_order(key).expect("should succeed");
+        assert_eq!(order[0], "fetch");
+        let switches = order[1..].windows(2).filter(|w| key(w[0]) != key(w[1])).count();
+        assert_eq!(switches, 1);
+    }
 }
@@ -1,10 +1,17 @@
 use std::sync::Arc;
 use ahash::AHashMap;
 
+use crate::tokenizer::{count_tokens, DEFAULT_ESTIMATION_MODEL};
+
 pub fn estimate_tokens(s: &str) -> u32 {
-    (s.len() / 4) as u32
+    count_tokens(DEFAULT_ESTIMATION_MODEL, s)
+}
+
+pub fn estimate_tokens_for(model: &str, s: &str) -> u32 {
+    count_tokens(model, s)
 }
 
+#[derive(Default)]
 pub struct StaticPromptCache {
     cache: AHashMap<String, Arc<str>>,
 }
@@ -24,6 +31,7 @@ impl StaticPromptCache {
     }
 }
 
+#[derive(Default)]
 pub struct ToolSchemaCache {
     cache: AHashMap<u64, Arc<str>>,
 }
@@ -50,6 +58,7 @@ impl ToolSchemaCache {
     }
 }
 
+#[derive(Default)]
 pub struct DeltaContextEngine {
     stored_outputs: AHashMap<String, serde_json::Value>,
 }
@@ -209,9 +218,24 @@ pub struct TokenBreakdown {
     pub schema_tokens: u32,
     pub response_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens served from the provider's prompt cache.
+    pub cached_tokens: u32,
+    /// Cost avoided by those cache hits (already deducted from `cost`).
+    pub cache_savings: f64,
     pub cost: f64,
+    pub conversation_id: Option<String>,
+}
+
+/// Aggregated agent spend attributed to one chat conversation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversationUsage {
+    pub conversation_id: String,
+    pub skill_calls: u32,
+    pub total_tokens: u32,
+    pub total_cost: f64,
 }
 
+#[derive(Default)]
 pub struct TokenTracker {
     records: Vec<TokenBreakdown>,
     total_cost: f64,
@@ -238,6 +262,10 @@ impl TokenTracker {
         self.records.iter().map(|r| r.total_tokens).sum()
     }
 
+    pub fn total_cache_savings(&self) -> f64 {
+        self.records.iter().map(|r| r.cache_savings).sum()
+    }
+
     pub fn report(&self) -> String {
         let mut out = String::new();
         out.push_str(&format!(
@@ -247,7 +275,7 @@ impl TokenTracker {
         ));
         for r in &self.records {
             out.push_str(&format!(
-                "  [{}] model={} prompt={} ctx={} mem={} schema={} resp={} total={} cost=${:.6}\n",
+                "  [{}] model={} prompt={} ctx={} mem={} schema={} resp={} total={} cost=${:.6}",
                 r.skill_id,
                 r.model,
                 r.prompt_tokens,
@@ -258,6 +286,16 @@ impl TokenTracker {
                 r.total_tokens,
                 r.cost,
             ));
+            if r.cached_tokens > 0 {
+                out.push_str(&format!(" cached={} saved=${:.6}", r.cached_tokens, r.cache_savings));
+            }
+            out.push('\n');
+        }
+        for c in self.usage_by_conversation() {
+            out.push_str(&format!(
+                "  conversation={} calls={} total={} cost=${:.6}\n",
+                c.conversation_id, c.skill_calls, c.total_tokens, c.total_cost,
+            ));
         }
         out
     }
@@ -265,6 +303,34 @@ impl TokenTracker {
     pub fn records(&self) -> &[TokenBreakdown] {
         &self.records
     }
+
+    pub fn conversation_usage(&self, conversation_id: &str) -> ConversationUsage {
+        let mut usage = ConversationUsage {
+            conversation_id: conversation_id.to_owned(),
+            ..Default::default()
+        };
+        for r in self
+            .records
+            .iter()
+            .filter(|r| r.conversation_id.as_deref() == Some(conversation_id))
+        {
+            usage.skill_calls += 1;
+            usage.total_tokens += r.total_tokens;
+            usage.total_cost += r.cost;
+        }
+        usage
+    }
+
+    pub fn usage_by_conversation(&self) -> Vec<ConversationUsage> {
+        let mut ids: Vec<&str> = self
+            .records
+            .iter()
+            .filter_map(|r| r.conversation_id.as_deref())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter().map(|id| self.conversation_usage(id)).collect()
+    }
 }
 
 #[cfg(test)]
@@ -334,4 +400,25 @@ mod tests {
         assert_eq!(t.total_tokens(), 150);
         assert!((t.total_cost() - 0.0011).abs() < 1e-9);
     }
+
+    #[test]
+    fn tracker_attributes_to_conversations() {
+        let mut t = TokenTracker::new();
+        for (conv, tokens) in [(Some("c1"), 100), (Some("c2"), 40), (Some("c1"), 60), (None, 10)] {
+            t.record(TokenBreakdown {
+                skill_id: "s".into(),
+                total_tokens: tokens,
+                cost: tokens as f64 / 1000.0,
+                conversation_id: conv.map(String::from),
+                ..Default::default()
+            });
+        }
+        let c1 = t.conversation_usage("c1");
+        assert_eq!(c1.skill_calls, 2);
+        assert_eq!(c1.total_tokens, 160);
+        let all = t.usage_by_conversation();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].conversation_id, "c2");
+        assert_eq!(t.conversation_usage("missing").skill_calls, 0);
+    }
 }
@@ -0,0 +1,168 @@
+use std::sync::{Arc, OnceLock, RwLock};
+use ahash::AHashMap;
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton, CoreBPE};
+
+/// Counts tokens the way a model's tokenizer would.
+///
+/// The built-in encoders run the real tiktoken BPE: `o200k` for GPT-4o,
+/// GPT-4.1, GPT-5 and the o-series, `cl100k` for the older GPT models and
+/// embeddings. Anthropic and Google don't publish their tokenizers, so
+/// Claude is counted with `cl100k` and Gemini / Gemma with `o200k`, which
+/// is close but not exact. Hosts that have an exact count (a provider's
+/// count-tokens endpoint, a bundled vocabulary) can install it with
+/// [`register_encoder`].
+pub trait TokenEncoder: Send + Sync {
+    fn name(&self) -> &str;
+    fn count(&self, text: &str) -> u32;
+}
+
+/// Model used when the caller doesn't know the target model.
+pub const DEFAULT_ESTIMATION_MODEL: &str = "gpt-4o";
+
+/// A tiktoken BPE vocabulary, loaded on first use.
+#[derive(Clone, Copy)]
+pub struct Tiktoken {
+    name: &'static str,
+    bpe: fn() -> &'static CoreBPE,
+}
+
+pub const CL100K: Tiktoken = Tiktoken { name: "cl100k", bpe: cl100k_base_singleton };
+pub const O200K: Tiktoken = Tiktoken { name: "o200k", bpe: o200k_base_singleton };
+
+impl TokenEncoder for Tiktoken {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        // Special-token markup in user text is counted as plain text.
+        let tokens = (self.bpe)().encode_ordinary(text).len();
+        u32::try_from(tokens).unwrap_or(u32::MAX)
+    }
+}
+
+/// Encoding for a model name.
+pub fn encoding_for(model: &str) -> Tiktoken {
+    let m = model.to_ascii_lowercase();
+    if m.starts_with("gpt-4o")
+        || m.starts_with("gpt-4.1")
+        || m.starts_with("gpt-5")
+        || m.starts_with("chatgpt")
+        || m.starts_with('o') && m[1..].starts_with(|c: char| c.is_ascii_digit())
+        || m.starts_with("gemini")
+        || m.starts_with("gemma")
+        || m == "local"
+    {
+        O200K
+    } else {
+        // Older GPT models, embeddings, Claude, Llama / Mistral and other
+        // open models.
+        CL100K
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Host-installed exact encoders keyed by model-name prefix.
+    overrides: Vec<(String, Arc<dyn TokenEncoder>)>,
+    /// Resolved encoder per model name.
+    cache: AHashMap<String, Arc<dyn TokenEncoder>>,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(Registry::default()))
+}
+
+/// Installs `encoder` for every model whose name starts with `model_prefix`.
+/// The longest matching prefix wins.
+pub fn register_encoder(model_prefix: &str, encoder: Arc<dyn TokenEncoder>) {
+    let mut reg = registry().write().unwrap_or_else(|e| e.into_inner());
+    reg.overrides.retain(|(p, _)| p != model_prefix);
+    reg.overrides.push((model_prefix.to_owned(), encoder));
+    reg.cache.clear();
+}
+
+/// Returns the (cached) encoder for `model`.
+pub fn encoder_for(model: &str) -> Arc<dyn TokenEncoder> {
+    if let Some(enc) = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .cache
+        .get(model)
+    {
+        return Arc::clone(enc);
+    }
+
+    let mut reg = registry().write().unwrap_or_else(|e| e.into_inner());
+    let encoder = reg
+        .overrides
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, enc)| Arc::clone(enc))
+        .unwrap_or_else(|| Arc::new(encoding_for(model)));
+    reg.cache.insert(model.to_owned(), Arc::clone(&encoder));
+    encoder
+}
+
+pub fn count_tokens(model: &str, text: &str) -> u32 {
+    encoder_for(model).count(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_counts_words() {
+        let n = count_tokens("gpt-4o", "The quick brown fox jumps over the lazy dog.");
+        assert_eq!(n, 10);
+    }
+
+    #[test]
+    fn digits_group_in_threes() {
+        assert_eq!(count_tokens("gpt-4", "1234567"), 3);
+    }
+
+    #[test]
+    fn cjk_is_denser_than_byte_heuristic() {
+        let text = "北京是中国的首都，历史悠久。";
+        let bytes_over_four = (text.len() / 4) as u32;
+        let cl100k = count_tokens("gpt-4", text);
+        assert!(cl100k > bytes_over_four);
+        assert!(count_tokens("gpt-4o", text) < cl100k);
+    }
+
+    #[test]
+    fn families_resolve() {
+        assert_eq!(encoding_for("gpt-4o-mini").name, "o200k");
+        assert_eq!(encoding_for("o3-mini").name, "o200k");
+        assert_eq!(encoding_for("gpt-3.5-turbo").name, "cl100k");
+        assert_eq!(encoding_for("claude-3-5-haiku-20241022").name, "cl100k");
+        assert_eq!(encoding_for("gemini-2.0-flash").name, "o200k");
+    }
+
+    #[test]
+    fn special_tokens_count_as_text() {
+        assert!(count_tokens("gpt-4o", "<|endoftext|>") > 1);
+    }
+
+    struct Fixed;
+
+    impl TokenEncoder for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+        fn count(&self, _text: &str) -> u32 {
+            42
+        }
+    }
+
+    #[test]
+    fn registered_encoder_overrides_builtin() {
+        register_encoder("test-exact", Arc::new(Fixed));
+        assert_eq!(count_tokens("test-exact-1", "anything"), 42);
+        assert_eq!(encoder_for("test-exact-1").name(), "fixed");
+    }
+}